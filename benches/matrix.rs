@@ -15,7 +15,10 @@ use sha2::Sha256;
 const BENCHMARK_MATRIX_DIMENSIONS: [(usize, usize); 4] =
     [(2048, 1), (2048, 12), (65536, 1), (65536, 24)];
 
-fn matrix_row_commitment_bench<F: GpuField + Field, D: Digest>(c: &mut Criterion, name: &str) {
+fn matrix_row_commitment_bench<F: GpuField + Field, D: Digest + 'static>(
+    c: &mut Criterion,
+    name: &str,
+) {
     let mut rng = ark_std::test_rng();
     let mut group = c.benchmark_group(name);
     group.sample_size(10);
@@ -43,6 +46,8 @@ fn matrix_row_commitment_bench<F: GpuField + Field, D: Digest>(c: &mut Criterion
 
 fn matrix_row_commitment_benches(c: &mut Criterion) {
     matrix_row_commitment_bench::<Fp, Sha256>(c, "matrix row commitment (sha256)");
+    #[cfg(feature = "blake3")]
+    matrix_row_commitment_bench::<Fp, ministark::Blake3>(c, "matrix row commitment (blake3)");
 }
 
 criterion_group!(benches, matrix_row_commitment_benches);
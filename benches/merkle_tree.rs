@@ -15,6 +15,9 @@ use sha2::Sha256;
 
 const BENCHMARK_TREE_DEPTH: [usize; 4] = [14, 15, 16, 17];
 
+#[cfg(feature = "parallel")]
+const SCALING_BENCHMARK_TREE_DEPTH: usize = 20;
+
 fn build_merkle_tree_bench<F: GpuField, D: Digest>(c: &mut Criterion, name: &str) {
     let mut rng = ark_std::test_rng();
     let mut group = c.benchmark_group(name);
@@ -38,8 +41,37 @@ fn build_merkle_tree_bench<F: GpuField, D: Digest>(c: &mut Criterion, name: &str
     }
 }
 
+/// Tree construction with an increasing number of threads
+#[cfg(feature = "parallel")]
+fn merkle_tree_scaling_bench<D: Digest>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    let n = 1 << SCALING_BENCHMARK_TREE_DEPTH;
+    let leaf_nodes = (0..n as u64)
+        .map(|i| D::new_with_prefix(i.to_le_bytes()).finalize())
+        .collect::<Vec<Output<D>>>();
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut num_threads = 1;
+    while num_threads <= max_threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("threads", num_threads),
+            &num_threads,
+            |b, _| b.iter(|| pool.install(|| MerkleTree::<D>::new(leaf_nodes.clone()))),
+        );
+        num_threads *= 2;
+    }
+}
+
 fn build_merkle_tree_benches(c: &mut Criterion) {
     build_merkle_tree_bench::<Fp, Sha256>(c, "build merkle tree (sha256)");
+    #[cfg(feature = "parallel")]
+    merkle_tree_scaling_bench::<Sha256>(c, "merkle tree scaling (sha256, 2^20 leaves)");
 }
 
 criterion_group!(benches, build_merkle_tree_benches);
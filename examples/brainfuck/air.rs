@@ -1,6 +1,7 @@
 use crate::tables;
 use crate::tables::Challenge;
 use crate::tables::EvaluationArgumentHint;
+use crate::trace::BrainfuckTrace;
 use crate::vm::compile;
 use ark_ff::Field;
 use ark_ff::One;
@@ -19,6 +20,7 @@ use ministark::constraints::VerifierChallenge;
 use ministark::hints::Hints;
 use ministark::Air;
 use ministark::ProofOptions;
+use ministark::Trace;
 use ministark::TraceInfo;
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
@@ -152,6 +154,10 @@ impl Air for BrainfuckAir {
     fn trace_info(&self) -> &TraceInfo {
         &self.trace_info
     }
+
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        vec![BrainfuckTrace::NUM_EXTENSION_COLUMNS]
+    }
 }
 
 // Computes the evaluation terminal for the instruction table
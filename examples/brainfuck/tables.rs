@@ -19,11 +19,7 @@ pub enum Challenge {
     Eta,
 }
 
-impl ministark::constraints::VerifierChallenge for Challenge {
-    fn index(&self) -> usize {
-        *self as usize
-    }
-}
+ministark::impl_verifier_challenge!(Challenge);
 
 #[derive(Clone, Copy)]
 pub enum EvaluationArgumentHint {
@@ -34,11 +30,7 @@ pub enum EvaluationArgumentHint {
     OutputOffset,
 }
 
-impl ministark::constraints::Hint for EvaluationArgumentHint {
-    fn index(&self) -> usize {
-        *self as usize
-    }
-}
+ministark::impl_hint!(EvaluationArgumentHint);
 
 #[derive(Clone, Copy)]
 pub enum ProcessorBaseColumn {
@@ -161,11 +153,7 @@ impl BrainfuckColumn for OutputExtensionColumn {
 
 macro_rules! impl_column {
     ($t:ty) => {
-        impl ministark::constraints::ExecutionTraceColumn for $t {
-            fn index(&self) -> usize {
-                Self::FIRST_TRACE_COL_INDEX + *self as usize
-            }
-        }
+        ministark::impl_execution_trace_column!($t, <$t>::FIRST_TRACE_COL_INDEX);
     };
 }
 
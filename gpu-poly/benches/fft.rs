@@ -11,6 +11,7 @@ use criterion::criterion_main;
 use criterion::BenchmarkId;
 use criterion::Criterion;
 use gpu_poly::fields::p18446744069414584321::Fp as Fp64;
+use gpu_poly::fields::p18446744069414584321::Fq2 as Fq64x2;
 use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp as Fp252;
 use gpu_poly::prelude::*;
 
@@ -72,6 +73,7 @@ where
 
 fn fft_benches(c: &mut Criterion) {
     fft_bench::<Fp64>(c, "FFT (64-bit prime field)");
+    fft_bench::<Fq64x2>(c, "FFT (64-bit prime field quadratic extension)");
     fft_bench::<Fp252>(c, "FFT (252-bit prime field)");
 }
 
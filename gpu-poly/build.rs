@@ -0,0 +1,54 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Compiles `src/metal/shaders.metal` into `$OUT_DIR/shaders.metallib` so the
+/// library loaded by `plan.rs` always has every kernel in the shader sources.
+/// A checked in binary goes stale whenever a kernel is added.
+fn main() {
+    let shader_dir = Path::new("src/metal");
+    for entry in fs::read_dir(shader_dir).unwrap() {
+        println!("cargo:rerun-if-changed={}", entry.unwrap().path().display());
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let metallib = out_dir.join("shaders.metallib");
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    if target_os != "macos" || target_arch != "aarch64" {
+        // the GPU code isn't compiled for this target
+        fs::write(&metallib, []).unwrap();
+        return;
+    }
+
+    let air = out_dir.join("shaders.air");
+    xcrun(&[
+        "-sdk",
+        "macosx",
+        "metal",
+        "-c",
+        "src/metal/shaders.metal",
+        "-o",
+        air.to_str().unwrap(),
+    ]);
+    xcrun(&[
+        "-sdk",
+        "macosx",
+        "metallib",
+        air.to_str().unwrap(),
+        "-o",
+        metallib.to_str().unwrap(),
+    ]);
+}
+
+fn xcrun(args: &[&str]) {
+    let status = Command::new("xcrun")
+        .args(args)
+        .status()
+        .unwrap_or_else(|error| {
+            panic!("failed to run xcrun (are the Xcode tools installed?): {error}")
+        });
+    assert!(status.success(), "xcrun {} failed", args.join(" "));
+}
@@ -1,7 +1,10 @@
+use crate::GpuVec;
+use alloc::vec::Vec;
 use ark_std::alloc::Global;
 use core::alloc::AllocError;
 use core::alloc::Allocator;
 use core::alloc::Layout;
+use core::cell::RefCell;
 use core::ptr::NonNull;
 use once_cell::sync::Lazy;
 
@@ -13,23 +16,206 @@ pub struct PageAlignedAllocator;
 
 // TODO: come up with better allocation abstraction for different architectures
 #[cfg(target_arch = "aarch64")]
-unsafe impl Allocator for PageAlignedAllocator {
-    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        Global.allocate(layout.align_to(*PAGE_SIZE).unwrap().pad_to_align())
-    }
-
-    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        Global.deallocate(ptr, layout.align_to(*PAGE_SIZE).unwrap().pad_to_align())
-    }
+fn page_aligned(layout: Layout) -> Layout {
+    layout.align_to(*PAGE_SIZE).unwrap().pad_to_align()
 }
 
 #[cfg(not(target_arch = "aarch64"))]
+fn page_aligned(layout: Layout) -> Layout {
+    layout
+}
+
 unsafe impl Allocator for PageAlignedAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let layout = page_aligned(layout);
+        #[cfg(unix)]
+        if spill::is_spilled(layout.size()) {
+            return spill::allocate(layout.size());
+        }
         Global.allocate(layout)
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let layout = page_aligned(layout);
+        // allocations made before spilling was enabled are on the heap even
+        // if they're larger than the threshold
+        #[cfg(unix)]
+        if spill::is_spilled(layout.size()) && spill::deallocate(ptr) {
+            return;
+        }
         Global.deallocate(ptr, layout)
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskSpillingError {
+    /// Disk spilling can only be enabled once
+    AlreadyEnabled,
+    /// The spill directory isn't a valid path
+    InvalidDirectory,
+}
+
+/// Backs all [PageAlignedAllocator] allocations of at least `threshold` bytes
+/// with memory mapped temporary files created in `dir`. The operating system
+/// pages memory in and out of these files on demand which allows proving over
+/// traces larger than RAM. Files are unlinked as soon as they're mapped so
+/// they don't outlive the process.
+#[cfg(unix)]
+pub fn enable_disk_spilling(threshold: usize, dir: &str) -> Result<(), DiskSpillingError> {
+    spill::enable(threshold, dir)
+}
+
+#[cfg(unix)]
+mod spill {
+    extern crate std;
+
+    use super::DiskSpillingError;
+    use alloc::collections::BTreeMap;
+    use alloc::ffi::CString;
+    use core::alloc::AllocError;
+    use core::ptr::NonNull;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    // allocations of this many bytes or more are backed by a file
+    static THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+    // `mkstemp` template of the spill files
+    static TEMPLATE: Mutex<Option<CString>> = Mutex::new(None);
+    // address and size of every mapping. Pointers that aren't in here were
+    // allocated on the heap.
+    static MAPPINGS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+    pub fn enable(threshold: usize, dir: &str) -> Result<(), DiskSpillingError> {
+        assert!(threshold > 0, "threshold must be non-zero");
+        let dir = dir.trim_end_matches('/');
+        let template = CString::new(format!("{dir}/ministark-spill-XXXXXX"))
+            .map_err(|_| DiskSpillingError::InvalidDirectory)?;
+        let mut current_template = TEMPLATE.lock().unwrap();
+        if current_template.is_some() {
+            return Err(DiskSpillingError::AlreadyEnabled);
+        }
+        *current_template = Some(template);
+        THRESHOLD.store(threshold, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn is_spilled(size: usize) -> bool {
+        size >= THRESHOLD.load(Ordering::Relaxed)
+    }
+
+    pub fn allocate(size: usize) -> Result<NonNull<[u8]>, AllocError> {
+        let template = TEMPLATE.lock().unwrap().clone().ok_or(AllocError)?;
+        let mut path = template.into_bytes_with_nul();
+        unsafe {
+            let fd = libc::mkstemp(path.as_mut_ptr() as *mut libc::c_char);
+            if fd < 0 {
+                return Err(AllocError);
+            }
+            // the file is removed once the mapping is released
+            libc::unlink(path.as_ptr() as *const libc::c_char);
+            if libc::ftruncate(fd, size.try_into().map_err(|_| AllocError)?) != 0 {
+                libc::close(fd);
+                return Err(AllocError);
+            }
+            let ptr = libc::mmap(
+                core::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            libc::close(fd);
+            if ptr == libc::MAP_FAILED {
+                return Err(AllocError);
+            }
+            let ptr = NonNull::new(ptr as *mut u8).ok_or(AllocError)?;
+            MAPPINGS.lock().unwrap().insert(ptr.as_ptr() as usize, size);
+            Ok(NonNull::slice_from_raw_parts(ptr, size))
+        }
+    }
+
+    /// Unmaps `ptr` if it was allocated by [allocate]. Returns false if `ptr`
+    /// is a heap allocation.
+    pub unsafe fn deallocate(ptr: NonNull<u8>) -> bool {
+        let mapping = MAPPINGS.lock().unwrap().remove(&(ptr.as_ptr() as usize));
+        match mapping {
+            Some(size) => {
+                libc::munmap(ptr.as_ptr() as *mut libc::c_void, size);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A pool of page aligned buffers that can be reused between allocations.
+/// Intended to be scoped to a single proving session to reduce allocator
+/// pressure and page faults when working with large traces.
+pub struct BufferPool<T> {
+    buffers: RefCell<Vec<GpuVec<T>>>,
+}
+
+impl<T> BufferPool<T> {
+    pub fn new() -> Self {
+        BufferPool {
+            buffers: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns an empty buffer with a capacity of at least `capacity`.
+    /// Reuses the smallest pooled buffer that is large enough otherwise
+    /// allocates a new buffer.
+    pub fn get(&self, capacity: usize) -> GpuVec<T> {
+        let mut buffers = self.buffers.borrow_mut();
+        let best_fit = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(i, _)| i);
+        match best_fit {
+            Some(i) => buffers.swap_remove(i),
+            None => Vec::with_capacity_in(capacity, PageAlignedAllocator),
+        }
+    }
+
+    /// Returns a buffer of length `len` with every element set to `value`
+    pub fn get_filled(&self, len: usize, value: T) -> GpuVec<T>
+    where
+        T: Clone,
+    {
+        let mut buffer = self.get(len);
+        buffer.resize(len, value);
+        buffer
+    }
+
+    /// Returns a buffer to the pool so it can be reused
+    pub fn recycle(&self, mut buffer: GpuVec<T>) {
+        if buffer.capacity() != 0 {
+            buffer.clear();
+            self.buffers.borrow_mut().push(buffer);
+        }
+    }
+
+    /// Number of buffers available for reuse
+    pub fn len(&self) -> usize {
+        self.buffers.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Frees all pooled buffers
+    pub fn clear(&self) {
+        self.buffers.borrow_mut().clear()
+    }
+}
+
+impl<T> Default for BufferPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
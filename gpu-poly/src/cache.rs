@@ -0,0 +1,150 @@
+#![cfg(target_arch = "aarch64")]
+
+extern crate std;
+
+use crate::utils::void_ptr;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use once_cell::sync::Lazy;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Process wide cache of compiled compute pipelines
+pub static KERNEL_CACHE: Lazy<KernelCache> = Lazy::new(KernelCache::default);
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct PipelineKey {
+    kernel_name: String,
+    constants: Vec<u32>,
+}
+
+/// Compute pipelines keyed by kernel name and function constants. Pipelines
+/// are compiled on first use and can be persisted to disk (as a Metal binary
+/// archive) so subsequent runs skip shader compilation.
+#[derive(Default)]
+pub struct KernelCache {
+    pipelines: Mutex<BTreeMap<PipelineKey, metal::ComputePipelineState>>,
+    /// Archive backing pipeline compilation and the URL it's persisted to
+    archive: Mutex<Option<(metal::BinaryArchive, String)>>,
+}
+
+// SAFETY: the pipelines and the archive are only accessed while holding their
+// mutex so the cache is `Sync` if they can be sent between threads. Metal
+// objects are reference counted atomically so moving them between threads is
+// sound. Pipelines handed out by `pipeline` are also used concurrently which
+// Metal allows since compute pipeline states are immutable and documented as
+// thread safe.
+unsafe impl Send for KernelCache {}
+unsafe impl Sync for KernelCache {}
+
+impl KernelCache {
+    /// Returns the pipeline for `kernel_name` specialized with `constants`.
+    /// Constants are bound as `uint` function constants at indices
+    /// `0..constants.len()`.
+    pub fn pipeline(
+        &self,
+        library: &metal::LibraryRef,
+        kernel_name: &str,
+        constants: &[u32],
+    ) -> metal::ComputePipelineState {
+        let key = PipelineKey {
+            kernel_name: kernel_name.to_string(),
+            constants: constants.to_vec(),
+        };
+        if let Some(pipeline) = self.pipelines.lock().unwrap().get(&key) {
+            return pipeline.clone();
+        }
+
+        let values = (!constants.is_empty()).then(|| {
+            let values = metal::FunctionConstantValues::new();
+            for (i, constant) in constants.iter().enumerate() {
+                values.set_constant_value_at_index(
+                    void_ptr(constant),
+                    metal::MTLDataType::UInt,
+                    i as u64,
+                );
+            }
+            values
+        });
+        let func = library.get_function(kernel_name, values).unwrap();
+        let descriptor = metal::ComputePipelineDescriptor::new();
+        descriptor.set_compute_function(Some(&func));
+        if let Some((archive, _)) = self.archive.lock().unwrap().as_ref() {
+            // pipeline binaries are looked up in the archive before compiling
+            descriptor.set_binary_archives(&[archive]);
+            archive
+                .add_compute_pipeline_functions_with_descriptor(&descriptor)
+                .unwrap();
+        }
+        let pipeline = library
+            .device()
+            .new_compute_pipeline_state(&descriptor)
+            .unwrap();
+        self.pipelines.lock().unwrap().insert(key, pipeline.clone());
+        pipeline
+    }
+
+    /// Uses the binary archive at `path` to back pipeline compilation. The
+    /// archive is created if it doesn't exist. Call [KernelCache::persist] to
+    /// write newly compiled pipelines back to disk.
+    pub fn load(&self, device: &metal::DeviceRef, path: &str) -> Result<(), String> {
+        // resolved now so changing directory doesn't move the archive
+        let url = archive_url(path)?;
+        let descriptor = metal::BinaryArchiveDescriptor::new();
+        if Path::new(path).exists() {
+            descriptor.set_url(&metal::URL::new_with_string(&url));
+        }
+        let archive = device.new_binary_archive_with_descriptor(&descriptor)?;
+        *self.archive.lock().unwrap() = Some((archive, url));
+        Ok(())
+    }
+
+    /// Writes all pipelines compiled since [KernelCache::load] to disk
+    pub fn persist(&self) -> Result<(), String> {
+        match self.archive.lock().unwrap().as_ref() {
+            Some((archive, url)) => archive
+                .serialize_to_url(&metal::URL::new_with_string(url))
+                .map(|_| ()),
+            None => Err("no kernel cache loaded".to_string()),
+        }
+    }
+
+    /// Returns the number of pipelines compiled in this process
+    pub fn len(&self) -> usize {
+        self.pipelines.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns the `file://` URL of `path`. Metal needs absolute URLs so relative
+/// paths are resolved against the working directory. Only the directory is
+/// canonicalized since an archive is persisted before it exists.
+pub fn archive_url(path: &str) -> Result<String, String> {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{} doesn't name a file", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = std::fs::canonicalize(dir)
+        .map_err(|err| format!("can't resolve {}: {err}", dir.display()))?;
+    let mut url = String::from("file://");
+    for &byte in dir.join(file_name).as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    Ok(url)
+}
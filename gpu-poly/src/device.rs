@@ -0,0 +1,94 @@
+#![cfg(target_arch = "aarch64")]
+
+extern crate std;
+
+use crate::plan::planner;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+std::thread_local! {
+    // index of the device GPU work on this thread goes to. `None` means the
+    // system default
+    static CURRENT_DEVICE: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// Capabilities of a GPU device
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    /// Index of the device as used by [with_device]
+    pub index: usize,
+    pub name: String,
+    /// Approximate amount of memory (in bytes) the device can use without
+    /// affecting runtime performance
+    pub max_working_set_size: u64,
+    pub max_threadgroup_size: u64,
+    pub has_unified_memory: bool,
+    pub is_low_power: bool,
+    /// True for external GPUs
+    pub is_removable: bool,
+}
+
+impl DeviceInfo {
+    fn new(index: usize, device: &metal::DeviceRef) -> Self {
+        let max_threads = device.max_threads_per_threadgroup();
+        DeviceInfo {
+            index,
+            name: device.name().to_string(),
+            max_working_set_size: device.recommended_max_working_set_size(),
+            max_threadgroup_size: max_threads.width * max_threads.height * max_threads.depth,
+            has_unified_memory: device.has_unified_memory(),
+            is_low_power: device.is_low_power(),
+            is_removable: device.is_removable(),
+        }
+    }
+
+    /// Enumerates all available devices
+    pub fn all() -> Vec<DeviceInfo> {
+        metal::Device::all()
+            .iter()
+            .enumerate()
+            .map(|(i, device)| DeviceInfo::new(i, device))
+            .collect()
+    }
+
+    /// Returns info for the device GPU work on this thread goes to
+    pub fn current() -> DeviceInfo {
+        let device = planner().command_queue.device();
+        let index = metal::Device::all()
+            .iter()
+            .position(|d| d.registry_id() == device.registry_id())
+            .unwrap_or(0);
+        DeviceInfo::new(index, device)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceSelectionError {
+    InvalidIndex { index: usize, num_devices: usize },
+}
+
+/// Runs `f` with all GPU work on the current thread going to the device at
+/// `index` (as indexed by [DeviceInfo::all]). Work on other threads, and on
+/// this thread once `f` returns, goes to the device it went to before.
+pub fn with_device<R>(index: usize, f: impl FnOnce() -> R) -> Result<R, DeviceSelectionError> {
+    let num_devices = metal::Device::all().len();
+    if index >= num_devices {
+        return Err(DeviceSelectionError::InvalidIndex { index, num_devices });
+    }
+    // restores the previous device even if `f` panics
+    struct Restore(Option<usize>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT_DEVICE.with(|device| device.set(self.0));
+        }
+    }
+    let _restore = Restore(CURRENT_DEVICE.with(|device| device.replace(Some(index))));
+    Ok(f())
+}
+
+/// Returns the index of the device selected with [with_device] on this thread
+pub(crate) fn current_device() -> Option<usize> {
+    CURRENT_DEVICE.with(Cell::get)
+}
@@ -5,8 +5,12 @@ use crate::GpuMul;
 use alloc::string::ToString;
 use ark_ff::BigInt;
 use ark_ff::Field;
+use ark_ff::Fp2;
+use ark_ff::Fp2Config;
 use ark_ff::Fp3;
 use ark_ff::Fp3Config;
+use ark_ff::Fp4;
+use ark_ff::Fp4Config;
 use ark_ff::FpConfig;
 use ark_std::string::String;
 use core::ops::Add;
@@ -179,11 +183,468 @@ pub mod p18446744069414584321 {
             "p18446744069414584321_fq3".to_string()
         }
     }
+
+    pub struct Fq2Config;
+
+    impl Fp2Config for Fq2Config {
+        type Fp = Fp;
+        const NONRESIDUE: Fp = /* =7 */ ark_ff::Fp(BigInt([30064771065]), PhantomData);
+
+        // NOTE: these are used for pairings which I don't need so they are left empty
+        const FROBENIUS_COEFF_FP2_C1: &'static [Fp] = &[];
+    }
+
+    wrap_field!(Fq2; Fp2<Fq2Config>);
+
+    impl MulAssign<&Fp> for Fq2 {
+        fn mul_assign(&mut self, rhs: &Fp) {
+            self.0.mul_assign_by_basefield(rhs)
+        }
+    }
+
+    impl MulAssign<Fp> for Fq2 {
+        fn mul_assign(&mut self, rhs: Fp) {
+            self.0.mul_assign_by_basefield(&rhs)
+        }
+    }
+
+    impl AddAssign<Fp> for Fq2 {
+        fn add_assign(&mut self, rhs: Fp) {
+            *self += Fq2::from(rhs);
+        }
+    }
+
+    impl AddAssign<&Fp> for Fq2 {
+        fn add_assign(&mut self, rhs: &Fp) {
+            *self += Fq2::from(*rhs);
+        }
+    }
+
+    impl Add<&Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn add(self, rhs: &Fp) -> Self::Output {
+            self + Fq2::from(*rhs)
+        }
+    }
+
+    impl Add<Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn add(self, rhs: Fp) -> Self::Output {
+            self + Fq2::from(rhs)
+        }
+    }
+
+    impl SubAssign<Fp> for Fq2 {
+        fn sub_assign(&mut self, rhs: Fp) {
+            *self -= Fq2::from(rhs);
+        }
+    }
+
+    impl SubAssign<&Fp> for Fq2 {
+        fn sub_assign(&mut self, rhs: &Fp) {
+            *self -= Fq2::from(*rhs);
+        }
+    }
+
+    impl Sub<&Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn sub(self, rhs: &Fp) -> Self::Output {
+            self - Fq2::from(*rhs)
+        }
+    }
+
+    impl Sub<Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn sub(self, rhs: Fp) -> Self::Output {
+            self - Fq2::from(rhs)
+        }
+    }
+
+    impl Mul<&Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn mul(mut self, rhs: &Fp) -> Self::Output {
+            self.0.mul_assign_by_basefield(rhs);
+            self
+        }
+    }
+
+    impl Mul<Fp> for Fq2 {
+        type Output = Fq2;
+
+        fn mul(mut self, rhs: Fp) -> Self::Output {
+            self.0.mul_assign_by_basefield(&rhs);
+            self
+        }
+    }
+
+    impl From<Fp> for Fq2 {
+        fn from(value: Fp) -> Self {
+            Fq2(Fp2::<Fq2Config>::from_base_prime_field(value))
+        }
+    }
+
+    impl GpuMul<Fp> for Fq2 {}
+
+    impl GpuMul<&Fp> for Fq2 {}
+
+    impl GpuMul<Fq2> for Fq2 {}
+
+    impl GpuMul<&Fq2> for Fq2 {}
+
+    impl GpuAdd<Fp> for Fq2 {}
+
+    impl GpuAdd<&Fp> for Fq2 {}
+
+    impl GpuAdd<Fq2> for Fq2 {}
+
+    impl GpuAdd<&Fq2> for Fq2 {}
+
+    impl GpuField for Fq2 {
+        type FftField = Fp;
+
+        fn field_name() -> String {
+            "p18446744069414584321_fq2".to_string()
+        }
+    }
+
+    /// Degree four extension `Fp[x]/(x^4 - 7)` represented as a quadratic
+    /// extension of `Fp[u]/(u^2 - 7)`. Gives ~256 bit challenges which leaves
+    /// enough headroom for 128-bit security over the 64-bit base field.
+    pub struct Fq4Config;
+
+    impl Fp4Config for Fq4Config {
+        type Fp2Config = Fq2Config;
+        const NONRESIDUE: Fp2<Fq2Config> = Fp2::<Fq2Config>::new(Fp::ZERO, Fp::ONE);
+
+        // NOTE: these are used for pairings which I don't need so they are left empty
+        const FROBENIUS_COEFF_FP4_C1: &'static [Fp] = &[];
+    }
+
+    wrap_field!(Fq4; Fp4<Fq4Config>);
+
+    impl MulAssign<&Fp> for Fq4 {
+        fn mul_assign(&mut self, rhs: &Fp) {
+            self.0.c0.mul_assign_by_basefield(rhs);
+            self.0.c1.mul_assign_by_basefield(rhs);
+        }
+    }
+
+    impl MulAssign<Fp> for Fq4 {
+        fn mul_assign(&mut self, rhs: Fp) {
+            self.0.c0.mul_assign_by_basefield(&rhs);
+            self.0.c1.mul_assign_by_basefield(&rhs);
+        }
+    }
+
+    impl AddAssign<Fp> for Fq4 {
+        fn add_assign(&mut self, rhs: Fp) {
+            *self += Fq4::from(rhs);
+        }
+    }
+
+    impl AddAssign<&Fp> for Fq4 {
+        fn add_assign(&mut self, rhs: &Fp) {
+            *self += Fq4::from(*rhs);
+        }
+    }
+
+    impl Add<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn add(self, rhs: &Fp) -> Self::Output {
+            self + Fq4::from(*rhs)
+        }
+    }
+
+    impl Add<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn add(self, rhs: Fp) -> Self::Output {
+            self + Fq4::from(rhs)
+        }
+    }
+
+    impl SubAssign<Fp> for Fq4 {
+        fn sub_assign(&mut self, rhs: Fp) {
+            *self -= Fq4::from(rhs);
+        }
+    }
+
+    impl SubAssign<&Fp> for Fq4 {
+        fn sub_assign(&mut self, rhs: &Fp) {
+            *self -= Fq4::from(*rhs);
+        }
+    }
+
+    impl Sub<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn sub(self, rhs: &Fp) -> Self::Output {
+            self - Fq4::from(*rhs)
+        }
+    }
+
+    impl Sub<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn sub(self, rhs: Fp) -> Self::Output {
+            self - Fq4::from(rhs)
+        }
+    }
+
+    impl Mul<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn mul(mut self, rhs: &Fp) -> Self::Output {
+            self.0.c0.mul_assign_by_basefield(rhs);
+            self.0.c1.mul_assign_by_basefield(rhs);
+            self
+        }
+    }
+
+    impl Mul<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn mul(mut self, rhs: Fp) -> Self::Output {
+            self.0.c0.mul_assign_by_basefield(&rhs);
+            self.0.c1.mul_assign_by_basefield(&rhs);
+            self
+        }
+    }
+
+    impl From<Fp> for Fq4 {
+        fn from(value: Fp) -> Self {
+            Fq4(Fp4::<Fq4Config>::from_base_prime_field(value))
+        }
+    }
+
+    impl GpuMul<Fp> for Fq4 {}
+
+    impl GpuMul<&Fp> for Fq4 {}
+
+    impl GpuMul<Fq4> for Fq4 {}
+
+    impl GpuMul<&Fq4> for Fq4 {}
+
+    impl GpuAdd<Fp> for Fq4 {}
+
+    impl GpuAdd<&Fp> for Fq4 {}
+
+    impl GpuAdd<Fq4> for Fq4 {}
+
+    impl GpuAdd<&Fq4> for Fq4 {}
+
+    impl GpuField for Fq4 {
+        type FftField = Fp;
+
+        fn field_name() -> String {
+            "p18446744069414584321_fq4".to_string()
+        }
+    }
+}
+
+// BabyBear field
+pub mod p2013265921 {
+    use super::*;
+    use core::ops::Sub;
+    use core::ops::SubAssign;
+
+    #[derive(ark_ff::MontConfig)]
+    #[modulus = "2013265921"]
+    #[generator = "31"]
+    #[small_subgroup_base = "3"]
+    #[small_subgroup_power = "1"]
+    pub struct FpMontConfig;
+
+    /// The 31-bit BabyBear prime field
+    /// Field has modulus `15 * 2^27 + 1`
+    pub type Fp = ark_ff::Fp64<ark_ff::MontBackend<FpMontConfig, 1>>;
+
+    impl GpuField for Fp {
+        type FftField = Self;
+
+        fn field_name() -> String {
+            "p2013265921_fp".to_string()
+        }
+    }
+
+    impl GpuMul<Fp> for Fp {}
+
+    impl GpuMul<&Fp> for Fp {}
+
+    impl GpuAdd<Fp> for Fp {}
+
+    impl GpuAdd<&Fp> for Fp {}
+
+    impl GpuFftField for Fp {}
+
+    pub struct Fq2Config;
+
+    impl Fp2Config for Fq2Config {
+        type Fp = Fp;
+        const NONRESIDUE: Fp = ark_ff::MontFp!("11");
+
+        // NOTE: these are used for pairings which I don't need so they are left empty
+        const FROBENIUS_COEFF_FP2_C1: &'static [Fp] = &[];
+    }
+
+    /// Degree four extension `Fp[x]/(x^4 - 11)` represented as a quadratic
+    /// extension of `Fp[u]/(u^2 - 11)`
+    pub struct Fq4Config;
+
+    impl Fp4Config for Fq4Config {
+        type Fp2Config = Fq2Config;
+        const NONRESIDUE: Fp2<Fq2Config> = Fp2::<Fq2Config>::new(Fp::ZERO, Fp::ONE);
+
+        // NOTE: these are used for pairings which I don't need so they are left empty
+        const FROBENIUS_COEFF_FP4_C1: &'static [Fp] = &[];
+    }
+
+    wrap_field!(Fq4; Fp4<Fq4Config>);
+
+    impl MulAssign<&Fp> for Fq4 {
+        fn mul_assign(&mut self, rhs: &Fp) {
+            self.0.c0.mul_assign_by_basefield(rhs);
+            self.0.c1.mul_assign_by_basefield(rhs);
+        }
+    }
+
+    impl MulAssign<Fp> for Fq4 {
+        fn mul_assign(&mut self, rhs: Fp) {
+            self.0.c0.mul_assign_by_basefield(&rhs);
+            self.0.c1.mul_assign_by_basefield(&rhs);
+        }
+    }
+
+    impl AddAssign<Fp> for Fq4 {
+        fn add_assign(&mut self, rhs: Fp) {
+            *self += Fq4::from(rhs);
+        }
+    }
+
+    impl AddAssign<&Fp> for Fq4 {
+        fn add_assign(&mut self, rhs: &Fp) {
+            *self += Fq4::from(*rhs);
+        }
+    }
+
+    impl Add<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn add(self, rhs: &Fp) -> Self::Output {
+            self + Fq4::from(*rhs)
+        }
+    }
+
+    impl Add<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn add(self, rhs: Fp) -> Self::Output {
+            self + Fq4::from(rhs)
+        }
+    }
+
+    impl SubAssign<Fp> for Fq4 {
+        fn sub_assign(&mut self, rhs: Fp) {
+            *self -= Fq4::from(rhs);
+        }
+    }
+
+    impl SubAssign<&Fp> for Fq4 {
+        fn sub_assign(&mut self, rhs: &Fp) {
+            *self -= Fq4::from(*rhs);
+        }
+    }
+
+    impl Sub<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn sub(self, rhs: &Fp) -> Self::Output {
+            self - Fq4::from(*rhs)
+        }
+    }
+
+    impl Sub<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn sub(self, rhs: Fp) -> Self::Output {
+            self - Fq4::from(rhs)
+        }
+    }
+
+    impl Mul<&Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn mul(mut self, rhs: &Fp) -> Self::Output {
+            self.0.c0.mul_assign_by_basefield(rhs);
+            self.0.c1.mul_assign_by_basefield(rhs);
+            self
+        }
+    }
+
+    impl Mul<Fp> for Fq4 {
+        type Output = Fq4;
+
+        fn mul(mut self, rhs: Fp) -> Self::Output {
+            self.0.c0.mul_assign_by_basefield(&rhs);
+            self.0.c1.mul_assign_by_basefield(&rhs);
+            self
+        }
+    }
+
+    impl From<Fp> for Fq4 {
+        fn from(value: Fp) -> Self {
+            Fq4(Fp4::<Fq4Config>::from_base_prime_field(value))
+        }
+    }
+
+    impl GpuMul<Fp> for Fq4 {}
+
+    impl GpuMul<&Fp> for Fq4 {}
+
+    impl GpuMul<Fq4> for Fq4 {}
+
+    impl GpuMul<&Fq4> for Fq4 {}
+
+    impl GpuAdd<Fp> for Fq4 {}
+
+    impl GpuAdd<&Fp> for Fq4 {}
+
+    impl GpuAdd<Fq4> for Fq4 {}
+
+    impl GpuAdd<&Fq4> for Fq4 {}
+
+    impl GpuField for Fq4 {
+        type FftField = Fp;
+
+        fn field_name() -> String {
+            "p2013265921_fq4".to_string()
+        }
+    }
+}
+
+// Mersenne-31 field
+pub mod p2147483647 {
+    #[derive(ark_ff::MontConfig)]
+    #[modulus = "2147483647"]
+    #[generator = "7"]
+    pub struct FpMontConfig;
+
+    /// The 31-bit Mersenne prime field
+    /// Field has modulus `2^31 - 1`. The multiplicative group has two-adicity
+    /// of one so FFTs are performed over the circle group of order `2^31`.
+    pub type Fp = ark_ff::Fp64<ark_ff::MontBackend<FpMontConfig, 1>>;
 }
 
 // StarkWare field
 pub mod p3618502788666131213697322783095070105623107215331596699973092056135872020481 {
     use super::*;
+    use ark_ff::PrimeField;
 
     #[derive(ark_ff::MontConfig)]
     #[modulus = "3618502788666131213697322783095070105623107215331596699973092056135872020481"]
@@ -194,7 +655,6 @@ pub mod p36185027886661312136973227830950701056231072153315966999730920561358720
     /// Field has modulus `2^251 + 17 * 2^192 + 1`
     pub type Fp = ark_ff::Fp256<ark_ff::MontBackend<FpMontConfig, 4>>;
 
-    // TODO: GPU field implementation
     impl GpuField for Fp {
         type FftField = Self;
 
@@ -212,7 +672,54 @@ pub mod p36185027886661312136973227830950701056231072153315966999730920561358720
 
     impl GpuAdd<&Fp> for Fp {}
 
-    // impl GpuAdd<&Fp> for Fp {}
-
     impl GpuFftField for Fp {}
+
+    /// Returns the 32 byte big-endian canonical encoding of a field element.
+    /// This is how Cairo and Starknet tooling encode felts.
+    pub fn to_felt_bytes(v: &Fp) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, limb) in bytes
+            .chunks_exact_mut(8)
+            .zip(v.into_bigint().0.iter().rev())
+        {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a 32 byte big-endian felt. Returns `None` if the encoded value
+    /// isn't less than the field modulus.
+    pub fn from_felt_bytes(bytes: &[u8; 32]) -> Option<Fp> {
+        let mut limbs = [0; 4];
+        for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Fp::from_bigint(BigInt(limbs))
+    }
+
+    /// Returns the `0x` prefixed hex encoding of a felt without leading zeros
+    /// e.g. `0x1a` (matches the format used by Starknet JSON-RPC)
+    pub fn to_felt_hex(v: &Fp) -> String {
+        let hex = to_felt_bytes(v)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        let digits = hex.trim_start_matches('0');
+        format!("0x{}", if digits.is_empty() { "0" } else { digits })
+    }
+
+    /// Parses a `0x` prefixed hex felt. Returns `None` for malformed or non
+    /// canonical values.
+    pub fn from_felt_hex(hex: &str) -> Option<Fp> {
+        let digits = hex.strip_prefix("0x")?;
+        if digits.is_empty() || digits.len() > 64 {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        let padded = format!("{digits:0>64}");
+        for (byte, pair) in bytes.iter_mut().zip(padded.as_bytes().chunks_exact(2)) {
+            *byte = u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+        from_felt_bytes(&bytes)
+    }
 }
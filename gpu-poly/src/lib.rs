@@ -10,6 +10,8 @@ use allocator::PageAlignedAllocator;
 #[macro_use]
 pub mod macros;
 pub mod allocator;
+pub mod cache;
+pub mod device;
 pub mod fields;
 pub mod plan;
 pub mod prelude;
@@ -4,6 +4,7 @@
 #include <metal_stdlib>
 #include "felt_u128.h.metal"
 #include "felt_u64.h.metal"
+#include "felt_u32.h.metal"
 #include "permute.h.metal"
 using namespace metal;
 
@@ -15,6 +16,36 @@ InverseInPlace(device FieldT *dst [[ buffer(0) ]],
     dst[i] = v.inverse();
 }
 
+// Inverts values in place using Montgomery's trick. Each thread inverts a
+// chunk of `chunk_size` values with a single field inversion. `scratch` holds
+// the running products and must be at least `N` elements.
+template<typename FieldT> kernel void
+BatchInverse(device FieldT *vals [[ buffer(0) ]],
+        device FieldT *scratch [[ buffer(1) ]],
+        constant unsigned &chunk_size [[ buffer(2) ]],
+        unsigned global_tid [[ thread_position_in_grid ]]) {
+    unsigned start = global_tid * chunk_size;
+    unsigned end = min(start + chunk_size, N);
+    if (start >= end) {
+        return;
+    }
+
+    FieldT acc = vals[start];
+    scratch[start] = acc;
+    for (unsigned i = start + 1; i < end; i++) {
+        acc = acc * vals[i];
+        scratch[i] = acc;
+    }
+
+    FieldT inv = acc.inverse();
+    for (unsigned i = end - 1; i > start; i--) {
+        FieldT v = vals[i];
+        vals[i] = inv * scratch[i - 1];
+        inv = inv * v;
+    }
+    vals[start] = inv;
+}
+
 template<typename FieldT> kernel void
 ExpInPlace(device FieldT *dst [[ buffer(0) ]],
         constant unsigned &exponent [[ buffer(1) ]],
@@ -160,6 +191,81 @@ MulPow(device LHSFieldT *lhs [[ buffer(0) ]],
     lhs[lhs_idx] = lhs_val * rhs_val.pow(exponent);
 }
 
+// vals[i] *= scale * ratio^i
+// Each thread multiplies a chunk of `chunk_size` values so powers of `ratio`
+// can be accumulated rather than computed from scratch.
+template<typename LHSFieldT, typename RHSFieldT = LHSFieldT> kernel void
+MulGeometricSeries(device LHSFieldT *vals [[ buffer(0) ]],
+        constant RHSFieldT &ratio [[ buffer(1) ]],
+        constant RHSFieldT &scale [[ buffer(2) ]],
+        constant unsigned &chunk_size [[ buffer(3) ]],
+        unsigned global_tid [[ thread_position_in_grid ]]) {
+    unsigned start = global_tid * chunk_size;
+    unsigned end = min(start + chunk_size, N);
+    RHSFieldT step = ratio;
+    RHSFieldT base = ratio;
+    RHSFieldT pow = scale * base.pow(start);
+    for (unsigned i = start; i < end; i++) {
+        LHSFieldT val = vals[i];
+        vals[i] = val * pow;
+        pow = pow * step;
+    }
+}
+
+// Folds `N` evaluations over the coset `offset * <generator>` by
+// `folding_factor`. Evaluations folded into `dst[i]` are
+// `evals[i + k * N / folding_factor]` at the points `x_i * w_k` where
+// `x_i = offset * generator^i` and `w_k` is the `k`th `folding_factor`-th root
+// of unity:
+// dst[i] = 1/folding_factor * Σ_k evals[..] * Σ_j (alpha / (x_i * w_k))^j
+template<typename LHSFieldT, typename RHSFieldT = LHSFieldT> kernel void
+FriFold(device LHSFieldT *dst [[ buffer(0) ]],
+        constant LHSFieldT *evals [[ buffer(1) ]],
+        constant LHSFieldT &alpha [[ buffer(2) ]],
+        constant RHSFieldT &offset_inv [[ buffer(3) ]],
+        constant RHSFieldT &generator_inv [[ buffer(4) ]],
+        constant RHSFieldT *roots_inv [[ buffer(5) ]],
+        constant RHSFieldT &folding_factor_inv [[ buffer(6) ]],
+        constant unsigned &folding_factor [[ buffer(7) ]],
+        unsigned global_tid [[ thread_position_in_grid ]]) {
+    unsigned stride = N / folding_factor;
+    RHSFieldT generator_inv_val = generator_inv;
+    RHSFieldT x_inv = generator_inv_val.pow(global_tid) * offset_inv;
+    LHSFieldT alpha_val = alpha;
+
+    LHSFieldT acc = evals[global_tid];
+    for (unsigned k = 0; k < folding_factor; k++) {
+        RHSFieldT root_inv = roots_inv[k];
+        LHSFieldT ratio = alpha_val * (x_inv * root_inv);
+        LHSFieldT term = evals[global_tid + k * stride];
+        LHSFieldT sum = term;
+        for (unsigned j = 1; j < folding_factor; j++) {
+            term = term * ratio;
+            sum = sum + term;
+        }
+        acc = k == 0 ? sum : acc + sum;
+    }
+
+    RHSFieldT scale = folding_factor_inv;
+    dst[global_tid] = acc * scale;
+}
+
+// Accumulates the DEEP quotient of one column evaluated over a domain
+// dst[i] += (alpha * evals[i] - alpha_ood) * denominator_invs[i]
+// where alpha_ood = alpha * ood_eval and denominator_invs[i] = 1 / (x_i - z)
+template<typename LHSFieldT, typename RHSFieldT = LHSFieldT> kernel void
+DeepQuotient(device LHSFieldT *dst [[ buffer(0) ]],
+        constant RHSFieldT *evals [[ buffer(1) ]],
+        constant LHSFieldT *denominator_invs [[ buffer(2) ]],
+        constant LHSFieldT &alpha [[ buffer(3) ]],
+        constant LHSFieldT &alpha_ood [[ buffer(4) ]],
+        unsigned i [[ thread_position_in_grid ]]) {
+    LHSFieldT alpha_val = alpha;
+    LHSFieldT alpha_ood_val = alpha_ood;
+    LHSFieldT numerator = alpha_val * evals[i] - alpha_ood_val;
+    dst[i] = dst[i] + numerator * denominator_invs[i];
+}
+
 template<typename FieldT> kernel void
 FillBuff(device FieldT *dst [[ buffer(0) ]],
         constant FieldT &value [[ buffer(1) ]],
@@ -167,6 +273,73 @@ FillBuff(device FieldT *dst [[ buffer(0) ]],
     dst[global_tid] = value;
 }
 
+// Must match `MAX_FUSED_REGISTERS` in ministark's `constraints.rs`
+#define MAX_FUSED_REGISTERS 32
+
+// Opcodes of a fused program. Must match `Opcode` in ministark's `constraints.rs`
+#define OP_LOAD_X 0
+#define OP_LOAD_CONST_FP 1
+#define OP_LOAD_CONST_FQ 2
+#define OP_LOAD_TRACE_FP 3
+#define OP_LOAD_TRACE_FQ 4
+#define OP_ADD_FP 5
+#define OP_ADD_FQ 6
+#define OP_ADD_FQ_FP 7
+#define OP_MUL_FP 8
+#define OP_MUL_FQ 9
+#define OP_MUL_FQ_FP 10
+#define OP_NEG_FP 11
+#define OP_NEG_FQ 12
+#define OP_EXP_FP 13
+#define OP_EXP_FQ 14
+#define OP_INV_EXP_FP 15
+#define OP_RETURN_FP 16
+#define OP_RETURN_FQ 17
+
+// Evaluates an entire constraint for a single row by interpreting a program
+// of `[opcode, dst, a, b]` instructions. Trace columns are stored column-major
+// with `N` rows each.
+template<typename FqT, typename FpT> kernel void
+EvaluateFused(device FqT *dst [[ buffer(0) ]],
+        constant unsigned *program [[ buffer(1) ]],
+        constant unsigned &program_len [[ buffer(2) ]],
+        constant FpT *fp_constants [[ buffer(3) ]],
+        constant FqT *fq_constants [[ buffer(4) ]],
+        constant FpT *xs [[ buffer(5) ]],
+        constant FpT *fp_trace [[ buffer(6) ]],
+        constant FqT *fq_trace [[ buffer(7) ]],
+        unsigned i [[ thread_position_in_grid ]]) {
+    FpT fp_regs[MAX_FUSED_REGISTERS];
+    FqT fq_regs[MAX_FUSED_REGISTERS];
+
+    for (unsigned pc = 0; pc < program_len; pc++) {
+        unsigned opcode = program[pc * 4];
+        unsigned d = program[pc * 4 + 1];
+        unsigned a = program[pc * 4 + 2];
+        unsigned b = program[pc * 4 + 3];
+        switch (opcode) {
+            case OP_LOAD_X: fp_regs[d] = xs[i]; break;
+            case OP_LOAD_CONST_FP: fp_regs[d] = fp_constants[a]; break;
+            case OP_LOAD_CONST_FQ: fq_regs[d] = fq_constants[a]; break;
+            case OP_LOAD_TRACE_FP: fp_regs[d] = fp_trace[a * N + (i + b) % N]; break;
+            case OP_LOAD_TRACE_FQ: fq_regs[d] = fq_trace[a * N + (i + b) % N]; break;
+            case OP_ADD_FP: fp_regs[d] = fp_regs[a] + fp_regs[b]; break;
+            case OP_ADD_FQ: fq_regs[d] = fq_regs[a] + fq_regs[b]; break;
+            case OP_ADD_FQ_FP: fq_regs[d] = fq_regs[a] + fp_regs[b]; break;
+            case OP_MUL_FP: fp_regs[d] = fp_regs[a] * fp_regs[b]; break;
+            case OP_MUL_FQ: fq_regs[d] = fq_regs[a] * fq_regs[b]; break;
+            case OP_MUL_FQ_FP: fq_regs[d] = fq_regs[a] * fp_regs[b]; break;
+            case OP_NEG_FP: fp_regs[d] = fp_regs[a].neg(); break;
+            case OP_NEG_FQ: fq_regs[d] = fq_regs[a].neg(); break;
+            case OP_EXP_FP: fp_regs[d] = fp_regs[a].pow(b); break;
+            case OP_EXP_FQ: fq_regs[d] = fq_regs[a].pow(b); break;
+            case OP_INV_EXP_FP: fp_regs[d] = fp_regs[a].pow(b).inverse(); break;
+            case OP_RETURN_FP: dst[i] = FqT(fp_regs[a]); return;
+            case OP_RETURN_FQ: dst[i] = fq_regs[a]; return;
+        }
+    }
+}
+
 // ===========================================================
 // Evaluation for Fp=18446744069414584321
 template [[ host_name("add_assign_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
@@ -190,6 +363,32 @@ AddAssignConst<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
         constant p18446744069414584321::Fp&,
         unsigned);
+template [[ host_name("mul_geometric_series_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
+MulGeometricSeries<p18446744069414584321::Fp, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
+FriFold<p18446744069414584321::Fp, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
+DeepQuotient<p18446744069414584321::Fp, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        unsigned);
 template [[ host_name("mul_assign_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
 MulAssign<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
@@ -238,6 +437,12 @@ template [[ host_name("inverse_in_place_p18446744069414584321_fp") ]] kernel voi
 InverseInPlace<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
         unsigned);
+template [[ host_name("batch_inverse_p18446744069414584321_fp") ]] kernel void
+BatchInverse<p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        device p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
 template [[ host_name("exp_in_place_p18446744069414584321_fp") ]] kernel void
 ExpInPlace<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
@@ -268,6 +473,17 @@ FillBuff<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
         constant p18446744069414584321::Fp&,
         unsigned);
+template [[ host_name("evaluate_fused_LHS_p18446744069414584321_fp_RHS_p18446744069414584321_fp") ]] kernel void
+EvaluateFused<p18446744069414584321::Fp, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        unsigned);
 // ===========================================================
 // Evaluation for cubic extension of Fp=18446744069414584321
 template [[ host_name("add_assign_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fq3") ]] kernel void
@@ -347,6 +563,40 @@ FillBuff<p18446744069414584321::Fq3>(
         device p18446744069414584321::Fq3*,
         constant p18446744069414584321::Fq3&,
         unsigned);
+template [[ host_name("mul_geometric_series_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fp") ]] kernel void
+MulGeometricSeries<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fp") ]] kernel void
+FriFold<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fp") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3&,
+        constant p18446744069414584321::Fq3&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fq3") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq3, p18446744069414584321::Fq3>(
+        device p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fq3&,
+        constant p18446744069414584321::Fq3&,
+        unsigned);
 template [[ host_name("mul_assign_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fp") ]] kernel void
 MulAssign<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
         device p18446744069414584321::Fq3*,
@@ -403,6 +653,377 @@ NegInto<p18446744069414584321::Fq3>(
         device p18446744069414584321::Fq3*,
         constant p18446744069414584321::Fq3*,
         unsigned);
+template [[ host_name("evaluate_fused_LHS_p18446744069414584321_fq3_RHS_p18446744069414584321_fp") ]] kernel void
+EvaluateFused<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq3*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq3*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq3*,
+        unsigned);
+// ===========================================================
+// Evaluation for quadratic extension of Fp=18446744069414584321
+template [[ host_name("add_assign_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+AddAssign<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+AddAssignConst<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("add_assign_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+AddAssign<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+AddAssignConst<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+AddInto<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+AddIntoConst<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+AddIntoConst<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+AddInto<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+MulAssign<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+MulAssignConst<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+MulInto<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+MulIntoConst<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("fill_buff_p18446744069414584321_fq2") ]] kernel void
+FillBuff<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("mul_geometric_series_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulGeometricSeries<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+FriFold<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq2, p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2&,
+        constant p18446744069414584321::Fq2&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulAssign<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulAssignConst<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulInto<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulIntoConst<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fq2") ]] kernel void
+MulPow<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+MulPow<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+// template [[ host_name("inverse_in_place_p18446744069414584321_fq2") ]] kernel void
+// InverseInPlace<p18446744069414584321::Fq2>(
+//         device p18446744069414584321::Fq2*,
+//         unsigned);
+template [[ host_name("neg_in_place_p18446744069414584321_fq2") ]] kernel void
+NegInPlace<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        unsigned);
+// template [[ host_name("inverse_into_p18446744069414584321_fq2") ]] kernel void
+// InverseInto<p18446744069414584321::Fq2>(
+//         device p18446744069414584321::Fq2*,
+//         constant p18446744069414584321::Fq2*,
+//         unsigned);
+template [[ host_name("neg_into_p18446744069414584321_fq2") ]] kernel void
+NegInto<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fq2*,
+        unsigned);
+template [[ host_name("evaluate_fused_LHS_p18446744069414584321_fq2_RHS_p18446744069414584321_fp") ]] kernel void
+EvaluateFused<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq2*,
+        unsigned);
+// ===========================================================
+// Evaluation for degree four extension of Fp=18446744069414584321
+template [[ host_name("add_assign_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+AddAssign<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+AddAssignConst<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("add_assign_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+AddAssign<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+AddAssignConst<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+AddInto<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+AddIntoConst<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+AddIntoConst<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+AddInto<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+MulAssign<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+MulAssignConst<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+MulInto<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+MulIntoConst<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("fill_buff_p18446744069414584321_fq4") ]] kernel void
+FillBuff<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("mul_geometric_series_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulGeometricSeries<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+FriFold<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+DeepQuotient<p18446744069414584321::Fq4, p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4&,
+        constant p18446744069414584321::Fq4&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulAssign<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulAssignConst<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulInto<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulIntoConst<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fq4") ]] kernel void
+MulPow<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+MulPow<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+// template [[ host_name("inverse_in_place_p18446744069414584321_fq4") ]] kernel void
+// InverseInPlace<p18446744069414584321::Fq4>(
+//         device p18446744069414584321::Fq4*,
+//         unsigned);
+template [[ host_name("neg_in_place_p18446744069414584321_fq4") ]] kernel void
+NegInPlace<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        unsigned);
+// template [[ host_name("inverse_into_p18446744069414584321_fq4") ]] kernel void
+// InverseInto<p18446744069414584321::Fq4>(
+//         device p18446744069414584321::Fq4*,
+//         constant p18446744069414584321::Fq4*,
+//         unsigned);
+template [[ host_name("neg_into_p18446744069414584321_fq4") ]] kernel void
+NegInto<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fq4*,
+        unsigned);
+template [[ host_name("evaluate_fused_LHS_p18446744069414584321_fq4_RHS_p18446744069414584321_fp") ]] kernel void
+EvaluateFused<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fp*,
+        constant p18446744069414584321::Fq4*,
+        unsigned);
 // ===========================================================
 // Evaluation for Fp=3618502788666131213697322783095070105623107215331596699973092056135872020481
 template [[ host_name("add_assign_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
@@ -421,6 +1042,32 @@ AddAssignConst<p3618502788666131213697322783095070105623107215331596699973092056
         device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
         constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
         unsigned);
+template [[ host_name("mul_geometric_series_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
+MulGeometricSeries<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp, p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
+FriFold<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp, p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
+DeepQuotient<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp, p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
+        unsigned);
 template [[ host_name("mul_assign_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
 MulAssign<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
         device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
@@ -469,6 +1116,12 @@ template [[ host_name("inverse_in_place_p361850278866613121369732278309507010562
 InverseInPlace<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
         device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
         unsigned);
+template [[ host_name("batch_inverse_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
+BatchInverse<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant unsigned&,
+        unsigned);
 template [[ host_name("exp_in_place_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
 ExpInPlace<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
         device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
@@ -499,6 +1152,341 @@ FillBuff<p3618502788666131213697322783095070105623107215331596699973092056135872
         device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
         constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp&,
         unsigned);
+template [[ host_name("evaluate_fused_LHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp_RHS_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
+EvaluateFused<p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp, p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp>(
+        device p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        constant p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp*,
+        unsigned);
 // ===========================================================
 
+// ===========================================================
+// Evaluation for Fp=2013265921
+template [[ host_name("add_assign_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+AddAssign<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("convert_into_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+ConvertInto<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("convert_into_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+ConvertInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+AddAssignConst<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_geometric_series_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulGeometricSeries<p2013265921::Fp, p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+FriFold<p2013265921::Fp, p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+DeepQuotient<p2013265921::Fp, p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulAssign<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulAssignConst<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+AddInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+AddIntoConst<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulIntoConst<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+MulPow<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("inverse_in_place_p2013265921_fp") ]] kernel void
+InverseInPlace<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        unsigned);
+template [[ host_name("batch_inverse_p2013265921_fp") ]] kernel void
+BatchInverse<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        device p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("exp_in_place_p2013265921_fp") ]] kernel void
+ExpInPlace<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("neg_in_place_p2013265921_fp") ]] kernel void
+NegInPlace<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        unsigned);
+template [[ host_name("inverse_into_p2013265921_fp") ]] kernel void
+InverseInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("exp_into_p2013265921_fp") ]] kernel void
+ExpInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("neg_into_p2013265921_fp") ]] kernel void
+NegInto<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("fill_buff_p2013265921_fp") ]] kernel void
+FillBuff<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("evaluate_fused_LHS_p2013265921_fp_RHS_p2013265921_fp") ]] kernel void
+EvaluateFused<p2013265921::Fp, p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        unsigned);
+// ===========================================================
+// Evaluation for degree four extension of Fp=2013265921
+template [[ host_name("add_assign_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+AddAssign<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+AddAssignConst<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("add_assign_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+AddAssign<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_assign_const_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+AddAssignConst<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+AddInto<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+AddIntoConst<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("add_into_const_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+AddIntoConst<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("add_into_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+AddInto<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+MulAssign<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+MulAssignConst<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+MulInto<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+MulIntoConst<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("fill_buff_p2013265921_fq4") ]] kernel void
+FillBuff<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("mul_geometric_series_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulGeometricSeries<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("fri_fold_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+FriFold<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp&,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+DeepQuotient<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("deep_quotient_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+DeepQuotient<p2013265921::Fq4, p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fq4&,
+        constant p2013265921::Fq4&,
+        unsigned);
+template [[ host_name("mul_assign_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulAssign<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_assign_const_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulAssignConst<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_into_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulInto<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_into_const_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulIntoConst<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p2013265921_fq4_RHS_p2013265921_fq4") ]] kernel void
+MulPow<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+template [[ host_name("mul_pow_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+MulPow<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant unsigned&,
+        constant unsigned&,
+        unsigned);
+// template [[ host_name("inverse_in_place_p2013265921_fq4") ]] kernel void
+// InverseInPlace<p2013265921::Fq4>(
+//         device p2013265921::Fq4*,
+//         unsigned);
+template [[ host_name("neg_in_place_p2013265921_fq4") ]] kernel void
+NegInPlace<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        unsigned);
+// template [[ host_name("inverse_into_p2013265921_fq4") ]] kernel void
+// InverseInto<p2013265921::Fq4>(
+//         device p2013265921::Fq4*,
+//         constant p2013265921::Fq4*,
+//         unsigned);
+template [[ host_name("neg_into_p2013265921_fq4") ]] kernel void
+NegInto<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fq4*,
+        unsigned);
+template [[ host_name("evaluate_fused_LHS_p2013265921_fq4_RHS_p2013265921_fp") ]] kernel void
+EvaluateFused<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant unsigned*,
+        constant unsigned&,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        constant p2013265921::Fq4*,
+        unsigned);
 #endif /* evaluation_shaders_h */
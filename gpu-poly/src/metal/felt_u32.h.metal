@@ -0,0 +1,204 @@
+#ifndef felt_u32_h
+#define felt_u32_h
+
+// Fields that use prime 2013265921
+namespace p2013265921
+{
+
+    // Prime field (BabyBear)
+    // Elements use the same layout as arkworks: a 64-bit word holding the
+    // Montgomery form `x * 2^64 mod p`. Every value is less than 2^31 so sums
+    // never overflow and products fit in 64 bits before reduction.
+    class Fp
+    {
+    public:
+        Fp() = default;
+        constexpr Fp(unsigned long v) : inner(v) {}
+
+        constexpr Fp operator+(const Fp rhs) const
+        {
+            unsigned long sum = inner + rhs.inner;
+            return Fp(sum >= N ? sum - N : sum);
+        }
+
+        constexpr Fp operator-(const Fp rhs) const
+        {
+            return Fp(inner >= rhs.inner ? inner - rhs.inner : inner + N - rhs.inner);
+        }
+
+        Fp operator*(const Fp rhs) const
+        {
+            return Fp(mul(inner, rhs.inner));
+        }
+
+        Fp pow(unsigned exp)
+        {
+            Fp res = ONE;
+
+            while (exp > 0)
+            {
+                if (exp & 1)
+                {
+                    res = res * *this;
+                }
+                exp >>= 1;
+                *this = *this * *this;
+            }
+
+            return res;
+        }
+
+        Fp inverse()
+        {
+            // Fermat's little theorem: x^(p - 2)
+            return pow(N - 2);
+        }
+
+        Fp neg()
+        {
+            return Fp(inner == 0 ? 0 : N - inner);
+        }
+
+        // 1 in Montgomery representation
+        constexpr static const constant unsigned long ONE = 1172168163;
+
+    private:
+        unsigned long inner;
+
+        // Field modulus `p = 15 * 2^27 + 1`
+        constexpr static const constant unsigned long N = 2013265921;
+
+        // `-p^(-1) mod 2^64`
+        constexpr static const constant unsigned long N_PRIME = 14393504411089371135;
+
+        inline unsigned long mul(const unsigned long lhs, const unsigned long rhs) const
+        {
+            // Montgomery reduction of a product less than p^2 < 2^62
+            unsigned long t = lhs * rhs;
+            unsigned long m = t * N_PRIME;
+            // low word of `t + m * p` is always zero so only the carry remains
+            unsigned long r = metal::mulhi(m, N) + (t != 0);
+            return r >= N ? r - N : r;
+        }
+    };
+
+    // Quadratic extension field over irreducible polynomial x^2 - 11
+    class Fq2
+    {
+    public:
+        Fq2() = default;
+        constexpr Fq2(Fp c) : c0(c), c1(Fp(0)) {}
+        constexpr Fq2(Fp c0, Fp c1) : c0(c0), c1(c1) {}
+
+        constexpr Fq2 operator+(const Fq2 rhs) const
+        {
+            return Fq2(c0 + rhs.c0, c1 + rhs.c1);
+        }
+
+        constexpr Fq2 operator-(const Fq2 rhs) const
+        {
+            return Fq2(c0 - rhs.c0, c1 - rhs.c1);
+        }
+
+        Fq2 operator*(const Fq2 rhs) const
+        {
+            // Karatsuba
+            Fp v0 = c0 * rhs.c0;
+            Fp v1 = c1 * rhs.c1;
+            return Fq2(
+                /* =c0 */ v0 + v1 * Fp(NONREDIDUE),
+                /* =c1 */ (c0 + c1) * (rhs.c0 + rhs.c1) - v0 - v1
+            );
+        }
+
+        Fq2 operator*(const Fp rhs) const
+        {
+            return Fq2(c0 * rhs, c1 * rhs);
+        }
+
+        // Multiplies by `u` where `u^2 = NONRESIDUE`
+        Fq2 mul_by_nonresidue() const
+        {
+            return Fq2(c1 * Fp(NONREDIDUE), c0);
+        }
+
+        Fq2 neg()
+        {
+            return Fq2(c0.neg(), c1.neg());
+        }
+
+    private:
+        Fp c0, c1;
+
+        // `11` in Montgomery representation
+        constexpr static const constant unsigned long NONREDIDUE = /* =11 */ 814254267;
+    };
+
+    // Degree four extension built as a quadratic extension of Fq2 over
+    // irreducible polynomial x^2 - u. Matches the arkworks `Fp4` tower layout
+    // and is isomorphic to Fp[x]/(x^4 - 11).
+    class Fq4
+    {
+    public:
+        Fq4() = default;
+        constexpr Fq4(Fp c) : c0(Fq2(c)), c1(Fq2(Fp(0))) {}
+        constexpr Fq4(Fq2 c0, Fq2 c1) : c0(c0), c1(c1) {}
+
+        constexpr Fq4 operator+(const Fp rhs) const
+        {
+            return Fq4(c0 + Fq2(rhs), c1);
+        }
+
+        constexpr Fq4 operator+(const Fq4 rhs) const
+        {
+            return Fq4(c0 + rhs.c0, c1 + rhs.c1);
+        }
+
+        constexpr Fq4 operator-(const Fq4 rhs) const
+        {
+            return Fq4(c0 - rhs.c0, c1 - rhs.c1);
+        }
+
+        Fq4 operator*(const Fq4 rhs) const
+        {
+            // Karatsuba
+            Fq2 v0 = c0 * rhs.c0;
+            Fq2 v1 = c1 * rhs.c1;
+            return Fq4(
+                /* =c0 */ v0 + v1.mul_by_nonresidue(),
+                /* =c1 */ (c0 + c1) * (rhs.c0 + rhs.c1) - v0 - v1
+            );
+        }
+
+        Fq4 operator*(const Fp rhs) const
+        {
+            return Fq4(c0 * rhs, c1 * rhs);
+        }
+
+        Fq4 pow(unsigned exp)
+        {
+            Fq4 res = Fq4(Fp(Fp::ONE));
+
+            while (exp > 0) {
+                if (exp & 1) {
+                    res = res * *this;
+                }
+                exp >>= 1;
+                *this = *this * *this;
+            }
+
+            return res;
+        }
+
+        Fq4 neg()
+        {
+            return Fq4(c0.neg(), c1.neg());
+        }
+
+    private:
+        Fq2 c0, c1;
+    };
+
+}
+
+#endif /* felt_u32_h */
@@ -125,6 +125,82 @@ namespace p18446744069414584321
         }
     };
 
+    // Quadratic extension field over irreducible polynomial x^2 - 7
+    // adapted from arkworks
+    class Fq2
+    {
+    public:
+        Fq2() = default;
+        constexpr Fq2(Fp c) : c0(c), c1(Fp(0)) {}
+        constexpr Fq2(Fp c0, Fp c1) : c0(c0), c1(c1) {}
+
+        constexpr Fq2 operator+(const Fp rhs) const
+        {
+            return Fq2(c0 + rhs, c1);
+        }
+
+        constexpr Fq2 operator+(const Fq2 rhs) const
+        {
+            return Fq2(c0 + rhs.c0, c1 + rhs.c1);
+        }
+
+        constexpr Fq2 operator-(const Fq2 rhs) const
+        {
+            return Fq2(c0 - rhs.c0, c1 - rhs.c1);
+        }
+
+        Fq2 operator*(const Fq2 rhs) const
+        {
+            // Karatsuba
+            Fp v0 = c0 * rhs.c0;
+            Fp v1 = c1 * rhs.c1;
+            return Fq2(
+                /* =c0 */ v0 + v1 * Fp(NONREDIDUE),
+                /* =c1 */ (c0 + c1) * (rhs.c0 + rhs.c1) - v0 - v1
+            );
+        }
+
+        Fq2 operator*(const Fp rhs) const
+        {
+            return Fq2(c0 * rhs, c1 * rhs);
+        }
+
+        // TODO: make util function
+        Fq2 pow(unsigned exp)
+        {
+            Fq2 res = Fq2(Fp(Fp::ONE), Fp(0));
+
+            while (exp > 0) {
+                if (exp & 1) {
+                    res = res * *this;
+                }
+                exp >>= 1;
+                *this = *this * *this;
+            }
+
+            return res;
+        }
+
+        // Multiplies by `u` where `u^2 = NONRESIDUE`
+        Fq2 mul_by_nonresidue() const
+        {
+            return Fq2(c1 * Fp(NONREDIDUE), c0);
+        }
+
+        Fq2 neg()
+        {
+            return Fq2(Fp(0) - c0, Fp(0) - c1);
+        }
+
+    private:
+        Fp c0, c1;
+
+        // Quadratic non-residue used to construct the extension field in montgomery representation.
+        // That is, `NONRESIDUE` is such that the quadratic polynomial
+        // `f(X) = X^2 - NONRESIDUE` in Fp\[X\] is irreducible in `Fp`.
+        constexpr static const constant unsigned long NONREDIDUE = /* =7 */ 30064771065;
+    };
+
     // Cubig extension field over irreducible polynomial x^3 - 2
     // adapted from arkworks
     // TODO: make into generic cubic extension class
@@ -226,6 +302,72 @@ namespace p18446744069414584321
         constexpr static const constant unsigned long NONREDIDUE = /* =2 */ 8589934590;
     };
 
+
+    // Degree four extension built as a quadratic extension of Fq2 over
+    // irreducible polynomial x^2 - u. Matches the arkworks `Fp4` tower layout
+    // and is isomorphic to Fp[x]/(x^4 - 7).
+    class Fq4
+    {
+    public:
+        Fq4() = default;
+        constexpr Fq4(Fp c) : c0(Fq2(c)), c1(Fq2(Fp(0))) {}
+        constexpr Fq4(Fq2 c0, Fq2 c1) : c0(c0), c1(c1) {}
+
+        constexpr Fq4 operator+(const Fp rhs) const
+        {
+            return Fq4(c0 + rhs, c1);
+        }
+
+        constexpr Fq4 operator+(const Fq4 rhs) const
+        {
+            return Fq4(c0 + rhs.c0, c1 + rhs.c1);
+        }
+
+        constexpr Fq4 operator-(const Fq4 rhs) const
+        {
+            return Fq4(c0 - rhs.c0, c1 - rhs.c1);
+        }
+
+        Fq4 operator*(const Fq4 rhs) const
+        {
+            // Karatsuba
+            Fq2 v0 = c0 * rhs.c0;
+            Fq2 v1 = c1 * rhs.c1;
+            return Fq4(
+                /* =c0 */ v0 + v1.mul_by_nonresidue(),
+                /* =c1 */ (c0 + c1) * (rhs.c0 + rhs.c1) - v0 - v1
+            );
+        }
+
+        Fq4 operator*(const Fp rhs) const
+        {
+            return Fq4(c0 * rhs, c1 * rhs);
+        }
+
+        Fq4 pow(unsigned exp)
+        {
+            Fq4 res = Fq4(Fp(Fp::ONE));
+
+            while (exp > 0) {
+                if (exp & 1) {
+                    res = res * *this;
+                }
+                exp >>= 1;
+                *this = *this * *this;
+            }
+
+            return res;
+        }
+
+        Fq4 neg()
+        {
+            return Fq4(c0.neg(), c1.neg());
+        }
+
+    private:
+        Fq2 c0, c1;
+    };
+
 }
 
 #endif /* felt_u64_h */
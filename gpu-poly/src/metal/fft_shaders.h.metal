@@ -5,6 +5,7 @@
 #include "felt_u256.h.metal"
 #include "felt_u128.h.metal"
 #include "felt_u64.h.metal"
+#include "felt_u32.h.metal"
 #include "permute.h.metal"
 using namespace metal;
 
@@ -162,6 +163,42 @@ FftMultiple<p18446744069414584321::Fq3, p18446744069414584321::Fp>(
         unsigned,
         unsigned);
 // ===========================================================
+// FFT for quadratic extension of Fp=18446744069414584321
+template [[ host_name("bit_reverse_p18446744069414584321_fq2") ]] kernel void
+BitReverse<p18446744069414584321::Fq2>(
+        device p18446744069414584321::Fq2*,
+        unsigned);
+template [[ host_name("fft_single_p18446744069414584321_fq2") ]] kernel void
+FftSingle<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        unsigned);
+template [[ host_name("fft_multiple_p18446744069414584321_fq2") ]] kernel void
+FftMultiple<p18446744069414584321::Fq2, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq2*,
+        constant p18446744069414584321::Fp*,
+        threadgroup p18446744069414584321::Fq2*,
+        unsigned,
+        unsigned);
+// ===========================================================
+// FFT for degree four extension of Fp=18446744069414584321
+template [[ host_name("bit_reverse_p18446744069414584321_fq4") ]] kernel void
+BitReverse<p18446744069414584321::Fq4>(
+        device p18446744069414584321::Fq4*,
+        unsigned);
+template [[ host_name("fft_single_p18446744069414584321_fq4") ]] kernel void
+FftSingle<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        unsigned);
+template [[ host_name("fft_multiple_p18446744069414584321_fq4") ]] kernel void
+FftMultiple<p18446744069414584321::Fq4, p18446744069414584321::Fp>(
+        device p18446744069414584321::Fq4*,
+        constant p18446744069414584321::Fp*,
+        threadgroup p18446744069414584321::Fq4*,
+        unsigned,
+        unsigned);
+// ===========================================================
 // FFT for Fp=3618502788666131213697322783095070105623107215331596699973092056135872020481
 // StarkWare's field
 template [[ host_name("bit_reverse_p3618502788666131213697322783095070105623107215331596699973092056135872020481_fp") ]] kernel void
@@ -324,4 +361,48 @@ FftMultiple<p3618502788666131213697322783095070105623107215331596699973092056135
 //         device p18446744069414584321::Fp*,
 //         unsigned);
 
+// ===========================================================
+// FFT for Fp=2013265921
+// - 31 bit prime field (15*2^27+1 = 2013265921)
+// - BabyBear field (used by RISC Zero)
+// - Elements are stored as 64-bit Montgomery words like arkworks
+template [[ host_name("bit_reverse_p2013265921_fp") ]] kernel void
+BitReverse<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        unsigned);
+template [[ host_name("generate_twiddles_p2013265921_fp") ]] kernel void
+GenerateTwiddles<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp&,
+        unsigned);
+template [[ host_name("fft_single_p2013265921_fp") ]] kernel void
+FftSingle<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("fft_multiple_p2013265921_fp") ]] kernel void
+FftMultiple<p2013265921::Fp>(
+        device p2013265921::Fp*,
+        constant p2013265921::Fp*,
+        threadgroup p2013265921::Fp*,
+        unsigned,
+        unsigned);
+// ===========================================================
+// FFT for degree four extension of Fp=2013265921
+template [[ host_name("bit_reverse_p2013265921_fq4") ]] kernel void
+BitReverse<p2013265921::Fq4>(
+        device p2013265921::Fq4*,
+        unsigned);
+template [[ host_name("fft_single_p2013265921_fq4") ]] kernel void
+FftSingle<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        unsigned);
+template [[ host_name("fft_multiple_p2013265921_fq4") ]] kernel void
+FftMultiple<p2013265921::Fq4, p2013265921::Fp>(
+        device p2013265921::Fq4*,
+        constant p2013265921::Fp*,
+        threadgroup p2013265921::Fq4*,
+        unsigned,
+        unsigned);
 #endif /* fft_shaders_h */
\ No newline at end of file
@@ -1,14 +1,19 @@
 #![cfg(target_arch = "aarch64")]
 
+extern crate std;
+
 use crate::allocator::PageAlignedAllocator;
+use crate::device::current_device;
 use crate::stage::BitReverseGpuStage;
 use crate::stage::FftGpuStage;
 use crate::stage::ScaleAndNormalizeGpuStage;
 use crate::stage::Variant;
 use crate::utils;
+use crate::GpuFftField;
 use crate::GpuField;
-use crate::GpuVec;
-use alloc::rc::Rc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
@@ -17,10 +22,13 @@ use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
 use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
+use std::sync::Mutex;
 
-const LIBRARY_DATA: &[u8] = include_bytes!("metal/shaders.metallib");
+// compiled from `metal/shaders.metal` by the build script
+const LIBRARY_DATA: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/shaders.metallib"));
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 enum FftDirection {
     /// FFT
     Forward,
@@ -33,10 +41,8 @@ where
     F::FftField: FftField,
 {
     n: usize,
-    command_queue: Rc<metal::CommandQueue>,
-    // twiddles_buffer references this memory
-    // field exists to keep the memory around
-    _twiddles: GpuVec<F::FftField>,
+    command_queue: Arc<metal::CommandQueue>,
+    // shared by every plan over a domain of the same size
     twiddles_buffer: metal::Buffer,
     scale_and_normalize_stage: Option<ScaleAndNormalizeGpuStage<F, F::FftField>>,
     butterfly_stages: Vec<FftGpuStage<F>>,
@@ -113,7 +119,7 @@ where
     F::FftField: FftField,
 {
     fn from(domain: Radix2EvaluationDomain<F::FftField>) -> Self {
-        PLANNER.plan_fft(domain)
+        planner().plan_fft(domain)
     }
 }
 
@@ -153,31 +159,93 @@ where
     F::FftField: FftField,
 {
     fn from(domain: Radix2EvaluationDomain<F::FftField>) -> Self {
-        PLANNER.plan_ifft(domain)
+        planner().plan_ifft(domain)
     }
 }
 
+/// Planner bound to the system default device
 pub static PLANNER: Lazy<Planner> = Lazy::new(Planner::default);
 
+// planners of devices selected with `with_device`. Created on first use
+static DEVICE_PLANNERS: Lazy<Vec<OnceCell<Planner>>> = Lazy::new(|| {
+    metal::Device::all()
+        .iter()
+        .map(|_| OnceCell::new())
+        .collect()
+});
+
+/// Returns the planner of the device selected with
+/// [with_device](crate::device::with_device) on the current thread or
+/// [PLANNER] if no device was selected
+pub fn planner() -> &'static Planner {
+    match current_device() {
+        Some(index) => {
+            DEVICE_PLANNERS[index].get_or_init(|| Planner::new(&metal::Device::all()[index]))
+        }
+        None => &PLANNER,
+    }
+}
+
+/// Twiddles are keyed by field name, domain size and direction
+type TwiddlesKey = (String, usize, FftDirection);
+
 pub struct Planner {
     pub library: metal::Library,
-    pub command_queue: Rc<metal::CommandQueue>,
+    pub command_queue: Arc<metal::CommandQueue>,
+    /// Twiddle buffers shared by every plan with the same key so plans for
+    /// the same domain (e.g. those of a batch of proofs) are cheap to create
+    twiddles: Mutex<BTreeMap<TwiddlesKey, metal::Buffer>>,
 }
 
-// TODO: unsafe
+// SAFETY: Metal objects are reference counted atomically so moving them
+// between threads is sound and the command queue is shared with `Arc` rather
+// than `Rc` for the same reason. The library and the command queue are used
+// concurrently which Metal allows: libraries are immutable and command queues
+// are documented as thread safe, each thread encoding into its own command
+// buffer. Twiddle buffers are only accessed while holding their mutex and
+// are never written to once created.
 unsafe impl Send for Planner {}
 unsafe impl Sync for Planner {}
 
 impl Planner {
     pub fn new(device: &metal::DeviceRef) -> Self {
         let library = device.new_library_with_data(LIBRARY_DATA).unwrap();
-        let command_queue = Rc::new(device.new_command_queue());
+        let command_queue = Arc::new(device.new_command_queue());
         Self {
             library,
             command_queue,
+            twiddles: Mutex::new(BTreeMap::new()),
         }
     }
 
+    /// Returns the twiddles of an FFT over a domain of size `n` with
+    /// generator `root`. Computed on first use and shared afterwards.
+    fn twiddles<F: GpuFftField + FftField>(
+        &self,
+        direction: FftDirection,
+        n: usize,
+        root: F,
+    ) -> metal::Buffer {
+        let key = (F::field_name(), n, direction);
+        let mut twiddles = self.twiddles.lock().unwrap();
+        if let Some(buffer) = twiddles.get(&key) {
+            return buffer.clone();
+        }
+
+        let mut values = Vec::with_capacity_in(n / 2, PageAlignedAllocator);
+        values.resize(n / 2, F::zero());
+        utils::fill_twiddles(&mut values, root);
+        utils::bit_reverse(&mut values);
+        // copied so the buffer doesn't borrow `values`
+        let buffer = self.command_queue.device().new_buffer_with_data(
+            values.as_ptr() as *const core::ffi::c_void,
+            (values.len() * core::mem::size_of::<F>()) as u64,
+            metal::MTLResourceOptions::StorageModeShared,
+        );
+        twiddles.insert(key, buffer.clone());
+        buffer
+    }
+
     pub fn plan_fft<F: GpuField + Field>(
         &self,
         domain: Radix2EvaluationDomain<F::FftField>,
@@ -217,12 +285,7 @@ impl Planner {
             FftDirection::Inverse => domain.group_gen_inv,
         };
 
-        // generate twiddles buffer
-        let mut _twiddles = Vec::with_capacity_in(n / 2, PageAlignedAllocator);
-        _twiddles.resize(n / 2, F::FftField::zero());
-        utils::fill_twiddles(&mut _twiddles, root);
-        utils::bit_reverse(&mut _twiddles);
-        let twiddles_buffer = utils::buffer_no_copy(device, &_twiddles);
+        let twiddles_buffer = self.twiddles(direction, n, root);
 
         // in-place FFT requires a bit reversal
         let bit_reverse_stage = BitReverseGpuStage::new(&self.library, n);
@@ -234,7 +297,6 @@ impl Planner {
             } else {
                 Some(ScaleAndNormalizeGpuStage::new(
                     &self.library,
-                    &self.command_queue,
                     n,
                     domain.offset,
                     F::FftField::one(),
@@ -243,7 +305,6 @@ impl Planner {
         } else {
             Some(ScaleAndNormalizeGpuStage::new(
                 &self.library,
-                &self.command_queue,
                 n,
                 domain.offset_inv,
                 domain.size_inv,
@@ -277,12 +338,11 @@ impl Planner {
 
         FftEncoder {
             n,
-            _twiddles,
             twiddles_buffer,
             scale_and_normalize_stage,
             butterfly_stages,
             bit_reverse_stage,
-            command_queue: Rc::clone(&self.command_queue),
+            command_queue: Arc::clone(&self.command_queue),
             command_buffer: self.command_queue.new_command_buffer(),
         }
     }
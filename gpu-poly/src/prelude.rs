@@ -1,5 +1,12 @@
+#[cfg(unix)]
+pub use crate::allocator::enable_disk_spilling;
+pub use crate::allocator::BufferPool;
 pub use crate::allocator::PageAlignedAllocator;
 #[cfg(target_arch = "aarch64")]
+pub use crate::cache::KERNEL_CACHE;
+#[cfg(target_arch = "aarch64")]
+pub use crate::plan::planner;
+#[cfg(target_arch = "aarch64")]
 pub use crate::plan::GpuFft;
 #[cfg(target_arch = "aarch64")]
 pub use crate::plan::GpuIfft;
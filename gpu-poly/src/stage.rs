@@ -1,16 +1,11 @@
 #![cfg(target_arch = "aarch64")]
 
 use super::GpuField;
-use crate::allocator::PageAlignedAllocator;
-use crate::utils::buffer_no_copy;
-use crate::utils::distribute_powers;
+use crate::cache::KERNEL_CACHE;
 use crate::utils::void_ptr;
 use crate::GpuAdd;
 use crate::GpuMul;
-use crate::GpuVec;
 use alloc::string::String;
-use alloc::vec::Vec;
-use ark_ff::Field;
 use core::marker::PhantomData;
 use core::mem::size_of;
 
@@ -49,7 +44,6 @@ impl<F: GpuField> FftGpuStage<F> {
         variant: Variant,
         threadgroup_fft_size: usize,
     ) -> FftGpuStage<F> {
-        use metal::MTLDataType::UInt;
         assert!(n.is_power_of_two());
         assert!(num_boxes.is_power_of_two());
         assert!(threadgroup_fft_size.is_power_of_two());
@@ -57,20 +51,14 @@ impl<F: GpuField> FftGpuStage<F> {
         assert!((2048..=1073741824).contains(&n));
 
         // Create the compute pipeline
-        let fft_constants = metal::FunctionConstantValues::new();
         let n = n as u32;
         let num_boxes = num_boxes as u32;
         let tg_fft_size = threadgroup_fft_size as u32;
-        fft_constants.set_constant_value_at_index(void_ptr(&n), UInt, 0);
-        fft_constants.set_constant_value_at_index(void_ptr(&num_boxes), UInt, 1);
-        fft_constants.set_constant_value_at_index(void_ptr(&tg_fft_size), UInt, 2);
-        let func = library
-            .get_function(&fft_kernel_name::<F>(variant), Some(fft_constants))
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &fft_kernel_name::<F>(variant),
+            &[n, num_boxes, tg_fft_size],
+        );
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         // TODO: figure out a solution to handle if this arises
         assert!(threadgroup_fft_size / 2 <= max_threadgroup_threads as usize);
@@ -121,19 +109,13 @@ pub struct MulIntoStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulIntoStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         let kernel_name = format!(
             "mul_into_LHS_{}_RHS_{}",
             LhsF::field_name(),
             RhsF::field_name()
         );
-        let func = library.get_function(&kernel_name, Some(constants)).unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(library, &kernel_name, &[n]);
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -182,19 +164,13 @@ pub struct MulAssignStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulAssignStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         let kernel_name = format!(
             "mul_assign_LHS_{}_RHS_{}",
             LhsF::field_name(),
             RhsF::field_name()
         );
-        let func = library.get_function(&kernel_name, Some(constants)).unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(library, &kernel_name, &[n]);
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -230,35 +206,88 @@ impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulAssignStage<LhsF, RhsF> {
     }
 }
 
+/// Number of values each thread of [MulGeometricSeriesStage] multiplies
+pub const GEOMETRIC_SERIES_CHUNK_SIZE: usize = 256;
+
+/// Multiplies values by a geometric series i.e. `vals[i] *= scale * ratio^i`.
+/// Used for converting between cosets without leaving the GPU.
+pub struct MulGeometricSeriesStage<LhsF, RhsF = LhsF> {
+    chunk_size: u32,
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<(LhsF, RhsF)>,
+}
+
+impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulGeometricSeriesStage<LhsF, RhsF> {
+    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+        let n = n as u32;
+        // Create the compute pipeline
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "mul_geometric_series_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[n],
+        );
+
+        let chunk_size = GEOMETRIC_SERIES_CHUNK_SIZE as u32;
+        let num_threads = n.div_ceil(chunk_size);
+        let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
+        let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
+        let grid_dim = metal::MTLSize::new(num_threads.into(), 1, 1);
+
+        MulGeometricSeriesStage {
+            chunk_size,
+            threadgroup_dim,
+            pipeline,
+            grid_dim,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        dst_buffer: &metal::BufferRef,
+        ratio: RhsF,
+        scale: RhsF,
+    ) {
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(dst_buffer), 0);
+        command_encoder.set_bytes(1, size_of::<RhsF>().try_into().unwrap(), void_ptr(&ratio));
+        command_encoder.set_bytes(2, size_of::<RhsF>().try_into().unwrap(), void_ptr(&scale));
+        command_encoder.set_bytes(
+            3,
+            size_of::<u32>().try_into().unwrap(),
+            void_ptr(&self.chunk_size),
+        );
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[dst_buffer]);
+        command_encoder.end_encoding()
+    }
+}
+
 pub struct ScaleAndNormalizeGpuStage<LhsF, RhsF = LhsF> {
-    mul_assign_stage: MulAssignStage<LhsF, RhsF>,
-    _scale_factors: GpuVec<RhsF>,
-    scale_factors_buffer: metal::Buffer,
+    mul_geometric_series_stage: MulGeometricSeriesStage<LhsF, RhsF>,
+    scale_factor: RhsF,
+    norm_factor: RhsF,
 }
 
-// TODO: replace `Field` with `One + PartialEq` to support multiple libraries
-impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField + Field + PartialEq + Copy>
-    ScaleAndNormalizeGpuStage<LhsF, RhsF>
-{
+impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField + Copy> ScaleAndNormalizeGpuStage<LhsF, RhsF> {
     pub fn new(
         library: &metal::LibraryRef,
-        command_queue: &metal::CommandQueue,
         n: usize,
         scale_factor: RhsF,
         norm_factor: RhsF,
     ) -> Self {
-        let mul_assign_stage = MulAssignStage::<LhsF, RhsF>::new(library, n);
-        let mut _scale_factors = Vec::with_capacity_in(n, PageAlignedAllocator);
-        _scale_factors.resize(n, norm_factor);
-        if !scale_factor.is_one() {
-            distribute_powers(&mut _scale_factors, scale_factor);
-        }
-        let scale_factors_buffer = buffer_no_copy(command_queue.device(), &_scale_factors);
-
         ScaleAndNormalizeGpuStage {
-            mul_assign_stage,
-            _scale_factors,
-            scale_factors_buffer,
+            mul_geometric_series_stage: MulGeometricSeriesStage::new(library, n),
+            scale_factor,
+            norm_factor,
         }
     }
 
@@ -267,8 +296,12 @@ impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField + Field + PartialEq + Copy>
         command_buffer: &metal::CommandBufferRef,
         input_buffer: &metal::BufferRef,
     ) {
-        self.mul_assign_stage
-            .encode(command_buffer, input_buffer, &self.scale_factors_buffer, 0);
+        self.mul_geometric_series_stage.encode(
+            command_buffer,
+            input_buffer,
+            self.scale_factor,
+            self.norm_factor,
+        );
     }
 }
 
@@ -282,24 +315,14 @@ pub struct BitReverseGpuStage<F> {
 
 impl<F: GpuField> BitReverseGpuStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
-        use metal::MTLDataType::UInt;
         assert!(n.is_power_of_two());
         assert!((2048..=1073741824).contains(&n));
 
         // Create the compute pipeline
-        let fft_constants = metal::FunctionConstantValues::new();
         let n = n as u32;
         let num_boxes = 5u32;
-        fft_constants.set_constant_value_at_index(void_ptr(&n), UInt, 0);
-        fft_constants.set_constant_value_at_index(void_ptr(&num_boxes), UInt, 1);
         let kernel_name = format!("bit_reverse_{}", F::field_name());
-        let func = library
-            .get_function(&kernel_name, Some(fft_constants))
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(library, &kernel_name, &[n, num_boxes]);
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -337,19 +360,13 @@ pub struct MulPowStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulPowStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         let kernel_name = format!(
             "mul_pow_LHS_{}_RHS_{}",
             LhsF::field_name(),
             RhsF::field_name()
         );
-        let func = library.get_function(&kernel_name, Some(constants)).unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(library, &kernel_name, &[n]);
 
         // TODO: remove
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -396,24 +413,17 @@ pub struct AddAssignStage<LhsF, RhsF = LhsF> {
 
 impl<LhsF: GpuField + GpuAdd<RhsF>, RhsF: GpuField> AddAssignStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "add_assign_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                Some(constants),
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "add_assign_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[n],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -460,24 +470,17 @@ pub struct AddIntoStage<LhsF, RhsF = LhsF> {
 
 impl<LhsF: GpuField + GpuAdd<RhsF>, RhsF: GpuField> AddIntoStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "add_into_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                Some(constants),
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "add_into_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[n],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -526,20 +529,15 @@ pub struct AddIntoConstStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuAdd<RhsF>, RhsF: GpuField> AddIntoConstStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "add_into_const_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "add_into_const_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -583,20 +581,15 @@ pub struct ConvertIntoStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuAdd<RhsF>, RhsF: GpuField> ConvertIntoStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "convert_into_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "convert_into_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -639,20 +632,15 @@ pub struct AddAssignConstStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuAdd<RhsF>, RhsF: GpuField> AddAssignConstStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "add_assign_const_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "add_assign_const_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -696,20 +684,15 @@ pub struct MulIntoConstStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulIntoConstStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "mul_into_const_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "mul_into_const_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -754,20 +737,15 @@ pub struct MulAssignConstStage<LhsF, RhsF = LhsF> {
 impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> MulAssignConstStage<LhsF, RhsF> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!(
-                    "mul_assign_const_LHS_{}_RHS_{}",
-                    LhsF::field_name(),
-                    RhsF::field_name()
-                ),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "mul_assign_const_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[],
+        );
 
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
@@ -810,13 +788,11 @@ pub struct InverseInPlaceStage<F> {
 impl<F: GpuField> InverseInPlaceStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("inverse_in_place_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!("inverse_in_place_{}", F::field_name()),
+            &[],
+        );
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -844,6 +820,63 @@ impl<F: GpuField> InverseInPlaceStage<F> {
     }
 }
 
+/// Number of values each thread of [BatchInverseStage] inverts
+pub const BATCH_INVERSE_CHUNK_SIZE: usize = 64;
+
+/// Inverts values in place using Montgomery's trick. Each GPU thread inverts
+/// a chunk of values using a single field inversion.
+pub struct BatchInverseStage<F> {
+    chunk_size: u32,
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: GpuField> BatchInverseStage<F> {
+    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+        let n = n as u32;
+        // Create the compute pipeline
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("batch_inverse_{}", F::field_name()), &[n]);
+
+        let chunk_size = BATCH_INVERSE_CHUNK_SIZE as u32;
+        let num_threads = n.div_ceil(chunk_size);
+        let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
+        let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
+        let grid_dim = metal::MTLSize::new(num_threads.into(), 1, 1);
+
+        BatchInverseStage {
+            chunk_size,
+            threadgroup_dim,
+            pipeline,
+            grid_dim,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// `scratch_buffer` must have space for at least `n` field elements
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        dst_buffer: &metal::BufferRef,
+        scratch_buffer: &metal::BufferRef,
+    ) {
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(dst_buffer), 0);
+        command_encoder.set_buffer(1, Some(scratch_buffer), 0);
+        command_encoder.set_bytes(
+            2,
+            size_of::<u32>().try_into().unwrap(),
+            void_ptr(&self.chunk_size),
+        );
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[dst_buffer, scratch_buffer]);
+        command_encoder.end_encoding()
+    }
+}
+
 pub struct NegInPlaceStage<F> {
     pipeline: metal::ComputePipelineState,
     threadgroup_dim: metal::MTLSize,
@@ -854,13 +887,8 @@ pub struct NegInPlaceStage<F> {
 impl<F: GpuField> NegInPlaceStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("neg_in_place_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("neg_in_place_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -898,13 +926,8 @@ pub struct NegIntoStage<F> {
 impl<F: GpuField> NegIntoStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("neg_into_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("neg_into_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -948,13 +971,8 @@ pub struct InverseIntoStage<F> {
 impl<F: GpuField> InverseIntoStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("inverse_into_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("inverse_into_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -998,13 +1016,8 @@ pub struct ExpIntoStage<F> {
 impl<F: GpuField> ExpIntoStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("exp_into_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("exp_into_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -1055,13 +1068,8 @@ pub struct ExpInPlaceStage<F> {
 impl<F: GpuField> ExpInPlaceStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("exp_in_place_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("exp_in_place_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
@@ -1110,13 +1118,8 @@ pub struct FillBuffStage<F> {
 impl<F: GpuField> FillBuffStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("fill_buff_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline =
+            KERNEL_CACHE.pipeline(library, &format!("fill_buff_{}", F::field_name()), &[]);
 
         let n = n as u32;
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
@@ -1156,23 +1159,12 @@ pub struct GenerateTwiddlesStage<F> {
 impl<F: GpuField> GenerateTwiddlesStage<F> {
     pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(
-            &n as *const u32 as *const core::ffi::c_void,
-            metal::MTLDataType::UInt,
-            0,
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!("generate_twiddles_{}", F::field_name()),
+            &[n],
         );
-        let func = library
-            .get_function(
-                &format!("generate_twiddles_{}", F::field_name()),
-                Some(constants),
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
 
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
         let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
@@ -1200,3 +1192,236 @@ impl<F: GpuField> GenerateTwiddlesStage<F> {
         command_encoder.end_encoding()
     }
 }
+
+/// Evaluates a fused constraint program for every row of a domain.
+/// Trace columns are expected to be stored column-major in a single buffer per
+/// field with `n` rows per column.
+pub struct EvaluateFusedStage<Fq, Fp = Fq> {
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<(Fq, Fp)>,
+}
+
+impl<Fq: GpuField, Fp: GpuField> EvaluateFusedStage<Fq, Fp> {
+    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+        let n = n as u32;
+        // Create the compute pipeline
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "evaluate_fused_LHS_{}_RHS_{}",
+                Fq::field_name(),
+                Fp::field_name()
+            ),
+            &[n],
+        );
+
+        let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
+        let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
+        let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
+
+        EvaluateFusedStage {
+            threadgroup_dim,
+            pipeline,
+            grid_dim,
+            _phantom: PhantomData,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        dst_buffer: &metal::BufferRef,
+        program: &[u32],
+        fp_constants_buffer: &metal::BufferRef,
+        fq_constants_buffer: &metal::BufferRef,
+        xs_buffer: &metal::BufferRef,
+        fp_trace_buffer: &metal::BufferRef,
+        fq_trace_buffer: &metal::BufferRef,
+    ) {
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(dst_buffer), 0);
+        command_encoder.set_bytes(
+            1,
+            (program.len() * size_of::<u32>()).try_into().unwrap(),
+            program.as_ptr() as *const core::ffi::c_void,
+        );
+        let program_len = (program.len() / 4) as u32;
+        command_encoder.set_bytes(
+            2,
+            size_of::<u32>().try_into().unwrap(),
+            void_ptr(&program_len),
+        );
+        command_encoder.set_buffer(3, Some(fp_constants_buffer), 0);
+        command_encoder.set_buffer(4, Some(fq_constants_buffer), 0);
+        command_encoder.set_buffer(5, Some(xs_buffer), 0);
+        command_encoder.set_buffer(6, Some(fp_trace_buffer), 0);
+        command_encoder.set_buffer(7, Some(fq_trace_buffer), 0);
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[dst_buffer]);
+        command_encoder.end_encoding()
+    }
+}
+
+/// Folds FRI layer evaluations by a random linear combination of
+/// `folding_factor` evaluations. Computes the same layer as interpolating the
+/// evaluations, combining coefficients with powers of `alpha` and evaluating
+/// over the folded domain but without leaving evaluation form.
+pub struct FriFoldStage<LhsF, RhsF = LhsF> {
+    folding_factor: u32,
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<(LhsF, RhsF)>,
+}
+
+impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> FriFoldStage<LhsF, RhsF> {
+    /// `n` is the number of evaluations before folding
+    pub fn new(library: &metal::LibraryRef, n: usize, folding_factor: usize) -> Self {
+        assert!(n.is_power_of_two());
+        assert!(folding_factor.is_power_of_two());
+        assert!(folding_factor <= n);
+        let n = n as u32;
+        let folding_factor = folding_factor as u32;
+        // Create the compute pipeline
+        let pipeline = KERNEL_CACHE.pipeline(
+            library,
+            &format!(
+                "fri_fold_LHS_{}_RHS_{}",
+                LhsF::field_name(),
+                RhsF::field_name()
+            ),
+            &[n],
+        );
+
+        let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
+        let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
+        let grid_dim = metal::MTLSize::new((n / folding_factor).into(), 1, 1);
+
+        FriFoldStage {
+            folding_factor,
+            threadgroup_dim,
+            pipeline,
+            grid_dim,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Evaluations in `src_buffer` are over the coset `offset * <generator>`.
+    /// `roots_inv` holds the inverses of the `folding_factor`-th roots of
+    /// unity in order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        dst_buffer: &mut metal::BufferRef,
+        src_buffer: &metal::BufferRef,
+        alpha: LhsF,
+        offset_inv: RhsF,
+        generator_inv: RhsF,
+        roots_inv: &[RhsF],
+        folding_factor_inv: RhsF,
+    ) {
+        assert_eq!(roots_inv.len(), self.folding_factor as usize);
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(dst_buffer), 0);
+        command_encoder.set_buffer(1, Some(src_buffer), 0);
+        command_encoder.set_bytes(2, size_of::<LhsF>().try_into().unwrap(), void_ptr(&alpha));
+        command_encoder.set_bytes(
+            3,
+            size_of::<RhsF>().try_into().unwrap(),
+            void_ptr(&offset_inv),
+        );
+        command_encoder.set_bytes(
+            4,
+            size_of::<RhsF>().try_into().unwrap(),
+            void_ptr(&generator_inv),
+        );
+        command_encoder.set_bytes(
+            5,
+            (roots_inv.len() * size_of::<RhsF>()).try_into().unwrap(),
+            roots_inv.as_ptr() as *const core::ffi::c_void,
+        );
+        command_encoder.set_bytes(
+            6,
+            size_of::<RhsF>().try_into().unwrap(),
+            void_ptr(&folding_factor_inv),
+        );
+        command_encoder.set_bytes(
+            7,
+            size_of::<u32>().try_into().unwrap(),
+            void_ptr(&self.folding_factor),
+        );
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[src_buffer, dst_buffer]);
+        command_encoder.end_encoding()
+    }
+}
+
+/// Accumulates the DEEP quotient `alpha * (T(x) - T(z)) / (x - z)` of a
+/// column `T` evaluated over a domain. Lets the DEEP composition polynomial
+/// be built in evaluation form without leaving the GPU.
+pub struct DeepQuotientStage<LhsF, RhsF = LhsF> {
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<(LhsF, RhsF)>,
+}
+
+impl<LhsF: GpuField + GpuMul<RhsF>, RhsF: GpuField> DeepQuotientStage<LhsF, RhsF> {
+    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+        let n = n as u32;
+        let kernel_name = format!(
+            "deep_quotient_LHS_{}_RHS_{}",
+            LhsF::field_name(),
+            RhsF::field_name()
+        );
+        let pipeline = KERNEL_CACHE.pipeline(library, &kernel_name, &[n]);
+
+        let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
+        let threadgroup_dim = metal::MTLSize::new(max_threadgroup_threads, 1, 1);
+        let grid_dim = metal::MTLSize::new(n.into(), 1, 1);
+
+        DeepQuotientStage {
+            pipeline,
+            threadgroup_dim,
+            grid_dim,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// `denominator_invs_buffer` holds `1 / (x - z)` for each `x` of the
+    /// domain and `alpha_ood` is `alpha * T(z)`
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        dst_buffer: &mut metal::BufferRef,
+        evals_buffer: &metal::BufferRef,
+        denominator_invs_buffer: &metal::BufferRef,
+        alpha: LhsF,
+        alpha_ood: LhsF,
+    ) {
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(dst_buffer), 0);
+        command_encoder.set_buffer(1, Some(evals_buffer), 0);
+        command_encoder.set_buffer(2, Some(denominator_invs_buffer), 0);
+        command_encoder.set_bytes(3, size_of::<LhsF>().try_into().unwrap(), void_ptr(&alpha));
+        command_encoder.set_bytes(
+            4,
+            size_of::<LhsF>().try_into().unwrap(),
+            void_ptr(&alpha_ood),
+        );
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[
+            dst_buffer,
+            evals_buffer,
+            denominator_invs_buffer,
+        ]);
+        command_encoder.end_encoding()
+    }
+}
@@ -47,7 +47,8 @@ pub fn bit_reverse<T>(v: &mut [T]) {
 pub fn bit_reverse<T: Send>(v: &mut [T]) {
     assert!(v.len().is_power_of_two());
     let n = v.len();
-    let num_batches = rayon::current_num_threads().next_power_of_two();
+    // custom thread pools can have more threads than there are values
+    let num_batches = core::cmp::min(rayon::current_num_threads().next_power_of_two(), n);
     let batch_size = n / num_batches;
     rayon::scope(|s| {
         for batch_idx in 0..num_batches {
@@ -119,7 +120,8 @@ pub fn buffer_mut_no_copy<T: Sized>(
 // adapted form arkworks
 /// Multiply the `i`-th element of `coeffs` with `g^i`.
 #[cfg(target_arch = "aarch64")]
-pub(crate) fn distribute_powers<F: crate::GpuField + ark_ff::Field>(coeffs: &mut [F], g: F) {
+/// Multiplies each coefficient by successive powers of `g`
+pub fn distribute_powers<F: crate::GpuField + ark_ff::Field>(coeffs: &mut [F], g: F) {
     let n = coeffs.len();
     #[cfg(not(feature = "parallel"))]
     let chunk_size = n;
@@ -0,0 +1,31 @@
+#![cfg(target_arch = "aarch64")]
+
+use gpu_poly::cache::archive_url;
+use std::env;
+use std::fs;
+
+#[test]
+fn relative_archive_path_is_resolved_against_the_working_directory() {
+    let dir = env::current_dir().unwrap().canonicalize().unwrap();
+
+    let url = archive_url("kernels.metallib").unwrap();
+
+    assert_eq!(format!("file://{}/kernels.metallib", dir.display()), url);
+    assert!(url.starts_with("file:///"));
+}
+
+#[test]
+fn archive_path_is_canonicalized_and_percent_encoded() {
+    let dir = env::temp_dir().join("gpu poly cache");
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let path = dir
+        .join("..")
+        .join("gpu poly cache")
+        .join("kernels.metallib");
+
+    let url = archive_url(path.to_str().unwrap()).unwrap();
+
+    let expected = format!("{}/kernels.metallib", dir.display()).replace(' ', "%20");
+    assert_eq!(format!("file://{expected}"), url);
+}
@@ -0,0 +1,15 @@
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::from_felt_hex;
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::to_felt_hex;
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp;
+
+#[test]
+fn felt_hex_roundtrip() {
+    // modulus - 1
+    let max = "0x800000000000011000000000000000000000000000000000000000000000000";
+    let modulus = "0x800000000000011000000000000000000000000000000000000000000000001";
+
+    assert_eq!(-Fp::from(1u8), from_felt_hex(max).unwrap());
+    assert_eq!(max, to_felt_hex(&from_felt_hex(max).unwrap()));
+    assert_eq!("0x1a", to_felt_hex(&Fp::from(26u8)));
+    assert!(from_felt_hex(modulus).is_none());
+}
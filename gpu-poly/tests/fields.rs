@@ -4,6 +4,7 @@
 use ark_ff::UniformRand;
 use gpu_poly::prelude::PageAlignedAllocator;
 use gpu_poly::prelude::PLANNER;
+use gpu_poly::stage::DeepQuotientStage;
 use gpu_poly::stage::MulPowStage;
 use gpu_poly::utils::buffer_mut_no_copy;
 use gpu_poly::utils::buffer_no_copy;
@@ -134,4 +135,148 @@ pub mod p18446744069414584321 {
             }
         });
     }
+
+    #[test]
+    fn deep_quotient_fq3_by_fp() {
+        autoreleasepool(|| {
+            let n = 2048;
+            let mut rng = ark_std::test_rng();
+            let evals = (0..n)
+                .map(|_| Fp::rand(&mut rng))
+                .collect::<Vec<Fp>>()
+                .to_vec_in(PageAlignedAllocator);
+            let expected = deep_quotient_cpu(&mut rng.clone(), &evals);
+            let actual = deep_quotient_gpu(&mut rng, &evals);
+
+            for (i, (expected, actual)) in expected.into_iter().zip(actual).enumerate() {
+                assert_eq!(expected, actual, "mismatch at index {i}");
+            }
+        });
+    }
+
+    #[test]
+    fn deep_quotient_fq3() {
+        autoreleasepool(|| {
+            let n = 2048;
+            let mut rng = ark_std::test_rng();
+            let evals = (0..n)
+                .map(|_| Fq3::rand(&mut rng))
+                .collect::<Vec<Fq3>>()
+                .to_vec_in(PageAlignedAllocator);
+            let expected = deep_quotient_cpu(&mut rng.clone(), &evals);
+            let actual = deep_quotient_gpu(&mut rng, &evals);
+
+            for (i, (expected, actual)) in expected.into_iter().zip(actual).enumerate() {
+                assert_eq!(expected, actual, "mismatch at index {i}");
+            }
+        });
+    }
+
+    /// Accumulates two quotients into random values. Draws the same values as
+    /// [deep_quotient_gpu] from an rng in the same state.
+    fn deep_quotient_cpu<F: Copy>(rng: &mut impl ark_std::rand::Rng, evals: &[F]) -> Vec<Fq3>
+    where
+        Fq3: From<F>,
+    {
+        let mut acc = (0..evals.len())
+            .map(|_| Fq3::rand(rng))
+            .collect::<Vec<Fq3>>();
+        for _ in 0..2 {
+            let denominator_invs = (0..evals.len())
+                .map(|_| Fq3::rand(rng))
+                .collect::<Vec<Fq3>>();
+            let (alpha, ood_eval) = (Fq3::rand(rng), Fq3::rand(rng));
+            for ((acc, &eval), denominator_inv) in acc.iter_mut().zip(evals).zip(denominator_invs) {
+                *acc += (alpha * Fq3::from(eval) - alpha * ood_eval) * denominator_inv;
+            }
+        }
+        acc
+    }
+
+    fn deep_quotient_gpu<F>(
+        rng: &mut impl ark_std::rand::Rng,
+        evals: &gpu_poly::GpuVec<F>,
+    ) -> gpu_poly::GpuVec<Fq3>
+    where
+        F: gpu_poly::GpuField,
+        Fq3: gpu_poly::GpuMul<F>,
+    {
+        let n = evals.len();
+        let mut acc = (0..n)
+            .map(|_| Fq3::rand(rng))
+            .collect::<Vec<Fq3>>()
+            .to_vec_in(PageAlignedAllocator);
+        let command_queue = &PLANNER.command_queue;
+        let mut acc_buffer = buffer_mut_no_copy(command_queue.device(), &mut acc);
+        let evals_buffer = buffer_no_copy(command_queue.device(), evals);
+        let stage = DeepQuotientStage::<Fq3, F>::new(&PLANNER.library, n);
+        // both quotients are encoded into one command buffer
+        let command_buffer = command_queue.new_command_buffer();
+        // the inverted denominators must outlive the command buffer
+        let mut denominator_invs = Vec::new();
+        for _ in 0..2 {
+            let invs = (0..n)
+                .map(|_| Fq3::rand(rng))
+                .collect::<Vec<Fq3>>()
+                .to_vec_in(PageAlignedAllocator);
+            let invs_buffer = buffer_no_copy(command_queue.device(), &invs);
+            let (alpha, ood_eval) = (Fq3::rand(rng), Fq3::rand(rng));
+            stage.encode(
+                command_buffer,
+                &mut acc_buffer,
+                &evals_buffer,
+                &invs_buffer,
+                alpha,
+                alpha * ood_eval,
+            );
+            denominator_invs.push(invs);
+        }
+        command_buffer.commit();
+        command_buffer.wait_until_completed();
+        acc
+    }
+}
+
+pub mod p3618502788666131213697322783095070105623107215331596699973092056135872020481 {
+    use super::*;
+    use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp;
+
+    #[test]
+    fn mul_pow_fp() {
+        autoreleasepool(|| {
+            let n = 2048;
+            let mut rng = &mut ark_std::test_rng();
+            let mut a = (0..n)
+                .map(|_| Fp::rand(&mut rng))
+                .collect::<Vec<Fp>>()
+                .to_vec_in(PageAlignedAllocator);
+            let b = (0..n)
+                .map(|_| Fp::rand(&mut rng))
+                .collect::<Vec<Fp>>()
+                .to_vec_in(PageAlignedAllocator);
+            let expected = a
+                .iter()
+                .copied()
+                .zip(&b)
+                .map(|(mut a, b)| {
+                    a *= b;
+                    a
+                })
+                .collect::<Vec<Fp>>()
+                .to_vec_in(PageAlignedAllocator);
+            let command_queue = &PLANNER.command_queue;
+            let mut a_buffer = buffer_mut_no_copy(command_queue.device(), &mut a);
+            let b_buffer = buffer_no_copy(command_queue.device(), &b);
+            let command_buffer = command_queue.new_command_buffer();
+
+            let multiplier = MulPowStage::<Fp>::new(&PLANNER.library, n);
+            multiplier.encode(command_buffer, &mut a_buffer, &b_buffer, 1, 0);
+            command_buffer.commit();
+            command_buffer.wait_until_completed();
+
+            for (i, (expected, actual)) in expected.into_iter().zip(a).enumerate() {
+                assert_eq!(expected, actual, "mismatch at index {i}");
+            }
+        });
+    }
 }
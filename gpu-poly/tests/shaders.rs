@@ -116,6 +116,25 @@ fn ifft() {
     });
 }
 
+#[test]
+fn library_has_every_kernel() {
+    autoreleasepool(|| {
+        let shaders = include_str!("../src/metal/evaluation_shaders.h.metal");
+        let function_names = PLANNER.library.function_names();
+        let kernel_names = shaders
+            .lines()
+            .filter(|line| line.starts_with("template [[ host_name(\""))
+            .map(|line| line.split('"').nth(1).unwrap());
+
+        for kernel_name in kernel_names {
+            assert!(
+                function_names.iter().any(|name| name == kernel_name),
+                "kernel {kernel_name} is missing from the library"
+            );
+        }
+    });
+}
+
 // TODO: evaluate_over_domain_by_ref needs to support DomainCoeff
 // #[test]
 // fn ifft_with_extension_field() {
@@ -1,30 +1,103 @@
+use crate::assertions::Assertion;
+use crate::assertions::PublicCell;
 use crate::challenges::Challenges;
+use crate::commitment::VectorCommitment;
 use crate::composer::DeepCompositionCoeffs;
 use crate::constraints::AlgebraicExpression;
+use crate::constraints::FieldConstant;
+use crate::constraints::TransitionConstraint;
+use crate::divisor::DividedConstraint;
+use crate::fri::FriVerifier;
+use crate::fri::LdtVerifier;
 use crate::hints::Hints;
+use crate::merkle::CompressedLeafEncoder;
+use crate::merkle::LeafEncoder;
+use crate::merkle::MerkleTree;
+use crate::periodic::PeriodicColumn;
+use crate::random::DigestPublicCoin;
 use crate::random::PublicCoin;
+use crate::trace::interpolate_base_segments;
+use crate::trace::AirVersion;
+use crate::trace::Padding;
 use crate::utils;
+use crate::Matrix;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
 use crate::TraceInfo;
 use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::FftField;
-use ark_ff::UniformRand;
+use ark_ff::Field;
+use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use core::fmt::Display;
+use core::fmt::Formatter;
 use digest::Digest;
 use gpu_poly::GpuFftField;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use sha2::Sha256;
+
+/// A constraint that doesn't hold on a row of the execution trace. See
+/// [Air::validate_trace].
+#[derive(Clone, Debug)]
+pub struct UnsatisfiedConstraint<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> {
+    /// Index of the constraint in [Air::all_constraints]
+    pub constraint: usize,
+    pub row: usize,
+    /// Terms of the constraint's numerator that are non-zero on the row. The
+    /// value is `None` if the term itself divides by zero.
+    pub terms: Vec<(String, Option<FieldConstant<Fp, Fq>>)>,
+    /// Values of `x` and the trace cells, challenges and hints the constraint
+    /// references. Trace cells are named by [Air::trace_column_name].
+    pub values: Vec<(String, FieldConstant<Fp, Fq>)>,
+}
+
+impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Display
+    for UnsatisfiedConstraint<Fp, Fq>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (constraint, row) = (self.constraint, self.row);
+        writeln!(f, "constraint {constraint} does not hold at row {row}")?;
+        writeln!(f, "non-zero terms:")?;
+        for (term, value) in &self.terms {
+            match value {
+                Some(value) => writeln!(f, "  {term} = {value}")?,
+                None => writeln!(f, "  {term} divides by zero")?,
+            }
+        }
+        writeln!(f, "values:")?;
+        for (name, value) in &self.values {
+            writeln!(f, "  {name} = {value}")?;
+        }
+        Ok(())
+    }
+}
 
 pub trait Air {
     type Fp: GpuFftField<FftField = Self::Fp> + FftField;
     type Fq: StarkExtensionOf<Self::Fp>;
     // TODO: consider removing clone requirement
     type PublicInputs: CanonicalSerialize + CanonicalDeserialize + Clone;
+    /// Hash function used for Merkle commitments and the Fiat-Shamir channel
+    type Digest: Digest + 'static = Sha256;
+    /// Fiat-Shamir transcript. Defaults to a coin built from [Air::Digest].
+    type PublicCoin: PublicCoin = DigestPublicCoin<Self::Digest>;
+    /// Encoding of trace rows in Merkle leaves
+    type LeafEncoder: LeafEncoder = CompressedLeafEncoder;
+    /// Low-degree test for the DEEP composition polynomial. Proofs are made by
+    /// its [LdtVerifier::Prover].
+    type LdtVerifier: LdtVerifier<Self::Fq, Self::Digest> = FriVerifier<Self::Fq, Self::Digest>;
+    /// Commitment scheme for the execution and composition trace rows
+    type VectorCommitment: VectorCommitment<Self::Digest> = MerkleTree<Self::Digest>;
+    /// Version of the arithmetization. Bump the major version whenever
+    /// constraints change so proofs for older constraints are rejected.
+    const VERSION: AirVersion = AirVersion::new(0, 0, 0);
 
     // TODO: could make this borrow info and options if so inclined
     fn new(info: TraceInfo, inputs: Self::PublicInputs, options: ProofOptions) -> Self;
@@ -45,22 +118,37 @@ pub trait Air {
         len
     }
 
+    /// Degrees of the polynomials of each of [Air::all_constraints] given the
+    /// trace polynomials have degree `trace_len - 1`
+    fn constraint_degrees(&self) -> Vec<usize> {
+        let trace_degree = self.trace_len() - 1;
+        self.all_constraints()
+            .iter()
+            .map(|constraint| {
+                let (numerator_degree, denominator_degree) = constraint.degree(trace_degree);
+                numerator_degree.saturating_sub(denominator_degree)
+            })
+            .collect()
+    }
+
     /// Constraint evaluation blowup factor
     /// Must be a power of two.
+    ///
+    /// The composition polynomial is split into this many columns of degree
+    /// less than the trace length so it must be large enough to fit the
+    /// highest degree constraint.
     fn ce_blowup_factor(&self) -> usize {
-        let trace_degree = self.trace_len() - 1;
-        let ret = utils::ceil_power_of_two(
-            self.constraints()
-                .iter()
-                .map(|constraint| {
-                    let (numerator_degree, denominator_degree) = constraint.degree(trace_degree);
-                    numerator_degree - denominator_degree
-                })
-                .max()
-                // TODO: ceil_power_of_two might not be correct here. check the math
-                .map_or(0, |degree| utils::ceil_power_of_two(degree) / trace_degree),
-        );
-        ret
+        let trace_len = self.trace_len();
+        let max_degree = self.constraint_degrees().into_iter().max().unwrap_or(0);
+        utils::ceil_power_of_two((max_degree + 1).div_ceil(trace_len))
+    }
+
+    /// Number of columns in the composition trace. The composition polynomial
+    /// is split into [Air::ce_blowup_factor] columns and if
+    /// [ProofOptions::zk] is set these are followed by a random column that
+    /// masks the DEEP composition polynomial.
+    fn num_composition_trace_columns(&self) -> usize {
+        self.ce_blowup_factor() + usize::from(self.options().zk)
     }
 
     /// Returns a degree that all constraints polynomials must be normalized to.
@@ -80,9 +168,53 @@ pub trait Air {
         let lde_blowup_factor = self.lde_blowup_factor();
         assert!(
             ce_blowup_factor <= lde_blowup_factor,
-            "constraint evaluation blowup factor {ce_blowup_factor} is 
-            larger than the lde blowup factor {lde_blowup_factor}"
+            "constraint evaluation blowup factor {ce_blowup_factor} is larger than the lde blowup factor {lde_blowup_factor}"
         );
+        let trace_len = self.trace_len();
+        for (column, offset) in self.trace_arguments() {
+            assert!(
+                offset.unsigned_abs() < trace_len,
+                "column {column} is referenced {offset} rows away but the trace has {trace_len} rows"
+            );
+        }
+        let fq_degree = Self::Fq::extension_degree();
+        if let Some(extension_degree) = self.options().extension_degree {
+            assert_eq!(
+                u64::from(extension_degree),
+                fq_degree,
+                "extension field has degree {fq_degree} but the proof options declare {extension_degree}"
+            );
+        }
+        let num_base_columns = self.trace_info().num_base_columns;
+        for PublicCell { column, row, .. } in self.public_cells() {
+            assert!(
+                column < num_base_columns,
+                "public cell column {column} is not a base column"
+            );
+            assert!(
+                row < trace_len - self.num_randomizer_rows(),
+                "public cell row {row} is outside the trace or a randomizer row"
+            );
+        }
+        let (padding, num_padding_rows) = self.padding();
+        if num_padding_rows > 0 {
+            assert_eq!(
+                self.trace_info().base_segment_lengths,
+                [trace_len],
+                "padding requires a single base segment"
+            );
+        }
+        if padding == Padding::HaltState && num_padding_rows > 0 {
+            assert_eq!(
+                num_base_columns,
+                self.halt_state().len(),
+                "halt state must have a value for each base column"
+            );
+            assert!(
+                num_padding_rows > self.num_randomizer_rows(),
+                "the first padding row is a randomizer row"
+            );
+        }
     }
 
     fn trace_domain(&self) -> Radix2EvaluationDomain<Self::Fp> {
@@ -109,22 +241,386 @@ pub trait Air {
     // TODO: consider changing back to borrow
     fn constraints(&self) -> Vec<AlgebraicExpression<Self::Fp, Self::Fq>>;
 
-    fn get_challenges(&self, public_coin: &mut PublicCoin<impl Digest>) -> Challenges<Self::Fq> {
-        let mut num_challenges = 0;
+    /// Values the execution trace must have at given rows. Constraints for
+    /// each assertion are added to [Air::constraints] automatically.
+    fn assertions(&self) -> Vec<Assertion<Self::Fp, Self::Fq>> {
+        Vec::new()
+    }
+
+    /// Cells of the base trace bound to public values. Each is asserted like
+    /// [Air::assertions] and all cells are absorbed into the public coin
+    /// before any trace is committed.
+    fn public_cells(&self) -> Vec<PublicCell<Self::Fp>> {
+        Vec::new()
+    }
+
+    /// Values of the base columns in the halt state. Only used if the trace is
+    /// padded with [Padding::HaltState].
+    fn halt_state(&self) -> Vec<Self::Fp> {
+        Vec::new()
+    }
+
+    /// Assertions that the first padding row is the halt state if the trace
+    /// is padded with [Padding::HaltState]. Empty otherwise.
+    fn padding_assertions(&self) -> Vec<Assertion<Self::Fp, Self::Fq>> {
+        let (padding, num_padding_rows) = self.padding();
+        if padding != Padding::HaltState || num_padding_rows == 0 {
+            return Vec::new();
+        }
+        let row = self.trace_len() - num_padding_rows;
+        self.halt_state()
+            .into_iter()
+            .enumerate()
+            .map(|(column, value)| Assertion::single(column, row, FieldConstant::Fp(value)))
+            .collect()
+    }
+
+    /// Constraints between rows of the execution trace. Each is divided by the
+    /// vanishing polynomial of the rows it applies to automatically.
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Self::Fp, Self::Fq>> {
+        Vec::new()
+    }
+
+    /// Constraints the execution trace must satisfy. Includes the constraints
+    /// of all [Air::transition_constraints], [Air::assertions] and
+    /// [Air::public_cells] followed by the [Air::padding_assertions].
+    /// Transition constraints without a custom divisor also exempt the rows
+    /// given by [Air::num_transition_exemptions].
+    fn all_constraints(&self) -> Vec<AlgebraicExpression<Self::Fp, Self::Fq>> {
+        let trace_domain = self.trace_domain();
+        self.divided_constraints()
+            .into_iter()
+            .map(|(numerator, divisor)| match divisor {
+                Some(divisor) => divisor.divide(numerator, trace_domain),
+                None => numerator,
+            })
+            .collect()
+    }
+
+    /// Numerators of [Air::all_constraints] paired with the divisor they are
+    /// divided by. Constraints from [Air::constraints] have no divisor. The
+    /// prover evaluates each divisor over the constraint evaluation domain
+    /// once rather than evaluating its factors at every point.
+    fn divided_constraints(&self) -> Vec<DividedConstraint<Self::Fp, Self::Fq>> {
+        let trace_len = self.trace_len();
+        let num_extra_exemptions = self.num_transition_exemptions();
+        let mut constraints = self
+            .constraints()
+            .into_iter()
+            .map(|constraint| (constraint, None))
+            .collect::<Vec<_>>();
+        constraints.extend(
+            self.transition_constraints()
+                .into_iter()
+                .map(|mut constraint| {
+                    constraint.num_exemptions += num_extra_exemptions;
+                    let divisor = constraint.divisor(trace_len);
+                    (constraint.expression, Some(divisor))
+                }),
+        );
+        let public_cells = self.public_cells();
+        let assertions = self
+            .assertions()
+            .into_iter()
+            .chain(public_cells.iter().map(PublicCell::assertion))
+            .chain(self.padding_assertions());
+        constraints.extend(assertions.map(|assertion| {
+            let divisor = assertion.divisor(trace_len);
+            (assertion.numerator(), Some(divisor))
+        }));
+        constraints
+    }
+
+    /// Exports the constraint system as a canonical S-expression for audits
+    /// and for other prover implementations e.g.
+    ///
+    /// ```text
+    /// (air
+    ///   (trace-len 8)
+    ///   (columns (base 1) (extension 0) (preprocessed 0))
+    ///   (transition 0 (divisor (rows) (periodic (0 1)) (exemptions 7)) ...)
+    ///   (constraint 0 (degree 0) (* (+ (trace 0 1) (- (trace 0 0))) ...)))
+    /// ```
+    ///
+    /// Periodic columns, challenge labels, transition constraints,
+    /// assertions, public cells and halt state assertions are listed with the
+    /// index of the constraint they produce in [Air::all_constraints].
+    /// Every constraint is then listed in full with its divisor and its
+    /// degree (see [Air::constraint_degrees]). Equal constraint systems
+    /// export equal strings so exports can be diffed.
+    fn constraint_system(&self) -> String {
+        let trace_info = self.trace_info();
+        let trace_len = self.trace_len();
+        let mut forms = vec![
+            format!("(trace-len {trace_len})"),
+            format!(
+                "(columns (base {}) (extension {}) (preprocessed {}))",
+                trace_info.num_base_columns,
+                trace_info.num_extension_columns,
+                trace_info.num_preprocessed_columns
+            ),
+        ];
+        for (i, column) in self.periodic_columns().iter().enumerate() {
+            let values = column
+                .values()
+                .iter()
+                .map(|&v| FieldConstant::<Self::Fp, Self::Fq>::Fp(v).to_sexpr())
+                .collect::<Vec<String>>();
+            forms.push(format!("(periodic-column {i} {})", values.join(" ")));
+        }
+        for (i, label) in self.challenge_labels().iter().enumerate() {
+            forms.push(format!("(challenge {i} {label})"));
+        }
+
+        let mut index = self.constraints().len();
+        for mut constraint in self.transition_constraints() {
+            constraint.num_exemptions += self.num_transition_exemptions();
+            let divisor = constraint.divisor(trace_len).to_sexpr();
+            let expression = constraint.expression.to_sexpr();
+            forms.push(format!("(transition {index} {divisor} {expression})"));
+            index += 1;
+        }
+        let assertions = self.assertions().into_iter().map(|a| ("assertion", a));
+        let public_cells = self
+            .public_cells()
+            .into_iter()
+            .map(|cell| ("public-cell", cell.assertion()));
+        let halt_state = self
+            .padding_assertions()
+            .into_iter()
+            .map(|a| ("halt-state", a));
+        for (kind, assertion) in assertions.chain(public_cells).chain(halt_state) {
+            let divisor = assertion.divisor(trace_len).to_sexpr();
+            let (column, value) = (assertion.column, assertion.value.to_sexpr());
+            forms.push(format!(
+                "({kind} {index} (column {column}) {value} {divisor})"
+            ));
+            index += 1;
+        }
+
+        let degrees = self.constraint_degrees();
+        for (i, constraint) in self.all_constraints().iter().enumerate() {
+            let (degree, expression) = (degrees[i], constraint.to_sexpr());
+            forms.push(format!("(constraint {i} (degree {degree}) {expression})"));
+        }
+
+        format!("(air\n  {})", forms.join("\n  "))
+    }
+
+    /// Digest of the constraint system, column layout and proof options.
+    /// Deployments pin the key of their AIR so only proofs for exactly this
+    /// arithmetization are accepted (see [crate::Proof::verify_with_key]).
+    /// Assertions and public cells usually hold public inputs so only the
+    /// cells they constrain are included, not their values. Divisors, segment
+    /// lengths and rows depend on the trace length so each trace length has
+    /// its own key.
+    fn verification_key(&self) -> Vec<u8> {
+        let trace_info = self.trace_info();
+        let trace_len = self.trace_len();
+        let (padding, num_padding_rows) = self.padding();
+        let base_segments = trace_info
+            .base_segment_widths
+            .iter()
+            .zip(&trace_info.base_segment_lengths)
+            .map(|(width, len)| format!("({width} {len})"))
+            .collect::<Vec<String>>();
+        let extension_segments = trace_info
+            .extension_segment_widths
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+        let mut forms = vec![
+            format!("(version {})", Self::VERSION),
+            format!("(trace-len {trace_len})"),
+            format!("(base-segments {})", base_segments.join(" ")),
+            format!("(extension-segments {})", extension_segments.join(" ")),
+            format!("(preprocessed {})", trace_info.num_preprocessed_columns),
+            format!("(randomizer-rows {})", self.num_randomizer_rows()),
+            format!("(padding {padding:?} {num_padding_rows})"),
+        ];
+        for (i, column) in self.periodic_columns().iter().enumerate() {
+            let values = column
+                .values()
+                .iter()
+                .map(|&v| FieldConstant::<Self::Fp, Self::Fq>::Fp(v).to_sexpr())
+                .collect::<Vec<String>>();
+            forms.push(format!("(periodic-column {i} {})", values.join(" ")));
+        }
+        for (i, label) in self.challenge_labels().iter().enumerate() {
+            forms.push(format!("(challenge {i} {label})"));
+        }
+        for segment in 1..trace_info.num_extension_segments() {
+            let num_challenges = self.num_segment_challenges(segment);
+            forms.push(format!("(segment-challenges {segment} {num_challenges})"));
+        }
+
         for constraint in self.constraints() {
+            forms.push(format!("(constraint {})", constraint.to_sexpr()));
+        }
+        for mut constraint in self.transition_constraints() {
+            constraint.num_exemptions += self.num_transition_exemptions();
+            let divisor = constraint.divisor(trace_len).to_sexpr();
+            let expression = constraint.expression.to_sexpr();
+            forms.push(format!("(transition {divisor} {expression})"));
+        }
+        for assertion in self.assertions() {
+            let (column, divisor) = (assertion.column, assertion.divisor(trace_len).to_sexpr());
+            forms.push(format!("(assertion (column {column}) {divisor})"));
+        }
+        for PublicCell { column, row, .. } in self.public_cells() {
+            forms.push(format!("(public-cell (column {column}) (row {row}))"));
+        }
+        for assertion in self.padding_assertions() {
+            let divisor = assertion.divisor(trace_len).to_sexpr();
+            let (column, value) = (assertion.column, assertion.value.to_sexpr());
+            forms.push(format!("(halt-state (column {column}) {value} {divisor})"));
+        }
+
+        let mut options = Vec::new();
+        self.options().serialize_compressed(&mut options).unwrap();
+        Self::Digest::new_with_prefix(forms.join("\n"))
+            .chain_update(options)
+            .finalize()
+            .to_vec()
+    }
+
+    /// Columns of constants that repeat throughout the trace. Constraints
+    /// reference column `i` with `AlgebraicExpression::Periodic(i)`.
+    fn periodic_columns(&self) -> Vec<PeriodicColumn<Self::Fp>> {
+        Vec::new()
+    }
+
+    /// Number of columns in each extension segment (see
+    /// [crate::Trace::extension_segment_widths]). AIRs with an extension trace
+    /// must override this. The verifier rejects proofs with different
+    /// segments since the trace info is chosen by the prover.
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// Number of preprocessed columns (see
+    /// [crate::Prover::preprocessed_trace]). AIRs with preprocessed columns
+    /// must override this. The verifier rejects proofs that declare a
+    /// different number.
+    fn num_preprocessed_columns(&self) -> usize {
+        0
+    }
+
+    /// Number of rows at the end of the trace (and of each base segment)
+    /// filled with random values if [ProofOptions::zk] is set (see
+    /// [TraceInfo::num_randomizer_rows]). With zk there must be at least one
+    /// randomizer row per query plus one for each of the two out-of-domain
+    /// rows. The verifier rejects proofs that declare a different number.
+    fn num_randomizer_rows(&self) -> usize {
+        0
+    }
+
+    /// How the trace is padded and the number of padding rows at the end of
+    /// the trace (see [TraceInfo::padding]). Usually derived from the public
+    /// inputs e.g. the number of steps of the computation. The verifier
+    /// rejects proofs that declare different padding.
+    fn padding(&self) -> (Padding, usize) {
+        (Padding::default(), 0)
+    }
+
+    /// Number of rows at the end of the trace that transition constraints
+    /// exempt on top of their own exemptions. Randomizer rows are only exempt
+    /// if [ProofOptions::zk] is set.
+    fn num_transition_exemptions(&self) -> usize {
+        let num_randomizer_rows = match self.options().zk {
+            true => self.num_randomizer_rows(),
+            false => 0,
+        };
+        match self.padding() {
+            (Padding::ZeroWithExemptions, num_padding_rows) => {
+                num_padding_rows.max(num_randomizer_rows)
+            }
+            (Padding::RepeatLastRow | Padding::HaltState, _) => num_randomizer_rows,
+        }
+    }
+
+    /// Number of challenges drawn after extension segment `segment - 1` is
+    /// committed and before segment `segment` is built. These are appended
+    /// to the challenges of earlier segments. Challenges of the first segment
+    /// are drawn by [Air::get_challenges].
+    fn num_segment_challenges(&self, _segment: usize) -> usize {
+        0
+    }
+
+    /// Names of the verifier challenges in order i.e. `labels[i]` names
+    /// `AlgebraicExpression::Challenge(i)`. Lets constraints and extension
+    /// columns refer to challenges by name (see [Air::challenge_index] and
+    /// [Challenges::by_label]) rather than by position.
+    fn challenge_labels(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Index of the challenge labelled `label` by [Air::challenge_labels]
+    fn challenge_index(&self, label: &str) -> usize {
+        self.challenge_labels()
+            .iter()
+            .position(|l| *l == label)
+            .unwrap_or_else(|| panic!("no challenge is labelled {label}"))
+    }
+
+    /// Draws the challenges of the first extension segment after the base
+    /// trace is committed.
+    fn get_challenges(&self, public_coin: &mut impl PublicCoin) -> Challenges<Self::Fq> {
+        let mut num_challenges = 0;
+        for constraint in self.all_constraints() {
             constraint.traverse(&mut |node| {
                 if let AlgebraicExpression::Challenge(i) = node {
                     num_challenges = core::cmp::max(num_challenges, *i + 1)
                 }
             })
         }
+        // challenges of later segments are drawn separately
+        let num_segments = self.trace_info().num_extension_segments();
+        num_challenges = num_challenges.saturating_sub(
+            (1..num_segments)
+                .map(|segment| self.num_segment_challenges(segment))
+                .sum(),
+        );
 
-        if num_challenges == 0 {
-            Challenges::default()
-        } else {
-            let mut rng = public_coin.draw_rng();
-            Challenges::new(&mut rng, num_challenges)
+        let challenges = (0..num_challenges)
+            .map(|_| public_coin.draw())
+            .collect::<Vec<_>>();
+        self.label_challenges(Challenges::from(challenges), 0)
+    }
+
+    /// Draws the challenges of extension segment `segment` once the previous
+    /// segment is committed and appends them to `challenges`
+    fn get_segment_challenges(
+        &self,
+        segment: usize,
+        public_coin: &mut impl PublicCoin,
+        challenges: &mut Challenges<Self::Fq>,
+    ) {
+        let segment_challenges = (0..self.num_segment_challenges(segment))
+            .map(|_| public_coin.draw())
+            .collect::<Vec<_>>();
+        let segment_challenges =
+            self.label_challenges(Challenges::from(segment_challenges), challenges.len());
+        challenges.append(segment_challenges);
+    }
+
+    /// Labels `challenges` with the [Air::challenge_labels] starting from
+    /// label `start`. Leaves them unlabelled if the Air has no labels.
+    fn label_challenges(
+        &self,
+        challenges: Challenges<Self::Fq>,
+        start: usize,
+    ) -> Challenges<Self::Fq> {
+        let labels = self.challenge_labels();
+        if labels.is_empty() {
+            return challenges;
         }
+        let labels = labels
+            .into_iter()
+            .skip(start)
+            .take(challenges.len())
+            .collect();
+        challenges.with_labels(labels)
     }
 
     fn get_hints(&self, _challenges: &Challenges<Self::Fq>) -> Hints<Self::Fq> {
@@ -134,16 +630,16 @@ pub trait Air {
     // TODO: make this generic
     fn get_constraint_composition_coeffs(
         &self,
-        public_coin: &mut PublicCoin<impl Digest>,
+        public_coin: &mut impl PublicCoin,
     ) -> Vec<(Self::Fq, Self::Fq)> {
-        let mut rng = public_coin.draw_rng();
-        (0..self.constraints().len())
-            .map(|_| (Self::Fq::rand(&mut rng), Self::Fq::rand(&mut rng)))
-            .collect()
+        let num_constraints = self.all_constraints().len();
+        let mode = self.options().composition_coeffs;
+        let coeffs = mode.draw::<Self::Fq>(public_coin, 2 * num_constraints);
+        coeffs.chunks(2).map(|pair| (pair[0], pair[1])).collect()
     }
 
     fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
-        self.constraints()
+        self.all_constraints()
             .iter()
             .map(AlgebraicExpression::trace_arguments)
             .fold(BTreeSet::new(), |a, b| &a | &b)
@@ -154,27 +650,25 @@ pub trait Air {
     /// degree_adjustment_coeffs)`
     fn get_deep_composition_coeffs(
         &self,
-        public_coin: &mut PublicCoin<impl Digest>,
+        public_coin: &mut impl PublicCoin,
     ) -> DeepCompositionCoeffs<Self::Fq> {
-        let mut rng = public_coin.draw_rng();
-
         // execution trace coeffs
         let mut execution_trace_coeffs = Vec::new();
         for _ in self.trace_arguments() {
-            execution_trace_coeffs.push(Self::Fq::rand(&mut rng));
+            execution_trace_coeffs.push(public_coin.draw());
         }
 
         // composition trace coeffs
-        let num_composition_trace_cols = self.ce_blowup_factor();
+        let num_composition_trace_cols = self.num_composition_trace_columns();
         let mut composition_trace_coeffs = Vec::new();
         for _ in 0..num_composition_trace_cols {
-            composition_trace_coeffs.push(Self::Fq::rand(&mut rng));
+            composition_trace_coeffs.push(public_coin.draw());
         }
 
         DeepCompositionCoeffs {
             execution_trace: execution_trace_coeffs,
             composition_trace: composition_trace_coeffs,
-            degree: (Self::Fq::rand(&mut rng), Self::Fq::rand(&mut rng)),
+            degree: (public_coin.draw(), public_coin.draw()),
         }
     }
 
@@ -183,21 +677,18 @@ pub trait Air {
         &self,
         challenges: &Challenges<Self::Fq>,
         hints: &Hints<Self::Fq>,
-        base_trace: &crate::Matrix<Self::Fp>,
-        extension_trace: Option<&crate::Matrix<Self::Fq>>,
+        base_trace: &Matrix<Self::Fp>,
+        extension_trace: Option<&Matrix<Self::Fq>>,
+        preprocessed_trace: Option<&Matrix<Self::Fp>>,
     ) {
-        use crate::constraints::FieldConstant;
-        use AlgebraicExpression::*;
-
         let trace_info = self.trace_info();
-        let num_execution_trace_columns =
-            trace_info.num_base_columns + trace_info.num_extension_columns;
+        let num_execution_trace_columns = trace_info.num_columns();
 
         let mut col_indicies = vec![false; num_execution_trace_columns];
         let mut challenge_indicies = vec![false; challenges.len()];
         let mut hint_indicies = vec![false; hints.len()];
 
-        for constraint in self.constraints() {
+        for constraint in self.all_constraints() {
             constraint.traverse(&mut |node| {
                 use AlgebraicExpression::*;
                 match node {
@@ -230,9 +721,88 @@ pub trait Air {
             }
         }
 
+        if let Err(unsatisfied) = self.check_constraints(
+            challenges,
+            hints,
+            base_trace,
+            extension_trace,
+            preprocessed_trace,
+        ) {
+            panic!("{unsatisfied}");
+        }
+    }
+
+    /// Name of execution trace column `column` in constraint reports.
+    /// Defaults to the name given by [TraceInfo::column_name]. Override to
+    /// report columns by the names of the AIR's column enums.
+    fn trace_column_name(&self, column: usize) -> String {
+        self.trace_info().column_name(column)
+    }
+
+    /// Checks `trace` satisfies every constraint on every row and reports the
+    /// first constraint that doesn't hold. Extension segments are built with
+    /// challenges drawn from a fixed seed rather than the proof transcript.
+    /// Traces with preprocessed columns can be checked with
+    /// [Air::check_constraints].
+    fn validate_trace<T: crate::Trace<Fp = Self::Fp, Fq = Self::Fq>>(
+        &self,
+        trace: &T,
+    ) -> Result<(), UnsatisfiedConstraint<Self::Fp, Self::Fq>> {
+        let mut public_coin = Self::PublicCoin::new(b"validate-trace");
+        let mut challenges = self.get_challenges(&mut public_coin);
+        let mut extension_segments = Vec::new();
+        for segment in 0..self.trace_info().num_extension_segments() {
+            if segment != 0 {
+                self.get_segment_challenges(segment, &mut public_coin, &mut challenges);
+            }
+            let segment_trace = trace
+                .build_aux_segment(segment, &extension_segments, &challenges)
+                .expect("extension segment was not built");
+            extension_segments.push(segment_trace);
+        }
+        let hints = self.get_hints(&challenges);
+        let has_extension_trace = !extension_segments.is_empty();
+        let extension_trace = has_extension_trace.then(|| Matrix::join(extension_segments));
+        self.check_constraints(
+            &challenges,
+            &hints,
+            trace.base_columns(),
+            extension_trace.as_ref(),
+            None,
+        )
+    }
+
+    /// Evaluates every constraint on every row of the execution trace and
+    /// reports the first constraint that doesn't hold
+    fn check_constraints(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+        hints: &Hints<Self::Fq>,
+        base_trace: &Matrix<Self::Fp>,
+        extension_trace: Option<&Matrix<Self::Fq>>,
+        preprocessed_trace: Option<&Matrix<Self::Fp>>,
+    ) -> Result<(), UnsatisfiedConstraint<Self::Fp, Self::Fq>> {
+        use AlgebraicExpression::*;
+
+        let trace_info = self.trace_info();
+        let num_execution_trace_columns = trace_info.num_columns();
         let trace_domain = self.trace_domain();
+        // columns of shorter base segments are checked on every row
+        let expanded_base_trace;
+        let base_trace = if trace_info.base_segment_lengths == [trace_info.trace_len] {
+            base_trace
+        } else {
+            expanded_base_trace =
+                interpolate_base_segments(trace_info, base_trace).evaluate(trace_domain);
+            &expanded_base_trace
+        };
         let base_column_range = trace_info.base_columns_range();
         let extension_column_range = trace_info.extension_columns_range();
+        let preprocessed_column_range = trace_info.preprocessed_columns_range();
+        // periodic columns are checked as columns following the execution trace
+        let periodic_columns = self.periodic_columns();
+        let periodic_column_range =
+            num_execution_trace_columns..num_execution_trace_columns + periodic_columns.len();
 
         // helper function to get a value from the execution trace
         let get_trace_value = |row: usize, col: usize, offset: isize| {
@@ -242,50 +812,93 @@ pub trait Air {
             } else if extension_column_range.contains(&col) {
                 let col = col - trace_info.num_base_columns;
                 FieldConstant::Fq(extension_trace.unwrap().0[col][pos])
+            } else if preprocessed_column_range.contains(&col) {
+                let col = col - preprocessed_column_range.start;
+                FieldConstant::Fp(preprocessed_trace.unwrap().0[col][pos])
+            } else if periodic_column_range.contains(&col) {
+                FieldConstant::Fp(periodic_columns[col - num_execution_trace_columns].get(pos))
             } else {
                 unreachable!("requested column {col} does not exist")
             }
         };
+        let column_name = |col: usize| {
+            if periodic_column_range.contains(&col) {
+                format!("Periodic({})", col - num_execution_trace_columns)
+            } else {
+                self.trace_column_name(col)
+            }
+        };
 
-        for (c_idx, constraint) in self.constraints().into_iter().enumerate() {
+        let trace_len = trace_domain.size();
+        for (c_idx, (constraint, divisor)) in self.divided_constraints().into_iter().enumerate() {
+            let constraint =
+                constraint.substitute_periodic(&mut |i| Trace(num_execution_trace_columns + i, 0));
+            // the numerator of a divided constraint only has to vanish on the
+            // rows its divisor vanishes on. Finding these rows up front is
+            // linear in the trace length rather than evaluating every factor
+            // of the divisor on every row.
+            let roots = divisor.map(|divisor| divisor.roots(trace_len));
             for (row, x) in trace_domain.elements().enumerate() {
-                let is_valid = constraint
-                    .check(
+                if let Some(roots) = &roots && !roots[row] {
+                    continue;
+                }
+                let check = |expression: &AlgebraicExpression<Self::Fp, Self::Fq>| {
+                    expression.check(
                         &FieldConstant::Fp(x),
                         &|i| FieldConstant::Fq(hints[i]),
                         &|i| FieldConstant::Fq(challenges[i]),
                         &|col, offset| get_trace_value(row, col, offset),
                     )
-                    .is_some();
-
-                if !is_valid {
-                    let mut vals = vec![format!("x = {x}")];
+                };
+
+                let holds = match (&roots, check(&constraint)) {
+                    (Some(_), value) => value.is_some_and(|v| v.is_zero()),
+                    (None, value) => value.is_some(),
+                };
+                if !holds {
+                    // terms of the numerator that don't cancel
+                    let terms = constraint
+                        .terms()
+                        .iter()
+                        .map(|term| (term, check(term)))
+                        .filter(|(_, value)| !value.is_some_and(|v| v.is_zero()))
+                        .map(|(term, value)| {
+                            let term = term.map(&mut |node| match node {
+                                Trace(col, 0) if periodic_column_range.contains(&col) => {
+                                    Periodic(col - num_execution_trace_columns)
+                                }
+                                other => other,
+                            });
+                            (term.to_string(), value)
+                        })
+                        .collect();
+
+                    // get a description of each leaf node
+                    let mut values = vec![(String::from("x"), FieldConstant::Fp(x))];
                     constraint.traverse(&mut |node| match *node {
-                        // get a description of each leaf node
-                        Trace(col, offset) => vals.push(format!(
-                            "Trace(col={col:0>3}, offset={offset:0>3}) = {}",
-                            get_trace_value(row, col, offset)
+                        Trace(col, offset) => values.push((
+                            format!("{} at offset {offset}", column_name(col)),
+                            get_trace_value(row, col, offset),
                         )),
-                        Challenge(i) => vals.push(format!("Challenge({i}) = {}", challenges[i])),
-                        Hint(i) => vals.push(format!("Hint({i}) = {}", hints[i])),
-
+                        Challenge(i) => values
+                            .push((format!("Challenge({i})"), FieldConstant::Fq(challenges[i]))),
+                        Hint(i) => values.push((format!("Hint({i})"), FieldConstant::Fq(hints[i]))),
                         // skip tree nodes
                         _ => (),
                     });
-
-                    vals.sort();
-                    vals.dedup();
-
-                    // TODO: display constraint? eprintln!("Constraint is:\n{constraint}\n");
-                    #[cfg(feature = "std")]
-                    eprint!("Constraint {c_idx} does not evaluate to a low degree polynomial. ");
-                    #[cfg(feature = "std")]
-                    eprintln!("Divide by zero occurs at row {row}.\n");
-                    #[cfg(feature = "std")]
-                    eprintln!("Expression values:\n{}", vals.join("\n"));
-                    panic!();
+                    values.sort_by(|a, b| a.0.cmp(&b.0));
+                    values.dedup_by(|a, b| a.0 == b.0);
+
+                    return Err(UnsatisfiedConstraint {
+                        constraint: c_idx,
+                        row,
+                        terms,
+                        values,
+                    });
                 }
             }
         }
+
+        Ok(())
     }
 }
@@ -0,0 +1,146 @@
+use crate::constraints::AlgebraicExpression;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::FieldConstant;
+use crate::divisor::Divisor;
+use crate::StarkExtensionOf;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::ops::Range;
+use gpu_poly::GpuFftField;
+
+/// Rows of the trace an [Assertion] applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssertionSteps {
+    /// A single row
+    Single(usize),
+    /// Every `stride` rows starting from `first_step`. The stride must be a
+    /// power of two.
+    Periodic { first_step: usize, stride: usize },
+    /// A contiguous range of rows
+    Range(Range<usize>),
+}
+
+/// A cell of the base trace that must equal a public value e.g. a program hash
+/// or an input/output word. Public cells are asserted like an [Assertion] and
+/// are also absorbed into the public coin so a proof can't be checked against
+/// a trace with different public values (see `Air::public_cells`).
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicCell<Fp: GpuFftField + FftField> {
+    pub column: usize,
+    pub row: usize,
+    pub value: Fp,
+}
+
+impl<Fp: GpuFftField + FftField> PublicCell<Fp> {
+    pub fn new(column: usize, row: usize, value: Fp) -> Self {
+        PublicCell { column, row, value }
+    }
+
+    /// Assertion that base trace column `column` equals `value` at row `row`
+    pub fn assertion<Fq: StarkExtensionOf<Fp>>(&self) -> Assertion<Fp, Fq> {
+        Assertion::single(self.column, self.row, FieldConstant::Fp(self.value))
+    }
+}
+
+/// Asserts an execution trace column equals a value at some rows. Constraints
+/// for assertions are built automatically (see `Air::assertions`).
+#[derive(Clone, Debug)]
+pub struct Assertion<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp> = Fp> {
+    pub column: usize,
+    pub steps: AssertionSteps,
+    pub value: FieldConstant<Fp, Fq>,
+}
+
+impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Assertion<Fp, Fq> {
+    /// Asserts `column` equals `value` at row `step`
+    pub fn single(column: usize, step: usize, value: FieldConstant<Fp, Fq>) -> Self {
+        Assertion {
+            column,
+            steps: AssertionSteps::Single(step),
+            value,
+        }
+    }
+
+    /// Asserts `column` equals `value` every `stride` rows starting from row
+    /// `first_step`
+    pub fn periodic(
+        column: usize,
+        first_step: usize,
+        stride: usize,
+        value: FieldConstant<Fp, Fq>,
+    ) -> Self {
+        assert!(stride.is_power_of_two(), "stride must be a power of two");
+        assert!(
+            first_step < stride,
+            "first step must be less than the stride"
+        );
+        Assertion {
+            column,
+            steps: AssertionSteps::Periodic { first_step, stride },
+            value,
+        }
+    }
+
+    /// Asserts `column` equals `value` on all rows in `steps`
+    pub fn range(column: usize, steps: Range<usize>, value: FieldConstant<Fp, Fq>) -> Self {
+        assert!(!steps.is_empty(), "range must contain at least one step");
+        Assertion {
+            column,
+            steps: AssertionSteps::Range(steps),
+            value,
+        }
+    }
+
+    /// Returns true if the assertion applies to row `step`
+    pub fn contains(&self, step: usize) -> bool {
+        match &self.steps {
+            &AssertionSteps::Single(s) => s == step,
+            &AssertionSteps::Periodic { first_step, stride } => step % stride == first_step,
+            AssertionSteps::Range(range) => range.contains(&step),
+        }
+    }
+
+    /// Vanishing polynomial of the rows the assertion applies to in a trace
+    /// with `trace_len` rows
+    pub fn divisor(&self, trace_len: usize) -> Divisor {
+        let n = trace_len;
+        match &self.steps {
+            &AssertionSteps::Single(step) => Divisor::row(step),
+            &AssertionSteps::Periodic { first_step, stride } => {
+                assert!(stride <= n, "stride {stride} exceeds the trace length");
+                Divisor::periodic(first_step, stride)
+            }
+            AssertionSteps::Range(range) => {
+                assert!(range.end <= n, "range {range:?} is outside the trace");
+                if range.len() <= n / 2 {
+                    range.clone().fold(Divisor::default(), Divisor::with_row)
+                } else {
+                    // cheaper to divide by the vanishing polynomial of the trace
+                    // domain and remove the rows outside the range
+                    (0..range.start)
+                        .chain(range.end..n)
+                        .fold(Divisor::periodic(0, 1), Divisor::with_exemption)
+                }
+            }
+        }
+    }
+
+    /// Expression that vanishes on the rows where the assertion holds
+    pub fn numerator(&self) -> AlgebraicExpression<Fp, Fq> {
+        self.column.curr() - self.value
+    }
+
+    /// Constraint that is a low degree polynomial iff the assertion holds for
+    /// a trace over `trace_domain`
+    pub fn into_constraint(
+        &self,
+        trace_domain: Radix2EvaluationDomain<Fp>,
+    ) -> AlgebraicExpression<Fp, Fq> {
+        self.divisor(trace_domain.size())
+            .divide(self.numerator(), trace_domain)
+    }
+}
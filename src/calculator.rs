@@ -4,23 +4,27 @@ use crate::constraints::AlgebraicExpression;
 use crate::constraints::EvaluationLde;
 use crate::constraints::FieldConstant;
 use crate::constraints::FieldType;
+use crate::constraints::FusedProgram;
+use crate::utils::commit_and_wait;
 use crate::Air;
 use crate::Matrix;
 use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 use ark_ff::One;
+use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use gpu_poly::prelude::planner;
 use gpu_poly::prelude::PageAlignedAllocator;
-use gpu_poly::prelude::PLANNER;
 use gpu_poly::prelude::*;
 use gpu_poly::stage::AddAssignConstStage;
 use gpu_poly::stage::AddIntoConstStage;
 use gpu_poly::stage::AddIntoStage;
+use gpu_poly::stage::BatchInverseStage;
 use gpu_poly::stage::ConvertIntoStage;
+use gpu_poly::stage::EvaluateFusedStage;
 use gpu_poly::stage::ExpInPlaceStage;
 use gpu_poly::stage::ExpIntoStage;
-use gpu_poly::stage::InverseInPlaceStage;
 use gpu_poly::stage::MulAssignConstStage;
 use gpu_poly::stage::MulAssignStage;
 use gpu_poly::stage::MulIntoConstStage;
@@ -38,8 +42,8 @@ pub fn lde_calculator<A: Air>(
 ) -> Matrix<A::Fq> {
     use AlgebraicExpression::*;
     let mut expr = expr.reuse_shared_nodes();
-    let library = &PLANNER.library;
-    let command_queue = &PLANNER.command_queue;
+    let library = &planner().library;
+    let command_queue = &planner().command_queue;
     let device = command_queue.device();
     // constraint evaluation (ce)
     let ce_domain = air.ce_domain();
@@ -132,6 +136,8 @@ pub fn lde_calculator<A: Air>(
             }
         }
         Lde(..) | Constant(_) => (/* skip */),
+        // periodic columns are substituted with trace LDEs by the composer
+        Periodic(_) => unreachable!(),
     });
 
     drop(trace_ldes);
@@ -163,7 +169,11 @@ pub fn lde_calculator<A: Air>(
     let add_assign_const_fq_fp = AddAssignConstStage::<A::Fq, A::Fp>::new(library, ce_lde_size);
     // TODO: this is problematic if Fp==Fq
     let convert_fp_into_fq = ConvertIntoStage::<A::Fq, A::Fp>::new(library, ce_lde_size);
-    let inverse_in_place_fp = InverseInPlaceStage::<A::Fp>::new(library, ce_lde_size);
+    // vanishing polynomial denominators are inverted with Montgomery's trick
+    let batch_inverse_fp = BatchInverseStage::<A::Fp>::new(library, ce_lde_size);
+    let mut batch_inverse_scratch = Vec::with_capacity_in(ce_lde_size, PageAlignedAllocator);
+    batch_inverse_scratch.resize(ce_lde_size, A::Fp::zero());
+    let batch_inverse_scratch_buffer = buffer_mut_no_copy(device, &mut batch_inverse_scratch);
     // let inverse_into_fp = InverseIntoStage::<A::Fp>::new(library, ce_lde_size);
     let neg_in_place_fp = NegInPlaceStage::<A::Fp>::new(library, ce_lde_size);
     let neg_in_place_fq = NegInPlaceStage::<A::Fq>::new(library, ce_lde_size);
@@ -624,7 +634,11 @@ pub fn lde_calculator<A: Air>(
                         if a_ref_count == 1 && Rc::strong_count(lde) <= 2 {
                             exp_in_place_fp.encode(command_buffer, buff, e.unsigned_abs());
                             if *e < 0 {
-                                inverse_in_place_fp.encode(command_buffer, buff);
+                                batch_inverse_fp.encode(
+                                    command_buffer,
+                                    buff,
+                                    &batch_inverse_scratch_buffer,
+                                );
                             }
                             Lde(Rc::clone(lde), *buff_offset)
                         } else {
@@ -636,7 +650,11 @@ pub fn lde_calculator<A: Air>(
                                 e.unsigned_abs(),
                             );
                             if *e < 0 {
-                                inverse_in_place_fp.encode(command_buffer, dst.get_gpu_buffer());
+                                batch_inverse_fp.encode(
+                                    command_buffer,
+                                    dst.get_gpu_buffer(),
+                                    &batch_inverse_scratch_buffer,
+                                );
                             }
                             Lde(dst, *buff_offset)
                         }
@@ -672,8 +690,7 @@ pub fn lde_calculator<A: Air>(
         unreachable!()
     }
 
-    command_buffer.commit();
-    command_buffer.wait_until_completed();
+    commit_and_wait(command_buffer);
     drop(lde_cache);
 
     if let Lde(buff, offset) = expr {
@@ -687,6 +704,71 @@ pub fn lde_calculator<A: Air>(
     }
 }
 
+/// Evaluates a constraint that has been compiled into a [FusedProgram] in a
+/// single GPU pass. Avoids materializing an LDE for every intermediate node.
+pub fn fused_calculator<A: Air>(
+    air: &A,
+    program: &FusedProgram<A::Fp, A::Fq>,
+    base_trace_lde: &Matrix<A::Fp>,
+    extension_trace_lde: Option<&Matrix<A::Fq>>,
+) -> Matrix<A::Fq> {
+    let library = &planner().library;
+    let command_queue = &planner().command_queue;
+    let device = command_queue.device();
+    let ce_domain = air.ce_domain();
+    let n = ce_domain.size();
+
+    let mut xs = Vec::with_capacity_in(n, PageAlignedAllocator);
+    xs.extend(ce_domain.elements());
+
+    // pack trace columns of each field into a single column-major buffer
+    let mut fp_trace = Vec::with_capacity_in(n * base_trace_lde.num_cols(), PageAlignedAllocator);
+    for column in &base_trace_lde.0 {
+        fp_trace.extend_from_slice(&column[0..n]);
+    }
+    let mut fq_trace = Vec::new_in(PageAlignedAllocator);
+    for column in extension_trace_lde.iter().flat_map(|m| &m.0) {
+        fq_trace.extend_from_slice(&column[0..n]);
+    }
+
+    let mut fp_constants = Vec::new_in(PageAlignedAllocator);
+    fp_constants.extend_from_slice(&program.fp_constants);
+    let mut fq_constants = Vec::new_in(PageAlignedAllocator);
+    fq_constants.extend_from_slice(&program.fq_constants);
+
+    // metal doesn't allow binding empty buffers
+    for buffer in [&mut fp_trace, &mut fp_constants] {
+        if buffer.is_empty() {
+            buffer.push(A::Fp::zero());
+        }
+    }
+    for buffer in [&mut fq_trace, &mut fq_constants] {
+        if buffer.is_empty() {
+            buffer.push(A::Fq::zero());
+        }
+    }
+
+    let mut result = Vec::with_capacity_in(n, PageAlignedAllocator);
+    // ok because every element is written by the kernel
+    unsafe { result.set_len(n) }
+
+    let command_buffer = command_queue.new_command_buffer();
+    let evaluate_fused = EvaluateFusedStage::<A::Fq, A::Fp>::new(library, n);
+    evaluate_fused.encode(
+        command_buffer,
+        &buffer_mut_no_copy(device, &mut result),
+        &program.encode(),
+        &buffer_no_copy(device, &fp_constants),
+        &buffer_no_copy(device, &fq_constants),
+        &buffer_no_copy(device, &xs),
+        &buffer_no_copy(device, &fp_trace),
+        &buffer_no_copy(device, &fq_trace),
+    );
+    commit_and_wait(command_buffer);
+
+    Matrix::new(vec![result])
+}
+
 pub struct LdeCache<Fp, Fq> {
     // TODO: make a type for vec and gpu buffer
     lde_size: usize,
@@ -708,7 +790,7 @@ impl<Fp: GpuField, Fq: GpuField> LdeCache<Fp, Fq> {
     }
 
     fn get_buffer(&mut self, ty: FieldType) -> Rc<EvaluationLde<Fp, Fq>> {
-        let command_queue = &PLANNER.command_queue;
+        let command_queue = &planner().command_queue;
         let device = command_queue.device();
         // TODO: make O(1)
         self.buffers
@@ -5,12 +5,77 @@ use ark_std::rand::Rng;
 use core::ops::Deref;
 use core::ops::Index;
 
+/// Verifier challenges. Challenges can be looked up by position, by any
+/// [VerifierChallenge] (e.g. an enum implemented with
+/// [impl_verifier_challenge](crate::impl_verifier_challenge)) or by the label
+/// given to them by `Air::challenge_labels`.
 #[derive(Default)]
-pub struct Challenges<F: Field>(Vec<F>);
+pub struct Challenges<F: Field> {
+    values: Vec<F>,
+    labels: Vec<&'static str>,
+}
 
 impl<F: Field> Challenges<F> {
     pub fn new<R: Rng + ?Sized>(rng: &mut R, num_challenges: usize) -> Self {
-        Challenges((0..num_challenges).map(|_| F::rand(rng)).collect())
+        Self::from(
+            (0..num_challenges)
+                .map(|_| F::rand(rng))
+                .collect::<Vec<F>>(),
+        )
+    }
+
+    /// Names the challenges in order i.e. challenge `i` is labelled
+    /// `labels[i]`. There must be one unique label per challenge.
+    pub fn with_labels(mut self, labels: Vec<&'static str>) -> Self {
+        assert_eq!(
+            self.values.len(),
+            labels.len(),
+            "expected one label per challenge"
+        );
+        assert_unique(&labels);
+        self.labels = labels;
+        self
+    }
+
+    /// Appends the challenges of a later extension segment. Either both or
+    /// neither of the challenges must be labelled.
+    pub fn append(&mut self, other: Challenges<F>) {
+        self.values.extend(other.values);
+        self.labels.extend(other.labels);
+        assert!(
+            self.labels.is_empty() || self.labels.len() == self.values.len(),
+            "only some challenges are labelled"
+        );
+        assert_unique(&self.labels);
+    }
+
+    /// Index of the challenge labelled `label`
+    pub fn index_of(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| *l == label)
+    }
+
+    /// Returns the challenge labelled `label`. Panics if there is no such
+    /// challenge.
+    pub fn by_label(&self, label: &str) -> F {
+        let index = self
+            .index_of(label)
+            .unwrap_or_else(|| panic!("no challenge is labelled {label}"));
+        self.values[index]
+    }
+}
+
+impl<F: Field> From<Vec<F>> for Challenges<F> {
+    fn from(values: Vec<F>) -> Self {
+        Challenges {
+            values,
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl<F: Field> Extend<F> for Challenges<F> {
+    fn extend<T: IntoIterator<Item = F>>(&mut self, challenges: T) {
+        self.values.extend(challenges)
     }
 }
 
@@ -18,7 +83,7 @@ impl<F: Field> Deref for Challenges<F> {
     type Target = Vec<F>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.values
     }
 }
 
@@ -26,6 +91,16 @@ impl<F: Field, C: VerifierChallenge> Index<C> for Challenges<F> {
     type Output = F;
 
     fn index(&self, challenge: C) -> &Self::Output {
-        &self.0[challenge.index()]
+        &self.values[challenge.index()]
     }
 }
+
+fn assert_unique(labels: &[&'static str]) {
+    assert!(
+        labels
+            .iter()
+            .enumerate()
+            .all(|(i, label)| !labels[..i].contains(label)),
+        "challenge labels must be unique"
+    );
+}
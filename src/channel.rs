@@ -1,68 +1,95 @@
 use crate::fri;
-use crate::fri::FriProof;
+use crate::merkle::LeafEncoder;
+use crate::random::hash_id;
+use crate::random::label;
 use crate::random::PublicCoin;
 use crate::trace::Queries;
 use crate::Air;
+use crate::LdtProof;
 use crate::Proof;
 use alloc::vec::Vec;
 use ark_serialize::CanonicalSerialize;
-use ark_std::rand::Rng;
-use core::ops::Deref;
+use core::marker::PhantomData;
 use digest::Digest;
-use digest::Output;
-#[cfg(feature = "parallel")]
-use rayon::prelude::*;
 
 pub struct ProverChannel<'a, A: Air, D: Digest> {
     air: &'a A,
-    pub public_coin: PublicCoin<D>,
-    base_trace_commitment: Output<D>,
-    extension_trace_commitment: Option<Output<D>>,
-    composition_trace_commitment: Output<D>,
-    fri_layer_commitments: Vec<Output<D>>,
+    pub public_coin: A::PublicCoin,
+    base_trace_commitment: Vec<u8>,
+    extension_trace_commitments: Vec<Vec<u8>>,
+    composition_trace_commitment: Vec<u8>,
+    fri_layer_commitments: Vec<Vec<u8>>,
     execution_trace_ood_evals: Vec<A::Fq>,
     composition_trace_ood_evals: Vec<A::Fq>,
     pow_nonce: u64,
+    _digest: PhantomData<D>,
 }
 
 // impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
 impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
-    pub fn new(air: &'a A) -> Self {
+    /// Creates a channel with a public coin seeded by the proof parameters
+    /// and the caller's `context` (see [crate::Prover::context])
+    pub fn new(air: &'a A, context: &[u8]) -> Self {
         let mut seed = Vec::new();
         // Seed the public coin with:
-        // 1. serialized public imputs
+        // 1. the hash function identifier
+        hash_id::<D>().serialize_compressed(&mut seed).unwrap();
+        // 2. the Merkle leaf encoding
+        A::LeafEncoder::ID.serialize_compressed(&mut seed).unwrap();
+        // 3. serialized public imputs
         air.pub_inputs().serialize_compressed(&mut seed).unwrap();
-        // 2. various metadata about the air and proof
+        // 4. various metadata about the air and proof
         // TODO: field bytes?
         air.trace_info().serialize_compressed(&mut seed).unwrap();
         air.options().serialize_compressed(&mut seed).unwrap();
-        let public_coin = PublicCoin::<D>::new(&seed);
+        let mut public_coin = A::PublicCoin::new(&seed);
+        // 5. context of the deployment the proof is for
+        if !context.is_empty() {
+            public_coin.reseed_with_label(label::CONTEXT, &context);
+        }
+        // 6. public trace cells (see `Air::public_cells`)
+        let public_cells = air.public_cells();
+        if !public_cells.is_empty() {
+            public_coin.reseed_with_label(label::PUBLIC_CELLS, &public_cells);
+        }
         ProverChannel {
             air,
             public_coin,
-            extension_trace_commitment: None,
             base_trace_commitment: Default::default(),
+            extension_trace_commitments: Default::default(),
             composition_trace_commitment: Default::default(),
             execution_trace_ood_evals: Default::default(),
             composition_trace_ood_evals: Default::default(),
             fri_layer_commitments: Default::default(),
             pow_nonce: 0,
+            _digest: PhantomData,
         }
     }
 
-    pub fn commit_base_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.base_trace_commitment = commitment.clone();
+    /// Absorbs the commitment to the preprocessed trace. It isn't added to
+    /// the proof since the verifier is given it separately.
+    pub fn commit_preprocessed_trace(&mut self, commitment: &[u8]) {
+        self.public_coin
+            .reseed_with_label(label::PREPROCESSED_TRACE_COMMITMENT, &commitment);
+    }
+
+    pub fn commit_base_trace(&mut self, commitment: &[u8]) {
+        self.public_coin
+            .reseed_with_label(label::BASE_TRACE_COMMITMENT, &commitment);
+        self.base_trace_commitment = commitment.to_vec();
     }
 
-    pub fn commit_extension_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.extension_trace_commitment = Some(commitment.clone());
+    /// Commits to the next extension trace segment
+    pub fn commit_extension_trace(&mut self, commitment: &[u8]) {
+        self.public_coin
+            .reseed_with_label(label::EXTENSION_TRACE_COMMITMENT, &commitment);
+        self.extension_trace_commitments.push(commitment.to_vec());
     }
 
-    pub fn commit_composition_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.composition_trace_commitment = commitment.clone();
+    pub fn commit_composition_trace(&mut self, commitment: &[u8]) {
+        self.public_coin
+            .reseed_with_label(label::COMPOSITION_TRACE_COMMITMENT, &commitment);
+        self.composition_trace_commitment = commitment.to_vec();
     }
 
     pub fn get_ood_point<F: ark_ff::Field>(&mut self) -> F {
@@ -70,52 +97,60 @@ impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
     }
 
     pub fn send_execution_trace_ood_evals(&mut self, evals: Vec<A::Fq>) {
-        self.public_coin.reseed(&evals);
+        self.public_coin
+            .reseed_with_label(label::EXECUTION_TRACE_OOD_EVALS, &evals);
         self.execution_trace_ood_evals = evals;
     }
 
     pub fn send_composition_trace_ood_evals(&mut self, evals: Vec<A::Fq>) {
-        self.public_coin.reseed(&evals);
+        self.public_coin
+            .reseed_with_label(label::COMPOSITION_TRACE_OOD_EVALS, &evals);
         self.composition_trace_ood_evals = evals;
     }
 
     pub fn grind_fri_commitments(&mut self) {
-        let grinding_factor = self.air.options().grinding_factor as u32;
-        if grinding_factor == 0 {
+        let found = self.grind_fri_commitments_until(|| false);
+        assert!(found, "nonce not found");
+    }
+
+    /// Grinds until a nonce is found or `is_cancelled` returns true. Returns
+    /// false if grinding was cancelled.
+    pub fn grind_fri_commitments_until(&mut self, is_cancelled: impl Fn() -> bool + Sync) -> bool {
+        let grinding = self.air.options().grinding;
+        if grinding.bits == 0 {
             // skip if there is no grinding required
-            return;
+            return true;
         }
 
-        #[cfg(not(feature = "parallel"))]
-        let nonce = (1..u64::MAX)
-            .find(|&nonce| self.public_coin.check_leading_zeros(nonce) >= grinding_factor);
-
-        #[cfg(feature = "parallel")]
-        let nonce = (1..u64::MAX)
-            .into_par_iter()
-            .find_any(|&nonce| self.public_coin.check_leading_zeros(nonce) >= grinding_factor);
+        match self.public_coin.grind_until(grinding, is_cancelled) {
+            Some(nonce) => self.pow_nonce = nonce,
+            None => return false,
+        }
+        self.public_coin
+            .reseed_with_label(label::POW, &self.pow_nonce);
+        true
+    }
 
-        self.pow_nonce = nonce.expect("nonce not found");
-        self.public_coin.reseed(&self.pow_nonce);
+    /// Nonce found by grinding. Zero if no grinding is required.
+    pub fn pow_nonce(&self) -> u64 {
+        self.pow_nonce
     }
 
     pub fn get_fri_query_positions(&mut self) -> Vec<usize> {
-        // TODO: voulnerability if multiple positions are the same
         let num_queries = self.air.options().num_queries;
         let lde_domain_size = self.air.trace_len() * self.air.lde_blowup_factor();
-        let mut rng = self.public_coin.draw_rng();
-        (0..num_queries)
-            .map(|_| rng.gen_range(0..lde_domain_size))
-            .collect()
+        self.public_coin
+            .draw_unique_queries(num_queries.into(), lde_domain_size)
     }
 
-    pub fn build_proof(self, trace_queries: Queries<A>, fri_proof: FriProof<A::Fq>) -> Proof<A> {
+    pub fn build_proof(self, trace_queries: Queries<A>, fri_proof: LdtProof<A>) -> Proof<A> {
         Proof {
             options: *self.air.options(),
             trace_info: self.air.trace_info().clone(),
-            base_trace_commitment: self.base_trace_commitment.to_vec(),
-            extension_trace_commitment: self.extension_trace_commitment.map(|o| o.to_vec()),
-            composition_trace_commitment: self.composition_trace_commitment.to_vec(),
+            hash_id: hash_id::<D>(),
+            base_trace_commitment: self.base_trace_commitment,
+            extension_trace_commitments: self.extension_trace_commitments,
+            composition_trace_commitment: self.composition_trace_commitment,
             public_inputs: self.air.pub_inputs().clone(),
             execution_trace_ood_evals: self.execution_trace_ood_evals,
             composition_trace_ood_evals: self.composition_trace_ood_evals,
@@ -130,12 +165,20 @@ impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
 impl<'a, A: Air, D: Digest> fri::ProverChannel<A::Fq> for ProverChannel<'a, A, D> {
     type Digest = D;
 
-    fn commit_fri_layer(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.fri_layer_commitments.push(commitment.clone());
+    fn commit_fri_layer(&mut self, commitment: &[u8]) {
+        let layer = self.fri_layer_commitments.len();
+        self.public_coin
+            .reseed_with_label(&label::fri_layer(layer), &commitment);
+        self.fri_layer_commitments.push(commitment.to_vec());
     }
 
     fn draw_fri_alpha(&mut self) -> A::Fq {
         self.public_coin.draw()
     }
+
+    fn commit_fri_remainder(&mut self, coeffs: &[A::Fq]) {
+        let layer = self.fri_layer_commitments.len();
+        self.public_coin
+            .reseed_with_label(&label::fri_layer(layer), &coeffs);
+    }
 }
@@ -0,0 +1,183 @@
+//! Snapshots of prover state so long proofs can resume after a restart (see
+//! `Prover::save_checkpoint` and `Prover::load_checkpoint`) and so the stages
+//! of a proof can run on different machines (see `Prover::commit_trace`).
+
+use crate::fri::FriLayerCheckpoint;
+use crate::Air;
+use crate::LdtProof;
+use crate::Matrix;
+use crate::ProofOptions;
+use crate::TraceInfo;
+use alloc::vec::Vec;
+use ark_ff::Field;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use digest::Digest;
+use digest::Output;
+
+/// Polynomials of a committed trace and the (unsalted) hashes of the rows of
+/// their low degree extension. The commitment is rebuilt from the leaves and
+/// the low degree extension is re-evaluated from the polynomials.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommittedTrace<F: Field> {
+    pub polys: Matrix<F>,
+    /// Leaf hashes concatenated in order. Empty while `Prover::prove` keeps
+    /// the commitment in memory and isn't taking checkpoints.
+    pub leaves: Vec<u8>,
+}
+
+impl<F: Field> CommittedTrace<F> {
+    pub fn new<D: Digest>(polys: Matrix<F>, leaves: &[Output<D>]) -> Self {
+        CommittedTrace {
+            polys,
+            leaves: leaves.iter().flatten().copied().collect(),
+        }
+    }
+
+    /// Committed trace whose leaves are only kept by its commitment
+    pub fn without_leaves(polys: Matrix<F>) -> Self {
+        CommittedTrace {
+            polys,
+            leaves: Vec::new(),
+        }
+    }
+
+    pub fn leaves<D: Digest>(&self) -> Vec<Output<D>> {
+        self.leaves
+            .chunks(<D as Digest>::output_size())
+            .map(Output::<D>::clone_from_slice)
+            .collect()
+    }
+}
+
+/// Prover state after the base trace, the composition trace and each FRI
+/// layer are committed to. Everything else is derived again from the
+/// transcript when proving resumes. Contains the secret salt seed of
+/// zero-knowledge proofs so must be stored as securely as the witness.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProverCheckpoint<A: Air> {
+    pub trace_info: TraceInfo,
+    pub options: ProofOptions,
+    pub public_inputs: A::PublicInputs,
+    /// Fiat-Shamir context (see `Prover::context`)
+    pub context: Vec<u8>,
+    /// Digest of the trace the checkpoint was taken for (see [trace_digest])
+    pub trace_digest: Vec<u8>,
+    pub salt_seed: Option<[u8; 32]>,
+    pub base_trace: Option<CommittedTrace<A::Fp>>,
+    pub composition_trace: Option<CommittedTrace<A::Fq>>,
+    /// FRI layers committed to so far
+    pub fri_layers: Vec<FriLayerCheckpoint<A::Fq>>,
+}
+
+impl<A: Air> ProverCheckpoint<A> {
+    /// Returns true if the checkpoint was taken while proving the trace with
+    /// digest `trace_digest` (see [trace_digest]) with `air` under `context`.
+    /// Traces with the same dimensions and public inputs can have different
+    /// witnesses so the digest is needed to tell them apart.
+    pub fn is_for(&self, air: &A, context: &[u8], trace_digest: &[u8]) -> bool {
+        let mut expected = Vec::new();
+        let mut actual = Vec::new();
+        air.trace_info()
+            .serialize_compressed(&mut expected)
+            .unwrap();
+        air.options().serialize_compressed(&mut expected).unwrap();
+        air.pub_inputs()
+            .serialize_compressed(&mut expected)
+            .unwrap();
+        self.trace_info.serialize_compressed(&mut actual).unwrap();
+        self.options.serialize_compressed(&mut actual).unwrap();
+        self.public_inputs
+            .serialize_compressed(&mut actual)
+            .unwrap();
+        expected == actual && self.context == context && self.trace_digest == trace_digest
+    }
+
+    /// Writes the checkpoint to `path`
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes).unwrap();
+        std::fs::write(path, bytes)
+    }
+
+    /// Reads a checkpoint written by [ProverCheckpoint::save]. Returns `None`
+    /// if there is no checkpoint at `path` or it can't be read.
+    #[cfg(feature = "std")]
+    pub fn load(path: &std::path::Path) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        Self::deserialize_compressed(&*bytes).ok()
+    }
+}
+
+/// Digest of the base columns of a trace and the commitment to the
+/// preprocessed trace if there is one. Identifies the trace a checkpoint was
+/// taken for without storing it.
+pub fn trace_digest<D: Digest, F: Field>(
+    base_columns: &Matrix<F>,
+    preprocessed_commitment: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut bytes = Vec::new();
+    for chunk in base_columns.0.iter().flat_map(|column| column.chunks(1024)) {
+        bytes.clear();
+        for value in chunk {
+            value.serialize_compressed(&mut bytes).unwrap();
+        }
+        hasher.update(&bytes);
+    }
+    if let Some(commitment) = preprocessed_commitment {
+        hasher.update(commitment);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Proof state once the base trace and each extension trace segment are
+/// committed to (see `Prover::commit_trace`). Contains the secret salt seed
+/// of zero-knowledge proofs so must be stored as securely as the witness.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct TraceCommitted<A: Air> {
+    pub trace_info: TraceInfo,
+    pub options: ProofOptions,
+    pub public_inputs: A::PublicInputs,
+    /// Fiat-Shamir context (see `Prover::context`)
+    pub context: Vec<u8>,
+    pub salt_seed: Option<[u8; 32]>,
+    pub base_trace: CommittedTrace<A::Fp>,
+    pub extension_segments: Vec<CommittedTrace<A::Fq>>,
+    pub base_trace_commitment: Vec<u8>,
+    pub extension_trace_commitments: Vec<Vec<u8>>,
+}
+
+impl<A: Air> TraceCommitted<A> {
+    /// AIR the trace was committed to under
+    pub fn air(&self) -> A {
+        A::new(
+            self.trace_info.clone(),
+            self.public_inputs.clone(),
+            self.options,
+        )
+    }
+}
+
+/// Proof state once the composition trace is committed to (see
+/// `Prover::commit_constraints`)
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ConstraintsCommitted<A: Air> {
+    pub trace: TraceCommitted<A>,
+    pub composition_trace: CommittedTrace<A::Fq>,
+    pub composition_trace_commitment: Vec<u8>,
+}
+
+/// Proof state once the FRI layers are committed to, the proof-of-work is
+/// found and the FRI layers are opened (see `Prover::commit_fri`). Only the
+/// trace openings remain.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct FriDone<A: Air> {
+    pub constraints: ConstraintsCommitted<A>,
+    pub execution_trace_ood_evals: Vec<A::Fq>,
+    pub composition_trace_ood_evals: Vec<A::Fq>,
+    pub fri_proof: LdtProof<A>,
+    pub pow_nonce: u64,
+    pub query_positions: Vec<usize>,
+}
@@ -0,0 +1,82 @@
+use crate::merkle::MerkleProof;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeError;
+use crate::ProofOptions;
+use alloc::vec::Vec;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::fmt::Debug;
+use digest::Digest;
+use digest::Output;
+
+/// Commitment to a vector of leaf hashes that can be opened at individual
+/// positions. The prover and verifier commit to the trace and composition
+/// trace with `Air::VectorCommitment` so schemes other than a [MerkleTree] can
+/// be swapped in e.g. Verkle trees or hash-based accumulators. Schemes read
+/// their parameters from the [ProofOptions] they're given.
+pub trait VectorCommitment<D: Digest>: Sized {
+    /// Opening of a single position
+    type Proof: CanonicalSerialize + CanonicalDeserialize + Clone + Send + Sync;
+    /// Reason an opening doesn't verify
+    type Error: Debug;
+
+    /// Commits to `leaves` with the parameters in `options`
+    fn commit(leaves: Vec<Output<D>>, options: &ProofOptions) -> Self;
+
+    /// Commitment absorbed by the channel and sent to the verifier
+    fn commitment(&self) -> Vec<u8>;
+
+    /// Opens the leaf at `position`
+    fn open(&self, position: usize) -> Self::Proof;
+
+    /// Checks `proof` opens `leaf` at `position` of a vector of `num_leaves`
+    /// leaves with the given `commitment`
+    fn verify(
+        commitment: &[u8],
+        num_leaves: usize,
+        position: usize,
+        leaf: &Output<D>,
+        proof: &Self::Proof,
+        options: &ProofOptions,
+    ) -> Result<(), Self::Error>;
+}
+
+impl<D: Digest> VectorCommitment<D> for MerkleTree<D> {
+    type Proof = MerkleProof;
+    type Error = MerkleTreeError;
+
+    fn commit(leaves: Vec<Output<D>>, options: &ProofOptions) -> Self {
+        MerkleTree::new_with_arity(leaves, options.merkle_arity.into())
+            .expect("failed to construct Merkle tree")
+            .with_cap_height(options.merkle_cap_height.into())
+    }
+
+    fn commitment(&self) -> Vec<u8> {
+        MerkleTree::commitment(self)
+    }
+
+    fn open(&self, position: usize) -> MerkleProof {
+        self.prove(position)
+            .expect("failed to generate Merkle proof")
+    }
+
+    fn verify(
+        commitment: &[u8],
+        num_leaves: usize,
+        position: usize,
+        leaf: &Output<D>,
+        proof: &MerkleProof,
+        options: &ProofOptions,
+    ) -> Result<(), MerkleTreeError> {
+        let arity = options.merkle_arity.into();
+        let cap_size = Self::cap_size(num_leaves, options.merkle_cap_height.into(), arity);
+        let cap = Self::cap_from_bytes(commitment)
+            .filter(|cap| cap.len() == cap_size)
+            .ok_or(MerkleTreeError::InvalidProof)?;
+        let proof = proof.parse::<D>();
+        if proof.first() != Some(leaf) {
+            return Err(MerkleTreeError::InvalidProof);
+        }
+        Self::verify_cap(&cap, &proof, position, arity)
+    }
+}
@@ -1,25 +1,85 @@
 use crate::challenges::Challenges;
 use crate::constraints::AlgebraicExpression;
 use crate::constraints::FieldConstant;
+use crate::divisor::Divisor;
+use crate::domain::Domain;
 use crate::hints::Hints;
-use crate::merkle::MerkleTree;
 use crate::utils;
 use crate::utils::divide_out_point_into;
 use crate::utils::horner_evaluate;
+#[cfg(feature = "gpu")]
+use crate::utils::wait_for_gpu;
 use crate::Air;
 use crate::Matrix;
+use crate::StarkExtensionOf;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
 use ark_ff::Field;
+use ark_ff::UniformRand;
 use ark_ff::Zero;
+#[cfg(feature = "gpu")]
+use ark_poly::domain::DomainCoeff;
 use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_std::rand::Rng;
+use digest::Output;
 use gpu_poly::prelude::*;
+use gpu_poly::GpuFftField;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use sha2::Sha256;
+
+/// Numerator of a constraint and the inverse of its divisor (if it has one)
+type InverseDividedConstraint<Fp, Fq> = (
+    AlgebraicExpression<Fp, Fq>,
+    Option<AlgebraicExpression<Fp, Fq>>,
+);
+
+/// `(lde, poly, lde_row_hashes)` of the composition trace (see
+/// [ConstraintComposer::build_commitment])
+type CompositionCommitment<A> = (
+    Matrix<<A as Air>::Fq>,
+    Matrix<<A as Air>::Fq>,
+    Vec<Output<<A as Air>::Digest>>,
+);
+
+/// Sums `numerators[i] * coeffs[i] * divisors[i]` over all constraints where
+/// `divisors[i]` is the inverse of the constraint's divisor (if it has one).
+/// Only regroups the sum: the weighted numerators of constraints that share a
+/// divisor are summed before multiplying by the divisor which saves one
+/// multiplication per constraint and point. The result is the same.
+fn batch_by_divisor<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+    constraints: Vec<InverseDividedConstraint<Fp, Fq>>,
+    coeffs: impl Iterator<Item = AlgebraicExpression<Fp, Fq>>,
+) -> AlgebraicExpression<Fp, Fq> {
+    // groups are keyed by the canonical form of the divisor
+    let mut groups = BTreeMap::<String, (AlgebraicExpression<Fp, Fq>, Vec<_>)>::new();
+    let mut undivided = Vec::new();
+    for ((numerator, divisor), coeff) in constraints.into_iter().zip(coeffs) {
+        match divisor {
+            Some(divisor) => groups
+                .entry(divisor.to_sexpr())
+                .or_insert_with(|| (divisor, Vec::new()))
+                .1
+                .push(numerator * coeff),
+            None => undivided.push(numerator * coeff),
+        }
+    }
+    groups
+        .into_values()
+        .map(|(divisor, numerators)| {
+            numerators.into_iter().sum::<AlgebraicExpression<Fp, Fq>>() * divisor
+        })
+        .chain(undivided)
+        .sum()
+}
 
 pub struct ConstraintComposer<'a, A: Air> {
     air: &'a A,
     composition_coeffs: Vec<(A::Fq, A::Fq)>,
+    mask: Option<GpuVec<A::Fq>>,
 }
 
 impl<'a, A: Air> ConstraintComposer<'a, A> {
@@ -27,9 +87,23 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         ConstraintComposer {
             air,
             composition_coeffs,
+            mask: None,
         }
     }
 
+    /// Appends a random polynomial of degree less than the trace length to
+    /// the composition trace. It's opened and added to the DEEP composition
+    /// polynomial like the other composition trace columns but isn't part of
+    /// the composition polynomial so it masks the values sent to the
+    /// verifier. Required if `ProofOptions::zk` is set.
+    pub fn with_mask<R: Rng + ?Sized>(mut self, rng: &mut R) -> Self {
+        let trace_len = self.air.trace_len();
+        let mut mask = Vec::with_capacity_in(trace_len, PageAlignedAllocator);
+        mask.extend((0..trace_len).map(|_| A::Fq::rand(rng)));
+        self.mask = Some(mask);
+        self
+    }
+
     #[cfg(feature = "gpu")]
     pub fn evaluate_constraint_gpu(
         &self,
@@ -39,10 +113,8 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         base_trace_lde: Matrix<A::Fp>,
         extension_trace_lde: Option<Matrix<A::Fq>>,
     ) -> Matrix<A::Fq> {
-        use crate::calculator::lde_calculator;
-        use crate::constraints::EvaluationLde;
-        let command_queue = &PLANNER.command_queue;
-        let device = command_queue.device();
+        use crate::calculator::fused_calculator;
+        use crate::constraints::FieldType;
 
         #[cfg(debug_assertions)]
         let expected_result = self.evaluate_constraint_cpu(
@@ -53,6 +125,64 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
             extension_trace_lde.as_ref(),
         );
 
+        // try evaluate the entire constraint with a single kernel
+        let num_base_columns = base_trace_lde.num_cols();
+        let fused_program = composition_constraint.compile(
+            &|i| FieldConstant::Fq(hints[i]),
+            &|i| FieldConstant::Fq(challenges[i]),
+            &|i| {
+                if i < num_base_columns {
+                    (FieldType::Fp, i)
+                } else {
+                    (FieldType::Fq, i - num_base_columns)
+                }
+            },
+            self.air.ce_blowup_factor(),
+            self.air.ce_domain().size(),
+        );
+
+        let result = if let Some(program) = fused_program {
+            fused_calculator(
+                self.air,
+                &program,
+                &base_trace_lde,
+                extension_trace_lde.as_ref(),
+            )
+        } else {
+            self.evaluate_constraint_unfused(
+                composition_constraint,
+                challenges,
+                hints,
+                base_trace_lde,
+                extension_trace_lde,
+            )
+        };
+
+        #[cfg(debug_assertions)]
+        expected_result.0[0]
+            .iter()
+            .zip(&result.0[0])
+            .enumerate()
+            .for_each(|(i, (expected, actual))| {
+                assert_eq!(expected, actual, "mismatch at {i}");
+            });
+
+        result
+    }
+
+    #[cfg(feature = "gpu")]
+    fn evaluate_constraint_unfused(
+        &self,
+        composition_constraint: AlgebraicExpression<A::Fp, A::Fq>,
+        challenges: &Challenges<A::Fq>,
+        hints: &Hints<A::Fq>,
+        base_trace_lde: Matrix<A::Fp>,
+        extension_trace_lde: Option<Matrix<A::Fq>>,
+    ) -> Matrix<A::Fq> {
+        use crate::calculator::lde_calculator;
+        use crate::constraints::EvaluationLde;
+        let command_queue = &planner().command_queue;
+        let device = command_queue.device();
         let mut trace_ldes = Vec::new();
 
         for lde in base_trace_lde.0.into_iter() {
@@ -65,24 +195,13 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
             trace_ldes.push(Some(EvaluationLde::Fq(lde, gpu_buffer)));
         }
 
-        let result = lde_calculator(
+        lde_calculator(
             self.air,
             composition_constraint,
             &|i| FieldConstant::Fq(hints[i]),
             &|i| FieldConstant::Fq(challenges[i]),
             &mut |i| trace_ldes[i].take().unwrap(),
-        );
-
-        #[cfg(debug_assertions)]
-        expected_result.0[0]
-            .iter()
-            .zip(&result.0[0])
-            .enumerate()
-            .for_each(|(i, (expected, actual))| {
-                assert_eq!(expected, actual, "mismatch at {i}");
-            });
-
-        result
+        )
     }
 
     #[cfg(any(not(feature = "gpu"), debug_assertions))]
@@ -94,6 +213,8 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         base_trace_lde: &Matrix<A::Fp>,
         extension_trace_lde: Option<&Matrix<A::Fq>>,
     ) -> Matrix<A::Fq> {
+        use crate::matrix::GroupColumn;
+        use crate::matrix::MatrixGroup;
         let ce_domain = self.air.ce_domain();
         let step = self.air.ce_blowup_factor() as isize;
         let xs = ce_domain.elements();
@@ -101,9 +222,11 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         let mut result = Vec::with_capacity_in(n, PageAlignedAllocator);
         result.resize(n, A::Fq::zero());
 
-        let trace_info = self.air.trace_info();
-        let base_columns_range = trace_info.base_columns_range();
-        let extension_columns_range = trace_info.extension_columns_range();
+        let mut trace_lde = MatrixGroup::new();
+        trace_lde.append_fp(base_trace_lde);
+        if let Some(extension_trace_lde) = extension_trace_lde {
+            trace_lde.append_fq(extension_trace_lde);
+        }
 
         for (i, (v, x)) in result.iter_mut().zip(xs).enumerate() {
             let eval_result = composition_constraint.eval(
@@ -112,15 +235,10 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
                 &|c| FieldConstant::Fq(challenges[c]),
                 &|col_idx, offset| {
                     let position = (i as isize + step * offset).rem_euclid(n as isize) as usize;
-                    if base_columns_range.contains(&col_idx) {
-                        let column = &base_trace_lde[col_idx];
-                        FieldConstant::Fp(column[position])
-                    } else if extension_columns_range.contains(&col_idx) {
-                        let extension_column_offset = col_idx - trace_info.num_base_columns;
-                        let column = &extension_trace_lde.unwrap()[extension_column_offset];
-                        FieldConstant::Fq(column[position])
-                    } else {
-                        panic!("invalid column {col_idx}")
+                    match trace_lde.get_column(col_idx) {
+                        Some(GroupColumn::Fp(column)) => FieldConstant::Fp(column[position]),
+                        Some(GroupColumn::Fq(column)) => FieldConstant::Fq(column[position]),
+                        None => panic!("invalid column {col_idx}"),
                     }
                 },
             );
@@ -140,9 +258,9 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         hints: &Hints<A::Fq>,
         mut base_trace_lde: Matrix<A::Fp>,
         mut extension_trace_lde: Option<Matrix<A::Fq>>,
+        preprocessed_trace_lde: Option<&Matrix<A::Fp>>,
     ) -> Matrix<A::Fq> {
         use AlgebraicExpression::*;
-        let trace_degree = self.air.trace_len() - 1;
         let composition_degree = self.air.composition_degree();
 
         // only a subset of the execution trace LDE evaluations need to be used for
@@ -151,13 +269,17 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         // TODO: reduce_lde_blowup_factor should take in an lde and output the reduced
         // LDE along with the now unused LDEs space. This will prevent having to
         // allocate memory buffers during constraint evaluation.
-        let lde_blowup_factor = self.air.lde_blowup_factor();
+        // NOTE: LDEs may already be evaluated over the constraint evaluation domain
+        // e.g. when streaming the LDE commitment
+        let trace_len = self.air.trace_len();
         let ce_blowup_factor = self.air.ce_blowup_factor();
+        let lde_blowup_factor = base_trace_lde.num_rows() / trace_len;
         ark_std::cfg_iter_mut!(base_trace_lde).for_each(|column| {
             utils::reduce_lde_blowup_factor(column, lde_blowup_factor, ce_blowup_factor)
         });
 
         if let Some(extension_trace_lde) = &mut extension_trace_lde {
+            let lde_blowup_factor = extension_trace_lde.num_rows() / trace_len;
             ark_std::cfg_iter_mut!(extension_trace_lde).for_each(|column| {
                 utils::reduce_lde_blowup_factor(column, lde_blowup_factor, ce_blowup_factor)
             });
@@ -165,23 +287,115 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
 
         // Constraint composition as in:
         // https://medium.com/starkware/starkdex-deep-dive-the-stark-core-engine-497942d0f0ab
-        let composition_constraint = self
+        let constraint_degrees = self.air.constraint_degrees();
+        let periodic_columns = self.air.periodic_columns();
+        // the inverse of each distinct divisor is evaluated over the constraint
+        // evaluation domain once and loaded like a periodic column following
+        // the AIR's periodic columns. Evaluating a divisor's factors at every
+        // point instead costs `O(n^2)` for divisors of many rows e.g. ranges.
+        let mut divisors = Vec::<Divisor>::new();
+        let mut divisor_inverse = |divisor: Divisor| {
+            let i = match divisors.iter().position(|d| *d == divisor) {
+                Some(i) => i,
+                None => {
+                    divisors.push(divisor);
+                    divisors.len() - 1
+                }
+            };
+            Periodic(periodic_columns.len() + i)
+        };
+        let constraints = self
             .air
-            .constraints()
-            .iter()
-            .enumerate()
-            .map(|(i, constraint)| {
-                let (numerator_degree, denominator_degree) = constraint.degree(trace_degree);
-                let evaluation_degree = numerator_degree - denominator_degree;
+            .divided_constraints()
+            .into_iter()
+            .map(|(numerator, divisor)| (numerator, divisor.map(&mut divisor_inverse)))
+            .collect::<Vec<_>>();
+        let composition_coeffs = constraint_degrees.iter().zip(&self.composition_coeffs).map(
+            |(&evaluation_degree, &(alpha, beta))| {
                 assert!(evaluation_degree <= composition_degree);
                 let degree_adjustment = composition_degree - evaluation_degree;
-                let (alpha, beta) = self.composition_coeffs[i];
                 // TODO: would be nice to use Fp is Fq and Fp are the same
-                constraint
-                    * (X.pow(degree_adjustment) * FieldConstant::Fq(alpha)
-                        + FieldConstant::Fq(beta))
+                X.pow(degree_adjustment) * FieldConstant::Fq(alpha) + FieldConstant::Fq(beta)
+            },
+        );
+        let composition_constraint = if self.air.options().batch_constraints {
+            batch_by_divisor(constraints, composition_coeffs)
+        } else {
+            constraints
+                .into_iter()
+                .zip(composition_coeffs)
+                .map(|((numerator, divisor), coeff)| match divisor {
+                    Some(divisor) => numerator * coeff * divisor,
+                    None => numerator * coeff,
+                })
+                .sum::<AlgebraicExpression<A::Fp, A::Fq>>()
+        };
+
+        // preprocessed columns are evaluated as base trace columns that follow
+        // the execution trace's base columns
+        let composition_constraint = match preprocessed_trace_lde {
+            None => composition_constraint,
+            Some(preprocessed_trace_lde) => {
+                let trace_info = self.air.trace_info();
+                let extension_columns_range = trace_info.extension_columns_range();
+                let preprocessed_columns_range = trace_info.preprocessed_columns_range();
+                let num_extension_columns = trace_info.num_extension_columns;
+                let num_preprocessed_columns = preprocessed_trace_lde.num_cols();
+                assert_eq!(
+                    trace_info.num_preprocessed_columns,
+                    num_preprocessed_columns
+                );
+                // the preprocessed trace is shared by every proof so only the
+                // evaluations over the constraint evaluation domain are copied
+                let lde_blowup_factor = preprocessed_trace_lde.num_rows() / trace_len;
+                let step = lde_blowup_factor / ce_blowup_factor;
+                for column in &preprocessed_trace_lde.0 {
+                    let mut ce_column =
+                        Vec::with_capacity_in(column.len() / step, PageAlignedAllocator);
+                    ce_column.extend(column.iter().step_by(step).copied());
+                    base_trace_lde.0.push(ce_column);
+                }
+                composition_constraint.map(&mut |node| match node {
+                    Trace(i, j) if extension_columns_range.contains(&i) => {
+                        Trace(i + num_preprocessed_columns, j)
+                    }
+                    Trace(i, j) if preprocessed_columns_range.contains(&i) => {
+                        Trace(i - num_extension_columns, j)
+                    }
+                    other => other,
+                })
+            }
+        };
+
+        // periodic columns (and divisor inverses) are evaluated as base trace
+        // columns that follow the execution trace's base (and preprocessed)
+        // columns
+        let composition_constraint = if periodic_columns.is_empty() && divisors.is_empty() {
+            composition_constraint
+        } else {
+            let num_base_columns = base_trace_lde.num_cols();
+            let num_periodic_columns = periodic_columns.len() + divisors.len();
+            let trace_domain = self.air.trace_domain();
+            let ce_domain = self.air.ce_domain();
+            for column in &periodic_columns {
+                base_trace_lde.0.push(column.evaluate(trace_len, ce_domain));
+            }
+            for divisor in &divisors {
+                let mut evals = divisor.evaluate(trace_domain, ce_domain);
+                batch_inversion(&mut evals);
+                base_trace_lde.0.push(evals.to_vec_in(PageAlignedAllocator));
+            }
+            composition_constraint.map(&mut |node| match node {
+                Trace(i, j) if i >= num_base_columns => Trace(i + num_periodic_columns, j),
+                Periodic(i) => Trace(num_base_columns + i, 0),
+                other => other,
             })
-            .sum::<AlgebraicExpression<A::Fp, A::Fq>>();
+        };
+
+        // challenges are the same at every point so are folded up front
+        let hint = |i: usize| FieldConstant::Fq(hints[i]);
+        let challenge = |i: usize| FieldConstant::Fq(challenges[i]);
+        let composition_constraint = composition_constraint.evaluate_challenges(&hint, &challenge);
 
         #[cfg(feature = "gpu")]
         return self.evaluate_constraint_gpu(
@@ -201,6 +415,10 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         );
     }
 
+    /// Splits the composition polynomial into `ce_blowup_factor` columns of
+    /// degree less than the trace length. Coefficients are interleaved so
+    /// `C(x) = C_0(x^k) + x*C_1(x^k) + ... + x^(k-1)*C_(k-1)(x^k)` which the
+    /// verifier recombines from evaluations of the columns at `z^k`.
     fn trace_polys(&self, composed_evaluations: Matrix<A::Fq>) -> Matrix<A::Fq> {
         assert_eq!(composed_evaluations.num_cols(), 1);
         let composition_poly = composed_evaluations.into_polynomials(self.air.ce_domain());
@@ -219,20 +437,33 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
     }
 
     /// builds a commitment to the composed trace polynomial.
-    /// Output is of the form `(lde, poly, lde_merkle_tree)`
+    /// Output is of the form `(lde, poly, lde_row_hashes)`
     pub fn build_commitment(
         mut self,
         challenges: &Challenges<A::Fq>,
         hints: &Hints<A::Fq>,
         base_trace_lde: Matrix<A::Fp>,
         extension_trace_lde: Option<Matrix<A::Fq>>,
-    ) -> (Matrix<A::Fq>, Matrix<A::Fq>, MerkleTree<Sha256>) {
-        let composed_evaluations =
-            self.evaluate(challenges, hints, base_trace_lde, extension_trace_lde);
-        let composition_trace_polys = self.trace_polys(composed_evaluations);
+        preprocessed_trace_lde: Option<&Matrix<A::Fp>>,
+    ) -> CompositionCommitment<A> {
+        let composed_evaluations = self.evaluate(
+            challenges,
+            hints,
+            base_trace_lde,
+            extension_trace_lde,
+            preprocessed_trace_lde,
+        );
+        let mut composition_trace_polys = self.trace_polys(composed_evaluations);
+        if let Some(mask) = self.mask.take() {
+            composition_trace_polys.0.push(mask);
+        }
+        assert_eq!(
+            self.air.num_composition_trace_columns(),
+            composition_trace_polys.num_cols()
+        );
         let composition_trace_lde = composition_trace_polys.evaluate(self.air.lde_domain());
-        let merkle_tree = composition_trace_lde.commit_to_rows();
-        (composition_trace_lde, composition_trace_polys, merkle_tree)
+        let row_hashes = composition_trace_lde.hash_rows::<A::Digest, A::LeafEncoder>();
+        (composition_trace_lde, composition_trace_polys, row_hashes)
     }
 }
 
@@ -241,7 +472,12 @@ pub struct DeepPolyComposer<'a, A: Air> {
     air: &'a A,
     base_trace_polys: &'a Matrix<A::Fp>,
     extension_trace_polys: Option<&'a Matrix<A::Fq>>,
-    composition_trace_polys: Matrix<A::Fq>,
+    preprocessed_trace_polys: Option<&'a Matrix<A::Fp>>,
+    preprocessed_trace_lde: Option<&'a Matrix<A::Fp>>,
+    composition_trace_polys: &'a Matrix<A::Fq>,
+    composition_trace_lde: Option<&'a Matrix<A::Fq>>,
+    execution_trace_ood_evals: Vec<A::Fq>,
+    composition_trace_ood_evals: Vec<A::Fq>,
 }
 
 impl<'a, A: Air> DeepPolyComposer<'a, A> {
@@ -250,17 +486,36 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         z: A::Fq,
         base_trace_polys: &'a Matrix<A::Fp>,
         extension_trace_polys: Option<&'a Matrix<A::Fq>>,
-        composition_trace_polys: Matrix<A::Fq>,
+        preprocessed_trace_polys: Option<&'a Matrix<A::Fp>>,
+        composition_trace_polys: &'a Matrix<A::Fq>,
     ) -> Self {
         DeepPolyComposer {
             z,
             air,
             base_trace_polys,
             extension_trace_polys,
+            preprocessed_trace_polys,
+            preprocessed_trace_lde: None,
             composition_trace_polys,
+            composition_trace_lde: None,
+            execution_trace_ood_evals: Vec::new(),
+            composition_trace_ood_evals: Vec::new(),
         }
     }
 
+    /// Reuses existing evaluations of the preprocessed and composition trace
+    /// over the LDE domain in [DeepPolyComposer::into_deep_lde] instead of
+    /// evaluating their polynomials again
+    pub fn with_ldes(
+        mut self,
+        preprocessed_trace_lde: Option<&'a Matrix<A::Fp>>,
+        composition_trace_lde: Option<&'a Matrix<A::Fq>>,
+    ) -> Self {
+        self.preprocessed_trace_lde = preprocessed_trace_lde;
+        self.composition_trace_lde = composition_trace_lde;
+        self
+    }
+
     /// Output is of the form `(execution_trace_evals, composition_trace_evals)`
     pub fn get_ood_evals(&mut self) -> (Vec<A::Fq>, Vec<A::Fq>) {
         let Self {
@@ -268,6 +523,7 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
             air,
             base_trace_polys,
             extension_trace_polys,
+            preprocessed_trace_polys,
             composition_trace_polys,
             ..
         } = self;
@@ -280,7 +536,8 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         let trace_info = air.trace_info();
         let base_columns_range = trace_info.base_columns_range();
         let extension_columns_range = trace_info.extension_columns_range();
-        let execution_trace_evals = ark_std::cfg_into_iter!(air.trace_arguments())
+        let preprocessed_columns_range = trace_info.preprocessed_columns_range();
+        let execution_trace_evals: Vec<A::Fq> = ark_std::cfg_into_iter!(air.trace_arguments())
             .map(|(column, offset)| {
                 let x = *z * if offset >= 0 { g } else { g_inv }.pow([offset.abs() as u64]);
                 if base_columns_range.contains(&column) {
@@ -290,30 +547,263 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
                     let coeffs =
                         &extension_trace_polys.unwrap()[column - trace_info.num_base_columns];
                     horner_evaluate(coeffs, &x)
+                } else if preprocessed_columns_range.contains(&column) {
+                    let coeffs = &preprocessed_trace_polys.unwrap()
+                        [column - preprocessed_columns_range.start];
+                    horner_evaluate(coeffs, &x)
                 } else {
                     panic!(
                         "column is {column} but there are only {} columns",
-                        trace_info.num_base_columns + trace_info.num_extension_columns
+                        trace_info.num_columns()
                     )
                 }
             })
             .collect();
 
         // generate ood evaluations for the composition trace polynomials
-        let z_n = self.z.pow([composition_trace_polys.num_cols() as u64]);
-        let composition_trace_evals = ark_std::cfg_iter!(composition_trace_polys)
+        let z_n = self.z.pow([air.ce_blowup_factor() as u64]);
+        let composition_trace_evals: Vec<A::Fq> = ark_std::cfg_iter!(composition_trace_polys)
             .map(|column| horner_evaluate(column, &z_n))
             .collect();
 
+        self.execution_trace_ood_evals = execution_trace_evals.clone();
+        self.composition_trace_ood_evals = composition_trace_evals.clone();
         (execution_trace_evals, composition_trace_evals)
     }
 
-    pub fn into_deep_poly(self, composition_coeffs: DeepCompositionCoeffs<A::Fq>) -> Matrix<A::Fq> {
+    /// Evaluations of the DEEP composition polynomial over `lde_domain`. With
+    /// the `gpu` feature the quotients are accumulated on the GPU in evaluation
+    /// form so the trace polynomials don't go through synthetic division on
+    /// the host.
+    /// Must be called after [DeepPolyComposer::get_ood_evals].
+    pub fn into_deep_lde(
+        self,
+        composition_coeffs: DeepCompositionCoeffs<A::Fq>,
+        lde_domain: Radix2EvaluationDomain<A::Fp>,
+        buffer_pool: &BufferPool<A::Fq>,
+    ) -> GpuVec<A::Fq> {
+        assert!(
+            !self.execution_trace_ood_evals.is_empty()
+                && self.composition_trace_ood_evals.len()
+                    == self.composition_trace_polys.num_cols(),
+            "out-of-domain evaluations are missing"
+        );
+        #[cfg(feature = "gpu")]
+        if lde_domain.size() >= GpuFft::<A::Fq>::MIN_SIZE {
+            return self.into_deep_lde_gpu(composition_coeffs, lde_domain);
+        }
+
+        let mut deep_lde = self.deep_poly_coeffs(composition_coeffs, buffer_pool);
+        deep_lde.resize(lde_domain.size(), A::Fq::zero());
+        lde_domain.evaluate_columns(core::slice::from_mut(&mut deep_lde));
+        deep_lde
+    }
+
+    /// Computes `Σ alpha_i * (T_i(x) - T_i(z_i)) / (x - z_i)` for every `x` of
+    /// `lde_domain` with the `DeepQuotient` kernel. Columns opened at the same
+    /// point are encoded into one command buffer. Metal has no extension field
+    /// inversion so the denominators of the next point are inverted on the
+    /// host while the GPU runs. The degree adjustment is folded into the
+    /// inverted denominators.
+    #[cfg(feature = "gpu")]
+    fn into_deep_lde_gpu(
+        self,
+        composition_coeffs: DeepCompositionCoeffs<A::Fq>,
+        lde_domain: Radix2EvaluationDomain<A::Fp>,
+    ) -> GpuVec<A::Fq> {
+        use gpu_poly::stage::DeepQuotientStage;
         let Self {
             z,
             air,
             base_trace_polys,
             extension_trace_polys,
+            preprocessed_trace_polys,
+            preprocessed_trace_lde,
+            composition_trace_polys,
+            composition_trace_lde,
+            execution_trace_ood_evals,
+            composition_trace_ood_evals,
+        } = self;
+
+        let DeepCompositionCoeffs {
+            execution_trace: execution_trace_alphas,
+            composition_trace: composition_trace_alphas,
+            degree: (degree_alpha, degree_beta),
+        } = composition_coeffs;
+
+        let n = lde_domain.size();
+        let xs = lde_domain.elements().collect::<Vec<A::Fp>>();
+        // (degree_alpha + degree_beta * x) / (x - point) for each x
+        let invert_denominators = |point: A::Fq| {
+            let mut denominators = Vec::with_capacity_in(n, PageAlignedAllocator);
+            denominators.extend(xs.iter().map(|&x| A::Fq::from(x) - point));
+            batch_inversion(&mut denominators);
+            ark_std::cfg_iter_mut!(denominators)
+                .zip(ark_std::cfg_iter!(xs))
+                .for_each(|(denominator_inv, &x)| {
+                    *denominator_inv *= degree_alpha + degree_beta * x;
+                });
+            denominators
+        };
+
+        // execution trace columns are opened at z * g^offset and composition
+        // trace columns at z^ce_blowup_factor
+        let mut arguments_by_offset = BTreeMap::<isize, Vec<_>>::new();
+        let mut last_offsets = BTreeMap::new();
+        for (((column, offset), alpha), ood_eval) in air
+            .trace_arguments()
+            .into_iter()
+            .zip(execution_trace_alphas)
+            .zip(execution_trace_ood_evals)
+        {
+            // arguments are ordered by column then offset
+            last_offsets.insert(column, offset);
+            arguments_by_offset
+                .entry(offset)
+                .or_default()
+                .push((column, alpha, ood_eval));
+        }
+        let trace_domain = air.trace_domain();
+        let g = trace_domain.group_gen();
+        let g_inv = trace_domain.group_gen_inv();
+        let mut points = arguments_by_offset
+            .keys()
+            .map(|&offset| {
+                z * if offset >= 0 { g } else { g_inv }.pow([offset.unsigned_abs() as u64])
+            })
+            .chain([z.pow([air.ce_blowup_factor() as u64])])
+            .collect::<Vec<A::Fq>>()
+            .into_iter();
+        let mut arguments_by_offset = arguments_by_offset.into_iter();
+
+        let library = &planner().library;
+        let command_queue = &planner().command_queue;
+        let device = command_queue.device();
+        let base_stage = DeepQuotientStage::<A::Fq, A::Fp>::new(library, n);
+        let extension_stage = DeepQuotientStage::<A::Fq, A::Fq>::new(library, n);
+        let mut deep_lde = Vec::with_capacity_in(n, PageAlignedAllocator);
+        deep_lde.resize(n, A::Fq::zero());
+        let mut deep_lde_buffer = buffer_mut_no_copy(device, &mut deep_lde);
+
+        let trace_info = air.trace_info();
+        let base_columns_range = trace_info.base_columns_range();
+        let extension_columns_range = trace_info.extension_columns_range();
+        let preprocessed_columns_range = trace_info.preprocessed_columns_range();
+        // LDEs of columns opened at several offsets are kept until their last
+        let mut base_ldes = BTreeMap::new();
+        let mut extension_ldes = BTreeMap::new();
+        let mut next_denominator_invs = points.next().map(&invert_denominators);
+        while let Some(denominator_invs) = next_denominator_invs {
+            let denominator_invs_buffer = buffer_no_copy(device, &denominator_invs);
+            let command_buffer = command_queue.new_command_buffer();
+            let arguments = arguments_by_offset.next();
+            let mut composition_ldes = Vec::new();
+            match &arguments {
+                Some((_, arguments)) => {
+                    for &(column, alpha, ood_eval) in arguments {
+                        if extension_columns_range.contains(&column) {
+                            let evals = extension_ldes.entry(column).or_insert_with(|| {
+                                let coeffs = &extension_trace_polys.unwrap()
+                                    [column - trace_info.num_base_columns];
+                                evaluate_column(coeffs, lde_domain)
+                            });
+                            extension_stage.encode(
+                                command_buffer,
+                                &mut deep_lde_buffer,
+                                &buffer_no_copy(device, evals),
+                                &denominator_invs_buffer,
+                                alpha,
+                                alpha * ood_eval,
+                            );
+                        } else if preprocessed_columns_range.contains(&column)
+                            && preprocessed_trace_lde.is_some()
+                        {
+                            let evals = &preprocessed_trace_lde.unwrap()
+                                [column - preprocessed_columns_range.start];
+                            base_stage.encode(
+                                command_buffer,
+                                &mut deep_lde_buffer,
+                                &buffer_no_copy(device, evals),
+                                &denominator_invs_buffer,
+                                alpha,
+                                alpha * ood_eval,
+                            );
+                        } else {
+                            let evals = base_ldes.entry(column).or_insert_with(|| {
+                                let coeffs = if base_columns_range.contains(&column) {
+                                    &base_trace_polys[column]
+                                } else if preprocessed_columns_range.contains(&column) {
+                                    &preprocessed_trace_polys.unwrap()
+                                        [column - preprocessed_columns_range.start]
+                                } else {
+                                    panic!(
+                                        "column is {column} but there are only {} columns",
+                                        trace_info.num_columns()
+                                    )
+                                };
+                                evaluate_column(coeffs, lde_domain)
+                            });
+                            base_stage.encode(
+                                command_buffer,
+                                &mut deep_lde_buffer,
+                                &buffer_no_copy(device, evals),
+                                &denominator_invs_buffer,
+                                alpha,
+                                alpha * ood_eval,
+                            );
+                        }
+                    }
+                }
+                None => {
+                    for (i, (&alpha, &ood_eval)) in composition_trace_alphas
+                        .iter()
+                        .zip(&composition_trace_ood_evals)
+                        .enumerate()
+                    {
+                        let evals = match composition_trace_lde {
+                            Some(composition_trace_lde) => &composition_trace_lde[i],
+                            None => {
+                                let coeffs = &composition_trace_polys[i];
+                                composition_ldes.push(evaluate_column(coeffs, lde_domain));
+                                composition_ldes.last().unwrap()
+                            }
+                        };
+                        extension_stage.encode(
+                            command_buffer,
+                            &mut deep_lde_buffer,
+                            &buffer_no_copy(device, evals),
+                            &denominator_invs_buffer,
+                            alpha,
+                            alpha * ood_eval,
+                        );
+                    }
+                }
+            }
+            command_buffer.commit();
+            // overlaps with the GPU
+            next_denominator_invs = points.next().map(&invert_denominators);
+            wait_for_gpu(|| command_buffer.wait_until_completed());
+            if let Some((offset, _)) = arguments {
+                base_ldes.retain(|column, _| last_offsets[column] != offset);
+                extension_ldes.retain(|column, _| last_offsets[column] != offset);
+            }
+        }
+
+        deep_lde
+    }
+
+    /// Coefficients of the DEEP composition polynomial
+    fn deep_poly_coeffs(
+        self,
+        composition_coeffs: DeepCompositionCoeffs<A::Fq>,
+        buffer_pool: &BufferPool<A::Fq>,
+    ) -> GpuVec<A::Fq> {
+        let Self {
+            z,
+            air,
+            base_trace_polys,
+            extension_trace_polys,
+            preprocessed_trace_polys,
             composition_trace_polys,
             ..
         } = self;
@@ -329,14 +819,18 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         let g_inv = trace_domain.group_gen_inv();
 
         // divide out OOD point from composition trace polys
-        let z_n = self.z.pow([composition_trace_polys.num_cols() as u64]);
+        let z_n = self.z.pow([air.ce_blowup_factor() as u64]);
+        // buffers are taken from the pool upfront since the pool can't be shared
+        // between threads
+        let composition_trace_buffers = (0..composition_trace_polys.num_cols())
+            .map(|_| buffer_pool.get_filled(trace_domain.size(), A::Fq::zero()))
+            .collect::<Vec<_>>();
         let composition_trace_quotients = Matrix::new(
-            ark_std::cfg_into_iter!(composition_trace_polys.0)
+            ark_std::cfg_iter!(composition_trace_polys.0)
                 .zip(composition_trace_alphas)
-                .map(|(coeffs, alpha)| {
-                    let mut res = Vec::new_in(PageAlignedAllocator);
-                    res.resize(trace_domain.size(), A::Fq::zero());
-                    divide_out_point_into(&mut res, &coeffs, &z_n, &alpha);
+                .zip(composition_trace_buffers)
+                .map(|((coeffs, alpha), mut res)| {
+                    divide_out_point_into(&mut res, coeffs, &z_n, &alpha);
                     res
                 })
                 .collect(),
@@ -346,16 +840,19 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         let trace_info = air.trace_info();
         let base_columns_range = trace_info.base_columns_range();
         let extension_columns_range = trace_info.extension_columns_range();
+        let preprocessed_columns_range = trace_info.preprocessed_columns_range();
         // NOTE: ark_std::cfg_into_iter! doesn't work with
         // .zip() on BTreeSet but works with Vec.
         #[allow(clippy::needless_collect)]
         let trace_arguments = air.trace_arguments().into_iter().collect::<Vec<_>>();
+        let execution_trace_buffers = (0..trace_arguments.len())
+            .map(|_| buffer_pool.get_filled(trace_domain.size(), A::Fq::zero()))
+            .collect::<Vec<_>>();
         let execution_trace_quotients = Matrix::new(
             ark_std::cfg_into_iter!(trace_arguments)
                 .zip(execution_trace_alphas)
-                .map(|((col, offset), alpha)| {
-                    let mut res = Vec::new_in(PageAlignedAllocator);
-                    res.resize(trace_domain.size(), A::Fq::zero());
+                .zip(execution_trace_buffers)
+                .map(|(((col, offset), alpha), mut res)| {
                     let x = z * if offset >= 0 { g } else { g_inv }.pow([offset.abs() as u64]);
                     if base_columns_range.contains(&col) {
                         let coeffs = &base_trace_polys[col];
@@ -364,10 +861,14 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
                         let coeffs =
                             &extension_trace_polys.unwrap()[col - trace_info.num_base_columns];
                         divide_out_point_into(&mut res, coeffs, &x, &alpha);
+                    } else if preprocessed_columns_range.contains(&col) {
+                        let coeffs = &preprocessed_trace_polys.unwrap()
+                            [col - preprocessed_columns_range.start];
+                        divide_out_point_into(&mut res, coeffs, &x, &alpha);
                     } else {
                         panic!(
                             "column is {col} but there are only {} columns",
-                            trace_info.num_base_columns + trace_info.num_extension_columns
+                            trace_info.num_columns()
                         )
                     }
                     res
@@ -376,7 +877,8 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         );
 
         let quotients = Matrix::join(vec![execution_trace_quotients, composition_trace_quotients]);
-        let mut combined_coeffs = GpuVec::try_from(quotients.sum_columns()).unwrap();
+        let mut combined_coeffs = GpuVec::try_from(quotients.sum_columns_in(buffer_pool)).unwrap();
+        quotients.recycle(buffer_pool);
 
         // Adjust the degree
         // P(x) * (alpha + x * beta)
@@ -388,7 +890,7 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
             last = tmp;
         }
 
-        Matrix::new(vec![combined_coeffs])
+        combined_coeffs
     }
 }
 
@@ -400,3 +902,17 @@ pub struct DeepCompositionCoeffs<F> {
     /// Degree adjustment coefficients
     pub degree: (F, F),
 }
+
+/// Evaluates a column of coefficients over `domain`
+#[cfg(feature = "gpu")]
+fn evaluate_column<F>(coeffs: &[F], domain: Radix2EvaluationDomain<F::FftField>) -> GpuVec<F>
+where
+    F: GpuField + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    let mut evals = Vec::with_capacity_in(domain.size(), PageAlignedAllocator);
+    evals.extend_from_slice(coeffs);
+    evals.resize(domain.size(), F::zero());
+    domain.evaluate_columns(core::slice::from_mut(&mut evals));
+    evals
+}
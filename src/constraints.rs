@@ -1,12 +1,17 @@
 // Implementation is adapted from RationalExpression in https://github.com/0xProject/OpenZKP
 
+use crate::divisor::Divisor;
 use crate::StarkExtensionOf;
 use alloc::collections::BTreeMap;
 use alloc::collections::BTreeSet;
 use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
 use ark_std::Zero;
 use core::cell::RefCell;
 use core::fmt::Display;
@@ -71,6 +76,24 @@ impl VerifierChallenge for usize {
     }
 }
 
+/// An interface for types that can symbolically represent one of the columns
+/// returned by `Air::periodic_columns`
+pub trait Periodic {
+    fn index(&self) -> usize;
+
+    fn periodic<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+    ) -> AlgebraicExpression<Fp, Fq> {
+        AlgebraicExpression::Periodic(self.index())
+    }
+}
+
+impl Periodic for usize {
+    fn index(&self) -> usize {
+        *self
+    }
+}
+
 /// An interface for types that can symbolically represent a column of an
 /// execution trace
 pub trait ExecutionTraceColumn {
@@ -105,6 +128,104 @@ impl ExecutionTraceColumn for usize {
     }
 }
 
+/// Constraint between rows of the execution trace that must hold on every row
+/// apart from the last `num_exemptions` rows and the first
+/// `num_leading_exemptions` rows. Rows near the end (or start) of the trace
+/// are exempt since the rows they reference wrap around to the start (or end).
+#[derive(Clone, Debug)]
+pub struct TransitionConstraint<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp> = Fp> {
+    pub expression: AlgebraicExpression<Fp, Fq>,
+    pub num_exemptions: usize,
+    /// Number of rows the constraint looks back i.e. the largest negative row
+    /// offset in the constraint
+    pub num_leading_exemptions: usize,
+    /// Overrides the rows the constraint applies to. See [Self::with_divisor].
+    pub divisor: Option<Divisor>,
+}
+
+impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> TransitionConstraint<Fp, Fq> {
+    /// Exempts as many rows as the largest row offset in `expression` (at
+    /// least one) so constraints that look more than one row ahead are sound.
+    /// Constraints that reference previous rows also exempt the first rows.
+    pub fn new(expression: AlgebraicExpression<Fp, Fq>) -> Self {
+        let num_exemptions = Self::lookahead(&expression).max(1);
+        let num_leading_exemptions = Self::lookback(&expression);
+        TransitionConstraint {
+            expression,
+            num_exemptions,
+            num_leading_exemptions,
+            divisor: None,
+        }
+    }
+
+    /// Exempts the last `num_exemptions` rows. Must be at least the largest
+    /// row offset in the constraint.
+    pub fn with_exemptions(mut self, num_exemptions: usize) -> Self {
+        let lookahead = Self::lookahead(&self.expression);
+        assert!(
+            num_exemptions >= lookahead,
+            "constraint references {lookahead} rows ahead but only exempts {num_exemptions} rows"
+        );
+        self.num_exemptions = num_exemptions;
+        self
+    }
+
+    /// Applies the constraint to the rows `divisor` vanishes on rather than
+    /// all rows apart from the exempt rows. The divisor must not vanish on
+    /// rows where the constraint references rows that wrap around.
+    pub fn with_divisor(mut self, divisor: Divisor) -> Self {
+        self.divisor = Some(divisor);
+        self
+    }
+
+    fn lookahead(expression: &AlgebraicExpression<Fp, Fq>) -> usize {
+        let mut lookahead = 0;
+        expression.traverse(&mut |node| {
+            if let &AlgebraicExpression::Trace(_, offset) = node && offset > 0 {
+                lookahead = lookahead.max(offset as usize);
+            }
+        });
+        lookahead
+    }
+
+    fn lookback(expression: &AlgebraicExpression<Fp, Fq>) -> usize {
+        let mut lookback = 0;
+        expression.traverse(&mut |node| {
+            if let &AlgebraicExpression::Trace(_, offset) = node && offset < 0 {
+                lookback = lookback.max(offset.unsigned_abs());
+            }
+        });
+        lookback
+    }
+
+    /// Vanishing polynomial of all rows of a trace with `trace_len` rows other
+    /// than the exempt rows (or the custom divisor)
+    pub fn divisor(&self, trace_len: usize) -> Divisor {
+        match &self.divisor {
+            Some(divisor) => divisor.clone(),
+            None => {
+                assert!(
+                    self.num_exemptions + self.num_leading_exemptions < trace_len,
+                    "all rows are exempt"
+                );
+                (0..self.num_leading_exemptions).fold(
+                    Divisor::transition(trace_len, self.num_exemptions),
+                    Divisor::with_exemption,
+                )
+            }
+        }
+    }
+
+    /// Divides the constraint by its divisor for a trace over `trace_domain`
+    pub fn into_constraint(
+        &self,
+        trace_domain: Radix2EvaluationDomain<Fp>,
+    ) -> AlgebraicExpression<Fp, Fq> {
+        self.divisor(trace_domain.size())
+            .divide(self.expression.clone(), trace_domain)
+    }
+}
+
 macro_rules! map {
     ($self:expr, $f1:ident $(, $x:expr)*) => {
         match $self {
@@ -141,6 +262,21 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> FieldConstant<Fp, Fq>
             FieldConstant::Fq(v) => *v,
         }
     }
+
+    /// Canonical S-expression e.g. `(fp 7)` or `(fq 1 2 3)` where extension
+    /// field elements are written as their base field coefficients
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            FieldConstant::Fp(v) => format!("(fp {v})"),
+            FieldConstant::Fq(v) => {
+                let coeffs = v
+                    .to_base_prime_field_elements()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>();
+                format!("(fq {})", coeffs.join(" "))
+            }
+        }
+    }
 }
 
 impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Display for FieldConstant<Fp, Fq> {
@@ -233,6 +369,8 @@ pub enum AlgebraicExpression<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp
     Challenge(usize),
     Hint(usize),
     Trace(/* =column */ usize, /* =offset */ isize),
+    /// Periodic column. Substituted with its values before evaluation
+    Periodic(usize),
     #[cfg(feature = "gpu")]
     Lde(Rc<EvaluationLde<Fp, Fq>>, /* =offset */ isize),
     Add(
@@ -252,35 +390,72 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
         Self::Exp(Rc::new(RefCell::new(self.clone())), exp.try_into().unwrap())
     }
 
+    /// Canonical S-expression of the expression for exporting the constraint
+    /// system (see `Air::constraint_system`). Unlike [Display] every node is
+    /// written explicitly e.g. `(+ (trace 0 1) (- (trace 0 0)))` so other
+    /// implementations can parse it without knowing operator precedence.
+    pub fn to_sexpr(&self) -> String {
+        use AlgebraicExpression::*;
+        match self {
+            X => "x".to_string(),
+            Constant(c) => c.to_sexpr(),
+            Challenge(i) => format!("(challenge {i})"),
+            Hint(i) => format!("(hint {i})"),
+            Trace(i, j) => format!("(trace {i} {j})"),
+            Periodic(i) => format!("(periodic {i})"),
+            Add(a, b) => format!("(+ {} {})", a.borrow().to_sexpr(), b.borrow().to_sexpr()),
+            Neg(a) => format!("(- {})", a.borrow().to_sexpr()),
+            Mul(a, b) => format!("(* {} {})", a.borrow().to_sexpr(), b.borrow().to_sexpr()),
+            Exp(a, e) => format!("(^ {} {e})", a.borrow().to_sexpr()),
+            #[cfg(feature = "gpu")]
+            Lde(..) => unreachable!(),
+        }
+    }
+
     /// Calculates an upper bound on the degree in X.
     /// Output is of the form `(numerator_degree, denominator_degree)`
     pub fn degree(&self, trace_degree: usize) -> (usize, usize) {
-        self.degree_impl(1, trace_degree)
+        use AlgebraicExpression::*;
+        self.degree_impl(&|node| match node {
+            X => 1,
+            // a periodic column has degree `trace_len - trace_len/period`
+            Trace(..) | Periodic(_) => trace_degree,
+            _ => 0,
+        })
+    }
+
+    /// Degree of the expression as a rational function of the verifier
+    /// challenges in the form `(numerator_degree, denominator_degree)`.
+    /// Challenges can appear with powers and products e.g. `alpha^3 * beta`
+    /// (as in batched lookups) and the numerator degree bounds the soundness
+    /// error of the expression vanishing at a random challenge.
+    pub fn challenge_degree(&self) -> (usize, usize) {
+        self.degree_impl(&|node| usize::from(matches!(node, AlgebraicExpression::Challenge(_))))
     }
 
     // Copied from https://github.com/0xProject/OpenZKP
-    fn degree_impl(&self, x_degree: usize, trace_degree: usize) -> (usize, usize) {
+    fn degree_impl(&self, leaf_degree: &impl Fn(&Self) -> usize) -> (usize, usize) {
         use AlgebraicExpression::*;
         match self {
-            X => (x_degree, 0),
-            Hint(_) | Challenge(_) | Constant(_) => (0, 0),
-            Trace(..) => (trace_degree, 0),
+            X | Hint(_) | Challenge(_) | Constant(_) | Trace(..) | Periodic(_) => {
+                (leaf_degree(self), 0)
+            }
             Add(a, b) => {
-                let (a_numerator, a_denominator) = a.borrow().degree_impl(x_degree, trace_degree);
-                let (b_numerator, b_denominator) = b.borrow().degree_impl(x_degree, trace_degree);
+                let (a_numerator, a_denominator) = a.borrow().degree_impl(leaf_degree);
+                let (b_numerator, b_denominator) = b.borrow().degree_impl(leaf_degree);
                 (
                     core::cmp::max(a_numerator + b_denominator, b_numerator + a_denominator),
                     a_denominator + b_denominator,
                 )
             }
-            Neg(a) => a.borrow().degree_impl(x_degree, trace_degree),
+            Neg(a) => a.borrow().degree_impl(leaf_degree),
             Mul(a, b) => {
-                let (an, ad) = a.borrow().degree_impl(x_degree, trace_degree);
-                let (bn, bd) = b.borrow().degree_impl(x_degree, trace_degree);
+                let (an, ad) = a.borrow().degree_impl(leaf_degree);
+                let (bn, bd) = b.borrow().degree_impl(leaf_degree);
                 (an + bn, ad + bd)
             }
             Exp(a, e) => {
-                let (n, d) = a.borrow().degree_impl(x_degree, trace_degree);
+                let (n, d) = a.borrow().degree_impl(leaf_degree);
                 if *e >= 0 {
                     (*e as usize * n, *e as usize * d)
                 } else {
@@ -295,6 +470,48 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
         }
     }
 
+    /// Substitutes hints and challenges with their values and folds the
+    /// constant subexpressions they appear in i.e. powers, products and
+    /// inverses of challenges are computed once rather than at every point
+    /// the expression is evaluated at.
+    pub fn evaluate_challenges(
+        &self,
+        hint: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        challenge: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+    ) -> Self {
+        use AlgebraicExpression::*;
+        let constant = |node: &Rc<RefCell<Self>>| match *node.borrow() {
+            Constant(c) => Some(c),
+            _ => None,
+        };
+        self.map(&mut |node| match node {
+            Hint(i) => Constant(hint(i)),
+            Challenge(i) => Constant(challenge(i)),
+            Add(a, b) => match (constant(&a), constant(&b)) {
+                (Some(a), Some(b)) => Constant(a + b),
+                _ => Add(a, b),
+            },
+            Mul(a, b) => match (constant(&a), constant(&b)) {
+                (Some(a), Some(b)) => Constant(a * b),
+                _ => Mul(a, b),
+            },
+            Neg(a) => match constant(&a) {
+                Some(a) => Constant(-a),
+                None => Neg(a),
+            },
+            Exp(_, 0) => Constant(FieldConstant::Fp(Fp::one())),
+            Exp(a, e) => match constant(&a) {
+                Some(a) if e > 0 => Constant(a.pow([e as u64])),
+                Some(a) => {
+                    let inverse = a.inverse().expect("division by zero");
+                    Constant(inverse.pow([e.unsigned_abs() as u64]))
+                }
+                None => Exp(a, e),
+            },
+            other => other,
+        })
+    }
+
     // Copied from https://github.com/0xProject/OpenZKP
     /// Applies a mapped bottom-up traversal.
     /// The function applies to each node after application to its descendants
@@ -357,6 +574,63 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
         f(self)
     }
 
+    /// Replaces each periodic column `i` with `f(i)`
+    pub fn substitute_periodic(&self, f: &mut impl FnMut(usize) -> Self) -> Self {
+        self.map(&mut |node| match node {
+            AlgebraicExpression::Periodic(i) => f(i),
+            other => other,
+        })
+    }
+
+    /// Splits a constraint `numerator / divisor` into its numerator and the
+    /// `divisor^-1` factor. Expressions without a top-level division have no
+    /// divisor.
+    pub fn split_divisor(&self) -> (Self, Option<Self>) {
+        use AlgebraicExpression::*;
+        match self {
+            Mul(a, b) if matches!(*b.borrow(), Exp(_, e) if e < 0) => {
+                (a.borrow().clone(), Some(b.borrow().clone()))
+            }
+            other => (other.clone(), None),
+        }
+    }
+
+    /// Summands of the numerator of the expression i.e. the terms that must
+    /// cancel for a constraint `numerator / divisor` to hold on a row. Factors
+    /// of the numerator that only depend on `x` (e.g. the `(x - g^i)` factors
+    /// of exempt rows) are removed first.
+    pub fn terms(&self) -> Vec<Self> {
+        use AlgebraicExpression::*;
+        let depends_on_x_only = |expression: &Self| {
+            let mut x_only = true;
+            expression.traverse(&mut |node| {
+                if matches!(node, Trace(..) | Periodic(_) | Challenge(_) | Hint(_)) {
+                    x_only = false;
+                }
+            });
+            x_only
+        };
+        let mut numerator = self.split_divisor().0;
+        while let Mul(a, b) = &numerator && depends_on_x_only(&b.borrow()) {
+            let a = a.borrow().clone();
+            numerator = a;
+        }
+        numerator.summands()
+    }
+
+    fn summands(&self) -> Vec<Self> {
+        use AlgebraicExpression::*;
+        match self {
+            Add(a, b) => {
+                let mut summands = a.borrow().summands();
+                summands.extend(b.borrow().summands());
+                summands
+            }
+            Neg(a) => a.borrow().summands().into_iter().map(|v| -v).collect(),
+            other => vec![other.clone()],
+        }
+    }
+
     // Adapted from https://github.com/0xProject/OpenZKP
     pub fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
         use AlgebraicExpression::*;
@@ -387,6 +661,7 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
             &Challenge(i) => challenge(i),
             &Hint(i) => hint(i),
             &Trace(i, j) => trace(i, j),
+            Periodic(_) => panic!("periodic columns must be substituted before evaluation"),
             Add(a, b) => {
                 a.borrow().eval(x, hint, challenge, trace)
                     + b.borrow().eval(x, hint, challenge, trace)
@@ -447,7 +722,16 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
             FieldConstant::Fq(from_bytes::<Fq>(&hasher.finalize()))
         };
 
-        self.eval(&FieldConstant::Fq(x), &hint, &challenge, &trace)
+        let periodic = |i: usize| {
+            let mut hasher = Sha256::new();
+            hasher.update(&x_bytes);
+            hasher.update("periodic");
+            hasher.update(i.to_ne_bytes());
+            FieldConstant::Fq(from_bytes::<Fq>(&hasher.finalize()))
+        };
+
+        self.substitute_periodic(&mut |i| AlgebraicExpression::Constant(periodic(i)))
+            .eval(&FieldConstant::Fq(x), &hint, &challenge, &trace)
             .as_fq()
     }
 
@@ -468,6 +752,7 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
             &Challenge(i) => Some(challenge(i)),
             &Hint(i) => Some(hint(i)),
             &Trace(i, j) => Some(trace(i, j)),
+            Periodic(_) => panic!("periodic columns must be substituted before evaluation"),
             Add(a, b) => {
                 let a = a.borrow().check(x, hint, challenge, trace);
                 let b = b.borrow().check(x, hint, challenge, trace);
@@ -575,6 +860,371 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> AlgebraicExpression<F
             .unwrap()
             .into_inner()
     }
+
+    /// Lowers the expression into a [FusedProgram] that evaluates the entire
+    /// expression in a single pass over the rows of the trace. Hints and
+    /// challenges are substituted as constants. `trace_column` maps an
+    /// execution trace column to its field and the column's index amongst
+    /// columns of the same field. Returns `None` if the expression can't be
+    /// expressed as a fused program e.g. if it requires an extension field
+    /// inverse or more registers than are available.
+    pub fn compile(
+        &self,
+        hint: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        challenge: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        trace_column: &impl Fn(usize) -> (FieldType, usize),
+        lde_step: usize,
+        lde_size: usize,
+    ) -> Option<FusedProgram<Fp, Fq>> {
+        let mut builder = FusedProgramBuilder {
+            values: Vec::new(),
+            shared_values: BTreeMap::new(),
+            fp_constants: Vec::new(),
+            fq_constants: Vec::new(),
+        };
+        let root = builder.emit(self, hint, challenge, trace_column, lde_step, lde_size)?;
+        builder.into_program(root)
+    }
+}
+
+/// Maximum number of registers available to a [FusedProgram] for each field.
+/// Must match `MAX_FUSED_REGISTERS` in `evaluation_shaders.h.metal`.
+pub const MAX_FUSED_REGISTERS: usize = 32;
+
+/// Opcodes of a [FusedProgram].
+/// Must be kept in sync with `EvaluateFused` in `evaluation_shaders.h.metal`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Opcode {
+    LoadX = 0,
+    LoadConstFp = 1,
+    LoadConstFq = 2,
+    LoadTraceFp = 3,
+    LoadTraceFq = 4,
+    AddFp = 5,
+    AddFq = 6,
+    AddFqFp = 7,
+    MulFp = 8,
+    MulFq = 9,
+    MulFqFp = 10,
+    NegFp = 11,
+    NegFq = 12,
+    ExpFp = 13,
+    ExpFq = 14,
+    InvExpFp = 15,
+    ReturnFp = 16,
+    ReturnFq = 17,
+}
+
+/// A single instruction of a [FusedProgram].
+/// Register operands are indices into the register file of the field the
+/// opcode operates on. For `AddFqFp` and `MulFqFp` `a` is an `Fq` register and
+/// `b` is an `Fp` register. Load instructions use `a` as a constant or column
+/// index and `b` as a row shift. Exponentiations use `b` as the exponent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub dst: u32,
+    pub a: u32,
+    pub b: u32,
+}
+
+/// An [AlgebraicExpression] lowered into a register based program that
+/// evaluates the expression for a single row. This allows the GPU to evaluate
+/// a constraint with one kernel launch rather than a launch per node.
+#[derive(Clone, Debug)]
+pub struct FusedProgram<Fp, Fq> {
+    pub instructions: Vec<Instruction>,
+    pub fp_constants: Vec<Fp>,
+    pub fq_constants: Vec<Fq>,
+    pub num_fp_registers: usize,
+    pub num_fq_registers: usize,
+}
+
+impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> FusedProgram<Fp, Fq> {
+    /// Encodes the program as `[opcode, dst, a, b]` words for the GPU
+    pub fn encode(&self) -> Vec<u32> {
+        self.instructions
+            .iter()
+            .flat_map(|ins| [ins.opcode as u32, ins.dst, ins.a, ins.b])
+            .collect()
+    }
+
+    /// Evaluates the program on the CPU. `trace` is given a field type, a
+    /// column index and a row shift.
+    pub fn eval(
+        &self,
+        x: Fp,
+        trace: &impl Fn(FieldType, usize, usize) -> FieldConstant<Fp, Fq>,
+    ) -> Fq {
+        use Opcode::*;
+        let mut fp_registers = vec![Fp::zero(); self.num_fp_registers];
+        let mut fq_registers = vec![Fq::zero(); self.num_fq_registers];
+        for &Instruction { opcode, dst, a, b } in &self.instructions {
+            let (dst, a, b) = (dst as usize, a as usize, b as usize);
+            match opcode {
+                LoadX => fp_registers[dst] = x,
+                LoadConstFp => fp_registers[dst] = self.fp_constants[a],
+                LoadConstFq => fq_registers[dst] = self.fq_constants[a],
+                LoadTraceFp => match trace(FieldType::Fp, a, b) {
+                    FieldConstant::Fp(v) => fp_registers[dst] = v,
+                    FieldConstant::Fq(_) => panic!("expected base field value"),
+                },
+                LoadTraceFq => fq_registers[dst] = trace(FieldType::Fq, a, b).as_fq(),
+                AddFp => fp_registers[dst] = fp_registers[a] + fp_registers[b],
+                AddFq => fq_registers[dst] = fq_registers[a] + fq_registers[b],
+                AddFqFp => fq_registers[dst] = fq_registers[a] + fp_registers[b],
+                MulFp => fp_registers[dst] = fp_registers[a] * fp_registers[b],
+                MulFq => fq_registers[dst] = fq_registers[a] * fq_registers[b],
+                MulFqFp => fq_registers[dst] = fq_registers[a] * fp_registers[b],
+                NegFp => fp_registers[dst] = -fp_registers[a],
+                NegFq => fq_registers[dst] = -fq_registers[a],
+                ExpFp => fp_registers[dst] = fp_registers[a].pow([b as u64]),
+                ExpFq => fq_registers[dst] = fq_registers[a].pow([b as u64]),
+                InvExpFp => fp_registers[dst] = fp_registers[a].pow([b as u64]).inverse().unwrap(),
+                ReturnFp => return Fq::from(fp_registers[a]),
+                ReturnFq => return fq_registers[a],
+            }
+        }
+        unreachable!("program has no return instruction")
+    }
+}
+
+// A value in static single assignment form
+struct SsaValue {
+    opcode: Opcode,
+    field: FieldType,
+    // operands are either immediates or indices of other `SsaValue`s
+    a: u32,
+    b: u32,
+}
+
+impl SsaValue {
+    fn operands(&self) -> &'static [bool; 2] {
+        use Opcode::*;
+        match self.opcode {
+            LoadX | LoadConstFp | LoadConstFq | LoadTraceFp | LoadTraceFq => &[false, false],
+            NegFp | NegFq | ExpFp | ExpFq | InvExpFp | ReturnFp | ReturnFq => &[true, false],
+            AddFp | AddFq | AddFqFp | MulFp | MulFq | MulFqFp => &[true, true],
+        }
+    }
+}
+
+struct FusedProgramBuilder<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> {
+    values: Vec<SsaValue>,
+    // nodes that are referenced multiple times are only emitted once
+    shared_values: BTreeMap<*const RefCell<AlgebraicExpression<Fp, Fq>>, usize>,
+    fp_constants: Vec<Fp>,
+    fq_constants: Vec<Fq>,
+}
+
+impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> FusedProgramBuilder<Fp, Fq> {
+    fn push(&mut self, opcode: Opcode, field: FieldType, a: u32, b: u32) -> usize {
+        self.values.push(SsaValue {
+            opcode,
+            field,
+            a,
+            b,
+        });
+        self.values.len() - 1
+    }
+
+    fn push_constant(&mut self, constant: FieldConstant<Fp, Fq>) -> usize {
+        match constant {
+            FieldConstant::Fp(v) => {
+                self.fp_constants.push(v);
+                let i = self.fp_constants.len() as u32 - 1;
+                self.push(Opcode::LoadConstFp, FieldType::Fp, i, 0)
+            }
+            FieldConstant::Fq(v) => {
+                self.fq_constants.push(v);
+                let i = self.fq_constants.len() as u32 - 1;
+                self.push(Opcode::LoadConstFq, FieldType::Fq, i, 0)
+            }
+        }
+    }
+
+    fn emit_shared(
+        &mut self,
+        node: &Rc<RefCell<AlgebraicExpression<Fp, Fq>>>,
+        hint: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        challenge: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        trace_column: &impl Fn(usize) -> (FieldType, usize),
+        lde_step: usize,
+        lde_size: usize,
+    ) -> Option<usize> {
+        let key = Rc::as_ptr(node);
+        if let Some(&value) = self.shared_values.get(&key) {
+            return Some(value);
+        }
+        let value = self.emit(
+            &node.borrow(),
+            hint,
+            challenge,
+            trace_column,
+            lde_step,
+            lde_size,
+        )?;
+        self.shared_values.insert(key, value);
+        Some(value)
+    }
+
+    fn emit(
+        &mut self,
+        node: &AlgebraicExpression<Fp, Fq>,
+        hint: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        challenge: &impl Fn(usize) -> FieldConstant<Fp, Fq>,
+        trace_column: &impl Fn(usize) -> (FieldType, usize),
+        lde_step: usize,
+        lde_size: usize,
+    ) -> Option<usize> {
+        use AlgebraicExpression::*;
+        Some(match node {
+            X => self.push(Opcode::LoadX, FieldType::Fp, 0, 0),
+            &Constant(c) => self.push_constant(c),
+            &Hint(i) => self.push_constant(hint(i)),
+            &Challenge(i) => self.push_constant(challenge(i)),
+            &Trace(col, offset) => {
+                let (field, idx) = trace_column(col);
+                let shift = (offset * lde_step as isize).rem_euclid(lde_size as isize);
+                let opcode = match field {
+                    FieldType::Fp => Opcode::LoadTraceFp,
+                    FieldType::Fq => Opcode::LoadTraceFq,
+                };
+                self.push(opcode, field, idx.try_into().ok()?, shift.try_into().ok()?)
+            }
+            Periodic(_) => return None,
+            Add(a, b) | Mul(a, b) => {
+                let is_add = matches!(node, Add(..));
+                let a = self.emit_shared(a, hint, challenge, trace_column, lde_step, lde_size)?;
+                let b = self.emit_shared(b, hint, challenge, trace_column, lde_step, lde_size)?;
+                let (opcode, field, a, b) = match (self.values[a].field, self.values[b].field) {
+                    (FieldType::Fp, FieldType::Fp) if is_add => {
+                        (Opcode::AddFp, FieldType::Fp, a, b)
+                    }
+                    (FieldType::Fp, FieldType::Fp) => (Opcode::MulFp, FieldType::Fp, a, b),
+                    (FieldType::Fq, FieldType::Fq) if is_add => {
+                        (Opcode::AddFq, FieldType::Fq, a, b)
+                    }
+                    (FieldType::Fq, FieldType::Fq) => (Opcode::MulFq, FieldType::Fq, a, b),
+                    // mixed operations always take the extension field value first
+                    (FieldType::Fq, FieldType::Fp) if is_add => {
+                        (Opcode::AddFqFp, FieldType::Fq, a, b)
+                    }
+                    (FieldType::Fq, FieldType::Fp) => (Opcode::MulFqFp, FieldType::Fq, a, b),
+                    (FieldType::Fp, FieldType::Fq) if is_add => {
+                        (Opcode::AddFqFp, FieldType::Fq, b, a)
+                    }
+                    (FieldType::Fp, FieldType::Fq) => (Opcode::MulFqFp, FieldType::Fq, b, a),
+                };
+                self.push(opcode, field, a as u32, b as u32)
+            }
+            Neg(a) => {
+                let a = self.emit_shared(a, hint, challenge, trace_column, lde_step, lde_size)?;
+                let field = self.values[a].field;
+                let opcode = match field {
+                    FieldType::Fp => Opcode::NegFp,
+                    FieldType::Fq => Opcode::NegFq,
+                };
+                self.push(opcode, field, a as u32, 0)
+            }
+            &Exp(ref a, e) => {
+                let a = self.emit_shared(a, hint, challenge, trace_column, lde_step, lde_size)?;
+                let field = self.values[a].field;
+                let opcode = match (field, e >= 0) {
+                    (FieldType::Fp, true) => Opcode::ExpFp,
+                    (FieldType::Fq, true) => Opcode::ExpFq,
+                    (FieldType::Fp, false) => Opcode::InvExpFp,
+                    // TODO: extension field inverses aren't implemented on the GPU
+                    (FieldType::Fq, false) => return None,
+                };
+                self.push(opcode, field, a as u32, e.unsigned_abs().try_into().ok()?)
+            }
+            #[cfg(feature = "gpu")]
+            Lde(..) => return None,
+        })
+    }
+
+    // Assigns registers to SSA values. Registers are reused once the value they
+    // hold is no longer needed.
+    fn into_program(mut self, root: usize) -> Option<FusedProgram<Fp, Fq>> {
+        let opcode = match self.values[root].field {
+            FieldType::Fp => Opcode::ReturnFp,
+            FieldType::Fq => Opcode::ReturnFq,
+        };
+        self.push(opcode, self.values[root].field, root as u32, 0);
+
+        let mut last_use = vec![0; self.values.len()];
+        for (i, value) in self.values.iter().enumerate() {
+            for (&is_register, operand) in value.operands().iter().zip([value.a, value.b]) {
+                if is_register {
+                    last_use[operand as usize] = i;
+                }
+            }
+        }
+
+        let mut registers = vec![0u32; self.values.len()];
+        let mut free_fp_registers = Vec::new();
+        let mut free_fq_registers = Vec::new();
+        let mut num_fp_registers = 0;
+        let mut num_fq_registers = 0;
+        let mut instructions = Vec::new();
+        for (i, value) in self.values.iter().enumerate() {
+            let [a_is_register, b_is_register] = *value.operands();
+            let a = if a_is_register {
+                registers[value.a as usize]
+            } else {
+                value.a
+            };
+            let b = if b_is_register {
+                registers[value.b as usize]
+            } else {
+                value.b
+            };
+
+            // release operand registers that won't be read again
+            for (&is_register, operand) in value.operands().iter().zip([value.a, value.b]) {
+                let operand = operand as usize;
+                if is_register && last_use[operand] == i {
+                    match self.values[operand].field {
+                        FieldType::Fp => free_fp_registers.push(registers[operand]),
+                        FieldType::Fq => free_fq_registers.push(registers[operand]),
+                    }
+                }
+            }
+
+            let (free_registers, num_registers) = match value.field {
+                FieldType::Fp => (&mut free_fp_registers, &mut num_fp_registers),
+                FieldType::Fq => (&mut free_fq_registers, &mut num_fq_registers),
+            };
+            let dst = free_registers.pop().unwrap_or_else(|| {
+                *num_registers += 1;
+                *num_registers - 1
+            });
+            registers[i] = dst;
+
+            instructions.push(Instruction {
+                opcode: value.opcode,
+                dst,
+                a,
+                b,
+            });
+        }
+
+        let num_fp_registers = num_fp_registers as usize;
+        let num_fq_registers = num_fq_registers as usize;
+        if num_fp_registers > MAX_FUSED_REGISTERS || num_fq_registers > MAX_FUSED_REGISTERS {
+            return None;
+        }
+
+        Some(FusedProgram {
+            instructions,
+            fp_constants: self.fp_constants,
+            fq_constants: self.fq_constants,
+            num_fp_registers,
+            num_fq_registers,
+        })
+    }
 }
 
 impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Display for AlgebraicExpression<Fp, Fq> {
@@ -586,6 +1236,7 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Display for Algebraic
             Challenge(i) => write!(f, "challenge[{i}]"),
             Hint(i) => write!(f, "hint[{i}]"),
             Trace(i, j) => write!(f, "Trace({i}, {j})"),
+            Periodic(i) => write!(f, "periodic[{i}]"),
             Add(a, b) => match &*b.borrow() {
                 Neg(b) => write!(f, "({} - {})", a.borrow(), b.borrow()),
                 other => write!(f, "({} + {})", a.borrow(), other),
@@ -624,6 +1275,10 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Hash for AlgebraicExp
                 i.hash(state);
                 j.hash(state);
             }
+            Periodic(i) => {
+                "periodic".hash(state);
+                i.hash(state);
+            }
             Add(a, b) => {
                 "add".hash(state);
                 a.borrow().hash(state);
@@ -0,0 +1,188 @@
+//! Continuations split one long execution into segments that are proven
+//! independently. Segment `k + 1` starts at the last row of segment `k` and
+//! the boundary rows of each segment are public inputs so a chain of segment
+//! proofs proves the whole execution (see [verify_continuation]).
+
+use crate::assertions::PublicCell;
+use crate::prover::ProvingError;
+use crate::trace::pad_rows;
+use crate::trace::Padding;
+use crate::verifier::VerificationError;
+use crate::Air;
+use crate::Matrix;
+use crate::Proof;
+use crate::Prover;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::Rng;
+use gpu_poly::GpuFftField;
+use snafu::Snafu;
+
+/// Public inputs that link a segment to its neighbours. Segment AIRs expose
+/// their boundary with `AsRef` and must assert it with
+/// [SegmentBoundary::public_cells] (see [verify_continuation]).
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentBoundary<F: Field> {
+    /// Position of the segment in the execution
+    pub index: usize,
+    /// Row the segment starts from i.e. the last row of the previous segment
+    pub first_row: Vec<F>,
+    /// Row the segment ends on i.e. the first row of the next segment
+    pub last_row: Vec<F>,
+}
+
+impl<F: Field> SegmentBoundary<F> {
+    /// Boundary of segment `index` of a trace (see [split_into_segments])
+    /// that ends at row `last_row`. The last row must come before any
+    /// randomizer rows.
+    pub fn of_segment(index: usize, segment: &Matrix<F>, last_row: usize) -> Self {
+        SegmentBoundary {
+            index,
+            first_row: segment.get_row(0).unwrap(),
+            last_row: segment.get_row(last_row).unwrap(),
+        }
+    }
+
+    /// Public cells asserting the first row of the segment and its last row
+    /// at row `last_row`
+    pub fn public_cells(&self, last_row: usize) -> Vec<PublicCell<F>>
+    where
+        F: GpuFftField + FftField,
+    {
+        let first = self.first_row.iter().enumerate();
+        let last = self.last_row.iter().enumerate();
+        first
+            .map(|(column, &value)| PublicCell::new(column, 0, value))
+            .chain(last.map(|(column, &value)| PublicCell::new(column, last_row, value)))
+            .collect()
+    }
+
+    /// Returns true if `next` is the boundary of the segment following this
+    /// one
+    pub fn links_to(&self, next: &SegmentBoundary<F>) -> bool {
+        self.index + 1 == next.index && self.last_row == next.first_row
+    }
+
+    /// Returns true if `cells` assert the whole boundary i.e. the first row
+    /// at row zero and the last row at some later row
+    pub fn is_asserted_by(&self, cells: &[PublicCell<F>]) -> bool
+    where
+        F: GpuFftField + FftField,
+    {
+        let asserts_row = |row: usize, values: &[F]| {
+            !values.is_empty()
+                && values
+                    .iter()
+                    .enumerate()
+                    .all(|(column, &value)| cells.contains(&PublicCell::new(column, row, value)))
+        };
+        let mut last_rows = cells
+            .iter()
+            .filter(|cell| cell.column == 0 && cell.row != 0)
+            .map(|cell| cell.row);
+        asserts_row(0, &self.first_row) && last_rows.any(|row| asserts_row(row, &self.last_row))
+    }
+}
+
+impl<F: Field> AsRef<SegmentBoundary<F>> for SegmentBoundary<F> {
+    fn as_ref(&self) -> &SegmentBoundary<F> {
+        self
+    }
+}
+
+/// Splits the rows of `trace` into segments of `segment_len` rows where each
+/// segment starts at the last row of the previous segment. The last segment
+/// is padded by repeating its last row.
+pub fn split_into_segments<F: Field>(trace: &Matrix<F>, segment_len: usize) -> Vec<Matrix<F>> {
+    assert!(segment_len > 1, "segments must have at least two rows");
+    let num_rows = trace.num_rows();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + segment_len).min(num_rows);
+        let mut segment = trace.slice_rows(start..end);
+        if end - start < segment_len {
+            for column in &mut segment.0 {
+                column.resize(segment_len, F::zero());
+            }
+            let num_padding_rows = segment_len - (end - start);
+            pad_rows(&mut segment, num_padding_rows, Padding::RepeatLastRow, &[]);
+        }
+        segments.push(segment);
+        if end == num_rows {
+            return segments;
+        }
+        start = end - 1;
+    }
+}
+
+/// Proves each segment of a continuation in order with prover randomness
+/// drawn from `rng` (see [Prover::prove_with_rng])
+pub fn prove_segments<P: Prover, R: Rng + ?Sized>(
+    prover: &P,
+    segments: impl IntoIterator<Item = P::Trace>,
+    rng: &mut R,
+) -> Result<Vec<Proof<P::Air>>, ProvingError> {
+    segments
+        .into_iter()
+        .map(|segment| prover.prove_with_rng(segment, rng))
+        .collect()
+}
+
+/// Errors that are returned when verifying a continuation
+#[derive(Debug, Snafu)]
+pub enum ContinuationError {
+    #[snafu(display("continuation has no segments"))]
+    NoSegments,
+    #[snafu(display("segment {index} is invalid: {source}"))]
+    InvalidSegment {
+        index: usize,
+        source: VerificationError,
+    },
+    #[snafu(display("segment {index} does not continue from the previous segment"))]
+    BrokenLink { index: usize },
+    #[snafu(display("the air of segment {index} does not assert its boundary"))]
+    UnassertedBoundary { index: usize },
+}
+
+/// Verifies every segment proof and checks the segments form a chain that
+/// starts at segment zero. The boundaries are only bound to the traces if
+/// the AIR asserts them so segments whose [Air::public_cells] don't assert
+/// their whole boundary are rejected. Returns the boundaries of the segments.
+pub fn verify_continuation<A: Air>(
+    proofs: Vec<Proof<A>>,
+) -> Result<Vec<SegmentBoundary<A::Fp>>, ContinuationError>
+where
+    A::PublicInputs: AsRef<SegmentBoundary<A::Fp>>,
+{
+    if proofs.is_empty() {
+        return Err(ContinuationError::NoSegments);
+    }
+    let mut boundaries: Vec<SegmentBoundary<A::Fp>> = Vec::new();
+    for (index, proof) in proofs.into_iter().enumerate() {
+        let boundary = proof.public_inputs.as_ref().clone();
+        let is_linked = match boundaries.last() {
+            Some(prev) => prev.links_to(&boundary),
+            None => boundary.index == 0,
+        };
+        if !is_linked {
+            return Err(ContinuationError::BrokenLink { index });
+        }
+        let air = A::new(
+            proof.trace_info.clone(),
+            proof.public_inputs.clone(),
+            proof.options,
+        );
+        if !boundary.is_asserted_by(&air.public_cells()) {
+            return Err(ContinuationError::UnassertedBoundary { index });
+        }
+        proof
+            .verify()
+            .map_err(|source| ContinuationError::InvalidSegment { index, source })?;
+        boundaries.push(boundary);
+    }
+    Ok(boundaries)
+}
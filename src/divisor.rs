@@ -0,0 +1,277 @@
+use crate::constraints::AlgebraicExpression;
+use crate::constraints::FieldConstant;
+use crate::utils::fill_vanishing_polynomial;
+use crate::StarkExtensionOf;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use gpu_poly::GpuFftField;
+
+/// Numerator of a constraint and the divisor it's divided by (if any). See
+/// [crate::Air::divided_constraints].
+pub type DividedConstraint<Fp, Fq> = (AlgebraicExpression<Fp, Fq>, Option<Divisor>);
+
+/// Vanishing polynomial of the rows of the trace a constraint applies to. A
+/// constraint holds on these rows iff it's divisible by the divisor.
+///
+/// Built from a product of `(x - g^i)` factors for individual rows and
+/// vanishing polynomials of cosets of subgroups of the trace domain (i.e.
+/// every `stride` rows). Exempt rows cancel a factor of one of the vanishing
+/// polynomials so e.g. the usual transition constraint divisor is
+/// `Divisor::periodic(0, 1).with_exemption(n - 1)`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Divisor {
+    /// Rows contributing an `(x - g^i)` factor
+    pub rows: Vec<usize>,
+    /// `(first_step, stride)` pairs contributing the vanishing polynomial
+    /// `x^(n/stride) - g^(first_step * n/stride)` of rows
+    /// `first_step + i * stride`
+    pub periodic: Vec<(usize, usize)>,
+    /// Rows whose `(x - g^i)` factor is removed from the divisor
+    pub exemptions: Vec<usize>,
+}
+
+impl Divisor {
+    /// Divisor that vanishes on a single row
+    pub fn row(row: usize) -> Self {
+        Divisor::default().with_row(row)
+    }
+
+    /// Divisor that vanishes every `stride` rows starting from `first_step`.
+    /// `Divisor::periodic(0, 1)` vanishes on all rows.
+    pub fn periodic(first_step: usize, stride: usize) -> Self {
+        Divisor::default().with_periodic(first_step, stride)
+    }
+
+    /// Divisor of a transition constraint that applies to all rows apart from
+    /// the last `num_exemptions` rows of a trace with `trace_len` rows
+    pub fn transition(trace_len: usize, num_exemptions: usize) -> Self {
+        assert!(num_exemptions < trace_len, "all rows are exempt");
+        (trace_len - num_exemptions..trace_len)
+            .fold(Divisor::periodic(0, 1), Divisor::with_exemption)
+    }
+
+    pub fn with_row(mut self, row: usize) -> Self {
+        self.rows.push(row);
+        self
+    }
+
+    pub fn with_periodic(mut self, first_step: usize, stride: usize) -> Self {
+        assert!(stride.is_power_of_two(), "stride must be a power of two");
+        assert!(
+            first_step < stride,
+            "first step must be less than the stride"
+        );
+        self.periodic.push((first_step, stride));
+        self
+    }
+
+    pub fn with_exemption(mut self, row: usize) -> Self {
+        self.exemptions.push(row);
+        self
+    }
+
+    /// Returns which rows of a trace with `trace_len` rows the divisor
+    /// vanishes on
+    pub fn roots(&self, trace_len: usize) -> Vec<bool> {
+        let mut roots = vec![false; trace_len];
+        for &row in &self.rows {
+            roots[row] = true;
+        }
+        for &(first_step, stride) in &self.periodic {
+            roots[first_step..]
+                .iter_mut()
+                .step_by(stride)
+                .for_each(|root| *root = true);
+        }
+        for &row in &self.exemptions {
+            roots[row] = false;
+        }
+        roots
+    }
+
+    /// Divides `expression` by the divisor for a trace over `trace_domain`
+    pub fn divide<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        expression: AlgebraicExpression<Fp, Fq>,
+        trace_domain: Radix2EvaluationDomain<Fp>,
+    ) -> AlgebraicExpression<Fp, Fq> {
+        use AlgebraicExpression::*;
+        let n = trace_domain.size();
+        let x_minus_row = |&row: &usize| {
+            assert!(row < n, "row {row} is outside the trace");
+            X - FieldConstant::Fp(trace_domain.element(row))
+        };
+        let numerator = self
+            .exemptions
+            .iter()
+            .map(x_minus_row)
+            .fold(expression, |acc, factor| acc * factor);
+        let denominator = self
+            .rows
+            .iter()
+            .map(x_minus_row)
+            .chain(self.periodic.iter().map(|&(first_step, stride)| {
+                assert!(stride <= n, "stride {stride} exceeds the trace length");
+                let (num_steps, offset) = Self::periodic_offset(trace_domain, first_step, stride);
+                X.pow(num_steps) - FieldConstant::Fp(offset)
+            }))
+            .reduce(|acc, factor| acc * factor);
+        match denominator {
+            Some(denominator) => numerator / denominator,
+            None => numerator,
+        }
+    }
+
+    /// Canonical S-expression e.g. `(divisor (rows 0) (periodic (0 1))
+    /// (exemptions 2047))` (see `Air::constraint_system`)
+    pub fn to_sexpr(&self) -> String {
+        let join = |items: Vec<String>| items.join(" ");
+        let rows = join(self.rows.iter().map(|row| row.to_string()).collect());
+        let periodic = join(
+            self.periodic
+                .iter()
+                .map(|(first_step, stride)| format!("({first_step} {stride})"))
+                .collect(),
+        );
+        let exemptions = join(self.exemptions.iter().map(|row| row.to_string()).collect());
+        format!("(divisor (rows {rows}) (periodic {periodic}) (exemptions {exemptions}))")
+    }
+
+    /// Evaluates the divisor at `x` for a trace over `trace_domain`
+    pub fn evaluate_at<Fp: FftField, T: Field + From<Fp>>(
+        &self,
+        x: T,
+        trace_domain: Radix2EvaluationDomain<Fp>,
+    ) -> T {
+        let x_minus_row = |&row: &usize| x - T::from(trace_domain.element(row));
+        let numerator = self.rows.iter().map(x_minus_row).product::<T>()
+            * self
+                .periodic
+                .iter()
+                .map(|&(first_step, stride)| {
+                    let (num_steps, offset) =
+                        Self::periodic_offset(trace_domain, first_step, stride);
+                    x.pow([num_steps as u64]) - T::from(offset)
+                })
+                .product::<T>();
+        let denominator = self.exemptions.iter().map(x_minus_row).product::<T>();
+        numerator * denominator.inverse().unwrap()
+    }
+
+    /// Evaluates the divisor over all of `eval_domain` for a trace over
+    /// `trace_domain`. `eval_domain` must be disjoint from the trace domain.
+    pub fn evaluate<F: FftField>(
+        &self,
+        trace_domain: Radix2EvaluationDomain<F>,
+        eval_domain: Radix2EvaluationDomain<F>,
+    ) -> Vec<F> {
+        let n = eval_domain.size();
+        let mut result = vec![F::one(); n];
+        let mut factor = vec![F::one(); n];
+        for &(first_step, stride) in &self.periodic {
+            let (num_steps, _) = Self::periodic_offset(trace_domain, first_step, stride);
+            let offset = trace_domain.element(first_step);
+            let vanish_domain = Radix2EvaluationDomain::new_coset(num_steps, offset).unwrap();
+            fill_vanishing_polynomial(&mut factor, &vanish_domain, &eval_domain);
+            result.iter_mut().zip(&factor).for_each(|(v, f)| *v *= f);
+        }
+        if !self.rows.is_empty() {
+            let rows = Self::evaluate_rows(&self.rows, trace_domain, eval_domain);
+            result.iter_mut().zip(&rows).for_each(|(v, f)| *v *= f);
+        }
+        if !self.exemptions.is_empty() {
+            let mut exemptions = Self::evaluate_rows(&self.exemptions, trace_domain, eval_domain);
+            batch_inversion(&mut exemptions);
+            result
+                .iter_mut()
+                .zip(&exemptions)
+                .for_each(|(v, f)| *v *= f);
+        }
+        result
+    }
+
+    /// Evaluates the product of `(x - g^i)` for all `rows` over `eval_domain`.
+    /// A run of consecutive rows `a..b` costs `O(|eval_domain|)` rather than
+    /// `O(|eval_domain| * (b - a))` since the product `P` over the run
+    /// satisfies `P(gx) = g^(b-a) * P(x) * (x - g^(a-1)) / (x - g^(b-1))` and
+    /// multiplying by `g` steps `|eval_domain|/n` points through `eval_domain`.
+    fn evaluate_rows<F: FftField>(
+        rows: &[usize],
+        trace_domain: Radix2EvaluationDomain<F>,
+        eval_domain: Radix2EvaluationDomain<F>,
+    ) -> Vec<F> {
+        let n = trace_domain.size();
+        let blowup = eval_domain.size() / n;
+        assert_eq!(
+            blowup * n,
+            eval_domain.size(),
+            "trace domain must divide the domain"
+        );
+        let xs = eval_domain.elements().collect::<Vec<F>>();
+        let mut result = vec![F::one(); xs.len()];
+        let mut rows = rows.to_vec();
+        rows.sort_unstable();
+        let mut start = 0;
+        while start < rows.len() {
+            let mut end = start + 1;
+            while end < rows.len() && rows[end] == rows[end - 1] + 1 {
+                end += 1;
+            }
+            let run = &rows[start..end];
+            start = end;
+            if run.len() <= 2 {
+                for &row in run {
+                    let row_x = trace_domain.element(row);
+                    result
+                        .iter_mut()
+                        .zip(&xs)
+                        .for_each(|(v, &x)| *v *= x - row_x);
+                }
+                continue;
+            }
+
+            let (first, last) = (run[0], run[run.len() - 1]);
+            let before_first = trace_domain.element((first + n - 1) % n);
+            let last_x = trace_domain.element(last);
+            let scale = trace_domain.element(run.len() % n);
+            let mut last_inv = xs[..xs.len() - blowup]
+                .iter()
+                .map(|&x| x - last_x)
+                .collect::<Vec<F>>();
+            batch_inversion(&mut last_inv);
+            let mut run_evals = xs[..blowup]
+                .iter()
+                .map(|&x| {
+                    run.iter()
+                        .map(|&row| x - trace_domain.element(row))
+                        .product()
+                })
+                .collect::<Vec<F>>();
+            run_evals.reserve(xs.len() - blowup);
+            for i in 0..xs.len() - blowup {
+                let next = scale * run_evals[i] * (xs[i] - before_first) * last_inv[i];
+                run_evals.push(next);
+            }
+            result.iter_mut().zip(&run_evals).for_each(|(v, f)| *v *= f);
+        }
+        result
+    }
+
+    /// Rows `first_step + i * stride` are the roots of `x^k - g^(first_step*k)`
+    /// with `k = n/stride`. Returns `(k, g^(first_step*k))`.
+    fn periodic_offset<F: FftField>(
+        trace_domain: Radix2EvaluationDomain<F>,
+        first_step: usize,
+        stride: usize,
+    ) -> (usize, F) {
+        let num_steps = trace_domain.size() / stride;
+        let offset = trace_domain.element(first_step).pow([num_steps as u64]);
+        (num_steps, offset)
+    }
+}
@@ -0,0 +1,393 @@
+//! Domains the columns of a [Matrix](crate::Matrix) can be evaluated over.
+//!
+//! Besides the usual multiplicative subgroups ([Radix2EvaluationDomain]) this
+//! module provides [CircleDomain] for fields like Mersenne-31 whose
+//! multiplicative group isn't smooth. These use the circle group
+//! `{(x, y) : x^2 + y^2 = 1}` of order `p + 1` instead. See "Circle STARKs"
+//! <https://eprint.iacr.org/2024/278>. Evaluations over a [CircleDomain] can
+//! be proven low degree with circle FRI (see
+//! [prove_circle_low_degree](crate::fri::prove_circle_low_degree)) but the
+//! prover's constraint evaluation and DEEP composition are multiplicative so
+//! M31 AIRs can't be proven end to end yet.
+//!
+//! [StarkDomain] abstracts over multiplicative cosets so sizes other than
+//! powers of two (e.g. `3 * 2^k` with [MixedRadixEvaluationDomain]) can be
+//! used without rounding up. [Matrix](crate::Matrix) commitments and the
+//! vanishing polynomial helpers accept any [StarkDomain] but the prover's
+//! trace and LDE domains are still radix-2.
+#[cfg(feature = "gpu")]
+use crate::utils::wait_for_gpu;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::MontFp;
+use ark_ff::PrimeField;
+use ark_poly::domain::DomainCoeff;
+use ark_poly::EvaluationDomain;
+use ark_poly::GeneralEvaluationDomain;
+use ark_poly::MixedRadixEvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use core::ops::Mul;
+use gpu_poly::fields::p2147483647::Fp as M31;
+use gpu_poly::prelude::*;
+use gpu_poly::utils::bit_reverse;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// A domain columns of polynomial coefficients can be evaluated over and
+/// interpolated from.
+pub trait Domain<F> {
+    /// Evaluates each column of coefficients in place. Columns shorter than
+    /// the domain are padded with zeros.
+    fn evaluate_columns(&self, columns: &mut [GpuVec<F>]);
+
+    /// Interpolates each column of evaluations in place
+    fn interpolate_columns(&self, columns: &mut [GpuVec<F>]);
+}
+
+impl<F> Domain<F> for Radix2EvaluationDomain<F::FftField>
+where
+    F: GpuField + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    fn evaluate_columns(&self, columns: &mut [GpuVec<F>]) {
+        #[cfg(not(feature = "gpu"))]
+        columns.iter_mut().for_each(|col| self.fft_in_place(col));
+        #[cfg(feature = "gpu")]
+        {
+            let mut fft = GpuFft::from(*self);
+            columns.iter_mut().for_each(|col| fft.encode(col));
+            wait_for_gpu(|| fft.execute());
+        }
+    }
+
+    fn interpolate_columns(&self, columns: &mut [GpuVec<F>]) {
+        #[cfg(not(feature = "gpu"))]
+        columns.iter_mut().for_each(|col| self.ifft_in_place(col));
+        #[cfg(feature = "gpu")]
+        {
+            let mut ifft = GpuIfft::from(*self);
+            columns.iter_mut().for_each(|col| ifft.encode(col));
+            wait_for_gpu(|| ifft.execute());
+        }
+    }
+}
+
+impl<F> Domain<F> for MixedRadixEvaluationDomain<F::FftField>
+where
+    F: GpuField + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    // TODO: GPU mixed-radix FFT
+    fn evaluate_columns(&self, columns: &mut [GpuVec<F>]) {
+        ark_std::cfg_iter_mut!(columns).for_each(|col| self.fft_in_place(col));
+    }
+
+    fn interpolate_columns(&self, columns: &mut [GpuVec<F>]) {
+        ark_std::cfg_iter_mut!(columns).for_each(|col| self.ifft_in_place(col));
+    }
+}
+
+impl<F> Domain<F> for GeneralEvaluationDomain<F::FftField>
+where
+    F: GpuField + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    fn evaluate_columns(&self, columns: &mut [GpuVec<F>]) {
+        match self {
+            Self::Radix2(domain) => domain.evaluate_columns(columns),
+            Self::MixedRadix(domain) => domain.evaluate_columns(columns),
+        }
+    }
+
+    fn interpolate_columns(&self, columns: &mut [GpuVec<F>]) {
+        match self {
+            Self::Radix2(domain) => domain.interpolate_columns(columns),
+            Self::MixedRadix(domain) => domain.interpolate_columns(columns),
+        }
+    }
+}
+
+/// A multiplicative coset `offset * <g>` of the field. Used for the trace,
+/// constraint evaluation and LDE domains.
+pub trait StarkDomain<F: FftField>: EvaluationDomain<F> {
+    /// Returns the coset of size `size` with the given offset. The domain
+    /// type is preserved so a mixed-radix trace domain has mixed-radix LDE
+    /// domains.
+    fn new_coset_like(&self, size: usize, offset: F) -> Option<Self> {
+        Self::new_coset(size, offset)
+    }
+}
+
+impl<F: FftField> StarkDomain<F> for Radix2EvaluationDomain<F> {}
+
+impl<F: FftField> StarkDomain<F> for MixedRadixEvaluationDomain<F> {}
+
+impl<F: FftField> StarkDomain<F> for GeneralEvaluationDomain<F> {
+    fn new_coset_like(&self, size: usize, offset: F) -> Option<Self> {
+        Some(match self {
+            Self::Radix2(_) => Self::Radix2(Radix2EvaluationDomain::new_coset(size, offset)?),
+            Self::MixedRadix(_) => {
+                Self::MixedRadix(MixedRadixEvaluationDomain::new_coset(size, offset)?)
+            }
+        })
+    }
+}
+
+/// A point on the circle `x^2 + y^2 = 1`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CirclePoint<F> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: Field> CirclePoint<F> {
+    /// Identity of the circle group
+    pub fn identity() -> Self {
+        CirclePoint {
+            x: F::one(),
+            y: F::zero(),
+        }
+    }
+
+    pub fn double(&self) -> Self {
+        *self * *self
+    }
+
+    pub fn repeated_double(&self, n: u32) -> Self {
+        (0..n).fold(*self, |p, _| p.double())
+    }
+
+    /// Returns the group inverse `(x, -y)`
+    pub fn conjugate(&self) -> Self {
+        CirclePoint {
+            x: self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl<F: Field> Mul for CirclePoint<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        CirclePoint {
+            x: self.x * rhs.x - self.y * rhs.y,
+            y: self.x * rhs.y + self.y * rhs.x,
+        }
+    }
+}
+
+/// Prime fields with a circle group containing a large power-of-two subgroup
+pub trait CircleFftField: PrimeField {
+    /// log2 of the order of [Self::CIRCLE_GENERATOR]
+    const CIRCLE_TWO_ADICITY: u32;
+
+    /// Generator of the circle subgroup of order `2^CIRCLE_TWO_ADICITY`
+    const CIRCLE_GENERATOR: CirclePoint<Self>;
+
+    /// Returns a generator of the circle subgroup of order `2^log_order`
+    fn circle_subgroup_generator(log_order: u32) -> CirclePoint<Self> {
+        assert!(log_order <= Self::CIRCLE_TWO_ADICITY);
+        Self::CIRCLE_GENERATOR.repeated_double(Self::CIRCLE_TWO_ADICITY - log_order)
+    }
+}
+
+impl CircleFftField for M31 {
+    const CIRCLE_TWO_ADICITY: u32 = 31;
+    const CIRCLE_GENERATOR: CirclePoint<Self> = CirclePoint {
+        x: MontFp!("2"),
+        y: MontFp!("1268011823"),
+    };
+}
+
+/// Canonic coset of the circle group i.e. the odd powers of a generator `q`
+/// of order `2n`. Point `i < n/2` is `q^(4i + 1)` and point `i + n/2` is its
+/// conjugate. Canonic cosets of different sizes are disjoint so a larger one
+/// can be used as the low degree extension domain of a smaller one.
+///
+/// Polynomials are represented in the basis `y^b0 * x^b1 * π(x)^b2 * ...`
+/// where `π(x) = 2x^2 - 1` with coefficient `b0 + 2*b1 + 4*b2 + ...`. This
+/// ordering means coefficients can be zero padded to evaluate over a larger
+/// domain just like univariate coefficients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircleDomain<F> {
+    log_size: u32,
+    half_coset_offset: CirclePoint<F>,
+    half_coset_step: CirclePoint<F>,
+}
+
+impl<F: CircleFftField> CircleDomain<F> {
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size.is_power_of_two() && size >= 2,
+            "invalid domain size {size}"
+        );
+        let log_size = size.ilog2();
+        let q = F::circle_subgroup_generator(log_size + 1);
+        CircleDomain {
+            log_size,
+            half_coset_offset: q,
+            half_coset_step: q.repeated_double(2),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        1 << self.log_size
+    }
+
+    /// Returns the `i`th point of the domain
+    pub fn at(&self, i: usize) -> CirclePoint<F> {
+        let half_size = self.size() / 2;
+        let point = self.half_coset_offset * pow(self.half_coset_step, (i % half_size) as u64);
+        if i < half_size {
+            point
+        } else {
+            point.conjugate()
+        }
+    }
+
+    /// Returns the first half of the domain. The second half is the conjugate.
+    pub fn half_coset(&self) -> Vec<CirclePoint<F>> {
+        let mut point = self.half_coset_offset;
+        (0..self.size() / 2)
+            .map(|_| {
+                let res = point;
+                point = point * self.half_coset_step;
+                res
+            })
+            .collect()
+    }
+
+    /// Returns twiddles for each layer of the circle FFT. The first layer
+    /// holds y-coordinates and all subsequent layers x-coordinates.
+    fn twiddles(&self) -> Vec<Vec<F>> {
+        let half_coset = self.half_coset();
+        let mut layers = vec![half_coset.iter().map(|p| p.y).collect::<Vec<F>>()];
+        let mut xs = half_coset.iter().map(|p| p.x).collect::<Vec<F>>();
+        while xs.len() > 1 {
+            xs.truncate(xs.len() / 2);
+            layers.push(xs.clone());
+            xs.iter_mut()
+                .for_each(|x| *x = x.square().double() - F::one());
+        }
+        layers
+    }
+
+    /// Evaluates coefficients in place using the circle FFT
+    pub fn fft_in_place(&self, values: &mut GpuVec<F>) {
+        let n = self.size();
+        assert!(values.len() <= n, "too many coefficients for domain");
+        values.resize(n, F::zero());
+        bit_reverse(values);
+        for twiddles in self.twiddles().into_iter().rev() {
+            let chunk_size = 2 * twiddles.len();
+            ark_std::cfg_chunks_mut!(values, chunk_size).for_each(|chunk| {
+                let (lo, hi) = chunk.split_at_mut(twiddles.len());
+                for ((a, b), t) in lo.iter_mut().zip(hi).zip(&twiddles) {
+                    let tb = *b * t;
+                    *b = *a - tb;
+                    *a += tb;
+                }
+            });
+        }
+    }
+
+    /// Interpolates evaluations in place using the inverse circle FFT
+    pub fn ifft_in_place(&self, values: &mut GpuVec<F>) {
+        let n = self.size();
+        assert_eq!(n, values.len(), "evaluations must match domain size");
+        for mut twiddles in self.twiddles() {
+            ark_ff::batch_inversion(&mut twiddles);
+            let chunk_size = 2 * twiddles.len();
+            ark_std::cfg_chunks_mut!(values, chunk_size).for_each(|chunk| {
+                let (lo, hi) = chunk.split_at_mut(twiddles.len());
+                for ((a, b), t) in lo.iter_mut().zip(hi).zip(&twiddles) {
+                    let (u, v) = (*a, *b);
+                    *a = u + v;
+                    *b = (u - v) * t;
+                }
+            });
+        }
+        let n_inv = F::from(n as u64).inverse().unwrap();
+        ark_std::cfg_iter_mut!(values).for_each(|v| *v *= n_inv);
+        bit_reverse(values);
+    }
+}
+
+impl<F: CircleFftField> Domain<F> for CircleDomain<F> {
+    fn evaluate_columns(&self, columns: &mut [GpuVec<F>]) {
+        ark_std::cfg_iter_mut!(columns).for_each(|col| self.fft_in_place(col));
+    }
+
+    fn interpolate_columns(&self, columns: &mut [GpuVec<F>]) {
+        ark_std::cfg_iter_mut!(columns).for_each(|col| self.ifft_in_place(col));
+    }
+}
+
+/// First round of circle FRI. Folds evaluations over a circle domain into
+/// evaluations over the domain's x-coordinates (the "line" domain) using
+/// `f(x) = f0(x) + α * f1(x)` where `f(x, y) = f0(x) + y * f1(x)`. Returns the
+/// folded evaluations and x-coordinates they're evaluated at.
+pub fn fold_circle_into_line<F: CircleFftField, E: Field + From<F>>(
+    evals: &[E],
+    domain: &CircleDomain<F>,
+    alpha: E,
+) -> (Vec<E>, Vec<F>) {
+    let n = domain.size();
+    assert_eq!(n, evals.len());
+    let half_coset = domain.half_coset();
+    let mut y_invs = half_coset.iter().map(|p| p.y.double()).collect::<Vec<F>>();
+    ark_ff::batch_inversion(&mut y_invs);
+    let two_inv = E::from(F::from(2u8).inverse().unwrap());
+    let (lo, hi) = evals.split_at(n / 2);
+    let folded = lo
+        .iter()
+        .zip(hi)
+        .zip(y_invs)
+        .map(|((&a, &b), y_inv)| (a + b) * two_inv + alpha * (a - b) * E::from(y_inv))
+        .collect();
+    let xs = half_coset.iter().map(|p| p.x).collect();
+    (folded, xs)
+}
+
+/// Subsequent rounds of circle FRI. Folds evaluations over a line domain,
+/// where the x-coordinate at `i + n/2` is the negation of the one at `i`,
+/// using `f(π(x)) = f0(π(x)) + α * f1(π(x))` where `f(x) = f0(π(x)) + x *
+/// f1(π(x))`. Returns the folded evaluations and their x-coordinates.
+pub fn fold_line<F: CircleFftField, E: Field + From<F>>(
+    evals: &[E],
+    xs: &[F],
+    alpha: E,
+) -> (Vec<E>, Vec<F>) {
+    let n = evals.len();
+    assert_eq!(n, xs.len());
+    assert!(n.is_power_of_two() && n >= 2);
+    let mut x_invs = xs[..n / 2].iter().map(|x| x.double()).collect::<Vec<F>>();
+    ark_ff::batch_inversion(&mut x_invs);
+    let two_inv = E::from(F::from(2u8).inverse().unwrap());
+    let (lo, hi) = evals.split_at(n / 2);
+    let folded = lo
+        .iter()
+        .zip(hi)
+        .zip(x_invs)
+        .map(|((&a, &b), x_inv)| (a + b) * two_inv + alpha * (a - b) * E::from(x_inv))
+        .collect();
+    let next_xs = xs[..n / 2]
+        .iter()
+        .map(|x| x.square().double() - F::one())
+        .collect();
+    (folded, next_xs)
+}
+
+fn pow<F: Field>(mut base: CirclePoint<F>, mut exp: u64) -> CirclePoint<F> {
+    let mut res = CirclePoint::identity();
+    while exp != 0 {
+        if exp & 1 == 1 {
+            res = res * base;
+        }
+        base = base.double();
+        exp >>= 1;
+    }
+    res
+}
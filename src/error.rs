@@ -0,0 +1,43 @@
+use crate::merkle::MerkleTreeError;
+use crate::prover::ProvingError;
+use alloc::string::String;
+use alloc::string::ToString;
+use ark_serialize::SerializationError;
+use snafu::Snafu;
+
+/// Errors returned by the public constructors and entry points of the crate
+/// for malformed input. Lets services reject bad requests without panicking.
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("invalid proof options: {reason}"))]
+    InvalidOptions { reason: String },
+    #[snafu(display("trace has {len} rows but needs at least {min}"))]
+    TraceTooShort { len: usize, min: usize },
+    #[snafu(display("trace has {len} rows which is not a power of two"))]
+    TraceLengthNotPowerOfTwo { len: usize },
+    #[snafu(display("trace has {num_columns} columns but at most {max} are supported"))]
+    TooManyColumns { num_columns: usize, max: usize },
+    #[snafu(display("invalid trace info: {reason}"))]
+    InvalidTraceInfo { reason: String },
+    #[snafu(display("serialization failed: {message}"))]
+    Serialization { message: String },
+    #[snafu(context(false))]
+    #[snafu(display("commitment failed: {source}"))]
+    MerkleTree { source: MerkleTreeError },
+    #[snafu(display("gpu error: {message}"))]
+    Gpu { message: String },
+    #[snafu(context(false))]
+    #[snafu(display("{source}"))]
+    Proving { source: ProvingError },
+    /// Proving panicked on the thread of [crate::Prover::generate_proof_async]
+    #[snafu(display("proving panicked: {message}"))]
+    Panicked { message: String },
+}
+
+impl From<SerializationError> for Error {
+    fn from(error: SerializationError) -> Self {
+        Error::Serialization {
+            message: error.to_string(),
+        }
+    }
+}
@@ -1,8 +1,19 @@
+use crate::domain::fold_circle_into_line;
+use crate::domain::fold_line;
+use crate::domain::CircleDomain;
+use crate::domain::CircleFftField;
+use crate::merkle::is_supported_arity;
 use crate::merkle::MerkleProof;
 use crate::merkle::MerkleTree;
 use crate::merkle::MerkleTreeError;
+use crate::random::label;
 use crate::random::PublicCoin;
+#[cfg(feature = "gpu")]
+use crate::utils::commit_and_wait;
 use crate::utils::interleave;
+use crate::utils::serialized_vec_size;
+#[cfg(feature = "gpu")]
+use crate::utils::wait_for_gpu;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
@@ -18,42 +29,273 @@ use core::ops::Deref;
 use digest::Digest;
 use digest::Output;
 use gpu_poly::prelude::*;
+use gpu_poly::utils::bit_reverse;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use snafu::Snafu;
 
+/// Folding factors for the first layers of FRI e.g. `[8, 4]` folds the first
+/// layer by 8 and the second by 4. Later layers use the default folding
+/// factor of [FriOptions].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FoldingSchedule {
+    factors: [u8; Self::MAX_LEN],
+    len: u8,
+}
+
+impl FoldingSchedule {
+    pub const MAX_LEN: usize = 8;
+
+    pub fn new(factors: &[u8]) -> Self {
+        assert!(factors.len() <= Self::MAX_LEN);
+        for &factor in factors {
+            assert!(
+                is_supported_folding_factor(factor.into()),
+                "folding factor {factor} is not supported"
+            );
+        }
+        let mut schedule = FoldingSchedule {
+            factors: [0; Self::MAX_LEN],
+            len: factors.len() as u8,
+        };
+        schedule.factors[..factors.len()].copy_from_slice(factors);
+        schedule
+    }
+
+    pub fn factors(&self) -> &[u8] {
+        &self.factors[..self.len.into()]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns false if the schedule is too long or has a folding factor that
+    /// isn't supported e.g. if it was deserialized from a malformed proof
+    pub fn is_supported(&self) -> bool {
+        usize::from(self.len) <= Self::MAX_LEN
+            && self
+                .factors()
+                .iter()
+                .all(|&factor| is_supported_folding_factor(factor.into()))
+    }
+}
+
+/// Order the evaluations of each layer are stored, committed to and queried in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvaluationOrder {
+    /// `evals[i]` is the evaluation at `offset * ω^i`. Each leaf holds the
+    /// evaluations at positions `i, i + n/N, ..., i + (N-1)n/N` so layers are
+    /// interleaved before they are committed.
+    #[default]
+    Natural,
+    /// `evals[i]` is the evaluation at `offset * ω^bitrev(i)` as output by the
+    /// GPU FFT. Each leaf holds `N` consecutive evaluations so layers are
+    /// committed and folded without being reordered.
+    BitReversed,
+}
+
+impl EvaluationOrder {
+    /// Position in the folded layer (and leaf index in this layer) of the
+    /// evaluation at `position`
+    pub fn fold_position(
+        self,
+        position: usize,
+        domain_size: usize,
+        folding_factor: usize,
+    ) -> usize {
+        match self {
+            Self::Natural => position % (domain_size / folding_factor),
+            Self::BitReversed => position / folding_factor,
+        }
+    }
+
+    /// Index of the evaluation at `position` within its leaf
+    pub fn leaf_index(self, position: usize, domain_size: usize, folding_factor: usize) -> usize {
+        match self {
+            Self::Natural => position / (domain_size / folding_factor),
+            Self::BitReversed => position % folding_factor,
+        }
+    }
+
+    /// Returns `i` such that the evaluation at `position` is at `offset * ω^i`
+    pub fn domain_index(self, position: usize, domain_size: usize) -> usize {
+        match self {
+            Self::Natural => position,
+            Self::BitReversed => bit_reverse_index(domain_size, position),
+        }
+    }
+}
+
+/// Returns true if layers can be folded by `folding_factor`
+pub fn is_supported_folding_factor(folding_factor: usize) -> bool {
+    matches!(folding_factor, 2 | 4 | 8 | 16)
+}
+
 #[derive(Clone, Copy)]
 pub struct FriOptions {
     folding_factor: usize,
+    folding_schedule: FoldingSchedule,
     max_remainder_size: usize,
     blowup_factor: usize,
+    remainder_coeffs: bool,
+    early_stop: bool,
+    cap_height: usize,
+    merkle_arity: usize,
+    evaluation_order: EvaluationOrder,
 }
 
 impl FriOptions {
     pub fn new(blowup_factor: usize, folding_factor: usize, max_remainder_size: usize) -> Self {
         FriOptions {
             folding_factor,
+            folding_schedule: FoldingSchedule::default(),
             max_remainder_size,
             blowup_factor,
+            remainder_coeffs: false,
+            early_stop: false,
+            cap_height: 0,
+            merkle_arity: 2,
+            evaluation_order: EvaluationOrder::Natural,
         }
     }
 
+    /// Commits to each layer with a Merkle cap of `2^cap_height` nodes rather
+    /// than the root. The remainder is always committed to by its root.
+    pub fn with_merkle_cap_height(mut self, cap_height: usize) -> Self {
+        self.cap_height = cap_height;
+        self
+    }
+
+    /// Commits to each layer with a Merkle tree whose nodes have `arity`
+    /// children. The remainder is always committed to by a binary tree.
+    pub fn with_merkle_arity(mut self, arity: usize) -> Self {
+        assert!(is_supported_arity(arity));
+        self.merkle_arity = arity;
+        self
+    }
+
+    /// Sends the remainder as the coefficients of a polynomial rather than a
+    /// committed codeword. The verifier checks the degree bound directly and
+    /// evaluates the polynomial at query positions. Shrinks proofs when the
+    /// remainder is large.
+    pub fn with_remainder_coeffs(mut self) -> Self {
+        self.remainder_coeffs = true;
+        self
+    }
+
+    /// Stops folding once the polynomial has at most `max_remainder_size`
+    /// coefficients even if the domain is larger. Proofs of low degree
+    /// polynomials then have fewer layers. Implies
+    /// [FriOptions::with_remainder_coeffs].
+    pub fn with_early_stop(mut self) -> Self {
+        self.remainder_coeffs = true;
+        self.early_stop = true;
+        self
+    }
+
+    /// Layers (including the codeword passed to the prover) are stored in
+    /// `evaluation_order` and query positions index into that order
+    pub fn with_evaluation_order(mut self, evaluation_order: EvaluationOrder) -> Self {
+        self.evaluation_order = evaluation_order;
+        self
+    }
+
+    pub fn evaluation_order(&self) -> EvaluationOrder {
+        self.evaluation_order
+    }
+
+    pub fn with_folding_schedule(mut self, folding_schedule: FoldingSchedule) -> Self {
+        self.folding_schedule = folding_schedule;
+        self
+    }
+
+    pub fn blowup_factor(&self) -> usize {
+        self.blowup_factor
+    }
+
+    /// Folding factor of the `i`th layer. The remainder is layer
+    /// `num_layers(domain_size)`.
+    pub fn layer_folding_factor(&self, layer: usize) -> usize {
+        self.folding_schedule
+            .factors()
+            .get(layer)
+            .map_or(self.folding_factor, |&factor| factor.into())
+    }
+
     pub fn num_layers(&self, mut domain_size: usize) -> usize {
         let mut num_layers = 0;
         while domain_size > self.max_remainder_size {
-            domain_size /= self.folding_factor;
+            domain_size /= self.layer_folding_factor(num_layers);
+            num_layers += 1;
+        }
+        num_layers
+    }
+
+    /// Number of layers committed to by the prover i.e. the folded layers and
+    /// the remainder unless it is sent as coefficients. Early stopping can
+    /// commit to fewer layers.
+    pub fn num_committed_layers(&self, domain_size: usize) -> usize {
+        self.num_layers(domain_size) + usize::from(!self.remainder_coeffs)
+    }
+
+    /// Number of layers folded by a prover with [FriOptions::with_early_stop]
+    /// for a polynomial with `num_coeffs` coefficients
+    pub fn num_early_stop_layers(&self, domain_size: usize, mut num_coeffs: usize) -> usize {
+        let max_num_layers = self.num_layers(domain_size);
+        let mut num_layers = 0;
+        while num_layers < max_num_layers && num_coeffs > self.max_remainder_size {
+            num_coeffs = num_coeffs.div_ceil(self.layer_folding_factor(num_layers));
             num_layers += 1;
         }
         num_layers
     }
 
     pub fn remainder_size(&self, mut domain_size: usize) -> usize {
+        let mut layer = 0;
         while domain_size > self.max_remainder_size {
-            domain_size /= self.folding_factor;
+            domain_size /= self.layer_folding_factor(layer);
+            layer += 1;
         }
         domain_size
     }
 
+    /// Upper bound on the serialized size (in bytes) of a FRI proof over an
+    /// evaluation domain of `domain_size` with `num_queries` queries. Assumes
+    /// no two queries open the same leaf of a layer.
+    pub fn estimated_proof_size<F: Field, D: Digest>(
+        &self,
+        mut domain_size: usize,
+        num_queries: usize,
+    ) -> usize {
+        let element_size = F::zero().compressed_size();
+        let digest_size = <D as Digest>::output_size();
+        let mut layers_size = 0;
+        for layer in 0..self.num_layers(domain_size) {
+            let folding_factor = self.layer_folding_factor(layer);
+            domain_size /= folding_factor;
+            let num_leaf_queries = num_queries.min(domain_size);
+            let arity = self.merkle_arity;
+            let cap_size = MerkleTree::<D>::cap_size(domain_size, self.cap_height, arity);
+            let path_len = MerkleTree::<D>::proof_len(domain_size, self.cap_height, arity);
+            // a merkle proof is the concatenated digests of the path
+            let proof_size = serialized_vec_size(path_len * digest_size, 1);
+            layers_size += serialized_vec_size(num_leaf_queries * folding_factor, element_size);
+            layers_size += serialized_vec_size(num_leaf_queries, proof_size);
+            layers_size += serialized_vec_size(cap_size * digest_size, 1);
+        }
+        let (remainder_len, remainder_commitment_len) = if self.remainder_coeffs {
+            ((domain_size / self.blowup_factor).max(1), 0)
+        } else {
+            (domain_size, digest_size)
+        };
+        // length prefix of the layers
+        serialized_vec_size(0, 0)
+            + layers_size
+            + serialized_vec_size(remainder_len, element_size)
+            + serialized_vec_size(remainder_commitment_len, 1)
+    }
+
     pub fn domain_offset<F: GpuField>(&self) -> F::FftField
     where
         F::FftField: FftField,
@@ -65,7 +307,10 @@ impl FriOptions {
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct FriProof<F: Field> {
     layers: Vec<FriProofLayer<F>>,
+    /// Evaluations of the remainder or its coefficients if
+    /// [FriOptions::with_remainder_coeffs] is set
     remainder: Vec<F>,
+    /// Empty if the remainder is sent as coefficients
     remainder_commitment: Vec<u8>,
 }
 
@@ -84,11 +329,24 @@ where
             remainder,
         }
     }
+
+    pub fn layers(&self) -> &[FriProofLayer<F>] {
+        &self.layers
+    }
+
+    pub fn remainder(&self) -> &[F] {
+        &self.remainder
+    }
+
+    pub fn remainder_commitment(&self) -> &[u8] {
+        &self.remainder_commitment
+    }
 }
 
 pub struct FriProver<F: GpuField, D: Digest> {
     options: FriOptions,
     layers: Vec<FriLayer<F, D>>,
+    remainder_coeffs: Vec<F>,
 }
 
 struct FriLayer<F: GpuField, D: Digest> {
@@ -96,6 +354,32 @@ struct FriLayer<F: GpuField, D: Digest> {
     evaluations: Vec<F>,
 }
 
+/// A committed FRI layer saved in a checkpoint so proving can resume without
+/// folding and hashing it again (see [LdtProver::build_layers_from])
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FriLayerCheckpoint<F: Field> {
+    /// Evaluations in the order they were committed to
+    pub evaluations: Vec<F>,
+    /// Leaf hashes concatenated in order
+    pub leaves: Vec<u8>,
+}
+
+impl<F: Field> FriLayerCheckpoint<F> {
+    pub fn new<D: Digest>(evaluations: &[F], leaves: &[Output<D>]) -> Self {
+        FriLayerCheckpoint {
+            evaluations: evaluations.to_vec(),
+            leaves: leaves.iter().flatten().copied().collect(),
+        }
+    }
+
+    pub fn leaves<D: Digest>(&self) -> Vec<Output<D>> {
+        self.leaves
+            .chunks(<D as Digest>::output_size())
+            .map(Output::<D>::clone_from_slice)
+            .collect()
+    }
+}
+
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct FriProofLayer<F: Field> {
     values: Vec<F>,
@@ -120,6 +404,19 @@ where
         }
     }
 
+    /// Queried values in chunks of the layer's folding factor
+    pub fn values(&self) -> &[F] {
+        &self.values
+    }
+
+    pub fn proofs(&self) -> &[MerkleProof] {
+        &self.proofs
+    }
+
+    pub fn commitment(&self) -> &[u8] {
+        &self.commitment
+    }
+
     pub fn verify<D: Digest, const N: usize>(
         &self,
         positions: &[usize],
@@ -155,33 +452,48 @@ where
         FriProver {
             options,
             layers: Vec::new(),
+            remainder_coeffs: Vec::new(),
         }
     }
 
     pub fn into_proof(self, positions: &[usize]) -> FriProof<F> {
-        let folding_factor = self.options.folding_factor;
-        let (last_layer, initial_layers) = self.layers.split_last().unwrap();
-        let mut domain_size = self.layers[0].evaluations.len();
+        let (last_layer, initial_layers) = if self.options.remainder_coeffs {
+            (None, &*self.layers)
+        } else {
+            let (last_layer, initial_layers) = self.layers.split_last().unwrap();
+            (Some(last_layer), initial_layers)
+        };
+        let mut domain_size = self.layers.first().map_or(0, |l| l.evaluations.len());
         let mut proof_layers = Vec::new();
         let mut positions = positions.to_vec();
-        for layer in initial_layers {
-            let num_eval_chunks = domain_size / folding_factor;
-            positions = fold_positions(&positions, num_eval_chunks);
-            domain_size = num_eval_chunks;
+        for (i, layer) in initial_layers.iter().enumerate() {
+            let folding_factor = self.options.layer_folding_factor(i);
+            let order = self.options.evaluation_order;
+            positions = fold_positions(&positions, domain_size, folding_factor, order);
+            domain_size /= folding_factor;
 
             proof_layers.push(match folding_factor {
                 2 => query_layer::<F, D, 2>(layer, &positions),
                 4 => query_layer::<F, D, 4>(layer, &positions),
-                6 => query_layer::<F, D, 6>(layer, &positions),
                 8 => query_layer::<F, D, 8>(layer, &positions),
                 16 => query_layer::<F, D, 16>(layer, &positions),
                 _ => unimplemented!("folding factor {folding_factor} is not supported"),
             });
         }
 
-        // layers store interlaved evaluations so they need to be un-interleaved
+        let last_layer = match last_layer {
+            Some(last_layer) => last_layer,
+            None => return FriProof::new(proof_layers, Vec::new(), self.remainder_coeffs),
+        };
+
         let remainder_commitment = last_layer.tree.root().to_vec();
         let last_evals = &last_layer.evaluations;
+        if self.options.evaluation_order == EvaluationOrder::BitReversed {
+            return FriProof::new(proof_layers, remainder_commitment, last_evals.clone());
+        }
+
+        // layers store interlaved evaluations so they need to be un-interleaved
+        let folding_factor = self.options.layer_folding_factor(initial_layers.len());
         let mut remainder = vec![F::zero(); last_evals.len()];
         let num_eval_chunks = last_evals.len() / folding_factor;
         for i in 0..num_eval_chunks {
@@ -193,23 +505,172 @@ where
         FriProof::new(proof_layers, remainder_commitment, remainder)
     }
 
+    /// Runs FRI on a random linear combination `Σ β^i * codewords[i]` of
+    /// codewords over the same domain. All codewords must have the same
+    /// degree bound. `β` is drawn from the channel before any layer is
+    /// committed so callers must commit to the codewords beforehand.
+    pub fn build_batched_layers(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        codewords: Vec<GpuVec<F>>,
+    ) {
+        let batching_coeff = channel.draw_fri_alpha();
+        let combined_codeword = combine_codewords(codewords, batching_coeff);
+        self.build_layers(channel, combined_codeword)
+    }
+
     pub fn build_layers(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+    ) {
+        self.build_layers_from(channel, evaluations, Vec::new())
+    }
+
+    /// Same as [FriProver::build_layers] but the first layers are restored
+    /// from `restored_layers` rather than folded and hashed again. Layers
+    /// that don't match the options are folded again.
+    pub fn build_layers_from(
         &mut self,
         channel: &mut impl ProverChannel<F, Digest = D>,
         mut evaluations: GpuVec<F>,
+        mut restored_layers: Vec<FriLayerCheckpoint<F>>,
     ) {
         assert!(self.layers.is_empty());
         // let codeword = evaluations.0[0];
 
-        for _ in 0..self.options.num_layers(evaluations.len()) + 1 {
-            evaluations = match self.options.folding_factor {
-                2 => self.build_layer::<2>(channel, evaluations),
-                4 => self.build_layer::<4>(channel, evaluations),
-                8 => self.build_layer::<8>(channel, evaluations),
-                16 => self.build_layer::<16>(channel, evaluations),
+        let n = evaluations.len();
+        let order = self.options.evaluation_order;
+        let num_layers = if self.options.early_stop {
+            let domain = Radix2EvaluationDomain::new(n).unwrap();
+            let evals = natural_order(evaluations.to_vec_in(PageAlignedAllocator), order);
+            let coeffs = ifft(evals, domain);
+            let num_coeffs = coeffs
+                .iter()
+                .rposition(|c| !c.is_zero())
+                .map_or(0, |i| i + 1);
+            self.options.num_early_stop_layers(n, num_coeffs)
+        } else {
+            self.options.num_layers(n)
+        };
+        let num_committed_layers = if self.options.remainder_coeffs {
+            num_layers
+        } else {
+            num_layers + 1
+        };
+
+        if !self.can_restore(n, num_committed_layers, &restored_layers) {
+            restored_layers.clear();
+        }
+        let num_restored_layers = restored_layers.len();
+        for (i, layer) in restored_layers.into_iter().enumerate() {
+            let tree = MerkleTree::new_with_arity(layer.leaves::<D>(), self.options.merkle_arity)
+                .unwrap()
+                .with_cap_height(self.layer_cap_height(i, num_layers));
+            channel.commit_fri_layer(&tree.commitment());
+            let alpha = channel.draw_fri_alpha();
+            if i + 1 == num_restored_layers {
+                let folding_factor = self.options.layer_folding_factor(i);
+                let layer_evaluations = self.folding_order(&layer.evaluations, folding_factor);
+                evaluations = match folding_factor {
+                    2 => self.fold::<2>(layer_evaluations, alpha),
+                    4 => self.fold::<4>(layer_evaluations, alpha),
+                    8 => self.fold::<8>(layer_evaluations, alpha),
+                    16 => self.fold::<16>(layer_evaluations, alpha),
+                    folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+                };
+            }
+            self.layers.push(FriLayer {
+                tree,
+                evaluations: layer.evaluations,
+            });
+        }
+
+        for i in num_restored_layers..num_committed_layers {
+            let cap_height = self.layer_cap_height(i, num_layers);
+            evaluations = match self.options.layer_folding_factor(i) {
+                2 => self.build_layer::<2>(channel, evaluations, cap_height),
+                4 => self.build_layer::<4>(channel, evaluations, cap_height),
+                8 => self.build_layer::<8>(channel, evaluations, cap_height),
+                16 => self.build_layer::<16>(channel, evaluations, cap_height),
                 folding_factor => unreachable!("folding factor {folding_factor} not supported"),
             }
         }
+
+        if self.options.remainder_coeffs {
+            let n = evaluations.len();
+            let domain = Radix2EvaluationDomain::new(n).unwrap();
+            let mut coeffs = ifft(natural_order(evaluations, order), domain).to_vec();
+            let mut max_num_coeffs = core::cmp::max(n / self.options.blowup_factor, 1);
+            if self.options.early_stop {
+                max_num_coeffs = max_num_coeffs.min(self.options.max_remainder_size);
+            }
+            debug_assert!(coeffs[max_num_coeffs..].iter().all(|c| c.is_zero()));
+            coeffs.truncate(max_num_coeffs);
+            channel.commit_fri_remainder(&coeffs);
+            self.remainder_coeffs = coeffs;
+        }
+    }
+
+    /// Height of the Merkle cap of layer `i`. The remainder is committed to by
+    /// its root.
+    fn layer_cap_height(&self, i: usize, num_layers: usize) -> usize {
+        if i < num_layers {
+            self.options.cap_height
+        } else {
+            0
+        }
+    }
+
+    /// Returns true if `restored_layers` are the first layers of a codeword
+    /// of length `n` with `num_committed_layers` committed layers
+    fn can_restore(
+        &self,
+        mut n: usize,
+        num_committed_layers: usize,
+        restored_layers: &[FriLayerCheckpoint<F>],
+    ) -> bool {
+        if restored_layers.len() > num_committed_layers {
+            return false;
+        }
+        restored_layers.iter().enumerate().all(|(i, layer)| {
+            let folding_factor = self.options.layer_folding_factor(i);
+            let num_leaves = n / folding_factor;
+            let is_valid = layer.evaluations.len() == n
+                && layer.leaves.len() == num_leaves * <D as Digest>::output_size();
+            n = num_leaves;
+            is_valid
+        })
+    }
+
+    /// Evaluations of a layer in the order they're folded in given the order
+    /// they were committed to (see [FriProver::build_layer])
+    fn folding_order(&self, evaluations: &[F], folding_factor: usize) -> GpuVec<F> {
+        let n = evaluations.len();
+        let mut res = Vec::with_capacity_in(n, PageAlignedAllocator);
+        match self.options.evaluation_order {
+            EvaluationOrder::Natural => {
+                // undo the interleaving of the committed evaluations
+                let num_chunks = n / folding_factor;
+                res.extend((0..n).map(|i| {
+                    let (j, chunk) = (i / num_chunks, i % num_chunks);
+                    evaluations[chunk * folding_factor + j]
+                }));
+            }
+            EvaluationOrder::BitReversed => res.extend_from_slice(evaluations),
+        }
+        res
+    }
+
+    /// Folds `evaluations` by `N` with the random challenge `alpha`
+    fn fold<const N: usize>(&self, evaluations: GpuVec<F>, alpha: F) -> GpuVec<F> {
+        let domain_offset = self.options.domain_offset::<F>();
+        match self.options.evaluation_order {
+            EvaluationOrder::Natural => apply_drp(evaluations, domain_offset, alpha, N),
+            EvaluationOrder::BitReversed => {
+                apply_drp_bit_reversed::<F, N>(&evaluations, domain_offset, alpha)
+            }
+        }
     }
 
     /// Builds a single layer of the FRI protocol
@@ -218,6 +679,7 @@ where
         &mut self,
         channel: &mut impl ProverChannel<F, Digest = D>,
         mut evaluations: GpuVec<F>,
+        cap_height: usize,
     ) -> GpuVec<F> {
         // Each layer requires decommitting to `folding_factor` many evaluations e.g.
         // `folding_factor = 2` decommits to an evaluation for LHS_i and RHS_i
@@ -225,7 +687,11 @@ where
         // committed to in their natural order. If we instead commit to interleaved
         // evaluations i.e. [[LHS0, RHS0], [LHS1, RHS1], ...] LHS_i and RHS_i
         // only require a single merkle path for their decommitment.
-        let interleaved_evals: Vec<[F; N]> = interleave(&evaluations);
+        let order = self.options.evaluation_order;
+        let interleaved_evals: Vec<[F; N]> = match order {
+            EvaluationOrder::Natural => interleave(&evaluations),
+            EvaluationOrder::BitReversed => evaluations.as_chunks::<N>().0.to_vec(),
+        };
         let hashed_evals = ark_std::cfg_iter!(interleaved_evals)
             .map(|chunk| {
                 let mut buff = Vec::with_capacity(chunk.compressed_size());
@@ -234,21 +700,20 @@ where
             })
             .collect();
 
-        let evals_merkle_tree = MerkleTree::new(hashed_evals).unwrap();
-        channel.commit_fri_layer(evals_merkle_tree.root());
+        let evals_merkle_tree = MerkleTree::new_with_arity(hashed_evals, self.options.merkle_arity)
+            .unwrap()
+            .with_cap_height(cap_height);
+        channel.commit_fri_layer(&evals_merkle_tree.commitment());
 
         let alpha = channel.draw_fri_alpha();
-        evaluations = apply_drp(
-            evaluations,
-            self.options.domain_offset::<F>(),
-            alpha,
-            self.options.folding_factor,
-        );
-
-        self.layers.push(FriLayer {
+        evaluations = self.fold::<N>(evaluations, alpha);
+
+        let layer = FriLayer {
             tree: evals_merkle_tree,
             evaluations: interleaved_evals.into_flattened(),
-        });
+        };
+        channel.save_fri_layer(&layer.evaluations, layer.tree.leaves());
+        self.layers.push(layer);
 
         evaluations
     }
@@ -256,10 +721,22 @@ where
 
 #[derive(Debug, Snafu)]
 pub enum VerificationError {
-    #[snafu(display("queries do not resolve to their commitment in layer {layer}"))]
+    #[snafu(display("commitment of layer {layer} is malformed"))]
     LayerCommitmentInvalid { layer: usize },
-    #[snafu(display("degree respecting projection is invalid for layer {layer}"))]
-    InvalidDegreeRespectingProjection { layer: usize },
+    #[snafu(display(
+        "query at position {position} does not resolve to the commitment of layer {layer}"
+    ))]
+    LayerQueryInvalid { layer: usize, position: usize },
+    #[snafu(display("layer {layer} opens {actual} values, expected {expected}"))]
+    NumLayerValuesMismatch {
+        layer: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display(
+        "degree respecting projection is invalid for query {query} of layer {layer}"
+    ))]
+    InvalidDegreeRespectingProjection { layer: usize, query: usize },
     #[snafu(display("the number of query positions does not match the number of evaluations"))]
     NumPositionEvaluationMismatch,
     #[snafu(display("remainder does not resolve to its commitment"))]
@@ -270,6 +747,16 @@ pub enum VerificationError {
     RemainderDegreeMismatch { degree: usize },
     #[snafu(display("degree-respecting projection is invalid at the last layer"))]
     InvalidRemainderDegreeRespectingProjection,
+    #[snafu(display("folding factor {folding_factor} is not supported"))]
+    UnsupportedFoldingFactor { folding_factor: usize },
+    #[snafu(display("proof has {actual} FRI layers but the options require {expected}"))]
+    NumLayersMismatch { expected: usize, actual: usize },
+    #[snafu(display("folding schedule is too long or has an unsupported folding factor"))]
+    UnsupportedFoldingSchedule,
+    #[snafu(display("evaluation domain is too large or not a power of two"))]
+    InvalidDomain,
+    #[snafu(display("query position {position} is outside the domain of size {domain_size}"))]
+    PositionOutOfRange { position: usize, domain_size: usize },
     #[snafu(display("{size} can't be divided by {folding_factor} (layer {layer})"))]
     CodewordTruncation {
         size: usize,
@@ -283,10 +770,12 @@ where
     F::FftField: FftField,
 {
     options: FriOptions,
-    layer_commitments: Vec<Output<D>>,
+    /// Merkle cap of each layer
+    layer_commitments: Vec<Vec<Output<D>>>,
     layer_alphas: Vec<F>,
     proof: FriProof<F>,
     domain: Radix2EvaluationDomain<F::FftField>,
+    batching_coeff: Option<F>,
 }
 
 impl<F: GpuField + Field, D: Digest> FriVerifier<F, D>
@@ -295,24 +784,55 @@ where
     F::FftField: FftField,
 {
     pub fn new(
-        public_coin: &mut PublicCoin<impl Digest>,
+        public_coin: &mut impl PublicCoin,
         options: FriOptions,
         proof: FriProof<F>,
         max_poly_degree: usize,
     ) -> Result<Self, VerificationError> {
+        if !options.folding_schedule.is_supported() {
+            return Err(VerificationError::UnsupportedFoldingSchedule);
+        }
         let folding_factor = options.folding_factor;
+        if !is_supported_folding_factor(folding_factor) {
+            return Err(VerificationError::UnsupportedFoldingFactor { folding_factor });
+        }
+
         let domain_offset = options.domain_offset::<F>();
-        let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
-        let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
+        let domain_size = max_poly_degree
+            .checked_next_power_of_two()
+            .and_then(|len| len.checked_mul(options.blowup_factor))
+            .filter(|size| size.is_power_of_two())
+            .ok_or(VerificationError::InvalidDomain)?;
+        let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset)
+            .ok_or(VerificationError::InvalidDomain)?;
+
+        let max_num_layers = options.num_layers(domain_size);
+        let num_layers = proof.layers.len();
+        let is_valid_num_layers = if options.early_stop {
+            num_layers <= max_num_layers
+        } else {
+            num_layers == max_num_layers
+        };
+        if !is_valid_num_layers {
+            return Err(VerificationError::NumLayersMismatch {
+                expected: max_num_layers,
+                actual: num_layers,
+            });
+        }
 
         let mut layer_alphas = Vec::new();
         let mut layer_commitments = Vec::new();
         let mut layer_codeword_len = domain_size;
         for (i, layer) in proof.layers.iter().enumerate() {
             // TODO: batch merkle tree proofs
-            // get the merkle root from the first merkle path
-            let layer_commitment = Output::<D>::from_slice(&layer.commitment).clone();
-            public_coin.reseed(&layer_commitment.deref());
+            let folding_factor = options.layer_folding_factor(i);
+            let num_leaves = layer_codeword_len / folding_factor;
+            let cap_size =
+                MerkleTree::<D>::cap_size(num_leaves, options.cap_height, options.merkle_arity);
+            let layer_commitment = MerkleTree::<D>::cap_from_bytes(&layer.commitment)
+                .filter(|cap| cap.len() == cap_size)
+                .ok_or(VerificationError::LayerCommitmentInvalid { layer: i })?;
+            public_coin.reseed_with_label(&label::fri_layer(i), &layer.commitment.as_slice());
             let alpha = public_coin.draw();
             layer_alphas.push(alpha);
             layer_commitments.push(layer_commitment);
@@ -328,11 +848,19 @@ where
             layer_codeword_len /= folding_factor;
         }
 
-        let remainder_root = Output::<D>::from_slice(&proof.remainder_commitment).clone();
-        public_coin.reseed(&remainder_root.deref());
-        let remainder_alpha = public_coin.draw();
-        layer_alphas.push(remainder_alpha);
-        layer_commitments.push(remainder_root);
+        let remainder_label = label::fri_layer(proof.layers.len());
+        if options.remainder_coeffs {
+            public_coin.reseed_with_label(&remainder_label, &proof.remainder);
+        } else {
+            if proof.remainder_commitment.len() != <D as Digest>::output_size() {
+                return Err(VerificationError::RemainderCommitmentInvalid);
+            }
+            let remainder_root = Output::<D>::from_slice(&proof.remainder_commitment).clone();
+            public_coin.reseed_with_label(&remainder_label, &remainder_root.deref());
+            let remainder_alpha = public_coin.draw();
+            layer_alphas.push(remainder_alpha);
+            layer_commitments.push(vec![remainder_root]);
+        }
 
         Ok(FriVerifier {
             options,
@@ -340,72 +868,160 @@ where
             layer_commitments,
             layer_alphas,
             proof,
+            batching_coeff: None,
         })
     }
 
-    pub fn verify_generic<const N: usize>(
+    /// Verifier for a proof generated by [FriProver::build_batched_layers].
+    /// Draws the batching coefficient before the layer commitments are
+    /// absorbed.
+    pub fn new_batched(
+        public_coin: &mut impl PublicCoin,
+        options: FriOptions,
+        proof: FriProof<F>,
+        max_poly_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        let batching_coeff = public_coin.draw();
+        let mut verifier = Self::new(public_coin, options, proof, max_poly_degree)?;
+        verifier.batching_coeff = Some(batching_coeff);
+        Ok(verifier)
+    }
+
+    /// Verifies a batched proof. `evaluations[i]` are the evaluations of the
+    /// `i`th polynomial at the query positions.
+    pub fn verify_batched(
         self,
         positions: &[usize],
-        evaluations: &[F],
+        evaluations: &[Vec<F>],
     ) -> Result<(), VerificationError> {
-        let domain_offset = self.domain.coset_offset();
-        let folding_domain = Radix2EvaluationDomain::new(N).unwrap();
+        let batching_coeff = self.batching_coeff.expect("verifier is not batched");
+        if evaluations
+            .iter()
+            .any(|evals| evals.len() != positions.len())
+        {
+            return Err(VerificationError::NumPositionEvaluationMismatch);
+        }
 
-        let mut layers = self.proof.layers.into_iter();
+        let mut combined_evaluations = vec![F::zero(); positions.len()];
+        for evals in evaluations.iter().rev() {
+            for (combined, eval) in combined_evaluations.iter_mut().zip(evals) {
+                *combined *= batching_coeff;
+                *combined += eval;
+            }
+        }
+
+        self.verify(positions, &combined_evaluations)
+    }
+
+    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
+        if positions.len() != evaluations.len() {
+            return Err(VerificationError::NumPositionEvaluationMismatch);
+        }
+        let domain_size = self.domain.size();
+        if let Some(&position) = positions.iter().find(|&&position| position >= domain_size) {
+            return Err(VerificationError::PositionOutOfRange {
+                position,
+                domain_size,
+            });
+        }
+
+        let domain_offset = self.domain.coset_offset();
+        let mut layers = self.proof.layers.iter();
         let mut layer_alphas = self.layer_alphas.into_iter();
         let mut layer_commitments = self.layer_commitments.into_iter();
         let mut positions = positions.to_vec();
         let mut evaluations = evaluations.to_vec();
-        let mut domain_size = self.domain.size();
+        let mut domain_size = domain_size;
         let mut domain_generator = self.domain.group_gen();
 
         // verify all layers
-        for i in 0..self.options.num_layers(domain_size) {
-            let folded_positions = fold_positions(&positions, domain_size / N);
+        let num_layers = self.proof.layers.len();
+        for i in 0..num_layers {
+            let layer = layers.next().unwrap();
             let layer_alpha = layer_alphas.next().unwrap();
             let layer_commitment = layer_commitments.next().unwrap();
+            let folding_factor = self.options.layer_folding_factor(i);
+            (positions, evaluations) = match folding_factor {
+                2 => verify_layer::<F, D, 2>(
+                    i,
+                    layer,
+                    &layer_commitment,
+                    layer_alpha,
+                    &positions,
+                    &evaluations,
+                    domain_size,
+                    domain_offset,
+                    domain_generator,
+                    self.options.evaluation_order,
+                    self.options.merkle_arity,
+                ),
+                4 => verify_layer::<F, D, 4>(
+                    i,
+                    layer,
+                    &layer_commitment,
+                    layer_alpha,
+                    &positions,
+                    &evaluations,
+                    domain_size,
+                    domain_offset,
+                    domain_generator,
+                    self.options.evaluation_order,
+                    self.options.merkle_arity,
+                ),
+                8 => verify_layer::<F, D, 8>(
+                    i,
+                    layer,
+                    &layer_commitment,
+                    layer_alpha,
+                    &positions,
+                    &evaluations,
+                    domain_size,
+                    domain_offset,
+                    domain_generator,
+                    self.options.evaluation_order,
+                    self.options.merkle_arity,
+                ),
+                16 => verify_layer::<F, D, 16>(
+                    i,
+                    layer,
+                    &layer_commitment,
+                    layer_alpha,
+                    &positions,
+                    &evaluations,
+                    domain_size,
+                    domain_offset,
+                    domain_generator,
+                    self.options.evaluation_order,
+                    self.options.merkle_arity,
+                ),
+                // TODO: move this to options
+                folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+            }?;
 
-            // TODO: change assert to error. Check remainder
-            let layer = layers.next().unwrap();
-            let (chunks, _) = &layer.values.as_chunks::<N>();
-            assert_eq!(chunks.len(), folded_positions.len());
-
-            // verify the layer values against the layer's commitment
-            for (j, position) in folded_positions.iter().enumerate() {
-                let proof = layer.proofs[j].parse::<D>();
-                let expected_leaf = &proof[0];
-                let chunk = chunks[j];
-                let mut chunk_bytes = Vec::with_capacity(chunk.compressed_size());
-                chunk.serialize_compressed(&mut chunk_bytes).unwrap();
-                let actual_leaf = D::new_with_prefix(&chunk_bytes).finalize();
-
-                if *expected_leaf != actual_leaf {
-                    return Err(VerificationError::LayerCommitmentInvalid { layer: i });
-                }
-
-                MerkleTree::<D>::verify(&layer_commitment, &proof, *position)
-                    .map_err(|_| VerificationError::LayerCommitmentInvalid { layer: i })?
-            }
-
-            let query_values = get_query_values(chunks, &positions, &folded_positions, domain_size);
-            if evaluations != query_values {
-                return Err(VerificationError::InvalidDegreeRespectingProjection { layer: i });
-            }
+            // prepare for next layer
+            domain_generator = domain_generator.pow([folding_factor as u64]);
+            domain_size /= folding_factor;
+        }
 
-            let polys = chunks
-                .iter()
-                .zip(&folded_positions)
-                .map(|(chunk, position)| {
-                    let offset = domain_offset * domain_generator.pow([*position as u64]);
-                    let domain = folding_domain.get_coset(offset).unwrap();
-                    DensePolynomial::from_coefficients_vec(domain.ifft(chunk))
-                });
+        // the remainder has the same rate as the original codeword
+        let mut max_num_coeffs = core::cmp::max(domain_size / self.options.blowup_factor, 1);
+        if self.options.early_stop {
+            max_num_coeffs = max_num_coeffs.min(self.options.max_remainder_size);
+        }
+        if self.options.remainder_coeffs {
+            return verify_remainder_coeffs(
+                &self.proof.remainder,
+                &positions,
+                &evaluations,
+                domain_size,
+                domain_generator,
+                max_num_coeffs,
+                self.options.evaluation_order,
+            );
+        }
 
-            // prepare for next layer
-            evaluations = polys.map(|poly| poly.evaluate(&layer_alpha)).collect();
-            positions = folded_positions;
-            domain_generator = domain_generator.pow([N as u64]);
-            domain_size /= N;
+        if self.proof.remainder.len() != domain_size {
+            return Err(VerificationError::RemainderTooSmall);
         }
 
         for (position, evaluation) in positions.into_iter().zip(evaluations) {
@@ -414,33 +1030,185 @@ where
             }
         }
 
-        verify_remainder::<F, D, N>(
-            layer_commitments.next().unwrap(),
-            self.proof.remainder,
-            domain_size - 1,
-        )
+        let remainder_commitment = layer_commitments.next().unwrap().remove(0);
+        let remainder = self.proof.remainder;
+        let max_degree = max_num_coeffs
+            .checked_sub(1)
+            .ok_or(VerificationError::RemainderTooSmall)?;
+        let order = self.options.evaluation_order;
+        let arity = self.options.merkle_arity;
+        let verify = match self.options.layer_folding_factor(num_layers) {
+            2 => verify_remainder::<F, D, 2>,
+            4 => verify_remainder::<F, D, 4>,
+            8 => verify_remainder::<F, D, 8>,
+            16 => verify_remainder::<F, D, 16>,
+            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+        };
+        verify(remainder_commitment, remainder, max_degree, order, arity)
     }
+}
 
-    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
-        if positions.len() != evaluations.len() {
-            return Err(VerificationError::NumPositionEvaluationMismatch);
+/// Verifies the queried values of a single layer against the layer's
+/// commitment and the evaluations of the previous layer. Returns the folded
+/// positions and evaluations for the next layer.
+#[allow(clippy::too_many_arguments)]
+fn verify_layer<F: GpuField + Field, D: Digest, const N: usize>(
+    i: usize,
+    layer: &FriProofLayer<F>,
+    layer_commitment: &[Output<D>],
+    layer_alpha: F,
+    positions: &[usize],
+    evaluations: &[F],
+    domain_size: usize,
+    domain_offset: F::FftField,
+    domain_generator: F::FftField,
+    order: EvaluationOrder,
+    merkle_arity: usize,
+) -> Result<(Vec<usize>, Vec<F>), VerificationError>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    let folding_constants = FoldingConstants::<F::FftField, N>::new();
+    let folded_positions = fold_positions(positions, domain_size, N, order);
+
+    let (chunks, remainder) = &layer.values.as_chunks::<N>();
+    if chunks.len() != folded_positions.len() || !remainder.is_empty() {
+        return Err(VerificationError::NumLayerValuesMismatch {
+            layer: i,
+            expected: folded_positions.len() * N,
+            actual: layer.values.len(),
+        });
+    }
+
+    // verify the layer values against the layer's commitment
+    for (j, position) in folded_positions.iter().enumerate() {
+        let invalid_query = || VerificationError::LayerQueryInvalid {
+            layer: i,
+            position: *position,
+        };
+        let proof = layer.proofs.get(j).ok_or_else(invalid_query)?.parse::<D>();
+        let expected_leaf = proof.first().ok_or_else(invalid_query)?;
+        let chunk = chunks[j];
+        let mut chunk_bytes = Vec::with_capacity(chunk.compressed_size());
+        chunk.serialize_compressed(&mut chunk_bytes).unwrap();
+        let actual_leaf = D::new_with_prefix(&chunk_bytes).finalize();
+
+        if *expected_leaf != actual_leaf {
+            return Err(invalid_query());
         }
 
-        match self.options.folding_factor {
-            2 => self.verify_generic::<2>(positions, evaluations),
-            4 => self.verify_generic::<4>(positions, evaluations),
-            8 => self.verify_generic::<8>(positions, evaluations),
-            16 => self.verify_generic::<16>(positions, evaluations),
-            // TODO: move this to options
-            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+        MerkleTree::<D>::verify_cap(layer_commitment, &proof, *position, merkle_arity)
+            .map_err(|_| invalid_query())?
+    }
+
+    let query_values = get_query_values(chunks, positions, &folded_positions, domain_size, order);
+    if let Some(query) = evaluations
+        .iter()
+        .zip(&query_values)
+        .position(|(a, b)| a != b)
+    {
+        return Err(VerificationError::InvalidDegreeRespectingProjection { layer: i, query });
+    }
+
+    let folded_evaluations = chunks
+        .iter()
+        .zip(&folded_positions)
+        .map(|(chunk, &position)| {
+            let exponent = order.domain_index(position, domain_size / N);
+            let x = domain_offset * domain_generator.pow([exponent as u64]);
+            folding_constants.fold(&natural_chunk(chunk, order), x, layer_alpha)
+        })
+        .collect();
+
+    Ok((folded_positions, folded_evaluations))
+}
+
+/// Constants for folding chunks of `N` evaluations. Computed once per layer
+/// rather than once per query.
+struct FoldingConstants<F: FftField, const N: usize> {
+    /// Inverses of the `N`th roots of unity `ω_N^-k`
+    roots_inv: [F; N],
+    n_inv: F,
+}
+
+impl<F: FftField, const N: usize> FoldingConstants<F, N> {
+    fn new() -> Self {
+        let root_inv = F::get_root_of_unity(N as u64).unwrap().inverse().unwrap();
+        let mut roots_inv = [F::one(); N];
+        for k in 1..N {
+            roots_inv[k] = roots_inv[k - 1] * root_inv;
+        }
+        let n_inv = F::from(N as u64).inverse().unwrap();
+        FoldingConstants { roots_inv, n_inv }
+    }
+
+    /// Evaluates the interpolant of `chunk` over the coset `x * <ω_N>` at
+    /// `alpha` i.e. `1/N * Σ_k chunk[k] * Σ_j (alpha / (x * ω_N^k))^j`.
+    /// Matches the evaluations of the folded layer computed by [apply_drp].
+    fn fold<E: Field + DomainCoeff<F>>(&self, chunk: &[E; N], x: F, alpha: E) -> E {
+        let x_inv = x.inverse().unwrap();
+        let mut acc = E::zero();
+        for (&value, &root_inv) in chunk.iter().zip(&self.roots_inv) {
+            let mut ratio = alpha;
+            ratio *= x_inv * root_inv;
+            let mut term = value;
+            let mut sum = value;
+            for _ in 1..N {
+                term *= ratio;
+                sum += term;
+            }
+            acc += sum;
+        }
+        acc *= self.n_inv;
+        acc
+    }
+}
+
+/// Checks the remainder polynomial has at most `max_num_coeffs` coefficients
+/// and evaluates to the folded evaluations at the query positions. Position
+/// `i` corresponds to the point `generator^order.domain_index(i)`.
+fn verify_remainder_coeffs<F: GpuField + Field>(
+    coeffs: &[F],
+    positions: &[usize],
+    evaluations: &[F],
+    domain_size: usize,
+    generator: F::FftField,
+    max_num_coeffs: usize,
+    order: EvaluationOrder,
+) -> Result<(), VerificationError>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    if coeffs.len() > max_num_coeffs {
+        return Err(VerificationError::RemainderDegreeMismatch {
+            degree: max_num_coeffs.saturating_sub(1),
+        });
+    }
+
+    for (&position, &evaluation) in positions.iter().zip(evaluations) {
+        let x = generator.pow([order.domain_index(position, domain_size) as u64]);
+        let mut remainder_eval = F::zero();
+        for &coeff in coeffs.iter().rev() {
+            remainder_eval *= x;
+            remainder_eval += coeff;
+        }
+
+        if remainder_eval != evaluation {
+            return Err(VerificationError::InvalidRemainderDegreeRespectingProjection);
         }
     }
+
+    Ok(())
 }
 
 fn verify_remainder<F: GpuField + Field, D: Digest, const N: usize>(
     commitment: Output<D>,
-    mut remainder_evals: Vec<F>,
+    remainder_evals: Vec<F>,
     max_degree: usize,
+    order: EvaluationOrder,
+    merkle_arity: usize,
 ) -> Result<(), VerificationError>
 where
     F: DomainCoeff<F::FftField>,
@@ -450,7 +1218,10 @@ where
         return Err(VerificationError::RemainderTooSmall);
     }
 
-    let interleaved_evals: Vec<[F; N]> = interleave(&remainder_evals);
+    let interleaved_evals: Vec<[F; N]> = match order {
+        EvaluationOrder::Natural => interleave(&remainder_evals),
+        EvaluationOrder::BitReversed => remainder_evals.as_chunks::<N>().0.to_vec(),
+    };
     let hashed_evals = interleaved_evals
         .into_iter()
         .map(|chunk| {
@@ -459,12 +1230,18 @@ where
             D::new_with_prefix(&buff).finalize()
         })
         .collect();
-    let remainder_merkle_tree = MerkleTree::<D>::new(hashed_evals).unwrap();
+    let remainder_merkle_tree = MerkleTree::<D>::new_with_arity(hashed_evals, merkle_arity)
+        .map_err(|_| VerificationError::RemainderCommitmentInvalid)?;
 
     if commitment != *remainder_merkle_tree.root() {
         return Err(VerificationError::RemainderCommitmentInvalid);
     }
 
+    let mut remainder_evals = remainder_evals;
+    if order == EvaluationOrder::BitReversed {
+        bit_reverse(&mut remainder_evals);
+    }
+
     if max_degree == 0 {
         if remainder_evals.array_windows().all(|[a, b]| a == b) {
             Ok(())
@@ -472,7 +1249,8 @@ where
             Err(VerificationError::RemainderDegreeMismatch { degree: max_degree })
         }
     } else {
-        let domain = Radix2EvaluationDomain::new(remainder_evals.len()).unwrap();
+        let domain = Radix2EvaluationDomain::new(remainder_evals.len())
+            .ok_or(VerificationError::InvalidDomain)?;
         domain.ifft_in_place(&mut remainder_evals);
         let poly = DensePolynomial::from_coefficients_vec(remainder_evals);
 
@@ -487,9 +1265,422 @@ where
 pub trait ProverChannel<F: GpuField> {
     type Digest: Digest;
 
-    fn commit_fri_layer(&mut self, layer_root: &Output<Self::Digest>);
+    /// Absorbs the Merkle cap (or root) of a layer
+    fn commit_fri_layer(&mut self, commitment: &[u8]);
 
     fn draw_fri_alpha(&mut self) -> F;
+
+    /// Absorbs the coefficients of the remainder polynomial. Only used if
+    /// [FriOptions::with_remainder_coeffs] is set.
+    fn commit_fri_remainder(&mut self, coeffs: &[F]);
+
+    /// Called once a layer is committed to with its evaluations in committed
+    /// order and its leaf hashes so provers can checkpoint it (see
+    /// [FriLayerCheckpoint])
+    fn save_fri_layer(&mut self, _evaluations: &[F], _leaves: &[Output<Self::Digest>]) {}
+}
+
+/// Prover side of a low-degree test. Proves the evaluations of a polynomial
+/// over the LDE domain are close to a polynomial of bounded degree. [FriProver]
+/// is the default but alternative tests e.g. STIR-style tests with fewer
+/// queries can be used instead by setting `Air::LdtVerifier` to a verifier
+/// whose [LdtVerifier::Prover] is the alternative prover.
+pub trait LdtProver<F: GpuField + Field, D: Digest>: Sized {
+    type Proof: CanonicalSerialize + CanonicalDeserialize + Clone;
+
+    fn new(options: FriOptions) -> Self;
+
+    /// Commits to `evaluations` and any subsequent rounds of the test
+    fn build_layers(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+    );
+
+    /// Same as [LdtProver::build_layers] but resumes from layers saved with
+    /// [ProverChannel::save_fri_layer]. Provers that can't resume ignore
+    /// `restored_layers`.
+    fn build_layers_from(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+        _restored_layers: Vec<FriLayerCheckpoint<F>>,
+    ) {
+        self.build_layers(channel, evaluations)
+    }
+
+    /// Opens the commitments at the query `positions`
+    fn into_proof(self, positions: &[usize]) -> Self::Proof;
+}
+
+/// Verifier side of a low-degree test. Set by `Air::LdtVerifier`.
+pub trait LdtVerifier<F: GpuField + Field, D: Digest>: Sized {
+    type Proof: CanonicalSerialize + CanonicalDeserialize + Clone;
+    /// Prover whose proofs this verifier accepts
+    type Prover: LdtProver<F, D, Proof = Self::Proof>;
+
+    /// Absorbs the commitments in `proof`. Must mirror the transcript of
+    /// [LdtProver::build_layers].
+    fn new(
+        public_coin: &mut impl PublicCoin,
+        options: FriOptions,
+        proof: Self::Proof,
+        max_poly_degree: usize,
+    ) -> Result<Self, VerificationError>;
+
+    /// Checks `evaluations` at query `positions` are consistent with a
+    /// polynomial of degree at most `max_poly_degree`
+    fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError>;
+}
+
+impl<F: GpuField + Field, D: Digest> LdtProver<F, D> for FriProver<F, D>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    type Proof = FriProof<F>;
+
+    fn new(options: FriOptions) -> Self {
+        FriProver::new(options)
+    }
+
+    fn build_layers(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+    ) {
+        FriProver::build_layers(self, channel, evaluations)
+    }
+
+    fn build_layers_from(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+        restored_layers: Vec<FriLayerCheckpoint<F>>,
+    ) {
+        FriProver::build_layers_from(self, channel, evaluations, restored_layers)
+    }
+
+    fn into_proof(self, positions: &[usize]) -> FriProof<F> {
+        FriProver::into_proof(self, positions)
+    }
+}
+
+impl<F: GpuField + Field, D: Digest> LdtVerifier<F, D> for FriVerifier<F, D>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    type Proof = FriProof<F>;
+    type Prover = FriProver<F, D>;
+
+    fn new(
+        public_coin: &mut impl PublicCoin,
+        options: FriOptions,
+        proof: FriProof<F>,
+        max_poly_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        FriVerifier::new(public_coin, options, proof, max_poly_degree)
+    }
+
+    fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
+        FriVerifier::verify(self, positions, evaluations)
+    }
+}
+
+/// Standalone proof that a vector of evaluations is close to a low degree
+/// polynomial. Generated by [prove_low_degree].
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct LowDegreeProof<F: Field> {
+    /// Evaluations at the query positions drawn from the public coin
+    pub queried_evaluations: Vec<F>,
+    pub fri_proof: FriProof<F>,
+}
+
+/// FRI prover channel backed by a public coin
+struct PublicCoinChannel<'a, C: PublicCoin, D: Digest> {
+    public_coin: &'a mut C,
+    num_layers: usize,
+    _digest: core::marker::PhantomData<D>,
+}
+
+impl<F: GpuField + Field, C: PublicCoin, D: Digest> ProverChannel<F>
+    for PublicCoinChannel<'_, C, D>
+{
+    type Digest = D;
+
+    fn commit_fri_layer(&mut self, commitment: &[u8]) {
+        let label = label::fri_layer(self.num_layers);
+        self.public_coin.reseed_with_label(&label, &commitment);
+        self.num_layers += 1;
+    }
+
+    fn draw_fri_alpha(&mut self) -> F {
+        self.public_coin.draw()
+    }
+
+    fn commit_fri_remainder(&mut self, coeffs: &[F]) {
+        let label = label::fri_layer(self.num_layers);
+        self.public_coin.reseed_with_label(&label, &coeffs);
+    }
+}
+
+/// Proves `evaluations` are close to a polynomial of degree less than
+/// `evaluations.len() / blowup_factor`. Evaluations must be over the coset
+/// `options.domain_offset() * <ω>` in [FriOptions::evaluation_order]. Query
+/// positions are drawn from `public_coin` once all layers are committed.
+/// Independent of any AIR.
+pub fn prove_low_degree<F: GpuField + Field, D: Digest>(
+    evaluations: GpuVec<F>,
+    options: FriOptions,
+    num_queries: usize,
+    public_coin: &mut impl PublicCoin,
+) -> LowDegreeProof<F>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    let domain_size = evaluations.len();
+    assert!(domain_size.is_power_of_two());
+    let mut channel = PublicCoinChannel::<_, D> {
+        public_coin,
+        num_layers: 0,
+        _digest: core::marker::PhantomData,
+    };
+    let mut prover = FriProver::<F, D>::new(options);
+    prover.build_layers(&mut channel, evaluations.to_vec_in(PageAlignedAllocator));
+    let positions = channel
+        .public_coin
+        .draw_unique_queries(num_queries, domain_size);
+    let queried_evaluations = positions.iter().map(|&i| evaluations[i]).collect();
+    LowDegreeProof {
+        queried_evaluations,
+        fri_proof: prover.into_proof(&positions),
+    }
+}
+
+/// Verifies a proof generated by [prove_low_degree]. `max_poly_degree` is the
+/// degree bound of the evaluations and `public_coin` must be in the same state
+/// as the prover's when it called [prove_low_degree].
+pub fn verify_low_degree<F: GpuField + Field, D: Digest>(
+    proof: LowDegreeProof<F>,
+    options: FriOptions,
+    num_queries: usize,
+    max_poly_degree: usize,
+    public_coin: &mut impl PublicCoin,
+) -> Result<(), VerificationError>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    let LowDegreeProof {
+        queried_evaluations,
+        fri_proof,
+    } = proof;
+    let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
+    let verifier = FriVerifier::<F, D>::new(public_coin, options, fri_proof, max_poly_degree)?;
+    let positions = public_coin.draw_unique_queries(num_queries, domain_size);
+    verifier.verify(&positions, &queried_evaluations)
+}
+
+/// Standalone proof that evaluations over a [CircleDomain] are close to a
+/// polynomial with `domain.size() / blowup_factor` coefficients. Generated by
+/// [prove_circle_low_degree].
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct CircleLowDegreeProof<F: Field> {
+    pub layers: Vec<CircleFriLayer<F>>,
+    /// Evaluations of the last layer. All equal if the evaluations are low
+    /// degree.
+    pub remainder: Vec<F>,
+}
+
+/// Committed layer of circle FRI. Leaf `i` holds the evaluations at `i` and
+/// `i + n/2` which fold into evaluation `i` of the next layer.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct CircleFriLayer<F: Field> {
+    pub commitment: Vec<u8>,
+    /// Queried pairs of evaluations in query order
+    pub values: Vec<[F; 2]>,
+    pub proofs: Vec<MerkleProof>,
+}
+
+/// Proves evaluations over a [CircleDomain] are close to a polynomial with
+/// `domain.size() / blowup_factor` coefficients using circle FRI. The first
+/// layer folds the circle domain onto its x-coordinates and the rest fold by
+/// `π(x) = 2x^2 - 1` until `blowup_factor` evaluations remain. Query positions
+/// are drawn from `public_coin` once all layers are committed.
+pub fn prove_circle_low_degree<F: CircleFftField, D: Digest>(
+    evaluations: &[F],
+    domain: CircleDomain<F>,
+    blowup_factor: usize,
+    num_queries: usize,
+    public_coin: &mut impl PublicCoin,
+) -> CircleLowDegreeProof<F> {
+    let n = domain.size();
+    assert_eq!(n, evaluations.len());
+    assert!(blowup_factor.is_power_of_two() && 2 <= blowup_factor && blowup_factor < n);
+    let mut trees = Vec::<MerkleTree<D>>::new();
+    let alpha = commit_circle_layer(&mut trees, evaluations, public_coin);
+    let (mut folded, mut xs) = fold_circle_into_line(evaluations, &domain, alpha);
+    let mut layers = vec![evaluations.to_vec()];
+    while folded.len() > blowup_factor {
+        let alpha = commit_circle_layer(&mut trees, &folded, public_coin);
+        let (next_folded, next_xs) = fold_line(&folded, &xs, alpha);
+        layers.push(core::mem::replace(&mut folded, next_folded));
+        xs = next_xs;
+    }
+    public_coin.reseed_with_label(&label::fri_layer(trees.len()), &folded);
+
+    // queries select a pair of the first layer and the pairs it folds into
+    let positions = public_coin.draw_unique_queries(num_queries, n / 2);
+    let layers = layers
+        .iter()
+        .zip(&trees)
+        .map(|(evaluations, tree)| {
+            let half_size = evaluations.len() / 2;
+            let indices = positions
+                .iter()
+                .map(|p| p % half_size)
+                .collect::<Vec<usize>>();
+            CircleFriLayer {
+                commitment: tree.commitment(),
+                values: indices
+                    .iter()
+                    .map(|&i| [evaluations[i], evaluations[i + half_size]])
+                    .collect(),
+                proofs: tree
+                    .open_many(&indices)
+                    .expect("failed to generate Merkle proof"),
+            }
+        })
+        .collect();
+    CircleLowDegreeProof {
+        layers,
+        remainder: folded,
+    }
+}
+
+/// Verifies a proof generated by [prove_circle_low_degree]. `public_coin` must
+/// be in the same state as the prover's when it called
+/// [prove_circle_low_degree].
+pub fn verify_circle_low_degree<F: CircleFftField, D: Digest>(
+    proof: CircleLowDegreeProof<F>,
+    domain: CircleDomain<F>,
+    blowup_factor: usize,
+    num_queries: usize,
+    public_coin: &mut impl PublicCoin,
+) -> Result<(), VerificationError> {
+    let n = domain.size();
+    if !blowup_factor.is_power_of_two() || blowup_factor < 2 || blowup_factor >= n {
+        return Err(VerificationError::InvalidDomain);
+    }
+    // each layer halves the number of evaluations
+    let num_layers = (n / blowup_factor).ilog2() as usize;
+    if proof.layers.len() != num_layers {
+        return Err(VerificationError::NumLayersMismatch {
+            expected: num_layers,
+            actual: proof.layers.len(),
+        });
+    }
+    if proof.remainder.len() != blowup_factor {
+        return Err(VerificationError::RemainderTooSmall);
+    }
+    let mut alphas = Vec::with_capacity(num_layers);
+    for (layer, proof_layer) in proof.layers.iter().enumerate() {
+        if proof_layer.commitment.len() != <D as Digest>::output_size() {
+            return Err(VerificationError::LayerCommitmentInvalid { layer });
+        }
+        let label = label::fri_layer(layer);
+        public_coin.reseed_with_label(&label, &proof_layer.commitment);
+        alphas.push(public_coin.draw::<F>());
+    }
+    public_coin.reseed_with_label(&label::fri_layer(num_layers), &proof.remainder);
+    let positions = public_coin.draw_unique_queries(num_queries, n / 2);
+
+    let two_inv = F::from(2u8).inverse().unwrap();
+    let mut folded = vec![F::zero(); positions.len()];
+    let mut layer_size = n;
+    for (layer, (proof_layer, alpha)) in proof.layers.iter().zip(alphas).enumerate() {
+        if proof_layer.values.len() != positions.len()
+            || proof_layer.proofs.len() != positions.len()
+        {
+            return Err(VerificationError::NumLayerValuesMismatch {
+                layer,
+                expected: positions.len(),
+                actual: proof_layer.values.len(),
+            });
+        }
+        let half_size = layer_size / 2;
+        let root = Output::<D>::from_slice(&proof_layer.commitment);
+        for (query, &position) in positions.iter().enumerate() {
+            let [a, b] = proof_layer.values[query];
+            let index = position % half_size;
+            // the previous layer folded into one of this pair
+            if layer != 0 && [a, b][position % layer_size / half_size] != folded[query] {
+                return Err(VerificationError::InvalidDegreeRespectingProjection { layer, query });
+            }
+
+            let merkle_proof = &proof_layer.proofs[query];
+            let path = if merkle_proof.as_bytes().len() % <D as Digest>::output_size() == 0 {
+                merkle_proof.parse::<D>()
+            } else {
+                Vec::new()
+            };
+            let leaf = hash_circle_pair::<F, D>(&[a, b]);
+            if path.first() != Some(&leaf) || MerkleTree::<D>::verify(root, &path, index).is_err() {
+                return Err(VerificationError::LayerQueryInvalid {
+                    layer,
+                    position: index,
+                });
+            }
+
+            // y-coordinate of the circle point or x-coordinate of the line point
+            let twiddle = if layer == 0 {
+                domain.at(index).y
+            } else {
+                (1..layer).fold(domain.at(index).x, |x, _| x.square().double() - F::one())
+            };
+            folded[query] =
+                (a + b) * two_inv + alpha * (a - b) * twiddle.double().inverse().unwrap();
+        }
+        layer_size = half_size;
+    }
+
+    for (&position, value) in positions.iter().zip(folded) {
+        if proof.remainder[position % blowup_factor] != value {
+            return Err(VerificationError::InvalidRemainderDegreeRespectingProjection);
+        }
+    }
+    if proof.remainder.iter().any(|v| *v != proof.remainder[0]) {
+        return Err(VerificationError::RemainderDegreeMismatch { degree: 0 });
+    }
+    Ok(())
+}
+
+/// Commits to the pairs `(i, i + n/2)` of a circle FRI layer and draws the
+/// layer's folding challenge
+fn commit_circle_layer<F: Field, D: Digest>(
+    trees: &mut Vec<MerkleTree<D>>,
+    evaluations: &[F],
+    public_coin: &mut impl PublicCoin,
+) -> F {
+    let (lo, hi) = evaluations.split_at(evaluations.len() / 2);
+    let leaves = lo
+        .iter()
+        .zip(hi)
+        .map(|(&a, &b)| hash_circle_pair::<F, D>(&[a, b]))
+        .collect();
+    let tree = MerkleTree::new(leaves).unwrap();
+    let label = label::fri_layer(trees.len());
+    public_coin.reseed_with_label(&label, &tree.commitment());
+    trees.push(tree);
+    public_coin.draw()
+}
+
+fn hash_circle_pair<F: Field, D: Digest>(pair: &[F; 2]) -> Output<D> {
+    let mut buff = Vec::with_capacity(pair.compressed_size());
+    pair.serialize_compressed(&mut buff).unwrap();
+    D::new_with_prefix(&buff).finalize()
 }
 
 /// Performs a degree respecting projection (drp) on polynomial evaluations.
@@ -536,6 +1727,11 @@ where
     F: DomainCoeff<F::FftField>,
     F::FftField: FftField,
 {
+    #[cfg(feature = "gpu")]
+    if evals.len() >= GpuFft::<F>::MIN_SIZE {
+        return apply_drp_gpu(&evals, domain_offset, alpha, folding_factor);
+    }
+
     let n = evals.len();
     let domain = Radix2EvaluationDomain::new_coset(n, domain_offset).unwrap();
     let coeffs = ifft(evals, domain);
@@ -562,6 +1758,96 @@ where
     fft(drp_coeffs, drp_domain)
 }
 
+/// Computes the same DRP as [apply_drp] directly from evaluations on the GPU.
+/// Each folded evaluation is a random linear combination of the
+/// `folding_factor` evaluations that make up a chunk of the interleaved layer
+/// (see [interleave]) so the layer never leaves the device.
+#[cfg(feature = "gpu")]
+fn apply_drp_gpu<F: GpuField + Field>(
+    evals: &GpuVec<F>,
+    domain_offset: F::FftField,
+    alpha: F,
+    folding_factor: usize,
+) -> GpuVec<F>
+where
+    F::FftField: FftField,
+{
+    use gpu_poly::stage::FriFoldStage;
+    let n = evals.len();
+    let domain = Radix2EvaluationDomain::new_coset(n, domain_offset).unwrap();
+    let root = F::FftField::get_root_of_unity(folding_factor as u64).unwrap();
+    let root_inv = root.inverse().unwrap();
+    let roots_inv = (0..folding_factor)
+        .map(|k| root_inv.pow([k as u64]))
+        .collect::<Vec<F::FftField>>();
+    let folding_factor_inv = F::FftField::from(folding_factor as u64).inverse().unwrap();
+
+    let mut drp = Vec::with_capacity_in(n / folding_factor, PageAlignedAllocator);
+    drp.resize(n / folding_factor, F::zero());
+    let library = &planner().library;
+    let command_queue = &planner().command_queue;
+    let device = command_queue.device();
+    let command_buffer = command_queue.new_command_buffer();
+    let fri_fold = FriFoldStage::<F, F::FftField>::new(library, n, folding_factor);
+    fri_fold.encode(
+        command_buffer,
+        &mut buffer_mut_no_copy(device, &mut drp),
+        &buffer_no_copy(device, evals),
+        alpha,
+        domain_offset.inverse().unwrap(),
+        domain.group_gen_inv(),
+        &roots_inv,
+        folding_factor_inv,
+    );
+    commit_and_wait(command_buffer);
+    drp
+}
+
+/// Performs the same DRP as [apply_drp] on evaluations in bit-reversed order.
+/// Each chunk of `N` consecutive evaluations folds to a single evaluation so
+/// the result is also in bit-reversed order.
+pub fn apply_drp_bit_reversed<F: GpuField + Field, const N: usize>(
+    evals: &[F],
+    domain_offset: F::FftField,
+    alpha: F,
+) -> GpuVec<F>
+where
+    F: DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+{
+    let n = evals.len();
+    let num_chunks = n / N;
+    let generator = F::FftField::get_root_of_unity(n as u64).unwrap();
+    let folding_constants = FoldingConstants::<F::FftField, N>::new();
+    let (chunks, _) = evals.as_chunks::<N>();
+    ark_std::cfg_iter!(chunks)
+        .enumerate()
+        .map(|(j, chunk)| {
+            let exponent = bit_reverse_index(num_chunks, j);
+            let x = domain_offset * generator.pow([exponent as u64]);
+            let chunk = natural_chunk(chunk, EvaluationOrder::BitReversed);
+            folding_constants.fold(&chunk, x, alpha)
+        })
+        .collect::<Vec<F>>()
+        .to_vec_in(PageAlignedAllocator)
+}
+
+/// Returns `Σ coeff^i * codewords[i]`
+pub fn combine_codewords<F: Field>(codewords: Vec<GpuVec<F>>, coeff: F) -> GpuVec<F> {
+    let mut codewords = codewords.into_iter().rev();
+    let mut combined = codewords.next().expect("no codewords to combine");
+    for codeword in codewords {
+        assert_eq!(combined.len(), codeword.len());
+        ark_std::cfg_iter_mut!(combined)
+            .zip(ark_std::cfg_iter!(codeword))
+            .for_each(|(combined, v)| {
+                *combined *= coeff;
+                *combined += v;
+            });
+    }
+    combined
+}
+
 fn ifft<F: GpuField + Field>(
     evals: GpuVec<F>,
     domain: Radix2EvaluationDomain<F::FftField>,
@@ -575,7 +1861,7 @@ where
         let mut coeffs = evals;
         let mut ifft = GpuIfft::from(domain);
         ifft.encode(&mut coeffs);
-        ifft.execute();
+        wait_for_gpu(|| ifft.execute());
         return coeffs;
     }
 
@@ -596,7 +1882,7 @@ where
         let mut evals = coeffs;
         let mut fft = GpuFft::from(domain);
         fft.encode(&mut evals);
-        fft.execute();
+        wait_for_gpu(|| fft.execute());
         return evals;
     }
 
@@ -604,10 +1890,15 @@ where
     evals.to_vec_in(PageAlignedAllocator)
 }
 
-fn fold_positions(positions: &[usize], max: usize) -> Vec<usize> {
+fn fold_positions(
+    positions: &[usize],
+    domain_size: usize,
+    folding_factor: usize,
+    order: EvaluationOrder,
+) -> Vec<usize> {
     let mut res = positions
         .iter()
-        .map(|pos| pos % max)
+        .map(|&pos| order.fold_position(pos, domain_size, folding_factor))
         .collect::<Vec<usize>>();
     res.sort();
     res.dedup();
@@ -620,20 +1911,44 @@ fn get_query_values<F: Field, const N: usize>(
     positions: &[usize],
     folded_positions: &[usize],
     domain_size: usize,
+    order: EvaluationOrder,
 ) -> Vec<F> {
-    let stride_len = domain_size / N;
     positions
         .iter()
-        .map(|position| {
+        .map(|&position| {
+            let folded_position = order.fold_position(position, domain_size, N);
             let i = folded_positions
                 .iter()
-                .position(|&v| v == position % stride_len)
+                .position(|&v| v == folded_position)
                 .unwrap();
-            chunks[i][position / stride_len]
+            chunks[i][order.leaf_index(position, domain_size, N)]
         })
         .collect()
 }
 
+/// Reorders a leaf so the `k`th value is the evaluation at `x * ω_N^k`
+fn natural_chunk<F: Copy, const N: usize>(chunk: &[F; N], order: EvaluationOrder) -> [F; N] {
+    match order {
+        EvaluationOrder::Natural => *chunk,
+        EvaluationOrder::BitReversed => core::array::from_fn(|k| chunk[bit_reverse_index(N, k)]),
+    }
+}
+
+fn natural_order<F: Send>(mut evals: GpuVec<F>, order: EvaluationOrder) -> GpuVec<F> {
+    if order == EvaluationOrder::BitReversed {
+        bit_reverse(&mut evals);
+    }
+    evals
+}
+
+fn bit_reverse_index(n: usize, i: usize) -> usize {
+    debug_assert!(n.is_power_of_two());
+    // `checked_shr` handles `n = 1` where the shift equals the bit width
+    i.reverse_bits()
+        .checked_shr(usize::BITS - n.ilog2())
+        .unwrap_or(0)
+}
+
 fn query_layer<F: GpuField + Field, D: Digest, const N: usize>(
     layer: &FriLayer<F, D>,
     positions: &[usize],
@@ -656,5 +1971,5 @@ where
         let chunk = &layer.evaluations[i..i + N];
         values.push(chunk.try_into().unwrap());
     }
-    FriProofLayer::new(values, proofs, layer.tree.root().to_vec())
+    FriProofLayer::new(values, proofs, layer.tree.commitment())
 }
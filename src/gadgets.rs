@@ -0,0 +1,388 @@
+use crate::assertions::Assertion;
+use crate::challenges::Challenges;
+use crate::constraints::AlgebraicExpression;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::FieldConstant;
+use crate::constraints::Periodic;
+use crate::constraints::TransitionConstraint;
+use crate::constraints::VerifierChallenge;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
+use ark_ff::PrimeField;
+use gpu_poly::prelude::*;
+use gpu_poly::GpuFftField;
+
+/// Checks the rows of two sets of base trace columns are permutations of each
+/// other (multiset equality) with a grand product column.
+///
+/// Each row is compressed into a single element with the verifier challenge
+/// `alpha` i.e. `c_0 + alpha*c_1 + alpha^2*c_2 + ...`. The grand product column
+/// `z` starts at one and accumulates `(gamma - lhs_i) / (gamma - rhs_i)` so
+/// `z[i+1] * (gamma - rhs_i) = z[i] * (gamma - lhs_i)`. The constraint also
+/// holds between the last and first row iff the product of all the ratios is
+/// one which is the case (with high probability) iff the rows are a
+/// permutation.
+#[derive(Clone, Debug)]
+pub struct PermutationArgument {
+    /// Base trace columns of the left multiset
+    pub lhs: Vec<usize>,
+    /// Base trace columns of the right multiset
+    pub rhs: Vec<usize>,
+    /// Execution trace column of the grand product
+    pub column: usize,
+    /// Index of the challenge used to compress rows
+    pub alpha: usize,
+    /// Index of the challenge used to offset compressed rows
+    pub gamma: usize,
+}
+
+impl PermutationArgument {
+    pub fn new(
+        lhs: Vec<usize>,
+        rhs: Vec<usize>,
+        column: usize,
+        alpha: usize,
+        gamma: usize,
+    ) -> Self {
+        assert!(!lhs.is_empty(), "no columns to permute");
+        assert_eq!(lhs.len(), rhs.len(), "multisets have different widths");
+        PermutationArgument {
+            lhs,
+            rhs,
+            column,
+            alpha,
+            gamma,
+        }
+    }
+
+    /// Builds the grand product column from the base trace. Returns a matrix
+    /// with a single column so it can be joined with other extension columns.
+    pub fn build_column<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        base_trace: &Matrix<Fp>,
+        challenges: &Challenges<Fq>,
+    ) -> Matrix<Fq> {
+        let n = base_trace.num_rows();
+        let alpha = challenges[self.alpha];
+        let gamma = challenges[self.gamma];
+        let compress = |columns: &[usize], row: usize| {
+            gamma
+                - columns.iter().rev().fold(Fq::zero(), |acc, &column| {
+                    acc * alpha + Fq::from(base_trace[column][row])
+                })
+        };
+
+        let mut denominators = (0..n)
+            .map(|row| compress(&self.rhs, row))
+            .collect::<Vec<Fq>>();
+        batch_inversion(&mut denominators);
+
+        let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+        let mut acc = Fq::one();
+        for (row, denominator_inv) in denominators.into_iter().enumerate() {
+            column.push(acc);
+            acc *= compress(&self.lhs, row) * denominator_inv;
+        }
+        Matrix::new(vec![column])
+    }
+
+    /// Constraints on the grand product column. Applies to all rows.
+    pub fn constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_len: usize,
+    ) -> Vec<AlgebraicExpression<Fp, Fq>> {
+        use AlgebraicExpression::X;
+        let one = FieldConstant::Fp(Fp::one());
+        let alpha = self.alpha.challenge();
+        let gamma = self.gamma.challenge::<Fp, Fq>();
+        let compress = |columns: &[usize]| {
+            let mut columns = columns.iter().rev();
+            let last = columns.next().unwrap().curr();
+            gamma.clone() - columns.fold(last, |acc, column| acc * alpha.clone() + column.curr())
+        };
+        let z = self.column;
+        let constraint = z.next() * compress(&self.rhs) - z.curr() * compress(&self.lhs);
+        vec![constraint / (X.pow(trace_len) - one)]
+    }
+
+    /// Asserts the grand product column starts at one
+    pub fn assertions<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+    ) -> Vec<Assertion<Fp, Fq>> {
+        vec![Assertion::single(
+            self.column,
+            0,
+            FieldConstant::Fp(Fp::one()),
+        )]
+    }
+}
+
+/// Checks every value in a set of base trace columns appears in a table column
+/// with a LogUp (log-derivative) lookup argument.
+///
+/// The base trace has a column of multiplicities `m` counting the number of
+/// times each table row is looked up (see [LookupArgument::multiplicities]).
+/// Given the verifier challenge `alpha` the lookups are valid (with high
+/// probability) iff `sum_i sum_j 1/(alpha - v_j[i]) = sum_i m[i]/(alpha -
+/// t[i])`. The running sum column `s` starts at zero and accumulates the
+/// difference of the two sides one row at a time. Like [PermutationArgument]
+/// the constraint also holds between the last and first row iff the total is
+/// zero.
+///
+/// The table and multiplicities can belong to a shorter base segment than the
+/// looked up values (see [LookupArgument::with_table_stride]) which bridges
+/// e.g. a main trace and a co-processor trace.
+#[derive(Clone, Debug)]
+pub struct LookupArgument {
+    /// Base trace columns of the looked up values
+    pub values: Vec<usize>,
+    /// Base trace column of the table
+    pub table: usize,
+    /// Base trace column of the table multiplicities
+    pub multiplicities: usize,
+    /// Execution trace column of the running sum
+    pub column: usize,
+    /// Index of the challenge the log derivatives are evaluated at
+    pub alpha: usize,
+    /// Number of trace rows between rows of the table
+    pub table_stride: usize,
+    /// Index of the periodic column flagging the rows of the table if it has
+    /// a stride (see [crate::selectors::stride_selector])
+    pub table_selector: Option<usize>,
+}
+
+impl LookupArgument {
+    pub fn new(
+        values: Vec<usize>,
+        table: usize,
+        multiplicities: usize,
+        column: usize,
+        alpha: usize,
+    ) -> Self {
+        assert!(!values.is_empty(), "no columns to look up");
+        LookupArgument {
+            values,
+            table,
+            multiplicities,
+            column,
+            alpha,
+            table_stride: 1,
+            table_selector: None,
+        }
+    }
+
+    /// Looks up values in a table (and multiplicities) from a base segment
+    /// with stride `stride` i.e. with `stride` times fewer rows than the
+    /// looked up values. The running sum only accumulates table rows on trace
+    /// rows that are multiples of `stride`. `selector` is the index of the
+    /// periodic column `selectors::stride_selector(stride)` which the Air must
+    /// return from `Air::periodic_columns`.
+    pub fn with_table_stride(mut self, stride: usize, selector: usize) -> Self {
+        assert!(stride.is_power_of_two(), "stride must be a power of two");
+        self.table_stride = stride;
+        self.table_selector = (stride != 1).then_some(selector);
+        self
+    }
+
+    /// Counts the number of times each row of `table` is looked up by
+    /// `values`. Values that appear in multiple rows of the table are counted
+    /// against the first. Panics if a value is missing from the table.
+    pub fn multiplicities<F: PrimeField>(values: &[&[F]], table: &[F]) -> Vec<F> {
+        let mut rows = BTreeMap::new();
+        for (row, value) in table.iter().enumerate().rev() {
+            rows.insert(value, row);
+        }
+        let mut counts = vec![0u64; table.len()];
+        for value in values.iter().flat_map(|column| column.iter()) {
+            let row = rows.get(value).expect("value is missing from the table");
+            counts[*row] += 1;
+        }
+        counts.into_iter().map(F::from).collect()
+    }
+
+    /// Builds the running sum column from the base trace. Returns a matrix with
+    /// a single column so it can be joined with other extension columns.
+    pub fn build_column<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        base_trace: &Matrix<Fp>,
+        challenges: &Challenges<Fq>,
+    ) -> Matrix<Fq> {
+        let n = base_trace[self.values[0]].len();
+        let stride = self.table_stride;
+        let alpha = challenges[self.alpha];
+        let num_values = self.values.len();
+        // table rows only contribute on rows that are multiples of the stride
+        let table_row = |row: usize| (row % stride == 0).then_some(row / stride);
+
+        // `1/(alpha - v_j[i])` for all values followed by `1/(alpha - t[i])`
+        let mut inverses = Vec::with_capacity(n * (num_values + 1));
+        for row in 0..n {
+            for &column in &self.values {
+                inverses.push(alpha - Fq::from(base_trace[column][row]));
+            }
+            inverses.push(match table_row(row) {
+                Some(row) => alpha - Fq::from(base_trace[self.table][row]),
+                None => Fq::one(),
+            });
+        }
+        batch_inversion(&mut inverses);
+
+        let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+        let mut acc = Fq::zero();
+        for (row, row_inverses) in inverses.chunks(num_values + 1).enumerate() {
+            column.push(acc);
+            let (table_inverse, value_inverses) = row_inverses.split_last().unwrap();
+            let multiplicity = match table_row(row) {
+                Some(row) => base_trace[self.multiplicities][row],
+                None => Fp::zero(),
+            };
+            acc += value_inverses.iter().sum::<Fq>() - *table_inverse * Fq::from(multiplicity);
+        }
+        Matrix::new(vec![column])
+    }
+
+    /// Constraints on the running sum column. Applies to all rows.
+    pub fn constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_len: usize,
+    ) -> Vec<AlgebraicExpression<Fp, Fq>> {
+        use AlgebraicExpression::X;
+        let one = FieldConstant::Fp(Fp::one());
+        let alpha = self.alpha.challenge::<Fp, Fq>();
+        let value_denominators = self
+            .values
+            .iter()
+            .map(|column| alpha.clone() - column.curr())
+            .collect::<Vec<_>>();
+        let table_denominator = alpha - self.table.curr();
+        // `sum_j 1/(alpha - v_j)` with denominators cleared
+        let values_sum = (0..value_denominators.len())
+            .map(|j| {
+                value_denominators
+                    .iter()
+                    .enumerate()
+                    .filter(|&(l, _)| l != j)
+                    .fold(table_denominator.clone(), |acc, (_, d)| acc * d.clone())
+            })
+            .sum::<AlgebraicExpression<Fp, Fq>>();
+        let values_denominator = value_denominators
+            .into_iter()
+            .reduce(|acc, d| acc * d)
+            .unwrap();
+        let mut table_sum = self.multiplicities.curr() * values_denominator.clone();
+        if let Some(selector) = self.table_selector {
+            table_sum *= selector.periodic();
+        }
+        let s = self.column;
+        let constraint =
+            (s.next() - s.curr()) * values_denominator * table_denominator - values_sum + table_sum;
+        vec![constraint / (X.pow(trace_len) - one)]
+    }
+
+    /// Asserts the running sum column starts at zero
+    pub fn assertions<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+    ) -> Vec<Assertion<Fp, Fq>> {
+        vec![Assertion::single(
+            self.column,
+            0,
+            FieldConstant::Fp(Fp::zero()),
+        )]
+    }
+}
+
+/// Constrains the values of base trace column `column` to `[0, 2^bits)` with a
+/// lookup into a table of all values in the range. The table, its
+/// multiplicities and the lookup's running sum are stored in the columns
+/// `table`, `multiplicities` and `running_sum`. `alpha` is the index of the
+/// challenge used by the lookup. The trace must have at least `2^bits` rows.
+pub fn range_check(
+    column: usize,
+    bits: u32,
+    table: usize,
+    multiplicities: usize,
+    running_sum: usize,
+    alpha: usize,
+) -> RangeCheck {
+    RangeCheck {
+        bits,
+        lookup: LookupArgument::new(vec![column], table, multiplicities, running_sum, alpha),
+    }
+}
+
+/// Range check built by [range_check]
+#[derive(Clone, Debug)]
+pub struct RangeCheck {
+    pub bits: u32,
+    pub lookup: LookupArgument,
+}
+
+impl RangeCheck {
+    /// Number of values in the range
+    pub fn range_size(&self) -> usize {
+        1 << self.bits
+    }
+
+    /// Table column of a trace with `trace_len` rows. Counts up from zero and
+    /// repeats the largest value in the range once it's reached.
+    pub fn table<F: PrimeField>(&self, trace_len: usize) -> Vec<F> {
+        let range_size = self.range_size();
+        assert!(trace_len >= range_size, "trace too small for range");
+        (0..trace_len)
+            .map(|i| F::from(i.min(range_size - 1) as u64))
+            .collect()
+    }
+
+    /// Multiplicities column for the range checked `values`. Panics if a value
+    /// is out of range.
+    pub fn multiplicities<F: PrimeField>(&self, values: &[F]) -> Vec<F> {
+        LookupArgument::multiplicities(&[values], &self.table(values.len()))
+    }
+
+    /// Builds the lookup's running sum column from the base trace
+    pub fn build_column<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        base_trace: &Matrix<Fp>,
+        challenges: &Challenges<Fq>,
+    ) -> Matrix<Fq> {
+        self.lookup.build_column(base_trace, challenges)
+    }
+
+    /// Constraints of the lookup. Applies to all rows.
+    pub fn constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_len: usize,
+    ) -> Vec<AlgebraicExpression<Fp, Fq>> {
+        self.lookup.constraints(trace_len)
+    }
+
+    /// Constrains the table to increase by zero or one each row
+    pub fn transition_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+    ) -> Vec<TransitionConstraint<Fp, Fq>> {
+        let one = FieldConstant::Fp(Fp::one());
+        let table = self.lookup.table;
+        let step = table.next() - table.curr();
+        vec![TransitionConstraint::new(step.clone() * (step - one))]
+    }
+
+    /// Asserts the table starts at zero and ends at the largest value in the
+    /// range and that the lookup's running sum starts at zero
+    pub fn assertions<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_len: usize,
+    ) -> Vec<Assertion<Fp, Fq>> {
+        let table = self.lookup.table;
+        let max = Fp::from((self.range_size() - 1) as u64);
+        let mut assertions = vec![
+            Assertion::single(table, 0, FieldConstant::Fp(Fp::zero())),
+            Assertion::single(table, trace_len - 1, FieldConstant::Fp(max)),
+        ];
+        assertions.extend(self.lookup.assertions());
+        assertions
+    }
+}
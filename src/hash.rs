@@ -0,0 +1,496 @@
+//! Algebraic hash functions for recursion friendly commitments.
+//!
+//! Permutations operate on a state of [WIDTH] field elements. Parameters are
+//! generated deterministically: the S-box exponent is the smallest prime `α`
+//! coprime to `p - 1`, the MDS matrix is the Cauchy matrix `1 / (i + j + t)`
+//! and round constants are sampled from a ChaCha20 stream seeded with the
+//! SHA-256 hash of the permutation's name. [Poseidon2] uses the `circ(2·M4,
+//! M4, M4)` external matrix from the paper and an internal matrix `J + D`
+//! whose diagonal `D` is sampled from the same stream.
+use crate::merkle::HashFn;
+use alloc::vec::Vec;
+use ark_ff::BigInteger;
+use ark_ff::PrimeField;
+use digest::consts::U128;
+use digest::consts::U32;
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp as GoldilocksFp;
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp as Stark252Fp;
+use once_cell::sync::Lazy;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Sha256;
+
+/// Number of field elements in the permutation state
+pub const WIDTH: usize = 12;
+/// Number of state elements reserved for security
+pub const CAPACITY: usize = 4;
+/// Number of field elements absorbed per permutation
+pub const RATE: usize = WIDTH - CAPACITY;
+/// Number of field elements in a digest
+pub const DIGEST_SIZE: usize = 4;
+/// Bits of security the round numbers of [Poseidon] and [Poseidon2] target
+pub const SECURITY_LEVEL: usize = 128;
+
+pub trait Permutation<F>: Send + Sync {
+    fn permute(&self, state: &mut [F; WIDTH]);
+}
+
+/// Poseidon permutation <https://eprint.iacr.org/2019/458>
+pub struct Poseidon<F> {
+    alpha: u64,
+    num_full_rounds: usize,
+    num_partial_rounds: usize,
+    mds: [[F; WIDTH]; WIDTH],
+    round_constants: Vec<[F; WIDTH]>,
+}
+
+impl<F: PrimeField> Poseidon<F> {
+    pub fn new() -> Self {
+        let alpha = sbox_exponent::<F>();
+        let (num_full_rounds, num_partial_rounds) = poseidon_round_numbers::<F>(alpha);
+        let num_rounds = num_full_rounds + num_partial_rounds;
+        Poseidon {
+            alpha,
+            num_full_rounds,
+            num_partial_rounds,
+            mds: cauchy_mds(),
+            round_constants: round_constants(b"ministark-poseidon", num_rounds),
+        }
+    }
+
+    pub fn num_full_rounds(&self) -> usize {
+        self.num_full_rounds
+    }
+
+    pub fn num_partial_rounds(&self) -> usize {
+        self.num_partial_rounds
+    }
+
+    fn full_round(&self, state: &mut [F; WIDTH], round: usize) {
+        add_constants(state, &self.round_constants[round]);
+        state.iter_mut().for_each(|v| *v = v.pow([self.alpha]));
+        apply_mds(state, &self.mds);
+    }
+
+    fn partial_round(&self, state: &mut [F; WIDTH], round: usize) {
+        add_constants(state, &self.round_constants[round]);
+        state[0] = state[0].pow([self.alpha]);
+        apply_mds(state, &self.mds);
+    }
+}
+
+impl<F: PrimeField> Default for Poseidon<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField> Permutation<F> for Poseidon<F> {
+    fn permute(&self, state: &mut [F; WIDTH]) {
+        let half_full_rounds = self.num_full_rounds / 2;
+        let mut round = 0;
+        for _ in 0..half_full_rounds {
+            self.full_round(state, round);
+            round += 1;
+        }
+        for _ in 0..self.num_partial_rounds {
+            self.partial_round(state, round);
+            round += 1;
+        }
+        for _ in 0..half_full_rounds {
+            self.full_round(state, round);
+            round += 1;
+        }
+    }
+}
+
+/// Poseidon2 permutation <https://eprint.iacr.org/2023/323>. Uses the same
+/// round numbers as [Poseidon].
+pub struct Poseidon2<F> {
+    alpha: u64,
+    num_full_rounds: usize,
+    num_partial_rounds: usize,
+    // diagonal of the internal matrix minus the identity
+    internal_diagonal: [F; WIDTH],
+    round_constants: Vec<[F; WIDTH]>,
+}
+
+impl<F: PrimeField> Poseidon2<F> {
+    pub fn new() -> Self {
+        let alpha = sbox_exponent::<F>();
+        let (num_full_rounds, num_partial_rounds) = poseidon_round_numbers::<F>(alpha);
+        let num_rounds = num_full_rounds + num_partial_rounds;
+        let mut rng = chacha_stream(b"ministark-poseidon2");
+        let internal_diagonal = [(); WIDTH].map(|_| F::rand(&mut rng) - F::one());
+        Poseidon2 {
+            alpha,
+            num_full_rounds,
+            num_partial_rounds,
+            internal_diagonal,
+            round_constants: (0..num_rounds)
+                .map(|_| [(); WIDTH].map(|_| F::rand(&mut rng)))
+                .collect(),
+        }
+    }
+
+    pub fn num_full_rounds(&self) -> usize {
+        self.num_full_rounds
+    }
+
+    pub fn num_partial_rounds(&self) -> usize {
+        self.num_partial_rounds
+    }
+
+    fn external_round(&self, state: &mut [F; WIDTH], round: usize) {
+        add_constants(state, &self.round_constants[round]);
+        state.iter_mut().for_each(|v| *v = v.pow([self.alpha]));
+        apply_external_matrix(state);
+    }
+
+    fn internal_round(&self, state: &mut [F; WIDTH], round: usize) {
+        state[0] += self.round_constants[round][0];
+        state[0] = state[0].pow([self.alpha]);
+        // `J + D` where `J` is the all ones matrix
+        let sum = state.iter().sum::<F>();
+        for (v, d) in state.iter_mut().zip(&self.internal_diagonal) {
+            *v = sum + *d * *v;
+        }
+    }
+}
+
+impl<F: PrimeField> Default for Poseidon2<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField> Permutation<F> for Poseidon2<F> {
+    fn permute(&self, state: &mut [F; WIDTH]) {
+        let half_full_rounds = self.num_full_rounds / 2;
+        let mut round = 0;
+        apply_external_matrix(state);
+        for _ in 0..half_full_rounds {
+            self.external_round(state, round);
+            round += 1;
+        }
+        for _ in 0..self.num_partial_rounds {
+            self.internal_round(state, round);
+            round += 1;
+        }
+        for _ in 0..half_full_rounds {
+            self.external_round(state, round);
+            round += 1;
+        }
+    }
+}
+
+/// Rescue-Prime permutation <https://eprint.iacr.org/2020/1143>
+pub struct RescuePrime<F> {
+    alpha: u64,
+    // little-endian limbs of `α^(-1) mod (p - 1)`
+    inv_alpha: Vec<u64>,
+    mds: [[F; WIDTH]; WIDTH],
+    round_constants: Vec<[F; WIDTH]>,
+}
+
+impl<F: PrimeField> RescuePrime<F> {
+    pub const NUM_ROUNDS: usize = 7;
+
+    pub fn new() -> Self {
+        let alpha = sbox_exponent::<F>();
+        RescuePrime {
+            alpha,
+            inv_alpha: inverse_sbox_exponent::<F>(alpha),
+            mds: cauchy_mds(),
+            round_constants: round_constants(b"ministark-rescue-prime", 2 * Self::NUM_ROUNDS),
+        }
+    }
+}
+
+impl<F: PrimeField> Default for RescuePrime<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField> Permutation<F> for RescuePrime<F> {
+    fn permute(&self, state: &mut [F; WIDTH]) {
+        for round in 0..Self::NUM_ROUNDS {
+            state.iter_mut().for_each(|v| *v = v.pow([self.alpha]));
+            apply_mds(state, &self.mds);
+            add_constants(state, &self.round_constants[2 * round]);
+            state.iter_mut().for_each(|v| *v = v.pow(&self.inv_alpha));
+            apply_mds(state, &self.mds);
+            add_constants(state, &self.round_constants[2 * round + 1]);
+        }
+    }
+}
+
+macro_rules! impl_hash_fn {
+    ($name:ident, $permutation:ident, $field:ty, $output_size:ty) => {
+        pub struct $name;
+
+        impl HashFn for $name {
+            type Field = $field;
+            type OutputSize = $output_size;
+
+            fn permutation() -> &'static dyn Permutation<$field> {
+                static PERMUTATION: Lazy<$permutation<$field>> = Lazy::new($permutation::new);
+                &*PERMUTATION
+            }
+        }
+    };
+}
+
+impl_hash_fn!(PoseidonGoldilocks, Poseidon, GoldilocksFp, U32);
+impl_hash_fn!(RescuePrimeGoldilocks, RescuePrime, GoldilocksFp, U32);
+impl_hash_fn!(PoseidonStark252, Poseidon, Stark252Fp, U128);
+impl_hash_fn!(RescuePrimeStark252, RescuePrime, Stark252Fp, U128);
+impl_hash_fn!(Poseidon2Goldilocks, Poseidon2, GoldilocksFp, U32);
+impl_hash_fn!(Poseidon2Stark252, Poseidon2, Stark252Fp, U128);
+
+fn add_constants<F: PrimeField>(state: &mut [F; WIDTH], constants: &[F; WIDTH]) {
+    state.iter_mut().zip(constants).for_each(|(v, c)| *v += c);
+}
+
+fn apply_mds<F: PrimeField>(state: &mut [F; WIDTH], mds: &[[F; WIDTH]; WIDTH]) {
+    let mut result = [F::zero(); WIDTH];
+    for (row, v) in mds.iter().zip(&mut result) {
+        *v = row.iter().zip(state.iter()).map(|(&m, &s)| m * s).sum();
+    }
+    *state = result;
+}
+
+/// Multiplies the state by `circ(2·M4, M4, M4)` where `M4` is the 4x4 MDS
+/// matrix from the Poseidon2 paper
+fn apply_external_matrix<F: PrimeField>(state: &mut [F; WIDTH]) {
+    const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+    let mut chunks = [[F::zero(); 4]; WIDTH / 4];
+    for (chunk, input) in chunks.iter_mut().zip(state.chunks(4)) {
+        for (v, row) in chunk.iter_mut().zip(&M4) {
+            *v = row.iter().zip(input).map(|(&m, &s)| s * F::from(m)).sum();
+        }
+    }
+    let mut sums = [F::zero(); 4];
+    for chunk in &chunks {
+        sums.iter_mut().zip(chunk).for_each(|(s, v)| *s += v);
+    }
+    for (output, chunk) in state.chunks_mut(4).zip(&chunks) {
+        for ((v, c), s) in output.iter_mut().zip(chunk).zip(&sums) {
+            *v = *c + s;
+        }
+    }
+}
+
+/// Cauchy matrix `M[i][j] = 1 / (i + j + WIDTH)` which is MDS for any field
+/// with characteristic larger than `3 * WIDTH`
+fn cauchy_mds<F: PrimeField>() -> [[F; WIDTH]; WIDTH] {
+    let mut mds = [[F::zero(); WIDTH]; WIDTH];
+    for (i, row) in mds.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = F::from((i + j + WIDTH) as u64).inverse().unwrap();
+        }
+    }
+    mds
+}
+
+fn chacha_stream(label: &[u8]) -> ChaCha20Rng {
+    ChaCha20Rng::from_seed(Sha256::digest(label).into())
+}
+
+fn round_constants<F: PrimeField>(label: &[u8], num_rounds: usize) -> Vec<[F; WIDTH]> {
+    let mut rng = chacha_stream(label);
+    (0..num_rounds)
+        .map(|_| [(); WIDTH].map(|_| F::rand(&mut rng)))
+        .collect()
+}
+
+/// Returns `(p - 1) mod m`
+fn modulus_minus_one_mod<F: PrimeField>(m: u64) -> u64 {
+    let p_mod_m = F::MODULUS
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |r, &limb| ((r << 64) + limb as u128) % m as u128);
+    (p_mod_m as u64 + m - 1) % m
+}
+
+/// Smallest prime `α` such that `x -> x^α` is a permutation of the field
+fn sbox_exponent<F: PrimeField>() -> u64 {
+    [3, 5, 7, 11, 13, 17]
+        .into_iter()
+        .find(|&alpha| modulus_minus_one_mod::<F>(alpha) != 0)
+        .expect("no suitable S-box exponent")
+}
+
+/// Computes `α^(-1) mod (p - 1)` as `((p - 1) * k + 1) / α` for the `k < α`
+/// that makes the division exact
+fn inverse_sbox_exponent<F: PrimeField>(alpha: u64) -> Vec<u64> {
+    let mut p_minus_one = F::MODULUS;
+    p_minus_one.sub_with_borrow(&F::BigInt::from(1u64));
+    let r = modulus_minus_one_mod::<F>(alpha);
+    let k = (1..alpha).find(|k| (r * k + 1) % alpha == 0).unwrap();
+
+    // (p - 1) * k + 1
+    let mut carry = 1u128;
+    let mut limbs = p_minus_one
+        .as_ref()
+        .iter()
+        .map(|&limb| {
+            let v = limb as u128 * k as u128 + carry;
+            carry = v >> 64;
+            v as u64
+        })
+        .collect::<Vec<u64>>();
+    limbs.push(carry as u64);
+
+    // divide by α
+    let mut remainder = 0u128;
+    for limb in limbs.iter_mut().rev() {
+        let v = (remainder << 64) + *limb as u128;
+        *limb = (v / alpha as u128) as u64;
+        remainder = v % alpha as u128;
+    }
+    assert_eq!(0, remainder);
+    limbs
+}
+
+/// Number of full and partial rounds of Poseidon over `F` with S-box `x^α`.
+/// Follows the round number script of the Poseidon paper: the cheapest round
+/// numbers resisting the statistical, interpolation and Gröbner basis attacks
+/// (including <https://eprint.iacr.org/2023/537>) at [SECURITY_LEVEL] plus a
+/// security margin of two full rounds and 7.5% more partial rounds. This
+/// gives 8 full and 22 partial rounds over Goldilocks (`α = 7`) and 8 full and
+/// 85 partial rounds over the Starknet field (`α = 3`).
+fn poseidon_round_numbers<F: PrimeField>(alpha: u64) -> (usize, usize) {
+    const TWO_POW_64: f64 = 18446744073709551616.0;
+    let log2_p = log2(
+        F::MODULUS
+            .as_ref()
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &limb| acc * TWO_POW_64 + limb as f64),
+    );
+    // (number of S-boxes, full rounds, partial rounds)
+    let mut best: Option<(usize, usize, usize)> = None;
+    for num_partial_rounds in 1..500 {
+        // every full round has `WIDTH` S-boxes and there are at least 6
+        if let Some((min_cost, ..)) = best
+            && WIDTH * 6 + num_partial_rounds > min_cost
+        {
+            break;
+        }
+        let num_full_rounds = (4..100)
+            .step_by(2)
+            .find(|&r_f| resists_known_attacks(log2_p, alpha, r_f, num_partial_rounds));
+        if let Some(num_full_rounds) = num_full_rounds {
+            let r_f = num_full_rounds + 2;
+            let r_p = ceil(num_partial_rounds as f64 * 1.075) as usize;
+            let cost = WIDTH * r_f + r_p;
+            if best.map_or(true, |(min_cost, min_r_f, _)| {
+                cost < min_cost || (cost == min_cost && r_f < min_r_f)
+            }) {
+                best = Some((cost, r_f, r_p));
+            }
+        }
+    }
+    let (_, num_full_rounds, num_partial_rounds) = best.expect("no secure round numbers");
+    (num_full_rounds, num_partial_rounds)
+}
+
+/// Checks the bounds of `sat_inequiv_alpha` in the Poseidon round number
+/// script for a state of [WIDTH] elements over a field of `log2_p` bits
+fn resists_known_attacks(log2_p: f64, alpha: u64, r_f: usize, r_p: usize) -> bool {
+    let t = WIDTH as f64;
+    let m = SECURITY_LEVEL as f64;
+    let (r_f, r_p, alpha) = (r_f as f64, r_p as f64, alpha as f64);
+    let log2_alpha = log2(alpha);
+    // statistical attacks
+    let r_f_1 = if m <= floor(log2_p - (alpha - 1.0) / 2.0) * (t + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    // interpolation attacks
+    let r_f_2 = 1.0 + ceil(m.min(log2_p) / log2_alpha) + ceil(log2(t) / log2_alpha) - r_p;
+    // Gröbner basis attacks
+    let r_f_3 = m.min(log2_p) / log2_alpha - r_p;
+    let r_f_4 = t - 1.0 + (m / (t + 1.0)).min(log2_p / 2.0) / log2_alpha - r_p;
+    let r_f_5 = (t - 2.0 + m / (2.0 * log2_alpha) - r_p) / (t - 1.0);
+    let r_f_max = [r_f_1, r_f_2, r_f_3, r_f_4, r_f_5]
+        .map(ceil)
+        .into_iter()
+        .fold(f64::MIN, f64::max);
+    // Gröbner basis attack of <https://eprint.iacr.org/2023/537>
+    let r_temp = floor(t / 3.0);
+    let over = (r_f - 1.0) * t + r_p + r_temp + r_temp * (r_f / 2.0) + r_p + alpha;
+    let under = r_temp * (r_f / 2.0) + r_p + alpha;
+    r_f >= r_f_max && ceil(2.0 * log2_binomial(over as u64, under as u64)) >= m
+}
+
+/// Returns `log2(n choose k)`
+fn log2_binomial(n: u64, k: u64) -> f64 {
+    (1..=k)
+        .map(|i| log2((n - k + i) as f64) - log2(i as f64))
+        .sum()
+}
+
+/// Base 2 logarithm of a positive number. Computed one bit of the fractional
+/// part at a time since `f64::log2` needs `std`.
+fn log2(mut x: f64) -> f64 {
+    let mut res = 0.0;
+    while x >= 2.0 {
+        x /= 2.0;
+        res += 1.0;
+    }
+    while x < 1.0 {
+        x *= 2.0;
+        res -= 1.0;
+    }
+    let mut bit = 1.0;
+    for _ in 0..f64::MANTISSA_DIGITS {
+        x *= x;
+        bit /= 2.0;
+        if x >= 2.0 {
+            x /= 2.0;
+            res += bit;
+        }
+    }
+    res
+}
+
+fn floor(x: f64) -> f64 {
+    let truncated = x as i64 as f64;
+    if truncated > x {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+fn ceil(x: f64) -> f64 {
+    let truncated = x as i64 as f64;
+    if truncated < x {
+        truncated + 1.0
+    } else {
+        truncated
+    }
+}
+
+/// Bytes absorbed per field element when hashing byte strings. Chosen so
+/// every chunk is a canonical field element which keeps packing injective.
+fn bytes_per_element<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize - 1) / 8
+}
+
+/// Packs bytes little-endian into field elements of [bytes_per_element] bytes
+/// after appending a `0x01` padding byte
+pub(crate) fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    let mut bytes = bytes.to_vec();
+    bytes.push(1);
+    bytes
+        .chunks(bytes_per_element::<F>())
+        .map(F::from_le_bytes_mod_order)
+        .collect()
+}
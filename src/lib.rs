@@ -6,69 +6,153 @@
     array_windows,
     array_chunks,
     iter_partition_in_place,
+    int_roundings,
     slice_flatten,
     slice_as_chunks,
-    async_fn_in_trait
+    async_fn_in_trait,
+    portable_simd,
+    associated_type_defaults,
+    specialization
 )]
 
 #[macro_use]
 mod macros;
 mod air;
+pub mod assertions;
 pub mod calculator;
 pub mod challenges;
 pub mod channel;
+pub mod checkpoint;
+pub mod commitment;
 mod composer;
 pub mod constraints;
+pub mod continuation;
+pub mod divisor;
+pub mod domain;
+mod error;
 pub mod fri;
+pub mod gadgets;
+pub mod hash;
 pub mod hints;
 pub mod matrix;
 pub mod merkle;
+pub mod periodic;
 pub mod prover;
 pub mod random;
+pub mod report;
+pub mod selectors;
+#[cfg(not(feature = "gpu"))]
+mod simd;
+pub mod starknet;
 pub mod trace;
 pub mod utils;
 mod verifier;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+
+#[cfg(all(feature = "gpu", target_arch = "wasm32"))]
+compile_error!("the `gpu` feature isn't supported on wasm32");
 
 #[macro_use]
 extern crate alloc;
 pub use air::Air;
+pub use air::UnsatisfiedConstraint;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::BigInteger;
 use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::PrimeField;
+use ark_ff::Zero;
 use ark_poly::domain::DomainCoeff;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+#[cfg(feature = "blake3")]
+pub use blake3::Hasher as Blake3;
+use commitment::VectorCommitment;
 use core::ops::Add;
 use core::ops::AddAssign;
 use core::ops::Mul;
 use core::ops::MulAssign;
 use core::ops::Sub;
 use core::ops::SubAssign;
+use digest::Digest;
+pub use error::Error;
+use fri::FoldingSchedule;
 use fri::FriOptions;
-use fri::FriProof;
+use fri::LdtVerifier;
 use gpu_poly::GpuAdd;
 use gpu_poly::GpuFftField;
 use gpu_poly::GpuField;
 use gpu_poly::GpuMul;
 pub use matrix::Matrix;
 pub use prover::Prover;
+pub use random::CoefficientMode;
+pub use random::GrindingConfig;
+pub use random::GrindingHash;
+#[cfg(feature = "keccak")]
+pub use sha3::Keccak256;
 use trace::Queries;
 pub use trace::Trace;
 pub use trace::TraceInfo;
+pub use verifier::VerificationError;
+pub use verifier::VerifierPolicy;
 
-// TODO: include ability to specify:
-// - base field
-// - extension field
-// - hashing function
 #[derive(Debug, Clone, Copy, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq)]
 pub struct ProofOptions {
     pub num_queries: u8,
     pub lde_blowup_factor: u8,
-    pub grinding_factor: u8,
+    pub grinding: GrindingConfig,
     pub fri_folding_factor: u8,
+    /// Folding factors of the first FRI layers. Remaining layers are folded by
+    /// `fri_folding_factor`.
+    pub fri_folding_schedule: FoldingSchedule,
     pub fri_max_remainder_size: u8,
+    /// Send the FRI remainder as polynomial coefficients rather than a
+    /// committed codeword
+    pub fri_remainder_coeffs: bool,
+    /// Stop folding FRI layers once the polynomial has at most
+    /// `fri_max_remainder_size` coefficients. Implies `fri_remainder_coeffs`.
+    pub fri_early_stop: bool,
+    /// Degree of the extension field challenges are drawn from. If specified
+    /// the degree of `Air::Fq` must match. Allows a proof to declare e.g. a
+    /// cubic rather than quadratic extension.
+    pub extension_degree: Option<u8>,
+    /// Minimum security level (in bits) a proof must achieve. Conjectured
+    /// unless `proven_security` is set
+    pub security_target: Option<u8>,
+    /// Check `security_target` against the proven rather than conjectured
+    /// security level
+    pub proven_security: bool,
+    /// Johnson bound proximity parameter `m` used for the proven security
+    /// level. Larger values tighten the list size at the cost of the FRI
+    /// commit phase error.
+    pub list_decoding_param: u8,
+    /// Trace and FRI layers are committed to by the `2^merkle_cap_height`
+    /// Merkle tree nodes at this depth rather than the root. Shortens each
+    /// authentication path by `merkle_cap_height` nodes.
+    pub merkle_cap_height: u8,
+    /// Number of children of each node in the trace and FRI layer Merkle
+    /// trees. Higher arity trees are shallower which shortens authentication
+    /// paths for hashes with a wide input.
+    pub merkle_arity: u8,
+    /// Salt the leaves of the trace Merkle trees so authentication paths don't
+    /// reveal unopened rows. Salts of opened leaves are sent with the proof.
+    /// Also fills the randomizer rows of the trace (see
+    /// [Air::num_randomizer_rows]) with random values and masks the
+    /// DEEP composition polynomial with a random composition trace column so
+    /// opened values don't reveal the witness.
+    pub zk: bool,
+    /// Sum the randomly weighted numerators of constraints that share a
+    /// divisor before multiplying by the divisor's inverse. Saves a
+    /// multiplication per constraint and point when the prover evaluates
+    /// constraints and the verifier groups its out-of-domain evaluation the
+    /// same way. The composition polynomial is unchanged.
+    pub batch_constraints: bool,
+    /// How constraint composition coefficients are drawn. Recorded in the
+    /// proof so verifiers draw them the same way.
+    pub composition_coeffs: CoefficientMode,
 }
 
 impl ProofOptions {
@@ -77,7 +161,11 @@ impl ProofOptions {
     pub const MIN_BLOWUP_FACTOR: u8 = 1;
     pub const MAX_BLOWUP_FACTOR: u8 = 64;
     pub const MAX_GRINDING_FACTOR: u8 = 32;
+    pub const MAX_EXTENSION_DEGREE: u8 = 4;
+    pub const MAX_MERKLE_CAP_HEIGHT: u8 = 16;
+    pub const MIN_LIST_DECODING_PARAM: u8 = 3;
 
+    /// Panics if the options are invalid. See [ProofOptions::try_new].
     pub fn new(
         num_queries: u8,
         lde_blowup_factor: u8,
@@ -85,40 +173,298 @@ impl ProofOptions {
         fri_folding_factor: u8,
         fri_max_remainder_size: u8,
     ) -> Self {
-        assert!(num_queries >= Self::MIN_NUM_QUERIES);
-        assert!(num_queries <= Self::MAX_NUM_QUERIES);
-        assert!(lde_blowup_factor.is_power_of_two());
-        assert!(lde_blowup_factor >= Self::MIN_BLOWUP_FACTOR);
-        assert!(lde_blowup_factor <= Self::MAX_BLOWUP_FACTOR);
-        assert!(grinding_factor <= Self::MAX_GRINDING_FACTOR);
-        ProofOptions {
+        Self::try_new(
             num_queries,
             lde_blowup_factor,
             grinding_factor,
             fri_folding_factor,
             fri_max_remainder_size,
+        )
+        .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Returns [Error::InvalidOptions] if a parameter is out of range
+    pub fn try_new(
+        num_queries: u8,
+        lde_blowup_factor: u8,
+        grinding_factor: u8,
+        fri_folding_factor: u8,
+        fri_max_remainder_size: u8,
+    ) -> Result<Self, Error> {
+        let options = ProofOptions {
+            num_queries,
+            lde_blowup_factor,
+            grinding: GrindingConfig {
+                hash: GrindingHash::Channel,
+                bits: grinding_factor,
+            },
+            fri_folding_factor,
+            fri_folding_schedule: FoldingSchedule::default(),
+            fri_max_remainder_size,
+            fri_remainder_coeffs: false,
+            fri_early_stop: false,
+            extension_degree: None,
+            security_target: None,
+            proven_security: false,
+            list_decoding_param: Self::MIN_LIST_DECODING_PARAM,
+            merkle_cap_height: 0,
+            merkle_arity: 2,
+            zk: false,
+            batch_constraints: false,
+            composition_coeffs: CoefficientMode::Independent,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Checks every parameter is in range
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::InvalidOptions { reason });
+        let num_queries = self.num_queries;
+        if !(Self::MIN_NUM_QUERIES..=Self::MAX_NUM_QUERIES).contains(&num_queries) {
+            return invalid(format!("{num_queries} queries is out of range"));
+        }
+        let blowup_factor = self.lde_blowup_factor;
+        if !blowup_factor.is_power_of_two()
+            || !(Self::MIN_BLOWUP_FACTOR..=Self::MAX_BLOWUP_FACTOR).contains(&blowup_factor)
+        {
+            return invalid(format!("lde blowup factor {blowup_factor} is invalid"));
+        }
+        let grinding_factor = self.grinding.bits;
+        if grinding_factor > Self::MAX_GRINDING_FACTOR {
+            return invalid(format!("grinding factor {grinding_factor} is too large"));
+        }
+        if let Some(degree) = self.extension_degree
+            && !(1..=Self::MAX_EXTENSION_DEGREE).contains(&degree)
+        {
+            return invalid(format!("extension degree {degree} is out of range"));
+        }
+        let folding_factor = self.fri_folding_factor;
+        if !fri::is_supported_folding_factor(folding_factor.into()) {
+            return invalid(format!(
+                "fri folding factor {folding_factor} is not supported"
+            ));
+        }
+        let remainder_size = self.fri_max_remainder_size;
+        if !remainder_size.is_power_of_two() {
+            return invalid(format!(
+                "fri max remainder size {remainder_size} is invalid"
+            ));
+        }
+        if !self.fri_folding_schedule.is_supported() {
+            return invalid("fri folding schedule is not supported".to_string());
+        }
+        let cap_height = self.merkle_cap_height;
+        if cap_height > Self::MAX_MERKLE_CAP_HEIGHT {
+            return invalid(format!("merkle cap height {cap_height} is too large"));
+        }
+        let param = self.list_decoding_param;
+        if param < Self::MIN_LIST_DECODING_PARAM {
+            return invalid(format!("list decoding param {param} is too small"));
+        }
+        Ok(())
+    }
+
+    pub fn with_extension_degree(mut self, extension_degree: u8) -> Self {
+        assert!(extension_degree >= 1);
+        assert!(extension_degree <= Self::MAX_EXTENSION_DEGREE);
+        self.extension_degree = Some(extension_degree);
+        self
+    }
+
+    /// Uses a different hash function for the proof-of-work than the channel
+    pub fn with_grinding_hash(mut self, hash: GrindingHash) -> Self {
+        self.grinding.hash = hash;
+        self
+    }
+
+    /// Folds the first FRI layers by `schedule` e.g. `&[8, 4]` folds by 8 then
+    /// by 4 before continuing with `fri_folding_factor`
+    pub fn with_fri_folding_schedule(mut self, schedule: &[u8]) -> Self {
+        self.fri_folding_schedule = FoldingSchedule::new(schedule);
+        self
+    }
+
+    pub fn with_fri_remainder_coeffs(mut self) -> Self {
+        self.fri_remainder_coeffs = true;
+        self
+    }
+
+    pub fn with_fri_early_stop(mut self) -> Self {
+        self.fri_early_stop = true;
+        self
+    }
+
+    pub fn with_security_target(mut self, security_target: u8) -> Self {
+        self.security_target = Some(security_target);
+        self
+    }
+
+    /// Requires proofs to achieve `security_target` bits of proven security
+    pub fn with_proven_security_target(mut self, security_target: u8) -> Self {
+        self.security_target = Some(security_target);
+        self.proven_security = true;
+        self
+    }
+
+    pub fn with_list_decoding_param(mut self, list_decoding_param: u8) -> Self {
+        assert!(list_decoding_param >= Self::MIN_LIST_DECODING_PARAM);
+        self.list_decoding_param = list_decoding_param;
+        self
+    }
+
+    pub fn with_merkle_cap_height(mut self, merkle_cap_height: u8) -> Self {
+        assert!(merkle_cap_height <= Self::MAX_MERKLE_CAP_HEIGHT);
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
+    pub fn with_merkle_arity(mut self, merkle_arity: u8) -> Self {
+        assert!(merkle::is_supported_arity(merkle_arity.into()));
+        self.merkle_arity = merkle_arity;
+        self
+    }
+
+    /// Hides the witness (see [ProofOptions::zk]). The AIR must also declare
+    /// at least [ProofOptions::min_randomizer_rows] randomizer rows (see
+    /// [Air::num_randomizer_rows]) otherwise proving fails.
+    pub fn with_zk(mut self) -> Self {
+        self.zk = true;
+        self
+    }
+
+    /// Fewest randomizer rows that hide the witness. Each query opens a row
+    /// of the trace and the out-of-domain evaluations reveal two more linear
+    /// combinations of rows. Zero if [ProofOptions::zk] isn't set.
+    pub fn min_randomizer_rows(&self) -> usize {
+        match self.zk {
+            true => usize::from(self.num_queries) + 2,
+            false => 0,
         }
     }
 
+    /// Batches constraints that share a divisor (see
+    /// [ProofOptions::batch_constraints])
+    pub fn with_batched_constraints(mut self) -> Self {
+        self.batch_constraints = true;
+        self
+    }
+
+    /// Draws constraint composition coefficients as powers of one challenge
+    /// (see [CoefficientMode::Powers])
+    pub fn with_composition_coeffs(mut self, mode: CoefficientMode) -> Self {
+        self.composition_coeffs = mode;
+        self
+    }
+
+    /// Upper bound on the serialized size (in bytes) of a proof for a trace
+    /// described by `trace_info`. Allows picking parameters for a target proof
+    /// size without generating a proof. `ce_blowup_factor` is the number of
+    /// columns the composition polynomial is split into (see
+    /// [Air::ce_blowup_factor]) excluding the zk mask column. Assumes every
+    /// column is opened at the current and next row. Excludes public inputs.
+    pub fn estimated_proof_size<A: Air>(
+        &self,
+        trace_info: &TraceInfo,
+        ce_blowup_factor: usize,
+    ) -> usize {
+        use utils::serialized_vec_size as vec_size;
+        let fp_size = A::Fp::zero().compressed_size();
+        let fq_size = A::Fq::zero().compressed_size();
+        let u64_size = 0u64.compressed_size();
+        let digest_size = <A::Digest as Digest>::output_size();
+        let lde_domain_size = trace_info.trace_len * usize::from(self.lde_blowup_factor);
+        let cap_height = self.merkle_cap_height.into();
+        let arity = self.merkle_arity.into();
+        let cap_size =
+            merkle::MerkleTree::<A::Digest>::cap_size(lde_domain_size, cap_height, arity);
+        let path_len =
+            merkle::MerkleTree::<A::Digest>::proof_len(lde_domain_size, cap_height, arity);
+        let num_queries = usize::from(self.num_queries).min(lde_domain_size);
+        let num_segments = trace_info.num_extension_segments();
+        let num_composition_columns = ce_blowup_factor + usize::from(self.zk);
+        // commitments are caps and merkle proofs are paths of concatenated digests
+        let commitment_size = vec_size(cap_size * digest_size, 1);
+        let opening_size = vec_size(path_len * digest_size, 1);
+        let openings_size = vec_size(num_queries, opening_size);
+        let salts_size = vec_size(if self.zk { num_queries } else { 0 }, merkle::SALT_SIZE);
+        let num_preprocessed_queries = if trace_info.num_preprocessed_columns > 0 {
+            num_queries
+        } else {
+            0
+        };
+
+        // fields of [Queries] in order
+        let queries_size = vec_size(num_queries * trace_info.num_base_columns, fp_size)
+            + vec_size(num_queries * trace_info.num_extension_columns, fq_size)
+            + vec_size(num_queries * num_composition_columns, fq_size)
+            + vec_size(num_queries * trace_info.num_preprocessed_columns, fp_size)
+            + openings_size
+            + vec_size(num_segments, openings_size)
+            + openings_size
+            + vec_size(num_preprocessed_queries, opening_size)
+            + salts_size
+            + vec_size(num_segments, salts_size)
+            + salts_size;
+        let fri_proof_size = self
+            .into_fri_options()
+            .estimated_proof_size::<A::Fq, A::Digest>(lde_domain_size, num_queries);
+
+        // fields of [Proof] in order excluding the public inputs
+        self.compressed_size()
+            + trace_info.compressed_size()
+            + u64_size
+            + commitment_size
+            + vec_size(num_segments, commitment_size)
+            + commitment_size
+            + fri_proof_size
+            + u64_size
+            + queries_size
+            + vec_size(2 * trace_info.num_columns(), fq_size)
+            + vec_size(num_composition_columns, fq_size)
+    }
+
     pub fn into_fri_options(self) -> FriOptions {
         // TODO: move fri params into struct
-        FriOptions::new(
+        let options = FriOptions::new(
             self.lde_blowup_factor.into(),
             self.fri_folding_factor.into(),
             self.fri_max_remainder_size.into(),
         )
+        .with_folding_schedule(self.fri_folding_schedule)
+        .with_merkle_cap_height(self.merkle_cap_height.into())
+        .with_merkle_arity(self.merkle_arity.into());
+        if self.fri_early_stop {
+            options.with_early_stop()
+        } else if self.fri_remainder_coeffs {
+            options.with_remainder_coeffs()
+        } else {
+            options
+        }
     }
 }
 
+/// Proof of the low-degree test used by `A`
+pub type LdtProof<A> =
+    <<A as Air>::LdtVerifier as LdtVerifier<<A as Air>::Fq, <A as Air>::Digest>>::Proof;
+
+/// Opening of a trace commitment of `A`
+pub type VectorCommitmentProof<A> =
+    <<A as Air>::VectorCommitment as VectorCommitment<<A as Air>::Digest>>::Proof;
+
 /// A proof generated by a mini-stark prover
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct Proof<A: Air> {
     pub options: ProofOptions,
     pub trace_info: TraceInfo,
+    /// Identifier of the hash function used for commitments and the
+    /// transcript. See [random::hash_id].
+    pub hash_id: u64,
     pub base_trace_commitment: Vec<u8>,
-    pub extension_trace_commitment: Option<Vec<u8>>,
+    /// Commitments to each extension trace segment
+    pub extension_trace_commitments: Vec<Vec<u8>>,
     pub composition_trace_commitment: Vec<u8>,
-    pub fri_proof: FriProof<A::Fq>,
+    pub fri_proof: LdtProof<A>,
     pub pow_nonce: u64,
     pub trace_queries: Queries<A>,
     pub public_inputs: A::PublicInputs,
@@ -127,19 +473,59 @@ pub struct Proof<A: Air> {
 }
 
 impl<A: Air> Proof<A> {
+    /// Conjectured security level (in bits). Composition coefficients drawn
+    /// as powers of one challenge cost bits of the field (see
+    /// [CoefficientMode::security_loss]).
     pub fn conjectured_security_level(&self) -> usize {
         let prime_field_bits = <<A::Fp as Field>::BasePrimeField as PrimeField>::MODULUS.num_bits();
         let fq_bits = prime_field_bits as usize * A::Fq::extension_degree() as usize;
-        let sha256_collision_resistance_security = 128;
+        // birthday bound on the digest
+        let collision_resistance = <A::Digest as Digest>::output_size() * 8 / 2;
         utils::conjectured_security_level(
-            fq_bits,
-            sha256_collision_resistance_security,
+            fq_bits.saturating_sub(self.composition_coeffs_security_loss()),
+            collision_resistance,
             self.options.lde_blowup_factor.into(),
             self.trace_info.trace_len,
             self.options.num_queries.into(),
-            self.options.grinding_factor.into(),
+            self.options.grinding.bits.into(),
         )
     }
+
+    /// Security level (in bits) provable in the list-decoding regime. See
+    /// [utils::proven_security_level]. Composition coefficients cost bits of
+    /// the field as for [Proof::conjectured_security_level].
+    pub fn proven_security_level(&self) -> usize {
+        let prime_field_bits = <<A::Fp as Field>::BasePrimeField as PrimeField>::MODULUS.num_bits();
+        let fq_bits = prime_field_bits as usize * A::Fq::extension_degree() as usize;
+        // birthday bound on the digest
+        let collision_resistance = <A::Digest as Digest>::output_size() * 8 / 2;
+        utils::proven_security_level(
+            fq_bits.saturating_sub(self.composition_coeffs_security_loss()),
+            collision_resistance,
+            self.options.lde_blowup_factor.into(),
+            self.trace_info.trace_len,
+            self.options.num_queries.into(),
+            self.options.grinding.bits.into(),
+            self.options.list_decoding_param.into(),
+        )
+    }
+
+    /// Bits of the field lost to how the composition coefficients are drawn.
+    /// Each constraint is mixed in with two coefficients.
+    fn composition_coeffs_security_loss(&self) -> usize {
+        let mode = self.options.composition_coeffs;
+        match mode {
+            CoefficientMode::Independent => 0,
+            CoefficientMode::Powers => {
+                let air = A::new(
+                    self.trace_info.clone(),
+                    self.public_inputs.clone(),
+                    self.options,
+                );
+                mode.security_loss(2 * air.all_constraints().len())
+            }
+        }
+    }
 }
 
 pub trait StarkExtensionOf<Fp: GpuFftField + FftField>:
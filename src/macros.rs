@@ -46,3 +46,69 @@ macro_rules! forward_ref_op_assign {
         }
     };
 }
+
+/// Implements [ExecutionTraceColumn](crate::constraints::ExecutionTraceColumn)
+/// for a fieldless enum so variants can be used in constraints e.g.
+/// `Column::A.next() - Column::B.curr()`. Variants are numbered in declaration
+/// order starting at `first` (defaults to 0). The enum must be `Copy`.
+#[macro_export]
+macro_rules! impl_execution_trace_column {
+    ($t:ty) => {
+        $crate::impl_execution_trace_column!($t, 0);
+    };
+    ($t:ty, $first:expr) => {
+        impl $crate::constraints::ExecutionTraceColumn for $t {
+            fn index(&self) -> usize {
+                ($first) + *self as usize
+            }
+        }
+    };
+}
+
+/// Implements [VerifierChallenge](crate::constraints::VerifierChallenge) for a
+/// fieldless enum. See [impl_execution_trace_column].
+#[macro_export]
+macro_rules! impl_verifier_challenge {
+    ($t:ty) => {
+        $crate::impl_verifier_challenge!($t, 0);
+    };
+    ($t:ty, $first:expr) => {
+        impl $crate::constraints::VerifierChallenge for $t {
+            fn index(&self) -> usize {
+                ($first) + *self as usize
+            }
+        }
+    };
+}
+
+/// Implements [Hint](crate::constraints::Hint) for a fieldless enum. See
+/// [impl_execution_trace_column].
+#[macro_export]
+macro_rules! impl_hint {
+    ($t:ty) => {
+        $crate::impl_hint!($t, 0);
+    };
+    ($t:ty, $first:expr) => {
+        impl $crate::constraints::Hint for $t {
+            fn index(&self) -> usize {
+                ($first) + *self as usize
+            }
+        }
+    };
+}
+
+/// Implements [Periodic](crate::constraints::Periodic) for a fieldless enum.
+/// See [impl_execution_trace_column].
+#[macro_export]
+macro_rules! impl_periodic {
+    ($t:ty) => {
+        $crate::impl_periodic!($t, 0);
+    };
+    ($t:ty, $first:expr) => {
+        impl $crate::constraints::Periodic for $t {
+            fn index(&self) -> usize {
+                ($first) + *self as usize
+            }
+        }
+    };
+}
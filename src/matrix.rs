@@ -1,27 +1,51 @@
 use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::FieldType;
+use crate::domain::Domain;
+use crate::domain::StarkDomain;
+use crate::merkle::CompressedLeafEncoder;
+use crate::merkle::LeafEncoder;
 use crate::merkle::MerkleTree;
+#[cfg(feature = "gpu")]
+use crate::utils::commit_and_wait;
 use crate::utils::horner_evaluate;
+use crate::Error;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
-use ark_poly::domain::DomainCoeff;
-use ark_poly::domain::Radix2EvaluationDomain;
-#[cfg(not(feature = "gpu"))]
-use ark_poly::EvaluationDomain;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
 use core::cmp::Ordering;
 use core::ops::Add;
 use core::ops::Deref;
 use core::ops::DerefMut;
 use core::ops::Index;
 use core::ops::IndexMut;
+use core::ops::Range;
 use digest::Digest;
+use digest::Output;
 use gpu_poly::prelude::*;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+/// Rows at least this many bytes wide are hashed one at a time with Blake3's
+/// multi-threaded tree hashing. Below this, splitting a row across threads
+/// costs more than it saves.
+#[cfg(all(feature = "blake3", feature = "parallel"))]
+const BLAKE3_WIDE_ROW_BYTES: usize = 128 * 1024;
+
+/// Number of columns transposed together by [Matrix::from_row_major]. A block
+/// of a row fits in a cache line for 8 byte fields.
+pub const TRANSPOSE_BLOCK_COLS: usize = 8;
+
 /// Matrix is an array of columns.
 pub struct Matrix<F>(pub Vec<GpuVec<F>>);
 
@@ -31,21 +55,64 @@ impl<F: Field> Matrix<F> {
     }
 
     pub fn from_rows(rows: Vec<Vec<F>>) -> Self {
-        let num_rows = rows.len();
         let num_cols = rows.first().map(|first| first.len()).unwrap_or(0);
+        Self::from_row_iter(rows, num_cols)
+    }
+
+    /// Builds a matrix from an iterator of rows with `num_cols` values each.
+    /// Values are written straight into the columns so no intermediate rows
+    /// are allocated.
+    pub fn from_row_iter<R: AsRef<[F]>>(
+        rows: impl IntoIterator<Item = R>,
+        num_cols: usize,
+    ) -> Self {
+        let rows = rows.into_iter();
         let mut cols = (0..num_cols)
-            .map(|_| Vec::with_capacity_in(num_rows, PageAlignedAllocator))
+            .map(|_| Vec::with_capacity_in(rows.size_hint().0, PageAlignedAllocator))
             .collect::<Vec<GpuVec<F>>>();
-        // TODO: parallelise
         for row in rows {
-            debug_assert_eq!(row.len(), num_cols);
+            let row = row.as_ref();
+            assert_eq!(num_cols, row.len(), "row has the wrong number of values");
             for (col, value) in cols.iter_mut().zip(row) {
-                col.push(value)
+                col.push(*value)
             }
         }
         Matrix::new(cols)
     }
 
+    /// Builds a matrix from values laid out row by row i.e. value `j` of row
+    /// `i` is `values[i * num_cols + j]`. Columns are transposed in blocks of
+    /// [TRANSPOSE_BLOCK_COLS] in parallel. Each block reads a contiguous part
+    /// of every row and writes each of its columns sequentially.
+    pub fn from_row_major(values: &[F], num_cols: usize) -> Self {
+        assert!(num_cols > 0, "matrix must have at least one column");
+        assert_eq!(
+            0,
+            values.len() % num_cols,
+            "values don't fill a whole number of rows"
+        );
+        let num_rows = values.len() / num_cols;
+        let mut cols = (0..num_cols)
+            .map(|_| {
+                let mut col = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+                col.resize(num_rows, F::zero());
+                col
+            })
+            .collect::<Vec<GpuVec<F>>>();
+        ark_std::cfg_chunks_mut!(cols, TRANSPOSE_BLOCK_COLS)
+            .enumerate()
+            .for_each(|(block, block_cols)| {
+                let start = block * TRANSPOSE_BLOCK_COLS;
+                for (i, row) in values.chunks_exact(num_cols).enumerate() {
+                    let row_block = &row[start..start + block_cols.len()];
+                    for (col, value) in block_cols.iter_mut().zip(row_block) {
+                        col[i] = *value;
+                    }
+                }
+            });
+        Matrix::new(cols)
+    }
+
     // TODO: perhaps bring naming of rows and cols in line with
     // how the trace is names i.e. len and width.
     pub fn num_rows(&self) -> usize {
@@ -60,6 +127,13 @@ impl<F: Field> Matrix<F> {
         expected_len
     }
 
+    /// Number of rows of the longest column. Unlike [Matrix::num_rows] the
+    /// columns can have different lengths e.g. a base trace with segments of
+    /// different lengths.
+    pub fn max_num_rows(&self) -> usize {
+        self.0.iter().map(|col| col.len()).max().unwrap_or(0)
+    }
+
     pub fn append(&mut self, other: Matrix<F>) {
         for col in other.0 {
             self.0.push(col)
@@ -78,117 +152,150 @@ impl<F: Field> Matrix<F> {
         self.0.len()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.num_rows() == 0
-    }
-
-    #[cfg(feature = "gpu")]
-    fn into_polynomials_gpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
-    where
-        F: GpuField + DomainCoeff<F::FftField>,
-        F::FftField: FftField,
-    {
-        let mut ifft = GpuIfft::from(domain);
-
-        for column in &mut self.0 {
-            ifft.encode(column);
-        }
-
-        ifft.execute();
-
-        self
+    /// Copies rows `range` of every column into a new matrix e.g. to inspect
+    /// a range of cycles of a long execution or to split a trace into
+    /// continuation segments.
+    pub fn slice_rows(&self, range: Range<usize>) -> Matrix<F> {
+        Matrix::new(
+            self.0
+                .iter()
+                .map(|col| col[range.clone()].to_vec_in(PageAlignedAllocator))
+                .collect(),
+        )
     }
 
-    #[cfg(not(feature = "gpu"))]
-    fn into_polynomials_cpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
-    where
-        F: GpuField + DomainCoeff<F::FftField>,
-        F::FftField: FftField,
-    {
-        self.0.iter_mut().for_each(|col| domain.ifft_in_place(col));
-        self
+    pub fn is_empty(&self) -> bool {
+        self.num_rows() == 0
     }
 
     /// Interpolates the columns of the polynomials over the domain
-    pub fn into_polynomials(self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
-    where
-        F: GpuField + DomainCoeff<F::FftField>,
-        F::FftField: FftField,
-    {
+    pub fn into_polynomials<D: Domain<F>>(mut self, domain: D) -> Self {
         // TODO: using the newtype pattern for type safety would be cool
         // i.e. take as input Matrix<Evaluations> and return Matrix<Polynomials>
         // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
-        #[cfg(not(feature = "gpu"))]
-        return self.into_polynomials_cpu(domain);
-        #[cfg(feature = "gpu")]
-        return self.into_polynomials_gpu(domain);
+        domain.interpolate_columns(&mut self.0);
+        self
     }
 
     /// Interpolates the columns of the matrix over the domain
-    pub fn interpolate(&self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
-    where
-        F: GpuField + DomainCoeff<F::FftField>,
-        F::FftField: FftField,
-    {
+    pub fn interpolate<D: Domain<F>>(&self, domain: D) -> Self {
         self.clone().into_polynomials(domain)
     }
 
-    #[cfg(not(feature = "gpu"))]
-    fn into_evaluations_cpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
-    where
-        F: GpuField + DomainCoeff<F::FftField>,
-        F::FftField: FftField,
-    {
-        for column in &mut self.0 {
-            domain.fft_in_place(column);
-        }
+    /// Evaluates the columns of the matrix
+    pub fn into_evaluations<D: Domain<F>>(mut self, domain: D) -> Self {
+        // TODO: using the newtype pattern for type safety would be cool
+        // i.e. take as input Matrix<Polynomials> and return Matrix<Evaluations>
+        // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
+        domain.evaluate_columns(&mut self.0);
         self
     }
 
-    #[cfg(feature = "gpu")]
-    fn into_evaluations_gpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
+    /// Evaluates the columns of the matrix
+    pub fn evaluate<D: Domain<F>>(&self, domain: D) -> Self {
+        self.clone().into_evaluations(domain)
+    }
+
+    pub fn commit_to_rows<D: Digest + 'static>(&self) -> MerkleTree<D> {
+        self.commit_to_rows_with::<D, CompressedLeafEncoder>()
+    }
+
+    /// Commits to the rows of the matrix with leaves encoded by `E`
+    pub fn commit_to_rows_with<D: Digest + 'static, E: LeafEncoder>(&self) -> MerkleTree<D> {
+        self.try_commit_to_rows_with::<D, E>()
+            .expect("failed to construct Merkle tree")
+    }
+
+    /// Same as [Matrix::commit_to_rows_with] but returns an error rather
+    /// than panicking if the matrix can't be committed to e.g. if the number
+    /// of rows isn't a power of two
+    pub fn try_commit_to_rows_with<D: Digest + 'static, E: LeafEncoder>(
+        &self,
+    ) -> Result<MerkleTree<D>, Error> {
+        Ok(MerkleTree::new(self.hash_rows::<D, E>())?)
+    }
+
+    /// Commits to the rows of the low degree extension of the matrix's
+    /// polynomials without materializing the entire LDE. The LDE is evaluated
+    /// one coset of the trace domain at a time; each coset's rows are hashed
+    /// and then discarded. Produces the same tree as
+    /// `self.evaluate(lde_domain).commit_to_rows()`.
+    pub fn commit_to_lde_rows<D: Digest + 'static>(
+        &self,
+        trace_domain: impl StarkDomain<F::FftField> + Domain<F>,
+        lde_domain: impl StarkDomain<F::FftField>,
+    ) -> MerkleTree<D>
     where
         F: GpuField,
         F::FftField: FftField,
     {
-        let mut fft = GpuFft::from(domain);
-
-        for column in &mut self.0 {
-            fft.encode(column);
-        }
-
-        fft.execute();
-
-        self
+        self.commit_to_lde_rows_with::<D, CompressedLeafEncoder>(trace_domain, lde_domain)
     }
 
-    /// Evaluates the columns of the matrix
-    pub fn into_evaluations(self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
+    /// Same as [Matrix::commit_to_lde_rows] with leaves encoded by `E`
+    pub fn commit_to_lde_rows_with<D: Digest + 'static, E: LeafEncoder>(
+        &self,
+        trace_domain: impl StarkDomain<F::FftField> + Domain<F>,
+        lde_domain: impl StarkDomain<F::FftField>,
+    ) -> MerkleTree<D>
     where
-        F: GpuField + DomainCoeff<F::FftField>,
+        F: GpuField,
         F::FftField: FftField,
     {
-        // TODO: using the newtype pattern for type safety would be cool
-        // i.e. take as input Matrix<Polynomials> and return Matrix<Evaluations>
-        // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
-        #[cfg(not(feature = "gpu"))]
-        return self.into_evaluations_cpu(domain);
-        #[cfg(feature = "gpu")]
-        return self.into_evaluations_gpu(domain);
+        let row_hashes = self.hash_lde_rows::<D, E>(trace_domain, lde_domain);
+        MerkleTree::new(row_hashes).expect("failed to construct Merkle tree")
     }
 
-    /// Evaluates the columns of the matrix
-    pub fn evaluate(&self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
+    /// Hashes of the rows of the low degree extension of the matrix's
+    /// polynomials. Evaluated one coset at a time like
+    /// [Matrix::commit_to_lde_rows].
+    pub fn hash_lde_rows<D: Digest + 'static, E: LeafEncoder>(
+        &self,
+        trace_domain: impl StarkDomain<F::FftField> + Domain<F>,
+        lde_domain: impl StarkDomain<F::FftField>,
+    ) -> Vec<Output<D>>
     where
-        F: GpuField + DomainCoeff<F::FftField>,
+        F: GpuField,
         F::FftField: FftField,
     {
-        self.clone().into_evaluations(domain)
+        let n = trace_domain.size();
+        let lde_size = lde_domain.size();
+        let blowup_factor = lde_size / n;
+        let mut row_hashes = vec![Output::<D>::default(); lde_size];
+        let mut coset_offset = lde_domain.coset_offset();
+        for coset_idx in 0..blowup_factor {
+            // row `i` of the coset is row `coset_idx + i * blowup_factor` of the LDE
+            let coset = trace_domain.new_coset_like(n, coset_offset).unwrap();
+            let coset_hashes = self.evaluate(coset).hash_rows::<D, E>();
+            for (i, hash) in coset_hashes.into_iter().enumerate() {
+                row_hashes[coset_idx + i * blowup_factor] = hash;
+            }
+            coset_offset *= lde_domain.group_gen();
+        }
+        row_hashes
     }
 
-    pub fn commit_to_rows<D: Digest>(&self) -> MerkleTree<D> {
+    /// Hashes of the rows of the matrix with leaves encoded by `E` (see
+    /// [LeafEncoder::hash_rows])
+    pub fn hash_rows<D: Digest + 'static, E: LeafEncoder>(&self) -> Vec<Output<D>> {
+        E::hash_rows::<F, D>(self)
+    }
+
+    /// Hashes of the rows of the matrix with each row encoded into a buffer
+    /// by [LeafEncoder::encode_row]
+    pub fn hash_encoded_rows<D: Digest + 'static, E: LeafEncoder>(&self) -> Vec<Output<D>> {
         let num_rows = self.num_rows();
 
+        #[cfg(all(feature = "blake3", feature = "parallel"))]
+        if core::any::TypeId::of::<D>() == core::any::TypeId::of::<crate::Blake3>()
+            && self.num_cols() * F::zero().compressed_size() >= BLAKE3_WIDE_ROW_BYTES
+        {
+            return self
+                .hash_wide_rows_blake3::<E>()
+                .map(|hash| Output::<D>::clone_from_slice(hash.as_bytes()))
+                .collect();
+        }
+
         let mut row_hashes = vec![Default::default(); num_rows];
 
         #[cfg(not(feature = "parallel"))]
@@ -210,12 +317,66 @@ impl<F: Field> Matrix<F> {
                 for (i, row_hash) in chunk.iter_mut().enumerate() {
                     row_bytes.clear();
                     self.read_row(offset + i, &mut row_buffer);
-                    row_buffer.serialize_compressed(&mut row_bytes).unwrap();
+                    E::encode_row(&row_buffer, &mut row_bytes);
                     *row_hash = D::new_with_prefix(&row_bytes).finalize();
                 }
             });
 
-        MerkleTree::new(row_hashes).expect("failed to construct Merkle tree")
+        row_hashes
+    }
+
+    /// Hashes the rows of the matrix with leaves encoded by
+    /// [crate::merkle::LittleEndianLeafEncoder]. The fixed-width limbs of each
+    /// element are fed to the hasher straight from the columns so rows are
+    /// never copied or serialized. Hashing has the same layout a GPU hashing
+    /// stage would use.
+    pub fn hash_rows_native<D: Digest>(&self) -> Vec<Output<D>> {
+        let mut row_hashes = vec![Default::default(); self.num_rows()];
+
+        #[cfg(not(feature = "parallel"))]
+        let chunk_size = row_hashes.len();
+        #[cfg(feature = "parallel")]
+        let chunk_size = core::cmp::max(
+            row_hashes.len() / rayon::current_num_threads().next_power_of_two(),
+            128,
+        );
+
+        ark_std::cfg_chunks_mut!(row_hashes, chunk_size)
+            .enumerate()
+            .for_each(|(chunk_offset, chunk)| {
+                let offset = chunk_size * chunk_offset;
+                for (i, row_hash) in chunk.iter_mut().enumerate() {
+                    let mut hasher = D::new();
+                    for column in &self.0 {
+                        let element = column[offset + i];
+                        for coeff in element.to_base_prime_field_elements() {
+                            for limb in coeff.into_bigint().as_ref() {
+                                hasher.update(limb.to_le_bytes());
+                            }
+                        }
+                    }
+                    *row_hash = hasher.finalize();
+                }
+            });
+
+        row_hashes
+    }
+
+    /// Hashes rows one at a time using all threads per row. Blake3 is a tree
+    /// hash so each row can be split across threads which beats hashing many
+    /// rows in parallel once rows are wide enough.
+    #[cfg(all(feature = "blake3", feature = "parallel"))]
+    fn hash_wide_rows_blake3<E: LeafEncoder>(&self) -> impl Iterator<Item = blake3::Hash> + '_ {
+        let mut row_buffer = vec![F::zero(); self.num_cols()];
+        let mut row_bytes = Vec::with_capacity(row_buffer.compressed_size());
+        (0..self.num_rows()).map(move |i| {
+            row_bytes.clear();
+            self.read_row(i, &mut row_buffer);
+            E::encode_row(&row_buffer, &mut row_bytes);
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_rayon(&row_bytes);
+            blake3::Hasher::finalize(&hasher)
+        })
     }
 
     pub fn evaluate_at<T: Field>(&self, x: T) -> Vec<T>
@@ -264,7 +425,13 @@ impl<F: Field> Matrix<F> {
     #[cfg(not(feature = "gpu"))]
     pub fn sum_columns_cpu(&self) -> Matrix<F> {
         let n = self.num_rows();
-        let mut accumulator = Vec::with_capacity_in(n, PageAlignedAllocator);
+        self.sum_columns_cpu_into(Vec::with_capacity_in(n, PageAlignedAllocator))
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn sum_columns_cpu_into(&self, mut accumulator: GpuVec<F>) -> Matrix<F> {
+        let n = self.num_rows();
+        accumulator.clear();
         accumulator.resize(n, F::zero());
 
         if self.num_cols() != 0 {
@@ -280,9 +447,18 @@ impl<F: Field> Matrix<F> {
                 .enumerate()
                 .for_each(|(chunk_offset, chunk)| {
                     let offset = chunk_size * chunk_offset;
-                    for column in &self.0 {
-                        for i in 0..chunk_size {
-                            chunk[i] += column[offset + i];
+                    let column_chunks = self
+                        .0
+                        .iter()
+                        .map(|col| &col[offset..offset + chunk.len()])
+                        .collect::<Vec<_>>();
+                    if crate::simd::try_sum_columns(chunk, &column_chunks) {
+                        return;
+                    }
+
+                    for column in column_chunks {
+                        for (acc, v) in chunk.iter_mut().zip(column) {
+                            *acc += v;
                         }
                     }
                 });
@@ -297,14 +473,22 @@ impl<F: Field> Matrix<F> {
         F: GpuField,
     {
         let n = self.num_rows();
-        // TODO: add into_sum_columns and prevent having to allocate new memory
-        let mut accumulator = Vec::with_capacity_in(n, PageAlignedAllocator);
+        self.sum_columns_gpu_into(Vec::with_capacity_in(n, PageAlignedAllocator))
+    }
+
+    #[cfg(feature = "gpu")]
+    fn sum_columns_gpu_into(&self, mut accumulator: GpuVec<F>) -> Matrix<F>
+    where
+        F: GpuField,
+    {
+        let n = self.num_rows();
+        accumulator.clear();
         accumulator.resize(n, F::zero());
 
         if self.num_cols() != 0 {
             // TODO: could improve
-            let library = &PLANNER.library;
-            let command_queue = &PLANNER.command_queue;
+            let library = &planner().library;
+            let command_queue = &planner().command_queue;
             let device = command_queue.device();
             let command_buffer = command_queue.new_command_buffer();
             let mut accumulator_buffer = buffer_mut_no_copy(device, &mut accumulator);
@@ -313,8 +497,7 @@ impl<F: Field> Matrix<F> {
                 let column_buffer = buffer_no_copy(command_queue.device(), column);
                 adder.encode(command_buffer, &mut accumulator_buffer, &column_buffer, 0);
             }
-            command_buffer.commit();
-            command_buffer.wait_until_completed();
+            commit_and_wait(command_buffer);
         }
 
         Matrix::new(vec![accumulator])
@@ -330,6 +513,102 @@ impl<F: Field> Matrix<F> {
         #[cfg(feature = "gpu")]
         return self.sum_columns_gpu();
     }
+
+    /// Sums columns into a single column matrix. The result column is taken
+    /// from `pool`.
+    pub fn sum_columns_in(&self, pool: &BufferPool<F>) -> Matrix<F>
+    where
+        F: GpuField,
+    {
+        let accumulator = pool.get(self.num_rows());
+        #[cfg(not(feature = "gpu"))]
+        return self.sum_columns_cpu_into(accumulator);
+        #[cfg(feature = "gpu")]
+        return self.sum_columns_gpu_into(accumulator);
+    }
+
+    /// Clones the matrix using columns taken from `pool`
+    pub fn clone_in(&self, pool: &BufferPool<F>) -> Matrix<F> {
+        Matrix::new(
+            self.0
+                .iter()
+                .map(|col| {
+                    let mut res = pool.get(col.len());
+                    res.extend_from_slice(col);
+                    res
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the matrix's columns to `pool` so their memory can be reused
+    pub fn recycle(self, pool: &BufferPool<F>) {
+        for col in self.0 {
+            pool.recycle(col);
+        }
+    }
+}
+
+/// A column of a [MatrixGroup]
+#[derive(Clone, Copy)]
+pub enum GroupColumn<'a, Fp, Fq> {
+    Fp(&'a GpuVec<Fp>),
+    Fq(&'a GpuVec<Fq>),
+}
+
+/// Groups matrices over a base field and an extension field so their columns
+/// can be accessed with a single index. Columns are indexed in the order the
+/// matrices were appended to the group.
+pub struct MatrixGroup<'a, Fp: Field, Fq: Field> {
+    fp_matrices: Vec<&'a Matrix<Fp>>,
+    fq_matrices: Vec<&'a Matrix<Fq>>,
+    // maps a column index to `(field_type, matrix_index, matrix_column_index)`
+    column_map: Vec<(FieldType, usize, usize)>,
+}
+
+impl<'a, Fp: Field, Fq: Field> MatrixGroup<'a, Fp, Fq> {
+    pub fn new() -> Self {
+        MatrixGroup {
+            fp_matrices: Vec::new(),
+            fq_matrices: Vec::new(),
+            column_map: Vec::new(),
+        }
+    }
+
+    pub fn append_fp(&mut self, matrix: &'a Matrix<Fp>) {
+        let matrix_idx = self.fp_matrices.len();
+        for col_idx in 0..matrix.num_cols() {
+            self.column_map.push((FieldType::Fp, matrix_idx, col_idx));
+        }
+        self.fp_matrices.push(matrix);
+    }
+
+    pub fn append_fq(&mut self, matrix: &'a Matrix<Fq>) {
+        let matrix_idx = self.fq_matrices.len();
+        for col_idx in 0..matrix.num_cols() {
+            self.column_map.push((FieldType::Fq, matrix_idx, col_idx));
+        }
+        self.fq_matrices.push(matrix);
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.column_map.len()
+    }
+
+    /// Returns the column at index `col` or `None` if it's out of bounds
+    pub fn get_column(&self, col: usize) -> Option<GroupColumn<'a, Fp, Fq>> {
+        let &(field_type, matrix_idx, col_idx) = self.column_map.get(col)?;
+        Some(match field_type {
+            FieldType::Fp => GroupColumn::Fp(&self.fp_matrices[matrix_idx].0[col_idx]),
+            FieldType::Fq => GroupColumn::Fq(&self.fq_matrices[matrix_idx].0[col_idx]),
+        })
+    }
+}
+
+impl<'a, Fp: Field, Fq: Field> Default for MatrixGroup<'a, Fp, Fq> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<F: Field> Clone for Matrix<F> {
@@ -343,6 +622,73 @@ impl<F: Field> Clone for Matrix<F> {
     }
 }
 
+/// Matrices are serialized as the number of columns followed by the length
+/// and values of each column in order. Columns can have different lengths
+/// e.g. base traces with segments of different lengths. Lets expensive traces
+/// be generated once, stored and proven later.
+impl<F: Field> CanonicalSerialize for Matrix<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.num_cols().serialize_with_mode(&mut writer, compress)?;
+        for col in &self.0 {
+            col.len().serialize_with_mode(&mut writer, compress)?;
+            for value in col {
+                value.serialize_with_mode(&mut writer, compress)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        let cols_size = self
+            .0
+            .iter()
+            .map(|col| {
+                col.len().serialized_size(compress)
+                    + col
+                        .iter()
+                        .map(|value| value.serialized_size(compress))
+                        .sum::<usize>()
+            })
+            .sum::<usize>();
+        self.num_cols().serialized_size(compress) + cols_size
+    }
+}
+
+impl<F: Field> Valid for Matrix<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        for col in &self.0 {
+            F::batch_check(col.iter())?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> CanonicalDeserialize for Matrix<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        // lengths are untrusted so columns grow as values are read rather
+        // than being allocated up front
+        let num_cols = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut cols = Vec::new();
+        for _ in 0..num_cols {
+            let num_rows = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+            let mut col = Vec::new_in(PageAlignedAllocator);
+            for _ in 0..num_rows {
+                col.push(F::deserialize_with_mode(&mut reader, compress, validate)?);
+            }
+            cols.push(col);
+        }
+        Ok(Matrix::new(cols))
+    }
+}
+
 impl<F: GpuField> IntoIterator for Matrix<F> {
     type Item = GpuVec<F>;
     type IntoIter = <Vec<GpuVec<F>> as IntoIterator>::IntoIter;
@@ -1,9 +1,38 @@
 //! Use arkwork_rs or re make this. Just used for personal education.
+use crate::hash::pack_bytes;
+use crate::hash::Permutation;
+use crate::hash::CAPACITY;
+use crate::hash::DIGEST_SIZE;
+use crate::hash::RATE;
+use crate::hash::WIDTH;
+use crate::Matrix;
 use alloc::vec::Vec;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+use ark_ff::Zero;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use core::marker::PhantomData;
+use digest::generic_array::typenum::Unsigned;
+use digest::generic_array::ArrayLength;
 use digest::Digest;
+use digest::FixedOutput;
+use digest::HashMarker;
 use digest::Output;
+use digest::OutputSizeUser;
+use digest::Update;
+use gpu_poly::GpuField;
+use rand_chacha::rand_core::RngCore;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use snafu::Snafu;
 
 /// MerkleTree tree error
@@ -11,12 +40,18 @@ use snafu::Snafu;
 pub enum MerkleTreeError {
     #[snafu(display("tree must contain `{expected}` leaves, but `{actual}` were provided"))]
     TooFewLeaves { expected: usize, actual: usize },
+    #[snafu(display("tree can contain `{expected}` leaves, but `{actual}` were provided"))]
+    TooManyLeaves { expected: usize, actual: usize },
     #[snafu(display("number of leaves must be a power of two, but `{n}` were provided"))]
     NumberOfLeavesNotPowerOfTwo { n: usize },
     #[snafu(display("leaf index `{i}` cannot exceed the number of leaves (`{n}`)"))]
     LeafIndexOutOfBounds { i: usize, n: usize },
     #[snafu(display("proof is invalid"))]
     InvalidProof,
+    #[snafu(display("nodes can't have `{arity}` children"))]
+    UnsupportedArity { arity: usize },
+    #[snafu(display("leaf node is not a valid digest"))]
+    InvalidDigest,
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
@@ -27,6 +62,11 @@ impl MerkleProof {
         MerkleProof(merkle_path.into_iter().flatten().collect())
     }
 
+    /// Digests of the path concatenated
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
     pub fn parse<D: Digest>(&self) -> Vec<Output<D>> {
         // TODO: would be great if this whole thing could be better.
         let chunk_size = <D as digest::OutputSizeUser>::output_size();
@@ -37,6 +77,9 @@ impl MerkleProof {
     }
 }
 
+/// Maximum number of children of a Merkle tree node
+pub const MAX_ARITY: usize = 16;
+
 /// Merkle tree implemented as a full power-of-two arity tree.
 ///
 /// ```text
@@ -48,14 +91,36 @@ impl MerkleProof {
 /// |   |   |   |
 /// +   +   +   +  <- leaf values
 /// ```
+///
+/// Each node is the hash of its `arity` children. Higher arity trees (see
+/// [Self::new_with_arity]) are shallower which shortens authentication paths
+/// when hashing more input is cheap e.g. with wide or algebraic hashes. If the
+/// number of leaves is not a power of the arity the root has fewer children.
+///
+/// Leaves can be salted (see [salt_leaf]) before the tree is built so sibling
+/// leaves in authentication paths reveal nothing about unopened leaf values.
+///
+/// The tree can be committed to by a "cap" of the nodes at depth `cap_height`
+/// rather than the root. Proofs then stop at the cap which shortens every
+/// authentication path by `cap_height` levels.
 pub struct MerkleTree<D: Digest> {
-    nodes: Vec<Output<D>>,
-    leaf_nodes: Vec<Output<D>>,
+    /// `levels[0]` are the leaf nodes and the last level is the root
+    levels: Vec<Vec<Output<D>>>,
+    arity: usize,
+    cap_height: usize,
 }
 
 impl<D: Digest> MerkleTree<D> {
     // TODO: why not just commit to leaf values directly
     pub fn new(leaf_nodes: Vec<Output<D>>) -> Result<Self, MerkleTreeError> {
+        Self::new_with_arity(leaf_nodes, 2)
+    }
+
+    /// Builds a tree where each node has `arity` children
+    pub fn new_with_arity(
+        leaf_nodes: Vec<Output<D>>,
+        arity: usize,
+    ) -> Result<Self, MerkleTreeError> {
         let n = leaf_nodes.len();
         if n < 2 {
             return Err(MerkleTreeError::TooFewLeaves {
@@ -64,61 +129,167 @@ impl<D: Digest> MerkleTree<D> {
             });
         } else if !n.is_power_of_two() {
             return Err(MerkleTreeError::NumberOfLeavesNotPowerOfTwo { n });
+        } else if !is_supported_arity(arity) {
+            return Err(MerkleTreeError::UnsupportedArity { arity });
         }
 
-        let nodes = build_merkle_nodes::<D>(&leaf_nodes);
-        Ok(MerkleTree { nodes, leaf_nodes })
+        let levels = build_merkle_levels::<D>(leaf_nodes, arity)?;
+        Ok(MerkleTree {
+            levels,
+            arity,
+            cap_height: 0,
+        })
+    }
+
+    /// Commits to the nodes at depth `cap_height` rather than the root. The
+    /// height is limited to the level above the leaf nodes (see
+    /// [Self::cap_size]).
+    pub fn with_cap_height(mut self, cap_height: usize) -> Self {
+        self.cap_height = cap_height.min(self.levels.len() - 2);
+        self
+    }
+
+    /// Leaf nodes the tree was built from
+    pub fn leaves(&self) -> &[Output<D>] {
+        &self.levels[0]
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Number of nodes in the cap of a tree with `num_leaves` leaves and
+    /// nodes with `arity` children
+    pub fn cap_size(num_leaves: usize, cap_height: usize, arity: usize) -> usize {
+        let level_sizes = level_sizes(num_leaves, arity);
+        let max_cap_height = level_sizes.len().saturating_sub(2);
+        level_sizes[level_sizes.len() - 1 - cap_height.min(max_cap_height)]
+    }
+
+    /// Number of digests in a proof (including the leaf) generated by a tree
+    /// with `num_leaves` leaves, nodes with `arity` children and a cap at
+    /// `cap_height`
+    pub fn proof_len(num_leaves: usize, cap_height: usize, arity: usize) -> usize {
+        let level_sizes = level_sizes(num_leaves, arity);
+        let max_cap_height = level_sizes.len().saturating_sub(2);
+        let cap_level = level_sizes.len() - 1 - cap_height.min(max_cap_height);
+        1 + level_sizes[..cap_level]
+            .iter()
+            .map(|&level_size| arity.min(level_size) - 1)
+            .sum::<usize>()
     }
 
     pub fn root(&self) -> &Output<D> {
-        &self.nodes[1]
+        &self.levels.last().unwrap()[0]
     }
 
+    pub fn cap(&self) -> &[Output<D>] {
+        &self.levels[self.levels.len() - 1 - self.cap_height]
+    }
+
+    /// Serialized cap. Equal to the root if the cap height is zero.
+    pub fn commitment(&self) -> Vec<u8> {
+        self.cap().iter().flatten().copied().collect()
+    }
+
+    /// Parses a cap serialized by [Self::commitment]. Returns `None` if
+    /// `bytes` is not a non-empty sequence of digests.
+    pub fn cap_from_bytes(bytes: &[u8]) -> Option<Vec<Output<D>>> {
+        let digest_size = <D as Digest>::output_size();
+        if bytes.is_empty() || bytes.len() % digest_size != 0 {
+            return None;
+        }
+        Some(
+            bytes
+                .chunks(digest_size)
+                .map(|chunk| Output::<D>::from_slice(chunk).clone())
+                .collect(),
+        )
+    }
+
+    /// Proof consists of the leaf followed by the siblings of the leaf and
+    /// each of its ancestors below the cap
     pub fn prove(&self, index: usize) -> Result<MerkleProof, MerkleTreeError> {
-        if index >= self.leaf_nodes.len() {
+        let leaf_nodes = &self.levels[0];
+        if index >= leaf_nodes.len() {
             return Err(MerkleTreeError::LeafIndexOutOfBounds {
-                n: self.leaf_nodes.len(),
+                n: leaf_nodes.len(),
                 i: index,
             });
         }
 
         // TODO: batch proofs
         // TODO: could omit leaf_nodes[index]
-        let mut path = vec![
-            self.leaf_nodes[index].clone(),
-            self.leaf_nodes[index ^ 1].clone(),
-        ];
-
-        let mut index = (index + self.nodes.len()) >> 1;
-        while index > 1 {
-            path.push(self.nodes[index ^ 1].clone());
-            index >>= 1;
+        let mut path = vec![leaf_nodes[index].clone()];
+        let mut index = index;
+        let cap_level = self.levels.len() - 1 - self.cap_height;
+        for level in &self.levels[..cap_level] {
+            let group_size = self.arity.min(level.len());
+            let group_start = index - index % group_size;
+            for (i, node) in level[group_start..group_start + group_size]
+                .iter()
+                .enumerate()
+            {
+                if group_start + i != index {
+                    path.push(node.clone());
+                }
+            }
+            index /= group_size;
         }
 
         Ok(MerkleProof::new::<D>(path))
     }
 
+    /// Generates a proof for each leaf in `indices`. Useful for answering
+    /// queries from a tree that was serialized after it was committed to.
+    pub fn open_many(&self, indices: &[usize]) -> Result<Vec<MerkleProof>, MerkleTreeError> {
+        indices.iter().map(|&index| self.prove(index)).collect()
+    }
+
     pub fn verify(
         root: &Output<D>,
         proof: &[Output<D>],
+        position: usize,
+    ) -> Result<(), MerkleTreeError> {
+        Self::verify_cap(core::slice::from_ref(root), proof, position, 2)
+    }
+
+    /// Verifies a proof generated by a tree with nodes of `arity` children
+    /// committed to by `cap`
+    pub fn verify_cap(
+        cap: &[Output<D>],
+        proof: &[Output<D>],
         mut position: usize,
+        arity: usize,
     ) -> Result<(), MerkleTreeError> {
-        let mut proof_iter = proof.iter();
-        let mut running_hash = proof_iter.next().unwrap().clone();
-        for node in proof_iter {
-            let mut hasher = D::new();
-            if position % 2 == 0 {
-                hasher.update(running_hash);
-                hasher.update(node);
-            } else {
-                hasher.update(node);
-                hasher.update(running_hash);
-            }
-            running_hash = hasher.finalize();
-            position >>= 1;
+        if !is_supported_arity(arity) {
+            return Err(MerkleTreeError::UnsupportedArity { arity });
+        }
+        let (leaf, siblings) = proof.split_first().ok_or(MerkleTreeError::InvalidProof)?;
+        // all groups have `arity` nodes apart from the root's children which
+        // may have fewer
+        let num_full_levels = siblings.len() / (arity - 1);
+        let root_group_size = siblings.len() % (arity - 1) + 1;
+        if !root_group_size.is_power_of_two() {
+            return Err(MerkleTreeError::InvalidProof);
+        }
+
+        let mut running_hash = leaf.clone();
+        let mut siblings = siblings.iter();
+        let group_sizes = core::iter::repeat(arity)
+            .take(num_full_levels)
+            .chain((root_group_size > 1).then_some(root_group_size));
+        for group_size in group_sizes {
+            let index = position % group_size;
+            let mut group = Vec::with_capacity(group_size);
+            group.extend(siblings.by_ref().take(index).cloned());
+            group.push(running_hash);
+            group.extend(siblings.by_ref().take(group_size - 1 - index).cloned());
+            running_hash = hash_children::<D>(&group).ok_or(MerkleTreeError::InvalidProof)?;
+            position /= group_size;
         }
 
-        if *root == running_hash {
+        if cap.get(position) == Some(&running_hash) {
             Ok(())
         } else {
             Err(MerkleTreeError::InvalidProof)
@@ -126,75 +297,505 @@ impl<D: Digest> MerkleTree<D> {
     }
 }
 
-#[cfg(feature = "parallel")]
-fn build_merkle_nodes<D: Digest>(leaf_nodes: &[Output<D>]) -> Vec<Output<D>> {
-    let n = leaf_nodes.len();
-    let num_subtrees = core::cmp::min(rayon::current_num_threads().next_power_of_two(), n / 2);
-    let mut nodes = vec![Output::<D>::default(); n];
-
-    // code adapted from winterfell
-    rayon::scope(|s| {
-        for i in 0..num_subtrees {
-            let nodes = unsafe { &mut *(&mut nodes[..] as *mut [Output<D>]) };
-            s.spawn(move |_| {
-                // generate layer of nodes from leaf nodes
-                let batch_size = n / num_subtrees;
-                let leaf_offset = batch_size * i;
-                for j in (0..batch_size).step_by(2) {
-                    let mut hasher = D::new();
-                    hasher.update(&leaf_nodes[leaf_offset + j]);
-                    hasher.update(&leaf_nodes[leaf_offset + j + 1]);
-                    nodes[(n + leaf_offset + j) / 2] = hasher.finalize();
-                }
+/// Builds a [MerkleTree] from leaf nodes absorbed in order. Nodes are hashed
+/// as soon as all their children have been absorbed so building the tree
+/// overlaps with producing the leaves.
+pub struct MerkleTreeBuilder<D: Digest> {
+    /// Number of nodes in each level starting from the leaves
+    level_sizes: Vec<usize>,
+    levels: Vec<Vec<Output<D>>>,
+    arity: usize,
+}
 
-                // generate remaining nodes
-                let mut batch_size = n / num_subtrees / 4;
-                let mut start_idx = n / 4 + batch_size * i;
-                while start_idx >= num_subtrees {
-                    for k in (start_idx..(start_idx + batch_size)).rev() {
-                        let mut hasher = D::new();
-                        hasher.update(&nodes[k * 2]);
-                        hasher.update(&nodes[k * 2 + 1]);
-                        nodes[k] = hasher.finalize();
-                    }
-                    start_idx /= 2;
-                    batch_size /= 2;
-                }
+impl<D: Digest> MerkleTreeBuilder<D> {
+    pub fn new(num_leaves: usize) -> Result<Self, MerkleTreeError> {
+        if num_leaves < 2 {
+            return Err(MerkleTreeError::TooFewLeaves {
+                expected: 2,
+                actual: num_leaves,
             });
+        } else if !num_leaves.is_power_of_two() {
+            return Err(MerkleTreeError::NumberOfLeavesNotPowerOfTwo { n: num_leaves });
         }
-    });
 
-    // finish the tip of the tree
-    for i in (1..num_subtrees).rev() {
-        let mut hasher = D::new();
-        hasher.update(&nodes[i * 2]);
-        hasher.update(&nodes[i * 2 + 1]);
-        nodes[i] = hasher.finalize();
+        Ok(Self::with_level_sizes(level_sizes(num_leaves, 2), 2))
+    }
+
+    /// Nodes of the tree have `arity` children. Must be called before any
+    /// leaves are absorbed.
+    pub fn with_arity(self, arity: usize) -> Result<Self, MerkleTreeError> {
+        assert!(
+            self.levels[0].is_empty(),
+            "leaves have already been absorbed"
+        );
+        if !is_supported_arity(arity) {
+            return Err(MerkleTreeError::UnsupportedArity { arity });
+        }
+        Ok(Self::with_level_sizes(
+            level_sizes(self.level_sizes[0], arity),
+            arity,
+        ))
+    }
+
+    fn with_level_sizes(level_sizes: Vec<usize>, arity: usize) -> Self {
+        let levels = level_sizes
+            .iter()
+            .map(|&size| Vec::with_capacity(size))
+            .collect();
+        MerkleTreeBuilder {
+            level_sizes,
+            levels,
+            arity,
+        }
     }
 
-    nodes
+    /// Appends `leaf_nodes` to the leaves absorbed so far and hashes any
+    /// nodes whose children are now complete
+    pub fn absorb_leaves(&mut self, leaf_nodes: &[Output<D>]) -> Result<(), MerkleTreeError> {
+        let num_leaves = self.levels[0].len() + leaf_nodes.len();
+        if num_leaves > self.level_sizes[0] {
+            return Err(MerkleTreeError::TooManyLeaves {
+                expected: self.level_sizes[0],
+                actual: num_leaves,
+            });
+        }
+
+        self.levels[0].extend_from_slice(leaf_nodes);
+        for level in 0..self.levels.len() - 1 {
+            let group_size = self.arity.min(self.level_sizes[level]);
+            let (children, parents) = self.levels.split_at_mut(level + 1);
+            let children = &children[level];
+            let parents = &mut parents[0];
+            let num_hashed = parents.len() * group_size;
+            let num_complete = children.len() - children.len() % group_size;
+            if num_hashed == num_complete {
+                break;
+            }
+            for siblings in children[num_hashed..num_complete].chunks(group_size) {
+                parents.push(hash_children::<D>(siblings).ok_or(MerkleTreeError::InvalidDigest)?);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<MerkleTree<D>, MerkleTreeError> {
+        let num_leaves = self.levels[0].len();
+        if num_leaves != self.level_sizes[0] {
+            return Err(MerkleTreeError::TooFewLeaves {
+                expected: self.level_sizes[0],
+                actual: num_leaves,
+            });
+        }
+
+        Ok(MerkleTree {
+            levels: self.levels,
+            arity: self.arity,
+            cap_height: 0,
+        })
+    }
 }
 
-#[cfg(not(feature = "parallel"))]
-fn build_merkle_nodes<D: Digest>(leaf_nodes: &[Output<D>]) -> Vec<Output<D>> {
-    let n = leaf_nodes.len();
-    let mut nodes = vec![Output::<D>::default(); n];
+/// Number of bytes in the salt of a leaf
+pub const SALT_SIZE: usize = 32;
 
-    // generate layer of nodes from leaf nodes
-    for i in 0..n / 2 {
-        let mut hasher = D::new();
-        hasher.update(&leaf_nodes[i * 2]);
-        hasher.update(&leaf_nodes[i * 2 + 1]);
-        nodes[n / 2 + i] = hasher.finalize();
+pub type Salt = [u8; SALT_SIZE];
+
+/// Salts of the leaves of a Merkle tree derived from a secret seed. The salt
+/// of a leaf is read from a ChaCha20 stream at an offset determined by the
+/// leaf's index so salts are never stored. Trees sharing a seed must use
+/// distinct streams.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LeafSalts {
+    seed: [u8; 32],
+    stream: u64,
+}
+
+impl LeafSalts {
+    pub fn new(seed: [u8; 32], stream: u64) -> Self {
+        LeafSalts { seed, stream }
+    }
+
+    pub fn salt(&self, index: usize) -> Salt {
+        let mut rng = ChaCha20Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        // the word position is in units of 32-bit words
+        rng.set_word_pos((index * SALT_SIZE / 4) as u128);
+        let mut salt = [0; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        salt
+    }
+}
+
+/// Hash of a leaf node and its salt
+pub fn salt_leaf<D: Digest>(leaf: &Output<D>, salt: &Salt) -> Output<D> {
+    let mut hasher = D::new();
+    hasher.update(leaf);
+    hasher.update(salt);
+    hasher.finalize()
+}
+
+/// Checks `path` authenticates `leaf` at `index` of a Merkle tree with the
+/// given `root` whose nodes have `arity` children. `path` contains the
+/// siblings of each node on the way from the leaf to the root i.e. a proof
+/// from [MerkleTree::prove] without its first element. Lets individual trace
+/// openings be checked without the verifier.
+pub fn verify_path<D: Digest>(
+    root: &Output<D>,
+    index: usize,
+    leaf: &Output<D>,
+    path: &[Output<D>],
+    arity: usize,
+) -> Result<(), MerkleTreeError> {
+    let mut proof = Vec::with_capacity(path.len() + 1);
+    proof.push(leaf.clone());
+    proof.extend_from_slice(path);
+    MerkleTree::<D>::verify_cap(core::slice::from_ref(root), &proof, index, arity)
+}
+
+/// Serialized as the tree's parameters followed by every node starting from
+/// the leaves so a tree can be persisted after it is committed to and opened
+/// later without rehashing.
+impl<D: Digest> CanonicalSerialize for MerkleTree<D> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.arity.serialize_with_mode(&mut writer, compress)?;
+        self.cap_height.serialize_with_mode(&mut writer, compress)?;
+        self.levels[0]
+            .len()
+            .serialize_with_mode(&mut writer, compress)?;
+        for node in self.levels.iter().flatten() {
+            writer.write_all(node)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        let num_nodes = self.levels.iter().map(Vec::len).sum::<usize>();
+        self.arity.serialized_size(compress)
+            + self.cap_height.serialized_size(compress)
+            + self.levels[0].len().serialized_size(compress)
+            + num_nodes * <D as Digest>::output_size()
+    }
+}
+
+impl<D: Digest> Valid for MerkleTree<D> {
+    /// Checks every node is the hash of its children
+    fn check(&self) -> Result<(), SerializationError> {
+        for (children, parents) in self.levels.iter().zip(&self.levels[1..]) {
+            let group_size = self.arity.min(children.len());
+            for (siblings, parent) in children.chunks(group_size).zip(parents) {
+                if hash_children::<D>(siblings).as_ref() != Some(parent) {
+                    return Err(SerializationError::InvalidData);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D: Digest> CanonicalDeserialize for MerkleTree<D> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let arity = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let cap_height = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let num_leaves = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        if !is_supported_arity(arity) || num_leaves < 2 || !num_leaves.is_power_of_two() {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let level_sizes = level_sizes(num_leaves, arity);
+        if cap_height > level_sizes.len() - 2 {
+            return Err(SerializationError::InvalidData);
+        }
+        // the number of leaves is untrusted so levels grow as nodes are read
+        // rather than being allocated up front
+        let mut levels = Vec::with_capacity(level_sizes.len());
+        for level_size in level_sizes {
+            let mut level = Vec::new();
+            for _ in 0..level_size {
+                let mut node = Output::<D>::default();
+                reader.read_exact(&mut node)?;
+                level.push(node);
+            }
+            levels.push(level);
+        }
+
+        let tree = MerkleTree {
+            levels,
+            arity,
+            cap_height,
+        };
+        if validate == Validate::Yes {
+            tree.check()?;
+        }
+        Ok(tree)
     }
+}
+
+/// Returns true if Merkle tree nodes can have `arity` children
+pub fn is_supported_arity(arity: usize) -> bool {
+    arity.is_power_of_two() && (2..=MAX_ARITY).contains(&arity)
+}
+
+/// Number of nodes in each level of a tree starting from the leaves
+fn level_sizes(num_leaves: usize, arity: usize) -> Vec<usize> {
+    let mut level_sizes = vec![num_leaves];
+    let mut level_size = num_leaves;
+    while level_size > 1 {
+        level_size /= arity.min(level_size);
+        level_sizes.push(level_size);
+    }
+    level_sizes
+}
+
+fn build_merkle_levels<D: Digest>(
+    leaf_nodes: Vec<Output<D>>,
+    arity: usize,
+) -> Result<Vec<Vec<Output<D>>>, MerkleTreeError> {
+    let mut levels = vec![leaf_nodes];
+    while levels.last().unwrap().len() > 1 {
+        let children = levels.last().unwrap();
+        let group_size = arity.min(children.len());
+        let mut parents = vec![Output::<D>::default(); children.len().div_ceil(group_size)];
+
+        // hash each level in a few large chunks rather than one task per node.
+        // levels with less than 128 nodes are hashed on a single thread.
+        #[cfg(not(feature = "parallel"))]
+        let chunk_size = parents.len();
+        #[cfg(feature = "parallel")]
+        let chunk_size = core::cmp::max(
+            parents.len() / rayon::current_num_threads().next_power_of_two(),
+            128,
+        );
+
+        ark_std::cfg_chunks_mut!(parents, chunk_size)
+            .zip(ark_std::cfg_chunks!(children, chunk_size * group_size))
+            .try_for_each(|(parents, children)| {
+                for (parent, siblings) in parents.iter_mut().zip(children.chunks(group_size)) {
+                    *parent = hash_children::<D>(siblings).ok_or(MerkleTreeError::InvalidDigest)?;
+                }
+                Ok(())
+            })?;
+        levels.push(parents);
+    }
+    Ok(levels)
+}
+
+/// Hash of the children of a node. Returns `None` if a child isn't a valid
+/// digest.
+fn hash_children<D: Digest>(children: &[Output<D>]) -> Option<Output<D>> {
+    <D as NodeHasher>::hash_children(children)
+}
 
-    // generate remaining nodes
-    for i in (1..n / 2).rev() {
+trait NodeHasher: Digest {
+    fn hash_children(children: &[Output<Self>]) -> Option<Output<Self>>;
+}
+
+impl<D: Digest> NodeHasher for D {
+    default fn hash_children(children: &[Output<D>]) -> Option<Output<D>> {
         let mut hasher = D::new();
-        hasher.update(&nodes[i * 2]);
-        hasher.update(&nodes[i * 2 + 1]);
-        nodes[i] = hasher.finalize();
+        for child in children {
+            Digest::update(&mut hasher, child);
+        }
+        Some(hasher.finalize())
+    }
+}
+
+/// Children are hashed as field elements rather than packed bytes. Binary
+/// nodes use [HashFn::merge].
+impl<H: HashFn> NodeHasher for AlgebraicDigest<H> {
+    fn hash_children(children: &[Output<Self>]) -> Option<Output<Self>> {
+        let children = children
+            .iter()
+            .map(Self::digest_elements)
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::serialize_elements(match children.as_slice() {
+            [lhs, rhs] => H::merge(lhs, rhs),
+            children => H::hash_elements(children.flatten()),
+        }))
+    }
+}
+
+/// Sponge based hash function over the elements of a prime field. Commitments
+/// made with an algebraic hash are cheap to verify inside another proof system.
+/// Use [AlgebraicDigest] to commit with a [HashFn] anywhere a [Digest] is
+/// expected e.g. `Matrix::commit_to_rows` or `Air::Digest`.
+pub trait HashFn: 'static {
+    type Field: GpuField + PrimeField;
+    /// Number of bytes in a serialized digest
+    type OutputSize: ArrayLength<u8> + 'static;
+
+    fn permutation() -> &'static dyn Permutation<Self::Field>;
+
+    fn hash_elements(elements: &[Self::Field]) -> [Self::Field; DIGEST_SIZE] {
+        let permutation = Self::permutation();
+        let mut state = [Self::Field::zero(); WIDTH];
+        // the input length is placed in the capacity for domain separation
+        state[0] = Self::Field::from(elements.len() as u64);
+        for chunk in elements.chunks(RATE) {
+            for (s, e) in state[CAPACITY..].iter_mut().zip(chunk) {
+                *s += e;
+            }
+            permutation.permute(&mut state);
+        }
+        if elements.is_empty() {
+            permutation.permute(&mut state);
+        }
+        state[CAPACITY..CAPACITY + DIGEST_SIZE].try_into().unwrap()
+    }
+
+    fn merge(
+        lhs: &[Self::Field; DIGEST_SIZE],
+        rhs: &[Self::Field; DIGEST_SIZE],
+    ) -> [Self::Field; DIGEST_SIZE] {
+        let mut elements = [Self::Field::zero(); 2 * DIGEST_SIZE];
+        elements[..DIGEST_SIZE].copy_from_slice(lhs);
+        elements[DIGEST_SIZE..].copy_from_slice(rhs);
+        Self::hash_elements(&elements)
+    }
+}
+
+/// [Digest] implementation for a [HashFn]. Input bytes are packed
+/// little-endian into field elements of `(MODULUS_BIT_SIZE - 1) / 8` bytes
+/// after appending a `0x01` padding byte. The output is the compressed
+/// serialization of the digest elements.
+pub struct AlgebraicDigest<H> {
+    buffer: Vec<u8>,
+    _phantom: PhantomData<fn() -> H>,
+}
+
+impl<H> Clone for AlgebraicDigest<H> {
+    fn clone(&self) -> Self {
+        AlgebraicDigest {
+            buffer: self.buffer.clone(),
+            _phantom: PhantomData,
+        }
     }
+}
+
+impl<H> Default for AlgebraicDigest<H> {
+    fn default() -> Self {
+        AlgebraicDigest {
+            buffer: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<H> HashMarker for AlgebraicDigest<H> {}
+
+impl<H> Update for AlgebraicDigest<H> {
+    fn update(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+}
+
+impl<H: HashFn> OutputSizeUser for AlgebraicDigest<H> {
+    type OutputSize = H::OutputSize;
+}
+
+impl<H: HashFn> AlgebraicDigest<H> {
+    /// Parses the elements of a serialized digest. Returns `None` if an
+    /// element isn't canonical.
+    fn digest_elements(digest: &Output<Self>) -> Option<[H::Field; DIGEST_SIZE]> {
+        let mut reader = digest.as_slice();
+        let mut elements = [H::Field::zero(); DIGEST_SIZE];
+        for element in &mut elements {
+            *element = H::Field::deserialize_compressed(&mut reader).ok()?;
+        }
+        Some(elements)
+    }
+
+    fn serialize_elements(elements: [H::Field; DIGEST_SIZE]) -> Output<Self> {
+        let mut bytes = Vec::with_capacity(H::OutputSize::USIZE);
+        for element in elements {
+            element.serialize_compressed(&mut bytes).unwrap();
+        }
+        Output::<Self>::clone_from_slice(&bytes)
+    }
+}
+
+impl<H: HashFn> FixedOutput for AlgebraicDigest<H> {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        let elements = pack_bytes::<H::Field>(&self.buffer);
+        *out = Self::serialize_elements(H::hash_elements(&elements));
+    }
+}
+
+/// Encodes a row of field elements as the bytes of a Merkle leaf. The leaf is
+/// the hash of the encoded row. Implement this to match the leaf format of
+/// another prover or verifier.
+pub trait LeafEncoder: Send + Sync + 'static {
+    /// Identifies the encoding. Bound into the transcript so a proof can only
+    /// be checked against leaves encoded the same way.
+    const ID: &'static [u8];
+
+    fn encode_row<F: Field>(row: &[F], bytes: &mut Vec<u8>);
 
-    nodes
+    /// Hashes of the rows of `matrix`. Defaults to hashing each row encoded by
+    /// [LeafEncoder::encode_row]. Encoders that can feed elements straight to
+    /// the hasher override this.
+    fn hash_rows<F: Field, D: Digest + 'static>(matrix: &Matrix<F>) -> Vec<Output<D>>
+    where
+        Self: Sized,
+    {
+        matrix.hash_encoded_rows::<D, Self>()
+    }
+}
+
+/// Arkworks compressed serialization of the row. This includes a `u64` length
+/// prefix.
+pub struct CompressedLeafEncoder;
+
+impl LeafEncoder for CompressedLeafEncoder {
+    const ID: &'static [u8] = b"ark-compressed";
+
+    fn encode_row<F: Field>(row: &[F], bytes: &mut Vec<u8>) {
+        row.serialize_compressed(bytes).unwrap();
+    }
+}
+
+/// Little-endian 64-bit limbs of the canonical integer representation of each
+/// base field coefficient. No length prefix.
+pub struct LittleEndianLeafEncoder;
+
+impl LeafEncoder for LittleEndianLeafEncoder {
+    const ID: &'static [u8] = b"canonical-le-limbs";
+
+    fn encode_row<F: Field>(row: &[F], bytes: &mut Vec<u8>) {
+        for coeff in row.iter().flat_map(F::to_base_prime_field_elements) {
+            for limb in coeff.into_bigint().as_ref() {
+                bytes.extend(limb.to_le_bytes());
+            }
+        }
+    }
+
+    fn hash_rows<F: Field, D: Digest + 'static>(matrix: &Matrix<F>) -> Vec<Output<D>> {
+        matrix.hash_rows_native::<D>()
+    }
+}
+
+/// Little-endian 64-bit limbs of the Montgomery form `x·R mod p` of each base
+/// field coefficient where `R = 2^(64·limbs)`. Matches provers that hash their
+/// internal representation directly. No length prefix.
+pub struct MontgomeryLeafEncoder;
+
+impl LeafEncoder for MontgomeryLeafEncoder {
+    const ID: &'static [u8] = b"montgomery-le-limbs";
+
+    fn encode_row<F: Field>(row: &[F], bytes: &mut Vec<u8>) {
+        let num_limbs = F::BasePrimeField::MODULUS.as_ref().len() as u64;
+        let r = F::BasePrimeField::from(2u64).pow([64 * num_limbs]);
+        for coeff in row.iter().flat_map(F::to_base_prime_field_elements) {
+            for limb in (coeff * r).into_bigint().as_ref() {
+                bytes.extend(limb.to_le_bytes());
+            }
+        }
+    }
 }
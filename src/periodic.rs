@@ -0,0 +1,73 @@
+use crate::utils::horner_evaluate;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use core::ops::Add;
+use gpu_poly::prelude::*;
+
+/// Column of constants that repeats every `period` rows e.g. the round
+/// constants of a hash function. Only a single period is stored.
+///
+/// Over a trace of length `n` the column is `p(x^(n/period))` where `p`
+/// interpolates the values of one period over the subgroup of size `period`.
+#[derive(Clone, Debug)]
+pub struct PeriodicColumn<F: FftField> {
+    values: Vec<F>,
+    coeffs: Vec<F>,
+}
+
+impl<F: FftField> PeriodicColumn<F> {
+    pub fn new(values: Vec<F>) -> Self {
+        let period = values.len();
+        assert!(period.is_power_of_two(), "period must be a power of two");
+        let domain = Radix2EvaluationDomain::<F>::new(period).unwrap();
+        let coeffs = domain.ifft(&values);
+        PeriodicColumn { values, coeffs }
+    }
+
+    pub fn period(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Values of the column over a single period
+    pub fn values(&self) -> &[F] {
+        &self.values
+    }
+
+    /// Value of the column at row `i` of the trace
+    pub fn get(&self, i: usize) -> F {
+        self.values[i % self.period()]
+    }
+
+    /// Evaluates the column at `x` for a trace of length `trace_len`
+    pub fn evaluate_at<T: Field>(&self, x: T, trace_len: usize) -> T
+    where
+        T: for<'a> Add<&'a F, Output = T>,
+    {
+        assert!(trace_len >= self.period());
+        let x = x.pow([(trace_len / self.period()) as u64]);
+        horner_evaluate(&self.coeffs, &x)
+    }
+
+    /// Low degree extension of the column over `domain` for a trace of length
+    /// `trace_len`. Only a coset the size of a single period of the extension
+    /// is evaluated which is then repeated over the entire domain.
+    pub fn evaluate(&self, trace_len: usize, domain: Radix2EvaluationDomain<F>) -> GpuVec<F> {
+        let n = domain.size();
+        assert!(trace_len >= self.period() && n >= trace_len);
+        // `x^(trace_len/period)` maps the domain onto a coset of size
+        // `n/(trace_len/period)`
+        let exponent = (trace_len / self.period()) as u64;
+        let coset_size = n / exponent as usize;
+        let coset_offset = domain.coset_offset().pow([exponent]);
+        let coset = Radix2EvaluationDomain::new_coset(coset_size, coset_offset).unwrap();
+        let coset_evals = coset.fft(&self.coeffs);
+        let mut evals = Vec::with_capacity_in(n, PageAlignedAllocator);
+        for _ in 0..n / coset_size {
+            evals.extend_from_slice(&coset_evals);
+        }
+        evals
+    }
+}
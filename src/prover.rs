@@ -1,28 +1,307 @@
+use crate::challenges::Challenges;
 use crate::channel::ProverChannel;
+use crate::checkpoint::trace_digest;
+use crate::checkpoint::CommittedTrace;
+use crate::checkpoint::ConstraintsCommitted;
+use crate::checkpoint::FriDone;
+use crate::checkpoint::ProverCheckpoint;
+use crate::checkpoint::TraceCommitted;
+use crate::commitment::VectorCommitment;
 use crate::composer::ConstraintComposer;
 use crate::composer::DeepPolyComposer;
-use crate::fri::FriProver;
+use crate::fri;
+use crate::fri::FriLayerCheckpoint;
+use crate::fri::LdtProver;
+use crate::fri::LdtVerifier;
+use crate::merkle::salt_leaf;
+use crate::merkle::LeafSalts;
+use crate::random::hash_id;
+#[cfg(feature = "std")]
+use crate::report::PhaseTiming;
+#[cfg(feature = "std")]
+use crate::report::ProverReport;
+use crate::trace::interpolate_base_segments;
+use crate::trace::randomize_rows;
+use crate::trace::Padding;
+use crate::trace::PreprocessedTrace;
 use crate::trace::Queries;
+#[cfg(feature = "std")]
+use crate::utils;
 use crate::Air;
+use crate::Error;
+use crate::Matrix;
 use crate::Proof;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
 use crate::Trace;
+use crate::TraceInfo;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+#[cfg(feature = "gpu")]
+use ark_poly::Radix2EvaluationDomain;
+#[cfg(feature = "std")]
+use core::future::Future;
+#[cfg(feature = "std")]
+use core::pin::Pin;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+#[cfg(feature = "std")]
+use core::task::Context;
+#[cfg(feature = "std")]
+use core::task::Poll;
+#[cfg(feature = "std")]
+use core::task::Waker;
+use digest::Digest;
+use digest::Output;
+use gpu_poly::allocator::BufferPool;
 use gpu_poly::GpuFftField;
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use sha2::Sha256;
+use snafu::Snafu;
+#[cfg(feature = "std")]
+use std::panic::AssertUnwindSafe;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
 
 /// Errors that can occur during the proving stage
-#[derive(Debug)]
+#[derive(Debug, Snafu)]
 pub enum ProvingError {
+    #[snafu(display("proving failed"))]
     Fail,
-    // TODO
+    /// The highest degree constraint doesn't fit in the LDE domain
+    #[snafu(display(
+        "constraints have degree {constraint_degree} which needs an lde blowup factor of at least {required} but the proof options use {actual}"
+    ))]
+    BlowupFactorTooSmall {
+        constraint_degree: usize,
+        required: usize,
+        actual: usize,
+    },
+    #[snafu(display("proving was cancelled before {phase:?}"))]
+    Cancelled { phase: ProvingPhase },
+    /// The trace's extension segments differ from those the AIR declares
+    #[snafu(display(
+        "trace has extension segments of widths {actual:?} but the air declares {expected:?}"
+    ))]
+    ExtensionSegmentWidthsMismatch {
+        expected: Vec<usize>,
+        actual: Vec<usize>,
+    },
+    #[snafu(display("trace didn't build extension segment {segment}"))]
+    ExtensionSegmentNotBuilt { segment: usize },
+    #[snafu(display(
+        "trace built extension segment {segment} with {actual} columns but the air declares {expected}"
+    ))]
+    ExtensionSegmentWidthMismatch {
+        segment: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// The base trace has a different number of columns than
+    /// [Trace::NUM_BASE_COLUMNS]
+    #[snafu(display("trace has {actual} base columns but declares {expected}"))]
+    BaseColumnsMismatch { expected: usize, actual: usize },
+    /// The preprocessed trace was committed over a different LDE domain
+    #[snafu(display(
+        "preprocessed trace has an lde of {actual} rows but the proof's lde domain has {expected}"
+    ))]
+    PreprocessedLdeSizeMismatch { expected: usize, actual: usize },
+    /// The preprocessed trace has a different number of columns than the AIR
+    /// declares
+    #[snafu(display("preprocessed trace has {actual} columns but the air declares {expected}"))]
+    PreprocessedColumnsMismatch { expected: usize, actual: usize },
+    #[snafu(display("trace has {actual} randomizer rows but the air declares {expected}"))]
+    RandomizerRowsMismatch { expected: usize, actual: usize },
+    #[snafu(display(
+        "zk proofs need at least {required} randomizer rows but the air declares {actual}"
+    ))]
+    TooFewRandomizerRows { required: usize, actual: usize },
+    #[snafu(display(
+        "trace declares {actual:?} padding but the air declares {expected:?} padding"
+    ))]
+    PaddingMismatch {
+        expected: (Padding, usize),
+        actual: (Padding, usize),
+    },
+    /// [Prover::device] isn't the index of a GPU
+    #[snafu(display("gpu device {index} doesn't exist, there are {num_devices} devices"))]
+    InvalidDevice { index: usize, num_devices: usize },
 }
 
+/// Phases of proof generation in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvingPhase {
+    /// Low degree extension of and commitment to the base trace
+    BaseTrace,
+    /// Building, extending and committing to each extension segment
+    ExtensionTrace,
+    /// Evaluating constraints over the constraint evaluation domain and
+    /// committing to the composition trace
+    ConstraintEvaluation,
+    /// Out-of-domain evaluations and the DEEP composition polynomial
+    DeepComposition,
+    /// Committing to the FRI layers
+    Fri,
+    /// Proof of work on the FRI commitments
+    Grinding,
+    /// Opening the trace and FRI layers at the query positions
+    Queries,
+}
+
+/// Progress of a proof reported to [Prover::on_event]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverEvent {
+    PhaseStarted(ProvingPhase),
+    /// Percentage of a phase that is done e.g. after each extension segment
+    /// or FRI layer
+    Progress {
+        phase: ProvingPhase,
+        percent: u8,
+    },
+    PhaseFinished(ProvingPhase),
+}
+
+/// Cancels proof generation from another thread. Proving checks the token
+/// before each [ProvingPhase] (see [Prover::cancellation_token]).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Estimated peak memory use of a [ProvingPhase] (see
+/// [Prover::estimate_memory])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    pub phase: ProvingPhase,
+    /// Bytes of host memory in use
+    pub host_bytes: usize,
+    /// Bytes of the host memory that are GPU buffers. GPU buffers are shared
+    /// with the host so these are included in `host_bytes`.
+    pub device_bytes: usize,
+}
+
+/// A [ProvingPhase] in progress. With the `tracing` feature each phase is a
+/// span so subscribers can collect phase durations.
+struct PhaseSpan {
+    phase: ProvingPhase,
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl PhaseSpan {
+    fn enter(phase: ProvingPhase) -> Self {
+        PhaseSpan {
+            phase,
+            #[cfg(feature = "std")]
+            started: std::time::Instant::now(),
+            #[cfg(feature = "tracing")]
+            _span: tracing::info_span!("phase", ?phase).entered(),
+        }
+    }
+}
+
+/// Receives the events, phase timings and checkpoints of one proof besides
+/// [Prover::on_event]. Passed down explicitly rather than kept in a thread
+/// local since the phases of a proof can run on any thread of a pool.
+#[derive(Clone, Copy, Default)]
+struct Observer<'a> {
+    /// Latest event for a [ProofHandle]
+    #[cfg(feature = "std")]
+    progress: Option<&'a Mutex<Option<ProverEvent>>>,
+    /// Phase timings for [Prover::prove_with_report]
+    #[cfg(feature = "std")]
+    timings: Option<&'a Mutex<Vec<PhaseTiming>>>,
+    /// Digest of the trace (see [trace_digest]) if checkpoints are handed to
+    /// [Prover::save_checkpoint]
+    trace_digest: Option<&'a [u8]>,
+}
+
+/// Passes `event` to [Prover::on_event] and to `observer`
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+fn report<P: Prover + ?Sized>(prover: &P, observer: &Observer, event: ProverEvent) {
+    prover.on_event(event);
+    #[cfg(feature = "std")]
+    if let Some(progress) = observer.progress {
+        *progress.lock().unwrap() = Some(event);
+    }
+}
+
+#[cfg(feature = "std")]
+struct ProofTask<A: Air> {
+    result: Option<Result<Proof<A>, Error>>,
+    waker: Option<Waker>,
+}
+
+/// A proof being generated on a dedicated thread (see
+/// [Prover::generate_proof_async]). Resolves to the proof once it is
+/// generated.
+#[cfg(feature = "std")]
+pub struct ProofHandle<A: Air> {
+    task: Arc<Mutex<ProofTask<A>>>,
+    progress: Arc<Mutex<Option<ProverEvent>>>,
+}
+
+#[cfg(feature = "std")]
+impl<A: Air> ProofHandle<A> {
+    /// Latest event reported by the prover. `None` until proving starts.
+    pub fn progress(&self) -> Option<ProverEvent> {
+        *self.progress.lock().unwrap()
+    }
+
+    /// Returns true once the proof has been generated (or failed)
+    pub fn is_finished(&self) -> bool {
+        self.task.lock().unwrap().result.is_some()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Air> Future for ProofHandle<A> {
+    type Output = Result<Proof<A>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut task = self.task.lock().unwrap();
+        match task.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                task.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Number of proofs [Prover::generate_proofs] works on concurrently. Bounds
+/// memory use while keeping the GPU busy.
+pub const BATCH_PIPELINE_DEPTH: usize = 4;
+
 pub trait Prover {
     type Fp: GpuFftField + PrimeField;
     type Fq: StarkExtensionOf<Self::Fp>;
-    type Air: Air<Fp = Self::Fp, Fq = Self::Fq>;
+    /// Hash function used for Merkle commitments and the Fiat-Shamir channel
+    type Digest: Digest + 'static = Sha256;
+    type Air: Air<Fp = Self::Fp, Fq = Self::Fq, Digest = Self::Digest>;
     type Trace: Trace<Fp = Self::Fp, Fq = Self::Fq>;
 
     fn new(options: ProofOptions) -> Self;
@@ -31,88 +310,1348 @@ pub trait Prover {
 
     fn options(&self) -> ProofOptions;
 
-    async fn generate_proof(&self, trace: Self::Trace) -> Result<Proof<Self::Air>, ProvingError> {
-        let options = self.options();
-        let trace_info = trace.info();
-        let pub_inputs = self.get_pub_inputs(&trace);
-        let air = Self::Air::new(trace_info, pub_inputs, options);
-        air.validate();
-        let mut channel = ProverChannel::<Self::Air, Sha256>::new(&air);
-
-        let trace_xs = air.trace_domain();
-        let lde_xs = air.lde_domain();
-        let base_trace = trace.base_columns();
-        let base_trace_polys = base_trace.interpolate(trace_xs);
-        assert_eq!(Self::Trace::NUM_BASE_COLUMNS, base_trace_polys.num_cols());
-        let base_trace_lde = base_trace_polys.evaluate(lde_xs);
-        let base_trace_lde_tree = base_trace_lde.commit_to_rows();
-        channel.commit_base_trace(base_trace_lde_tree.root());
-        let challenges = air.get_challenges(&mut channel.public_coin);
-        let hints = air.get_hints(&challenges);
-
-        let extension_trace = trace.build_extension_columns(&challenges);
-        let num_extension_columns = extension_trace.as_ref().map_or(0, |t| t.num_cols());
-        assert_eq!(Self::Trace::NUM_EXTENSION_COLUMNS, num_extension_columns);
-        let extension_trace_polys = extension_trace.as_ref().map(|t| t.interpolate(trace_xs));
-        let extension_trace_lde = extension_trace_polys.as_ref().map(|p| p.evaluate(lde_xs));
-        let extension_trace_tree = extension_trace_lde.as_ref().map(|lde| lde.commit_to_rows());
-        if let Some(t) = extension_trace_tree.as_ref() {
-            channel.commit_extension_trace(t.root())
-        }
-
-        #[cfg(all(feature = "std", debug_assertions))]
-        air.validate_constraints(&challenges, &hints, base_trace, extension_trace.as_ref());
-        drop((base_trace, extension_trace));
-
-        let composition_coeffs = air.get_constraint_composition_coeffs(&mut channel.public_coin);
-        let constraint_coposer = ConstraintComposer::new(&air, composition_coeffs);
-        // TODO: move commitment here
-        // NOTE: consuming LDEs here requires more compute later but saves on memory
-        let (composition_trace_lde, composition_trace_polys, composition_trace_lde_tree) =
-            constraint_coposer.build_commitment(
-                &challenges,
-                &hints,
-                base_trace_lde,
-                extension_trace_lde,
-            );
-        channel.commit_composition_trace(composition_trace_lde_tree.root());
-
-        let mut deep_poly_composer = DeepPolyComposer::new(
-            &air,
-            channel.get_ood_point(),
-            &base_trace_polys,
-            extension_trace_polys.as_ref(),
-            composition_trace_polys,
-        );
-        let (execution_trace_oods, composition_trace_oods) = deep_poly_composer.get_ood_evals();
-        channel.send_execution_trace_ood_evals(execution_trace_oods);
-        channel.send_composition_trace_ood_evals(composition_trace_oods);
-        let deep_coeffs = air.get_deep_composition_coeffs(&mut channel.public_coin);
-        let deep_composition_poly = deep_poly_composer.into_deep_poly(deep_coeffs);
-        let deep_composition_lde = deep_composition_poly.into_evaluations(lde_xs);
-
-        let mut fri_prover = FriProver::<Self::Fq, Sha256>::new(air.options().into_fri_options());
-        #[cfg(feature = "std")]
-        let now = std::time::Instant::now();
-        fri_prover.build_layers(&mut channel, deep_composition_lde.try_into().unwrap());
-        #[cfg(feature = "std")]
-        println!("yo {:?}", now.elapsed());
-
-        channel.grind_fri_commitments();
-
-        let query_positions = channel.get_fri_query_positions();
-        let fri_proof = fri_prover.into_proof(&query_positions);
-
-        let queries = Queries::new(
-            &air,
-            &base_trace_polys,
-            extension_trace_polys.as_ref(),
-            &composition_trace_lde,
-            base_trace_lde_tree,
-            extension_trace_tree,
-            composition_trace_lde_tree,
-            &query_positions,
+    /// Thread pool used for proving. Defaults to rayon's global thread pool.
+    /// Override to run the prover on a caller owned pool so it can coexist
+    /// with other workloads.
+    #[cfg(feature = "parallel")]
+    fn thread_pool(&self) -> Option<&rayon::ThreadPool> {
+        None
+    }
+
+    /// Index of the GPU (as indexed by [gpu_poly::device::DeviceInfo::all])
+    /// proofs are generated on. Defaults to the system default device.
+    /// Override so provers on multi-GPU or eGPU machines each use their own
+    /// device.
+    #[cfg(feature = "gpu")]
+    fn device(&self) -> Option<usize> {
+        None
+    }
+
+    /// If true the execution trace LDE is committed to one coset at a time
+    /// rather than being materialized in full. Bounds peak memory to the size
+    /// of the constraint evaluation domain at the cost of extra FFTs. The
+    /// resulting proof is identical.
+    fn streaming_lde(&self) -> bool {
+        false
+    }
+
+    /// Columns fixed by the AIR that are committed to once and shared by
+    /// every proof. Verifiers must be given its commitment (see
+    /// [PreprocessedTrace::commitment]).
+    fn preprocessed_trace(&self) -> Option<&PreprocessedTrace<Self::Air>> {
+        None
+    }
+
+    /// Secret seed all prover randomness (leaf salts, randomizer rows and the
+    /// composition mask) is derived from. Only drawn if [ProofOptions::zk] is
+    /// set. Must be unpredictable to the verifier. Defaults to the operating
+    /// system's random number generator. See [Prover::prove_with_rng] for
+    /// reproducible proofs.
+    fn salt_seed(&self) -> [u8; 32] {
+        let mut seed = [0; 32];
+        getrandom::getrandom(&mut seed).expect("no source of randomness for the salt");
+        seed
+    }
+
+    /// Bytes identifying the deployment proofs are made for e.g. a chain ID,
+    /// program hash or protocol version. Mixed into the Fiat-Shamir seed so a
+    /// proof only verifies with the same context (see
+    /// [Proof::verify_with_context]). Defaults to no context.
+    fn context(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Called as proving moves through each [ProvingPhase] so services can
+    /// report the progress of long proofs
+    fn on_event(&self, _event: ProverEvent) {}
+
+    /// Token checked before each [ProvingPhase] starts. Proving stops with
+    /// [ProvingError::Cancelled] once the token is cancelled.
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        None
+    }
+
+    /// If true checkpoints are handed to [Prover::save_checkpoint] and loaded
+    /// from [Prover::load_checkpoint]. Off by default since checkpoints copy
+    /// the committed leaves and hash the trace to identify it.
+    fn checkpointing(&self) -> bool {
+        false
+    }
+
+    /// Called once the base trace, the composition trace and each FRI layer
+    /// have been committed to if [Prover::checkpointing] is set. Long proofs
+    /// can persist the checkpoint (e.g. with [ProverCheckpoint::save]) and
+    /// resume after a restart from [Prover::load_checkpoint].
+    fn save_checkpoint(&self, _checkpoint: &ProverCheckpoint<Self::Air>) {}
+
+    /// Checkpoint to resume proving from if [Prover::checkpointing] is set.
+    /// Checkpoints taken for a different trace, different options or
+    /// different public inputs are ignored.
+    fn load_checkpoint(&self) -> Option<ProverCheckpoint<Self::Air>> {
+        None
+    }
+
+    /// Compiles the GPU kernels and computes the FFT twiddles used to prove
+    /// traces of length `trace_len` ahead of time so the first proof doesn't
+    /// pay for them. Twiddles are shared by every later FFT plan.
+    /// If `kernel_cache` is provided compiled kernels are loaded from, and
+    /// persisted to, this file so later processes start warm.
+    #[cfg(feature = "gpu")]
+    fn warm_up(&self, trace_len: usize, kernel_cache: Option<&str>) -> Result<(), Error> {
+        use gpu_poly::prelude::*;
+        on_device(self, || {
+            if let Some(path) = kernel_cache {
+                KERNEL_CACHE
+                    .load(planner().command_queue.device(), path)
+                    .map_err(|message| Error::Gpu { message })?;
+            }
+            let lde_len = trace_len * self.options().lde_blowup_factor as usize;
+            let offset = Self::Fp::GENERATOR;
+            let mut n = trace_len.max(GpuFft::<Self::Fp>::MIN_SIZE);
+            while n <= lde_len {
+                let domain = Radix2EvaluationDomain::new(n).unwrap();
+                let coset = Radix2EvaluationDomain::new_coset(n, offset).unwrap();
+                drop(GpuIfft::<Self::Fp>::from(domain));
+                drop(GpuIfft::<Self::Fq>::from(domain));
+                drop(GpuFft::<Self::Fp>::from(coset));
+                drop(GpuFft::<Self::Fq>::from(coset));
+                n *= 2;
+            }
+            match kernel_cache {
+                Some(_) => KERNEL_CACHE
+                    .persist()
+                    .map_err(|message| Error::Gpu { message }),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Estimated peak memory use of each [ProvingPhase] when proving a trace
+    /// described by `trace_info`. Lets schedulers pack proving jobs onto
+    /// machines without proving. Assumes the composition polynomial is split
+    /// into the maximum of `lde_blowup_factor` columns so estimates are upper
+    /// bounds. Includes the copies checkpoints take if [Prover::checkpointing]
+    /// is set but not what [Prover::save_checkpoint] does with them.
+    fn estimate_memory(&self, trace_info: &TraceInfo, options: &ProofOptions) -> Vec<MemoryEstimate>
+    where
+        Self: Sized,
+    {
+        estimate_memory::<Self::Fp, Self::Fq, Self::Digest>(
+            trace_info,
+            options,
+            self.checkpointing(),
+        )
+    }
+
+    /// Generates a proof. Returns an [Error] rather than panicking if the
+    /// options or the dimensions of the trace are invalid.
+    async fn generate_proof(&self, trace: Self::Trace) -> Result<Proof<Self::Air>, Error>
+    where
+        Self: Sync,
+        Self::Trace: Send,
+        Proof<Self::Air>: Send,
+    {
+        check_inputs(self, &trace)?;
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = self.thread_pool() {
+            return Ok(pool.install(|| self.prove(trace))?);
+        }
+
+        Ok(self.prove(trace)?)
+    }
+
+    /// Generates a proof for each trace. Inputs are checked and GPU kernels
+    /// and FFT twiddles are set up for every trace length before proving
+    /// starts so proofs share them. Proofs are pipelined: up to
+    /// [BATCH_PIPELINE_DEPTH] proofs are in flight at a time and a new proof
+    /// starts as soon as one finishes, so proofs drift into different phases
+    /// and one proof's commitments are hashed while another uses the GPU.
+    /// Proofs are returned in the order of `traces`.
+    #[cfg(feature = "std")]
+    async fn generate_proofs(
+        &self,
+        traces: Vec<Self::Trace>,
+    ) -> Result<Vec<Proof<Self::Air>>, Error>
+    where
+        Self: Sync,
+        Self::Trace: Send,
+        Proof<Self::Air>: Send,
+    {
+        for trace in &traces {
+            check_inputs(self, trace)?;
+        }
+        #[cfg(feature = "gpu")]
+        {
+            let mut trace_lens = traces.iter().map(Trace::len).collect::<Vec<usize>>();
+            trace_lens.sort_unstable();
+            trace_lens.dedup();
+            for trace_len in trace_lens {
+                self.warm_up(trace_len, None)?;
+            }
+        }
+        let num_traces = traces.len();
+        let queue = Mutex::new(traces.into_iter().enumerate());
+        let results = Mutex::new(Vec::with_capacity(num_traces));
+        let prove_queued = || loop {
+            let Some((i, trace)) = queue.lock().unwrap().next() else {
+                return;
+            };
+            #[cfg(feature = "parallel")]
+            let result = match self.thread_pool() {
+                Some(pool) => pool.install(|| self.prove(trace)),
+                None => self.prove(trace),
+            };
+            #[cfg(not(feature = "parallel"))]
+            let result = self.prove(trace);
+            results.lock().unwrap().push((i, result));
+        };
+        std::thread::scope(|s| {
+            for _ in 0..BATCH_PIPELINE_DEPTH.min(num_traces) {
+                s.spawn(prove_queued);
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_unstable_by_key(|(i, _)| *i);
+        let proofs = results.into_iter().map(|(_, result)| result);
+        Ok(proofs.collect::<Result<Vec<_>, ProvingError>>()?)
+    }
+
+    /// Same as [Prover::generate_proof] with all prover randomness drawn from
+    /// `rng` (see [Prover::prove_with_rng])
+    async fn generate_proof_with_rng<R: Rng + Send>(
+        &self,
+        trace: Self::Trace,
+        rng: &mut R,
+    ) -> Result<Proof<Self::Air>, Error>
+    where
+        Self: Sync,
+        Self::Trace: Send,
+        Proof<Self::Air>: Send,
+    {
+        check_inputs(self, &trace)?;
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = self.thread_pool() {
+            return Ok(pool.install(|| self.prove_with_rng(trace, rng))?);
+        }
+
+        Ok(self.prove_with_rng(trace, rng)?)
+    }
+
+    /// Generates a proof on a dedicated thread (or on [Prover::thread_pool])
+    /// so async executors aren't blocked for the duration of proving. The
+    /// returned handle resolves to the proof and reports the latest
+    /// [ProverEvent]. Proving can be stopped with
+    /// [Prover::cancellation_token].
+    #[cfg(feature = "std")]
+    fn generate_proof_async(self: Arc<Self>, trace: Self::Trace) -> ProofHandle<Self::Air>
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Trace: Send + 'static,
+        Proof<Self::Air>: Send,
+    {
+        let task = Arc::new(Mutex::new(ProofTask {
+            result: None,
+            waker: None,
+        }));
+        let progress = Arc::new(Mutex::new(None));
+        let handle = ProofHandle {
+            task: Arc::clone(&task),
+            progress: Arc::clone(&progress),
+        };
+        std::thread::spawn(move || {
+            let prove = || -> Result<Proof<Self::Air>, Error> {
+                check_inputs(&*self, &trace)?;
+                let observer = Observer {
+                    progress: Some(&progress),
+                    ..Default::default()
+                };
+                let mut rng = default_rng(&*self);
+                let prove = || prove_observed(&*self, trace, &mut rng, &observer);
+                #[cfg(feature = "parallel")]
+                if let Some(pool) = self.thread_pool() {
+                    return Ok(pool.install(prove)?);
+                }
+                Ok(prove()?)
+            };
+            // the handle must resolve even if proving panics or it never would
+            let result = std::panic::catch_unwind(AssertUnwindSafe(prove));
+            let result = result.unwrap_or_else(|panic| {
+                Err(Error::Panicked {
+                    message: panic_message(&*panic),
+                })
+            });
+            let mut task = task.lock().unwrap();
+            task.result = Some(result);
+            if let Some(waker) = task.waker.take() {
+                waker.wake();
+            }
+        });
+        handle
+    }
+
+    /// Generates a proof on the current thread (or thread pool)
+    fn prove(&self, trace: Self::Trace) -> Result<Proof<Self::Air>, ProvingError> {
+        self.prove_with_rng(trace, &mut default_rng(self))
+    }
+
+    /// Same as [Prover::prove] but also returns telemetry of the proof (phase
+    /// timings, estimated bytes hashed, peak memory and GPU time of the
+    /// process and proof size) so performance regressions can be tracked
+    /// across releases. Generate one proof at a time for accurate memory and
+    /// GPU time.
+    #[cfg(feature = "std")]
+    fn prove_with_report(
+        &self,
+        trace: Self::Trace,
+    ) -> Result<(Proof<Self::Air>, ProverReport), ProvingError> {
+        let gpu_time = utils::gpu_time();
+        let timings = Mutex::new(Vec::new());
+        let observer = Observer {
+            timings: Some(&timings),
+            ..Default::default()
+        };
+        let proof = prove_observed(self, trace, &mut default_rng(self), &observer)?;
+        let gpu_time = utils::gpu_time() - gpu_time;
+        let report = ProverReport::new(&proof, timings.into_inner().unwrap(), gpu_time);
+        Ok((proof, report))
+    }
+
+    /// Generates a proof with all prover randomness drawn from `rng` instead
+    /// of [Prover::salt_seed]. Proofs are deterministic if `rng` is e.g. a
+    /// seeded [ChaCha20Rng] which makes them reproducible in CI and when
+    /// debugging. Proofs are only zero-knowledge if `rng` is unpredictable
+    /// to the verifier.
+    fn prove_with_rng<R: Rng + ?Sized>(
+        &self,
+        trace: Self::Trace,
+        rng: &mut R,
+    ) -> Result<Proof<Self::Air>, ProvingError> {
+        prove_observed(self, trace, rng, &Observer::default())
+    }
+
+    /// Runs the first stage of a proof: commits to the base trace and each
+    /// extension trace segment. The stages of a proof can run on different
+    /// machines by serializing the state between them. The remaining stages
+    /// are [Prover::commit_constraints], [Prover::commit_fri] and
+    /// [Prover::open_queries]. Running every stage on one machine recomputes
+    /// trace LDEs that [Prover::prove] keeps in memory. Prover randomness is
+    /// drawn from `rng` so the stages produce the same proof as
+    /// [Prover::prove_with_rng] given an rng in the same state.
+    fn commit_trace<R: Rng + ?Sized>(
+        &self,
+        trace: Self::Trace,
+        rng: &mut R,
+    ) -> Result<TraceCommitted<Self::Air>, ProvingError> {
+        let air = build_air(self, &trace)?;
+        let salt_seed = air.options().zk.then(|| rng.gen::<[u8; 32]>());
+        let observer = Observer::default();
+        on_device(self, || {
+            Ok(commit_trace(self, &air, &trace, salt_seed, None, true, &observer)?.0)
+        })
+    }
+
+    /// Evaluates the constraints over the committed trace and commits to the
+    /// composition trace (see [Prover::commit_trace])
+    fn commit_constraints(
+        &self,
+        state: TraceCommitted<Self::Air>,
+    ) -> Result<ConstraintsCommitted<Self::Air>, ProvingError> {
+        let air = state.air();
+        let cache = StageCache::default();
+        let observer = Observer::default();
+        on_device(self, || {
+            Ok(commit_constraints(self, &air, state, cache, None, true, &observer)?.0)
+        })
+    }
+
+    /// Builds and commits to the FRI layers, grinds and opens the FRI layers
+    /// at the query positions (see [Prover::commit_trace])
+    fn commit_fri(
+        &self,
+        state: ConstraintsCommitted<Self::Air>,
+    ) -> Result<FriDone<Self::Air>, ProvingError> {
+        let air = state.trace.air();
+        let observer = Observer::default();
+        let cache = StageCache::default();
+        let (state, cache) = on_device(self, || {
+            commit_fri(self, &air, state, cache, Vec::new(), &observer)
+        })?;
+        if let Some(span) = cache.queries_span {
+            finish_phase(self, &observer, span);
+        }
+        Ok(state)
+    }
+
+    /// Opens the trace commitments at the query positions which completes the
+    /// proof (see [Prover::commit_trace])
+    fn open_queries(&self, state: FriDone<Self::Air>) -> Result<Proof<Self::Air>, ProvingError> {
+        let air = state.constraints.trace.air();
+        on_device(self, || {
+            open_queries(
+                self,
+                &air,
+                state,
+                StageCache::default(),
+                &Observer::default(),
+            )
+        })
+    }
+}
+
+/// Data kept in memory between the stages of [Prover::prove]. Stages run from
+/// deserialized state recompute it.
+struct StageCache<A: Air> {
+    base_trace_lde: Option<Matrix<A::Fp>>,
+    extension_trace_lde: Option<Matrix<A::Fq>>,
+    composition_trace_lde: Option<Matrix<A::Fq>>,
+    base_trace_commitment: Option<A::VectorCommitment>,
+    extension_commitments: Option<Vec<A::VectorCommitment>>,
+    composition_trace_commitment: Option<A::VectorCommitment>,
+    /// The queries phase starts with the FRI openings and ends with the
+    /// trace openings
+    queries_span: Option<PhaseSpan>,
+}
+
+impl<A: Air> Default for StageCache<A> {
+    fn default() -> Self {
+        StageCache {
+            base_trace_lde: None,
+            extension_trace_lde: None,
+            composition_trace_lde: None,
+            base_trace_commitment: None,
+            extension_commitments: None,
+            composition_trace_commitment: None,
+            queries_span: None,
+        }
+    }
+}
+
+/// Builds the AIR `trace` is proven under
+fn build_air<P: Prover + ?Sized>(prover: &P, trace: &P::Trace) -> Result<P::Air, ProvingError> {
+    let preprocessed_trace = prover.preprocessed_trace();
+    let num_preprocessed_columns = preprocessed_trace.map_or(0, PreprocessedTrace::num_cols);
+    let trace_info = trace
+        .info()
+        .with_preprocessed_columns(num_preprocessed_columns)
+        .with_air_version(<P::Air as Air>::VERSION);
+    let pub_inputs = prover.get_pub_inputs(trace);
+    let air = P::Air::new(trace_info, pub_inputs, prover.options());
+    let expected = air.extension_segment_widths();
+    let actual = &air.trace_info().extension_segment_widths;
+    if *actual != expected {
+        return Err(ProvingError::ExtensionSegmentWidthsMismatch {
+            expected,
+            actual: actual.clone(),
+        });
+    }
+    let expected = air.num_preprocessed_columns();
+    if num_preprocessed_columns != expected {
+        return Err(ProvingError::PreprocessedColumnsMismatch {
+            expected,
+            actual: num_preprocessed_columns,
+        });
+    }
+    let expected = air.num_randomizer_rows();
+    let actual = air.trace_info().num_randomizer_rows;
+    if actual != expected {
+        return Err(ProvingError::RandomizerRowsMismatch { expected, actual });
+    }
+    let required = air.options().min_randomizer_rows();
+    if expected < required {
+        return Err(ProvingError::TooFewRandomizerRows {
+            required,
+            actual: expected,
+        });
+    }
+    let expected = air.padding();
+    let trace_info = air.trace_info();
+    let actual = (trace_info.padding, trace_info.num_padding_rows);
+    if actual != expected {
+        return Err(ProvingError::PaddingMismatch { expected, actual });
+    }
+    let required_blowup_factor = air.ce_blowup_factor();
+    if required_blowup_factor > air.lde_blowup_factor() {
+        return Err(ProvingError::BlowupFactorTooSmall {
+            constraint_degree: air.constraint_degrees().into_iter().max().unwrap_or(0),
+            required: required_blowup_factor,
+            actual: air.lde_blowup_factor(),
+        });
+    }
+    air.validate();
+    Ok(air)
+}
+
+/// Runs `f` with GPU work on the current thread going to [Prover::device]
+#[cfg_attr(not(feature = "gpu"), allow(unused_variables))]
+fn on_device<P: Prover + ?Sized, T, E: From<ProvingError>>(
+    prover: &P,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    #[cfg(feature = "gpu")]
+    if let Some(index) = prover.device() {
+        use gpu_poly::device::DeviceSelectionError;
+        return gpu_poly::device::with_device(index, f).unwrap_or_else(|error| match error {
+            DeviceSelectionError::InvalidIndex { index, num_devices } => {
+                Err(ProvingError::InvalidDevice { index, num_devices }.into())
+            }
+        });
+    }
+    f()
+}
+
+/// See [Prover::prove_with_rng]. Events and phase timings also go to
+/// `observer`.
+fn prove_observed<P: Prover + ?Sized, R: Rng + ?Sized>(
+    prover: &P,
+    trace: P::Trace,
+    rng: &mut R,
+    observer: &Observer,
+) -> Result<Proof<P::Air>, ProvingError> {
+    on_device(prover, || prove_on_device(prover, trace, rng, observer))
+}
+
+fn prove_on_device<P: Prover + ?Sized, R: Rng + ?Sized>(
+    prover: &P,
+    trace: P::Trace,
+    rng: &mut R,
+    observer: &Observer,
+) -> Result<Proof<P::Air>, ProvingError> {
+    let air = build_air(prover, &trace)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!(
+        "prove",
+        trace_len = air.trace_len(),
+        lde_blowup_factor = air.lde_blowup_factor(),
+        ce_blowup_factor = air.ce_blowup_factor(),
+        num_queries = air.options().num_queries,
+    )
+    .entered();
+    let trace_digest = prover.checkpointing().then(|| {
+        let preprocessed_commitment = prover.preprocessed_trace().map(|t| t.commitment());
+        trace_digest::<P::Digest, _>(trace.base_columns(), preprocessed_commitment.as_deref())
+    });
+    let mut observer = *observer;
+    observer.trace_digest = trace_digest.as_deref();
+    let observer = &observer;
+    let checkpoint = trace_digest.as_ref().and_then(|trace_digest| {
+        let checkpoint = prover.load_checkpoint()?;
+        checkpoint
+            .is_for(&air, prover.context(), trace_digest)
+            .then_some(checkpoint)
+    });
+    let (salt_seed, restored_base_trace, restored_composition_trace, restored_fri_layers) =
+        match checkpoint {
+            Some(checkpoint) => (
+                checkpoint.salt_seed,
+                checkpoint.base_trace,
+                checkpoint.composition_trace,
+                checkpoint.fri_layers,
+            ),
+            None => {
+                let salt_seed = air.options().zk.then(|| rng.gen::<[u8; 32]>());
+                (salt_seed, None, None, Vec::new())
+            }
+        };
+    let (state, cache) = commit_trace(
+        prover,
+        &air,
+        &trace,
+        salt_seed,
+        restored_base_trace,
+        false,
+        observer,
+    )?;
+    drop(trace);
+    let (state, cache) = commit_constraints(
+        prover,
+        &air,
+        state,
+        cache,
+        restored_composition_trace,
+        false,
+        observer,
+    )?;
+    let (state, cache) = commit_fri(prover, &air, state, cache, restored_fri_layers, observer)?;
+    open_queries(prover, &air, state, cache, observer)
+}
+
+/// Source of prover randomness for [Prover::prove]. Randomness is only drawn
+/// with zk so [Prover::salt_seed] isn't called otherwise.
+fn default_rng<P: Prover + ?Sized>(prover: &P) -> ChaCha20Rng {
+    let seed = match prover.options().zk {
+        true => prover.salt_seed(),
+        false => [0; 32],
+    };
+    ChaCha20Rng::from_seed(seed)
+}
+
+/// Message of a panic caught while proving
+#[cfg(feature = "std")]
+fn panic_message(panic: &(dyn core::any::Any + Send)) -> String {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => panic
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+/// Checks for cancellation and reports the start of `phase`
+fn start_phase<P: Prover + ?Sized>(
+    prover: &P,
+    observer: &Observer,
+    phase: ProvingPhase,
+) -> Result<PhaseSpan, ProvingError> {
+    if prover
+        .cancellation_token()
+        .is_some_and(CancellationToken::is_cancelled)
+    {
+        return Err(ProvingError::Cancelled { phase });
+    }
+    report(prover, observer, ProverEvent::PhaseStarted(phase));
+    Ok(PhaseSpan::enter(phase))
+}
+
+fn finish_phase<P: Prover + ?Sized>(prover: &P, observer: &Observer, span: PhaseSpan) {
+    report(prover, observer, ProverEvent::PhaseFinished(span.phase));
+    #[cfg(feature = "std")]
+    if let Some(timings) = observer.timings {
+        timings.lock().unwrap().push(PhaseTiming {
+            phase: span.phase,
+            duration: span.started.elapsed(),
+        });
+    }
+}
+
+/// Prover randomness of zero-knowledge proofs. There is one salt stream per
+/// commitment: base trace, extension segments then composition trace. The
+/// following streams are for the randomizer rows and the composition mask.
+fn zk_rng(salt_seed: Option<[u8; 32]>, stream: usize) -> Option<ChaCha20Rng> {
+    salt_seed.map(|seed| {
+        let mut rng = ChaCha20Rng::from_seed(seed);
+        rng.set_stream(stream as u64);
+        rng
+    })
+}
+
+/// Commits to `leaves` salted with salt stream `stream` (see [zk_rng])
+fn commit_leaves<A: Air>(
+    air: &A,
+    salt_seed: Option<[u8; 32]>,
+    mut leaves: Vec<Output<A::Digest>>,
+    stream: usize,
+) -> A::VectorCommitment {
+    if let Some(seed) = salt_seed {
+        let salts = LeafSalts::new(seed, stream as u64);
+        for (i, leaf) in leaves.iter_mut().enumerate() {
+            *leaf = salt_leaf::<A::Digest>(leaf, &salts.salt(i));
+        }
+    }
+    <A::VectorCommitment as VectorCommitment<A::Digest>>::commit(leaves, air.options())
+}
+
+/// Checkpoint of the proof of the trace with digest `trace_digest` holding
+/// the committed traces so far and no FRI layers
+fn new_checkpoint<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    salt_seed: Option<[u8; 32]>,
+    trace_digest: &[u8],
+    base_trace: CommittedTrace<P::Fp>,
+    composition_trace: Option<CommittedTrace<P::Fq>>,
+) -> ProverCheckpoint<P::Air> {
+    ProverCheckpoint {
+        trace_info: air.trace_info().clone(),
+        options: *air.options(),
+        public_inputs: air.pub_inputs().clone(),
+        context: prover.context().to_vec(),
+        trace_digest: trace_digest.to_vec(),
+        salt_seed,
+        base_trace: Some(base_trace),
+        composition_trace,
+        fri_layers: Vec::new(),
+    }
+}
+
+/// Hands the state so far to [Prover::save_checkpoint] and takes it back
+fn save_progress<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    salt_seed: Option<[u8; 32]>,
+    trace_digest: &[u8],
+    base_trace: CommittedTrace<P::Fp>,
+    composition_trace: Option<CommittedTrace<P::Fq>>,
+) -> (CommittedTrace<P::Fp>, Option<CommittedTrace<P::Fq>>) {
+    let checkpoint = new_checkpoint(
+        prover,
+        air,
+        salt_seed,
+        trace_digest,
+        base_trace,
+        composition_trace,
+    );
+    prover.save_checkpoint(&checkpoint);
+    (checkpoint.base_trace.unwrap(), checkpoint.composition_trace)
+}
+
+/// Channel of a proof that has absorbed the trace commitments and the
+/// challenges drawn along the way
+type ReplayedTrace<'a, A, D> = (ProverChannel<'a, A, D>, Challenges<<A as Air>::Fq>);
+
+/// Output of a stage of [Prover::prove] and the data kept for the next stage
+type StageResult<S, A> = Result<(S, StageCache<A>), ProvingError>;
+
+/// Creates the channel of a proof and absorbs its trace commitments. Returns
+/// the channel and the challenges drawn along the way.
+fn replay_trace_commitments<'a, P: Prover + ?Sized>(
+    prover: &P,
+    air: &'a P::Air,
+    state: &TraceCommitted<P::Air>,
+) -> ReplayedTrace<'a, P::Air, P::Digest> {
+    let mut channel = ProverChannel::<P::Air, P::Digest>::new(air, &state.context);
+    if let Some(preprocessed_trace) = prover.preprocessed_trace() {
+        channel.commit_preprocessed_trace(&preprocessed_trace.commitment());
+    }
+    channel.commit_base_trace(&state.base_trace_commitment);
+    let mut challenges = air.get_challenges(&mut channel.public_coin);
+    for (segment, commitment) in state.extension_trace_commitments.iter().enumerate() {
+        if segment != 0 {
+            air.get_segment_challenges(segment, &mut channel.public_coin, &mut challenges);
+        }
+        channel.commit_extension_trace(commitment);
+    }
+    (channel, challenges)
+}
+
+/// See [Prover::commit_trace]. Starts from `restored_base_trace` if proving
+/// resumes from a checkpoint. Leaves are only copied into the committed
+/// traces if `keep_leaves` is set or they're needed for a checkpoint.
+fn commit_trace<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    trace: &P::Trace,
+    salt_seed: Option<[u8; 32]>,
+    restored_base_trace: Option<CommittedTrace<P::Fp>>,
+    keep_leaves: bool,
+    observer: &Observer,
+) -> StageResult<TraceCommitted<P::Air>, P::Air> {
+    let preprocessed_trace = prover.preprocessed_trace();
+    let mut channel = ProverChannel::<P::Air, P::Digest>::new(air, prover.context());
+    let trace_xs = air.trace_domain();
+    let lde_xs = air.lde_domain();
+    let ce_xs = air.ce_domain();
+    let streaming_lde = prover.streaming_lde();
+    let num_commitments = air.trace_info().num_extension_segments() + 2;
+    let num_randomizer_rows = air.trace_info().num_randomizer_rows;
+    let mut randomizer_rng = zk_rng(salt_seed, num_commitments).filter(|_| num_randomizer_rows > 0);
+    let actual = trace.base_columns().num_cols();
+    if actual != P::Trace::NUM_BASE_COLUMNS {
+        return Err(ProvingError::BaseColumnsMismatch {
+            expected: P::Trace::NUM_BASE_COLUMNS,
+            actual,
+        });
+    }
+    let mut randomized_base_trace = None;
+    let base_trace = match &mut randomizer_rng {
+        Some(rng) => {
+            let mut base_trace = trace.base_columns().clone();
+            randomize_rows(&mut base_trace, num_randomizer_rows, rng);
+            &*randomized_base_trace.insert(base_trace)
+        }
+        None => trace.base_columns(),
+    };
+    let base_trace_span = start_phase(prover, observer, ProvingPhase::BaseTrace)?;
+    if let Some(preprocessed_trace) = preprocessed_trace {
+        let expected = lde_xs.size();
+        let actual = preprocessed_trace.lde.num_rows();
+        if actual != expected {
+            return Err(ProvingError::PreprocessedLdeSizeMismatch { expected, actual });
+        }
+        channel.commit_preprocessed_trace(&preprocessed_trace.commitment());
+    }
+    let (base_trace_committed, base_trace_leaves, base_trace_lde) = match restored_base_trace {
+        Some(committed) => {
+            let leaves = committed.leaves::<P::Digest>();
+            (committed, leaves, None)
+        }
+        None => {
+            // TODO: base columns aren't kept resident on the GPU. They're copied
+            // to be interpolated and each phase wraps the LDE in new buffers.
+            let polys = interpolate_base_segments(air.trace_info(), base_trace);
+            let (lde, leaves) = if streaming_lde {
+                let leaves = polys
+                    .hash_lde_rows::<P::Digest, <P::Air as Air>::LeafEncoder>(trace_xs, lde_xs);
+                (polys.evaluate(ce_xs), leaves)
+            } else {
+                let lde = polys.evaluate(lde_xs);
+                let leaves = lde.hash_rows::<P::Digest, <P::Air as Air>::LeafEncoder>();
+                (lde, leaves)
+            };
+            let mut committed = match keep_leaves || observer.trace_digest.is_some() {
+                true => CommittedTrace::new::<P::Digest>(polys, &leaves),
+                false => CommittedTrace::without_leaves(polys),
+            };
+            if let Some(trace_digest) = observer.trace_digest {
+                (committed, _) =
+                    save_progress(prover, air, salt_seed, trace_digest, committed, None);
+            }
+            (committed, leaves, Some(lde))
+        }
+    };
+    let base_trace_commitment = commit_leaves(air, salt_seed, base_trace_leaves, 0);
+    channel.commit_base_trace(&base_trace_commitment.commitment());
+    finish_phase(prover, observer, base_trace_span);
+    let mut challenges = air.get_challenges(&mut channel.public_coin);
+
+    // build and commit to each extension segment once the challenges it
+    // depends on have been drawn
+    let extension_segment_widths = air.trace_info().extension_segment_widths.clone();
+    let mut extension_segments = Vec::new();
+    let mut extension_segments_committed = Vec::new();
+    let mut extension_segment_ldes = Vec::new();
+    let mut extension_commitments = Vec::new();
+    let num_extension_segments = extension_segment_widths.len();
+    let extension_trace_span = start_phase(prover, observer, ProvingPhase::ExtensionTrace)?;
+    for (segment, width) in extension_segment_widths.into_iter().enumerate() {
+        if segment != 0 {
+            air.get_segment_challenges(segment, &mut channel.public_coin, &mut challenges);
+        }
+        let mut segment_trace = trace
+            .build_aux_segment(segment, &extension_segments, &challenges)
+            .ok_or(ProvingError::ExtensionSegmentNotBuilt { segment })?;
+        if segment_trace.num_cols() != width {
+            return Err(ProvingError::ExtensionSegmentWidthMismatch {
+                segment,
+                expected: width,
+                actual: segment_trace.num_cols(),
+            });
+        }
+        if let Some(rng) = &mut randomizer_rng {
+            randomize_rows(&mut segment_trace, num_randomizer_rows, rng);
+        }
+        let polys = segment_trace.interpolate(trace_xs);
+        let (lde, leaves) = if streaming_lde {
+            let leaves =
+                polys.hash_lde_rows::<P::Digest, <P::Air as Air>::LeafEncoder>(trace_xs, lde_xs);
+            (polys.evaluate(ce_xs), leaves)
+        } else {
+            let lde = polys.evaluate(lde_xs);
+            let leaves = lde.hash_rows::<P::Digest, <P::Air as Air>::LeafEncoder>();
+            (lde, leaves)
+        };
+        extension_segments_committed.push(match keep_leaves {
+            true => CommittedTrace::new::<P::Digest>(polys, &leaves),
+            false => CommittedTrace::without_leaves(polys),
+        });
+        let commitment = commit_leaves(air, salt_seed, leaves, segment + 1);
+        channel.commit_extension_trace(&commitment.commitment());
+        extension_segments.push(segment_trace);
+        extension_segment_ldes.push(lde);
+        extension_commitments.push(commitment);
+        let percent = (100 * (segment + 1) / num_extension_segments) as u8;
+        let phase = ProvingPhase::ExtensionTrace;
+        report(prover, observer, ProverEvent::Progress { phase, percent });
+    }
+    finish_phase(prover, observer, extension_trace_span);
+
+    let has_extension_trace = !extension_segments.is_empty();
+    let extension_trace_lde = has_extension_trace.then(|| Matrix::join(extension_segment_ldes));
+    // segment widths sum to the number of extension columns (see
+    // TraceInfo::with_extension_segments) so the total needn't be checked
+
+    #[cfg(all(feature = "std", debug_assertions))]
+    let extension_trace = has_extension_trace.then(|| Matrix::join(extension_segments));
+    #[cfg(all(feature = "std", debug_assertions))]
+    air.validate_constraints(
+        &challenges,
+        &air.get_hints(&challenges),
+        base_trace,
+        extension_trace.as_ref(),
+        preprocessed_trace
+            .map(|trace| trace.polys.evaluate(trace_xs))
+            .as_ref(),
+    );
+
+    let state = TraceCommitted {
+        trace_info: air.trace_info().clone(),
+        options: *air.options(),
+        public_inputs: air.pub_inputs().clone(),
+        context: prover.context().to_vec(),
+        salt_seed,
+        base_trace: base_trace_committed,
+        extension_segments: extension_segments_committed,
+        base_trace_commitment: base_trace_commitment.commitment(),
+        extension_trace_commitments: extension_commitments
+            .iter()
+            .map(VectorCommitment::commitment)
+            .collect(),
+    };
+    let cache = StageCache {
+        base_trace_lde,
+        extension_trace_lde,
+        base_trace_commitment: Some(base_trace_commitment),
+        extension_commitments: Some(extension_commitments),
+        ..StageCache::default()
+    };
+    Ok((state, cache))
+}
+
+/// See [Prover::commit_constraints]. Starts from `restored_composition_trace`
+/// if proving resumes from a checkpoint. Leaves are kept as in [commit_trace].
+fn commit_constraints<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    mut state: TraceCommitted<P::Air>,
+    mut cache: StageCache<P::Air>,
+    restored_composition_trace: Option<CommittedTrace<P::Fq>>,
+    keep_leaves: bool,
+    observer: &Observer,
+) -> StageResult<ConstraintsCommitted<P::Air>, P::Air> {
+    let lde_xs = air.lde_domain();
+    let (mut channel, challenges) = replay_trace_commitments(prover, air, &state);
+    let hints = air.get_hints(&challenges);
+    let num_commitments = state.extension_segments.len() + 2;
+
+    let constraint_evaluation_span =
+        start_phase(prover, observer, ProvingPhase::ConstraintEvaluation)?;
+    let composition_coeffs = air.get_constraint_composition_coeffs(&mut channel.public_coin);
+    let (composition_trace, composition_trace_leaves, composition_trace_lde) =
+        match restored_composition_trace {
+            Some(committed) => {
+                let lde = committed.polys.evaluate(lde_xs);
+                let leaves = committed.leaves::<P::Digest>();
+                (committed, leaves, lde)
+            }
+            None => {
+                let mut constraint_coposer = ConstraintComposer::new(air, composition_coeffs);
+                if let Some(mut rng) = zk_rng(state.salt_seed, num_commitments + 1) {
+                    constraint_coposer = constraint_coposer.with_mask(&mut rng);
+                }
+                // traces are evaluated over the domain they were committed over
+                let xs = match prover.streaming_lde() {
+                    true => air.ce_domain(),
+                    false => lde_xs,
+                };
+                let base_trace_lde = cache
+                    .base_trace_lde
+                    .take()
+                    .unwrap_or_else(|| state.base_trace.polys.evaluate(xs));
+                let extension_trace_lde = cache.extension_trace_lde.take().or_else(|| {
+                    let segments = &state.extension_segments;
+                    (!segments.is_empty()).then(|| {
+                        Matrix::join(segments.iter().map(|s| s.polys.evaluate(xs)).collect())
+                    })
+                });
+                // NOTE: consuming LDEs here requires more compute later but saves on memory
+                let (lde, polys, leaves) = constraint_coposer.build_commitment(
+                    &challenges,
+                    &hints,
+                    base_trace_lde,
+                    extension_trace_lde,
+                    prover.preprocessed_trace().map(|trace| &trace.lde),
+                );
+                let mut committed = match keep_leaves || observer.trace_digest.is_some() {
+                    true => CommittedTrace::new::<P::Digest>(polys, &leaves),
+                    false => CommittedTrace::without_leaves(polys),
+                };
+                if let Some(trace_digest) = observer.trace_digest {
+                    let (base_trace, composition_trace) = save_progress(
+                        prover,
+                        air,
+                        state.salt_seed,
+                        trace_digest,
+                        state.base_trace,
+                        Some(committed),
+                    );
+                    state.base_trace = base_trace;
+                    committed = composition_trace.unwrap();
+                }
+                (committed, leaves, lde)
+            }
+        };
+    let composition_trace_commitment = commit_leaves(
+        air,
+        state.salt_seed,
+        composition_trace_leaves,
+        num_commitments - 1,
+    );
+    channel.commit_composition_trace(&composition_trace_commitment.commitment());
+    finish_phase(prover, observer, constraint_evaluation_span);
+
+    let state = ConstraintsCommitted {
+        trace: state,
+        composition_trace,
+        composition_trace_commitment: composition_trace_commitment.commitment(),
+    };
+    cache.composition_trace_lde = Some(composition_trace_lde);
+    cache.composition_trace_commitment = Some(composition_trace_commitment);
+    Ok((state, cache))
+}
+
+/// Forwards FRI commitments to the channel, reports the progress of the FRI
+/// phase after each committed layer and checkpoints each layer
+/// Prover of the low-degree test verified by `A`
+type LdtProverOf<A> =
+    <<A as Air>::LdtVerifier as LdtVerifier<<A as Air>::Fq, <A as Air>::Digest>>::Prover;
+
+struct FriProgress<'a, P: Prover + ?Sized, C> {
+    prover: &'a P,
+    observer: &'a Observer<'a>,
+    channel: &'a mut C,
+    /// Layers committed without early stopping
+    num_layers: usize,
+    num_committed_layers: usize,
+    /// Checkpoint the layers are added to if [Prover::checkpointing] is set
+    checkpoint: Option<&'a mut ProverCheckpoint<P::Air>>,
+}
+
+impl<'a, P, C> fri::ProverChannel<P::Fq> for FriProgress<'a, P, C>
+where
+    P: Prover + ?Sized,
+    C: fri::ProverChannel<P::Fq, Digest = P::Digest>,
+{
+    type Digest = P::Digest;
+
+    fn commit_fri_layer(&mut self, commitment: &[u8]) {
+        self.channel.commit_fri_layer(commitment);
+        self.num_committed_layers += 1;
+        // early stopping commits fewer layers so percentages may skip ahead
+        let percent = (100 * self.num_committed_layers / self.num_layers).min(100) as u8;
+        let phase = ProvingPhase::Fri;
+        report(
+            self.prover,
+            self.observer,
+            ProverEvent::Progress { phase, percent },
         );
-        Ok(channel.build_proof(queries, fri_proof))
     }
+
+    fn draw_fri_alpha(&mut self) -> P::Fq {
+        self.channel.draw_fri_alpha()
+    }
+
+    fn commit_fri_remainder(&mut self, coeffs: &[P::Fq]) {
+        self.channel.commit_fri_remainder(coeffs);
+    }
+
+    fn save_fri_layer(&mut self, evaluations: &[P::Fq], leaves: &[Output<P::Digest>]) {
+        self.channel.save_fri_layer(evaluations, leaves);
+        if let Some(checkpoint) = &mut self.checkpoint {
+            let layer = FriLayerCheckpoint::new::<P::Digest>(evaluations, leaves);
+            checkpoint.fri_layers.push(layer);
+            self.prover.save_checkpoint(checkpoint);
+        }
+    }
+}
+
+/// See [Prover::commit_fri]. Starts the queries phase which ends once the
+/// trace is opened. Starts from `restored_fri_layers` if proving resumes from
+/// a checkpoint.
+fn commit_fri<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    mut state: ConstraintsCommitted<P::Air>,
+    mut cache: StageCache<P::Air>,
+    restored_fri_layers: Vec<FriLayerCheckpoint<P::Fq>>,
+    observer: &Observer,
+) -> StageResult<FriDone<P::Air>, P::Air> {
+    let (mut channel, _) = replay_trace_commitments(prover, air, &state.trace);
+    // the coefficients aren't used but keep the transcript in sync
+    air.get_constraint_composition_coeffs(&mut channel.public_coin);
+    channel.commit_composition_trace(&state.composition_trace_commitment);
+    // reusable buffers for the duration of this stage
+    let buffer_pool = BufferPool::new();
+
+    let deep_composition_span = start_phase(prover, observer, ProvingPhase::DeepComposition)?;
+    // the columns of the extension segments are moved into one matrix and
+    // back once the DEEP composition polynomial is evaluated
+    let extension_segments = &mut state.trace.extension_segments;
+    let segment_widths = extension_segments
+        .iter()
+        .map(|segment| segment.polys.num_cols())
+        .collect::<Vec<usize>>();
+    let extension_trace_polys = (!extension_segments.is_empty()).then(|| {
+        let columns = extension_segments
+            .iter_mut()
+            .flat_map(|s| s.polys.0.drain(..));
+        Matrix::new(columns.collect())
+    });
+    let mut deep_poly_composer = DeepPolyComposer::new(
+        air,
+        channel.get_ood_point(),
+        &state.trace.base_trace.polys,
+        extension_trace_polys.as_ref(),
+        prover.preprocessed_trace().map(|trace| &trace.polys),
+        &state.composition_trace.polys,
+    )
+    .with_ldes(
+        prover.preprocessed_trace().map(|trace| &trace.lde),
+        cache.composition_trace_lde.as_ref(),
+    );
+    let (execution_trace_oods, composition_trace_oods) = deep_poly_composer.get_ood_evals();
+    channel.send_execution_trace_ood_evals(execution_trace_oods.clone());
+    channel.send_composition_trace_ood_evals(composition_trace_oods.clone());
+    let deep_coeffs = air.get_deep_composition_coeffs(&mut channel.public_coin);
+    let deep_composition_lde =
+        deep_poly_composer.into_deep_lde(deep_coeffs, air.lde_domain(), &buffer_pool);
+    if let Some(extension_trace_polys) = extension_trace_polys {
+        let mut columns = extension_trace_polys.0.into_iter();
+        let segments = state.trace.extension_segments.iter_mut();
+        for (segment, width) in segments.zip(segment_widths) {
+            segment.polys.0.extend(columns.by_ref().take(width));
+        }
+    }
+    finish_phase(prover, observer, deep_composition_span);
+
+    let fri_span = start_phase(prover, observer, ProvingPhase::Fri)?;
+    // the committed traces are moved into the checkpoint of each FRI layer
+    let mut checkpoint = new_checkpoint(
+        prover,
+        air,
+        state.trace.salt_seed,
+        observer.trace_digest.unwrap_or_default(),
+        state.trace.base_trace,
+        Some(state.composition_trace),
+    );
+    checkpoint.fri_layers = restored_fri_layers.clone();
+    let fri_options = air.options().into_fri_options();
+    let mut fri_prover = LdtProverOf::<P::Air>::new(fri_options);
+    let mut fri_channel = FriProgress {
+        prover,
+        observer,
+        channel: &mut channel,
+        num_layers: fri_options.num_committed_layers(air.lde_domain().size()),
+        num_committed_layers: 0,
+        checkpoint: observer.trace_digest.map(|_| &mut checkpoint),
+    };
+    fri_prover.build_layers_from(&mut fri_channel, deep_composition_lde, restored_fri_layers);
+    state.trace.base_trace = checkpoint.base_trace.unwrap();
+    state.composition_trace = checkpoint.composition_trace.unwrap();
+    finish_phase(prover, observer, fri_span);
+
+    let grinding_span = start_phase(prover, observer, ProvingPhase::Grinding)?;
+    let token = prover.cancellation_token();
+    let is_cancelled = || token.is_some_and(CancellationToken::is_cancelled);
+    if !channel.grind_fri_commitments_until(is_cancelled) {
+        return Err(ProvingError::Cancelled {
+            phase: ProvingPhase::Grinding,
+        });
+    }
+    finish_phase(prover, observer, grinding_span);
+
+    let queries_span = start_phase(prover, observer, ProvingPhase::Queries)?;
+    let query_positions = channel.get_fri_query_positions();
+    let fri_proof = fri_prover.into_proof(&query_positions);
+    cache.queries_span = Some(queries_span);
+
+    let state = FriDone {
+        constraints: state,
+        execution_trace_ood_evals: execution_trace_oods,
+        composition_trace_ood_evals: composition_trace_oods,
+        fri_proof,
+        pow_nonce: channel.pow_nonce(),
+        query_positions,
+    };
+    Ok((state, cache))
+}
+
+/// See [Prover::open_queries]
+fn open_queries<P: Prover + ?Sized>(
+    prover: &P,
+    air: &P::Air,
+    state: FriDone<P::Air>,
+    mut cache: StageCache<P::Air>,
+    observer: &Observer,
+) -> Result<Proof<P::Air>, ProvingError> {
+    let queries_span = match cache.queries_span.take() {
+        Some(span) => span,
+        None => start_phase(prover, observer, ProvingPhase::Queries)?,
+    };
+    let FriDone {
+        constraints,
+        execution_trace_ood_evals,
+        composition_trace_ood_evals,
+        fri_proof,
+        pow_nonce,
+        query_positions,
+    } = state;
+    let ConstraintsCommitted {
+        trace,
+        composition_trace,
+        composition_trace_commitment,
+    } = constraints;
+    let salt_seed = trace.salt_seed;
+    let num_commitments = trace.extension_segments.len() + 2;
+    // rebuild what wasn't kept in memory from the committed traces
+    let base_commitment = cache.base_trace_commitment.take().unwrap_or_else(|| {
+        let leaves = trace.base_trace.leaves::<P::Digest>();
+        commit_leaves(air, salt_seed, leaves, 0)
+    });
+    let extension_commitments = cache.extension_commitments.take().unwrap_or_else(|| {
+        let segments = trace.extension_segments.iter().enumerate();
+        segments
+            .map(|(segment, committed)| {
+                let leaves = committed.leaves::<P::Digest>();
+                commit_leaves(air, salt_seed, leaves, segment + 1)
+            })
+            .collect()
+    });
+    let composition_commitment = cache
+        .composition_trace_commitment
+        .take()
+        .unwrap_or_else(|| {
+            let leaves = composition_trace.leaves::<P::Digest>();
+            commit_leaves(air, salt_seed, leaves, num_commitments - 1)
+        });
+    let composition_trace_lde = cache
+        .composition_trace_lde
+        .take()
+        .unwrap_or_else(|| composition_trace.polys.evaluate(air.lde_domain()));
+    let extension_segments = trace.extension_segments;
+    let extension_trace_polys = (!extension_segments.is_empty())
+        .then(|| Matrix::join(extension_segments.into_iter().map(|s| s.polys).collect()));
+    let leaf_salts = salt_seed.map(|seed| {
+        (0..num_commitments as u64)
+            .map(|stream| LeafSalts::new(seed, stream))
+            .collect::<Vec<_>>()
+    });
+    let trace_queries = Queries::new(
+        air,
+        prover.preprocessed_trace(),
+        &trace.base_trace.polys,
+        extension_trace_polys.as_ref(),
+        &composition_trace_lde,
+        base_commitment,
+        extension_commitments,
+        composition_commitment,
+        leaf_salts,
+        &query_positions,
+    );
+    finish_phase(prover, observer, queries_span);
+    Ok(Proof {
+        options: trace.options,
+        trace_info: trace.trace_info,
+        hash_id: hash_id::<P::Digest>(),
+        base_trace_commitment: trace.base_trace_commitment,
+        extension_trace_commitments: trace.extension_trace_commitments,
+        composition_trace_commitment,
+        fri_proof,
+        pow_nonce,
+        trace_queries,
+        public_inputs: trace.public_inputs,
+        execution_trace_ood_evals,
+        composition_trace_ood_evals,
+    })
+}
+
+/// Checks the options and the dimensions of `trace` before proving
+fn check_inputs<P: Prover + ?Sized>(prover: &P, trace: &P::Trace) -> Result<(), Error> {
+    prover.options().validate()?;
+    TraceInfo::try_new(
+        P::Trace::NUM_BASE_COLUMNS,
+        P::Trace::NUM_EXTENSION_COLUMNS,
+        trace.len(),
+        trace.meta().map(<[u8]>::to_vec),
+    )?;
+    Ok(())
+}
+
+/// Allowance for allocations that don't grow with the trace e.g. row buffers,
+/// Merkle tree bookkeeping and the transcript
+const SMALL_ALLOCATIONS_SIZE: usize = 64 * 1024;
+
+/// See [Prover::estimate_memory]
+fn estimate_memory<Fp, Fq, D: Digest>(
+    trace_info: &TraceInfo,
+    options: &ProofOptions,
+    checkpointing: bool,
+) -> Vec<MemoryEstimate> {
+    let fp_size = core::mem::size_of::<Fp>();
+    let fq_size = core::mem::size_of::<Fq>();
+    let digest_size = <D as Digest>::output_size();
+    let trace_len = trace_info.trace_len;
+    let lde_len = trace_len * usize::from(options.lde_blowup_factor);
+    let base_row_size = trace_info.num_base_columns * fp_size;
+    let extension_row_size = trace_info.num_extension_columns * fq_size;
+    let num_composition_columns = usize::from(options.lde_blowup_factor) + usize::from(options.zk);
+    let composition_row_size = num_composition_columns * fq_size;
+    // a Merkle tree has as many internal nodes as leaves at most
+    let tree_size = 2 * lde_len * digest_size;
+    let num_trees = 2 + trace_info.num_extension_segments();
+
+    let base_lde_size = lde_len * base_row_size;
+    let extension_lde_size = lde_len * extension_row_size;
+    let composition_lde_size = lde_len * composition_row_size;
+    let polys_size = trace_len * (base_row_size + extension_row_size + composition_row_size);
+    // the trace, its polynomials and its LDE
+    let base_size = 2 * trace_len * base_row_size + base_lde_size;
+    let extension_size = 2 * trace_len * extension_row_size + extension_lde_size;
+    // constraint evaluations plus the trace LDEs they're evaluated over
+    let evaluations_size = lde_len * fq_size + base_lde_size + extension_lde_size;
+    // FRI layers shrink geometrically so take at most twice the first layer
+    let deep_lde_size = lde_len * fq_size;
+    let fri_size = 2 * lde_len * (fq_size + digest_size);
+    // on the GPU the DEEP LDE, the inverted denominators of two points, the
+    // LDE domain and the LDEs of trace columns opened at several offsets
+    let deep_composition_size = match cfg!(feature = "gpu") {
+        true => 3 * deep_lde_size + lde_len * fp_size + base_lde_size + extension_lde_size,
+        false => 2 * deep_lde_size,
+    };
+    // checkpoints copy the leaves of the base and composition trace and each
+    // FRI layer
+    let (leaves_copy_size, fri_copy_size) = match checkpointing {
+        true => (lde_len * digest_size, fri_size),
+        false => (0, 0),
+    };
+    // state kept once the composition trace is committed to
+    let committed_size =
+        polys_size + composition_lde_size + num_trees * tree_size + 2 * leaves_copy_size;
+
+    let gpu = |bytes: usize| if cfg!(feature = "gpu") { bytes } else { 0 };
+    let estimate = |phase, host_bytes, device_bytes| MemoryEstimate {
+        phase,
+        host_bytes: host_bytes + SMALL_ALLOCATIONS_SIZE,
+        device_bytes: gpu(device_bytes),
+    };
+    vec![
+        estimate(
+            ProvingPhase::BaseTrace,
+            base_size + tree_size + leaves_copy_size,
+            base_lde_size,
+        ),
+        estimate(
+            ProvingPhase::ExtensionTrace,
+            base_size + extension_size + (num_trees - 1) * tree_size + leaves_copy_size,
+            extension_lde_size,
+        ),
+        estimate(
+            ProvingPhase::ConstraintEvaluation,
+            polys_size
+                + evaluations_size
+                + composition_lde_size
+                + num_trees * tree_size
+                + 2 * leaves_copy_size,
+            evaluations_size,
+        ),
+        estimate(
+            ProvingPhase::DeepComposition,
+            committed_size + deep_composition_size,
+            deep_composition_size,
+        ),
+        estimate(
+            ProvingPhase::Fri,
+            committed_size + fri_size + fri_copy_size,
+            deep_lde_size,
+        ),
+        estimate(
+            ProvingPhase::Grinding,
+            committed_size + fri_size + fri_copy_size,
+            0,
+        ),
+        estimate(
+            ProvingPhase::Queries,
+            committed_size + fri_size + fri_copy_size,
+            0,
+        ),
+    ]
 }
@@ -1,25 +1,285 @@
+use crate::hash::pack_bytes;
+use crate::hash::CAPACITY;
+use crate::hash::RATE;
+use crate::hash::WIDTH;
+use crate::merkle::HashFn;
 use alloc::vec::Vec;
+use ark_ff::BigInteger;
 use ark_ff::Field;
+use ark_ff::PrimeField;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
 use digest::Digest;
 use digest::Output;
 use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaCha20Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::Sha256;
 
-// TODO: refactor public coin/channel stuff
-pub struct PublicCoin<D: Digest> {
-    pub seed: Output<D>,
-    counter: usize,
+/// Domain separation labels for each phase of the protocol. Absorbing data
+/// with a label ensures transcripts of different phases can't collide.
+pub mod label {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    pub const CONTEXT: &[u8] = b"context";
+    pub const PUBLIC_CELLS: &[u8] = b"public-cells";
+    pub const PREPROCESSED_TRACE_COMMITMENT: &[u8] = b"preprocessed-trace-commit";
+    pub const BASE_TRACE_COMMITMENT: &[u8] = b"trace-commit";
+    pub const EXTENSION_TRACE_COMMITMENT: &[u8] = b"extension-trace-commit";
+    pub const COMPOSITION_TRACE_COMMITMENT: &[u8] = b"composition-trace-commit";
+    pub const EXECUTION_TRACE_OOD_EVALS: &[u8] = b"trace-ood-evals";
+    pub const COMPOSITION_TRACE_OOD_EVALS: &[u8] = b"composition-ood-evals";
+    pub const POW: &[u8] = b"pow";
+
+    /// Label for the commitment to the `i`th FRI layer. The remainder is the
+    /// last layer.
+    pub fn fri_layer(i: usize) -> Vec<u8> {
+        String::into_bytes(format!("fri-layer-{i}"))
+    }
 }
 
-impl<D: Digest> PublicCoin<D> {
-    pub fn new(seed: &[u8]) -> Self {
-        PublicCoin {
-            seed: D::new_with_prefix(seed).finalize(),
-            counter: 0,
+/// Identifies a hash function by the first 8 bytes of its digest of a fixed
+/// message. Recorded in proofs so verifiers using a different hash function
+/// reject them upfront.
+pub fn hash_id<D: Digest>() -> u64 {
+    let digest = D::digest(b"ministark-hash-id");
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[0..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// Hash function used for the proof-of-work on FRI commitments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GrindingHash {
+    /// The hash used by the channel i.e. `Air::Digest`
+    #[default]
+    Channel,
+    Sha256,
+    #[cfg(feature = "blake3")]
+    Blake3,
+    #[cfg(feature = "keccak")]
+    Keccak256,
+}
+
+impl GrindingHash {
+    fn id(&self) -> u8 {
+        match self {
+            GrindingHash::Channel => 0,
+            GrindingHash::Sha256 => 1,
+            #[cfg(feature = "blake3")]
+            GrindingHash::Blake3 => 2,
+            #[cfg(feature = "keccak")]
+            GrindingHash::Keccak256 => 3,
+        }
+    }
+}
+
+impl CanonicalSerialize for GrindingHash {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.id().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.id().serialized_size(compress)
+    }
+}
+
+impl Valid for GrindingHash {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for GrindingHash {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(
+            match u8::deserialize_with_mode(reader, compress, validate)? {
+                0 => GrindingHash::Channel,
+                1 => GrindingHash::Sha256,
+                #[cfg(feature = "blake3")]
+                2 => GrindingHash::Blake3,
+                #[cfg(feature = "keccak")]
+                3 => GrindingHash::Keccak256,
+                _ => return Err(SerializationError::InvalidData),
+            },
+        )
+    }
+}
+
+/// Number of nonces tried between checks for cancellation when grinding
+pub const GRIND_POLL_INTERVAL: u64 = 1 << 12;
+
+/// Proof-of-work applied after the FRI commitments. A valid nonce makes
+/// `hash(seed || "pow" || nonce)` start with `bits` zero bits. The nonce is
+/// hashed as 8 little-endian bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GrindingConfig {
+    pub hash: GrindingHash,
+    pub bits: u8,
+}
+
+/// How the coefficients that mix constraints into the composition polynomial
+/// are drawn from the public coin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoefficientMode {
+    /// Each coefficient is drawn independently
+    #[default]
+    Independent,
+    /// Coefficients are consecutive powers `1, α, α^2, ...` of a single
+    /// challenge `α` (as in EthSTARK). Verifiers draw one challenge but the
+    /// soundness error grows by a factor of the number of coefficients.
+    Powers,
+}
+
+impl CoefficientMode {
+    fn id(&self) -> u8 {
+        match self {
+            CoefficientMode::Independent => 0,
+            CoefficientMode::Powers => 1,
+        }
+    }
+
+    /// Bits of security lost to drawing `n` coefficients. A combination with
+    /// powers of `α` is a polynomial of degree `n - 1` in `α` so the chance a
+    /// bad combination passes grows by up to a factor of `n` over independent
+    /// coefficients.
+    pub fn security_loss(&self, n: usize) -> usize {
+        match self {
+            CoefficientMode::Independent => 0,
+            CoefficientMode::Powers => n.next_power_of_two().ilog2() as usize,
         }
     }
 
+    /// Draws `n` coefficients from `public_coin`
+    pub fn draw<F: Field>(&self, public_coin: &mut impl PublicCoin, n: usize) -> Vec<F> {
+        match self {
+            CoefficientMode::Independent => (0..n).map(|_| public_coin.draw()).collect(),
+            CoefficientMode::Powers => {
+                let alpha = public_coin.draw::<F>();
+                core::iter::successors(Some(F::one()), |&power| Some(power * alpha))
+                    .take(n)
+                    .collect()
+            }
+        }
+    }
+}
+
+impl CanonicalSerialize for CoefficientMode {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.id().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.id().serialized_size(compress)
+    }
+}
+
+impl Valid for CoefficientMode {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for CoefficientMode {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(
+            match u8::deserialize_with_mode(reader, compress, validate)? {
+                0 => CoefficientMode::Independent,
+                1 => CoefficientMode::Powers,
+                _ => return Err(SerializationError::InvalidData),
+            },
+        )
+    }
+}
+
+/// Fiat-Shamir transcript shared by the prover and verifier
+pub trait PublicCoin: Sized + Send + Sync {
+    fn new(seed: &[u8]) -> Self;
+
+    /// Reseeds the coin with an item absorbed under a domain separation label
+    fn reseed_with_label(&mut self, label: &[u8], item: &impl CanonicalSerialize);
+
+    /// Draws a field element
+    fn draw<F: Field>(&mut self) -> F;
+
+    /// Draws query positions in `0..domain_size`
+    fn draw_queries(&mut self, num_queries: usize, domain_size: usize) -> Vec<usize>;
+
+    /// Draws `num_queries` query positions and returns the distinct positions
+    /// in ascending order. Colliding positions only need to be opened once.
+    fn draw_unique_queries(&mut self, num_queries: usize, domain_size: usize) -> Vec<usize> {
+        let mut positions = self.draw_queries(num_queries, domain_size);
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+
+    /// Returns the number of leading zeros of the proof-of-work hash of `nonce`
+    fn pow_leading_zeros(&self, hash: GrindingHash, nonce: u64) -> u32;
+
+    /// Searches for a nonce satisfying the proof-of-work. Nonces are checked
+    /// in parallel if the `parallel` feature is enabled. There is no GPU
+    /// search since it would need a Metal kernel for every [GrindingHash].
+    fn grind(&self, config: GrindingConfig) -> Option<u64> {
+        self.grind_until(config, || false)
+    }
+
+    /// Same as [PublicCoin::grind] but gives up and returns `None` once
+    /// `is_cancelled` returns true. Polled every [GRIND_POLL_INTERVAL]
+    /// nonces so high grinding factors can be abandoned early.
+    fn grind_until(
+        &self,
+        config: GrindingConfig,
+        is_cancelled: impl Fn() -> bool + Sync,
+    ) -> Option<u64> {
+        let bits = u32::from(config.bits);
+        let is_valid = |nonce: u64| self.pow_leading_zeros(config.hash, nonce) >= bits;
+        let is_done =
+            |&nonce: &u64| (nonce % GRIND_POLL_INTERVAL == 0 && is_cancelled()) || is_valid(nonce);
+
+        #[cfg(not(feature = "parallel"))]
+        let nonce = (1..u64::MAX).find(is_done);
+
+        #[cfg(feature = "parallel")]
+        let nonce = (1..u64::MAX).into_par_iter().find_any(is_done);
+
+        nonce.filter(|&nonce| is_valid(nonce))
+    }
+}
+
+/// Public coin built from a [Digest]. Every draw is `hash(seed || counter)`
+/// with the counter encoded as 8 big-endian bytes on every target.
+pub struct DigestPublicCoin<D: Digest> {
+    pub seed: Output<D>,
+    counter: u64,
+}
+
+impl<D: Digest> DigestPublicCoin<D> {
     pub fn reseed(&mut self, item: &impl CanonicalSerialize) {
         let mut data = Vec::new();
         item.serialize_compressed(&mut data).unwrap();
@@ -35,16 +295,7 @@ impl<D: Digest> PublicCoin<D> {
     }
 
     pub fn check_leading_zeros(&self, nonce: u64) -> u32 {
-        let mut nonce_bytes = Vec::with_capacity(nonce.compressed_size());
-        nonce.serialize_compressed(&mut nonce_bytes).unwrap();
-        let mut hasher = D::new();
-        hasher.update(&self.seed);
-        hasher.update(&nonce_bytes);
-        leading_zeros(&hasher.finalize())
-    }
-
-    pub fn draw<F: Field>(&mut self) -> F {
-        F::rand(&mut self.draw_rng())
+        pow_leading_zeros_with::<D>(&self.seed, nonce)
     }
 
     // TODO: make this generic
@@ -65,6 +316,202 @@ impl<D: Digest> PublicCoin<D> {
     }
 }
 
+impl<D: Digest> PublicCoin for DigestPublicCoin<D> {
+    fn new(seed: &[u8]) -> Self {
+        DigestPublicCoin {
+            seed: D::new_with_prefix(seed).finalize(),
+            counter: 0,
+        }
+    }
+
+    fn reseed_with_label(&mut self, label: &[u8], item: &impl CanonicalSerialize) {
+        let mut data = Vec::new();
+        item.serialize_compressed(&mut data).unwrap();
+        let mut hasher = labeled_hasher::<D>(&self.seed, label);
+        hasher.update(data);
+        self.seed = hasher.finalize();
+        self.counter = 0;
+    }
+
+    /// Draws a field element. Each base field coefficient is `hash(seed ||
+    /// counter)` interpreted as a big-endian integer reduced modulo the field's
+    /// characteristic. Only the hash function is needed to reproduce draws
+    /// which keeps on-chain verification cheap.
+    fn draw<F: Field>(&mut self) -> F {
+        let elements = (0..F::extension_degree())
+            .map(|_| F::BasePrimeField::from_be_bytes_mod_order(&self.next()))
+            .collect::<Vec<F::BasePrimeField>>();
+        F::from_base_prime_field_elems(&elements).unwrap()
+    }
+
+    /// Draws query positions in `0..domain_size`. Each position is the first 8
+    /// bytes of `hash(seed || counter)` as a big-endian integer reduced modulo
+    /// the domain size.
+    fn draw_queries(&mut self, num_queries: usize, domain_size: usize) -> Vec<usize> {
+        assert!(domain_size.is_power_of_two());
+        (0..num_queries)
+            .map(|_| {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&self.next()[0..8]);
+                (u64::from_be_bytes(bytes) % domain_size as u64) as usize
+            })
+            .collect()
+    }
+
+    fn pow_leading_zeros(&self, hash: GrindingHash, nonce: u64) -> u32 {
+        match hash {
+            GrindingHash::Channel => pow_leading_zeros_with::<D>(&self.seed, nonce),
+            hash => external_pow_leading_zeros(hash, &self.seed, nonce),
+        }
+    }
+}
+
+/// Duplex sponge public coin over the field of an algebraic hash e.g.
+/// [Poseidon2Goldilocks](crate::hash::Poseidon2Goldilocks). Field elements
+/// are squeezed directly from the sponge state so the transcript can be
+/// replayed inside an arithmetic circuit without emulating a bit oriented
+/// hash. Items are absorbed by packing their serialization with the same
+/// scheme as [AlgebraicDigest](crate::merkle::AlgebraicDigest).
+pub struct SpongePublicCoin<H: HashFn> {
+    state: [H::Field; WIDTH],
+    // index of the next rate element to squeeze
+    output_index: usize,
+}
+
+impl<H: HashFn> Clone for SpongePublicCoin<H> {
+    fn clone(&self) -> Self {
+        SpongePublicCoin {
+            state: self.state,
+            output_index: self.output_index,
+        }
+    }
+}
+
+impl<H: HashFn> SpongePublicCoin<H> {
+    pub fn state(&self) -> &[H::Field; WIDTH] {
+        &self.state
+    }
+
+    fn absorb(&mut self, elements: &[H::Field]) {
+        let permutation = H::permutation();
+        for chunk in elements.chunks(RATE) {
+            for (s, e) in self.state[CAPACITY..].iter_mut().zip(chunk) {
+                *s += e;
+            }
+            permutation.permute(&mut self.state);
+        }
+        self.output_index = 0;
+    }
+
+    fn absorb_with_label(&mut self, label: &[u8], data: &[u8]) {
+        let mut bytes = Vec::with_capacity(8 + label.len() + data.len());
+        bytes.extend((label.len() as u64).to_be_bytes());
+        bytes.extend(label);
+        bytes.extend(data);
+        self.absorb(&pack_bytes(&bytes));
+    }
+
+    fn squeeze(&mut self) -> H::Field {
+        if self.output_index == RATE {
+            H::permutation().permute(&mut self.state);
+            self.output_index = 0;
+        }
+        let element = self.state[CAPACITY + self.output_index];
+        self.output_index += 1;
+        element
+    }
+
+    fn serialized_state(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.state.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+impl<H: HashFn> PublicCoin for SpongePublicCoin<H> {
+    fn new(seed: &[u8]) -> Self {
+        let mut coin = SpongePublicCoin {
+            state: [H::Field::zero(); WIDTH],
+            output_index: 0,
+        };
+        coin.absorb(&pack_bytes(seed));
+        coin
+    }
+
+    fn reseed_with_label(&mut self, label: &[u8], item: &impl CanonicalSerialize) {
+        let mut data = Vec::new();
+        item.serialize_compressed(&mut data).unwrap();
+        self.absorb_with_label(label, &data);
+    }
+
+    /// Draws a field element. Each base field coefficient is a squeezed sponge
+    /// element reduced modulo the field's characteristic which is the identity
+    /// when the sponge and `F` share a base field.
+    fn draw<F: Field>(&mut self) -> F {
+        let elements = (0..F::extension_degree())
+            .map(|_| {
+                let bytes = self.squeeze().into_bigint().to_bytes_be();
+                F::BasePrimeField::from_be_bytes_mod_order(&bytes)
+            })
+            .collect::<Vec<F::BasePrimeField>>();
+        F::from_base_prime_field_elems(&elements).unwrap()
+    }
+
+    /// Draws query positions in `0..domain_size`. Each position is the low 64
+    /// bits of a squeezed element reduced modulo the domain size.
+    fn draw_queries(&mut self, num_queries: usize, domain_size: usize) -> Vec<usize> {
+        assert!(domain_size.is_power_of_two());
+        (0..num_queries)
+            .map(|_| {
+                let element = self.squeeze().into_bigint();
+                (element.as_ref()[0] % domain_size as u64) as usize
+            })
+            .collect()
+    }
+
+    /// The channel proof-of-work absorbs `"pow" || nonce` into a copy of the
+    /// sponge and counts the leading zeros of the first squeezed element
+    /// relative to the bit size of the modulus.
+    fn pow_leading_zeros(&self, hash: GrindingHash, nonce: u64) -> u32 {
+        match hash {
+            GrindingHash::Channel => {
+                let mut sponge = self.clone();
+                sponge.absorb_with_label(label::POW, &nonce.to_le_bytes());
+                let element = sponge.squeeze().into_bigint();
+                H::Field::MODULUS_BIT_SIZE - element.num_bits()
+            }
+            hash => external_pow_leading_zeros(hash, &self.serialized_state(), nonce),
+        }
+    }
+}
+
+/// Proof-of-work with a hash other than the channel's
+fn external_pow_leading_zeros(hash: GrindingHash, seed: &[u8], nonce: u64) -> u32 {
+    match hash {
+        GrindingHash::Channel => unreachable!(),
+        GrindingHash::Sha256 => pow_leading_zeros_with::<Sha256>(seed, nonce),
+        #[cfg(feature = "blake3")]
+        GrindingHash::Blake3 => pow_leading_zeros_with::<crate::Blake3>(seed, nonce),
+        #[cfg(feature = "keccak")]
+        GrindingHash::Keccak256 => pow_leading_zeros_with::<crate::Keccak256>(seed, nonce),
+    }
+}
+
+fn pow_leading_zeros_with<H: Digest>(seed: &[u8], nonce: u64) -> u32 {
+    let mut hasher = labeled_hasher::<H>(seed, label::POW);
+    hasher.update(nonce.to_le_bytes());
+    leading_zeros(&hasher.finalize())
+}
+
+/// Returns a hasher that has absorbed the seed and `label`
+fn labeled_hasher<H: Digest>(seed: &[u8], label: &[u8]) -> H {
+    let mut hasher = H::new();
+    hasher.update(seed);
+    hasher.update((label.len() as u64).to_be_bytes());
+    hasher.update(label);
+    hasher
+}
+
 fn leading_zeros(hash: &[u8]) -> u32 {
     let mut zeros = 0;
     for byte in hash {
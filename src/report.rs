@@ -0,0 +1,139 @@
+//! Telemetry of proof generation (see `Prover::prove_with_report`) so
+//! performance regressions can be tracked across releases.
+
+use crate::merkle::is_supported_arity;
+use crate::prover::ProvingPhase;
+use crate::Air;
+use crate::Proof;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ark_serialize::CanonicalSerialize;
+use core::time::Duration;
+use digest::Digest;
+
+/// Wall clock time spent in a [ProvingPhase]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseTiming {
+    pub phase: ProvingPhase,
+    pub duration: Duration,
+}
+
+/// Compressed size in bytes of each section of a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProofSize {
+    /// Trace and composition trace commitments
+    pub commitments: usize,
+    /// Out-of-domain evaluations of the trace and composition trace
+    pub ood_evals: usize,
+    pub fri_proof: usize,
+    pub trace_queries: usize,
+    pub public_inputs: usize,
+    /// Size of the whole proof. Includes the options, trace info and
+    /// proof-of-work nonce which aren't broken down.
+    pub total: usize,
+}
+
+impl ProofSize {
+    pub fn of<A: Air>(proof: &Proof<A>) -> Self {
+        ProofSize {
+            commitments: proof.base_trace_commitment.compressed_size()
+                + proof.extension_trace_commitments.compressed_size()
+                + proof.composition_trace_commitment.compressed_size(),
+            ood_evals: proof.execution_trace_ood_evals.compressed_size()
+                + proof.composition_trace_ood_evals.compressed_size(),
+            fri_proof: proof.fri_proof.compressed_size(),
+            trace_queries: proof.trace_queries.compressed_size(),
+            public_inputs: proof.public_inputs.compressed_size(),
+            total: proof.compressed_size(),
+        }
+    }
+}
+
+/// Telemetry of a single proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProverReport {
+    /// Phases in the order they finished
+    pub phases: Vec<PhaseTiming>,
+    /// Bytes hashed committing to the trace and composition trace (leaves
+    /// and Merkle tree nodes) estimated from the dimensions of the proof.
+    /// Hashing isn't measured and salting and FRI layers aren't counted.
+    pub estimated_bytes_hashed: u64,
+    /// Peak resident set size of the whole process since it started so
+    /// includes earlier and concurrent proofs. Only available on Linux.
+    pub process_peak_rss_bytes: Option<u64>,
+    /// Time the whole process spent waiting on the GPU while the proof was
+    /// generated so includes proofs generated concurrently
+    pub process_gpu_time: Duration,
+    pub proof_size: ProofSize,
+}
+
+impl ProverReport {
+    /// Report of `proof` given the phase timings and GPU time measured while
+    /// it was generated
+    pub fn new<A: Air>(proof: &Proof<A>, phases: Vec<PhaseTiming>, gpu_time: Duration) -> Self {
+        let air = A::new(
+            proof.trace_info.clone(),
+            proof.public_inputs.clone(),
+            proof.options,
+        );
+        ProverReport {
+            phases,
+            estimated_bytes_hashed: estimated_bytes_hashed(&air),
+            process_peak_rss_bytes: peak_rss_bytes(),
+            process_gpu_time: gpu_time,
+            proof_size: ProofSize::of(proof),
+        }
+    }
+}
+
+/// Bytes hashed committing to the trace and composition trace of `air`.
+/// Panics if the Merkle arity isn't supported.
+fn estimated_bytes_hashed<A: Air>(air: &A) -> u64 {
+    let trace_info = air.trace_info();
+    let num_leaves = air.trace_len() * air.lde_blowup_factor();
+    let arity = usize::from(air.options().merkle_arity);
+    assert!(
+        is_supported_arity(arity),
+        "merkle arity {arity} isn't supported"
+    );
+    let digest_size = <A::Digest as Digest>::output_size();
+    let fp_size = A::Fp::zero().compressed_size();
+    let fq_size = A::Fq::zero().compressed_size();
+    // every node but the root is hashed into its parent
+    let mut num_hashed_nodes = 0;
+    let mut level_size = num_leaves;
+    while level_size > 1 {
+        num_hashed_nodes += level_size;
+        level_size /= arity.min(level_size);
+    }
+    let tree_size = num_hashed_nodes * digest_size;
+    let mut row_sizes = vec![trace_info.num_base_columns * fp_size];
+    let extension_segment_widths = &trace_info.extension_segment_widths;
+    row_sizes.extend(extension_segment_widths.iter().map(|width| width * fq_size));
+    // zero-knowledge proofs commit to a mask column with the composition trace
+    let num_composition_columns = air.ce_blowup_factor() + usize::from(air.options().zk);
+    row_sizes.push(num_composition_columns * fq_size);
+    row_sizes
+        .into_iter()
+        .map(|row_size| (num_leaves * row_size + tree_size) as u64)
+        .sum()
+}
+
+/// Peak resident set size of this process in bytes
+#[cfg(feature = "std")]
+fn peak_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib = line
+        .trim_start_matches("VmHWM:")
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(feature = "std"))]
+fn peak_rss_bytes() -> Option<u64> {
+    None
+}
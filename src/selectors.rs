@@ -0,0 +1,51 @@
+//! Selectors are binary columns derived from the row index e.g. a flag for the
+//! first row of each segment of a VM trace. They're never committed to.
+//! Periodic selectors are periodic columns (see `Air::periodic_columns`) and
+//! row selectors are expressions of `X` so both are evaluated on the fly during
+//! constraint composition and at the out-of-domain point.
+use crate::constraints::AlgebraicExpression;
+use crate::constraints::FieldConstant;
+use crate::periodic::PeriodicColumn;
+use crate::StarkExtensionOf;
+use ark_ff::FftField;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use gpu_poly::GpuFftField;
+
+/// Periodic column that is one on `rows` of each period of `period` rows and
+/// zero elsewhere e.g. `periodic_selector(8, &[0])` flags the first row of
+/// every segment of 8 rows
+pub fn periodic_selector<F: FftField>(period: usize, rows: &[usize]) -> PeriodicColumn<F> {
+    let mut values = vec![F::zero(); period];
+    for &row in rows {
+        assert!(row < period, "row {row} is outside the period");
+        values[row] = F::one();
+    }
+    PeriodicColumn::new(values)
+}
+
+/// Expression that is one on row `row` of a trace over `trace_domain` and
+/// zero on all other rows i.e. the Lagrange basis polynomial
+/// `L_i(x) = g^i * (x^n - 1) / (n * (x - g^i))`. Has degree `n - 1`.
+pub fn row_selector<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+    row: usize,
+    trace_domain: Radix2EvaluationDomain<Fp>,
+) -> AlgebraicExpression<Fp, Fq> {
+    use AlgebraicExpression::X;
+    let n = trace_domain.size();
+    assert!(row < n, "row {row} is outside the trace");
+    let row_x = trace_domain.element(row);
+    let scale = row_x * trace_domain.size_as_field_element().inverse().unwrap();
+    let one = FieldConstant::Fp(Fp::one());
+    (X.pow(n) - one) * FieldConstant::Fp(scale) / (X - FieldConstant::Fp(row_x))
+}
+
+/// Periodic column that is one on rows that are multiples of `stride` and
+/// zero on all other rows i.e. on the rows of a base segment with stride
+/// `stride` (see `TraceInfo::with_base_segments`). Evaluated over a single
+/// period like any other periodic column rather than as a sum of `stride`
+/// powers of `X`.
+pub fn stride_selector<F: FftField>(stride: usize) -> PeriodicColumn<F> {
+    assert!(stride.is_power_of_two(), "stride must be a power of two");
+    periodic_selector(stride, &[0])
+}
@@ -0,0 +1,89 @@
+//! Explicitly vectorized CPU fallbacks for hot loops. Only used when the `gpu`
+//! feature is disabled.
+
+use core::any::TypeId;
+use core::simd::u64x4;
+use core::simd::SimdPartialOrd;
+use gpu_poly::fields::p18446744069414584321::Fp as GoldilocksFp;
+
+const LANES: usize = 4;
+
+/// Goldilocks modulus
+const P: u64 = 0xFFFFFFFF00000001;
+/// 2^64 - P
+const EPSILON: u64 = 0xFFFFFFFF;
+
+/// Sums `columns` into `accumulator`. Returns `false` if there is no vectorized
+/// implementation for the field in which case `accumulator` is untouched.
+pub fn try_sum_columns<F: 'static>(accumulator: &mut [F], columns: &[&[F]]) -> bool {
+    if TypeId::of::<F>() != TypeId::of::<GoldilocksFp>() {
+        return false;
+    }
+
+    // SAFETY: checked `F` is `GoldilocksFp`
+    let accumulator = unsafe { &mut *(accumulator as *mut [F] as *mut [GoldilocksFp]) };
+    let columns = unsafe { &*(columns as *const [&[F]] as *const [&[GoldilocksFp]]) };
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: checked the CPU supports AVX2
+        unsafe { sum_goldilocks_columns_avx2(accumulator, columns) };
+        return true;
+    }
+
+    sum_goldilocks_columns(accumulator, columns);
+    true
+}
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn sum_goldilocks_columns_avx2(
+    accumulator: &mut [GoldilocksFp],
+    columns: &[&[GoldilocksFp]],
+) {
+    sum_goldilocks_columns(accumulator, columns)
+}
+
+#[inline(always)]
+fn sum_goldilocks_columns(accumulator: &mut [GoldilocksFp], columns: &[&[GoldilocksFp]]) {
+    let n = accumulator.len();
+    let num_vectorized = n - n % LANES;
+    for i in (0..num_vectorized).step_by(LANES) {
+        let mut acc = load(&accumulator[i..i + LANES]);
+        for column in columns {
+            acc = add(acc, load(&column[i..i + LANES]));
+        }
+        store(&mut accumulator[i..i + LANES], acc);
+    }
+
+    // remaining values
+    for i in num_vectorized..n {
+        for column in columns {
+            accumulator[i] += column[i];
+        }
+    }
+}
+
+#[inline(always)]
+fn load(values: &[GoldilocksFp]) -> u64x4 {
+    u64x4::from_array(core::array::from_fn(|i| (values[i].0).0[0]))
+}
+
+#[inline(always)]
+fn store(dst: &mut [GoldilocksFp], values: u64x4) {
+    for (dst, v) in dst.iter_mut().zip(values.to_array()) {
+        (dst.0).0[0] = v;
+    }
+}
+
+/// Modular addition of Goldilocks elements. Elements are in Montgomery form
+/// which is preserved by addition.
+#[inline(always)]
+fn add(a: u64x4, b: u64x4) -> u64x4 {
+    let sum = a + b;
+    // add 2^64 mod P back on overflow
+    let overflowed = sum.simd_lt(a);
+    let sum = overflowed.select(sum + u64x4::splat(EPSILON), sum);
+    let reduce = sum.simd_ge(u64x4::splat(P));
+    reduce.select(sum - u64x4::splat(P), sum)
+}
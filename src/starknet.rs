@@ -0,0 +1,198 @@
+//! Serialization of proofs as arrays of Starknet field elements (felts) for
+//! verifiers written in Cairo e.g. to settle proofs on Starknet.
+//!
+//! Digests are written one felt per 32 bytes so commitments must be made with
+//! a hash over the Starknet field. Proofs for a Cairo verifier should use the
+//! [StarknetDigest] for commitments and the [StarknetPublicCoin] transcript.
+//! Both use the [Poseidon](crate::hash::Poseidon) instance of this crate
+//! rather than the Poseidon builtin of Starknet so a Cairo verifier has to
+//! implement that instance. The layout below is specific to this crate and
+//! isn't the layout of existing Cairo STARK verifiers.
+//!
+//! Sections are written in the order of the fields of [Proof]. Lists are
+//! prefixed with their length and written as follows:
+//! - field elements: the coefficients of each element over its base prime
+//!   field. The base prime field must be smaller than the Starknet field.
+//! - digests: one felt per 32 bytes prefixed with the number of felts
+//! - options, trace info, public inputs and salts: compressed and packed 31
+//!   bytes per felt the same way the [StarknetPublicCoin] absorbs them,
+//!   prefixed with the number of bytes
+//! - hash id and proof-of-work nonce: a single felt
+
+use crate::fri::FriProof;
+use crate::hash::pack_bytes;
+use crate::hash::PoseidonStark252;
+use crate::merkle::AlgebraicDigest;
+use crate::merkle::MerkleProof;
+use crate::merkle::Salt;
+use crate::random::SpongePublicCoin;
+use crate::Air;
+use crate::Error;
+use crate::LdtProof;
+use crate::Proof;
+use crate::VectorCommitmentProof;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ark_ff::BigInteger;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp as Stark252Fp;
+use gpu_poly::GpuField;
+
+/// Element of the Starknet field `p = 2^251 + 17·2^192 + 1`
+pub type Felt = Stark252Fp;
+
+/// Commitment hash of proofs for a Cairo verifier
+pub type StarknetDigest = AlgebraicDigest<PoseidonStark252>;
+
+/// Transcript of proofs for a Cairo verifier
+pub type StarknetPublicCoin = SpongePublicCoin<PoseidonStark252>;
+
+/// Number of bytes in a serialized felt
+const FELT_BYTES: usize = 32;
+
+/// Parts of a proof that can be written as felts
+pub trait ToFelts {
+    fn write_felts(&self, felts: &mut Vec<Felt>) -> Result<(), Error>;
+}
+
+impl ToFelts for MerkleProof {
+    fn write_felts(&self, felts: &mut Vec<Felt>) -> Result<(), Error> {
+        write_digests(self.as_bytes(), felts)
+    }
+}
+
+impl<F: GpuField + Field> ToFelts for FriProof<F>
+where
+    F::FftField: FftField,
+{
+    fn write_felts(&self, felts: &mut Vec<Felt>) -> Result<(), Error> {
+        write_len(self.layers().len(), felts);
+        for layer in self.layers() {
+            write_digests(layer.commitment(), felts)?;
+            write_elements(layer.values(), felts)?;
+            write_list(layer.proofs(), felts)?;
+        }
+        write_elements(self.remainder(), felts)?;
+        write_digests(self.remainder_commitment(), felts)
+    }
+}
+
+/// Serializes `proof` as felts (see the [module docs](self) for the layout)
+pub fn proof_to_felts<A: Air>(proof: &Proof<A>) -> Result<Vec<Felt>, Error>
+where
+    LdtProof<A>: ToFelts,
+    VectorCommitmentProof<A>: ToFelts,
+{
+    let mut felts = Vec::new();
+    write_serialized(&proof.options, &mut felts)?;
+    write_serialized(&proof.trace_info, &mut felts)?;
+    felts.push(Felt::from(proof.hash_id));
+    write_digests(&proof.base_trace_commitment, &mut felts)?;
+    write_len(proof.extension_trace_commitments.len(), &mut felts);
+    for commitment in &proof.extension_trace_commitments {
+        write_digests(commitment, &mut felts)?;
+    }
+    write_digests(&proof.composition_trace_commitment, &mut felts)?;
+    proof.fri_proof.write_felts(&mut felts)?;
+    felts.push(Felt::from(proof.pow_nonce));
+
+    let queries = &proof.trace_queries;
+    write_elements(&queries.base_trace_values, &mut felts)?;
+    write_elements(&queries.extension_trace_values, &mut felts)?;
+    write_elements(&queries.composition_trace_values, &mut felts)?;
+    write_elements(&queries.preprocessed_trace_values, &mut felts)?;
+    write_list(&queries.base_trace_proofs, &mut felts)?;
+    write_len(queries.extension_trace_proofs.len(), &mut felts);
+    for proofs in &queries.extension_trace_proofs {
+        write_list(proofs, &mut felts)?;
+    }
+    write_list(&queries.composition_trace_proofs, &mut felts)?;
+    write_list(&queries.preprocessed_trace_proofs, &mut felts)?;
+    write_salts(&queries.base_trace_salts, &mut felts);
+    write_len(queries.extension_trace_salts.len(), &mut felts);
+    for salts in &queries.extension_trace_salts {
+        write_salts(salts, &mut felts);
+    }
+    write_salts(&queries.composition_trace_salts, &mut felts);
+
+    write_serialized(&proof.public_inputs, &mut felts)?;
+    write_elements(&proof.execution_trace_ood_evals, &mut felts)?;
+    write_elements(&proof.composition_trace_ood_evals, &mut felts)?;
+    Ok(felts)
+}
+
+fn write_len(len: usize, felts: &mut Vec<Felt>) {
+    felts.push(Felt::from(len as u64));
+}
+
+fn write_list(items: &[impl ToFelts], felts: &mut Vec<Felt>) -> Result<(), Error> {
+    write_len(items.len(), felts);
+    items.iter().try_for_each(|item| item.write_felts(felts))
+}
+
+fn write_bytes(bytes: &[u8], felts: &mut Vec<Felt>) {
+    write_len(bytes.len(), felts);
+    felts.extend(pack_bytes::<Felt>(bytes));
+}
+
+fn write_serialized(item: &impl CanonicalSerialize, felts: &mut Vec<Felt>) -> Result<(), Error> {
+    let mut bytes = Vec::with_capacity(item.compressed_size());
+    item.serialize_compressed(&mut bytes)?;
+    write_bytes(&bytes, felts);
+    Ok(())
+}
+
+fn write_salts(salts: &[Salt], felts: &mut Vec<Felt>) {
+    write_len(salts.len(), felts);
+    for salt in salts {
+        write_bytes(salt, felts);
+    }
+}
+
+/// Writes concatenated digests one felt per 32 bytes. Fails unless every 32
+/// bytes are a serialized felt i.e. unless the digests are made by a hash
+/// over the Starknet field such as [StarknetDigest].
+fn write_digests(bytes: &[u8], felts: &mut Vec<Felt>) -> Result<(), Error> {
+    let not_felts = || Error::Serialization {
+        message: "digests must be made by a hash over the Starknet field".to_string(),
+    };
+    if bytes.len() % FELT_BYTES != 0 {
+        return Err(not_felts());
+    }
+    write_len(bytes.len() / FELT_BYTES, felts);
+    for word in bytes.chunks(FELT_BYTES) {
+        felts.push(Felt::deserialize_compressed(word).map_err(|_| not_felts())?);
+    }
+    Ok(())
+}
+
+/// Writes the coefficients of each element over its base prime field
+fn write_elements<F: Field>(elements: &[F], felts: &mut Vec<Felt>) -> Result<(), Error> {
+    write_len(elements.len(), felts);
+    for element in elements {
+        for coeff in element.to_base_prime_field_elements() {
+            felts.push(to_felt(coeff)?);
+        }
+    }
+    Ok(())
+}
+
+/// Felt with the same integer value as `value`. Fails if the value is larger
+/// than the Starknet field.
+fn to_felt<F: PrimeField>(value: F) -> Result<Felt, Error> {
+    let too_large = || Error::Serialization {
+        message: "field element is larger than the Starknet field".to_string(),
+    };
+    let bytes = value.into_bigint().to_bytes_le();
+    let (low, high) = bytes.split_at(bytes.len().min(FELT_BYTES));
+    if high.iter().any(|&byte| byte != 0) {
+        return Err(too_large());
+    }
+    let mut word = [0; FELT_BYTES];
+    word[..low.len()].copy_from_slice(low);
+    Felt::deserialize_compressed(&word[..]).map_err(|_| too_large())
+}
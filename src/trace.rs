@@ -1,88 +1,325 @@
 use crate::challenges::Challenges;
-use crate::merkle::MerkleProof;
-use crate::merkle::MerkleTree;
+use crate::commitment::VectorCommitment;
+use crate::merkle::LeafSalts;
+use crate::merkle::Salt;
 use crate::Air;
+use crate::Error;
 use crate::Matrix;
+use crate::VectorCommitmentProof;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
+use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::PrimeField;
 use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use ark_std::rand::Rng;
+use core::fmt::Display;
+use core::fmt::Formatter;
 use core::ops::Range;
-use digest::Digest;
+use gpu_poly::prelude::*;
+use gpu_poly::GpuFftField;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct Queries<A: Air> {
     pub base_trace_values: Vec<A::Fp>,
+    /// Rows of all extension segments. Segments are concatenated in order.
     pub extension_trace_values: Vec<A::Fq>,
     pub composition_trace_values: Vec<A::Fq>,
-    pub base_trace_proofs: Vec<MerkleProof>,
-    pub extension_trace_proofs: Vec<MerkleProof>,
-    pub composition_trace_proofs: Vec<MerkleProof>,
+    /// Rows of the preprocessed trace. Empty if there are no preprocessed
+    /// columns.
+    pub preprocessed_trace_values: Vec<A::Fp>,
+    pub base_trace_proofs: Vec<VectorCommitmentProof<A>>,
+    /// Openings of each extension segment commitment
+    pub extension_trace_proofs: Vec<Vec<VectorCommitmentProof<A>>>,
+    pub composition_trace_proofs: Vec<VectorCommitmentProof<A>>,
+    pub preprocessed_trace_proofs: Vec<VectorCommitmentProof<A>>,
+    /// Salts of the opened leaves. Empty unless `ProofOptions::zk` is set.
+    pub base_trace_salts: Vec<Salt>,
+    pub extension_trace_salts: Vec<Vec<Salt>>,
+    pub composition_trace_salts: Vec<Salt>,
 }
 
 impl<A: Air> Queries<A> {
+    /// Opens the commitments at `positions`. `leaf_salts` are the salts of
+    /// the leaves of each commitment in the order they were committed (base
+    /// trace, extension segments then composition trace) if they're salted.
+    /// The preprocessed trace is never salted.
     #[allow(clippy::too_many_arguments)]
-    pub fn new<D: Digest>(
+    pub fn new(
         air: &A,
+        preprocessed_trace: Option<&PreprocessedTrace<A>>,
         base_trace_polys: &Matrix<A::Fp>,
         extension_trace_polys: Option<&Matrix<A::Fq>>,
         composition_trace_lde: &Matrix<A::Fq>,
-        base_commitment: MerkleTree<D>,
-        extension_commitment: Option<MerkleTree<D>>,
-        composition_commitment: MerkleTree<D>,
+        base_commitment: A::VectorCommitment,
+        extension_commitments: Vec<A::VectorCommitment>,
+        composition_commitment: A::VectorCommitment,
+        leaf_salts: Option<Vec<LeafSalts>>,
         positions: &[usize],
     ) -> Self {
+        let num_segments = extension_commitments.len();
+        if let Some(salts) = &leaf_salts {
+            assert_eq!(num_segments + 2, salts.len());
+        }
+        let salts_of = |i: usize| leaf_salts.as_ref().map(|salts| &salts[i]);
         let lde_xs = air.lde_domain();
         let mut base_trace_values = Vec::new();
         let mut extension_trace_values = Vec::new();
         let mut composition_trace_values = Vec::new();
+        let mut preprocessed_trace_values = Vec::new();
         let mut base_trace_proofs = Vec::new();
-        let mut extension_trace_proofs = Vec::new();
+        let mut extension_trace_proofs = vec![Vec::new(); num_segments];
         let mut composition_trace_proofs = Vec::new();
+        let mut preprocessed_trace_proofs = Vec::new();
+        let mut base_trace_salts = Vec::new();
+        let mut extension_trace_salts = vec![Vec::new(); num_segments];
+        let mut composition_trace_salts = Vec::new();
         for &position in positions {
             // execution trace
             let lde_x = lde_xs.element(position);
             let base_trace_row = base_trace_polys.evaluate_at(lde_x);
             base_trace_values.extend(base_trace_row);
-            let base_proof = base_commitment.prove(position).unwrap();
-            base_trace_proofs.push(base_proof);
+            base_trace_proofs.push(base_commitment.open(position));
+            base_trace_salts.extend(salts_of(0).map(|salts| salts.salt(position)));
 
             if let Some(extension_trace_polys) = extension_trace_polys {
                 // TODO: suport ark DomainCoeff on evaluate_at
                 let extension_trace_row = extension_trace_polys.evaluate_at(A::Fq::from(lde_x));
                 extension_trace_values.extend(extension_trace_row);
-                let extension_proof = extension_commitment
-                    .as_ref()
-                    .unwrap()
-                    .prove(position)
-                    .unwrap();
-                extension_trace_proofs.push(extension_proof);
+            }
+
+            for (segment, commitment) in extension_commitments.iter().enumerate() {
+                extension_trace_proofs[segment].push(commitment.open(position));
+                extension_trace_salts[segment]
+                    .extend(salts_of(segment + 1).map(|salts| salts.salt(position)));
             }
 
             // composition trace
             let composition_trace_row = composition_trace_lde.get_row(position).unwrap();
             composition_trace_values.extend(composition_trace_row);
-            let composition_proof = composition_commitment.prove(position).unwrap();
-            composition_trace_proofs.push(composition_proof);
+            composition_trace_proofs.push(composition_commitment.open(position));
+            composition_trace_salts
+                .extend(salts_of(num_segments + 1).map(|salts| salts.salt(position)));
+
+            // preprocessed trace
+            if let Some(preprocessed_trace) = preprocessed_trace {
+                let preprocessed_trace_row = preprocessed_trace.lde.get_row(position).unwrap();
+                preprocessed_trace_values.extend(preprocessed_trace_row);
+                preprocessed_trace_proofs.push(preprocessed_trace.open(position));
+            }
         }
         Queries {
             base_trace_values,
             extension_trace_values,
             composition_trace_values,
+            preprocessed_trace_values,
             base_trace_proofs,
             extension_trace_proofs,
             composition_trace_proofs,
+            preprocessed_trace_proofs,
+            base_trace_salts,
+            extension_trace_salts,
+            composition_trace_salts,
+        }
+    }
+}
+
+/// Columns fixed by the AIR rather than the witness e.g. instruction decodings
+/// or hash round constants. Committed to once and reused by every proof that
+/// references it. Verifiers take the commitment as a public parameter rather
+/// than reading it from the proof (see `Proof::verify_with_preprocessed`).
+pub struct PreprocessedTrace<A: Air> {
+    pub polys: Matrix<A::Fp>,
+    /// Evaluations of `polys` over the LDE domain
+    pub lde: Matrix<A::Fp>,
+    commitment: A::VectorCommitment,
+}
+
+impl<A: Air> PreprocessedTrace<A> {
+    /// Commits to `columns` over the LDE domain of `air`. The commitment only
+    /// depends on the columns and the proof options so `air` can have any
+    /// public inputs.
+    pub fn new(air: &A, columns: &Matrix<A::Fp>) -> Self {
+        assert_eq!(
+            air.trace_len(),
+            columns.num_rows(),
+            "preprocessed columns must have one row per trace row"
+        );
+        let polys = columns.interpolate(air.trace_domain());
+        let lde = polys.evaluate(air.lde_domain());
+        let leaves = lde.hash_rows::<A::Digest, A::LeafEncoder>();
+        let commitment = A::VectorCommitment::commit(leaves, air.options());
+        PreprocessedTrace {
+            polys,
+            lde,
+            commitment,
+        }
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.polys.num_cols()
+    }
+
+    /// Commitment the verifier must be given to check proofs
+    pub fn commitment(&self) -> Vec<u8> {
+        self.commitment.commitment()
+    }
+
+    pub fn open(&self, position: usize) -> VectorCommitmentProof<A> {
+        self.commitment.open(position)
+    }
+}
+
+/// How the rows after the end of a computation are filled so the trace has a
+/// power of two number of rows. The strategy and the number of padding rows
+/// are part of [TraceInfo] so the prover and verifier agree on which rows are
+/// padding and how they are constrained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Padding {
+    /// Padding rows repeat the last row of the computation. Constraints must
+    /// hold between equal rows e.g. a halted machine that stays halted.
+    #[default]
+    RepeatLastRow,
+    /// Padding rows are the halt state given by `Air::halt_state`. The first
+    /// padding row is asserted to be the halt state so transition constraints
+    /// must keep the trace in the halt state.
+    HaltState,
+    /// Padding rows are zero. Transition constraints exempt the padding rows
+    /// so nothing is constrained after the last row of the computation.
+    ZeroWithExemptions,
+}
+
+impl Padding {
+    fn id(&self) -> u8 {
+        match self {
+            Padding::RepeatLastRow => 0,
+            Padding::HaltState => 1,
+            Padding::ZeroWithExemptions => 2,
         }
     }
 }
 
+impl CanonicalSerialize for Padding {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.id().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.id().serialized_size(compress)
+    }
+}
+
+impl Valid for Padding {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Padding {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(
+            match u8::deserialize_with_mode(reader, compress, validate)? {
+                0 => Padding::RepeatLastRow,
+                1 => Padding::HaltState,
+                2 => Padding::ZeroWithExemptions,
+                _ => return Err(SerializationError::InvalidData),
+            },
+        )
+    }
+}
+
+/// Semantic version of an arithmetization. Proofs record the version of the
+/// AIR they were generated for (see `Air::VERSION`) and verifiers reject
+/// proofs for incompatible versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AirVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl AirVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        AirVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Returns true if proofs for version `self` can be verified by an AIR at
+    /// version `other`. Versions are compatible if their major versions match
+    /// (and their minor versions match while the major version is zero).
+    pub fn is_compatible_with(&self, other: &AirVersion) -> bool {
+        self.major == other.major && (self.major != 0 || self.minor == other.minor)
+    }
+}
+
+impl Display for AirVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
 /// Public metadata about a trace.
 #[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct TraceInfo {
     pub num_base_columns: usize,
     pub num_extension_columns: usize,
+    /// Number of columns in each segment of base columns. Segments can have
+    /// fewer rows than the trace e.g. for co-processors that run for a fraction
+    /// of the cycles of the main trace.
+    pub base_segment_widths: Vec<usize>,
+    /// Number of rows in each segment of base columns
+    pub base_segment_lengths: Vec<usize>,
+    /// Number of columns in each extension segment. Segments are built and
+    /// committed to one after the other so later segments can depend on
+    /// challenges drawn after earlier segments are committed.
+    pub extension_segment_widths: Vec<usize>,
+    /// Number of columns fixed by the AIR rather than the witness. These are
+    /// committed to once ahead of time (see [PreprocessedTrace]) and follow
+    /// the extension columns.
+    pub num_preprocessed_columns: usize,
+    /// Number of rows at the end of the trace (and of each base segment) that
+    /// are filled with random values if `ProofOptions::zk` is set so opened
+    /// values don't reveal the witness. Constraints must not apply to these
+    /// rows. Transition constraints exempt them automatically if zk is set.
+    /// Must match `Air::num_randomizer_rows`.
+    pub num_randomizer_rows: usize,
+    /// How the last `num_padding_rows` rows of the trace are filled. Must
+    /// match `Air::padding`.
+    pub padding: Padding,
+    /// Number of rows at the end of the trace after the last row of the
+    /// computation. Randomizer rows overwrite the last of these.
+    pub num_padding_rows: usize,
+    /// Names of the columns in order (base, extension then preprocessed).
+    /// Empty if columns are unnamed.
+    pub column_names: Vec<String>,
+    /// Labels of the base segments followed by the labels of the extension
+    /// segments. Empty if segments are unlabelled.
+    pub segment_labels: Vec<String>,
+    /// Version of the AIR the trace was generated for
+    pub air_version: AirVersion,
     pub trace_len: usize,
     // TODO: want to change this to auxiliary data
     pub meta: Vec<u8>,
@@ -96,27 +333,283 @@ impl TraceInfo {
     pub const MAX_TRACE_WIDTH: usize = 255;
     /// Maximum number of bytes in trace metadata; currently set at 64KiB.
     pub const MAX_META_BYTES: usize = 65535;
+    /// Maximum number of bytes in all column names and segment labels;
+    /// currently set at 64KiB.
+    pub const MAX_NAME_BYTES: usize = 65535;
 
+    /// Panics if the trace is invalid. See [TraceInfo::try_new].
     pub fn new(
         num_base_columns: usize,
         num_extension_columns: usize,
         trace_len: usize,
         meta: Option<Vec<u8>>,
     ) -> Self {
-        let num_total_cols = num_base_columns + num_extension_columns;
-        let meta = meta.unwrap_or_default();
-        assert!(num_base_columns > 0, "not enough base columns");
-        assert!(num_total_cols <= Self::MAX_TRACE_WIDTH, "too many columns");
-        assert!(meta.len() <= Self::MAX_META_BYTES, "too much meta data");
-        assert!(trace_len >= Self::MIN_TRACE_LENGTH, "trace too small");
-        TraceInfo {
+        Self::try_new(num_base_columns, num_extension_columns, trace_len, meta)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Returns an [Error] if the trace is too short, too wide, has no base
+    /// columns or has too much metadata
+    pub fn try_new(
+        num_base_columns: usize,
+        num_extension_columns: usize,
+        trace_len: usize,
+        meta: Option<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let extension_segment_widths = if num_extension_columns == 0 {
+            Vec::new()
+        } else {
+            vec![num_extension_columns]
+        };
+        let trace_info = TraceInfo {
             num_base_columns,
             num_extension_columns,
+            base_segment_widths: vec![num_base_columns],
+            base_segment_lengths: vec![trace_len],
+            extension_segment_widths,
+            num_preprocessed_columns: 0,
+            num_randomizer_rows: 0,
+            padding: Padding::default(),
+            num_padding_rows: 0,
+            column_names: Vec::new(),
+            segment_labels: Vec::new(),
+            air_version: AirVersion::default(),
             trace_len,
-            meta,
+            meta: meta.unwrap_or_default(),
+        };
+        trace_info.validate()?;
+        Ok(trace_info)
+    }
+
+    /// Checks the trace info is consistent e.g. when it's deserialized from
+    /// a proof rather than built with the constructors
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: &str| {
+            let reason = reason.to_string();
+            Err(Error::InvalidTraceInfo { reason })
+        };
+        if self.num_base_columns == 0 {
+            return invalid("not enough base columns");
+        }
+        let num_total_cols = self
+            .num_base_columns
+            .saturating_add(self.num_extension_columns)
+            .saturating_add(self.num_preprocessed_columns);
+        if num_total_cols > Self::MAX_TRACE_WIDTH {
+            return Err(Error::TooManyColumns {
+                num_columns: num_total_cols,
+                max: Self::MAX_TRACE_WIDTH,
+            });
+        }
+        if self.meta.len() > Self::MAX_META_BYTES {
+            return invalid("too much meta data");
+        }
+        let trace_len = self.trace_len;
+        if trace_len < Self::MIN_TRACE_LENGTH {
+            return Err(Error::TraceTooShort {
+                len: trace_len,
+                min: Self::MIN_TRACE_LENGTH,
+            });
+        }
+        if !trace_len.is_power_of_two() {
+            return Err(Error::TraceLengthNotPowerOfTwo { len: trace_len });
+        }
+        let base_segment_widths = &self.base_segment_widths;
+        let base_segment_lengths = &self.base_segment_lengths;
+        if base_segment_widths.is_empty() || base_segment_widths.len() != base_segment_lengths.len()
+        {
+            return invalid("base segment widths and lengths don't match");
+        }
+        if checked_sum(base_segment_widths) != Some(self.num_base_columns)
+            || base_segment_widths.contains(&0)
+        {
+            return invalid("segment widths don't match the number of base columns");
+        }
+        if base_segment_lengths
+            .iter()
+            .any(|len| !len.is_power_of_two() || *len > trace_len)
+        {
+            return invalid("base segment length is invalid");
+        }
+        let extension_segment_widths = &self.extension_segment_widths;
+        if checked_sum(extension_segment_widths) != Some(self.num_extension_columns)
+            || extension_segment_widths.contains(&0)
+        {
+            return invalid("segment widths don't match the number of extension columns");
+        }
+        let min_segment_len = base_segment_lengths.iter().copied().min().unwrap();
+        if self.num_randomizer_rows >= min_segment_len {
+            return invalid("randomizer rows don't fit in a segment");
+        }
+        if self.num_padding_rows >= trace_len {
+            return invalid("all rows are padding");
+        }
+        let num_segments = base_segment_widths.len() + self.num_extension_segments();
+        if !self.segment_labels.is_empty() && self.segment_labels.len() != num_segments {
+            return invalid("expected a label for each segment");
+        }
+        if self.column_names.len() > num_total_cols {
+            return invalid("more column names than columns");
+        }
+        if names_len(&self.column_names, &self.segment_labels) > Self::MAX_NAME_BYTES {
+            return invalid("column names and segment labels are too long");
+        }
+        Ok(())
+    }
+
+    /// Reserves the last `num_rows` rows of the trace for randomizers (see
+    /// [TraceInfo::num_randomizer_rows])
+    pub fn with_randomizer_rows(mut self, num_rows: usize) -> Self {
+        let min_segment_len = self.base_segment_lengths.iter().copied().min().unwrap();
+        assert!(
+            num_rows < min_segment_len,
+            "{num_rows} randomizer rows don't fit in a segment with {min_segment_len} rows"
+        );
+        self.num_randomizer_rows = num_rows;
+        self
+    }
+
+    /// Declares the last `num_rows` rows of the trace as padding filled using
+    /// `padding` (see [pad_rows])
+    pub fn with_padding(mut self, padding: Padding, num_rows: usize) -> Self {
+        assert!(num_rows < self.trace_len, "all rows are padding");
+        self.padding = padding;
+        self.num_padding_rows = num_rows;
+        self
+    }
+
+    /// Names the columns of the trace in order i.e. column `i` is named
+    /// `names[i]`. Names are used in debug output (see
+    /// [TraceInfo::column_name]).
+    pub fn with_column_names(mut self, names: Vec<String>) -> Self {
+        assert!(
+            names_len(&names, &self.segment_labels) <= Self::MAX_NAME_BYTES,
+            "column names are too long"
+        );
+        self.column_names = names;
+        self
+    }
+
+    /// Labels the base segments followed by the extension segments
+    pub fn with_segment_labels(mut self, labels: Vec<String>) -> Self {
+        let num_segments = self.base_segment_widths.len() + self.num_extension_segments();
+        assert_eq!(
+            num_segments,
+            labels.len(),
+            "expected a label for each segment"
+        );
+        assert!(
+            names_len(&self.column_names, &labels) <= Self::MAX_NAME_BYTES,
+            "segment labels are too long"
+        );
+        self.segment_labels = labels;
+        self
+    }
+
+    /// Records the version of the AIR the trace is generated for
+    pub fn with_air_version(mut self, version: AirVersion) -> Self {
+        self.air_version = version;
+        self
+    }
+
+    /// Name of column `column` or `Trace(column)` if the column is unnamed
+    pub fn column_name(&self, column: usize) -> String {
+        match self.column_names.get(column) {
+            Some(name) => name.clone(),
+            None => format!("Trace({column})"),
         }
     }
 
+    /// Adds `num_columns` preprocessed columns to the trace
+    pub fn with_preprocessed_columns(mut self, num_columns: usize) -> Self {
+        let num_total_cols = self.num_base_columns + self.num_extension_columns + num_columns;
+        assert!(num_total_cols <= Self::MAX_TRACE_WIDTH, "too many columns");
+        self.num_preprocessed_columns = num_columns;
+        self
+    }
+
+    /// Splits the base columns into segments of `(width, len)` columns and
+    /// rows. Lengths must be powers of two no larger than the trace length.
+    /// Row `i` of a segment with `len` rows is row `i * trace_len / len` of
+    /// the trace so the next row of a segment is referenced at offset
+    /// `trace_len / len` (see [TraceInfo::base_segment_stride]). By default
+    /// all base columns belong to a single segment with `trace_len` rows.
+    pub fn with_base_segments(mut self, segments: &[(usize, usize)]) -> Self {
+        let (widths, lengths): (Vec<usize>, Vec<usize>) = segments.iter().copied().unzip();
+        assert_eq!(
+            self.num_base_columns,
+            widths.iter().sum::<usize>(),
+            "segment widths don't match the number of base columns"
+        );
+        assert!(widths.iter().all(|&width| width > 0), "empty base segment");
+        for len in &lengths {
+            assert!(
+                len.is_power_of_two(),
+                "segment length {len} is not a power of two"
+            );
+            assert!(
+                *len <= self.trace_len,
+                "segment has more rows than the trace"
+            );
+        }
+        self.base_segment_widths = widths;
+        self.base_segment_lengths = lengths;
+        self
+    }
+
+    /// Ranges of the columns of each base segment
+    pub fn base_segment_ranges(&self) -> Vec<Range<usize>> {
+        let mut start = 0;
+        self.base_segment_widths
+            .iter()
+            .map(|width| {
+                let range = start..start + width;
+                start = range.end;
+                range
+            })
+            .collect()
+    }
+
+    /// Number of trace rows between consecutive rows of base segment
+    /// `segment`
+    pub fn base_segment_stride(&self, segment: usize) -> usize {
+        self.trace_len / self.base_segment_lengths[segment]
+    }
+
+    /// Splits the extension columns into segments of the given widths. By
+    /// default all extension columns belong to a single segment.
+    pub fn with_extension_segments(mut self, widths: Vec<usize>) -> Self {
+        assert_eq!(
+            self.num_extension_columns,
+            widths.iter().sum::<usize>(),
+            "segment widths don't match the number of extension columns"
+        );
+        assert!(
+            widths.iter().all(|&width| width > 0),
+            "empty extension segment"
+        );
+        self.extension_segment_widths = widths;
+        self
+    }
+
+    pub fn num_extension_segments(&self) -> usize {
+        self.extension_segment_widths.len()
+    }
+
+    /// Ranges of the columns of each extension segment relative to the first
+    /// extension column.
+    pub fn extension_segment_ranges(&self) -> Vec<Range<usize>> {
+        let mut start = 0;
+        self.extension_segment_widths
+            .iter()
+            .map(|width| {
+                let range = start..start + width;
+                start = range.end;
+                range
+            })
+            .collect()
+    }
+
     pub fn base_columns_range(&self) -> Range<usize> {
         0..self.num_base_columns
     }
@@ -124,6 +617,170 @@ impl TraceInfo {
     pub fn extension_columns_range(&self) -> Range<usize> {
         self.num_base_columns..self.num_base_columns + self.num_extension_columns
     }
+
+    pub fn preprocessed_columns_range(&self) -> Range<usize> {
+        let start = self.extension_columns_range().end;
+        start..start + self.num_preprocessed_columns
+    }
+
+    /// Number of columns (base, extension and preprocessed) in the trace
+    pub fn num_columns(&self) -> usize {
+        self.preprocessed_columns_range().end
+    }
+}
+
+/// Sum of `values` or `None` if it overflows
+fn checked_sum(values: &[usize]) -> Option<usize> {
+    values
+        .iter()
+        .try_fold(0usize, |sum, &value| sum.checked_add(value))
+}
+
+/// Total number of bytes in `column_names` and `segment_labels`
+fn names_len(column_names: &[String], segment_labels: &[String]) -> usize {
+    column_names
+        .iter()
+        .chain(segment_labels)
+        .map(String::len)
+        .fold(0, usize::saturating_add)
+}
+
+/// Interpolates the columns of each base segment over the subgroup with one
+/// element per row of the segment. Coefficients are padded to the trace length
+/// so all polynomials are over the trace domain and a column of a segment with
+/// stride `k` evaluates to row `i` of the column at row `i * k` of the trace.
+pub fn interpolate_base_segments<F: GpuFftField<FftField = F> + FftField>(
+    trace_info: &TraceInfo,
+    base_trace: &Matrix<F>,
+) -> Matrix<F> {
+    let trace_len = trace_info.trace_len;
+    let segment_ranges = trace_info.base_segment_ranges();
+    let mut polys = Vec::with_capacity(trace_info.num_base_columns);
+    for (range, &len) in segment_ranges
+        .into_iter()
+        .zip(&trace_info.base_segment_lengths)
+    {
+        let segment = Matrix::new(
+            base_trace.0[range]
+                .iter()
+                .map(|column| column.to_vec_in(PageAlignedAllocator))
+                .collect(),
+        );
+        assert_eq!(
+            len,
+            segment.num_rows(),
+            "segment has the wrong number of rows"
+        );
+        let domain = Radix2EvaluationDomain::new(len).unwrap();
+        for mut column in segment.into_polynomials(domain).0 {
+            column.resize(trace_len, F::zero());
+            polys.push(column);
+        }
+    }
+    Matrix::new(polys)
+}
+
+/// Overwrites the last `num_rows` rows of each column with random values
+/// (see [TraceInfo::num_randomizer_rows])
+pub fn randomize_rows<F: Field, R: Rng + ?Sized>(
+    trace: &mut Matrix<F>,
+    num_rows: usize,
+    rng: &mut R,
+) {
+    for column in &mut trace.0 {
+        let n = column.len();
+        for value in &mut column[n - num_rows..] {
+            *value = F::rand(rng);
+        }
+    }
+}
+
+/// Fills the last `num_rows` rows of each column using `padding`. `halt_state`
+/// has a value for each column and is only used by [Padding::HaltState].
+pub fn pad_rows<F: Field>(
+    trace: &mut Matrix<F>,
+    num_rows: usize,
+    padding: Padding,
+    halt_state: &[F],
+) {
+    for (i, column) in trace.0.iter_mut().enumerate() {
+        let n = column.len();
+        let value = match padding {
+            Padding::RepeatLastRow => column[n - num_rows - 1],
+            Padding::HaltState => halt_state[i],
+            Padding::ZeroWithExemptions => F::zero(),
+        };
+        column[n - num_rows..].fill(value);
+    }
+}
+
+/// Builds the columns of a trace from its rows. The built trace is padded to a
+/// power of two number of rows and at least [TraceInfo::MIN_TRACE_LENGTH].
+#[derive(Clone, Copy, Debug)]
+pub struct TraceBuilder {
+    num_columns: usize,
+    num_rows: usize,
+}
+
+impl TraceBuilder {
+    pub fn new(num_columns: usize, num_rows: usize) -> Self {
+        assert!(num_columns > 0, "trace must have at least one column");
+        assert!(num_rows > 0, "trace must have at least one row");
+        TraceBuilder {
+            num_columns,
+            num_rows,
+        }
+    }
+
+    /// Number of rows of the built trace
+    pub fn padded_len(&self) -> usize {
+        self.num_rows
+            .next_power_of_two()
+            .max(TraceInfo::MIN_TRACE_LENGTH)
+    }
+
+    /// Builds the trace by repeatedly applying `transition` to the previous
+    /// row starting at `first_row`. Rows are inherently sequential so this
+    /// isn't parallelised. Padding rows keep applying the transition so
+    /// transition constraints hold on the padding as well.
+    pub fn from_transition<F: Field>(
+        &self,
+        first_row: Vec<F>,
+        transition: impl Fn(&[F]) -> Vec<F>,
+    ) -> Matrix<F> {
+        let n = self.padded_len();
+        let mut columns = (0..self.num_columns)
+            .map(|_| Vec::with_capacity_in(n, PageAlignedAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        let mut row = first_row;
+        for i in 0..n {
+            assert_eq!(self.num_columns, row.len(), "row {i} has the wrong width");
+            for (column, value) in columns.iter_mut().zip(&row) {
+                column.push(*value);
+            }
+            if i + 1 != n {
+                row = transition(&row);
+            }
+        }
+        Matrix::new(columns)
+    }
+
+    /// Builds the trace by calling `fill(i, row)` for every row `i` in
+    /// parallel. Padding rows are copies of the last row.
+    pub fn fill<F: Field>(&self, fill: impl Fn(usize, &mut [F]) + Send + Sync) -> Matrix<F> {
+        let n = self.padded_len();
+        let width = self.num_columns;
+        let mut rows = vec![F::zero(); self.num_rows * width];
+        ark_std::cfg_chunks_mut!(rows, width)
+            .enumerate()
+            .for_each(|(i, row)| fill(i, row));
+        let mut trace = Matrix::from_row_major(&rows, width);
+        for column in &mut trace.0 {
+            let last = *column.last().unwrap();
+            column.resize(n, last);
+        }
+        trace
+    }
 }
 
 // TODO: docs: An execution trace of a computation, or the trace in short, is a
@@ -135,9 +792,16 @@ pub trait Trace {
     type Fp: PrimeField;
     type Fq: Field<BasePrimeField = Self::Fp>;
 
-    /// Returns the number of rows in this trace.
+    /// Returns the number of rows in this trace i.e. the number of rows of
+    /// the longest base column
     fn len(&self) -> usize {
-        self.base_columns().num_rows()
+        self.base_columns().max_num_rows()
+    }
+
+    /// Returns the `(width, len)` of each segment of base columns. By default
+    /// all base columns have one row per trace row.
+    fn base_segments(&self) -> Vec<(usize, usize)> {
+        vec![(Self::NUM_BASE_COLUMNS, self.len())]
     }
 
     /// Returns a reference to the base trace columns.
@@ -153,14 +817,65 @@ pub trait Trace {
         None
     }
 
+    /// Returns the number of columns in each extension segment. By default
+    /// all extension columns are built in a single segment.
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        if Self::NUM_EXTENSION_COLUMNS == 0 {
+            Vec::new()
+        } else {
+            vec![Self::NUM_EXTENSION_COLUMNS]
+        }
+    }
+
+    /// Builds extension segment `segment` once the preceding segments have
+    /// been committed to. `challenges` includes the challenges drawn for all
+    /// segments up to and including this one (see
+    /// [Air::num_segment_challenges]). Defaults to
+    /// [Self::build_extension_columns] for the first segment.
+    fn build_aux_segment(
+        &self,
+        segment: usize,
+        _prev_segments: &[Matrix<Self::Fq>],
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        if segment == 0 {
+            self.build_extension_columns(challenges)
+        } else {
+            None
+        }
+    }
+
     /// Returns trace info for this trace.
     fn info(&self) -> TraceInfo {
+        let (padding, num_padding_rows) = self.padding();
         TraceInfo::new(
             Self::NUM_BASE_COLUMNS,
             Self::NUM_EXTENSION_COLUMNS,
             self.len(),
             self.meta().map(|meta| meta.to_vec()),
         )
+        .with_base_segments(&self.base_segments())
+        .with_extension_segments(self.extension_segment_widths())
+        .with_randomizer_rows(self.num_randomizer_rows())
+        .with_padding(padding, num_padding_rows)
+    }
+
+    /// Number of rows at the end of the trace reserved for randomizers. See
+    /// [TraceInfo::num_randomizer_rows].
+    fn num_randomizer_rows(&self) -> usize {
+        0
+    }
+
+    /// Returns how the trace is padded and the number of padding rows at the
+    /// end of the trace (see [Padding])
+    fn padding(&self) -> (Padding, usize) {
+        (Padding::default(), 0)
+    }
+
+    /// Returns a copy of `len` rows of the base columns starting at row `start`
+    /// (see [Matrix::slice_rows])
+    fn window(&self, start: usize, len: usize) -> Matrix<Self::Fp> {
+        self.base_columns().slice_rows(start..start + len)
     }
 
     /// Returns metadata associated with this trace.
@@ -172,3 +887,64 @@ pub trait Trace {
         self.len() == 0
     }
 }
+
+/// A trace of base columns built from the rows of each step of a computation
+/// (see [StepTrace::from_step_iterator]). Step traces can be serialized as a
+/// checkpoint and proven later without running the computation again.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct StepTrace<F: PrimeField, const N: usize> {
+    base_columns: Matrix<F>,
+    padding: Padding,
+    num_padding_rows: usize,
+}
+
+impl<F: PrimeField, const N: usize> StepTrace<F, N> {
+    /// Builds a trace from the `N` values of each step produced by `steps`.
+    /// Steps are written straight into page aligned columns without an
+    /// intermediate copy of the rows but the whole trace is still built
+    /// before proving: interpolating a column needs all of its rows so the
+    /// LDE can't be computed from the steps as they arrive. To bound memory
+    /// enable disk spilling (see [gpu_poly::allocator::enable_disk_spilling])
+    /// so large columns are backed by disk and prove with
+    /// `Prover::streaming_lde` so the LDE is committed one coset at a time.
+    /// The trace is padded to a power of two (and at least
+    /// [TraceInfo::MIN_TRACE_LENGTH]) rows using `padding`. `halt_state` is
+    /// only used by [Padding::HaltState].
+    pub fn from_step_iterator<R: AsRef<[F]>>(
+        steps: impl IntoIterator<Item = R>,
+        padding: Padding,
+        halt_state: &[F],
+    ) -> Self {
+        assert!(N > 0, "trace must have at least one column");
+        let mut base_columns = Matrix::from_row_iter(steps, N);
+        let num_steps = base_columns.num_rows();
+        assert!(num_steps > 0, "computation has no steps");
+        let n = num_steps
+            .next_power_of_two()
+            .max(TraceInfo::MIN_TRACE_LENGTH);
+        for column in &mut base_columns.0 {
+            column.resize(n, F::zero());
+        }
+        let num_padding_rows = n - num_steps;
+        pad_rows(&mut base_columns, num_padding_rows, padding, halt_state);
+        StepTrace {
+            base_columns,
+            padding,
+            num_padding_rows,
+        }
+    }
+}
+
+impl<F: PrimeField, const N: usize> Trace for StepTrace<F, N> {
+    const NUM_BASE_COLUMNS: usize = N;
+    type Fp = F;
+    type Fq = F;
+
+    fn base_columns(&self) -> &Matrix<F> {
+        &self.base_columns
+    }
+
+    fn padding(&self) -> (Padding, usize) {
+        (self.padding, self.num_padding_rows)
+    }
+}
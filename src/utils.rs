@@ -1,8 +1,8 @@
+use crate::domain::StarkDomain;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
-use ark_poly::domain::Radix2EvaluationDomain;
-use ark_poly::EvaluationDomain;
+use ark_serialize::CanonicalSerialize;
 use core::ops::Add;
 use core::ops::AddAssign;
 use core::ops::Mul;
@@ -10,27 +10,6 @@ use gpu_poly::GpuVec;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-#[cfg(feature = "std")]
-pub struct Timer<'a> {
-    name: &'a str,
-    start: std::time::Instant,
-}
-
-#[cfg(feature = "std")]
-impl<'a> Timer<'a> {
-    pub fn new(name: &'a str) -> Timer<'a> {
-        let start = std::time::Instant::now();
-        Timer { name, start }
-    }
-}
-
-#[cfg(feature = "std")]
-impl<'a> Drop for Timer<'a> {
-    fn drop(&mut self) {
-        println!("{} in {:?}", self.name, self.start.elapsed());
-    }
-}
-
 pub fn interleave<T: Copy + Send + Sync + Default, const RADIX: usize>(
     source: &[T],
 ) -> Vec<[T; RADIX]> {
@@ -53,6 +32,48 @@ pub fn interleave<T: Copy + Send + Sync + Default, const RADIX: usize>(
 //     println!()
 // }
 
+/// Nanoseconds this process has spent waiting on the GPU (see [gpu_time])
+#[cfg(feature = "std")]
+static GPU_NANOS: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+
+/// Runs `f` which blocks until GPU work completes. The time spent counts
+/// towards [gpu_time].
+#[cfg(feature = "gpu")]
+pub fn wait_for_gpu<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "std")]
+    let start = std::time::Instant::now();
+    let res = f();
+    #[cfg(feature = "std")]
+    GPU_NANOS.fetch_add(
+        start.elapsed().as_nanos() as u64,
+        core::sync::atomic::Ordering::Relaxed,
+    );
+    res
+}
+
+/// Commits `command_buffer` and blocks until the GPU has executed it
+#[cfg(feature = "gpu")]
+pub fn commit_and_wait(command_buffer: &metal::CommandBufferRef) {
+    wait_for_gpu(|| {
+        command_buffer.commit();
+        command_buffer.wait_until_completed();
+    })
+}
+
+/// Total time this process has spent waiting on the GPU. Measured on the
+/// host so includes the latency of scheduling GPU work.
+#[cfg(feature = "std")]
+pub fn gpu_time() -> core::time::Duration {
+    let nanos = GPU_NANOS.load(core::sync::atomic::Ordering::Relaxed);
+    core::time::Duration::from_nanos(nanos)
+}
+
+/// Compressed serialized size of a `Vec` of `len` elements that are each
+/// `element_size` bytes when serialized
+pub fn serialized_vec_size(len: usize, element_size: usize) -> usize {
+    Vec::<u8>::new().compressed_size() + len * element_size
+}
+
 /// Rounds the input value up the the nearest power of two
 pub fn ceil_power_of_two(value: usize) -> usize {
     if value.is_power_of_two() {
@@ -64,8 +85,8 @@ pub fn ceil_power_of_two(value: usize) -> usize {
 
 // from arkworks
 /// This evaluates the vanishing polynomial for this domain at tau.
-pub fn evaluate_vanishing_polynomial<F: FftField, T: Field>(
-    domain: &Radix2EvaluationDomain<F>,
+pub fn evaluate_vanishing_polynomial<F: FftField, T: Field, D: StarkDomain<F>>(
+    domain: &D,
     tau: T,
 ) -> T
 where
@@ -76,10 +97,10 @@ where
 
 // Evaluates the vanishing polynomial for `vanish_domain` over `eval_domain`
 // E.g. evaluates `(x - v_0)(x - v_1)...(x - v_n-1)` over `eval_domain`
-pub fn fill_vanishing_polynomial<F: FftField>(
+pub fn fill_vanishing_polynomial<F: FftField, V: StarkDomain<F>, E: StarkDomain<F>>(
     dst: &mut [F],
-    vanish_domain: &Radix2EvaluationDomain<F>,
-    eval_domain: &Radix2EvaluationDomain<F>,
+    vanish_domain: &V,
+    eval_domain: &E,
 ) {
     let n = vanish_domain.size();
     let scaled_eval_offset = eval_domain.coset_offset().pow([n as u64]);
@@ -87,9 +108,9 @@ pub fn fill_vanishing_polynomial<F: FftField>(
     let scaled_vanish_offset = vanish_domain.coset_offset_pow_size();
 
     #[cfg(feature = "parallel")]
-    let chunk_size = core::cmp::max(n / rayon::current_num_threads(), 1024);
+    let chunk_size = core::cmp::max(dst.len() / rayon::current_num_threads(), 1024);
     #[cfg(not(feature = "parallel"))]
-    let chunk_size = n;
+    let chunk_size = core::cmp::max(dst.len(), 1);
 
     ark_std::cfg_chunks_mut!(dst, chunk_size)
         .enumerate()
@@ -102,6 +123,31 @@ pub fn fill_vanishing_polynomial<F: FftField>(
         });
 }
 
+/// Inverts all values in place on the GPU using Montgomery's trick.
+/// Values must be non-zero.
+#[cfg(feature = "gpu")]
+pub fn batch_inverse_gpu<F: gpu_poly::GpuField + Field>(values: &mut GpuVec<F>) {
+    use gpu_poly::prelude::*;
+    use gpu_poly::stage::BatchInverseStage;
+    if values.is_empty() {
+        return;
+    }
+    let n = values.len();
+    let library = &planner().library;
+    let command_queue = &planner().command_queue;
+    let device = command_queue.device();
+    let mut scratch = Vec::with_capacity_in(n, PageAlignedAllocator);
+    scratch.resize(n, F::zero());
+    let command_buffer = command_queue.new_command_buffer();
+    let batch_inverse = BatchInverseStage::<F>::new(library, n);
+    batch_inverse.encode(
+        command_buffer,
+        &buffer_mut_no_copy(device, values),
+        &buffer_mut_no_copy(device, &mut scratch),
+    );
+    commit_and_wait(command_buffer);
+}
+
 // taken from arkworks-rs
 /// Horner's method for polynomial evaluation
 #[inline]
@@ -164,11 +210,81 @@ pub fn conjectured_security_level(
     }
 
     core::cmp::min(
-        core::cmp::min(field_security, query_security) - 1,
+        core::cmp::min(field_security, query_security).saturating_sub(1),
         hash_fn_security,
     )
 }
 
+/// Proven security level in the list-decoding regime using the Johnson bound
+/// with proximity parameter `m` (must be at least 3). Follows Theorem 8 of
+/// <https://eprint.iacr.org/2022/1216.pdf> with linear batching and the blowup
+/// factor as an upper bound on the constraint degree.
+pub fn proven_security_level(
+    field_bits: usize,
+    hash_fn_security: usize,
+    lde_blowup_factor: usize,
+    trace_len: usize,
+    num_fri_quiries: usize,
+    grinding_factor: usize,
+    m: usize,
+) -> usize {
+    assert!(m >= 3, "johnson bound requires m >= 3");
+    let field_bits = field_bits as f64;
+    let log_blowup = log2(lde_blowup_factor as f64);
+    let log_m = log2(m as f64 + 0.5);
+    let lde_domain_size = (lde_blowup_factor * trace_len) as f64;
+
+    // list size L = (m + 1/2) / sqrt(ρ)
+    let log_list_size = log_m + log_blowup / 2.0;
+
+    // ALI error L / |F|
+    let ali_security = field_bits - log_list_size;
+
+    // DEEP error L * (d * (n + 1) + n - 1) / |F| with two openings
+    let max_degree = lde_blowup_factor as f64;
+    let trace_len = trace_len as f64;
+    let deep_points = max_degree * (trace_len + 1.0) + trace_len - 1.0;
+    let deep_security = field_bits - log_list_size - log2(deep_points);
+
+    // FRI commit phase error (m + 1/2)^7 / (3 * ρ^(3/2)) * |D|^2 / |F|
+    let commit_error = 7.0 * log_m - log2(3.0) + 1.5 * log_blowup + 2.0 * log2(lde_domain_size);
+    let commit_security = field_bits - commit_error;
+
+    // FRI query phase error α^s where α = (1 + 1/(2m)) * sqrt(ρ)
+    let security_per_query = log_blowup / 2.0 - log2(1.0 + 0.5 / m as f64);
+    let query_security = security_per_query * num_fri_quiries as f64 + grinding_factor as f64;
+
+    let security = [ali_security, deep_security, commit_security, query_security]
+        .into_iter()
+        .fold(f64::INFINITY, f64::min)
+        - 1.0;
+    core::cmp::min(security.max(0.0) as usize, hash_fn_security)
+}
+
+/// Base 2 logarithm of a positive normal `x`. Computes the fractional part
+/// by repeated squaring since `f64::log2` requires std.
+fn log2(x: f64) -> f64 {
+    const MANTISSA_BITS: u32 = 52;
+    const EXPONENT_BIAS: i64 = 1023;
+    debug_assert!(x.is_normal() && x > 0.0);
+    let bits = x.to_bits();
+    let exponent = ((bits >> MANTISSA_BITS) & 0x7FF) as i64 - EXPONENT_BIAS;
+    // mantissa in [1, 2)
+    let mut mantissa =
+        f64::from_bits((bits & ((1 << MANTISSA_BITS) - 1)) | (1023 << MANTISSA_BITS));
+    let mut fraction = 0.0;
+    let mut bit = 0.5;
+    for _ in 0..32 {
+        mantissa *= mantissa;
+        if mantissa >= 2.0 {
+            mantissa /= 2.0;
+            fraction += bit;
+        }
+        bit /= 2.0;
+    }
+    exponent as f64 + fraction
+}
+
 // TODO: docs
 pub fn reduce_lde_blowup_factor<T: Copy>(
     lde: &mut GpuVec<T>,
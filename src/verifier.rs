@@ -1,55 +1,468 @@
 use crate::challenges::Challenges;
+use crate::commitment::VectorCommitment;
 use crate::composer::DeepCompositionCoeffs;
+use crate::constraints::AlgebraicExpression;
 use crate::constraints::FieldConstant;
+use crate::divisor::Divisor;
 use crate::fri;
-use crate::fri::FriVerifier;
+use crate::fri::LdtVerifier;
 use crate::hints::Hints;
-use crate::merkle::MerkleProof;
-use crate::merkle::MerkleTree;
-use crate::merkle::MerkleTreeError;
+use crate::merkle::is_supported_arity;
+use crate::merkle::salt_leaf;
+use crate::merkle::LeafEncoder;
+use crate::merkle::Salt;
+use crate::random::hash_id;
+use crate::random::label;
 use crate::random::PublicCoin;
+use crate::trace::AirVersion;
+use crate::trace::Padding;
+use crate::trace::Queries;
 use crate::Air;
+use crate::Error;
 // use crate::channel::VerifierChannel;
 use crate::Proof;
+use crate::ProofOptions;
+use crate::TraceInfo;
+use crate::VectorCommitmentProof;
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
+use ark_ff::batch_inversion;
 use ark_ff::Field;
-use ark_ff::One;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
-use core::ops::Deref;
+use ark_serialize::Read;
 use digest::Digest;
-use digest::Output;
-use rand::Rng;
-use sha2::Sha256;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use snafu::Snafu;
 
 /// Errors that are returned during verification of a STARK proof
 #[derive(Debug, Snafu)]
 pub enum VerificationError {
-    #[snafu(display("constraint evaluations at the out-of-domain point are inconsistent"))]
-    InconsistentOodConstraintEvaluations,
+    #[snafu(display(
+        "constraints evaluate to {calculated} at the out-of-domain point but the composition trace gives {provided}"
+    ))]
+    InconsistentOodConstraintEvaluations {
+        calculated: String,
+        provided: String,
+    },
     #[snafu(context(false))]
     #[snafu(display("fri verification failed: {source}"))]
     FriVerification { source: fri::VerificationError },
-    #[snafu(display("query does not resolve to the base trace commitment"))]
-    BaseTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the extension trace commitment"))]
-    ExtensionTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the composition trace commitment"))]
-    CompositionTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("insufficient proof of work on fri commitments"))]
-    FriProofOfWork,
+    #[snafu(display("query {query} does not resolve to the base trace commitment"))]
+    BaseTraceQueryDoesNotMatchCommitment { query: usize },
+    #[snafu(display(
+        "query {query} does not resolve to the commitment of extension trace segment {segment}"
+    ))]
+    ExtensionTraceQueryDoesNotMatchCommitment { segment: usize, query: usize },
+    #[snafu(display("proof has {actual} extension segment commitments, expected {expected}"))]
+    ExtensionSegmentsMismatch { expected: usize, actual: usize },
+    #[snafu(display(
+        "proof has extension segments of widths {actual:?} but the air declares {expected:?}"
+    ))]
+    ExtensionSegmentWidthsMismatch {
+        expected: Vec<usize>,
+        actual: Vec<usize>,
+    },
+    #[snafu(display("proof has {actual} execution trace ood evaluations, expected {expected}"))]
+    InvalidExecutionOodEvals { expected: usize, actual: usize },
+    #[snafu(display("proof has {actual} composition trace ood evaluations, expected {expected}"))]
+    InvalidCompositionOodEvals { expected: usize, actual: usize },
+    #[snafu(display("query {query} does not resolve to the composition trace commitment"))]
+    CompositionTraceQueryDoesNotMatchCommitment { query: usize },
+    #[snafu(display("query {query} does not resolve to the preprocessed trace commitment"))]
+    PreprocessedTraceQueryDoesNotMatchCommitment { query: usize },
+    #[snafu(display("proof can't be deserialized: {message}"))]
+    MalformedProof { message: String },
+    #[snafu(display("trace queries don't match the number of queries and trace columns"))]
+    MalformedTraceQueries,
+    #[snafu(display("query position {position} is outside the lde domain of size {num_leaves}"))]
+    QueryPositionOutOfRange { position: usize, num_leaves: usize },
+    #[snafu(display("proof has preprocessed columns but no preprocessed commitment was given"))]
+    MissingPreprocessedCommitment,
+    #[snafu(display(
+        "a preprocessed commitment was given but the proof has no preprocessed columns"
+    ))]
+    UnexpectedPreprocessedCommitment,
+    #[snafu(display("proof has {actual} preprocessed columns but the air declares {expected}"))]
+    PreprocessedColumnsMismatch { expected: usize, actual: usize },
+    #[snafu(display("proof has {actual} randomizer rows but the air declares {expected}"))]
+    RandomizerRowsMismatch { expected: usize, actual: usize },
+    #[snafu(display(
+        "zk proofs need at least {required} randomizer rows but the air declares {actual}"
+    ))]
+    TooFewRandomizerRows { required: usize, actual: usize },
+    #[snafu(display(
+        "proof declares {actual:?} padding but the air declares {expected:?} padding"
+    ))]
+    PaddingMismatch {
+        expected: (Padding, usize),
+        actual: (Padding, usize),
+    },
+    #[snafu(display(
+        "proof of work on fri commitments has {actual} leading zeros but {required} are required"
+    ))]
+    FriProofOfWork { required: u8, actual: u32 },
+    #[snafu(display("extension field has degree {actual} but the proof declares {expected}"))]
+    ExtensionDegreeMismatch { expected: u8, actual: u64 },
+    #[snafu(display("proof has {actual} bits of security but targets {target}"))]
+    SecurityTargetNotMet { target: u8, actual: usize },
+    #[snafu(display("proof uses hash function {actual:#x} but the air expects {expected:#x}"))]
+    HashMismatch { expected: u64, actual: u64 },
+    #[snafu(display("proof parameters are invalid: {source}"))]
+    InvalidParameters { source: Error },
+    #[snafu(display(
+        "a trace with {trace_len} rows has no lde domain with blowup factor {lde_blowup_factor}"
+    ))]
+    UnsupportedLdeDomain {
+        trace_len: usize,
+        lde_blowup_factor: u8,
+    },
+    #[snafu(display("constraints reference column {column} but the trace has {num_columns}"))]
+    TraceColumnOutOfRange { column: usize, num_columns: usize },
+    #[snafu(display("proof uses hash function {hash_id:#x} which the policy doesn't allow"))]
+    HashNotAllowed { hash_id: u64 },
+    #[snafu(display("trace has {actual} rows but the policy allows at most {max}"))]
+    TraceTooLong { max: usize, actual: usize },
+    #[snafu(display("the policy requires zero-knowledge proofs"))]
+    ZkRequired,
+    #[snafu(display("proof has {actual} bits of security but the policy requires {required}"))]
+    InsufficientSecurity { required: u8, actual: usize },
+    #[snafu(display(
+        "proof has a trace of {actual} rows but the verification key is for {expected}"
+    ))]
+    TraceLenMismatch { expected: usize, actual: usize },
+    #[snafu(display("proof is for a different constraint system than the verification key"))]
+    VerificationKeyMismatch,
+    #[snafu(display("Merkle tree nodes can't have {arity} children"))]
+    UnsupportedMerkleArity { arity: u8 },
+    #[snafu(display("proof is for air version {actual} but the verifier expects {expected}"))]
+    IncompatibleAirVersion {
+        expected: AirVersion,
+        actual: AirVersion,
+    },
+    #[snafu(display("proof {index} of the batch is invalid: {source}"))]
+    InvalidBatchProof {
+        index: usize,
+        source: Box<VerificationError>,
+    },
+}
+
+/// Parameters a verifier requires of the proofs it accepts. The proof options
+/// and trace info are chosen by the prover so without a policy a verifier
+/// accepts e.g. a proof with a single query and no grinding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifierPolicy {
+    /// Minimum conjectured security level (in bits)
+    pub min_security_level: u8,
+    /// Identifiers of the hash functions proofs can use (see
+    /// [crate::random::hash_id]). Any hash function is allowed if empty.
+    pub allowed_hashes: Vec<u64>,
+    /// Maximum number of rows in the trace
+    pub max_trace_len: Option<usize>,
+    /// Reject proofs that aren't zero-knowledge (see [ProofOptions::zk])
+    pub require_zk: bool,
 }
 
 impl<A: Air> Proof<A> {
     pub fn verify(self) -> Result<(), VerificationError> {
+        self.verify_impl(&[], None)
+    }
+
+    /// Verifies a proof of a trace with preprocessed columns. The commitment
+    /// to these columns is a public parameter of the AIR (see
+    /// [crate::trace::PreprocessedTrace::commitment]).
+    pub fn verify_with_preprocessed(
+        self,
+        preprocessed_commitment: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_impl(&[], Some(preprocessed_commitment))
+    }
+
+    /// Verifies a proof made for the deployment identified by `context` (see
+    /// [crate::Prover::context]). Proofs made with a different context are
+    /// rejected.
+    pub fn verify_with_context(
+        self,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<(), VerificationError> {
+        self.verify_impl(context, preprocessed_commitment)
+    }
+
+    /// Verifies a proof of a trace with `trace_len` rows for the
+    /// arithmetization pinned by `verification_key` (see
+    /// [Air::verification_key]). Keys depend on the trace length so it's
+    /// checked first. Proofs for different constraints, column layouts or
+    /// options are rejected before they're verified.
+    pub fn verify_with_key(
+        self,
+        trace_len: usize,
+        verification_key: &[u8],
+    ) -> Result<(), VerificationError> {
+        if self.trace_info.trace_len != trace_len {
+            return Err(VerificationError::TraceLenMismatch {
+                expected: trace_len,
+                actual: self.trace_info.trace_len,
+            });
+        }
+        let air = self.checked_air(None)?;
+        if air.verification_key() != verification_key {
+            return Err(VerificationError::VerificationKeyMismatch);
+        }
+        self.verify_with_air(air, &[], None)
+    }
+
+    /// Verifies a proof if its parameters meet `policy`. The policy is checked
+    /// before any verification work is done.
+    pub fn verify_with_policy(self, policy: &VerifierPolicy) -> Result<(), VerificationError> {
+        self.check_policy(policy)?;
+        self.verify_impl(&[], None)
+    }
+
+    /// Same as [Proof::verify_with_context] but only verifies the proof if its
+    /// parameters meet `policy` (see [Proof::verify_with_policy])
+    pub fn verify_with_context_and_policy(
+        self,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+        policy: &VerifierPolicy,
+    ) -> Result<(), VerificationError> {
+        self.check_policy(policy)?;
+        self.verify_impl(context, preprocessed_commitment)
+    }
+
+    /// Checks the parameters of the proof meet `policy` without verifying it
+    pub fn check_policy(&self, policy: &VerifierPolicy) -> Result<(), VerificationError> {
+        use VerificationError::*;
+        check_parameters::<A>(
+            &self.options,
+            &self.trace_info,
+            &self.public_inputs,
+            self.hash_id,
+        )?;
+        let allowed_hashes = &policy.allowed_hashes;
+        if !allowed_hashes.is_empty() && !allowed_hashes.contains(&self.hash_id) {
+            return Err(HashNotAllowed {
+                hash_id: self.hash_id,
+            });
+        }
+        let trace_len = self.trace_info.trace_len;
+        if let Some(max) = policy.max_trace_len
+            && trace_len > max
+        {
+            return Err(TraceTooLong {
+                max,
+                actual: trace_len,
+            });
+        }
+        if policy.require_zk && !self.options.zk {
+            return Err(ZkRequired);
+        }
+        let security_level = self.conjectured_security_level();
+        if security_level < policy.min_security_level.into() {
+            return Err(InsufficientSecurity {
+                required: policy.min_security_level,
+                actual: security_level,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads a compressed proof from `reader` and verifies it (see
+    /// [Proof::verify_with_context]). The proof is deserialized straight from
+    /// `reader` so its serialized bytes aren't buffered but the deserialized
+    /// proof is held in memory while it's verified. The options, trace info
+    /// and hash function at the start of the proof are checked before the
+    /// rest is read so proofs with unsupported parameters are rejected early.
+    pub fn verify_from_reader(
+        mut reader: impl Read,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<(), VerificationError> {
+        // sections are read in the order of the fields of [Proof]
+        let options = read_section::<ProofOptions>(&mut reader)?;
+        let trace_info = read_section::<TraceInfo>(&mut reader)?;
+        let hash_id = read_section::<u64>(&mut reader)?;
+        check_header::<A>(&options, &trace_info, hash_id)?;
+
+        let base_trace_commitment = read_section(&mut reader)?;
+        let extension_trace_commitments = read_section::<Vec<Vec<u8>>>(&mut reader)?;
+        let num_extension_segments = trace_info.num_extension_segments();
+        if extension_trace_commitments.len() != num_extension_segments {
+            return Err(VerificationError::ExtensionSegmentsMismatch {
+                expected: num_extension_segments,
+                actual: extension_trace_commitments.len(),
+            });
+        }
+        let composition_trace_commitment = read_section(&mut reader)?;
+        let fri_proof = read_section(&mut reader)?;
+        let pow_nonce = read_section(&mut reader)?;
+        let trace_queries = read_section(&mut reader)?;
+        let public_inputs = read_section(&mut reader)?;
+        let execution_trace_ood_evals = read_section(&mut reader)?;
+        let composition_trace_ood_evals = read_section(&mut reader)?;
+
+        let proof = Proof::<A> {
+            options,
+            trace_info,
+            hash_id,
+            base_trace_commitment,
+            extension_trace_commitments,
+            composition_trace_commitment,
+            fri_proof,
+            pow_nonce,
+            trace_queries,
+            public_inputs,
+            execution_trace_ood_evals,
+            composition_trace_ood_evals,
+        };
+        proof.verify_impl(context, preprocessed_commitment)
+    }
+
+    /// Verifies each of `proofs` made for the same deployment (see
+    /// [Proof::verify_with_context]). A convenience over calling
+    /// [Proof::verify_with_context] in a loop: Merkle paths and FRI layers
+    /// are checked separately for every proof and only the inversions of the
+    /// DEEP composition are shared. Returns the error of the first invalid
+    /// proof.
+    pub fn verify_all(
+        proofs: Vec<Proof<A>>,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<(), VerificationError>
+    where
+        A: Send,
+        A::LdtVerifier: Send,
+        Proof<A>: Send,
+    {
+        let in_batch = |index: usize| {
+            move |source| VerificationError::InvalidBatchProof {
+                index,
+                source: Box::new(source),
+            }
+        };
+        let checks = ark_std::cfg_into_iter!(proofs)
+            .enumerate()
+            .map(|(i, proof)| {
+                let air = proof
+                    .checked_air(preprocessed_commitment)
+                    .map_err(in_batch(i))?;
+                let checks = proof.replay_transcript(air, context, preprocessed_commitment);
+                checks.map_err(in_batch(i))
+            })
+            .collect::<Result<Vec<PendingChecks<A>>, VerificationError>>()?;
+
+        let denominators = checks
+            .iter()
+            .map(PendingChecks::deep_denominators)
+            .collect::<Vec<Vec<A::Fq>>>();
+        let mut denominator_invs = denominators.concat();
+        batch_inversion(&mut denominator_invs);
+        let mut remaining_invs = denominator_invs.as_slice();
+        let denominator_invs = denominators
+            .iter()
+            .map(|denominators| {
+                let (invs, rest) = remaining_invs.split_at(denominators.len());
+                remaining_invs = rest;
+                invs
+            })
+            .collect::<Vec<&[A::Fq]>>();
+
+        ark_std::cfg_into_iter!(checks)
+            .zip(denominator_invs)
+            .enumerate()
+            .try_for_each(|(i, (checks, invs))| {
+                let result = checks.verify(preprocessed_commitment, invs);
+                result.map_err(in_batch(i))
+            })
+    }
+
+    fn verify_impl(
+        self,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<(), VerificationError> {
+        let air = self.checked_air(preprocessed_commitment)?;
+        self.verify_with_air(air, context, preprocessed_commitment)
+    }
+
+    /// Verifies the proof against `air` built by [Proof::checked_air]
+    fn verify_with_air(
+        self,
+        air: A,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<(), VerificationError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "verify",
+            trace_len = self.trace_info.trace_len,
+            lde_blowup_factor = self.options.lde_blowup_factor,
+            num_queries = self.options.num_queries,
+        )
+        .entered();
+
+        let checks = self.replay_transcript(air, context, preprocessed_commitment)?;
+        let mut denominator_invs = checks.deep_denominators();
+        batch_inversion(&mut denominator_invs);
+        checks.verify(preprocessed_commitment, &denominator_invs)
+    }
+
+    /// Checks the proof parameters and builds the [Air] they describe
+    fn checked_air(&self, preprocessed_commitment: Option<&[u8]>) -> Result<A, VerificationError> {
         use VerificationError::*;
+        let air = check_parameters::<A>(
+            &self.options,
+            &self.trace_info,
+            &self.public_inputs,
+            self.hash_id,
+        )?;
 
+        let security_level = if self.options.proven_security {
+            self.proven_security_level()
+        } else {
+            self.conjectured_security_level()
+        };
+        if let Some(target) = self.options.security_target
+            && security_level < target.into()
+        {
+            return Err(SecurityTargetNotMet {
+                target,
+                actual: security_level,
+            });
+        }
+
+        match (
+            self.trace_info.num_preprocessed_columns,
+            preprocessed_commitment,
+        ) {
+            (0, Some(_)) => return Err(UnexpectedPreprocessedCommitment),
+            (1.., None) => return Err(MissingPreprocessedCommitment),
+            _ => {}
+        }
+
+        Ok(air)
+    }
+
+    /// Replays the transcript of a proof checked by [Proof::checked_air] up to
+    /// drawing the query positions
+    fn replay_transcript(
+        self,
+        air: A,
+        context: &[u8],
+        preprocessed_commitment: Option<&[u8]>,
+    ) -> Result<PendingChecks<A>, VerificationError> {
+        use VerificationError::*;
         let Proof {
             base_trace_commitment,
-            extension_trace_commitment,
+            extension_trace_commitments,
             composition_trace_commitment,
             execution_trace_ood_evals,
             composition_trace_ood_evals,
@@ -59,40 +472,78 @@ impl<A: Air> Proof<A> {
             options,
             fri_proof,
             pow_nonce,
-            ..
+            hash_id,
         } = self;
 
         let mut seed = Vec::new();
+        hash_id.serialize_compressed(&mut seed).unwrap();
+        A::LeafEncoder::ID.serialize_compressed(&mut seed).unwrap();
         public_inputs.serialize_compressed(&mut seed).unwrap();
         trace_info.serialize_compressed(&mut seed).unwrap();
         options.serialize_compressed(&mut seed).unwrap();
-        let mut public_coin = PublicCoin::<Sha256>::new(&seed);
-
-        let air = A::new(trace_info, public_inputs, options);
+        let mut public_coin = A::PublicCoin::new(&seed);
+        if !context.is_empty() {
+            public_coin.reseed_with_label(label::CONTEXT, &context);
+        }
 
-        let base_trace_comitment = Output::<Sha256>::from_iter(base_trace_commitment);
-        public_coin.reseed(&base_trace_comitment.deref());
-        let challenges = air.get_challenges(&mut public_coin);
-        let hints = air.get_hints(&challenges);
+        let public_cells = air.public_cells();
+        if !public_cells.is_empty() {
+            public_coin.reseed_with_label(label::PUBLIC_CELLS, &public_cells);
+        }
 
-        let extension_trace_commitment =
-            extension_trace_commitment.map(|extension_trace_commitment| {
-                let extension_trace_commitment =
-                    Output::<Sha256>::from_iter(extension_trace_commitment);
-                public_coin.reseed(&extension_trace_commitment.deref());
-                extension_trace_commitment
+        let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+        if let Some(commitment) = preprocessed_commitment {
+            public_coin.reseed_with_label(label::PREPROCESSED_TRACE_COMMITMENT, &commitment);
+        }
+        public_coin.reseed_with_label(
+            label::BASE_TRACE_COMMITMENT,
+            &base_trace_commitment.as_slice(),
+        );
+        let num_extension_segments = air.trace_info().num_extension_segments();
+        if extension_trace_commitments.len() != num_extension_segments {
+            return Err(ExtensionSegmentsMismatch {
+                expected: num_extension_segments,
+                actual: extension_trace_commitments.len(),
             });
+        }
+
+        let mut challenges = air.get_challenges(&mut public_coin);
+        for (segment, commitment) in extension_trace_commitments.iter().enumerate() {
+            if segment != 0 {
+                air.get_segment_challenges(segment, &mut public_coin, &mut challenges);
+            }
+            public_coin
+                .reseed_with_label(label::EXTENSION_TRACE_COMMITMENT, &commitment.as_slice());
+        }
+        let hints = air.get_hints(&challenges);
 
         let composition_coeffs = air.get_constraint_composition_coeffs(&mut public_coin);
-        let composition_trace_commitment =
-            Output::<Sha256>::from_iter(composition_trace_commitment);
-        public_coin.reseed(&composition_trace_commitment.deref());
+        public_coin.reseed_with_label(
+            label::COMPOSITION_TRACE_COMMITMENT,
+            &composition_trace_commitment.as_slice(),
+        );
 
         let z = public_coin.draw::<A::Fq>();
-        public_coin.reseed(&execution_trace_ood_evals);
+        public_coin.reseed_with_label(label::EXECUTION_TRACE_OOD_EVALS, &execution_trace_ood_evals);
+        let trace_arguments = air.trace_arguments();
+        let num_columns = air.trace_info().num_columns();
+        if let Some(&(column, _)) = trace_arguments
+            .iter()
+            .find(|(column, _)| *column >= num_columns)
+        {
+            return Err(TraceColumnOutOfRange {
+                column,
+                num_columns,
+            });
+        }
+        if execution_trace_ood_evals.len() != trace_arguments.len() {
+            return Err(InvalidExecutionOodEvals {
+                expected: trace_arguments.len(),
+                actual: execution_trace_ood_evals.len(),
+            });
+        }
         // execution trace ood evaluation map
-        let trace_ood_eval_map = air
-            .trace_arguments()
+        let trace_ood_eval_map = trace_arguments
             .into_iter()
             .zip(execution_trace_ood_evals.iter().copied())
             .collect::<BTreeMap<(usize, isize), A::Fq>>();
@@ -105,88 +556,355 @@ impl<A: Air> Proof<A> {
             z,
         );
 
-        public_coin.reseed(&composition_trace_ood_evals);
-        let mut acc = A::Fq::one();
-        let provided_ood_constraint_evaluation =
-            composition_trace_ood_evals
-                .iter()
-                .fold(A::Fq::zero(), |mut res, value| {
-                    res += *value * acc;
-                    acc *= z;
-                    res
-                });
+        public_coin.reseed_with_label(
+            label::COMPOSITION_TRACE_OOD_EVALS,
+            &composition_trace_ood_evals,
+        );
+        let num_composition_trace_columns = air.num_composition_trace_columns();
+        if composition_trace_ood_evals.len() != num_composition_trace_columns {
+            return Err(InvalidCompositionOodEvals {
+                expected: num_composition_trace_columns,
+                actual: composition_trace_ood_evals.len(),
+            });
+        }
+        // the zk mask column isn't part of the composition polynomial
+        let provided_ood_constraint_evaluation = recombine_composition_ood_evals(
+            &composition_trace_ood_evals[..air.ce_blowup_factor()],
+            z,
+        );
 
         if calculated_ood_constraint_evaluation != provided_ood_constraint_evaluation {
-            return Err(InconsistentOodConstraintEvaluations);
+            return Err(InconsistentOodConstraintEvaluations {
+                calculated: calculated_ood_constraint_evaluation.to_string(),
+                provided: provided_ood_constraint_evaluation.to_string(),
+            });
         }
 
         let deep_coeffs = air.get_deep_composition_coeffs(&mut public_coin);
-        let fri_verifier = FriVerifier::<A::Fq, Sha256>::new(
+        let fri_verifier = A::LdtVerifier::new(
             &mut public_coin,
             options.into_fri_options(),
             fri_proof,
             air.trace_len() - 1,
         )?;
 
-        if options.grinding_factor != 0 {
-            public_coin.reseed(&pow_nonce);
-            if public_coin.seed_leading_zeros() < options.grinding_factor as u32 {
-                return Err(FriProofOfWork);
+        let grinding = options.grinding;
+        if grinding.bits != 0 {
+            let leading_zeros = public_coin.pow_leading_zeros(grinding.hash, pow_nonce);
+            if leading_zeros < grinding.bits.into() {
+                return Err(FriProofOfWork {
+                    required: grinding.bits,
+                    actual: leading_zeros,
+                });
             }
+            public_coin.reseed_with_label(label::POW, &pow_nonce);
         }
 
-        let mut rng = public_coin.draw_rng();
+        let query_positions =
+            public_coin.draw_unique_queries(options.num_queries.into(), lde_domain_size);
+
+        Ok(PendingChecks {
+            air,
+            fri_verifier,
+            trace_queries,
+            base_trace_commitment,
+            extension_trace_commitments,
+            composition_trace_commitment,
+            query_positions,
+            deep_coeffs,
+            z,
+            trace_ood_eval_map,
+            composition_trace_ood_evals,
+        })
+    }
+}
+
+/// Checks the parameters at the start of a proof. They are read from the proof
+/// so are checked before they're used. Returns the AIR they describe.
+fn check_parameters<A: Air>(
+    options: &ProofOptions,
+    trace_info: &TraceInfo,
+    public_inputs: &A::PublicInputs,
+    hash_id: u64,
+) -> Result<A, VerificationError> {
+    use VerificationError::*;
+    check_header::<A>(options, trace_info, hash_id)?;
+    // the trace layout comes from the proof so it must match the layout the
+    // constraints were written for
+    let air = A::new(trace_info.clone(), public_inputs.clone(), *options);
+    let expected = air.extension_segment_widths();
+    if trace_info.extension_segment_widths != expected {
+        return Err(ExtensionSegmentWidthsMismatch {
+            expected,
+            actual: trace_info.extension_segment_widths.clone(),
+        });
+    }
+    let expected = air.num_preprocessed_columns();
+    if trace_info.num_preprocessed_columns != expected {
+        return Err(PreprocessedColumnsMismatch {
+            expected,
+            actual: trace_info.num_preprocessed_columns,
+        });
+    }
+    // exempt rows are chosen by the air rather than the prover
+    let expected = air.num_randomizer_rows();
+    if trace_info.num_randomizer_rows != expected {
+        return Err(RandomizerRowsMismatch {
+            expected,
+            actual: trace_info.num_randomizer_rows,
+        });
+    }
+    let required = options.min_randomizer_rows();
+    if expected < required {
+        return Err(TooFewRandomizerRows {
+            required,
+            actual: expected,
+        });
+    }
+    let expected = air.padding();
+    let actual = (trace_info.padding, trace_info.num_padding_rows);
+    if actual != expected {
+        return Err(PaddingMismatch { expected, actual });
+    }
+    Ok(air)
+}
+
+/// Checks the options, trace info and hash function at the start of a proof
+/// i.e. the parameters that can be checked without the public inputs
+fn check_header<A: Air>(
+    options: &ProofOptions,
+    trace_info: &TraceInfo,
+    actual_hash_id: u64,
+) -> Result<(), VerificationError> {
+    use VerificationError::*;
+    let expected_hash_id = hash_id::<A::Digest>();
+    if actual_hash_id != expected_hash_id {
+        return Err(HashMismatch {
+            expected: expected_hash_id,
+            actual: actual_hash_id,
+        });
+    }
+
+    if !trace_info.air_version.is_compatible_with(&A::VERSION) {
+        return Err(IncompatibleAirVersion {
+            expected: A::VERSION,
+            actual: trace_info.air_version,
+        });
+    }
+
+    let invalid_parameters = |source| InvalidParameters { source };
+    options.validate().map_err(invalid_parameters)?;
+    trace_info.validate().map_err(invalid_parameters)?;
+    let trace_len = trace_info.trace_len;
+    let lde_blowup_factor = options.lde_blowup_factor;
+    if trace_len
+        .checked_mul(lde_blowup_factor.into())
+        .and_then(Radix2EvaluationDomain::<A::Fp>::compute_size_of_domain)
+        .is_none()
+    {
+        return Err(UnsupportedLdeDomain {
+            trace_len,
+            lde_blowup_factor,
+        });
+    }
+
+    let fq_degree = A::Fq::extension_degree();
+    if let Some(expected) = options.extension_degree
+        && u64::from(expected) != fq_degree
+    {
+        return Err(ExtensionDegreeMismatch {
+            expected,
+            actual: fq_degree,
+        });
+    }
+    if !is_supported_arity(options.merkle_arity.into()) {
+        return Err(UnsupportedMerkleArity {
+            arity: options.merkle_arity,
+        });
+    }
+    Ok(())
+}
+
+/// Deserializes the next compressed section of a proof from `reader`
+fn read_section<T: CanonicalDeserialize>(reader: impl Read) -> Result<T, VerificationError> {
+    T::deserialize_compressed(reader).map_err(|error| VerificationError::MalformedProof {
+        message: error.to_string(),
+    })
+}
+
+/// Checks of a proof that remain once its transcript is replayed: the trace
+/// openings, the DEEP composition and FRI
+struct PendingChecks<A: Air> {
+    air: A,
+    fri_verifier: A::LdtVerifier,
+    trace_queries: Queries<A>,
+    base_trace_commitment: Vec<u8>,
+    extension_trace_commitments: Vec<Vec<u8>>,
+    composition_trace_commitment: Vec<u8>,
+    query_positions: Vec<usize>,
+    deep_coeffs: DeepCompositionCoeffs<A::Fq>,
+    z: A::Fq,
+    trace_ood_eval_map: BTreeMap<(usize, isize), A::Fq>,
+    composition_trace_ood_evals: Vec<A::Fq>,
+}
+
+impl<A: Air> PendingChecks<A> {
+    /// Distinct offsets of the rows the execution trace is evaluated at
+    fn trace_offsets(&self) -> Vec<isize> {
+        let mut offsets = self
+            .trace_ood_eval_map
+            .keys()
+            .map(|&(_, offset)| offset)
+            .collect::<Vec<isize>>();
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    /// Denominators of the DEEP composition at each query position: `x - z*g^i`
+    /// for each trace offset `i` followed by `x - z^k` for the composition
+    /// trace
+    fn deep_denominators(&self) -> Vec<A::Fq> {
+        let air = &self.air;
+        let trace_domain = air.trace_domain();
+        let g = trace_domain.group_gen();
+        let g_inv = trace_domain.group_gen_inv();
+        let lde_domain = air.lde_domain();
+        let z = self.z;
+        let z_n = z.pow([air.ce_blowup_factor() as u64]);
+        let shifted_zs = self
+            .trace_offsets()
+            .into_iter()
+            .map(|offset| {
+                let shift = if offset.is_positive() { g } else { g_inv };
+                z * shift.pow([offset.unsigned_abs() as u64])
+            })
+            .chain([z_n])
+            .collect::<Vec<A::Fq>>();
+        let mut denominators = Vec::new();
+        for &position in &self.query_positions {
+            let x = A::Fq::from(lde_domain.element(position));
+            denominators.extend(shifted_zs.iter().map(|&shifted_z| x - shifted_z));
+        }
+        denominators
+    }
+
+    /// Checks the trace openings and the DEEP composition given the inverses
+    /// of [Self::deep_denominators]
+    fn verify(
+        self,
+        preprocessed_commitment: Option<&[u8]>,
+        denominator_invs: &[A::Fq],
+    ) -> Result<(), VerificationError> {
+        use VerificationError::*;
+        let trace_offsets = self.trace_offsets();
+        let PendingChecks {
+            air,
+            fri_verifier,
+            trace_queries,
+            base_trace_commitment,
+            extension_trace_commitments,
+            composition_trace_commitment,
+            query_positions,
+            deep_coeffs,
+            trace_ood_eval_map,
+            composition_trace_ood_evals,
+            ..
+        } = self;
+        let options = *air.options();
         let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
-        let query_positions = (0..options.num_queries)
-            .map(|_| rng.gen_range(0..lde_domain_size))
-            .collect::<Vec<usize>>();
-
-        let base_trace_rows = trace_queries
-            .base_trace_values
-            .chunks(air.trace_info().num_base_columns)
-            .collect::<Vec<&[A::Fp]>>();
-        let extension_trace_rows = if air.trace_info().num_extension_columns > 0 {
-            trace_queries
-                .extension_trace_values
-                .chunks(air.trace_info().num_extension_columns)
-                .collect::<Vec<&[A::Fq]>>()
-        } else {
-            Vec::new()
-        };
+        let trace_info = air.trace_info();
+        let num_extension_segments = trace_info.num_extension_segments();
+        let num_composition_trace_columns = air.num_composition_trace_columns();
 
-        let composition_trace_rows = trace_queries
-            .composition_trace_values
-            .chunks(air.ce_blowup_factor())
-            .collect::<Vec<&[A::Fq]>>();
+        let num_queries = query_positions.len();
+        let base_trace_rows = split_rows(
+            &trace_queries.base_trace_values,
+            num_queries,
+            trace_info.num_base_columns,
+        )?;
+        let extension_trace_rows = split_rows(
+            &trace_queries.extension_trace_values,
+            num_queries,
+            trace_info.num_extension_columns,
+        )?;
+        let composition_trace_rows = split_rows(
+            &trace_queries.composition_trace_values,
+            num_queries,
+            num_composition_trace_columns,
+        )?;
+        let preprocessed_trace_rows = split_rows(
+            &trace_queries.preprocessed_trace_values,
+            num_queries,
+            trace_info.num_preprocessed_columns,
+        )?;
 
         // base trace positions
-        verify_positions::<Sha256>(
-            base_trace_comitment,
+        verify_positions::<A>(
+            &base_trace_commitment,
+            lde_domain_size,
             &query_positions,
             &base_trace_rows,
-            trace_queries.base_trace_proofs,
-        )
-        .map_err(|_| BaseTraceQueryDoesNotMatchCommitment)?;
+            &trace_queries.base_trace_proofs,
+            &trace_queries.base_trace_salts,
+            &options,
+            |query| BaseTraceQueryDoesNotMatchCommitment { query },
+        )?;
 
-        if let Some(extension_trace_commitment) = extension_trace_commitment {
-            // extension trace positions
-            verify_positions::<Sha256>(
-                extension_trace_commitment,
+        // extension trace segment positions
+        if trace_queries.extension_trace_proofs.len() != num_extension_segments
+            || trace_queries.extension_trace_salts.len() != num_extension_segments
+        {
+            return Err(MalformedTraceQueries);
+        }
+        let extension_segment_ranges = trace_info.extension_segment_ranges();
+        for (segment, commitment) in extension_trace_commitments.iter().enumerate() {
+            let range = &extension_segment_ranges[segment];
+            let segment_rows = extension_trace_rows
+                .iter()
+                .map(|row| row.get(range.clone()).unwrap_or_default())
+                .collect::<Vec<&[A::Fq]>>();
+            verify_positions::<A>(
+                commitment,
+                lde_domain_size,
                 &query_positions,
-                &extension_trace_rows,
-                trace_queries.extension_trace_proofs,
-            )
-            .map_err(|_| ExtensionTraceQueryDoesNotMatchCommitment)?;
+                &segment_rows,
+                &trace_queries.extension_trace_proofs[segment],
+                &trace_queries.extension_trace_salts[segment],
+                &options,
+                |query| ExtensionTraceQueryDoesNotMatchCommitment { segment, query },
+            )?;
         }
 
         // composition trace positions
-        verify_positions::<Sha256>(
-            composition_trace_commitment,
+        verify_positions::<A>(
+            &composition_trace_commitment,
+            lde_domain_size,
             &query_positions,
             &composition_trace_rows,
-            trace_queries.composition_trace_proofs,
-        )
-        .map_err(|_| CompositionTraceQueryDoesNotMatchCommitment)?;
+            &trace_queries.composition_trace_proofs,
+            &trace_queries.composition_trace_salts,
+            &options,
+            |query| CompositionTraceQueryDoesNotMatchCommitment { query },
+        )?;
+
+        // preprocessed trace positions. The preprocessed trace is committed to
+        // ahead of time without salts.
+        if let Some(commitment) = preprocessed_commitment {
+            verify_positions::<A>(
+                commitment,
+                lde_domain_size,
+                &query_positions,
+                &preprocessed_trace_rows,
+                &trace_queries.preprocessed_trace_proofs,
+                &[],
+                &ProofOptions {
+                    zk: false,
+                    ..options
+                },
+                |query| PreprocessedTraceQueryDoesNotMatchCommitment { query },
+            )?;
+        }
 
         let deep_evaluations = deep_composition_evaluations(
             &air,
@@ -195,7 +913,9 @@ impl<A: Air> Proof<A> {
             base_trace_rows,
             extension_trace_rows,
             composition_trace_rows,
-            z,
+            preprocessed_trace_rows,
+            &trace_offsets,
+            denominator_invs,
             trace_ood_eval_map,
             composition_trace_ood_evals,
         );
@@ -213,16 +933,40 @@ fn ood_constraint_evaluation<A: Air>(
     x: A::Fq,
 ) -> A::Fq {
     let mut result = A::Fq::zero();
-    let trace_degree = air.trace_len() - 1;
     let composition_degree = air.composition_degree();
+    let constraint_degrees = air.constraint_degrees();
+    let periodic_evals = air
+        .periodic_columns()
+        .iter()
+        .map(|column| column.evaluate_at(x, air.trace_len()))
+        .collect::<Vec<A::Fq>>();
+
+    // constraints that share a divisor share its evaluation
+    let trace_domain = air.trace_domain();
+    let mut divisor_inverses = Vec::<(Divisor, A::Fq)>::new();
+    let mut divisor_inverse = |divisor: Divisor| {
+        if let Some((_, inverse)) = divisor_inverses.iter().find(|(d, _)| *d == divisor) {
+            return *inverse;
+        }
+        let inverse = divisor.evaluate_at(x, trace_domain).inverse().unwrap();
+        divisor_inverses.push((divisor, inverse));
+        inverse
+    };
+    // weighted numerators of constraints that share a divisor are summed
+    // before dividing if the proof batches constraints (see
+    // [ProofOptions::batch_constraints])
+    let batch_constraints = air.options().batch_constraints;
+    let mut batched = Vec::<(Divisor, A::Fq)>::new();
 
-    for (i, constraint) in air.constraints().iter().enumerate() {
-        let (numerator_degree, denominator_degree) = constraint.degree(trace_degree);
-        let evaluation_degree = numerator_degree - denominator_degree;
+    for (i, (numerator, divisor)) in air.divided_constraints().into_iter().enumerate() {
+        let evaluation_degree = constraint_degrees[i];
         assert!(evaluation_degree <= composition_degree);
         let degree_adjustment = (composition_degree - evaluation_degree) as u64;
 
-        let eval_result = constraint.eval(
+        let numerator = numerator.substitute_periodic(&mut |i| {
+            AlgebraicExpression::Constant(FieldConstant::Fq(periodic_evals[i]))
+        });
+        let eval_result = numerator.eval(
             &FieldConstant::Fq(x),
             &|i| FieldConstant::Fq(hints[i]),
             &|i| FieldConstant::Fq(challenges[i]),
@@ -238,35 +982,99 @@ fn ood_constraint_evaluation<A: Air>(
         // TODO: proper errors
         // TODO: don't allow degree 0 constraints
         let (alpha, beta) = composition_coefficients[i];
-        result += eval_result * (alpha * x.pow([degree_adjustment]) + beta)
+        let eval_result = eval_result * (alpha * x.pow([degree_adjustment]) + beta);
+        match divisor {
+            Some(divisor) if batch_constraints => {
+                match batched.iter_mut().find(|(d, _)| *d == divisor) {
+                    Some((_, sum)) => *sum += eval_result,
+                    None => batched.push((divisor, eval_result)),
+                }
+            }
+            Some(divisor) => result += eval_result * divisor_inverse(divisor),
+            None => result += eval_result,
+        }
     }
 
+    for (divisor, sum) in batched {
+        result += sum * divisor_inverse(divisor);
+    }
     result
 }
 
-fn verify_positions<D: Digest>(
-    commitment: Output<D>,
+/// Evaluates the composition polynomial at `z` from the evaluations of its
+/// `k` split columns at `z^k` i.e. `C(z) = C_0(z^k) + z*C_1(z^k) + ...`
+fn recombine_composition_ood_evals<F: Field>(column_evals: &[F], z: F) -> F {
+    let mut acc = F::one();
+    column_evals.iter().fold(F::zero(), |mut res, value| {
+        res += *value * acc;
+        acc *= z;
+        res
+    })
+}
+
+/// Checks the opened `rows` resolve to `commitment`. If [ProofOptions::zk] is
+/// set each leaf is the hash of the row's hash and its salt. Returns the
+/// error made by `mismatch` for the first query that doesn't resolve.
+#[allow(clippy::too_many_arguments)]
+fn verify_positions<A: Air>(
+    commitment: &[u8],
+    num_leaves: usize,
     positions: &[usize],
-    rows: &[&[impl CanonicalSerialize]],
-    proofs: Vec<MerkleProof>,
-) -> Result<(), MerkleTreeError> {
-    for ((position, proof), row) in positions.iter().zip(proofs).zip(rows) {
-        let proof = proof.parse::<D>();
-        let expected_leaf = &proof[0];
-        let mut row_bytes = Vec::with_capacity(row.compressed_size());
-        row.serialize_compressed(&mut row_bytes).unwrap();
-        let actual_leaf = D::new_with_prefix(&row_bytes).finalize();
+    rows: &[&[impl Field]],
+    proofs: &[VectorCommitmentProof<A>],
+    salts: &[Salt],
+    options: &ProofOptions,
+    mismatch: impl Fn(usize) -> VerificationError,
+) -> Result<(), VerificationError> {
+    if rows.len() != positions.len() || proofs.len() != positions.len() {
+        return Err(VerificationError::MalformedTraceQueries);
+    }
+    if let Some(&position) = positions.iter().find(|&&position| position >= num_leaves) {
+        return Err(VerificationError::QueryPositionOutOfRange {
+            position,
+            num_leaves,
+        });
+    }
+
+    let expected_num_salts = if options.zk { positions.len() } else { 0 };
+    if salts.len() != expected_num_salts {
+        return Err(VerificationError::MalformedTraceQueries);
+    }
 
-        if *expected_leaf != actual_leaf {
-            return Err(MerkleTreeError::InvalidProof);
+    for (i, ((&position, proof), row)) in positions.iter().zip(proofs).zip(rows).enumerate() {
+        let mut row_bytes = Vec::with_capacity(row.compressed_size());
+        A::LeafEncoder::encode_row(row, &mut row_bytes);
+        let mut leaf = A::Digest::new_with_prefix(&row_bytes).finalize();
+        if let Some(salt) = salts.get(i) {
+            leaf = salt_leaf::<A::Digest>(&leaf, salt);
         }
 
-        MerkleTree::<D>::verify(&commitment, &proof, *position)?;
+        A::VectorCommitment::verify(commitment, num_leaves, position, &leaf, proof, options)
+            .map_err(|_| mismatch(i))?;
     }
 
     Ok(())
 }
 
+/// Splits the opened `values` into `num_rows` rows of `num_columns` values.
+/// Returns [VerificationError::MalformedTraceQueries] unless there are
+/// exactly enough values.
+fn split_rows<F>(
+    values: &[F],
+    num_rows: usize,
+    num_columns: usize,
+) -> Result<Vec<&[F]>, VerificationError> {
+    if values.len() != num_rows * num_columns {
+        return Err(VerificationError::MalformedTraceQueries);
+    }
+    if num_columns == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(values.chunks(num_columns).collect())
+}
+
+/// Evaluates the DEEP composition at each query position. `denominator_invs`
+/// are the inverses of [PendingChecks::deep_denominators].
 #[allow(clippy::too_many_arguments)]
 fn deep_composition_evaluations<A: Air>(
     air: &A,
@@ -275,48 +1083,51 @@ fn deep_composition_evaluations<A: Air>(
     base_trace_rows: Vec<&[A::Fp]>,
     extension_trace_rows: Vec<&[A::Fq]>,
     composition_trace_rows: Vec<&[A::Fq]>,
-    z: A::Fq,
+    preprocessed_trace_rows: Vec<&[A::Fp]>,
+    trace_offsets: &[isize],
+    denominator_invs: &[A::Fq],
     execution_trace_ood_evals_map: BTreeMap<(usize, isize), A::Fq>,
     composition_trace_ood_evals: Vec<A::Fq>,
 ) -> Vec<A::Fq> {
-    let trace_domain = air.trace_domain();
-    let g = trace_domain.group_gen();
-    let g_inv = trace_domain.group_gen_inv();
     let lde_domain = air.lde_domain();
     let xs = query_positions
         .iter()
         .map(|pos| lde_domain.element(*pos))
         .collect::<Vec<A::Fp>>();
-
+    // one denominator per trace offset and one for the composition trace
+    let denominator_invs = denominator_invs.chunks(trace_offsets.len() + 1);
     let mut evals = vec![A::Fq::zero(); query_positions.len()];
 
     // add execution trace
     let trace_info = air.trace_info();
     let base_columns_range = trace_info.base_columns_range();
     let extension_columns_range = trace_info.extension_columns_range();
-    for (i, (&x, eval)) in xs.iter().zip(&mut evals).enumerate() {
+    let preprocessed_columns_range = trace_info.preprocessed_columns_range();
+    for (i, (invs, eval)) in denominator_invs.clone().zip(&mut evals).enumerate() {
         for (j, ((column, offset), ood_eval)) in execution_trace_ood_evals_map.iter().enumerate() {
             let trace_value = if base_columns_range.contains(column) {
                 A::Fq::from(base_trace_rows[i][*column])
             } else if extension_columns_range.contains(column) {
                 extension_trace_rows[i][column - trace_info.num_base_columns]
+            } else if preprocessed_columns_range.contains(column) {
+                A::Fq::from(preprocessed_trace_rows[i][column - preprocessed_columns_range.start])
             } else {
                 panic!("column {column} does not exist");
             };
 
             let alpha = composition_coeffs.execution_trace[j];
-            let shift = if offset.is_positive() { g } else { g_inv }.pow([offset.abs() as u64]);
-            *eval += alpha * (trace_value - ood_eval) / (A::Fq::from(x) - z * shift);
+            let inv = invs[trace_offsets.binary_search(offset).unwrap()];
+            *eval += alpha * (trace_value - ood_eval) * inv;
         }
     }
 
     // add composition trace
-    let z_n = z.pow([air.ce_blowup_factor() as u64]);
-    for ((&x, row), eval) in xs.iter().zip(composition_trace_rows).zip(&mut evals) {
+    for ((invs, row), eval) in denominator_invs.zip(composition_trace_rows).zip(&mut evals) {
+        let inv = invs[trace_offsets.len()];
         for (i, value) in row.iter().enumerate() {
             let alpha = composition_coeffs.composition_trace[i];
             let ood_eval = composition_trace_ood_evals[i];
-            *eval += alpha * (*value - ood_eval) / (A::Fq::from(x) - z_n);
+            *eval += alpha * (*value - ood_eval) * inv;
         }
     }
 
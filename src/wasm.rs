@@ -0,0 +1,55 @@
+//! Verifier for browsers built for `wasm32-unknown-unknown` with the `wasm`
+//! feature (and without the `gpu` feature). Crates that define an AIR export
+//! its verifier to JavaScript with [wasm_verifier](crate::wasm_verifier).
+//! The crate uses nightly features (e.g. `allocator_api`) throughout so the
+//! verifier needs a nightly toolchain like every other build. CI checks the
+//! build.
+//!
+//! ```ignore
+//! ministark::wasm_verifier!(FibAir);
+//! ```
+//!
+//! ```sh
+//! cargo +nightly build -r --target wasm32-unknown-unknown -F wasm
+//! ```
+
+use crate::Air;
+use crate::Proof;
+use alloc::vec::Vec;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+#[doc(hidden)]
+pub use wasm_bindgen;
+
+/// Verifies a compressed proof for `A` made for the compressed public inputs
+/// in `public_inputs_bytes`. Returns false if the proof or public inputs
+/// can't be deserialized, the proof is for different public inputs or the
+/// proof is invalid.
+pub fn verify<A: Air>(proof_bytes: &[u8], public_inputs_bytes: &[u8]) -> bool {
+    let Ok(proof) = Proof::<A>::deserialize_compressed(proof_bytes) else {
+        return false;
+    };
+    let Ok(public_inputs) = A::PublicInputs::deserialize_compressed(public_inputs_bytes) else {
+        return false;
+    };
+    let mut expected = Vec::new();
+    let mut actual = Vec::new();
+    public_inputs.serialize_compressed(&mut expected).unwrap();
+    proof
+        .public_inputs
+        .serialize_compressed(&mut actual)
+        .unwrap();
+    expected == actual && proof.verify().is_ok()
+}
+
+/// Exports `verify(proof_bytes, public_inputs_bytes) -> bool` to JavaScript
+/// for the AIR `$air` (see [verify](crate::wasm::verify))
+#[macro_export]
+macro_rules! wasm_verifier {
+    ($air:ty) => {
+        #[$crate::wasm::wasm_bindgen::prelude::wasm_bindgen(wasm_bindgen = $crate::wasm::wasm_bindgen)]
+        pub fn verify(proof_bytes: &[u8], public_inputs_bytes: &[u8]) -> bool {
+            $crate::wasm::verify::<$air>(proof_bytes, public_inputs_bytes)
+        }
+    };
+}
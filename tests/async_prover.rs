@@ -0,0 +1,98 @@
+#![cfg(feature = "std")]
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::prove_fib;
+use common::FibAir;
+use common::FibProver;
+use common::FibTrace;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::prover::ProverEvent;
+use ministark::prover::ProvingPhase;
+use ministark::Error;
+use ministark::ProofOptions;
+use ministark::Prover;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn async_proof_reports_progress_to_its_handle() {
+    let prover = Arc::new(FibProver::new(options()));
+
+    let handle = prover.generate_proof_async(fib_trace(2048, &options()));
+    while !handle.is_finished() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(
+        Some(ProverEvent::PhaseFinished(ProvingPhase::Queries)),
+        handle.progress()
+    );
+    pollster::block_on(handle).unwrap().verify().unwrap();
+}
+
+#[test]
+fn batch_of_proofs_matches_proving_each_trace() {
+    let options = options();
+    // more traces than are in flight at a time and of different lengths
+    let trace_lens = [2048, 4096, 2048, 8192, 2048, 4096];
+    let traces = Vec::from(trace_lens.map(|n| fib_trace(n, &options)));
+
+    let proofs = pollster::block_on(FibProver::new(options).generate_proofs(traces)).unwrap();
+
+    assert_eq!(trace_lens.len(), proofs.len());
+    for (n, proof) in trace_lens.into_iter().zip(proofs) {
+        assert_eq!(n, proof.trace_info.trace_len);
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let mut expected_bytes = Vec::new();
+        prove_fib(options, n)
+            .serialize_compressed(&mut expected_bytes)
+            .unwrap();
+        assert_eq!(expected_bytes, bytes);
+        proof.verify().unwrap();
+    }
+}
+
+/// Panics once FRI starts
+struct PanickingFibProver(ProofOptions);
+
+impl Prover for PanickingFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        PanickingFibProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+
+    fn on_event(&self, event: ProverEvent) {
+        assert_ne!(ProverEvent::PhaseStarted(ProvingPhase::Fri), event);
+    }
+}
+
+#[test]
+fn async_proof_that_panics_resolves_to_an_error() {
+    let prover = Arc::new(PanickingFibProver::new(options()));
+
+    let handle = prover.generate_proof_async(fib_trace(2048, &options()));
+
+    assert!(matches!(
+        pollster::block_on(handle),
+        Err(Error::Panicked { .. })
+    ));
+}
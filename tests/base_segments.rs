@@ -0,0 +1,167 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use ark_ff::Zero;
+use common::options;
+use common::rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::divisor::Divisor;
+use ministark::gadgets::LookupArgument;
+use ministark::periodic::PeriodicColumn;
+use ministark::selectors;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+const TRACE_LEN: usize = 2048;
+const STRIDE: usize = 4;
+
+/// A main trace column `v` with one row per trace row whose values are looked
+/// up in a co-processor table `t = [0, 1, 2, ...]` with one row every
+/// `STRIDE` trace rows. The multiplicities `m` of the table belong to the
+/// same short segment and the running sum `s` of the lookup bridges the two.
+struct CoprocessorTrace(Matrix<Fp>);
+
+impl Trace for CoprocessorTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 3;
+    const NUM_EXTENSION_COLUMNS: usize = 1;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+
+    fn base_segments(&self) -> Vec<(usize, usize)> {
+        vec![(1, TRACE_LEN), (2, TRACE_LEN / STRIDE)]
+    }
+
+    fn build_extension_columns(&self, challenges: &Challenges<Fp>) -> Option<Matrix<Fp>> {
+        Some(lookup().build_column(&self.0, challenges))
+    }
+}
+
+struct CoprocessorAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+}
+
+impl Air for CoprocessorAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        CoprocessorAir {
+            options,
+            trace_info,
+        }
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn periodic_columns(&self) -> Vec<PeriodicColumn<Fp>> {
+        vec![selectors::stride_selector(STRIDE)]
+    }
+
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        lookup().constraints(self.trace_len())
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        // the next row of the table is `STRIDE` trace rows ahead
+        let n = self.trace_len();
+        let one = FieldConstant::Fp(Fp::one());
+        let divisor = Divisor::periodic(0, STRIDE).with_exemption(n - STRIDE);
+        vec![
+            TransitionConstraint::new(1.offset(STRIDE as isize) - 1.curr() - one)
+                .with_divisor(divisor),
+        ]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        let mut assertions = lookup().assertions();
+        assertions.push(Assertion::single(1, 0, FieldConstant::Fp(Fp::zero())));
+        assertions
+    }
+}
+
+struct CoprocessorProver(ProofOptions);
+
+impl Prover for CoprocessorProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = CoprocessorAir;
+    type Trace = CoprocessorTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CoprocessorProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _: &CoprocessorTrace) {}
+}
+
+fn lookup() -> LookupArgument {
+    LookupArgument::new(vec![0], 1, 2, 3, 0).with_table_stride(STRIDE, 0)
+}
+
+fn coprocessor_trace() -> CoprocessorTrace {
+    let table_len = TRACE_LEN / STRIDE;
+    let values = (0..TRACE_LEN)
+        .map(|i| Fp::from((i * i % table_len) as u64))
+        .collect::<Vec<Fp>>();
+    let table = (0..table_len as u64).map(Fp::from).collect::<Vec<Fp>>();
+    let multiplicities = LookupArgument::multiplicities(&[&values], &table);
+    CoprocessorTrace(Matrix::new(
+        [&values, &table, &multiplicities]
+            .map(|column| column.to_vec_in(PageAlignedAllocator))
+            .into(),
+    ))
+}
+
+#[test]
+fn proof_with_base_segments_of_different_lengths_verifies() {
+    let trace = coprocessor_trace();
+    assert_eq!(TRACE_LEN, trace.len());
+
+    let proof = CoprocessorProver::new(options())
+        .prove_with_rng(trace, &mut rng())
+        .unwrap();
+
+    assert_eq!(
+        vec![TRACE_LEN, TRACE_LEN / STRIDE],
+        proof.trace_info.base_segment_lengths
+    );
+    proof.verify().unwrap();
+}
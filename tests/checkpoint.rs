@@ -0,0 +1,150 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::prove_fib;
+use common::rng;
+use common::FibAir;
+use common::FibTrace;
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::checkpoint::trace_digest;
+use ministark::checkpoint::CommittedTrace;
+use ministark::checkpoint::ProverCheckpoint;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use sha2::Sha256;
+use std::sync::Mutex;
+
+#[test]
+fn committed_trace_roundtrip_preserves_leaves() {
+    let polys = Matrix::from_rows(vec![vec![Fp::one(), Fp::zero()]; 4]);
+    let leaves = (0u8..4).map(|i| Sha256::digest([i])).collect::<Vec<_>>();
+    let committed = CommittedTrace::new::<Sha256>(polys, &leaves);
+
+    let mut bytes = Vec::new();
+    committed.serialize_compressed(&mut bytes).unwrap();
+    let restored = CommittedTrace::<Fp>::deserialize_compressed(&*bytes).unwrap();
+
+    assert_eq!(leaves, restored.leaves::<Sha256>());
+    assert_eq!(committed.polys.0, restored.polys.0);
+}
+
+/// Keeps each checkpoint it's handed and resumes from `resume_from`
+struct CheckpointingFibProver {
+    options: ProofOptions,
+    checkpoints: Mutex<Vec<Vec<u8>>>,
+    resume_from: Option<Vec<u8>>,
+}
+
+impl Prover for CheckpointingFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CheckpointingFibProver {
+            options,
+            checkpoints: Mutex::new(Vec::new()),
+            resume_from: None,
+        }
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.options
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+
+    fn checkpointing(&self) -> bool {
+        true
+    }
+
+    fn save_checkpoint(&self, checkpoint: &ProverCheckpoint<FibAir>) {
+        let mut bytes = Vec::new();
+        checkpoint.serialize_compressed(&mut bytes).unwrap();
+        self.checkpoints.lock().unwrap().push(bytes);
+    }
+
+    fn load_checkpoint(&self) -> Option<ProverCheckpoint<FibAir>> {
+        let bytes = self.resume_from.as_ref()?;
+        Some(ProverCheckpoint::deserialize_compressed(&**bytes).unwrap())
+    }
+}
+
+fn checkpoint(bytes: &[u8]) -> ProverCheckpoint<FibAir> {
+    ProverCheckpoint::deserialize_compressed(bytes).unwrap()
+}
+
+fn resume(options: ProofOptions, checkpoint: &ProverCheckpoint<FibAir>) -> Proof<FibAir> {
+    let mut bytes = Vec::new();
+    checkpoint.serialize_compressed(&mut bytes).unwrap();
+    let prover = CheckpointingFibProver {
+        resume_from: Some(bytes),
+        ..CheckpointingFibProver::new(options)
+    };
+    prover
+        .prove_with_rng(fib_trace(2048, &options), &mut rng())
+        .unwrap()
+}
+
+fn serialized(proof: &Proof<FibAir>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn resuming_from_each_checkpoint_produces_the_same_proof() {
+    for options in [options(), options().with_zk()] {
+        let prover = CheckpointingFibProver::new(options);
+        let proof = prover
+            .prove_with_rng(fib_trace(2048, &options), &mut rng())
+            .unwrap();
+        let checkpoints = prover.checkpoints.into_inner().unwrap();
+        assert_eq!(serialized(&prove_fib(options, 2048)), serialized(&proof));
+
+        // base trace, composition trace and at least one FRI layer
+        assert!(checkpoints.len() > 2);
+        for (i, bytes) in checkpoints.iter().enumerate() {
+            let checkpoint = checkpoint(bytes);
+            assert_eq!(i.saturating_sub(1), checkpoint.fri_layers.len());
+            let resumed_proof = resume(options, &checkpoint);
+            assert_eq!(serialized(&proof), serialized(&resumed_proof));
+            resumed_proof.verify().unwrap();
+        }
+    }
+}
+
+#[test]
+fn checkpoint_for_a_different_trace_is_ignored() {
+    let options = options();
+    let prover = CheckpointingFibProver::new(options);
+    prover
+        .prove_with_rng(fib_trace(2048, &options), &mut rng())
+        .unwrap();
+    let checkpoints = prover.checkpoints.into_inner().unwrap();
+    // same dimensions and public inputs but a different witness
+    let mut forged = checkpoint(checkpoints.last().unwrap());
+    forged.base_trace.as_mut().unwrap().polys.0[0][1] += Fp::one();
+    let mut other_trace = fib_trace(2048, &options);
+    other_trace.0[0][1] += Fp::one();
+
+    // resuming from the forged checkpoint would produce an invalid proof
+    assert!(resume(options, &forged).verify().is_err());
+
+    forged.trace_digest = trace_digest::<Sha256, _>(other_trace.base_columns(), None);
+    resume(options, &forged).verify().unwrap();
+}
@@ -0,0 +1,154 @@
+//! A small Fibonacci AIR shared by the tests that prove and verify end to end
+
+#![allow(dead_code)]
+
+use ark_ff::One;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+/// Two columns `(a, b)` with `a' = b` and `b' = a + b` starting at `(1, 1)`
+/// followed by randomizer rows
+pub struct FibTrace(pub Matrix<Fp>, pub usize);
+
+impl FibTrace {
+    /// Value of `b` in the last row before the randomizer rows
+    pub fn result(&self) -> Fp {
+        self.0[1][self.len() - 1 - self.1]
+    }
+}
+
+impl Trace for FibTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 2;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+
+    fn num_randomizer_rows(&self) -> usize {
+        self.1
+    }
+}
+
+#[derive(Clone)]
+pub struct FibAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    result: Fp,
+}
+
+impl Air for FibAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        FibAir {
+            options,
+            trace_info,
+            result,
+        }
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        &self.result
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn num_randomizer_rows(&self) -> usize {
+        self.options.min_randomizer_rows()
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        vec![
+            TransitionConstraint::new(0.next() - 1.curr()),
+            TransitionConstraint::new(1.next() - 0.curr() - 1.curr()),
+        ]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        let last_row = self.trace_len() - 1 - self.num_randomizer_rows();
+        let one = FieldConstant::Fp(Fp::one());
+        vec![
+            Assertion::single(0, 0, one),
+            Assertion::single(1, 0, one),
+            Assertion::single(1, last_row, FieldConstant::Fp(self.result)),
+        ]
+    }
+}
+
+pub struct FibProver(pub ProofOptions);
+
+impl Prover for FibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        FibProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+}
+
+/// Fibonacci trace with `n` rows and enough randomizer rows for `options`
+pub fn fib_trace(n: usize, options: &ProofOptions) -> FibTrace {
+    let mut a = Vec::with_capacity_in(n, PageAlignedAllocator);
+    let mut b = Vec::with_capacity_in(n, PageAlignedAllocator);
+    let (mut x, mut y) = (Fp::one(), Fp::one());
+    for _ in 0..n {
+        a.push(x);
+        b.push(y);
+        (x, y) = (y, x + y);
+    }
+    FibTrace(Matrix::new(vec![a, b]), options.min_randomizer_rows())
+}
+
+pub fn options() -> ProofOptions {
+    ProofOptions::new(16, 4, 0, 2, 8)
+}
+
+/// Seeded so proofs are reproducible
+pub fn rng() -> ChaCha20Rng {
+    ChaCha20Rng::seed_from_u64(0)
+}
+
+pub fn prove_fib(options: ProofOptions, n: usize) -> Proof<FibAir> {
+    FibProver::new(options)
+        .prove_with_rng(fib_trace(n, &options), &mut rng())
+        .unwrap()
+}
@@ -11,23 +11,36 @@ use ark_poly::Polynomial;
 use ark_poly::Radix2EvaluationDomain;
 use ark_std::rand::seq::SliceRandom;
 use ark_std::rand::Rng;
-use core::marker::PhantomData;
 use gpu_poly::allocator::PageAlignedAllocator;
 use gpu_poly::fields::p18446744069414584321::Fp;
 use gpu_poly::GpuFftField;
 use gpu_poly::GpuField;
+use ministark::assertions::Assertion;
+use ministark::challenges::Challenges;
 use ministark::constraints::AlgebraicExpression;
 use ministark::constraints::ExecutionTraceColumn;
 use ministark::constraints::FieldConstant;
+use ministark::constraints::FieldType;
+use ministark::constraints::Periodic;
+use ministark::constraints::TransitionConstraint;
 use ministark::constraints::VerifierChallenge;
+use ministark::divisor::Divisor;
+use ministark::periodic::PeriodicColumn;
+use ministark::selectors;
+use ministark::trace::Padding;
 use ministark::utils;
 use ministark::Air;
 use ministark::Matrix;
 use ministark::ProofOptions;
 use ministark::StarkExtensionOf;
+use ministark::Trace;
 use ministark::TraceInfo;
 
-struct TestAir<Fp, Fq = Fp>(TraceInfo, ProofOptions, PhantomData<(Fp, Fq)>);
+struct TestAir<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp> = Fp>(
+    TraceInfo,
+    ProofOptions,
+    Vec<AlgebraicExpression<Fp, Fq>>,
+);
 
 impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Air for TestAir<Fp, Fq> {
     type Fp = Fp;
@@ -35,7 +48,7 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Air for TestAir<Fp, F
     type PublicInputs = ();
 
     fn new(info: TraceInfo, _: Self::PublicInputs, options: ProofOptions) -> Self {
-        TestAir(info, options, PhantomData)
+        TestAir(info, options, Vec::new())
     }
 
     fn pub_inputs(&self) -> &Self::PublicInputs {
@@ -50,8 +63,16 @@ impl<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>> Air for TestAir<Fp, F
         &self.1
     }
 
+    fn num_randomizer_rows(&self) -> usize {
+        self.0.num_randomizer_rows
+    }
+
+    fn padding(&self) -> (Padding, usize) {
+        (self.0.padding, self.0.num_padding_rows)
+    }
+
     fn constraints(&self) -> Vec<AlgebraicExpression<Self::Fp, Self::Fq>> {
-        todo!()
+        self.2.clone()
     }
 }
 
@@ -97,6 +118,218 @@ fn constraint_with_challenges() {
         .is_zero());
 }
 
+#[test]
+fn constraint_with_named_columns() {
+    #[derive(Clone, Copy)]
+    enum Column {
+        A,
+        B,
+    }
+    #[derive(Clone, Copy)]
+    enum Challenge {
+        Alpha,
+    }
+    ministark::impl_execution_trace_column!(Column, 1);
+    ministark::impl_verifier_challenge!(Challenge);
+
+    let named: AlgebraicExpression<Fp> =
+        (Challenge::Alpha.challenge() - Column::A.curr()) * Column::B.next();
+    let indexed: AlgebraicExpression<Fp> = (0.challenge() - 1.curr()) * 2.next();
+
+    assert_eq!(indexed.to_string(), named.to_string());
+}
+
+#[test]
+fn challenges_can_be_looked_up_by_label() {
+    let challenges =
+        Challenges::from(vec![Fp::from(3), Fp::from(5)]).with_labels(vec!["alpha", "gamma"]);
+
+    assert_eq!(Fp::from(5), challenges.by_label("gamma"));
+    assert_eq!(Some(0), challenges.index_of("alpha"));
+    assert_eq!(None, challenges.index_of("beta"));
+}
+
+#[test]
+#[should_panic]
+fn challenge_labels_must_be_unique() {
+    Challenges::<Fp>::from(vec![Fp::one(), Fp::one()]).with_labels(vec!["alpha", "alpha"]);
+}
+
+#[test]
+#[should_panic]
+fn challenges_must_have_one_label_each() {
+    Challenges::<Fp>::from(vec![Fp::one(), Fp::one()]).with_labels(vec!["alpha"]);
+}
+
+#[test]
+fn constraint_system_is_exported_as_sexpr() {
+    let n = 2048;
+    let trace_info = TraceInfo::new(1, 0, n, None);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let mut air = TestAir::<Fp>::new(trace_info, (), options);
+    air.2 = vec![0.next() - 0.curr()];
+
+    let export = air.constraint_system();
+
+    assert!(export.starts_with("(air\n  (trace-len 2048)\n"));
+    assert!(export.contains("(columns (base 1) (extension 0) (preprocessed 0))"));
+    assert!(export.contains("(constraint 0 (degree 2047) (+ (trace 0 1) (- (trace 0 0))))"));
+    assert_eq!(export, air.constraint_system());
+}
+
+#[test]
+fn verification_key_pins_constraints_and_options() {
+    let trace_info = TraceInfo::new(1, 0, 2048, None);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let mut air = TestAir::<Fp>::new(trace_info.clone(), (), options);
+    air.2 = vec![0.next() - 0.curr()];
+    let mut zk_air = TestAir::<Fp>::new(trace_info, (), options.with_zk());
+    zk_air.2 = air.2.clone();
+
+    let key = air.verification_key();
+
+    assert_eq!(key, air.verification_key());
+    assert_ne!(key, zk_air.verification_key());
+    air.2 = vec![0.next() + 0.curr()];
+    assert_ne!(key, air.verification_key());
+}
+
+#[test]
+fn constraints_split_into_numerator_and_divisor() {
+    use AlgebraicExpression::X;
+    let one = FieldConstant::Fp(Fp::one());
+    let constraint: AlgebraicExpression<Fp> = (0.next() - 0.curr()) / (X - one);
+
+    let (numerator, divisor) = constraint.split_divisor();
+
+    assert_eq!("(+ (trace 0 1) (- (trace 0 0)))", numerator.to_sexpr());
+    assert_eq!(
+        format!("(^ {} -1)", (X - one).to_sexpr()),
+        divisor.unwrap().to_sexpr()
+    );
+    assert!(0.curr::<Fp, Fp>().split_divisor().1.is_none());
+}
+
+#[test]
+fn constraint_with_challenge_powers_and_products() {
+    let mut rng = ark_std::test_rng();
+    let alpha: AlgebraicExpression<Fp> = 0.challenge();
+    let beta: AlgebraicExpression<Fp> = 1.challenge();
+    let constraint = (alpha.pow(3) * beta - 0.curr()) / alpha;
+    let challenges = [Fp::rand(&mut rng), Fp::rand(&mut rng)];
+    let x = FieldConstant::Fp(Fp::rand(&mut rng));
+    let trace_value = FieldConstant::Fp(Fp::rand(&mut rng));
+
+    let folded =
+        constraint.evaluate_challenges(&|_| unreachable!(), &|i| FieldConstant::Fp(challenges[i]));
+
+    assert_eq!((4, 1), constraint.challenge_degree());
+    assert_eq!((0, 0), folded.challenge_degree());
+    assert_eq!(constraint.degree(7), folded.degree(7));
+    let eval = |expression: &AlgebraicExpression<Fp>| {
+        expression
+            .eval(
+                &x,
+                &|_| unreachable!(),
+                &|i| FieldConstant::Fp(challenges[i]),
+                &|_, _| trace_value,
+            )
+            .as_fq()
+    };
+    assert_eq!(eval(&constraint), eval(&folded));
+}
+
+#[test]
+fn zk_adds_a_composition_mask_column() {
+    let n = 2048;
+    let trace_info = TraceInfo::new(1, 0, n, None);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let air = TestAir::<Fp>::new(trace_info.clone(), (), options);
+    let zk_air = TestAir::<Fp>::new(trace_info, (), options.with_zk());
+
+    assert_eq!(air.ce_blowup_factor(), air.num_composition_trace_columns());
+    assert_eq!(
+        zk_air.ce_blowup_factor() + 1,
+        zk_air.num_composition_trace_columns()
+    );
+}
+
+#[test]
+fn only_zk_and_zero_padding_exempt_transitions() {
+    let trace_info = TraceInfo::new(1, 0, 2048, None).with_randomizer_rows(4);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let repeat = trace_info.clone().with_padding(Padding::RepeatLastRow, 48);
+    let zero = trace_info.with_padding(Padding::ZeroWithExemptions, 48);
+    let exemptions = |trace_info: &TraceInfo, options| {
+        TestAir::<Fp>::new(trace_info.clone(), (), options).num_transition_exemptions()
+    };
+
+    assert_eq!(0, exemptions(&repeat, options));
+    assert_eq!(4, exemptions(&repeat, options.with_zk()));
+    assert_eq!(48, exemptions(&zero, options.with_zk()));
+}
+
+#[test]
+fn ce_blowup_factor_fits_highest_degree_constraint() {
+    use AlgebraicExpression::X;
+    let n = 2048;
+    let trace_info = TraceInfo::new(1, 0, n, None);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let mut air = TestAir::<Fp>::new(trace_info, (), options);
+    let one = FieldConstant::Fp(Fp::one());
+    let vanishing = X.pow(n) - one;
+
+    air.2 = vec![(0.next() - 0.curr().pow(2)) / vanishing.clone()];
+    assert_eq!(vec![n - 2], air.constraint_degrees());
+    assert_eq!(1, air.ce_blowup_factor());
+
+    air.2.push(0.curr().pow(3) / vanishing);
+    assert_eq!(vec![n - 2, 2 * n - 3], air.constraint_degrees());
+    assert_eq!(2, air.ce_blowup_factor());
+
+    air.2.push(0.curr().pow(5));
+    assert_eq!(8, air.ce_blowup_factor());
+}
+
+#[test]
+fn validate_trace_reports_failing_row() {
+    struct CounterTrace(Matrix<Fp>);
+
+    impl Trace for CounterTrace {
+        const NUM_BASE_COLUMNS: usize = 1;
+        type Fp = Fp;
+        type Fq = Fp;
+
+        fn base_columns(&self) -> &Matrix<Fp> {
+            &self.0
+        }
+    }
+
+    let n = 2048;
+    let mut column = (0..n as u64).map(Fp::from).collect::<Vec<Fp>>();
+    let trace_info = TraceInfo::new(1, 0, n, None);
+    let options = ProofOptions::new(32, 8, 0, 2, 2);
+    let mut air = TestAir::<Fp>::new(trace_info, (), options);
+    let one = FieldConstant::Fp(Fp::one());
+    let trace_domain = air.trace_domain();
+    air.2 = vec![Divisor::transition(n, 1).divide(0.next() - 0.curr() - one, trace_domain)];
+    let trace = CounterTrace(Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]));
+    assert!(air.validate_trace(&trace).is_ok());
+
+    column[5] += Fp::one();
+    let trace = CounterTrace(Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]));
+    let unsatisfied = air.validate_trace(&trace).unwrap_err();
+
+    assert_eq!(0, unsatisfied.constraint);
+    assert_eq!(4, unsatisfied.row);
+    // `a' - a - 1` is split into its terms
+    assert_eq!(3, unsatisfied.terms.len());
+    assert!(unsatisfied
+        .values
+        .iter()
+        .any(|(name, value)| name == "Trace(0) at offset 1" && value.as_fq() == Fp::from(6)));
+}
+
 #[test]
 fn symbolic_evaluation_with_challenges() {
     let n = 2048;
@@ -124,6 +357,41 @@ fn symbolic_evaluation_with_challenges() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn fused_program_matches_eval() {
+    let mut rng = ark_std::test_rng();
+    let n = 8;
+    let x = Fp::rand(&mut rng);
+    let challenges = [Fp::rand(&mut rng), Fp::rand(&mut rng)];
+    let columns = [
+        [(); 8].map(|_| Fp::rand(&mut rng)),
+        [(); 8].map(|_| Fp::rand(&mut rng)),
+    ];
+    let shared = 0.curr() - 0.challenge();
+    let constraint: AlgebraicExpression<Fp> = shared.clone() * shared.pow(3)
+        + (1.next() - 1.challenge()) / (AlgebraicExpression::X - FieldConstant::Fp(Fp::one()))
+        - 0.offset(-1) * 1.curr();
+
+    let program = constraint
+        .compile(
+            &|_| unreachable!(),
+            &|i| FieldConstant::Fp(challenges[i]),
+            &|i| (FieldType::Fp, i),
+            1,
+            n,
+        )
+        .unwrap();
+    let actual = program.eval(x, &|_, col, shift| FieldConstant::Fp(columns[col][shift]));
+    let expected = constraint.eval(
+        &FieldConstant::Fp(x),
+        &|_| unreachable!(),
+        &|i| FieldConstant::Fp(challenges[i]),
+        &|col, offset| FieldConstant::Fp(columns[col][offset.rem_euclid(n as isize) as usize]),
+    );
+
+    assert_eq!(expected.as_fq(), actual);
+}
+
 #[test]
 fn constraint_multiplication() {
     let zero = FieldConstant::Fp(Fp::zero());
@@ -287,6 +555,65 @@ fn evaluate_permutation_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn evaluate_permutation_constraint_over_quartic_extension() {
+    use gpu_poly::fields::p18446744069414584321::Fq4;
+    let n = 2048;
+    let mut rng = ark_std::test_rng();
+    let original_col = (0..n).map(|_| Fq4::rand(&mut rng)).collect::<Vec<Fq4>>();
+    let mut shuffled_col = original_col.clone();
+    shuffled_col.shuffle(&mut rng);
+    let challenge = Fq4::rand(&mut rng); // verifier challenge
+    let running_product = |col: &[Fq4]| {
+        col.iter()
+            .scan(Fq4::one(), |product, v| {
+                let ret = *product;
+                *product *= challenge - v;
+                Some(ret)
+            })
+            .collect::<Vec<Fq4>>()
+    };
+    let original_product = running_product(&original_col);
+    let shuffled_product = running_product(&shuffled_col);
+    let matrix = Matrix::new(vec![
+        original_col.to_vec_in(PageAlignedAllocator),
+        shuffled_col.to_vec_in(PageAlignedAllocator),
+        original_product.to_vec_in(PageAlignedAllocator),
+        shuffled_product.to_vec_in(PageAlignedAllocator),
+    ]);
+    let alpha = 0; // first verifier challenge
+    let constraints: Vec<AlgebraicExpression<Fp, Fq4>> = vec![
+        2.curr() * (alpha.challenge() - 0.curr()) - 2.next(),
+        3.curr() * (alpha.challenge() - 1.curr()) - 3.next(),
+    ];
+    let blowup = 2;
+    let trace_domain = Radix2EvaluationDomain::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    let poly_matrix = matrix.interpolate(trace_domain);
+    let lde_matrix = poly_matrix.evaluate(lde_domain);
+
+    for constraint in constraints {
+        let (numerator_degree, denominator_degree) = constraint.degree(n - 1);
+        let degree_bound = numerator_degree - denominator_degree;
+        assert!(degree_bound < lde_domain.size());
+        let constraint_eval = evaluate_symbolic(
+            lde_domain,
+            blowup,
+            &[],
+            &[challenge],
+            &constraint,
+            &lde_matrix,
+        );
+        let constraint_eval_poly = constraint_eval.interpolate(lde_domain);
+
+        // extension arithmetic must not inflate the degree past the symbolic bound
+        assert!(constraint_eval_poly[0][degree_bound + 1..]
+            .iter()
+            .all(Fq4::is_zero));
+        assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
+    }
+}
+
 #[test]
 fn evaluate_zerofier_constraint() {
     // TODO: clean up this testcase
@@ -331,6 +658,266 @@ fn evaluate_zerofier_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn periodic_column_low_degree_extension() {
+    let mut rng = ark_std::test_rng();
+    let trace_len = 64;
+    let column = PeriodicColumn::new((0..8).map(|_| Fp::rand(&mut rng)).collect());
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(trace_len * 4, Fp::GENERATOR).unwrap();
+
+    let lde = column.evaluate(trace_len, lde_domain);
+
+    for (i, x) in trace_domain.elements().enumerate() {
+        assert_eq!(column.get(i), column.evaluate_at(x, trace_len));
+    }
+    for (x, y) in lde_domain.elements().zip(&lde) {
+        assert_eq!(column.evaluate_at(x, trace_len), *y);
+    }
+}
+
+#[test]
+fn constraint_with_periodic_column() {
+    let column = PeriodicColumn::new(vec![Fp::from(3u32), Fp::from(5u32)]);
+    let constraint: AlgebraicExpression<Fp> = 0.curr() - 0.periodic();
+    let x = Radix2EvaluationDomain::<Fp>::new(4).unwrap().element(1);
+
+    let eval = constraint
+        .substitute_periodic(&mut |i| {
+            assert_eq!(0, i);
+            AlgebraicExpression::Constant(FieldConstant::Fp(column.evaluate_at(x, 4)))
+        })
+        .eval(
+            &FieldConstant::Fp(x),
+            &|_| unreachable!(),
+            &|_| unreachable!(),
+            &|_, _| FieldConstant::Fp(Fp::from(5u32)),
+        );
+
+    assert!(eval.is_zero());
+}
+
+#[test]
+fn assertion_constraints_are_low_degree() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let blowup = 4;
+    let seven = FieldConstant::Fp(Fp::from(7u32));
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    let assertions = [
+        Assertion::single(0, 5, seven),
+        Assertion::periodic(0, 3, 4, seven),
+        Assertion::range(0, 2..6, seven),
+        Assertion::range(0, 1..16, seven),
+    ];
+
+    for assertion in assertions {
+        let num_steps = (0..n).filter(|&i| assertion.contains(i)).count();
+        let column = (0..n)
+            .map(|i| match assertion.contains(i) {
+                true => Fp::from(7u32),
+                false => Fp::rand(&mut rng),
+            })
+            .collect::<Vec<Fp>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+        let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+        let constraint = assertion.into_constraint(trace_domain);
+
+        let constraint_eval =
+            evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+
+        let poly = DensePolynomial::from_coefficients_vec(
+            constraint_eval.interpolate(lde_domain).0[0].to_vec(),
+        );
+        assert!(
+            poly.degree() < n - num_steps,
+            "{assertion:?} is not low degree"
+        );
+    }
+}
+
+#[test]
+fn transition_constraint_with_lookahead() {
+    let n = 16;
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    // `a[i+2] = a[i+1] + a[i]` doesn't hold in the last two rows since the rows
+    // they reference wrap around to the start of the trace
+    let mut column = vec![Fp::one(), Fp::one()];
+    for i in 2..n {
+        column.push(column[i - 1] + column[i - 2]);
+    }
+    let matrix = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+    let transition = TransitionConstraint::new(0.offset(2) - 0.next() - 0.curr());
+    let constraint = transition.into_constraint(trace_domain);
+
+    let constraint_eval = evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+
+    assert_eq!(2, transition.num_exemptions);
+    let poly = DensePolynomial::from_coefficients_vec(
+        constraint_eval.interpolate(lde_domain).0[0].to_vec(),
+    );
+    assert!(poly.degree() < n - 2);
+}
+
+#[test]
+fn transition_constraint_with_wide_frame() {
+    let n = 64;
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    // `a[i+16] = a[i] + 1`
+    let column = (0..n)
+        .map(|i| Fp::from((i / 16) as u32))
+        .collect::<Vec<Fp>>();
+    let matrix = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+    let one = FieldConstant::Fp(Fp::one());
+    let transition = TransitionConstraint::new(0.offset(16) - 0.curr() - one);
+    let constraint = transition.into_constraint(trace_domain);
+
+    let constraint_eval = evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+
+    assert_eq!(16, transition.num_exemptions);
+    let poly = DensePolynomial::from_coefficients_vec(
+        constraint_eval.interpolate(lde_domain).0[0].to_vec(),
+    );
+    assert!(poly.degree() < n - 16);
+}
+
+#[test]
+fn transition_constraint_with_lookback() {
+    let n = 16;
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    // `a[i] = a[i-1] + a[i-2]` doesn't hold in the first two rows since the rows
+    // they reference wrap around to the end of the trace
+    let mut column = vec![Fp::one(), Fp::one()];
+    for i in 2..n {
+        column.push(column[i - 1] + column[i - 2]);
+    }
+    let matrix = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+    let transition = TransitionConstraint::new(0.curr() - 0.offset(-1) - 0.offset(-2));
+    let constraint = transition.into_constraint(trace_domain);
+
+    let constraint_eval = evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+
+    assert_eq!(2, transition.num_leading_exemptions);
+    let poly = DensePolynomial::from_coefficients_vec(
+        constraint_eval.interpolate(lde_domain).0[0].to_vec(),
+    );
+    assert!(poly.degree() < n);
+}
+
+#[test]
+fn transition_constraint_with_custom_divisor() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    // `a[i+1] = 2 * a[i]` only holds on even rows
+    let mut column = Vec::new();
+    for _ in 0..n / 2 {
+        let value = Fp::rand(&mut rng);
+        column.push(value);
+        column.push(value.double());
+    }
+    let matrix = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+    let two = FieldConstant::Fp(Fp::from(2u32));
+    let transition =
+        TransitionConstraint::new(0.next() - 0.curr() * two).with_divisor(Divisor::periodic(0, 2));
+    let constraint = transition.into_constraint(trace_domain);
+
+    let constraint_eval = evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+
+    let poly = DensePolynomial::from_coefficients_vec(
+        constraint_eval.interpolate(lde_domain).0[0].to_vec(),
+    );
+    assert!(poly.degree() < n / 2);
+}
+
+#[test]
+fn divisor_evaluation_matches_evaluate_at() {
+    let n = 16;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * 4, Fp::GENERATOR).unwrap();
+    let one = FieldConstant::<Fp>::Fp(Fp::one());
+    let divisors = [
+        Divisor::periodic(1, 4).with_row(2).with_exemption(5),
+        Divisor::transition(n, 3),
+        // runs of consecutive rows are evaluated incrementally
+        Assertion::range(0, 2..7, one).divisor(n),
+        Assertion::range(0, 1..14, one).divisor(n),
+        Assertion::range(0, 0..16, one).divisor(n),
+    ];
+
+    for divisor in divisors {
+        let evaluations = divisor.evaluate(trace_domain, lde_domain);
+
+        for (x, evaluation) in lde_domain.elements().zip(evaluations) {
+            assert_eq!(
+                divisor.evaluate_at(x, trace_domain),
+                evaluation,
+                "{divisor:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn row_selector_is_lagrange_basis_polynomial() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let x = Fp::rand(&mut rng);
+    let selector: AlgebraicExpression<Fp> = selectors::row_selector(5, trace_domain);
+
+    let eval = selector.eval(
+        &FieldConstant::Fp(x),
+        &|_| unreachable!(),
+        &|_| unreachable!(),
+        &|_, _| unreachable!(),
+    );
+
+    let expected = trace_domain.evaluate_all_lagrange_coefficients(x)[5];
+    assert_eq!(expected, eval.as_fq());
+    assert_eq!((n, 1), selector.degree(n - 1));
+}
+
+#[test]
+fn periodic_selector_flags_rows_of_each_period() {
+    let selector = selectors::periodic_selector::<Fp>(4, &[0, 3]);
+
+    let values = (0..8).map(|i| selector.get(i)).collect::<Vec<Fp>>();
+
+    let (zero, one) = (Fp::zero(), Fp::one());
+    assert_eq!(vec![one, zero, zero, one, one, zero, zero, one], values);
+}
+
+#[test]
+fn stride_selector_is_one_on_multiples_of_the_stride() {
+    let n = 16;
+    let selector = selectors::stride_selector::<Fp>(4);
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+
+    let values = trace_domain
+        .elements()
+        .map(|x| selector.evaluate_at(x, n))
+        .collect::<Vec<Fp>>();
+
+    let expected = (0..n)
+        .map(|i| if i % 4 == 0 { Fp::one() } else { Fp::zero() })
+        .collect::<Vec<Fp>>();
+    assert_eq!(expected, values);
+}
+
 /// Generates a matrix of fibbonacci sequence across two columns i.e.
 /// ┌───────┬───────┐
 /// │ Col 0 | Col 1 │
@@ -0,0 +1,184 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use common::options;
+use common::rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::assertions::PublicCell;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::continuation::prove_segments;
+use ministark::continuation::split_into_segments;
+use ministark::continuation::verify_continuation;
+use ministark::continuation::ContinuationError;
+use ministark::continuation::SegmentBoundary;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+const SEGMENT_LEN: usize = 2048;
+const NUM_SEGMENTS: usize = 3;
+
+/// Segment `.1` of a counter column
+struct SegmentTrace(Matrix<Fp>, usize);
+
+impl Trace for SegmentTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 1;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+/// Counter segment whose boundary is only asserted if `ASSERTS` is true
+struct CounterAir<const ASSERTS: bool> {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary: SegmentBoundary<Fp>,
+}
+
+impl<const ASSERTS: bool> Air for CounterAir<ASSERTS> {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = SegmentBoundary<Fp>;
+
+    fn new(trace_info: TraceInfo, boundary: SegmentBoundary<Fp>, options: ProofOptions) -> Self {
+        CounterAir {
+            options,
+            trace_info,
+            boundary,
+        }
+    }
+
+    fn pub_inputs(&self) -> &SegmentBoundary<Fp> {
+        &self.boundary
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        let one = FieldConstant::Fp(Fp::one());
+        vec![TransitionConstraint::new(0.next() - 0.curr() - one)]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        Vec::new()
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell<Fp>> {
+        if ASSERTS {
+            self.boundary.public_cells(self.trace_len() - 1)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+struct CounterProver<const ASSERTS: bool>(ProofOptions);
+
+impl<const ASSERTS: bool> Prover for CounterProver<ASSERTS> {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = CounterAir<ASSERTS>;
+    type Trace = SegmentTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CounterProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &SegmentTrace) -> SegmentBoundary<Fp> {
+        SegmentBoundary::of_segment(trace.1, &trace.0, trace.len() - 1)
+    }
+}
+
+/// Counter that exactly fills `NUM_SEGMENTS` segments
+fn counter_segments() -> Vec<SegmentTrace> {
+    let n = SEGMENT_LEN + (SEGMENT_LEN - 1) * (NUM_SEGMENTS - 1);
+    let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+    column.extend((0..n as u64).map(Fp::from));
+    split_into_segments(&Matrix::new(vec![column]), SEGMENT_LEN)
+        .into_iter()
+        .enumerate()
+        .map(|(i, segment)| SegmentTrace(segment, i))
+        .collect()
+}
+
+#[test]
+fn segments_start_at_the_last_row_of_the_previous_segment() {
+    let column = (0..20u64).map(Fp::from).collect::<Vec<Fp>>();
+    let trace = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+
+    let segments = split_into_segments(&trace, 8);
+
+    assert_eq!(3, segments.len());
+    let boundaries = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| SegmentBoundary::of_segment(i, segment, 7))
+        .collect::<Vec<SegmentBoundary<Fp>>>();
+    assert!(boundaries[0].links_to(&boundaries[1]));
+    assert!(boundaries[1].links_to(&boundaries[2]));
+    assert!(!boundaries[0].links_to(&boundaries[2]));
+    assert_eq!(vec![Fp::from(19u64)], boundaries[2].last_row);
+}
+
+#[test]
+fn continuation_of_segment_proofs_verifies() {
+    let prover = CounterProver::<true>::new(options());
+    let proofs = prove_segments(&prover, counter_segments(), &mut rng()).unwrap();
+
+    let boundaries = verify_continuation(proofs).unwrap();
+
+    assert_eq!(NUM_SEGMENTS, boundaries.len());
+    let last_value = (SEGMENT_LEN - 1) * NUM_SEGMENTS;
+    assert_eq!(vec![Fp::from(last_value as u64)], boundaries[2].last_row);
+}
+
+#[test]
+fn continuation_with_unasserted_boundary_is_rejected() {
+    let prover = CounterProver::<false>::new(options());
+    let proofs = prove_segments(&prover, counter_segments(), &mut rng()).unwrap();
+
+    assert!(matches!(
+        verify_continuation(proofs),
+        Err(ContinuationError::UnassertedBoundary { index: 0 })
+    ));
+}
+
+#[test]
+fn continuation_with_reordered_segments_is_rejected() {
+    let prover = CounterProver::<true>::new(options());
+    let mut proofs = prove_segments(&prover, counter_segments(), &mut rng()).unwrap();
+    proofs.swap(1, 2);
+
+    assert!(matches!(
+        verify_continuation(proofs),
+        Err(ContinuationError::BrokenLink { index: 1 })
+    ));
+}
@@ -0,0 +1,609 @@
+#![feature(allocator_api)]
+
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::UniformRand;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::DenseUVPolynomial;
+use ark_poly::EvaluationDomain;
+use ark_poly::Polynomial;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::prelude::*;
+use gpu_poly::utils::bit_reverse;
+use ministark::fri;
+use ministark::fri::EvaluationOrder;
+use ministark::fri::FoldingSchedule;
+use ministark::fri::FriOptions;
+use ministark::fri::FriProver;
+use ministark::fri::FriVerifier;
+use ministark::fri::LdtProver;
+use ministark::fri::LdtVerifier;
+use ministark::fri::VerificationError;
+use ministark::random::label;
+use ministark::random::DigestPublicCoin;
+use ministark::random::PublicCoin;
+use sha2::Sha256;
+
+struct TestChannel {
+    public_coin: DigestPublicCoin<Sha256>,
+    num_layers: usize,
+}
+
+impl fri::ProverChannel<Fp> for TestChannel {
+    type Digest = Sha256;
+
+    fn commit_fri_layer(&mut self, commitment: &[u8]) {
+        let label = label::fri_layer(self.num_layers);
+        self.public_coin.reseed_with_label(&label, &commitment);
+        self.num_layers += 1;
+    }
+
+    fn draw_fri_alpha(&mut self) -> Fp {
+        self.public_coin.draw()
+    }
+
+    fn commit_fri_remainder(&mut self, coeffs: &[Fp]) {
+        let label = label::fri_layer(self.num_layers);
+        self.public_coin.reseed_with_label(&label, &coeffs);
+    }
+}
+
+fn prove_and_verify(
+    prover_options: FriOptions,
+    verifier_options: FriOptions,
+) -> Result<usize, VerificationError> {
+    prove_and_verify_with(prover_options, verifier_options, |_| {}, |_| {})
+}
+
+/// Allows tampering with the serialized proof and queried evaluations before
+/// verification
+fn prove_and_verify_with(
+    prover_options: FriOptions,
+    verifier_options: FriOptions,
+    tamper_proof: impl FnOnce(&mut [u8]),
+    tamper_evaluations: impl FnOnce(&mut [Fp]),
+) -> Result<usize, VerificationError> {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let blowup_factor = 4;
+    let poly = DensePolynomial::<Fp>::rand(max_degree, &mut rng);
+    let domain_size = (max_degree + 1) * blowup_factor;
+    let domain = Radix2EvaluationDomain::new_coset(domain_size, Fp::GENERATOR).unwrap();
+    let evaluations = domain.fft(&poly.coeffs);
+    let positions = [1, 7, 100, 300, 1023];
+    let mut queried_evaluations = positions.map(|i| evaluations[i]);
+    tamper_evaluations(&mut queried_evaluations);
+
+    let mut channel = TestChannel {
+        public_coin: DigestPublicCoin::new(b"fri"),
+        num_layers: 0,
+    };
+    let mut prover = FriProver::<Fp, Sha256>::new(prover_options);
+    prover.build_layers(&mut channel, evaluations.to_vec_in(PageAlignedAllocator));
+    let proof = prover.into_proof(&positions);
+    let proof_size = proof.compressed_size();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    tamper_proof(&mut proof_bytes);
+    let proof = fri::FriProof::deserialize_compressed(&*proof_bytes).unwrap();
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"fri");
+    let verifier =
+        FriVerifier::<Fp, Sha256>::new(&mut public_coin, verifier_options, proof, max_degree)?;
+    verifier.verify(&positions, &queried_evaluations)?;
+    Ok(proof_size)
+}
+
+#[test]
+fn fri_with_folding_schedule() {
+    let schedule = FoldingSchedule::new(&[8, 4]);
+    let options = FriOptions::new(4, 2, 8).with_folding_schedule(schedule);
+    assert_eq!(4, options.num_layers(1024));
+    assert_eq!(8, options.layer_folding_factor(0));
+    assert_eq!(4, options.layer_folding_factor(1));
+    assert_eq!(2, options.layer_folding_factor(2));
+
+    assert!(prove_and_verify(options, options).is_ok());
+}
+
+#[test]
+fn fri_verifier_enforces_folding_schedule() {
+    let schedule = FoldingSchedule::new(&[8, 4]);
+    let prover_options = FriOptions::new(4, 2, 8).with_folding_schedule(schedule);
+    let verifier_options = FriOptions::new(4, 2, 8);
+
+    assert!(matches!(
+        prove_and_verify(prover_options, verifier_options),
+        Err(VerificationError::NumLayersMismatch {
+            expected: 7,
+            actual: 4
+        })
+    ));
+}
+
+#[test]
+fn fri_verifier_rejects_unsupported_folding_factor() {
+    let prover_options = FriOptions::new(4, 2, 8);
+    let verifier_options = FriOptions::new(4, 1, 8);
+
+    assert!(matches!(
+        prove_and_verify(prover_options, verifier_options),
+        Err(VerificationError::UnsupportedFoldingFactor { folding_factor: 1 })
+    ));
+}
+
+#[test]
+fn fri_with_remainder_coeffs() {
+    let options = FriOptions::new(4, 2, 64);
+    let coeffs_options = options.with_remainder_coeffs();
+
+    let evals_proof_size = prove_and_verify(options, options).unwrap();
+    let coeffs_proof_size = prove_and_verify(coeffs_options, coeffs_options).unwrap();
+
+    assert!(coeffs_proof_size < evals_proof_size);
+    assert!(prove_and_verify(coeffs_options, options).is_err());
+}
+
+fn prove_and_verify_batched(poly_degrees: &[usize]) -> Result<(), VerificationError> {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let options = FriOptions::new(4, 4, 16);
+    let domain = Radix2EvaluationDomain::new_coset(1024, Fp::GENERATOR).unwrap();
+    let codewords = poly_degrees
+        .iter()
+        .map(|&degree| {
+            let poly = DensePolynomial::<Fp>::rand(degree, &mut rng);
+            domain.fft(&poly.coeffs).to_vec_in(PageAlignedAllocator)
+        })
+        .collect::<Vec<_>>();
+    let positions = [3, 64, 511, 700];
+    let queried_evaluations = codewords
+        .iter()
+        .map(|codeword| positions.iter().map(|&i| codeword[i]).collect())
+        .collect::<Vec<Vec<Fp>>>();
+
+    let mut channel = TestChannel {
+        public_coin: DigestPublicCoin::new(b"batched-fri"),
+        num_layers: 0,
+    };
+    let mut prover = FriProver::<Fp, Sha256>::new(options);
+    prover.build_batched_layers(&mut channel, codewords);
+    let proof = prover.into_proof(&positions);
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"batched-fri");
+    let verifier =
+        FriVerifier::<Fp, Sha256>::new_batched(&mut public_coin, options, proof, max_degree)?;
+    verifier.verify_batched(&positions, &queried_evaluations)
+}
+
+#[test]
+fn batched_fri() {
+    assert!(prove_and_verify_batched(&[255, 100, 255]).is_ok());
+}
+
+#[test]
+fn batched_fri_rejects_high_degree_polynomial() {
+    assert!(prove_and_verify_batched(&[255, 511, 255]).is_err());
+}
+
+/// Runs FRI on a polynomial of degree `poly_degree` with a degree bound of 255
+fn prove_and_verify_degree(poly_degree: usize) -> Result<(), VerificationError> {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let options = FriOptions::new(4, 4, 16);
+    let domain = Radix2EvaluationDomain::new_coset(1024, Fp::GENERATOR).unwrap();
+    let poly = DensePolynomial::<Fp>::rand(poly_degree, &mut rng);
+    let evaluations = domain.fft(&poly.coeffs);
+    let positions = [3, 64, 511, 700];
+    let queried_evaluations = positions.map(|i| evaluations[i]);
+
+    let mut channel = TestChannel {
+        public_coin: DigestPublicCoin::new(b"fri-degree"),
+        num_layers: 0,
+    };
+    let mut prover = FriProver::<Fp, Sha256>::new(options);
+    prover.build_layers(&mut channel, evaluations.to_vec_in(PageAlignedAllocator));
+    let proof = prover.into_proof(&positions);
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"fri-degree");
+    let verifier = FriVerifier::<Fp, Sha256>::new(&mut public_coin, options, proof, max_degree)?;
+    verifier.verify(&positions, &queried_evaluations)
+}
+
+#[test]
+fn fri_remainder_respects_degree_bound() {
+    assert!(prove_and_verify_degree(255).is_ok());
+    // the remainder is low degree relative to its domain but not the bound
+    assert!(prove_and_verify_degree(511).is_err());
+}
+
+#[test]
+fn estimated_proof_size_bounds_fri_proof_size() {
+    for options in [
+        FriOptions::new(4, 2, 8),
+        FriOptions::new(4, 4, 64).with_remainder_coeffs(),
+        FriOptions::new(4, 2, 8).with_folding_schedule(FoldingSchedule::new(&[16])),
+        FriOptions::new(4, 2, 8).with_merkle_cap_height(3),
+    ] {
+        let proof_size = prove_and_verify(options, options).unwrap();
+        let estimated_size = options.estimated_proof_size::<Fp, Sha256>(1024, 5);
+        assert!(proof_size <= estimated_size);
+    }
+}
+
+#[test]
+fn apply_drp_matches_folded_polynomial() {
+    let mut rng = ark_std::test_rng();
+    let n = 4096;
+    let folding_factor = 4;
+    let poly = DensePolynomial::<Fp>::rand(n / 4 - 1, &mut rng);
+    let alpha = Fp::rand(&mut rng);
+    let domain = Radix2EvaluationDomain::new_coset(n, Fp::GENERATOR).unwrap();
+    let evaluations = domain.fft(&poly.coeffs).to_vec_in(PageAlignedAllocator);
+
+    let drp = fri::apply_drp(evaluations, Fp::GENERATOR, alpha, folding_factor);
+
+    let alpha_powers = [Fp::ONE, alpha, alpha * alpha, alpha * alpha * alpha];
+    let folded_coeffs = poly
+        .coeffs
+        .chunks(folding_factor)
+        .map(|chunk| chunk.iter().zip(alpha_powers).map(|(c, a)| *c * a).sum())
+        .collect::<Vec<Fp>>();
+    let folded_offset = Fp::GENERATOR.pow([folding_factor as u64]);
+    let folded_domain =
+        Radix2EvaluationDomain::new_coset(n / folding_factor, folded_offset).unwrap();
+    assert_eq!(folded_domain.fft(&folded_coeffs), drp.to_vec());
+}
+
+#[test]
+fn apply_drp_bit_reversed_matches_apply_drp() {
+    let mut rng = ark_std::test_rng();
+    let n = 1024;
+    let poly = DensePolynomial::<Fp>::rand(n / 4 - 1, &mut rng);
+    let alpha = Fp::rand(&mut rng);
+    let domain = Radix2EvaluationDomain::new_coset(n, Fp::GENERATOR).unwrap();
+    let evaluations = domain.fft(&poly.coeffs).to_vec_in(PageAlignedAllocator);
+    let mut bit_reversed_evaluations = evaluations.to_vec_in(PageAlignedAllocator);
+    bit_reverse(&mut bit_reversed_evaluations);
+
+    let mut drp = fri::apply_drp(evaluations, Fp::GENERATOR, alpha, 4);
+    bit_reverse(&mut drp);
+    let bit_reversed_drp =
+        fri::apply_drp_bit_reversed::<Fp, 4>(&bit_reversed_evaluations, Fp::GENERATOR, alpha);
+
+    assert_eq!(drp.to_vec(), bit_reversed_drp.to_vec());
+}
+
+#[test]
+fn fri_with_bit_reversed_evaluations() {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let num_queries = 20;
+    let poly = DensePolynomial::<Fp>::rand(max_degree, &mut rng);
+    let domain = Radix2EvaluationDomain::new_coset(1024, Fp::GENERATOR).unwrap();
+    let mut evaluations = domain.fft(&poly.coeffs).to_vec_in(PageAlignedAllocator);
+    bit_reverse(&mut evaluations);
+
+    for options in [
+        FriOptions::new(4, 2, 16),
+        FriOptions::new(4, 4, 64).with_remainder_coeffs(),
+    ] {
+        let options = options.with_evaluation_order(EvaluationOrder::BitReversed);
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"bit-reversed");
+        let proof = fri::prove_low_degree::<Fp, Sha256>(
+            evaluations.to_vec_in(PageAlignedAllocator),
+            options,
+            num_queries,
+            &mut public_coin,
+        );
+
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"bit-reversed");
+        let natural_options = options.with_evaluation_order(EvaluationOrder::Natural);
+        assert!(fri::verify_low_degree::<Fp, Sha256>(
+            proof.clone(),
+            natural_options,
+            num_queries,
+            max_degree,
+            &mut public_coin,
+        )
+        .is_err());
+
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"bit-reversed");
+        assert!(fri::verify_low_degree::<Fp, Sha256>(
+            proof,
+            options,
+            num_queries,
+            max_degree,
+            &mut public_coin,
+        )
+        .is_ok());
+    }
+}
+
+#[test]
+fn circle_low_degree_proof() {
+    use gpu_poly::fields::p2147483647::Fp as M31;
+    use ministark::domain::CircleDomain;
+    let mut rng = ark_std::test_rng();
+    let num_coeffs = 64;
+    let blowup_factor = 4;
+    let num_queries = 20;
+    let domain = CircleDomain::new(num_coeffs * blowup_factor);
+    let low_degree = (0..num_coeffs).map(|_| M31::rand(&mut rng)).collect();
+    let high_degree = (0..4 * num_coeffs).map(|_| M31::rand(&mut rng)).collect();
+
+    for (coeffs, is_low_degree) in [(low_degree, true), (high_degree, false)] {
+        let mut evaluations = Vec::new_in(PageAlignedAllocator);
+        evaluations.extend::<Vec<M31>>(coeffs);
+        domain.fft_in_place(&mut evaluations);
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"circle-low-degree");
+        let proof = fri::prove_circle_low_degree::<M31, Sha256>(
+            &evaluations,
+            domain,
+            blowup_factor,
+            num_queries,
+            &mut public_coin,
+        );
+
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let proof = fri::CircleLowDegreeProof::<M31>::deserialize_compressed(&*bytes).unwrap();
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"circle-low-degree");
+        let result = fri::verify_circle_low_degree::<M31, Sha256>(
+            proof,
+            domain,
+            blowup_factor,
+            num_queries,
+            &mut public_coin,
+        );
+        assert_eq!(is_low_degree, result.is_ok());
+    }
+}
+
+/// Low-degree test that sends the polynomial in the clear
+struct PolynomialLdt {
+    options: FriOptions,
+    coeffs: Vec<Fp>,
+    domain: Option<Radix2EvaluationDomain<Fp>>,
+}
+
+impl LdtProver<Fp, Sha256> for PolynomialLdt {
+    type Proof = Vec<Fp>;
+
+    fn new(options: FriOptions) -> Self {
+        PolynomialLdt {
+            options,
+            coeffs: Vec::new(),
+            domain: None,
+        }
+    }
+
+    fn build_layers(
+        &mut self,
+        channel: &mut impl fri::ProverChannel<Fp, Digest = Sha256>,
+        evaluations: GpuVec<Fp>,
+    ) {
+        let offset = self.options.domain_offset::<Fp>();
+        let domain = Radix2EvaluationDomain::new_coset(evaluations.len(), offset).unwrap();
+        let mut coeffs = domain.ifft(&evaluations);
+        coeffs.truncate(evaluations.len() / self.options.blowup_factor());
+        channel.commit_fri_remainder(&coeffs);
+        self.coeffs = coeffs;
+    }
+
+    fn into_proof(self, _positions: &[usize]) -> Vec<Fp> {
+        self.coeffs
+    }
+}
+
+impl LdtVerifier<Fp, Sha256> for PolynomialLdt {
+    type Proof = Vec<Fp>;
+    type Prover = PolynomialLdt;
+
+    fn new(
+        public_coin: &mut impl PublicCoin,
+        options: FriOptions,
+        proof: Vec<Fp>,
+        max_poly_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        if proof.len() > max_poly_degree + 1 {
+            return Err(VerificationError::RemainderDegreeMismatch {
+                degree: max_poly_degree,
+            });
+        }
+        public_coin.reseed_with_label(&label::fri_layer(0), &proof);
+        let domain_size = (max_poly_degree + 1).next_power_of_two() * options.blowup_factor();
+        let offset = options.domain_offset::<Fp>();
+        let domain = Radix2EvaluationDomain::new_coset(domain_size, offset).unwrap();
+        Ok(PolynomialLdt {
+            options,
+            coeffs: proof,
+            domain: Some(domain),
+        })
+    }
+
+    fn verify(self, positions: &[usize], evaluations: &[Fp]) -> Result<(), VerificationError> {
+        let domain = self.domain.unwrap();
+        let poly = DensePolynomial::from_coefficients_vec(self.coeffs);
+        for (&position, &evaluation) in positions.iter().zip(evaluations) {
+            if poly.evaluate(&domain.element(position)) != evaluation {
+                return Err(VerificationError::InvalidRemainderDegreeRespectingProjection);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_low_degree_test() {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let options = FriOptions::new(4, 2, 8);
+    let poly = DensePolynomial::<Fp>::rand(max_degree, &mut rng);
+    let domain = Radix2EvaluationDomain::new_coset(1024, Fp::GENERATOR).unwrap();
+    let evaluations = domain.fft(&poly.coeffs);
+    let positions = [1, 7, 100, 300, 1023];
+    let mut queried_evaluations = positions.map(|i| evaluations[i]);
+
+    let mut channel = TestChannel {
+        public_coin: DigestPublicCoin::new(b"ldt"),
+        num_layers: 0,
+    };
+    let mut prover = <PolynomialLdt as LdtProver<Fp, Sha256>>::new(options);
+    prover.build_layers(&mut channel, evaluations.to_vec_in(PageAlignedAllocator));
+    let proof = prover.into_proof(&positions);
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"ldt");
+    let verifier = <PolynomialLdt as LdtVerifier<Fp, Sha256>>::new(
+        &mut public_coin,
+        options,
+        proof.clone(),
+        max_degree,
+    )
+    .unwrap();
+    assert!(verifier.verify(&positions, &queried_evaluations).is_ok());
+
+    queried_evaluations[2] += Fp::ONE;
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"ldt");
+    let verifier = <PolynomialLdt as LdtVerifier<Fp, Sha256>>::new(
+        &mut public_coin,
+        options,
+        proof,
+        max_degree,
+    )
+    .unwrap();
+    assert!(verifier.verify(&positions, &queried_evaluations).is_err());
+}
+
+#[test]
+fn standalone_low_degree_proof() {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let num_queries = 20;
+    let options = FriOptions::new(4, 4, 16);
+    let domain = Radix2EvaluationDomain::new_coset(1024, Fp::GENERATOR).unwrap();
+    let low_degree = DensePolynomial::<Fp>::rand(max_degree, &mut rng);
+    let high_degree = DensePolynomial::<Fp>::rand(4 * max_degree, &mut rng);
+
+    for (poly, is_low_degree) in [(low_degree, true), (high_degree, false)] {
+        let evaluations = domain.fft(&poly.coeffs).to_vec_in(PageAlignedAllocator);
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"low-degree");
+        let proof = fri::prove_low_degree::<Fp, Sha256>(
+            evaluations,
+            options,
+            num_queries,
+            &mut public_coin,
+        );
+
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let proof = fri::LowDegreeProof::<Fp>::deserialize_compressed(&*bytes).unwrap();
+        let mut public_coin = DigestPublicCoin::<Sha256>::new(b"low-degree");
+        let result = fri::verify_low_degree::<Fp, Sha256>(
+            proof,
+            options,
+            num_queries,
+            max_degree,
+            &mut public_coin,
+        );
+        assert_eq!(is_low_degree, result.is_ok());
+    }
+}
+
+#[test]
+fn fri_with_merkle_caps() {
+    let options = FriOptions::new(4, 2, 8);
+    let capped_options = options.with_merkle_cap_height(3);
+
+    let proof_size = prove_and_verify(options, options).unwrap();
+    let capped_proof_size = prove_and_verify(capped_options, capped_options).unwrap();
+
+    assert!(capped_proof_size < proof_size);
+    assert!(prove_and_verify(capped_options, options).is_err());
+}
+
+#[test]
+fn fri_with_merkle_arity() {
+    let options = FriOptions::new(4, 2, 8);
+    let arity_options = options.with_merkle_arity(8);
+
+    let proof_size = prove_and_verify(options, options).unwrap();
+    let arity_proof_size = prove_and_verify(arity_options, arity_options).unwrap();
+
+    assert_ne!(arity_proof_size, proof_size);
+    assert!(prove_and_verify(arity_options, options).is_err());
+}
+
+#[test]
+fn fri_remainder_commitment_uses_merkle_arity() {
+    // the committed remainder has more leaves than the arity so the remainder
+    // root depends on the arity
+    let options = FriOptions::new(4, 2, 64).with_merkle_arity(8);
+
+    assert!(prove_and_verify(options, options).is_ok());
+}
+
+#[test]
+fn fri_verifies_all_folding_factors() {
+    for folding_factor in [2, 4, 8, 16] {
+        let options = FriOptions::new(4, folding_factor, 64);
+        assert!(prove_and_verify(options, options).is_ok());
+
+        // remainder commitment is the last field of the proof
+        let tamper_proof = |proof: &mut [u8]| *proof.last_mut().unwrap() ^= 1;
+        assert!(prove_and_verify_with(options, options, tamper_proof, |_| {}).is_err());
+
+        let tamper_evaluations = |evaluations: &mut [Fp]| evaluations[2] += Fp::ONE;
+        assert!(matches!(
+            prove_and_verify_with(options, options, |_| {}, tamper_evaluations),
+            Err(VerificationError::InvalidDegreeRespectingProjection { layer: 0, query: 2 })
+        ));
+    }
+}
+
+#[test]
+fn fri_early_stop_on_low_degree_polynomial() {
+    let mut rng = ark_std::test_rng();
+    let max_degree = 255;
+    let domain_size = 1024;
+    let poly = DensePolynomial::<Fp>::rand(63, &mut rng);
+    let domain = Radix2EvaluationDomain::new_coset(domain_size, Fp::GENERATOR).unwrap();
+    let evaluations = domain.fft(&poly.coeffs);
+    let positions = [1, 7, 100, 300, 1023];
+    let queried_evaluations = positions.map(|i| evaluations[i]);
+    let options = FriOptions::new(4, 2, 16);
+    let early_stop_options = options.with_early_stop();
+    assert_eq!(6, options.num_layers(domain_size));
+    assert_eq!(2, early_stop_options.num_early_stop_layers(domain_size, 64));
+
+    let mut channel = TestChannel {
+        public_coin: DigestPublicCoin::new(b"fri"),
+        num_layers: 0,
+    };
+    let mut prover = FriProver::<Fp, Sha256>::new(early_stop_options);
+    prover.build_layers(&mut channel, evaluations.to_vec_in(PageAlignedAllocator));
+    let proof = prover.into_proof(&positions);
+    assert_eq!(2, channel.num_layers);
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"fri");
+    let verifier = FriVerifier::<Fp, Sha256>::new(
+        &mut public_coin,
+        early_stop_options,
+        proof.clone(),
+        max_degree,
+    )
+    .unwrap();
+    assert!(verifier.verify(&positions, &queried_evaluations).is_ok());
+
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"fri");
+    assert!(matches!(
+        FriVerifier::<Fp, Sha256>::new(&mut public_coin, options, proof, max_degree),
+        Err(VerificationError::NumLayersMismatch {
+            expected: 6,
+            actual: 2
+        })
+    ));
+}
@@ -0,0 +1,167 @@
+#![feature(allocator_api)]
+use ark_ff::FftField;
+use ark_ff::One;
+use ark_ff::UniformRand;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::DenseUVPolynomial;
+use ark_poly::EvaluationDomain;
+use ark_poly::Polynomial;
+use ark_poly::Radix2EvaluationDomain;
+use ark_std::rand::seq::SliceRandom;
+use ark_std::rand::Rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::AssertionSteps;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::FieldConstant;
+use ministark::gadgets;
+use ministark::gadgets::LookupArgument;
+use ministark::gadgets::PermutationArgument;
+use ministark::Matrix;
+
+#[test]
+fn permutation_argument_constraint_holds_for_permutations() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let lhs = (0..n).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+    let mut rhs = lhs.clone();
+    rhs.shuffle(&mut rng);
+
+    assert!(permutation_constraint_degree(&mut rng, lhs, rhs) < n);
+}
+
+#[test]
+fn permutation_argument_constraint_fails_otherwise() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let lhs = (0..n).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+    let mut rhs = lhs.clone();
+    rhs[3] += Fp::one();
+
+    assert!(permutation_constraint_degree(&mut rng, lhs, rhs) >= n);
+}
+
+#[test]
+fn lookup_argument_constraint_holds_for_range_checks() {
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let challenges = Challenges::new(&mut rng, 1);
+    let argument = LookupArgument::new(vec![0, 1], 2, 3, 4, 0);
+    let values = [0; 2].map(|_| {
+        (0..n)
+            .map(|_| Fp::from(rng.gen_range(0..n as u64)))
+            .collect::<Vec<Fp>>()
+    });
+    let table = (0..n as u64).map(Fp::from).collect::<Vec<Fp>>();
+    let multiplicities = LookupArgument::multiplicities(&[&values[0], &values[1]], &table);
+    let mut base_trace = Matrix::new(
+        [&values[0], &values[1], &table, &multiplicities]
+            .map(|column| column.to_vec_in(PageAlignedAllocator))
+            .into(),
+    );
+    let running_sum = argument.build_column(&base_trace, &challenges);
+    let trace = Matrix::join(vec![base_trace.clone(), running_sum]);
+    let constraint = argument.constraints(n).remove(0);
+
+    // the running sum times two value denominators and the table denominator
+    // has degree 4(n-1) so the quotient has degree 3n-4
+    assert!(constraint_degree(&trace, &constraint, &challenges) < 3 * n);
+
+    // claim a table value is looked up one more time than it is
+    base_trace[3][5] += Fp::one();
+    let running_sum = argument.build_column(&base_trace, &challenges);
+    let trace = Matrix::join(vec![base_trace, running_sum]);
+    assert!(constraint_degree(&trace, &constraint, &challenges) >= 3 * n);
+}
+
+#[test]
+fn range_check_constraints_hold_for_values_in_range() {
+    let mut rng = ark_std::test_rng();
+    let n = 32;
+    let bits = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let challenges = Challenges::new(&mut rng, 1);
+    let range_check = gadgets::range_check(0, bits, 1, 2, 3, 0);
+    let values = (0..n)
+        .map(|_| Fp::from(rng.gen_range(0..1u64 << bits)))
+        .collect::<Vec<Fp>>();
+    let table = range_check.table::<Fp>(n);
+    let multiplicities = range_check.multiplicities(&values);
+    let base_trace = Matrix::new(
+        [&values, &table, &multiplicities]
+            .map(|column| column.to_vec_in(PageAlignedAllocator))
+            .into(),
+    );
+    let running_sum = range_check.build_column(&base_trace, &challenges);
+    let trace = Matrix::join(vec![base_trace, running_sum]);
+
+    let assertions = range_check.assertions::<Fp, Fp>(n);
+    let transition = range_check.transition_constraints().remove(0);
+    let constraints = [
+        range_check.constraints(n).remove(0),
+        transition.into_constraint(trace_domain),
+    ];
+    for assertion in assertions {
+        let AssertionSteps::Single(step) = assertion.steps else {
+            unreachable!()
+        };
+        assert_eq!(assertion.value.as_fq(), trace[assertion.column][step]);
+    }
+    for constraint in constraints {
+        assert!(constraint_degree(&trace, &constraint, &challenges) < 2 * n);
+    }
+}
+
+/// Returns the degree of the permutation argument constraint. Challenges are
+/// drawn from `rng` so they differ from the permuted values.
+fn permutation_constraint_degree(rng: &mut impl Rng, lhs: Vec<Fp>, rhs: Vec<Fp>) -> usize {
+    let n = lhs.len();
+    let challenges = Challenges::<Fp>::new(rng, 2);
+    let argument = PermutationArgument::new(vec![0], vec![1], 2, 0, 1);
+    let base_trace = Matrix::new(vec![
+        lhs.to_vec_in(PageAlignedAllocator),
+        rhs.to_vec_in(PageAlignedAllocator),
+    ]);
+    let grand_product = argument.build_column(&base_trace, &challenges);
+    assert!(grand_product[0][0].is_one());
+    let trace = Matrix::join(vec![base_trace, grand_product]);
+    let constraint = argument.constraints(n).remove(0);
+    constraint_degree(&trace, &constraint, &challenges)
+}
+
+/// Returns the degree of `constraint` evaluated over a low degree extension
+/// of `trace`
+fn constraint_degree(
+    trace: &Matrix<Fp>,
+    constraint: &AlgebraicExpression<Fp>,
+    challenges: &Challenges<Fp>,
+) -> usize {
+    let n = trace.num_rows();
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    let lde = trace.interpolate(trace_domain).evaluate(lde_domain);
+
+    let lde_size = lde_domain.size() as isize;
+    let xs = lde_domain.elements().collect::<Vec<Fp>>();
+    let mut evals = Vec::with_capacity_in(xs.len(), PageAlignedAllocator);
+    for (i, x) in xs.into_iter().enumerate() {
+        let eval = constraint.eval(
+            &FieldConstant::Fp(x),
+            &|_| unreachable!(),
+            &|c| FieldConstant::Fp(challenges[c]),
+            &|col, offset| {
+                let pos = (i as isize + blowup as isize * offset).rem_euclid(lde_size);
+                FieldConstant::Fp(lde[col][pos as usize])
+            },
+        );
+        evals.push(match eval {
+            FieldConstant::Fp(v) => v,
+            FieldConstant::Fq(v) => v,
+        });
+    }
+    let evals = Matrix::new(vec![evals]);
+    let poly = DensePolynomial::from_coefficients_vec(evals.interpolate(lde_domain).0[0].to_vec());
+    poly.degree()
+}
@@ -0,0 +1,29 @@
+use gpu_poly::fields::p18446744069414584321::Fp as GoldilocksFp;
+use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp as Stark252Fp;
+use ministark::hash::Poseidon;
+use ministark::hash::Poseidon2;
+
+#[test]
+fn poseidon_round_numbers_depend_on_field() {
+    let goldilocks = Poseidon::<GoldilocksFp>::new();
+    let stark252 = Poseidon::<Stark252Fp>::new();
+
+    // Plonky2 uses the same round numbers for its width 12 Poseidon
+    assert_eq!(8, goldilocks.num_full_rounds());
+    assert_eq!(22, goldilocks.num_partial_rounds());
+    // the cubic S-box needs more partial rounds
+    assert_eq!(8, stark252.num_full_rounds());
+    assert_eq!(85, stark252.num_partial_rounds());
+}
+
+#[test]
+fn poseidon2_uses_poseidon_round_numbers() {
+    let poseidon = Poseidon::<Stark252Fp>::new();
+    let poseidon2 = Poseidon2::<Stark252Fp>::new();
+
+    assert_eq!(poseidon.num_full_rounds(), poseidon2.num_full_rounds());
+    assert_eq!(
+        poseidon.num_partial_rounds(),
+        poseidon2.num_partial_rounds()
+    );
+}
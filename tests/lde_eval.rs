@@ -15,6 +15,7 @@ use ark_std::rand::Rng;
 use gpu_poly::allocator::PageAlignedAllocator;
 use gpu_poly::fields::p18446744069414584321::Fp;
 use gpu_poly::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::Fp as Fp256;
+use gpu_poly::fields::p18446744069414584321::Fq2;
 use gpu_poly::fields::p18446744069414584321::Fq3;
 use gpu_poly::prelude::PLANNER;
 use gpu_poly::utils::buffer_no_copy;
@@ -40,7 +41,7 @@ fn evaluate_x_lde() {
     let lde_blowup_factor = 4;
     let trace_len = 2048;
     let trace_info = TraceInfo::new(1, 0, trace_len, None);
-    let options = ProofOptions::new(1, lde_blowup_factor, 0, 1, 1);
+    let options = ProofOptions::new(1, lde_blowup_factor, 0, 2, 1);
     let test_air = TestAir::<Fp>::new(trace_info, (), options);
     let lde_domain = test_air.lde_domain();
     let five = Fp::from(5u32);
@@ -66,7 +67,7 @@ fn evaluate_x_lde_with_fp_and_fq() {
     let lde_blowup_factor = 4;
     let trace_len = 2048;
     let trace_info = TraceInfo::new(1, 0, trace_len, None);
-    let options = ProofOptions::new(1, lde_blowup_factor, 0, 1, 1);
+    let options = ProofOptions::new(1, lde_blowup_factor, 0, 2, 1);
     let test_air = TestAir::<Fp, Fq3>::new(trace_info, (), options);
     let lde_domain = test_air.lde_domain();
     let five = Fp::from(5u32);
@@ -86,6 +87,33 @@ fn evaluate_x_lde_with_fp_and_fq() {
     }
 }
 
+#[test]
+#[cfg(feature = "gpu")]
+fn evaluate_x_lde_with_quadratic_extension() {
+    use AlgebraicExpression::*;
+    let lde_blowup_factor = 4;
+    let trace_len = 2048;
+    let trace_info = TraceInfo::new(1, 0, trace_len, None);
+    let options = ProofOptions::new(1, lde_blowup_factor, 0, 2, 1).with_extension_degree(2);
+    let test_air = TestAir::<Fp, Fq2>::new(trace_info, (), options);
+    let lde_domain = test_air.lde_domain();
+    let five = Fp::from(5u32);
+    let extension_element = Fq2::from_base_prime_field_elems(&[five, five]).unwrap();
+    let expr = Constant(FieldConstant::Fq(extension_element)) * X;
+
+    let result = lde_calculator(
+        &test_air,
+        expr,
+        &|_| unreachable!(),
+        &|_| unreachable!(),
+        &mut |_| unreachable!(),
+    );
+
+    for (i, (v, x)) in result.0[0].iter().zip(lde_domain.elements()).enumerate() {
+        assert_eq!(*v, Fq2::from(x) * extension_element, "mismatch at {i}");
+    }
+}
+
 #[test]
 #[cfg(feature = "gpu")]
 fn evaluate_x_inverse_lde() {
@@ -93,7 +121,7 @@ fn evaluate_x_inverse_lde() {
     let lde_blowup_factor = 4;
     let trace_len = 2048;
     let trace_info = TraceInfo::new(1, 0, trace_len, None);
-    let options = ProofOptions::new(1, lde_blowup_factor, 0, 1, 1);
+    let options = ProofOptions::new(1, lde_blowup_factor, 0, 2, 1);
     let test_air = TestAir::<Fp>::new(trace_info, (), options);
     let lde_domain = test_air.lde_domain();
     let expr: AlgebraicExpression<Fp> = Constant(FieldConstant::Fp(Fp::one())) / X;
@@ -120,7 +148,7 @@ fn evaluate_trace_lde() {
     let trace_len = 2048;
     let n = trace_len * lde_blowup_factor;
     let trace_info = TraceInfo::new(1, 0, trace_len, None);
-    let options = ProofOptions::new(1, lde_blowup_factor as u8, 0, 1, 1);
+    let options = ProofOptions::new(1, lde_blowup_factor as u8, 0, 2, 1);
     let test_air = TestAir::<Fp>::new(trace_info, (), options);
     let trace = gen_fib_matrix(n);
     let expr: AlgebraicExpression<Fp> =
@@ -152,7 +180,7 @@ fn evaluate_constant_lde() {
     let trace_len = 2048;
     let n = trace_len * lde_blowup_factor;
     let trace_info = TraceInfo::new(1, 0, trace_len, None);
-    let options = ProofOptions::new(1, lde_blowup_factor as u8, 0, 1, 1);
+    let options = ProofOptions::new(1, lde_blowup_factor as u8, 0, 2, 1);
     let test_air = TestAir::<Fp256>::new(trace_info, (), options);
     let trace = Matrix::new(vec![vec![Fp256::one(); n].to_vec_in(PageAlignedAllocator)]);
     let one = AlgebraicExpression::Constant(FieldConstant::Fp(Fp256::one()));
@@ -0,0 +1,265 @@
+#![feature(allocator_api)]
+use ark_ff::FftField;
+use ark_ff::One;
+use ark_ff::UniformRand;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::BufferPool;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::matrix::GroupColumn;
+use ministark::matrix::MatrixGroup;
+use ministark::Matrix;
+use sha2::Sha256;
+
+#[test]
+fn matrix_group_get_column() {
+    let base = Matrix::from_rows(vec![vec![Fp::one(), Fp::zero()]; 4]);
+    let extension = Matrix::from_rows(vec![vec![Fq3::one()]; 4]);
+    let mut group = MatrixGroup::new();
+    group.append_fp(&base);
+    group.append_fq(&extension);
+
+    assert_eq!(3, group.num_cols());
+    assert!(matches!(group.get_column(0), Some(GroupColumn::Fp(col)) if col[0].is_one()));
+    assert!(matches!(group.get_column(1), Some(GroupColumn::Fp(col)) if col[0].is_zero()));
+    assert!(matches!(group.get_column(2), Some(GroupColumn::Fq(col)) if col[0].is_one()));
+    assert!(group.get_column(3).is_none());
+}
+
+#[test]
+fn sum_columns_reuses_pooled_buffers() {
+    let pool = BufferPool::new();
+    let matrix = Matrix::from_rows(vec![vec![Fp::one(), Fp::one()]; 4]);
+    let copy = matrix.clone_in(&pool);
+    copy.recycle(&pool);
+    assert_eq!(2, pool.len());
+
+    let sum = matrix.sum_columns_in(&pool);
+
+    assert_eq!(1, pool.len());
+    assert!(sum.0[0].iter().all(|v| *v == Fp::from(2u8)));
+}
+
+#[test]
+fn sum_columns_reduces_modulo_prime() {
+    let mut rng = ark_std::test_rng();
+    let rows = (0..4099)
+        .map(|_| (0..5).map(|_| -Fp::rand(&mut rng)).collect::<Vec<Fp>>())
+        .collect::<Vec<_>>();
+    let expected = rows
+        .iter()
+        .map(|row| row.iter().sum::<Fp>())
+        .collect::<Vec<_>>();
+    let matrix = Matrix::from_rows(rows);
+
+    let sum = matrix.sum_columns();
+
+    assert_eq!(expected, sum.0[0].to_vec());
+}
+
+#[test]
+fn streaming_lde_commitment_matches_full_lde() {
+    let mut rng = ark_std::test_rng();
+    let n = 2048;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * 4, Fp::GENERATOR).unwrap();
+    let rows = (0..n)
+        .map(|_| vec![Fp::rand(&mut rng), Fp::rand(&mut rng)])
+        .collect();
+    let polys = Matrix::from_rows(rows).interpolate(trace_domain);
+
+    let expected = polys.evaluate(lde_domain).commit_to_rows::<Sha256>();
+    let actual = polys.commit_to_lde_rows::<Sha256>(trace_domain, lde_domain);
+
+    assert_eq!(expected.root(), actual.root());
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn blake3_commitment_matches_row_hashes() {
+    use ark_serialize::CanonicalSerialize;
+    use digest::Digest;
+    use ministark::merkle::MerkleTree;
+    use ministark::Blake3;
+    let mut rng = ark_std::test_rng();
+    // wide enough to take the multi-threaded row hashing path
+    let num_cols = 20_000;
+    let rows = (0..4)
+        .map(|_| {
+            (0..num_cols)
+                .map(|_| Fp::rand(&mut rng))
+                .collect::<Vec<Fp>>()
+        })
+        .collect::<Vec<Vec<Fp>>>();
+    let row_hashes = rows
+        .iter()
+        .map(|row| {
+            let mut bytes = Vec::new();
+            row.serialize_compressed(&mut bytes).unwrap();
+            Blake3::new_with_prefix(&bytes).finalize()
+        })
+        .collect();
+    let expected = MerkleTree::<Blake3>::new(row_hashes).unwrap();
+
+    let actual = Matrix::from_rows(rows).commit_to_rows::<Blake3>();
+
+    assert_eq!(expected.root(), actual.root());
+}
+
+#[test]
+fn babybear_extension_lde_roundtrip() {
+    use gpu_poly::fields::p2013265921::Fq4;
+    let mut rng = ark_std::test_rng();
+    let n = 2048;
+    let domain = Radix2EvaluationDomain::new(n).unwrap();
+    let rows = (0..n)
+        .map(|_| vec![Fq4::rand(&mut rng)])
+        .collect::<Vec<_>>();
+    let evals = Matrix::from_rows(rows);
+
+    let polys = evals.interpolate(domain);
+
+    assert_eq!(evals.0[0].to_vec(), polys.evaluate(domain).0[0].to_vec());
+}
+
+#[test]
+fn mixed_radix_lde_row_hashes_match_full_lde() {
+    use ark_poly::MixedRadixEvaluationDomain;
+    use gpu_poly::fields::p2013265921::Fp;
+    use ministark::merkle::CompressedLeafEncoder;
+    let mut rng = ark_std::test_rng();
+    // not a power of two
+    let n = 3 * 64;
+    let trace_domain = MixedRadixEvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = MixedRadixEvaluationDomain::<Fp>::new_coset(n * 4, Fp::GENERATOR).unwrap();
+    let rows = (0..n).map(|_| vec![Fp::rand(&mut rng)]).collect::<Vec<_>>();
+    let evals = Matrix::from_rows(rows);
+    let polys = evals.interpolate(trace_domain);
+
+    // the rows can't be committed to since the LDE isn't a power of two
+    let expected = polys
+        .evaluate(lde_domain)
+        .hash_rows::<Sha256, CompressedLeafEncoder>();
+    let actual = polys.hash_lde_rows::<Sha256, CompressedLeafEncoder>(trace_domain, lde_domain);
+
+    assert_eq!(n, trace_domain.size());
+    assert_eq!(
+        evals.0[0].to_vec(),
+        polys.evaluate(trace_domain).0[0].to_vec()
+    );
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn m31_circle_lde_interpolates_to_padded_polynomial() {
+    use gpu_poly::fields::p2147483647::Fp as M31;
+    use ministark::domain::CircleDomain;
+    let mut rng = ark_std::test_rng();
+    let n = 16;
+    let blowup = 4;
+    let trace_domain = CircleDomain::new(n);
+    let lde_domain = CircleDomain::new(n * blowup);
+    let rows = (0..n).map(|_| vec![M31::rand(&mut rng)]).collect();
+    let evals = Matrix::from_rows(rows);
+
+    let polys = evals.interpolate(trace_domain);
+    let lde = polys.evaluate(lde_domain);
+
+    // the lde interpolates to the zero padded trace polynomial
+    let mut padded_coeffs = polys.0[0].to_vec();
+    padded_coeffs.resize(n * blowup, M31::zero());
+    assert_eq!(padded_coeffs, lde.interpolate(lde_domain).0[0].to_vec());
+    assert_eq!(
+        evals.0[0].to_vec(),
+        polys.evaluate(trace_domain).0[0].to_vec()
+    );
+}
+
+#[test]
+fn hash_rows_native_matches_little_endian_encoding() {
+    use digest::Digest;
+    use ministark::merkle::LeafEncoder;
+    use ministark::merkle::LittleEndianLeafEncoder;
+    let mut rng = ark_std::test_rng();
+    let rows = (0..300)
+        .map(|_| vec![Fq3::rand(&mut rng), Fq3::rand(&mut rng)])
+        .collect::<Vec<Vec<Fq3>>>();
+    let expected = rows
+        .iter()
+        .map(|row| {
+            let mut bytes = Vec::new();
+            LittleEndianLeafEncoder::encode_row(row, &mut bytes);
+            Sha256::new_with_prefix(bytes).finalize()
+        })
+        .collect::<Vec<_>>();
+
+    let actual = Matrix::from_rows(rows).hash_rows_native::<Sha256>();
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn row_major_ingestion_matches_from_rows() {
+    let mut rng = ark_std::test_rng();
+    let num_cols = 11;
+    let values = (0..num_cols * 64)
+        .map(|_| Fp::rand(&mut rng))
+        .collect::<Vec<Fp>>();
+    let rows = values
+        .chunks(num_cols)
+        .map(<[Fp]>::to_vec)
+        .collect::<Vec<Vec<Fp>>>();
+
+    let row_major = Matrix::from_row_major(&values, num_cols);
+    let row_iter = Matrix::from_row_iter(values.chunks(num_cols), num_cols);
+
+    assert_eq!(Matrix::from_rows(rows).0, row_major.0);
+    assert_eq!(row_major.0, row_iter.0);
+}
+
+#[test]
+fn matrix_serialization_roundtrip() {
+    let mut rng = ark_std::test_rng();
+    let rows = (0..16)
+        .map(|_| vec![Fp::rand(&mut rng), Fp::rand(&mut rng)])
+        .collect::<Vec<Vec<Fp>>>();
+    let matrix = Matrix::from_rows(rows);
+    let mut bytes = Vec::new();
+
+    matrix.serialize_compressed(&mut bytes).unwrap();
+    let deserialized = Matrix::<Fp>::deserialize_compressed(&bytes[..]).unwrap();
+
+    assert_eq!(matrix.0, deserialized.0);
+}
+
+#[test]
+fn ragged_matrix_serialization_roundtrip() {
+    let mut rng = ark_std::test_rng();
+    let long = (0..16).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+    let short = (0..4).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+    let matrix = Matrix::new(vec![
+        long.to_vec_in(PageAlignedAllocator),
+        short.to_vec_in(PageAlignedAllocator),
+    ]);
+    let mut bytes = Vec::new();
+
+    matrix.serialize_compressed(&mut bytes).unwrap();
+    let deserialized = Matrix::<Fp>::deserialize_compressed(&bytes[..]).unwrap();
+
+    assert_eq!(matrix.0, deserialized.0);
+    assert_eq!(bytes.len(), matrix.compressed_size());
+}
+
+#[test]
+fn truncated_matrix_with_huge_length_fails_to_deserialize() {
+    let mut bytes = Vec::new();
+    1usize.serialize_compressed(&mut bytes).unwrap();
+    (usize::MAX / 2).serialize_compressed(&mut bytes).unwrap();
+
+    assert!(Matrix::<Fp>::deserialize_compressed(&bytes[..]).is_err());
+}
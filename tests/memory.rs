@@ -0,0 +1,141 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use common::fib_trace;
+use common::options;
+use common::rng;
+use common::FibAir;
+use common::FibTrace;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::prover::ProverEvent;
+use ministark::prover::ProvingPhase;
+use ministark::ProofOptions;
+use ministark::Prover;
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+/// Bytes allocated and the most allocated at once since the last reset
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the bytes allocated by the test including GPU buffers since
+/// `PageAlignedAllocator` allocates from the global allocator
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Records the peak bytes allocated during each phase on top of `baseline`
+struct MeasuredFibProver {
+    options: ProofOptions,
+    checkpointing: bool,
+    baseline: usize,
+    peaks: Mutex<Vec<(ProvingPhase, usize)>>,
+}
+
+impl Prover for MeasuredFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        MeasuredFibProver {
+            options,
+            checkpointing: false,
+            baseline: ALLOCATED.load(Ordering::SeqCst),
+            peaks: Mutex::new(Vec::with_capacity(16)),
+        }
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.options
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+
+    fn checkpointing(&self) -> bool {
+        self.checkpointing
+    }
+
+    fn on_event(&self, event: ProverEvent) {
+        match event {
+            ProverEvent::PhaseStarted(_) => {
+                PEAK.store(ALLOCATED.load(Ordering::SeqCst), Ordering::SeqCst);
+            }
+            ProverEvent::PhaseFinished(phase) => {
+                let peak = PEAK.load(Ordering::SeqCst).saturating_sub(self.baseline);
+                self.peaks.lock().unwrap().push((phase, peak));
+            }
+            _ => {}
+        }
+    }
+}
+
+// a single test so no other test allocates while allocations are measured
+#[test]
+fn estimated_memory_bounds_measured_allocations() {
+    let options = options();
+    let mut max_estimates = Vec::new();
+    // the first proof also sets up thread pools and other lazily allocated
+    // state so it isn't measured
+    MeasuredFibProver::new(options)
+        .prove_with_rng(fib_trace(1 << 14, &options), &mut rng())
+        .unwrap();
+    for checkpointing in [false, true] {
+        let prover = MeasuredFibProver {
+            checkpointing,
+            ..MeasuredFibProver::new(options)
+        };
+        let proof = prover
+            .prove_with_rng(fib_trace(1 << 14, &options), &mut rng())
+            .unwrap();
+        let estimates = prover.estimate_memory(&proof.trace_info, &options);
+        let peaks = prover.peaks.into_inner().unwrap();
+        proof.verify().unwrap();
+
+        assert_eq!(
+            estimates.iter().map(|e| e.phase).collect::<Vec<_>>(),
+            peaks.iter().map(|(phase, _)| *phase).collect::<Vec<_>>()
+        );
+        for (estimate, &(phase, peak)) in estimates.iter().zip(&peaks) {
+            assert!(
+                peak <= estimate.host_bytes,
+                "{phase:?} allocated {peak} bytes but {} were estimated",
+                estimate.host_bytes
+            );
+            assert!(estimate.device_bytes <= estimate.host_bytes);
+        }
+        // bounds but not loose ones
+        let max_peak = peaks.iter().map(|(_, peak)| *peak).max().unwrap();
+        let max_estimate = estimates.iter().map(|e| e.host_bytes).max().unwrap();
+        assert!(max_estimate < 4 * max_peak);
+        max_estimates.push(max_estimate);
+    }
+
+    // checkpoints copy leaves and FRI layers
+    assert!(max_estimates[0] < max_estimates[1]);
+}
@@ -1,5 +1,24 @@
+use ark_ff::One;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
 use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::commitment::VectorCommitment;
+use ministark::hash::PoseidonGoldilocks;
+use ministark::hash::DIGEST_SIZE;
+use ministark::merkle::salt_leaf;
+use ministark::merkle::verify_path;
+use ministark::merkle::AlgebraicDigest;
+use ministark::merkle::CompressedLeafEncoder;
+use ministark::merkle::HashFn;
+use ministark::merkle::LeafEncoder;
+use ministark::merkle::LeafSalts;
+use ministark::merkle::LittleEndianLeafEncoder;
 use ministark::merkle::MerkleTree;
+use ministark::merkle::MerkleTreeBuilder;
+use ministark::merkle::MontgomeryLeafEncoder;
+use ministark::Matrix;
+use ministark::ProofOptions;
 use sha2::Sha256;
 
 #[test]
@@ -17,6 +36,29 @@ fn merkle_verify() {
     assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i).is_ok());
 }
 
+#[test]
+fn merkle_verify_path() {
+    let leaf_nodes = (0..16u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let i = 11;
+
+    for arity in [2, 4] {
+        let tree = MerkleTree::<Sha256>::new_with_arity(leaf_nodes.clone(), arity).unwrap();
+        let proof = tree.prove(i).unwrap().parse::<Sha256>();
+        let path = &proof[1..];
+        let verify =
+            |index, leaf, arity| verify_path::<Sha256>(tree.root(), index, leaf, path, arity);
+
+        assert!(verify(i, &leaf_nodes[i], arity).is_ok());
+        assert!(verify(i, &leaf_nodes[i - 1], arity).is_err());
+        assert!(verify(i + 16, &leaf_nodes[i], arity).is_err());
+        assert!(verify(i, &leaf_nodes[i], 8).is_err());
+    }
+    let root = Sha256::new().finalize();
+    assert!(verify_path::<Sha256>(&root, 0, &leaf_nodes[0], &[], 3).is_err());
+}
+
 #[test]
 fn merkle_verify_large_tree() {
     let leaf_values = (0..1 << 10).collect::<Vec<usize>>();
@@ -31,3 +73,263 @@ fn merkle_verify_large_tree() {
 
     assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i).is_ok());
 }
+
+#[test]
+fn merkle_verify_cap() {
+    let leaf_nodes = (0..64u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes)
+        .unwrap()
+        .with_cap_height(2);
+    let cap = tree.cap();
+    let i = 42;
+    let proof = tree.prove(i).unwrap().parse::<Sha256>();
+
+    assert_eq!(4, cap.len());
+    // leaf, sibling and nodes below the cap
+    assert_eq!(2 + 6 - 1 - 2, proof.len());
+    assert!(MerkleTree::<Sha256>::verify_cap(cap, &proof, i, 2).is_ok());
+    assert!(MerkleTree::<Sha256>::verify_cap(cap, &proof, i ^ 16, 2).is_err());
+    assert_eq!(
+        cap,
+        MerkleTree::<Sha256>::cap_from_bytes(&tree.commitment()).unwrap()
+    );
+}
+
+#[test]
+fn merkle_verify_with_arity() {
+    // 128 leaves so the root of the 4-ary and 8-ary trees has fewer children
+    let leaf_nodes = (0..128u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let i = 93;
+
+    for (arity, proof_len) in [(2, 8), (4, 11), (8, 16), (16, 23)] {
+        let tree = MerkleTree::<Sha256>::new_with_arity(leaf_nodes.clone(), arity).unwrap();
+        let proof = tree.prove(i).unwrap().parse::<Sha256>();
+
+        assert_eq!(proof_len, proof.len());
+        assert_eq!(proof_len, MerkleTree::<Sha256>::proof_len(128, 0, arity));
+        assert!(MerkleTree::<Sha256>::verify_cap(tree.cap(), &proof, i, arity).is_ok());
+        assert!(MerkleTree::<Sha256>::verify_cap(tree.cap(), &proof, i + 1, arity).is_err());
+    }
+
+    assert!(MerkleTree::<Sha256>::new_with_arity(leaf_nodes, 3).is_err());
+}
+
+#[test]
+fn merkle_verify_salted_leaves() {
+    let leaf_nodes = (0..32u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let salted_tree = |salts: &LeafSalts| {
+        let salted_leaf_nodes = leaf_nodes
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| salt_leaf::<Sha256>(leaf, &salts.salt(i)))
+            .collect();
+        MerkleTree::<Sha256>::new(salted_leaf_nodes).unwrap()
+    };
+    let salts = LeafSalts::new([7; 32], 0);
+    let tree = salted_tree(&salts);
+    let i = 11;
+    let salt = salts.salt(i);
+    let proof = tree.prove(i).unwrap().parse::<Sha256>();
+
+    assert_ne!(salt, salts.salt(i + 1));
+    assert_eq!(salt_leaf::<Sha256>(&leaf_nodes[i], &salt), proof[0]);
+    assert!(MerkleTree::<Sha256>::verify(tree.root(), &proof, i).is_ok());
+    let other_stream = salted_tree(&LeafSalts::new([7; 32], 1));
+    assert_ne!(tree.root(), other_stream.root());
+}
+
+#[test]
+fn merkle_tree_builder_matches_tree() {
+    let leaf_nodes = (0..128u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+
+    for arity in [2, 4, 8] {
+        let tree = MerkleTree::<Sha256>::new_with_arity(leaf_nodes.clone(), arity).unwrap();
+        let mut builder = MerkleTreeBuilder::<Sha256>::new(128)
+            .unwrap()
+            .with_arity(arity)
+            .unwrap();
+        for chunk in leaf_nodes.chunks(10) {
+            builder.absorb_leaves(chunk).unwrap();
+        }
+        assert!(builder.absorb_leaves(&leaf_nodes[..1]).is_err());
+        let built_tree = builder.build().unwrap();
+
+        assert_eq!(tree.root(), built_tree.root());
+        assert_eq!(
+            tree.prove(77).unwrap().parse::<Sha256>(),
+            built_tree.prove(77).unwrap().parse::<Sha256>()
+        );
+    }
+
+    let mut builder = MerkleTreeBuilder::<Sha256>::new(128).unwrap();
+    builder.absorb_leaves(&leaf_nodes[..64]).unwrap();
+    assert!(builder.build().is_err());
+}
+
+#[test]
+fn merkle_tree_reopens_after_deserialization() {
+    let leaf_nodes = (0..64u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect();
+    let tree = MerkleTree::<Sha256>::new_with_arity(leaf_nodes, 4)
+        .unwrap()
+        .with_cap_height(1);
+    let indices = [0, 17, 63];
+
+    let mut bytes = Vec::new();
+    tree.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(tree.compressed_size(), bytes.len());
+    let reopened = MerkleTree::<Sha256>::deserialize_compressed(&*bytes).unwrap();
+
+    assert_eq!(tree.cap(), reopened.cap());
+    let proofs = reopened.open_many(&indices).unwrap();
+    for (proof, index) in proofs.iter().zip(indices) {
+        let proof = proof.parse::<Sha256>();
+        assert!(MerkleTree::<Sha256>::verify_cap(tree.cap(), &proof, index, 4).is_ok());
+    }
+
+    // corrupt a leaf node
+    let num_nodes = 64 + 16 + 4 + 1;
+    let leaves_offset = bytes.len() - num_nodes * 32;
+    bytes[leaves_offset] ^= 1;
+    assert!(MerkleTree::<Sha256>::deserialize_compressed(&*bytes).is_err());
+}
+
+#[test]
+fn merkle_tree_deserialization_rejects_truncated_tree() {
+    // the header claims far more leaves than there are bytes
+    let mut bytes = Vec::new();
+    2usize.serialize_compressed(&mut bytes).unwrap();
+    0usize.serialize_compressed(&mut bytes).unwrap();
+    (1usize << 40).serialize_compressed(&mut bytes).unwrap();
+    bytes.extend([0; 64]);
+
+    assert!(MerkleTree::<Sha256>::deserialize_compressed(&*bytes).is_err());
+}
+
+#[test]
+fn merkle_verify_algebraic_hash() {
+    type D = AlgebraicDigest<PoseidonGoldilocks>;
+    let leaf_nodes = (0..16u32)
+        .map(|v| D::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    assert_ne!(leaf_nodes[0], leaf_nodes[1]);
+    let tree = MerkleTree::<D>::new(leaf_nodes).unwrap();
+    let commitment = tree.root();
+    let i = 5;
+    let proof = tree.prove(i).unwrap().parse::<D>();
+
+    assert!(MerkleTree::<D>::verify(commitment, &proof, i).is_ok());
+    assert!(MerkleTree::<D>::verify(commitment, &proof, i + 1).is_err());
+}
+
+#[test]
+fn algebraic_merkle_nodes_merge_children() {
+    type D = AlgebraicDigest<PoseidonGoldilocks>;
+    let digest_elements = |digest: &[u8]| -> [Fp; DIGEST_SIZE] {
+        let mut reader = digest;
+        core::array::from_fn(|_| Fp::deserialize_compressed(&mut reader).unwrap())
+    };
+    let leaf_nodes = (0..2u32)
+        .map(|v| D::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let expected = PoseidonGoldilocks::merge(
+        &digest_elements(&leaf_nodes[0]),
+        &digest_elements(&leaf_nodes[1]),
+    );
+
+    let tree = MerkleTree::<D>::new(leaf_nodes).unwrap();
+
+    assert_eq!(expected, digest_elements(tree.root()));
+}
+
+#[test]
+fn algebraic_merkle_proof_rejects_non_canonical_digest() {
+    type D = AlgebraicDigest<PoseidonGoldilocks>;
+    let leaf_nodes = (0..4u32)
+        .map(|v| D::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let tree = MerkleTree::<D>::new(leaf_nodes).unwrap();
+    let mut proof = tree.prove(0).unwrap().parse::<D>();
+    proof[1][..8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    assert!(MerkleTree::<D>::verify(tree.root(), &proof, 0).is_err());
+}
+
+#[test]
+fn leaf_encoders_match_reference_formats() {
+    let row = [Fp::one(), Fp::from(2u64)];
+    let encode = |encode_row: fn(&[Fp], &mut Vec<u8>)| {
+        let mut bytes = Vec::new();
+        encode_row(&row, &mut bytes);
+        bytes
+    };
+
+    let canonical = [1u64.to_le_bytes(), 2u64.to_le_bytes()].concat();
+    assert_eq!(canonical, encode(LittleEndianLeafEncoder::encode_row));
+    let compressed = [2u64.to_le_bytes().as_slice(), canonical.as_slice()].concat();
+    assert_eq!(compressed, encode(CompressedLeafEncoder::encode_row));
+    // R = 2^64 ≡ 2^32 - 1 (mod p)
+    let montgomery = [
+        (u32::MAX as u64).to_le_bytes(),
+        (2 * u32::MAX as u64).to_le_bytes(),
+    ]
+    .concat();
+    assert_eq!(montgomery, encode(MontgomeryLeafEncoder::encode_row));
+}
+
+#[test]
+fn commit_to_rows_with_leaf_encoder() {
+    let rows = (0..8u64)
+        .map(|i| vec![Fp::from(i), Fp::from(i * i)])
+        .collect::<Vec<Vec<Fp>>>();
+    let leaf_nodes = rows
+        .iter()
+        .map(|row| {
+            let mut bytes = Vec::new();
+            LittleEndianLeafEncoder::encode_row(row, &mut bytes);
+            Sha256::new_with_prefix(bytes).finalize()
+        })
+        .collect();
+    let expected = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+
+    let matrix = Matrix::from_rows(rows);
+    let actual = matrix.commit_to_rows_with::<Sha256, LittleEndianLeafEncoder>();
+
+    assert_eq!(expected.root(), actual.root());
+    assert_ne!(matrix.commit_to_rows::<Sha256>().root(), actual.root());
+}
+
+#[test]
+fn merkle_tree_vector_commitment() {
+    let options = ProofOptions::new(8, 4, 0, 2, 2).with_merkle_arity(4);
+    let leaf_nodes = (0..64u32)
+        .map(|v| Sha256::new_with_prefix(v.to_le_bytes()).finalize())
+        .collect::<Vec<_>>();
+    let vc = <MerkleTree<Sha256> as VectorCommitment<Sha256>>::commit(leaf_nodes.clone(), &options);
+    let commitment = VectorCommitment::commitment(&vc);
+    let i = 37;
+    let proof = vc.open(i);
+
+    let verify = |position: usize, leaf| {
+        <MerkleTree<Sha256> as VectorCommitment<Sha256>>::verify(
+            &commitment,
+            64,
+            position,
+            leaf,
+            &proof,
+            &options,
+        )
+    };
+    assert!(verify(i, &leaf_nodes[i]).is_ok());
+    assert!(verify(i, &leaf_nodes[i + 1]).is_err());
+    assert!(verify(i + 1, &leaf_nodes[i]).is_err());
+}
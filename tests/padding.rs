@@ -0,0 +1,132 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use ark_ff::Zero;
+use common::options;
+use common::rng;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::trace::Padding;
+use ministark::trace::StepTrace;
+use ministark::Air;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+
+const NUM_STEPS: u64 = 1000;
+
+/// A counter that runs for a public number of steps. The trace is padded with
+/// zero rows which transition constraints exempt.
+#[derive(Clone)]
+struct CounterAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    num_steps: u64,
+}
+
+impl Air for CounterAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = u64;
+
+    fn new(trace_info: TraceInfo, num_steps: u64, options: ProofOptions) -> Self {
+        CounterAir {
+            options,
+            trace_info,
+            num_steps,
+        }
+    }
+
+    fn pub_inputs(&self) -> &u64 {
+        &self.num_steps
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn padding(&self) -> (Padding, usize) {
+        let num_padding_rows = self.trace_len().saturating_sub(self.num_steps as usize);
+        (Padding::ZeroWithExemptions, num_padding_rows)
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        let one = FieldConstant::Fp(Fp::one());
+        vec![TransitionConstraint::new(0.next() - 0.curr() - one)]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        vec![Assertion::single(0, 0, FieldConstant::Fp(Fp::zero()))]
+    }
+}
+
+struct CounterProver(ProofOptions);
+
+impl Prover for CounterProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = CounterAir;
+    type Trace = StepTrace<Fp, 1>;
+
+    fn new(options: ProofOptions) -> Self {
+        CounterProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &StepTrace<Fp, 1>) -> u64 {
+        (trace.len() - trace.padding().1) as u64
+    }
+}
+
+fn counter_trace() -> StepTrace<Fp, 1> {
+    let steps = (0..NUM_STEPS).map(|i| [Fp::from(i)]);
+    StepTrace::from_step_iterator(steps, Padding::ZeroWithExemptions, &[])
+}
+
+#[test]
+fn proof_with_zero_padding_verifies() {
+    let proof = CounterProver::new(options())
+        .prove_with_rng(counter_trace(), &mut rng())
+        .unwrap();
+
+    assert_eq!(2048 - NUM_STEPS as usize, proof.trace_info.num_padding_rows);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn proof_with_forged_padding_is_rejected() {
+    let proof = CounterProver::new(options())
+        .prove_with_rng(counter_trace(), &mut rng())
+        .unwrap();
+    // exempting more rows would leave steps of the computation unconstrained
+    let mut more_padding = proof.clone();
+    more_padding.trace_info.num_padding_rows += 500;
+    let mut fewer_steps = proof;
+    fewer_steps.public_inputs -= 500;
+
+    for proof in [more_padding, fewer_steps] {
+        assert!(matches!(
+            proof.verify(),
+            Err(VerificationError::PaddingMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::Zero;
+use common::options;
+use common::rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::prover::ProvingError;
+use ministark::trace::PreprocessedTrace;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+
+const TRACE_LEN: usize = 2048;
+
+/// Running sum `a` of a preprocessed column `k` with `k[i] = i^2`
+struct SumTrace(Matrix<Fp>);
+
+impl Trace for SumTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 1;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+#[derive(Clone)]
+struct SumAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+}
+
+impl Air for SumAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        SumAir {
+            options,
+            trace_info,
+        }
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn num_preprocessed_columns(&self) -> usize {
+        1
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        // preprocessed columns follow the base and extension columns
+        vec![TransitionConstraint::new(0.next() - 0.curr() - 1.curr())]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        vec![Assertion::single(0, 0, FieldConstant::Fp(Fp::zero()))]
+    }
+}
+
+struct SumProver {
+    options: ProofOptions,
+    preprocessed_trace: PreprocessedTrace<SumAir>,
+}
+
+impl Prover for SumProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = SumAir;
+    type Trace = SumTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        let trace_info = TraceInfo::new(1, 0, TRACE_LEN, None).with_preprocessed_columns(1);
+        let air = SumAir::new(trace_info, (), options);
+        let preprocessed_trace = PreprocessedTrace::new(&air, &squares());
+        SumProver {
+            options,
+            preprocessed_trace,
+        }
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.options
+    }
+
+    fn get_pub_inputs(&self, _: &SumTrace) {}
+
+    fn preprocessed_trace(&self) -> Option<&PreprocessedTrace<SumAir>> {
+        Some(&self.preprocessed_trace)
+    }
+}
+
+fn squares() -> Matrix<Fp> {
+    let mut k = Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator);
+    k.extend((0..TRACE_LEN as u64).map(|i| Fp::from(i * i)));
+    Matrix::new(vec![k])
+}
+
+fn sum_trace() -> SumTrace {
+    let mut a = Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator);
+    let mut acc = Fp::zero();
+    for &k in &squares()[0] {
+        a.push(acc);
+        acc += k;
+    }
+    SumTrace(Matrix::new(vec![a]))
+}
+
+#[test]
+fn proof_with_preprocessed_columns_verifies() {
+    let prover = SumProver::new(options());
+    let commitment = prover.preprocessed_trace.commitment();
+
+    let proof = prover.prove_with_rng(sum_trace(), &mut rng()).unwrap();
+
+    assert_eq!(1, proof.trace_info.num_preprocessed_columns);
+    proof.verify_with_preprocessed(&commitment).unwrap();
+}
+
+#[test]
+fn proof_without_declared_preprocessed_columns_is_rejected() {
+    let prover = SumProver::new(options());
+    let commitment = prover.preprocessed_trace.commitment();
+    let mut proof = prover.prove_with_rng(sum_trace(), &mut rng()).unwrap();
+    let mut other_proof = proof.clone();
+
+    proof.trace_info.num_preprocessed_columns = 0;
+    other_proof.trace_info.num_preprocessed_columns = 2;
+
+    assert!(matches!(
+        proof.verify(),
+        Err(VerificationError::PreprocessedColumnsMismatch { .. })
+    ));
+    assert!(matches!(
+        other_proof.verify_with_preprocessed(&commitment),
+        Err(VerificationError::PreprocessedColumnsMismatch { .. })
+    ));
+}
+
+#[test]
+fn preprocessed_trace_over_another_lde_domain_is_an_error() {
+    let options = options();
+    let other_options = ProofOptions::new(16, 2 * options.lde_blowup_factor, 0, 2, 8);
+    let trace_info = TraceInfo::new(1, 0, TRACE_LEN, None).with_preprocessed_columns(1);
+    let other_air = SumAir::new(trace_info, (), other_options);
+    let prover = SumProver {
+        options,
+        preprocessed_trace: PreprocessedTrace::new(&other_air, &squares()),
+    };
+
+    assert!(matches!(
+        prover.prove_with_rng(sum_trace(), &mut rng()),
+        Err(ProvingError::PreprocessedLdeSizeMismatch { .. })
+    ));
+}
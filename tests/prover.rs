@@ -0,0 +1,219 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::prove_fib;
+use common::rng;
+use common::FibAir;
+use common::FibProver;
+use common::FibTrace;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::prover::CancellationToken;
+use ministark::prover::ProverEvent;
+use ministark::prover::ProvingError;
+use ministark::prover::ProvingPhase;
+use ministark::Air;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::VerificationError;
+use std::sync::Mutex;
+
+#[test]
+fn estimated_proof_size_bounds_serialized_proof_size() {
+    for options in [
+        options(),
+        options().with_zk(),
+        options().with_merkle_cap_height(2),
+        options().with_fri_remainder_coeffs(),
+    ] {
+        let proof = prove_fib(options, 2048);
+        let air = FibAir::new(proof.trace_info.clone(), proof.public_inputs, options);
+        let estimated_size = options
+            .estimated_proof_size::<FibAir>(&proof.trace_info, air.ce_blowup_factor())
+            + proof.public_inputs.compressed_size();
+        let proof_size = proof.compressed_size();
+        proof.verify().unwrap();
+
+        assert!(proof_size <= estimated_size);
+        // only FRI queries that open the same leaf make the proof smaller
+        assert!(estimated_size - proof_size < estimated_size / 4);
+    }
+}
+
+#[test]
+fn batched_constraints_verify() {
+    for options in [options(), options().with_zk()] {
+        let proof = prove_fib(options.with_batched_constraints(), 2048);
+
+        assert!(proof.options.batch_constraints);
+        proof.verify().unwrap();
+    }
+}
+
+#[test]
+fn proof_with_flipped_batch_constraints_is_rejected() {
+    let mut proof = prove_fib(options().with_batched_constraints(), 2048);
+    // the options are part of the transcript
+    proof.options.batch_constraints = false;
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn zk_proof_verifies() {
+    let options = options().with_zk();
+    let proof = prove_fib(options, 2048);
+
+    assert_eq!(
+        options.min_randomizer_rows(),
+        proof.trace_info.num_randomizer_rows
+    );
+    proof.verify().unwrap();
+}
+
+#[test]
+fn proof_with_forged_randomizer_rows_is_rejected() {
+    let mut proof = prove_fib(options().with_zk(), 2048);
+    // transition constraints would exempt more rows than the air intends
+    proof.trace_info.num_randomizer_rows += 64;
+
+    assert!(matches!(
+        proof.verify(),
+        Err(VerificationError::RandomizerRowsMismatch { .. })
+    ));
+}
+
+#[test]
+fn trace_with_extra_base_columns_is_an_error() {
+    let options = options();
+    let mut trace = fib_trace(2048, &options);
+    let extra = trace.0[0].to_vec_in(PageAlignedAllocator);
+    trace.0 .0.push(extra);
+
+    assert!(matches!(
+        FibProver::new(options).prove_with_rng(trace, &mut rng()),
+        Err(ProvingError::BaseColumnsMismatch {
+            expected: 2,
+            actual: 3
+        })
+    ));
+}
+
+/// Records the events of a proof and cancels it once `cancel_after` finishes
+struct ObservedFibProver {
+    options: ProofOptions,
+    events: Mutex<Vec<ProverEvent>>,
+    token: CancellationToken,
+    cancel_after: Option<ProvingPhase>,
+}
+
+impl Prover for ObservedFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        ObservedFibProver {
+            options,
+            events: Mutex::new(Vec::new()),
+            token: CancellationToken::new(),
+            cancel_after: None,
+        }
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.options
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+
+    fn on_event(&self, event: ProverEvent) {
+        if self
+            .cancel_after
+            .is_some_and(|p| event == ProverEvent::PhaseFinished(p))
+        {
+            self.token.cancel();
+        }
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        Some(&self.token)
+    }
+}
+
+#[test]
+fn prover_reports_each_phase_and_fri_layer() {
+    let prover = ObservedFibProver::new(options());
+    let proof = prover
+        .prove_with_rng(fib_trace(2048, &options()), &mut rng())
+        .unwrap();
+    let trace_len = proof.trace_info.trace_len;
+    proof.verify().unwrap();
+
+    let events = prover.events.into_inner().unwrap();
+    let phases = events
+        .iter()
+        .filter_map(|event| match event {
+            ProverEvent::PhaseStarted(phase) => Some(*phase),
+            _ => None,
+        })
+        .collect::<Vec<ProvingPhase>>();
+    assert_eq!(
+        vec![
+            ProvingPhase::BaseTrace,
+            ProvingPhase::ExtensionTrace,
+            ProvingPhase::ConstraintEvaluation,
+            ProvingPhase::DeepComposition,
+            ProvingPhase::Fri,
+            ProvingPhase::Grinding,
+            ProvingPhase::Queries,
+        ],
+        phases
+    );
+    let fri_progress = events
+        .iter()
+        .filter_map(|event| match event {
+            ProverEvent::Progress {
+                phase: ProvingPhase::Fri,
+                percent,
+            } => Some(*percent),
+            _ => None,
+        })
+        .collect::<Vec<u8>>();
+    let lde_domain_size = trace_len * usize::from(options().lde_blowup_factor);
+    let num_layers = options()
+        .into_fri_options()
+        .num_committed_layers(lde_domain_size);
+    assert_eq!(num_layers, fri_progress.len());
+    assert!(fri_progress.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(Some(&100), fri_progress.last());
+    assert_eq!(
+        Some(&ProverEvent::PhaseFinished(ProvingPhase::Queries)),
+        events.last()
+    );
+}
+
+#[test]
+fn cancelled_proof_stops_before_the_next_phase() {
+    let mut prover = ObservedFibProver::new(options());
+    prover.cancel_after = Some(ProvingPhase::DeepComposition);
+
+    let result = prover.prove_with_rng(fib_trace(2048, &options()), &mut rng());
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::Cancelled {
+            phase: ProvingPhase::Fri
+        })
+    ));
+    let events = prover.events.into_inner().unwrap();
+    assert!(!events.contains(&ProverEvent::PhaseStarted(ProvingPhase::Fri)));
+}
@@ -0,0 +1,133 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use ark_ff::Zero;
+use common::options;
+use common::rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::assertions::PublicCell;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// A counter column starting at zero whose last row is a public cell
+struct CounterTrace(Matrix<Fp>);
+
+impl Trace for CounterTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 1;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+struct CounterAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    result: Fp,
+}
+
+impl Air for CounterAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        CounterAir {
+            options,
+            trace_info,
+            result,
+        }
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        &self.result
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        let one = FieldConstant::Fp(Fp::one());
+        vec![TransitionConstraint::new(0.next() - 0.curr() - one)]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        vec![Assertion::single(0, 0, FieldConstant::Fp(Fp::zero()))]
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell<Fp>> {
+        vec![PublicCell::new(0, self.trace_len() - 1, self.result)]
+    }
+}
+
+struct CounterProver(ProofOptions);
+
+impl Prover for CounterProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = CounterAir;
+    type Trace = CounterTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CounterProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &CounterTrace) -> Fp {
+        *trace.0[0].last().unwrap()
+    }
+}
+
+fn counter_trace(n: usize) -> CounterTrace {
+    let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+    column.extend((0..n as u64).map(Fp::from));
+    CounterTrace(Matrix::new(vec![column]))
+}
+
+#[test]
+fn proof_with_public_cells_verifies() {
+    let proof = CounterProver::new(options())
+        .prove_with_rng(counter_trace(2048), &mut rng())
+        .unwrap();
+
+    assert_eq!(Fp::from(2047u64), proof.public_inputs);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn proof_with_tampered_public_cell_is_rejected() {
+    let mut proof = CounterProver::new(options())
+        .prove_with_rng(counter_trace(2048), &mut rng())
+        .unwrap();
+    // claim the counter ends at a different value than the trace does
+    proof.public_inputs += Fp::one();
+
+    assert!(proof.verify().is_err());
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "std")]
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::FibAir;
+use common::FibProver;
+use ministark::prover::ProvingPhase;
+use ministark::Air;
+use ministark::Prover;
+
+#[test]
+fn report_covers_every_phase_and_section_of_the_proof() {
+    let options = options();
+    let (proof, report) = FibProver::new(options)
+        .prove_with_report(fib_trace(2048, &options))
+        .unwrap();
+    proof.clone().verify().unwrap();
+
+    let phases = report.phases.iter().map(|timing| timing.phase);
+    assert!(phases.eq([
+        ProvingPhase::BaseTrace,
+        ProvingPhase::ExtensionTrace,
+        ProvingPhase::ConstraintEvaluation,
+        ProvingPhase::DeepComposition,
+        ProvingPhase::Fri,
+        ProvingPhase::Grinding,
+        ProvingPhase::Queries,
+    ]));
+    let size = report.proof_size;
+    assert_eq!(proof.compressed_size(), size.total);
+    let sections = size.commitments
+        + size.ood_evals
+        + size.fri_proof
+        + size.trace_queries
+        + size.public_inputs;
+    assert!(sections < size.total);
+    if !cfg!(feature = "gpu") {
+        assert!(report.process_gpu_time.is_zero());
+    }
+    if cfg!(target_os = "linux") {
+        assert!(report.process_peak_rss_bytes.unwrap() > 0);
+    }
+}
+
+#[test]
+fn estimated_bytes_hashed_counts_leaves_and_nodes_below_the_root() {
+    // 8192 leaves of which all levels but the root are hashed
+    for (arity, num_hashed_nodes) in [
+        (
+            2,
+            8192 + 4096 + 2048 + 1024 + 512 + 256 + 128 + 64 + 32 + 16 + 8 + 4 + 2,
+        ),
+        (4, 8192 + 2048 + 512 + 128 + 32 + 8 + 2),
+    ] {
+        let options = options().with_merkle_arity(arity);
+        let (proof, report) = FibProver::new(options)
+            .prove_with_report(fib_trace(2048, &options))
+            .unwrap();
+        let air = FibAir::new(proof.trace_info.clone(), proof.public_inputs, options);
+
+        // SHA-256 trees over the base trace and the composition trace
+        let tree_size = num_hashed_nodes * 32;
+        let base_trace_size = 8192 * 2 * 8;
+        let composition_trace_size = 8192 * air.ce_blowup_factor() * 8;
+        let expected = base_trace_size + composition_trace_size + 2 * tree_size;
+        assert_eq!(expected as u64, report.estimated_bytes_hashed);
+    }
+}
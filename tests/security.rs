@@ -0,0 +1,91 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use common::prove_fib;
+use common::FibAir;
+use ministark::utils;
+use ministark::Air;
+use ministark::CoefficientMode;
+use ministark::ProofOptions;
+
+#[test]
+fn proven_security_is_below_conjectured_security() {
+    // cubic extension of the 64-bit goldilocks field
+    let field_bits = 192;
+    let hash_fn_security = 128;
+    let trace_len = 1 << 20;
+    let num_queries = 64;
+    let grinding_factor = 16;
+
+    for blowup_factor in [4, 8, 16] {
+        let conjectured = utils::conjectured_security_level(
+            field_bits,
+            hash_fn_security,
+            blowup_factor,
+            trace_len,
+            num_queries,
+            grinding_factor,
+        );
+        let proven = utils::proven_security_level(
+            field_bits,
+            hash_fn_security,
+            blowup_factor,
+            trace_len,
+            num_queries,
+            grinding_factor,
+            3,
+        );
+        assert!(proven < conjectured);
+    }
+}
+
+#[test]
+fn proven_security_grows_with_queries() {
+    let proven_security =
+        |num_queries| utils::proven_security_level(192, 128, 8, 1 << 16, num_queries, 0, 3);
+
+    // each query contributes log2(8)/2 - log2(1 + 1/6) ≈ 1.28 bits
+    assert_eq!(62, proven_security(50));
+    assert!(proven_security(50) < proven_security(80));
+    assert_eq!(128, proven_security(128));
+}
+
+#[test]
+fn invalid_options_return_an_error() {
+    assert!(ProofOptions::try_new(32, 3, 0, 2, 2).is_err());
+    assert!(ProofOptions::try_new(0, 4, 0, 2, 2).is_err());
+    assert!(ProofOptions::try_new(32, 4, 0, 3, 2).is_err());
+    assert!(ProofOptions::try_new(32, 4, 0, 2, 0).is_err());
+    assert!(ProofOptions::try_new(32, 4, 0, 2, 6).is_err());
+    assert!(ProofOptions::try_new(32, 4, 0, 2, 2).is_ok());
+}
+
+#[test]
+fn conjectured_security_without_blowup_is_zero() {
+    assert_eq!(
+        0,
+        utils::conjectured_security_level(64, 128, 1, 2048, 128, 0)
+    );
+}
+
+#[test]
+fn powers_of_one_challenge_cost_field_security() {
+    // enough queries for the field to bound security
+    let options = ProofOptions::new(64, 4, 0, 2, 8);
+    let proof = prove_fib(options, 2048);
+    let powers_options = options.with_composition_coeffs(CoefficientMode::Powers);
+    let powers_proof = prove_fib(powers_options, 2048);
+    let air = FibAir::new(proof.trace_info.clone(), proof.public_inputs, options);
+    let loss = CoefficientMode::Powers.security_loss(2 * air.all_constraints().len());
+
+    assert!(loss > 0);
+    assert_eq!(
+        proof.conjectured_security_level() - loss,
+        powers_proof.conjectured_security_level()
+    );
+    assert_eq!(
+        proof.proven_security_level() - loss,
+        powers_proof.proven_security_level()
+    );
+}
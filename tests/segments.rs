@@ -0,0 +1,199 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use common::options;
+use common::rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::FieldConstant;
+use ministark::constraints::TransitionConstraint;
+use ministark::constraints::VerifierChallenge;
+use ministark::divisor::Divisor;
+use ministark::prover::ProvingError;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+
+/// A counter column `a` with two extension segments. The first holds the
+/// running product `b` of `alpha - a`. The second holds the running sum `c` of
+/// `beta * b` where `beta` is drawn once `b` is committed. The second field is
+/// the number of extra columns built in the last segment.
+struct SegmentedTrace(Matrix<Fp>, usize);
+
+impl Trace for SegmentedTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    const NUM_BASE_COLUMNS: usize = 1;
+    const NUM_EXTENSION_COLUMNS: usize = 2;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        vec![1, 1]
+    }
+
+    fn build_aux_segment(
+        &self,
+        segment: usize,
+        prev_segments: &[Matrix<Fp>],
+        challenges: &Challenges<Fp>,
+    ) -> Option<Matrix<Fp>> {
+        let a = &self.0[0];
+        let mut column = Vec::with_capacity_in(a.len(), PageAlignedAllocator);
+        match segment {
+            0 => {
+                let alpha = challenges[0];
+                let mut acc = Fp::one();
+                column.extend(a.iter().map(|&a| {
+                    acc *= alpha - a;
+                    acc
+                }));
+            }
+            1 => {
+                let beta = challenges[1];
+                let mut acc = Fp::from(0u8);
+                column.extend(prev_segments[0][0].iter().map(|&b| {
+                    acc += beta * b;
+                    acc
+                }));
+            }
+            _ => return None,
+        }
+        let num_extra_columns = (segment == 1).then_some(self.1).unwrap_or_default();
+        let extra_columns = (0..num_extra_columns).map(|_| column.to_vec_in(PageAlignedAllocator));
+        let mut columns = extra_columns.collect::<Vec<_>>();
+        columns.push(column);
+        Some(Matrix::new(columns))
+    }
+}
+
+struct SegmentedAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+}
+
+impl Air for SegmentedAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        SegmentedAir {
+            options,
+            trace_info,
+        }
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn extension_segment_widths(&self) -> Vec<usize> {
+        vec![1, 1]
+    }
+
+    fn num_segment_challenges(&self, segment: usize) -> usize {
+        usize::from(segment == 1)
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        Vec::new()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        let one = FieldConstant::Fp(Fp::one());
+        let (alpha, beta) = (0.challenge(), 1.challenge());
+        vec![
+            TransitionConstraint::new(0.next() - 0.curr() - one),
+            TransitionConstraint::new(1.next() - 1.curr() * (alpha.clone() - 0.next())),
+            TransitionConstraint::new(2.next() - 2.curr() - beta.clone() * 1.next()),
+            TransitionConstraint::new(1.curr() - (alpha - 0.curr())).with_divisor(Divisor::row(0)),
+            TransitionConstraint::new(2.curr() - beta * 1.curr()).with_divisor(Divisor::row(0)),
+        ]
+    }
+}
+
+struct SegmentedProver(ProofOptions);
+
+impl Prover for SegmentedProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = SegmentedAir;
+    type Trace = SegmentedTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        SegmentedProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _: &SegmentedTrace) {}
+}
+
+fn segmented_trace(n: usize) -> SegmentedTrace {
+    let mut a = Vec::with_capacity_in(n, PageAlignedAllocator);
+    a.extend((0..n as u64).map(Fp::from));
+    SegmentedTrace(Matrix::new(vec![a]), 0)
+}
+
+#[test]
+fn proof_with_multiple_extension_segments_verifies() {
+    let proof = SegmentedProver::new(options())
+        .prove_with_rng(segmented_trace(2048), &mut rng())
+        .unwrap();
+
+    assert_eq!(vec![1, 1], proof.trace_info.extension_segment_widths);
+    assert_eq!(2, proof.extension_trace_commitments.len());
+    proof.verify().unwrap();
+}
+
+#[test]
+fn proof_with_undeclared_extension_segments_is_rejected() {
+    let mut proof = SegmentedProver::new(options())
+        .prove_with_rng(segmented_trace(2048), &mut rng())
+        .unwrap();
+    // merging the segments would let the prover pick `b` after seeing `beta`
+    proof.trace_info.extension_segment_widths = vec![2];
+
+    assert!(matches!(
+        proof.verify(),
+        Err(VerificationError::ExtensionSegmentWidthsMismatch { .. })
+    ));
+}
+
+#[test]
+fn extension_segment_of_the_wrong_width_is_an_error() {
+    let mut trace = segmented_trace(2048);
+    trace.1 = 1;
+
+    assert!(matches!(
+        SegmentedProver::new(options()).prove_with_rng(trace, &mut rng()),
+        Err(ProvingError::ExtensionSegmentWidthMismatch {
+            segment: 1,
+            expected: 1,
+            actual: 2,
+        })
+    ));
+}
@@ -0,0 +1,32 @@
+#![cfg(unix)]
+#![feature(allocator_api)]
+use ark_ff::One;
+use gpu_poly::allocator::enable_disk_spilling;
+use gpu_poly::allocator::DiskSpillingError;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::Matrix;
+
+// runs in its own process since spilling is enabled globally
+#[test]
+fn spilled_matrix_sums_columns() {
+    const THRESHOLD: usize = 1 << 20;
+    let dir = std::env::temp_dir();
+    let dir = dir.to_str().unwrap();
+    // made before spilling is enabled so it stays on the heap
+    let heap_col = Vec::<Fp, _>::with_capacity_in(THRESHOLD, PageAlignedAllocator);
+    enable_disk_spilling(THRESHOLD, dir).unwrap();
+    assert_eq!(
+        Err(DiskSpillingError::AlreadyEnabled),
+        enable_disk_spilling(THRESHOLD, dir)
+    );
+    drop(heap_col);
+    let n = 2 * THRESHOLD / core::mem::size_of::<Fp>();
+    let mut col = Vec::with_capacity_in(n, PageAlignedAllocator);
+    col.resize(n, Fp::one());
+    let matrix = Matrix::new(vec![col.to_vec_in(PageAlignedAllocator), col]);
+
+    let sum = matrix.sum_columns();
+
+    assert!(sum.0[0].iter().all(|v| *v == Fp::from(2u8)));
+}
@@ -0,0 +1,62 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::prove_fib;
+use common::rng;
+use common::FibAir;
+use common::FibProver;
+use ministark::Proof;
+use ministark::Prover;
+
+fn serialized(value: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+/// Serializes and deserializes `state` as if it's sent to another machine
+fn roundtrip<T: CanonicalSerialize + CanonicalDeserialize>(state: T) -> T {
+    T::deserialize_compressed(&*serialized(&state)).unwrap()
+}
+
+#[test]
+fn staged_proof_matches_prove() {
+    for options in [options(), options().with_zk()] {
+        let prover = FibProver::new(options);
+        let state = prover
+            .commit_trace(fib_trace(2048, &options), &mut rng())
+            .unwrap();
+        let state = prover.commit_constraints(state).unwrap();
+        let state = prover.commit_fri(state).unwrap();
+        let proof = prover.open_queries(state).unwrap();
+
+        assert_eq!(serialized(&prove_fib(options, 2048)), serialized(&proof));
+    }
+}
+
+#[test]
+fn serialized_stages_resume_and_verify() {
+    for options in [options(), options().with_zk()] {
+        // each stage runs on a different prover
+        let state = FibProver::new(options)
+            .commit_trace(fib_trace(2048, &options), &mut rng())
+            .unwrap();
+        let state = FibProver::new(options)
+            .commit_constraints(roundtrip(state))
+            .unwrap();
+        let state = FibProver::new(options)
+            .commit_fri(roundtrip(state))
+            .unwrap();
+        let proof: Proof<FibAir> = FibProver::new(options)
+            .open_queries(roundtrip(state))
+            .unwrap();
+
+        assert_eq!(serialized(&prove_fib(options, 2048)), serialized(&proof));
+        proof.verify().unwrap();
+    }
+}
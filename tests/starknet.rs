@@ -0,0 +1,163 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::rng;
+use common::FibAir;
+use common::FibTrace;
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::TransitionConstraint;
+use ministark::hash::DIGEST_SIZE;
+use ministark::merkle::MerkleProof;
+use ministark::starknet::proof_to_felts;
+use ministark::starknet::Felt;
+use ministark::starknet::StarknetDigest;
+use ministark::starknet::StarknetPublicCoin;
+use ministark::starknet::ToFelts;
+use ministark::Air;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::TraceInfo;
+
+#[test]
+fn merkle_proof_is_written_one_felt_per_word() {
+    let node = StarknetDigest::digest(b"ministark");
+    let proof = MerkleProof::new::<StarknetDigest>(vec![node, node]);
+
+    let mut felts = Vec::new();
+    proof.write_felts(&mut felts).unwrap();
+
+    assert_eq!(1 + 2 * DIGEST_SIZE, felts.len());
+    assert_eq!(Felt::from(2 * DIGEST_SIZE as u64), felts[0]);
+    assert_eq!(felts[1..=DIGEST_SIZE], felts[DIGEST_SIZE + 1..]);
+}
+
+/// [FibAir] committed with the hash and transcript for a Cairo verifier
+#[derive(Clone)]
+struct StarknetFibAir(FibAir);
+
+impl Air for StarknetFibAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+    type Digest = StarknetDigest;
+    type PublicCoin = StarknetPublicCoin;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        StarknetFibAir(FibAir::new(trace_info, result, options))
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        self.0.pub_inputs()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        self.0.trace_info()
+    }
+
+    fn options(&self) -> &ProofOptions {
+        self.0.options()
+    }
+
+    fn num_randomizer_rows(&self) -> usize {
+        self.0.num_randomizer_rows()
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        self.0.constraints()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        self.0.transition_constraints()
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        self.0.assertions()
+    }
+}
+
+struct StarknetFibProver(ProofOptions);
+
+impl Prover for StarknetFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Digest = StarknetDigest;
+    type Air = StarknetFibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        StarknetFibProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+}
+
+fn prove_starknet_fib(n: usize) -> Proof<StarknetFibAir> {
+    let options = options();
+    StarknetFibProver::new(options)
+        .prove_with_rng(fib_trace(n, &options), &mut rng())
+        .unwrap()
+}
+
+/// Length prefixed bytes packed 31 bytes per felt after a `0x01` padding byte
+fn packed(bytes: &[u8]) -> Vec<Felt> {
+    let mut padded = bytes.to_vec();
+    padded.push(1);
+    let mut felts = vec![Felt::from(bytes.len() as u64)];
+    felts.extend(padded.chunks(31).map(Felt::from_le_bytes_mod_order));
+    felts
+}
+
+fn packed_serialized(item: &impl CanonicalSerialize) -> Vec<Felt> {
+    let mut bytes = Vec::new();
+    item.serialize_compressed(&mut bytes).unwrap();
+    packed(&bytes)
+}
+
+/// Length prefixed felts with the same integer values as `values`
+fn elements(values: &[Fp]) -> Vec<Felt> {
+    let mut felts = vec![Felt::from(values.len() as u64)];
+    felts.extend(values.iter().map(|v| Felt::from(v.into_bigint().0[0])));
+    felts
+}
+
+#[test]
+fn proof_is_written_in_field_order() {
+    let proof = prove_starknet_fib(2048);
+    proof.clone().verify().unwrap();
+
+    let felts = proof_to_felts(&proof).unwrap();
+
+    let mut header = packed_serialized(&proof.options);
+    header.extend(packed_serialized(&proof.trace_info));
+    header.push(Felt::from(proof.hash_id));
+    // number of felts in the base trace commitment
+    header.push(Felt::from(DIGEST_SIZE as u64));
+    assert_eq!(header, felts[..header.len()]);
+    let mut footer = packed_serialized(&proof.public_inputs);
+    footer.extend(elements(&proof.execution_trace_ood_evals));
+    footer.extend(elements(&proof.composition_trace_ood_evals));
+    assert_eq!(footer, felts[felts.len() - footer.len()..]);
+}
+
+#[test]
+fn proof_with_digests_outside_the_starknet_field_is_rejected() {
+    let mut proof = prove_starknet_fib(2048);
+    proof.base_trace_commitment = vec![u8::MAX; 32 * DIGEST_SIZE];
+
+    assert!(proof_to_felts(&proof).is_err());
+}
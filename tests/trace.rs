@@ -0,0 +1,209 @@
+#![feature(allocator_api)]
+
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::trace::pad_rows;
+use ministark::trace::randomize_rows;
+use ministark::trace::AirVersion;
+use ministark::trace::Padding;
+use ministark::trace::StepTrace;
+use ministark::trace::TraceBuilder;
+use ministark::Error;
+use ministark::Matrix;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+#[test]
+fn extension_columns_form_a_single_segment_by_default() {
+    let trace_info = TraceInfo::new(4, 3, 2048, None);
+
+    assert_eq!(vec![3], trace_info.extension_segment_widths);
+    assert_eq!(vec![0..3], trace_info.extension_segment_ranges());
+    assert_eq!(0, TraceInfo::new(4, 0, 2048, None).num_extension_segments());
+}
+
+#[test]
+fn validate_rejects_inconsistent_segments() {
+    let mut trace_info = TraceInfo::new(4, 3, 2048, None);
+    assert!(trace_info.validate().is_ok());
+
+    trace_info.extension_segment_widths = vec![1, 1];
+    assert!(matches!(
+        trace_info.validate(),
+        Err(Error::InvalidTraceInfo { .. })
+    ));
+}
+
+#[test]
+fn extension_segment_ranges() {
+    let trace_info = TraceInfo::new(4, 6, 2048, None).with_extension_segments(vec![2, 3, 1]);
+
+    assert_eq!(3, trace_info.num_extension_segments());
+    assert_eq!(
+        vec![0..2, 2..5, 5..6],
+        trace_info.extension_segment_ranges()
+    );
+    assert_eq!(4..10, trace_info.extension_columns_range());
+}
+
+#[test]
+#[should_panic]
+fn extension_segment_widths_must_cover_extension_columns() {
+    TraceInfo::new(4, 6, 2048, None).with_extension_segments(vec![2, 3]);
+}
+
+#[test]
+fn preprocessed_columns_follow_extension_columns() {
+    let trace_info = TraceInfo::new(4, 3, 2048, None).with_preprocessed_columns(2);
+
+    assert_eq!(7..9, trace_info.preprocessed_columns_range());
+    assert_eq!(9, trace_info.num_columns());
+    assert!(TraceInfo::new(4, 0, 2048, None)
+        .preprocessed_columns_range()
+        .is_empty());
+}
+
+#[test]
+fn base_segments_of_different_lengths() {
+    let trace_info = TraceInfo::new(5, 0, 2048, None).with_base_segments(&[(3, 2048), (2, 256)]);
+
+    assert_eq!(vec![0..3, 3..5], trace_info.base_segment_ranges());
+    assert_eq!(1, trace_info.base_segment_stride(0));
+    assert_eq!(8, trace_info.base_segment_stride(1));
+}
+
+#[test]
+#[should_panic]
+fn base_segments_can_not_be_longer_than_the_trace() {
+    TraceInfo::new(2, 0, 1024, None).with_base_segments(&[(1, 1024), (1, 2048)]);
+}
+
+#[test]
+fn randomize_rows_only_changes_randomizer_rows() {
+    let mut rng = ark_std::test_rng();
+    let n = 2048;
+    let column = (0..n as u64).map(Fp::from).collect::<Vec<Fp>>();
+    let mut trace = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let trace_info = TraceInfo::new(1, 0, n, None).with_randomizer_rows(4);
+
+    randomize_rows(&mut trace, trace_info.num_randomizer_rows, &mut rng);
+
+    assert_eq!(column[..n - 4], trace.0[0][..n - 4]);
+    assert_ne!(column[n - 4..], trace.0[0][n - 4..]);
+}
+
+#[test]
+#[should_panic]
+fn randomizer_rows_must_fit_in_every_base_segment() {
+    TraceInfo::new(2, 0, 2048, None)
+        .with_base_segments(&[(1, 2048), (1, 4)])
+        .with_randomizer_rows(4);
+}
+
+#[test]
+fn trace_builder_fill_matches_transition() {
+    let builder = TraceBuilder::new(2, 1000);
+    let fib_step = |row: &[Fp]| vec![row[1], row[0] + row[1]];
+
+    let transition_trace = builder.from_transition(vec![Fp::one(), Fp::one()], fib_step);
+    let fill_trace = builder.fill(|i, row: &mut [Fp]| {
+        row[0] = Fp::from(i as u64);
+        row[1] = Fp::from(2 * i as u64);
+    });
+
+    assert_eq!(2048, transition_trace.num_rows());
+    assert_eq!(transition_trace.0[1][2046], transition_trace.0[0][2047]);
+    assert_eq!(2048, fill_trace.num_rows());
+    assert_eq!(Fp::from(1998u64), fill_trace.0[1][999]);
+    assert_eq!(Fp::from(1998u64), fill_trace.0[1][2047]);
+}
+
+#[test]
+fn pad_rows_fills_padding_with_each_strategy() {
+    let n = 2048;
+    let column = (0..n as u64).map(Fp::from).collect::<Vec<Fp>>();
+    let trace = Matrix::new(vec![column.to_vec_in(PageAlignedAllocator)]);
+    let halt_state = [Fp::from(7u64)];
+    let pad = |padding| {
+        let mut trace = trace.clone();
+        pad_rows(&mut trace, 48, padding, &halt_state);
+        trace.0[0][n - 49..].to_vec()
+    };
+
+    assert!(pad(Padding::RepeatLastRow)
+        .iter()
+        .all(|&v| v == column[n - 49]));
+    assert!(pad(Padding::HaltState)[1..]
+        .iter()
+        .all(|&v| v == halt_state[0]));
+    assert!(pad(Padding::ZeroWithExemptions)[1..]
+        .iter()
+        .all(Fp::is_zero));
+}
+
+#[test]
+fn step_trace_is_padded_to_a_power_of_two() {
+    let steps = (0..1000u64).map(|i| [Fp::from(i), Fp::from(i * i)]);
+
+    let trace = StepTrace::<Fp, 2>::from_step_iterator(steps, Padding::RepeatLastRow, &[]);
+
+    let trace_info = trace.info();
+    assert_eq!(2048, trace_info.trace_len);
+    assert_eq!(1048, trace_info.num_padding_rows);
+    assert_eq!(Fp::from(999u64 * 999), trace.base_columns().0[1][2047]);
+}
+
+#[test]
+fn air_versions_with_different_major_versions_are_incompatible() {
+    let v1_2 = AirVersion::new(1, 2, 0);
+
+    assert!(v1_2.is_compatible_with(&AirVersion::new(1, 3, 1)));
+    assert!(!v1_2.is_compatible_with(&AirVersion::new(2, 0, 0)));
+    assert!(!AirVersion::new(0, 1, 0).is_compatible_with(&AirVersion::new(0, 2, 0)));
+}
+
+#[test]
+fn trace_info_names_columns() {
+    let names = vec!["pc".to_string(), "ap".to_string()];
+    let trace_info = TraceInfo::new(3, 0, 2048, None).with_column_names(names);
+
+    assert_eq!("ap", trace_info.column_name(1));
+    assert_eq!("Trace(2)", trace_info.column_name(2));
+}
+
+#[test]
+fn trace_info_with_long_column_names_is_invalid() {
+    let mut trace_info = TraceInfo::new(3, 0, 2048, None);
+    trace_info.column_names = vec!["a".repeat(TraceInfo::MAX_NAME_BYTES + 1)];
+
+    assert!(matches!(
+        trace_info.validate(),
+        Err(Error::InvalidTraceInfo { .. })
+    ));
+}
+
+#[test]
+fn window_copies_a_range_of_rows() {
+    let steps = (0..2048u64).map(|i| [Fp::from(i)]);
+    let trace = StepTrace::<Fp, 1>::from_step_iterator(steps, Padding::RepeatLastRow, &[]);
+
+    let window = trace.window(100, 4);
+
+    let expected = (100..104u64).map(Fp::from).collect::<Vec<Fp>>();
+    assert_eq!(expected, window.0[0].to_vec());
+}
+
+#[test]
+fn short_traces_return_an_error() {
+    let result = TraceInfo::try_new(1, 0, 1024, None);
+
+    assert!(matches!(
+        result,
+        Err(Error::TraceTooShort {
+            len: 1024,
+            min: 2048
+        })
+    ));
+}
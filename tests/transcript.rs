@@ -0,0 +1,173 @@
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::hash::Poseidon2Goldilocks;
+use ministark::hash::CAPACITY;
+use ministark::random::label;
+use ministark::random::CoefficientMode;
+use ministark::random::DigestPublicCoin;
+use ministark::random::PublicCoin;
+use ministark::random::SpongePublicCoin;
+use ministark::GrindingConfig;
+use ministark::GrindingHash;
+use sha2::Digest;
+use sha2::Sha256;
+
+#[cfg(feature = "keccak")]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Keccak256 transcript test vectors. A Solidity verifier must reproduce
+/// these bit-for-bit.
+#[cfg(feature = "keccak")]
+#[test]
+fn keccak_transcript_vectors() {
+    use ministark::random::hash_id;
+    use ministark::Keccak256;
+    // seed = keccak256("ministark")
+    let mut public_coin = DigestPublicCoin::<Keccak256>::new(b"ministark");
+    assert_eq!(
+        "2d7c2c2eccad9d72a3280e07fcd41ed1743c0679f2ef54004ba331d1d0c0a5bd",
+        hex(&public_coin.seed)
+    );
+
+    // seed = keccak256(seed || uint64(len(label)) || label || item)
+    // where the item 42u64 is serialized as 8 little-endian bytes
+    public_coin.reseed_with_label(label::BASE_TRACE_COMMITMENT, &42u64);
+    assert_eq!(
+        "0c570b68f94d8adacd4606b4686f3251e7d547555dfe302b6f2e2bc495a284a7",
+        hex(&public_coin.seed)
+    );
+
+    // keccak256(seed || uint64(1)) mod p
+    assert_eq!(Fp::from(3067445954803727486u64), public_coin.draw::<Fp>());
+    // uint64(keccak256(seed || uint64(counter))[0..8]) mod 1024 for counters 2..6
+    assert_eq!(vec![88, 792, 685, 621], public_coin.draw_queries(4, 1024));
+    assert_eq!(0x2f665cf8248af1dd, hash_id::<Keccak256>());
+}
+
+/// Draws on 32-bit targets (wasm32) must match native ones so the counter
+/// can't be hashed as a `usize`
+#[test]
+fn counter_is_hashed_as_eight_bytes() {
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let seed = public_coin.seed;
+    let draw_with_counter = |counter: &[u8]| {
+        let hash = Sha256::new_with_prefix(seed)
+            .chain_update(counter)
+            .finalize();
+        Fp::from_be_bytes_mod_order(&hash)
+    };
+
+    let draw = public_coin.draw::<Fp>();
+
+    assert_eq!(draw_with_counter(&1u64.to_be_bytes()), draw);
+    assert_ne!(draw_with_counter(&1u32.to_be_bytes()), draw);
+}
+
+#[test]
+fn grinding_with_separate_hash() {
+    let public_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let config = GrindingConfig {
+        hash: GrindingHash::Sha256,
+        bits: 8,
+    };
+
+    let nonce = public_coin.grind(config).unwrap();
+
+    assert!(public_coin.pow_leading_zeros(GrindingHash::Sha256, nonce) >= 8);
+    let mut bytes = Vec::new();
+    config.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(
+        config,
+        GrindingConfig::deserialize_compressed(&*bytes).unwrap()
+    );
+}
+
+#[test]
+fn sponge_coin_draws_are_state_elements() {
+    let mut public_coin = SpongePublicCoin::<Poseidon2Goldilocks>::new(b"ministark");
+    public_coin.reseed_with_label(label::BASE_TRACE_COMMITMENT, &42u64);
+    let state = *public_coin.state();
+
+    let alpha = public_coin.draw::<Fp>();
+    let beta = public_coin.draw::<Fq3>();
+
+    assert_eq!(state[CAPACITY], alpha);
+    let beta_coeffs = &state[CAPACITY + 1..CAPACITY + 4];
+    assert_eq!(Fq3::from_base_prime_field_elems(beta_coeffs).unwrap(), beta);
+    let config = GrindingConfig {
+        hash: GrindingHash::Channel,
+        bits: 8,
+    };
+    let nonce = public_coin.grind(config).unwrap();
+    assert!(public_coin.pow_leading_zeros(GrindingHash::Channel, nonce) >= 8);
+}
+
+#[test]
+fn pow_hashes_little_endian_nonce() {
+    use ark_ff::BigInteger;
+    use ark_ff::PrimeField;
+    use digest::Digest;
+    let nonce = 0x0102030405060708u64;
+    let digest_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let mut sponge_coin = SpongePublicCoin::<Poseidon2Goldilocks>::new(b"ministark");
+
+    let mut hasher = Sha256::new_with_prefix(digest_coin.seed);
+    hasher.update((label::POW.len() as u64).to_be_bytes());
+    hasher.update(label::POW);
+    hasher.update(nonce.to_le_bytes());
+    let digest_pow = hasher.finalize();
+    let sponge_zeros = sponge_coin.pow_leading_zeros(GrindingHash::Channel, nonce);
+    // the sponge absorbs the nonce the same way it's reseeded with it
+    sponge_coin.reseed_with_label(label::POW, &nonce);
+    let element = sponge_coin.draw::<Fp>().into_bigint();
+
+    let digest_zeros = digest_pow
+        .iter()
+        .position(|&byte| byte != 0)
+        .map_or(256, |i| 8 * i as u32 + digest_pow[i].leading_zeros());
+    assert_eq!(
+        digest_zeros,
+        digest_coin.pow_leading_zeros(GrindingHash::Channel, nonce)
+    );
+    assert_eq!(Fp::MODULUS_BIT_SIZE - element.num_bits(), sponge_zeros);
+}
+
+#[test]
+fn unique_queries_are_sorted_and_distinct() {
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let domain_size = 16;
+
+    let positions = public_coin.draw_unique_queries(64, domain_size);
+
+    assert!(positions.len() <= domain_size);
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn powers_of_alpha_coefficients() {
+    let mut public_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let coeffs = CoefficientMode::Powers.draw::<Fq3>(&mut public_coin, 4);
+
+    let alpha = coeffs[1];
+    assert_eq!(Fq3::one(), coeffs[0]);
+    assert_eq!(alpha.square(), coeffs[2]);
+    assert_eq!(alpha.square() * alpha, coeffs[3]);
+}
+
+#[test]
+fn grinding_stops_when_cancelled() {
+    let public_coin = DigestPublicCoin::<Sha256>::new(b"ministark");
+    let config = GrindingConfig {
+        hash: GrindingHash::Channel,
+        bits: 32,
+    };
+
+    assert_eq!(None, public_coin.grind_until(config, || true));
+}
@@ -0,0 +1,317 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use ark_ff::One;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use common::fib_trace;
+use common::options;
+use common::rng;
+use common::FibAir;
+use common::FibTrace;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertions::Assertion;
+use ministark::constraints::AlgebraicExpression;
+use ministark::constraints::TransitionConstraint;
+use ministark::Air;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+use ministark::VerifierPolicy;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Proves for the deployment identified by `context`
+struct ContextFibProver {
+    options: ProofOptions,
+    context: &'static [u8],
+}
+
+impl Prover for ContextFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        ContextFibProver {
+            options,
+            context: b"",
+        }
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.options
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        trace.result()
+    }
+
+    fn context(&self) -> &[u8] {
+        self.context
+    }
+}
+
+fn prove_in_context(context: &'static [u8], n: usize) -> Proof<FibAir> {
+    let options = options();
+    let prover = ContextFibProver { options, context };
+    prover
+        .prove_with_rng(fib_trace(n, &options), &mut rng())
+        .unwrap()
+}
+
+#[test]
+fn batch_of_valid_proofs_verifies() {
+    let proofs = [2048, 4096, 2048]
+        .into_iter()
+        .map(|n| prove_in_context(b"rollup", n))
+        .collect();
+
+    Proof::verify_all(proofs, b"rollup", None).unwrap();
+}
+
+#[test]
+fn batch_with_an_invalid_proof_is_rejected() {
+    let mut proofs = [2048, 4096, 2048]
+        .into_iter()
+        .map(|n| prove_in_context(b"rollup", n))
+        .collect::<Vec<_>>();
+    proofs[1].public_inputs += Fp::one();
+
+    assert!(matches!(
+        Proof::verify_all(proofs, b"rollup", None),
+        Err(VerificationError::InvalidBatchProof { index: 1, .. })
+    ));
+}
+
+#[test]
+fn batch_for_a_different_context_is_rejected() {
+    let proofs = vec![prove_in_context(b"rollup", 2048)];
+
+    assert!(matches!(
+        Proof::verify_all(proofs, b"other rollup", None),
+        Err(VerificationError::InvalidBatchProof { index: 0, .. })
+    ));
+}
+
+#[test]
+fn proof_meeting_the_policy_verifies() {
+    let proof = prove_in_context(b"rollup", 2048);
+    let policy = VerifierPolicy {
+        min_security_level: proof.conjectured_security_level() as u8,
+        allowed_hashes: vec![proof.hash_id],
+        max_trace_len: Some(2048),
+        require_zk: false,
+    };
+
+    proof
+        .verify_with_context_and_policy(b"rollup", None, &policy)
+        .unwrap();
+}
+
+#[test]
+fn proof_violating_each_policy_is_rejected() {
+    let proof = prove_in_context(b"rollup", 2048);
+    let security_level = proof.conjectured_security_level() as u8;
+    let hash_id = proof.hash_id;
+    let allowed_hashes = VerifierPolicy {
+        allowed_hashes: vec![hash_id + 1],
+        ..Default::default()
+    };
+    let max_trace_len = VerifierPolicy {
+        max_trace_len: Some(1024),
+        ..Default::default()
+    };
+    let require_zk = VerifierPolicy {
+        require_zk: true,
+        ..Default::default()
+    };
+    let required = security_level + 1;
+    let min_security_level = VerifierPolicy {
+        min_security_level: required,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        proof.clone().verify_with_policy(&allowed_hashes),
+        Err(VerificationError::HashNotAllowed { hash_id: id }) if id == hash_id
+    ));
+    assert!(matches!(
+        proof.clone().verify_with_policy(&max_trace_len),
+        Err(VerificationError::TraceTooLong {
+            max: 1024,
+            actual: 2048
+        })
+    ));
+    assert!(matches!(
+        proof.clone().verify_with_policy(&require_zk),
+        Err(VerificationError::ZkRequired)
+    ));
+    assert!(matches!(
+        proof.verify_with_context_and_policy(b"rollup", None, &min_security_level),
+        Err(VerificationError::InsufficientSecurity { required: r, .. }) if r == required
+    ));
+}
+
+#[test]
+fn policy_aware_verification_checks_the_context() {
+    let proof = prove_in_context(b"rollup", 2048);
+
+    let policy = VerifierPolicy::default();
+    assert!(proof
+        .verify_with_context_and_policy(b"other rollup", None, &policy)
+        .is_err());
+}
+
+fn serialized(proof: &Proof<FibAir>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn verifying_from_a_reader_agrees_with_verify() {
+    let proof = prove_in_context(b"", 2048);
+    let mut forged = proof.clone();
+    forged.public_inputs += Fp::one();
+
+    for proof in [proof, forged] {
+        let bytes = serialized(&proof);
+        let from_reader = Proof::<FibAir>::verify_from_reader(&*bytes, b"", None);
+        assert_eq!(proof.verify().is_ok(), from_reader.is_ok());
+    }
+}
+
+#[test]
+fn verifying_from_a_reader_checks_the_context() {
+    let bytes = serialized(&prove_in_context(b"rollup", 2048));
+
+    Proof::<FibAir>::verify_from_reader(&*bytes, b"rollup", None).unwrap();
+    assert!(Proof::<FibAir>::verify_from_reader(&*bytes, b"other rollup", None).is_err());
+}
+
+#[test]
+fn truncated_proof_is_malformed() {
+    let bytes = serialized(&prove_in_context(b"", 2048));
+
+    assert!(matches!(
+        Proof::<FibAir>::verify_from_reader(&bytes[..bytes.len() / 2], b"", None),
+        Err(VerificationError::MalformedProof { .. })
+    ));
+}
+
+#[test]
+fn proof_with_invalid_fri_options_is_rejected() {
+    let proof = prove_in_context(b"", 2048);
+    let mut zero_remainder = proof.clone();
+    zero_remainder.options.fri_max_remainder_size = 0;
+    let mut odd_remainder = proof.clone();
+    odd_remainder.options.fri_max_remainder_size = 6;
+    let mut zero_folding_factor = proof;
+    zero_folding_factor.options.fri_folding_factor = 0;
+
+    for proof in [zero_remainder, odd_remainder, zero_folding_factor] {
+        assert!(matches!(
+            proof.verify(),
+            Err(VerificationError::InvalidParameters { .. })
+        ));
+    }
+}
+
+fn verification_key(proof: &Proof<FibAir>, options: ProofOptions) -> Vec<u8> {
+    FibAir::new(proof.trace_info.clone(), proof.public_inputs, options).verification_key()
+}
+
+#[test]
+fn proof_for_the_pinned_key_verifies() {
+    let proof = prove_in_context(b"", 2048);
+    let key = verification_key(&proof, options());
+
+    proof.verify_with_key(2048, &key).unwrap();
+}
+
+#[test]
+fn proof_of_another_trace_len_is_rejected() {
+    let key = verification_key(&prove_in_context(b"", 2048), options());
+    let proof = prove_in_context(b"", 4096);
+
+    assert!(matches!(
+        proof.verify_with_key(2048, &key),
+        Err(VerificationError::TraceLenMismatch {
+            expected: 2048,
+            actual: 4096
+        })
+    ));
+}
+
+#[test]
+fn proof_for_another_key_is_rejected() {
+    let proof = prove_in_context(b"", 2048);
+    // same constraints but a different number of queries
+    let other_options = ProofOptions::new(32, 4, 0, 2, 8);
+    let key = verification_key(&proof, other_options);
+
+    assert!(matches!(
+        proof.verify_with_key(2048, &key),
+        Err(VerificationError::VerificationKeyMismatch)
+    ));
+}
+
+static NUM_COUNTED_AIRS: AtomicUsize = AtomicUsize::new(0);
+
+/// [FibAir] that counts how many times it's built
+struct CountedFibAir(FibAir);
+
+impl Air for CountedFibAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        NUM_COUNTED_AIRS.fetch_add(1, Ordering::Relaxed);
+        CountedFibAir(FibAir::new(trace_info, result, options))
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        self.0.pub_inputs()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        self.0.trace_info()
+    }
+
+    fn options(&self) -> &ProofOptions {
+        self.0.options()
+    }
+
+    fn num_randomizer_rows(&self) -> usize {
+        self.0.num_randomizer_rows()
+    }
+
+    fn constraints(&self) -> Vec<AlgebraicExpression<Fp>> {
+        self.0.constraints()
+    }
+
+    fn transition_constraints(&self) -> Vec<TransitionConstraint<Fp>> {
+        self.0.transition_constraints()
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        self.0.assertions()
+    }
+}
+
+#[test]
+fn verifying_with_a_key_builds_the_air_once() {
+    let proof = prove_in_context(b"", 2048);
+    let key = verification_key(&proof, options());
+    let bytes = serialized(&proof);
+    let proof = Proof::<CountedFibAir>::deserialize_compressed(&*bytes).unwrap();
+
+    proof.verify_with_key(2048, &key).unwrap();
+    assert_eq!(1, NUM_COUNTED_AIRS.load(Ordering::Relaxed));
+}